@@ -0,0 +1,36 @@
+//! `cargo zkm build`: builds the ZKM program in the current directory, as `build_program` does
+//! from a build script, and prints the path of its ELF.
+
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use zkm_build::{execute_build_program, BuildArgs};
+
+#[derive(Parser)]
+#[command(name = "cargo", bin_name = "cargo")]
+enum Cargo {
+    Zkm(ZkmCli),
+}
+
+#[derive(clap::Args)]
+#[command(version, about = "Tools for ZKM programs")]
+struct ZkmCli {
+    #[command(subcommand)]
+    command: ZkmCommand,
+}
+
+#[derive(Subcommand)]
+enum ZkmCommand {
+    /// Compile the program in the current directory for the zkVM.
+    Build(BuildArgs),
+}
+
+fn main() -> Result<()> {
+    let Cargo::Zkm(cli) = Cargo::parse();
+    match cli.command {
+        ZkmCommand::Build(args) => {
+            let elf_path = execute_build_program(&args, None)?;
+            println!("{}", elf_path);
+        }
+    }
+    Ok(())
+}
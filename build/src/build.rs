@@ -5,8 +5,8 @@ use cargo_metadata::camino::Utf8PathBuf;
 
 use crate::{
     command::{local::create_local_command, utils::execute_command},
-    utils::{cargo_rerun_if_changed, copy_elf_to_output_dir, current_datetime},
-    BuildArgs,
+    utils::{cargo_rerun_if_changed, copy_elf_to_output_dir, current_datetime, output_elf_path},
+    BuildArgs, ELF_ENV_PREFIX,
 };
 
 /// Build a program with the specified [`BuildArgs`]. The `program_dir` is specified as an argument
@@ -60,6 +60,15 @@ pub(crate) fn build_program_internal(path: &str, args: Option<BuildArgs>) {
         .as_ref()
         .map(|p| p.name.as_str())
         .unwrap_or("Program");
+    let args = args.unwrap_or_default();
+
+    // Tell the host where the ELF is, for `include_elf!`, even if the build is skipped.
+    println!(
+        "cargo:rustc-env={}{}={}",
+        ELF_ENV_PREFIX,
+        root_package_name,
+        output_elf_path(&args, &metadata)
+    );
 
     // Skip the program build if the ZKM_SKIP_PROGRAM_BUILD environment variable is set to true.
     let skip_program_build = std::env::var("ZKM_SKIP_PROGRAM_BUILD")
@@ -89,11 +98,7 @@ pub(crate) fn build_program_internal(path: &str, args: Option<BuildArgs>) {
     }
 
     // Build the program with the given arguments.
-    let path_output = if let Some(args) = args {
-        execute_build_program(&args, Some(program_dir.to_path_buf()))
-    } else {
-        execute_build_program(&BuildArgs::default(), Some(program_dir.to_path_buf()))
-    };
+    let path_output = execute_build_program(&args, Some(program_dir.to_path_buf()));
     if let Err(err) = path_output {
         panic!("Failed to build Zkm program: {}.", err);
     }
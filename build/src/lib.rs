@@ -10,6 +10,9 @@ pub const BUILD_TARGET: &str = "mips-zkm-zkvm-elf";
 // const DEFAULT_TAG: &str = "v1.0.0";
 pub const DEFAULT_OUTPUT_DIR: &str = "elf";
 pub const HELPER_TARGET_SUBDIR: &str = "elf-compilation";
+/// The prefix of the environment variables set by [`build_program`] for the host, followed by the
+/// name of the package of the program: their value is the path of its ELF.
+pub const ELF_ENV_PREFIX: &str = "ZKM_ELF_";
 
 /// Compile an ZKM program.
 ///
@@ -25,9 +25,23 @@ pub(crate) fn copy_elf_to_output_dir(
         .join("release")
         .join(root_package_name.unwrap());
 
+    let result_elf_path = output_elf_path(args, program_metadata);
+    fs::create_dir_all(result_elf_path.parent().unwrap())?;
+
+    // Copy the ELF to the specified output directory.
+    fs::copy(original_elf_path, &result_elf_path)?;
+
+    Ok(result_elf_path)
+}
+
+/// The path the ELF of the program is copied to.
+pub(crate) fn output_elf_path(
+    args: &BuildArgs,
+    program_metadata: &cargo_metadata::Metadata,
+) -> Utf8PathBuf {
     // The order of precedence for the ELF name is:
     // 1. --elf_name flag
-    // 2. --binary flag + -elf suffix (defaults to riscv32im-succinct-zkvm-elf)
+    // 2. --binary flag (defaults to mips-zkm-zkvm-elf)
     let elf_name = if !args.elf_name.is_empty() {
         args.elf_name.clone()
     } else if !args.binary.is_empty() {
@@ -38,18 +52,12 @@ pub(crate) fn copy_elf_to_output_dir(
         BUILD_TARGET.to_string()
     };
 
-    let elf_dir = program_metadata
+    program_metadata
         .target_directory
         .parent()
         .unwrap()
-        .join(&args.output_directory);
-    fs::create_dir_all(&elf_dir)?;
-    let result_elf_path = elf_dir.join(elf_name);
-
-    // Copy the ELF to the specified output directory.
-    fs::copy(original_elf_path, &result_elf_path)?;
-
-    Ok(result_elf_path)
+        .join(&args.output_directory)
+        .join(elf_name)
 }
 
 pub(crate) fn current_datetime() -> String {
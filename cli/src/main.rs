@@ -0,0 +1,253 @@
+//! `zkm`: executes, proves and verifies ZKM programs with [`ProverClient`], for the scripts and
+//! the CI which do not use Rust.
+//!
+//! The input of a program is the bytes of the file given with `--stdin`, which it reads with
+//! `zkm_runtime::io::read_vec()`. The proofs are compressed proofs, and the verifying keys those
+//! of [`ZkmVerifyingKey`], both in the format of `zkm_prover::serialization`.
+
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use clap::{Parser, Subcommand};
+use zkm_emulator::stdin::Stdin;
+use zkm_emulator::trace::ExecutionTrace;
+use zkm_prover::client::{ProverClient, ZkmCompressedProof, ZkmVerifyingKey};
+use zkm_prover::solidity::export_verifier;
+
+#[derive(Parser)]
+#[command(
+    name = "zkm",
+    version,
+    about = "Execute, prove and verify ZKM programs"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run a program without proving it, and print what it used and committed.
+    Execute {
+        elf: PathBuf,
+        #[arg(long)]
+        stdin: Option<PathBuf>,
+        /// Write the cycles by function, as collapsed stacks for a flamegraph.
+        #[arg(long)]
+        profile: Option<PathBuf>,
+        /// Wait for gdb to connect on this address, and run under its control.
+        #[arg(long, value_name = "ADDR", conflicts_with = "profile")]
+        debug: Option<String>,
+        /// Record the execution trace, to replay it with `zkm replay`.
+        #[arg(long, conflicts_with_all = ["profile", "debug"])]
+        trace: Option<PathBuf>,
+        /// Stop the run after this number of cycles.
+        #[arg(long)]
+        max_cycles: Option<u64>,
+    },
+    /// Run a program again, and check each instruction against a recorded execution trace.
+    Replay {
+        elf: PathBuf,
+        #[arg(long)]
+        stdin: Option<PathBuf>,
+        #[arg(long)]
+        trace: PathBuf,
+        /// Replay into the witness generation of the prover, instead of the emulator.
+        #[arg(long)]
+        witness: bool,
+    },
+    /// Prove the run of a program with a compressed proof.
+    Prove {
+        elf: PathBuf,
+        #[arg(long)]
+        stdin: Option<PathBuf>,
+        #[arg(long, default_value = "proof.bin")]
+        output: PathBuf,
+        /// Also write the key verifying the proof.
+        #[arg(long)]
+        vkey: Option<PathBuf>,
+        /// The number of instructions of a segment.
+        #[arg(long)]
+        seg_size: Option<usize>,
+        /// The directory caching the recursive circuits.
+        #[arg(long)]
+        key_cache: Option<PathBuf>,
+        /// Stop the run after this number of cycles.
+        #[arg(long)]
+        max_cycles: Option<u64>,
+    },
+    /// Verify a compressed proof, and that it is of the given program.
+    Verify {
+        proof: PathBuf,
+        #[arg(long)]
+        vkey: PathBuf,
+        /// The image id of the program, in hex.
+        #[arg(long, conflicts_with = "elf")]
+        image_id: Option<String>,
+        /// The program, whose image id the proof must have.
+        #[arg(long)]
+        elf: Option<PathBuf>,
+    },
+    /// Print the image id, the public values and the size of a compressed proof.
+    Inspect { proof: PathBuf },
+    /// Write the Solidity contract verifying the Groth16 proofs of a program.
+    ExportVerifier {
+        /// The Groth16 verifying key of the wrapped circuit.
+        #[arg(long)]
+        groth16_vkey: PathBuf,
+        /// The image id of the program, in hex.
+        #[arg(long)]
+        image_id: String,
+        #[arg(long, default_value = "Verifier.sol")]
+        out: PathBuf,
+    },
+}
+
+fn read_stdin(path: Option<&Path>) -> Result<Stdin> {
+    match path {
+        Some(path) => Ok(Stdin::from_slice(
+            &fs::read(path).with_context(|| format!("reading {}", path.display()))?,
+        )),
+        None => Ok(Stdin::default()),
+    }
+}
+
+fn read_elf(path: &Path) -> Result<Vec<u8>> {
+    fs::read(path).with_context(|| format!("reading {}", path.display()))
+}
+
+fn parse_image_id(image_id: &str) -> Result<[u8; 32]> {
+    hex::decode(image_id.trim_start_matches("0x"))?
+        .try_into()
+        .map_err(|_| anyhow!("the image id is not 32 bytes"))
+}
+
+fn main() -> Result<()> {
+    env_logger::init();
+    match Cli::parse().command {
+        Command::Execute {
+            elf,
+            stdin,
+            profile,
+            debug,
+            trace,
+            max_cycles,
+        } => {
+            let (elf, stdin) = (read_elf(&elf)?, read_stdin(stdin.as_deref())?);
+            let mut client = ProverClient::new();
+            if let Some(max_cycles) = max_cycles {
+                client = client.with_max_cycles(max_cycles);
+            }
+            if let Some(path) = profile {
+                let (report, profiler) = client.profile(&elf, &stdin)?;
+                profiler.write_collapsed(BufWriter::new(File::create(&path)?))?;
+                print!("{}", report);
+                println!("profile: {}", path.display());
+            } else if let Some(path) = trace {
+                let (report, trace) = client.trace(&elf, &stdin)?;
+                trace.save(&path)?;
+                print!("{}", report);
+                println!("trace: {} ({} steps)", path.display(), trace.steps.len());
+            } else if let Some(addr) = debug {
+                eprintln!("waiting for gdb on {}", addr);
+                print!("{}", client.debug(&elf, &stdin, addr.as_str())?);
+            } else {
+                let (journal, report) = client.execute(&elf, &stdin)?;
+                print!("{}", report);
+                println!("journal: {}", hex::encode(&journal.bytes));
+            }
+        }
+        Command::Replay {
+            elf,
+            stdin,
+            trace,
+            witness,
+        } => {
+            let (elf, stdin) = (read_elf(&elf)?, read_stdin(stdin.as_deref())?);
+            let trace = ExecutionTrace::load(&trace)?;
+            let client = ProverClient::new();
+            if witness {
+                client.replay_witness(&elf, &stdin, &trace)?;
+            } else {
+                print!("{}", client.replay(&elf, &stdin, &trace)?);
+            }
+            println!("the run matches its trace");
+        }
+        Command::Prove {
+            elf,
+            stdin,
+            output,
+            vkey,
+            seg_size,
+            key_cache,
+            max_cycles,
+        } => {
+            let stdin = read_stdin(stdin.as_deref())?;
+            let mut builder = ProverClient::builder();
+            if let Some(seg_size) = seg_size {
+                builder = builder.seg_size(seg_size);
+            }
+            if let Some(dir) = key_cache {
+                builder = builder.key_cache(dir);
+            }
+            if let Some(max_cycles) = max_cycles {
+                builder = builder.max_cycles(max_cycles);
+            }
+            let client = builder.build();
+            let proof = client.prove_compressed(&read_elf(&elf)?, &stdin)?;
+            proof.save(&output)?;
+            println!("image id: {}", hex::encode(proof.image_id()?));
+            println!("proof: {}", output.display());
+            if let Some(path) = vkey {
+                fs::write(&path, client.verifying_key().serialize()?)?;
+                println!("verifying key: {}", path.display());
+            }
+        }
+        Command::Verify {
+            proof,
+            vkey,
+            image_id,
+            elf,
+        } => {
+            let proof = ZkmCompressedProof::load(&proof)?;
+            let vkey = ZkmVerifyingKey::deserialize(&fs::read(&vkey)?)?;
+            let image_id = match (image_id, elf) {
+                (Some(image_id), _) => Some(parse_image_id(&image_id)?),
+                (None, Some(elf)) => Some(ProverClient::new().image_id(&read_elf(&elf)?)?),
+                (None, None) => None,
+            };
+            match image_id {
+                Some(image_id) => vkey.verify_program(&proof, &image_id)?,
+                None => vkey.verify(&proof)?,
+            }
+            println!("the proof is valid");
+        }
+        Command::Inspect { proof } => {
+            let size = fs::metadata(&proof)?.len();
+            let proof = ZkmCompressedProof::load(&proof)?;
+            let public_values = proof.public_values()?;
+            println!("image id: {}", hex::encode(public_values.image_id));
+            println!("hasher: {:?}", proof.hasher);
+            println!("userdata: {}", hex::encode(&public_values.userdata));
+            println!("exit code: {}", public_values.exit_code);
+            println!(
+                "journal (not proven): {} ({} bytes)",
+                hex::encode(&proof.receipt.journal),
+                proof.receipt.journal.len()
+            );
+            println!("size: {} bytes", size);
+        }
+        Command::ExportVerifier {
+            groth16_vkey,
+            image_id,
+            out,
+        } => {
+            let contract = export_verifier(&fs::read(&groth16_vkey)?, &parse_image_id(&image_id)?)?;
+            fs::write(&out, contract)?;
+            println!("verifier: {}", out.display());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,134 @@
+//! The BLAKE2b compression function `F` of EIP-152 and the BLAKE3 compression function, as
+//! computed by the `BLAKE2B_COMPRESS` and `BLAKE3_COMPRESS` syscalls.
+
+pub const BLAKE2B_IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+/// The message words of the BLAKE2b rounds, round `r` using `BLAKE2B_SIGMA[r % 10]`.
+pub const BLAKE2B_SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+/// The right rotations of the four XORs of the BLAKE2b mixing function.
+pub const BLAKE2B_ROTATIONS: [u32; 4] = [32, 24, 16, 63];
+
+pub const BLAKE3_IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+pub const BLAKE3_ROUNDS: usize = 7;
+
+/// The message words of the BLAKE3 rounds: the message permutation applied once per round.
+pub const BLAKE3_SCHEDULE: [[usize; 16]; BLAKE3_ROUNDS] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [2, 6, 3, 10, 7, 0, 4, 13, 1, 11, 12, 5, 9, 14, 15, 8],
+    [3, 4, 10, 12, 13, 2, 7, 14, 6, 5, 9, 0, 11, 15, 8, 1],
+    [10, 7, 12, 9, 14, 3, 13, 15, 4, 0, 11, 2, 5, 8, 1, 6],
+    [12, 13, 9, 11, 15, 10, 14, 8, 7, 2, 5, 3, 0, 1, 6, 4],
+    [9, 14, 11, 5, 8, 12, 15, 1, 13, 3, 0, 10, 2, 6, 4, 7],
+    [11, 15, 5, 0, 1, 9, 8, 6, 14, 10, 2, 12, 3, 4, 7, 13],
+];
+
+/// The right rotations of the four XORs of the BLAKE3 mixing function.
+pub const BLAKE3_ROTATIONS: [u32; 4] = [16, 12, 8, 7];
+
+/// The words of the state mixed by the eight calls to `G` of a round: the four columns, then
+/// the four diagonals.
+pub const MIX_INDICES: [[usize; 4]; 8] = [
+    [0, 4, 8, 12],
+    [1, 5, 9, 13],
+    [2, 6, 10, 14],
+    [3, 7, 11, 15],
+    [0, 5, 10, 15],
+    [1, 6, 11, 12],
+    [2, 7, 8, 13],
+    [3, 4, 9, 14],
+];
+
+fn blake2b_mix(v: &mut [u64; 16], [a, b, c, d]: [usize; 4], x: u64, y: u64) {
+    let [r0, r1, r2, r3] = BLAKE2B_ROTATIONS;
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(r0);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(r1);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(r2);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(r3);
+}
+
+fn blake3_mix(v: &mut [u32; 16], [a, b, c, d]: [usize; 4], x: u32, y: u32) {
+    let [r0, r1, r2, r3] = BLAKE3_ROTATIONS;
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(r0);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(r1);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(r2);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(r3);
+}
+
+/// Compresses the block `m` into the hash state `h` in `rounds` rounds, with the offset
+/// counter `t` and the final block flag `f`.
+pub fn blake2b_compress(rounds: u32, h: &mut [u64; 8], m: &[u64; 16], t: [u64; 2], f: bool) {
+    let mut v = [0u64; 16];
+    v[..8].copy_from_slice(h);
+    v[8..].copy_from_slice(&BLAKE2B_IV);
+    v[12] ^= t[0];
+    v[13] ^= t[1];
+    if f {
+        v[14] = !v[14];
+    }
+    for r in 0..rounds as usize {
+        let s = &BLAKE2B_SIGMA[r % 10];
+        for (i, indices) in MIX_INDICES.into_iter().enumerate() {
+            blake2b_mix(&mut v, indices, m[s[2 * i]], m[s[2 * i + 1]]);
+        }
+    }
+    for (i, word) in h.iter_mut().enumerate() {
+        *word ^= v[i] ^ v[i + 8];
+    }
+}
+
+/// Compresses the block `block`, of `block_len` bytes, into the chaining value `cv`.
+pub fn blake3_compress(
+    cv: &mut [u32; 8],
+    block: &[u32; 16],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+) {
+    let mut v = [0u32; 16];
+    v[..8].copy_from_slice(cv);
+    v[8..12].copy_from_slice(&BLAKE3_IV[..4]);
+    v[12] = counter as u32;
+    v[13] = (counter >> 32) as u32;
+    v[14] = block_len;
+    v[15] = flags;
+    for s in BLAKE3_SCHEDULE.iter() {
+        for (i, indices) in MIX_INDICES.into_iter().enumerate() {
+            blake3_mix(&mut v, indices, block[s[2 * i]], block[s[2 * i + 1]]);
+        }
+    }
+    for (i, word) in cv.iter_mut().enumerate() {
+        *word = v[i] ^ v[i + 8];
+    }
+}
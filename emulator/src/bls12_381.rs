@@ -0,0 +1,50 @@
+//! Arithmetic modulo the prime of the base field of BLS12-381, as computed by the
+//! `BLS12381_FP_ADD`, `BLS12381_FP_SUB` and `BLS12381_FP_MUL` syscalls.
+//!
+//! An element is twelve words, the little-endian words of an integer below `2^384`, which need
+//! not be reduced. Results are reduced.
+
+use lazy_static::lazy_static;
+use num_bigint::BigUint;
+
+pub type Element = [u32; 12];
+
+lazy_static! {
+    /// The modulus of the base field.
+    pub static ref MODULUS: BigUint = BigUint::parse_bytes(
+        b"1a0111ea397fe69a4b1ba7b6434bacd764774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffaaab",
+        16
+    )
+    .unwrap();
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FpOp {
+    Add,
+    Sub,
+    Mul,
+}
+
+pub fn from_element(x: &Element) -> BigUint {
+    BigUint::from_slice(x)
+}
+
+pub fn to_element(x: &BigUint) -> Element {
+    let mut words = [0u32; 12];
+    for (word, digit) in words.iter_mut().zip(x.to_u32_digits()) {
+        *word = digit;
+    }
+    words
+}
+
+/// Computes `x op y`, reduced modulo the prime.
+pub fn apply(op: FpOp, x: &Element, y: &Element) -> Element {
+    let p = &*MODULUS;
+    let (x, y) = (from_element(x), from_element(y));
+    let r = match op {
+        FpOp::Add => (x + y) % p,
+        FpOp::Sub => (x % p + p - y % p) % p,
+        FpOp::Mul => x * y % p,
+    };
+    to_element(&r)
+}
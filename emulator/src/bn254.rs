@@ -0,0 +1,16 @@
+//! The BN254 curve `y^2 = x^3 + 3`, also known as alt_bn128, of the `BN254_ADD` and
+//! `BN254_DOUBLE` syscalls.
+
+use lazy_static::lazy_static;
+use num_bigint::BigUint;
+
+lazy_static! {
+    /// The modulus of the base field.
+    pub static ref MODULUS: BigUint = BigUint::parse_bytes(
+        b"30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd47",
+        16
+    )
+    .unwrap();
+    /// `a = 0`.
+    pub static ref A: BigUint = BigUint::from(0u32);
+}
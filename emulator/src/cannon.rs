@@ -0,0 +1,136 @@
+//! The state snapshots of Cannon, the MIPS VM of the OP Stack fault proofs, in their JSON format,
+//! which [`State::from_cannon`] loads and [`State::to_cannon`] writes.
+//!
+//! A snapshot has the memory by page, each zlib-compressed then base64-encoded, and the
+//! registers. Its memory root is the Keccak-256 binary Merkle root of the memory by 32-byte leaf,
+//! see [`CannonState::memory_root`], not the image id of zkm, which hashes the pages with
+//! Poseidon.
+//!
+//! [`State::from_cannon`]: crate::state::State::from_cannon
+//! [`State::to_cannon`]: crate::state::State::to_cannon
+
+use crate::page::{PAGE_ADDR_SIZE, PAGE_SIZE};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use tiny_keccak::{Hasher, Keccak};
+
+/// The size of a leaf of the memory tree.
+const LEAF_SIZE: usize = 32;
+/// The depth of the tree of a page, and of the tree of the whole memory.
+const PAGE_DEPTH: usize = PAGE_ADDR_SIZE - 5;
+const MEMORY_DEPTH: usize = 32 - 5;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CannonPage {
+    pub index: u32,
+    pub data: String,
+}
+
+impl CannonPage {
+    pub fn encode(index: u32, data: &[u8; PAGE_SIZE]) -> Self {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        Self {
+            index,
+            data: STANDARD.encode(encoder.finish().unwrap()),
+        }
+    }
+
+    /// The data of the page. Older snapshots have it uncompressed.
+    pub fn decode(&self) -> Result<[u8; PAGE_SIZE], String> {
+        let mut bytes = STANDARD
+            .decode(&self.data)
+            .map_err(|e| format!("invalid page 0x{:x}: {}", self.index, e))?;
+        if bytes.len() != PAGE_SIZE {
+            let mut inflated = Vec::with_capacity(PAGE_SIZE);
+            ZlibDecoder::new(bytes.as_slice())
+                .read_to_end(&mut inflated)
+                .map_err(|e| format!("invalid page 0x{:x}: {}", self.index, e))?;
+            bytes = inflated;
+        }
+        bytes
+            .try_into()
+            .map_err(|_| format!("invalid page 0x{:x}: not {} bytes", self.index, PAGE_SIZE))
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CannonState {
+    pub memory: Vec<CannonPage>,
+    /// The key of the preimage being read, as 0x-prefixed hex, and the offset read so far.
+    pub preimage_key: String,
+    pub preimage_offset: u32,
+    pub pc: u32,
+    #[serde(rename = "nextPC")]
+    pub next_pc: u32,
+    pub lo: u32,
+    pub hi: u32,
+    pub heap: u32,
+    pub exit_code: u8,
+    pub exited: bool,
+    pub step: u64,
+    pub registers: [u32; 32],
+}
+
+impl CannonState {
+    /// The Merkle root of the memory, as Cannon computes it.
+    pub fn memory_root(&self) -> Result<[u8; 32], String> {
+        let zero_hashes = zero_hashes();
+        let mut nodes = BTreeMap::new();
+        for page in &self.memory {
+            nodes.insert(page.index, page_root(&page.decode()?));
+        }
+
+        for level in PAGE_DEPTH..MEMORY_DEPTH {
+            let mut parents = BTreeMap::new();
+            for &index in nodes.keys() {
+                let node = |index| nodes.get(&index).unwrap_or(&zero_hashes[level]);
+                parents
+                    .entry(index >> 1)
+                    .or_insert_with(|| keccak_pair(node(index & !1), node(index | 1)));
+            }
+            nodes = parents;
+        }
+        Ok(nodes.get(&0).copied().unwrap_or(zero_hashes[MEMORY_DEPTH]))
+    }
+}
+
+fn keccak_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut digest = [0u8; 32];
+    let mut keccak = Keccak::v256();
+    keccak.update(left);
+    keccak.update(right);
+    keccak.finalize(&mut digest);
+    digest
+}
+
+/// The roots of the trees of zeros, by depth.
+fn zero_hashes() -> [[u8; 32]; MEMORY_DEPTH + 1] {
+    let mut hash = [0u8; 32];
+    std::array::from_fn(|_| {
+        let root = hash;
+        hash = keccak_pair(&root, &root);
+        root
+    })
+}
+
+fn page_root(data: &[u8; PAGE_SIZE]) -> [u8; 32] {
+    let mut nodes: Vec<[u8; 32]> = data
+        .chunks_exact(LEAF_SIZE)
+        .map(|leaf| leaf.try_into().unwrap())
+        .collect();
+    while nodes.len() > 1 {
+        nodes = nodes
+            .chunks_exact(2)
+            .map(|pair| keccak_pair(&pair[0], &pair[1]))
+            .collect();
+    }
+    nodes[0]
+}
@@ -0,0 +1,39 @@
+//! The ChaCha20 block function of RFC 8439, as computed by the `CHACHA20_BLOCK` syscall.
+
+use crate::blake::MIX_INDICES;
+
+/// The first four words of the state, "expand 32-byte k".
+pub const CHACHA_CONSTANTS: [u32; 4] = [0x61707865, 0x3320646e, 0x79622d32, 0x6b206574];
+
+/// Each double round mixes the columns of the state, then its diagonals.
+pub const CHACHA_DOUBLE_ROUNDS: usize = 10;
+
+/// The left rotations of the four XORs of the quarter round.
+pub const CHACHA_ROTATIONS: [u32; 4] = [16, 12, 8, 7];
+
+fn quarter_round(v: &mut [u32; 16], [a, b, c, d]: [usize; 4]) {
+    let [r0, r1, r2, r3] = CHACHA_ROTATIONS;
+    v[a] = v[a].wrapping_add(v[b]);
+    v[d] = (v[d] ^ v[a]).rotate_left(r0);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_left(r1);
+    v[a] = v[a].wrapping_add(v[b]);
+    v[d] = (v[d] ^ v[a]).rotate_left(r2);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_left(r3);
+}
+
+/// Returns the keystream block of `state`: the constants, the key, the block counter and the
+/// nonce, as little-endian words.
+pub fn chacha20_block(state: &[u32; 16]) -> [u32; 16] {
+    let mut v = *state;
+    for _ in 0..CHACHA_DOUBLE_ROUNDS {
+        for indices in MIX_INDICES {
+            quarter_round(&mut v, indices);
+        }
+    }
+    for (word, x) in v.iter_mut().zip(state) {
+        *word = word.wrapping_add(*x);
+    }
+    v
+}
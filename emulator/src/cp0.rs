@@ -0,0 +1,70 @@
+//! The system control coprocessor (CP0), as far as exceptions need it.
+//!
+//! Only the registers involved in exception handling have a meaning: Status, Cause, EPC and
+//! BadVAddr. The others can be read and written with MFC0/MTC0, but have no effect.
+
+use serde::{Deserialize, Serialize};
+
+pub const CP0_BADVADDR: usize = 8;
+pub const CP0_STATUS: usize = 12;
+pub const CP0_CAUSE: usize = 13;
+pub const CP0_EPC: usize = 14;
+
+/// Status: exceptions are delivered to the handler.
+pub const STATUS_IE: u32 = 1;
+/// Status: an exception is being handled.
+pub const STATUS_EXL: u32 = 1 << 1;
+/// Cause: the exception was raised in a branch delay slot.
+pub const CAUSE_BD: u32 = 1 << 31;
+const CAUSE_EXC_CODE_SHIFT: u32 = 2;
+const CAUSE_EXC_CODE_MASK: u32 = 0x1f << CAUSE_EXC_CODE_SHIFT;
+
+/// The general exception vector, where the exception handler of the guest is expected.
+pub const EXCEPTION_VECTOR: u32 = 0x80000180;
+
+/// ERET, the only COP0 instruction besides MFC0 and MTC0.
+pub const ERET: u32 = 0x42000018;
+
+/// The index of CP0 register 0 in the register file of the prover, after the 39 registers
+/// committed to by the image id.
+pub const CP0_REGISTERS_START: usize = 39;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Cp0 {
+    pub regs: [u32; 32],
+}
+
+impl Cp0 {
+    /// Whether an exception enters the handler at [`EXCEPTION_VECTOR`]. A guest without a
+    /// handler leaves Status.IE clear, and an exception stops it instead, with the exception code
+    /// as exit code. So does an exception raised by the handler itself, while Status.EXL is set.
+    pub fn delivers(&self) -> bool {
+        let status = self.regs[CP0_STATUS];
+        status & STATUS_IE != 0 && status & STATUS_EXL == 0
+    }
+
+    /// Records an exception raised by the instruction at `pc`, `next_pc` being the instruction
+    /// executed after it, and returns the address of the handler. An instruction in a delay slot
+    /// is restarted from its branch. BadVAddr is only set by the exceptions having an address.
+    pub fn enter(&mut self, exc_code: u8, pc: u32, next_pc: u32, bad_vaddr: Option<u32>) -> u32 {
+        let (epc, bd) = if next_pc != pc.wrapping_add(4) {
+            (pc.wrapping_sub(4), CAUSE_BD)
+        } else {
+            (pc, 0)
+        };
+        let cause = self.regs[CP0_CAUSE] & !(CAUSE_BD | CAUSE_EXC_CODE_MASK);
+        self.regs[CP0_CAUSE] = cause | bd | ((exc_code as u32) << CAUSE_EXC_CODE_SHIFT);
+        self.regs[CP0_EPC] = epc;
+        self.regs[CP0_STATUS] |= STATUS_EXL;
+        if let Some(addr) = bad_vaddr {
+            self.regs[CP0_BADVADDR] = addr;
+        }
+        EXCEPTION_VECTOR
+    }
+
+    /// Leaves the handler, returning the address to resume at.
+    pub fn eret(&mut self) -> u32 {
+        self.regs[CP0_STATUS] &= !STATUS_EXL;
+        self.regs[CP0_EPC]
+    }
+}
@@ -0,0 +1,92 @@
+//! The cycle report of a program, built from the markers it writes to `FD_CYCLE_TRACKER`.
+//!
+//! A marker is a tag byte, [`CYCLE_TRACKER_START`] or [`CYCLE_TRACKER_END`], followed by the
+//! label of the section. Sections with the same label are summed, and may nest.
+
+use log::warn;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+pub const CYCLE_TRACKER_START: u8 = 0;
+pub const CYCLE_TRACKER_END: u8 = 1;
+
+/// The cost of the sections with a given label.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SectionCycles {
+    /// The number of times the section was run.
+    pub count: u64,
+    /// The number of instructions executed in the section.
+    pub steps: u64,
+    /// The number of cycles spent in the section, counting the rows of the precompiles.
+    pub cycles: u64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CycleTracker {
+    /// The steps and cycles at the start of the sections which are still running, by label.
+    running: BTreeMap<String, Vec<(u64, u64)>>,
+    report: BTreeMap<String, SectionCycles>,
+}
+
+impl CycleTracker {
+    /// Handles a marker written by the program, at the given step and cycle.
+    pub fn mark(&mut self, marker: &[u8], step: u64, cycle: u64) {
+        let Some((&tag, label)) = marker.split_first() else {
+            warn!("empty cycle tracker marker");
+            return;
+        };
+        let label = String::from_utf8_lossy(label).into_owned();
+        match tag {
+            CYCLE_TRACKER_START => self.start(label, step, cycle),
+            CYCLE_TRACKER_END => self.end(label, step, cycle),
+            _ => warn!("invalid cycle tracker marker {:02x?}", marker),
+        }
+    }
+
+    pub fn start(&mut self, label: String, step: u64, cycle: u64) {
+        self.running.entry(label).or_default().push((step, cycle));
+    }
+
+    pub fn end(&mut self, label: String, step: u64, cycle: u64) {
+        let Some((start_step, start_cycle)) = self.running.get_mut(&label).and_then(Vec::pop)
+        else {
+            warn!("cycle tracker section {} ended but not started", label);
+            return;
+        };
+        // A nested section with the same label is already counted by the outer one.
+        let nested = !self.running[&label].is_empty();
+        let section = self.report.entry(label).or_default();
+        section.count += 1;
+        if !nested {
+            section.steps += step - start_step;
+            section.cycles += cycle - start_cycle;
+        }
+    }
+
+    /// The cost of the sections which have ended, by label.
+    pub fn report(&self) -> &BTreeMap<String, SectionCycles> {
+        &self.report
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.report.is_empty()
+    }
+}
+
+impl Display for CycleTracker {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (label, section) in &self.report {
+            writeln!(
+                f,
+                "{}: {} runs, {} steps, {} cycles",
+                label, section.count, section.steps, section.cycles
+            )?;
+        }
+        for (label, starts) in &self.running {
+            if !starts.is_empty() {
+                writeln!(f, "{}: {} runs not ended", label, starts.len())?;
+            }
+        }
+        Ok(())
+    }
+}
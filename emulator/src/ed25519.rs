@@ -0,0 +1,94 @@
+//! Point addition and decompression on the twisted Edwards curve `-x^2 + y^2 = 1 + d x^2 y^2`
+//! of ed25519, as computed by the `ED_ADD` and `ED_DECOMPRESS` syscalls.
+//!
+//! A point is sixteen words: the little-endian words of `x`, then those of `y`.
+
+use lazy_static::lazy_static;
+use num_bigint::BigUint;
+
+pub type Point = [u32; 16];
+
+lazy_static! {
+    /// The modulus of the base field, `2^255 - 19`.
+    pub static ref MODULUS: BigUint = (BigUint::from(1u32) << 255u32) - 19u32;
+    /// The curve constant `d = -121665 / 121666`.
+    pub static ref D: BigUint = BigUint::parse_bytes(
+        b"52036cee2b6ffe738cc740797779e89800700a4d4141d8ab75eb4dca135978a3",
+        16
+    )
+    .unwrap();
+}
+
+pub fn to_coordinates(p: &Point) -> (BigUint, BigUint) {
+    (BigUint::from_slice(&p[..8]), BigUint::from_slice(&p[8..]))
+}
+
+pub fn from_coordinates(x: &BigUint, y: &BigUint) -> Point {
+    let mut p = [0u32; 16];
+    for (words, c) in [(&mut p[..8], x), (&mut p[8..], y)] {
+        for (word, digit) in words.iter_mut().zip(c.to_u32_digits()) {
+            *word = digit;
+        }
+    }
+    p
+}
+
+fn inverse(x: &BigUint) -> Option<BigUint> {
+    let p = &*MODULUS;
+    let x = x % p;
+    if x == BigUint::from(0u32) {
+        return None;
+    }
+    Some(x.modpow(&(p - 2u32), p))
+}
+
+/// The products `x_p x_q`, `y_p y_q` and `d x_p x_q y_p y_q` of the addition formula.
+pub fn add_products(p: &Point, q: &Point) -> (BigUint, BigUint, BigUint) {
+    let m = &*MODULUS;
+    let ((x1, y1), (x2, y2)) = (to_coordinates(p), to_coordinates(q));
+    let f = x1 * x2 % m;
+    let g = y1 * y2 % m;
+    let k = &*D * (&f * &g % m) % m;
+    (f, g, k)
+}
+
+/// Computes `p + q` as `((x_p y_q + y_p x_q) / (1 + k), (y_p y_q + x_p x_q) / (1 - k))` with
+/// `k = d x_p x_q y_p y_q`. This is complete on the curve, so it only fails on a denominator of
+/// zero, for points off the curve.
+pub fn add(p: &Point, q: &Point) -> Option<Point> {
+    let m = &*MODULUS;
+    let ((x1, y1), (x2, y2)) = (to_coordinates(p), to_coordinates(q));
+    let (f, g, k) = add_products(p, q);
+    let x3 = (x1 * y2 + y1 * x2) * inverse(&(&k + 1u32))? % m;
+    let y3 = (g + f) * inverse(&(m + 1u32 - &k))? % m;
+    Some(from_coordinates(&x3, &y3))
+}
+
+/// Recovers `x` from the compressed point `y`, whose top bit is the sign of `x`, as specified in
+/// RFC 8032. Fails if `y` is not on the curve, or if `x = 0` with the sign bit set.
+pub fn decompress(compressed: &[u8; 32]) -> Option<BigUint> {
+    let m = &*MODULUS;
+    let sign = compressed[31] >> 7;
+    let mut y = *compressed;
+    y[31] &= 0x7f;
+    let y = BigUint::from_bytes_le(&y);
+
+    let u = &y * &y % m;
+    let x2 = (&u + m - 1u32) * inverse(&(&*D * &u + 1u32))? % m;
+    let mut x = x2.modpow(&((m + 3u32) >> 3), m);
+    if &x * &x % m != x2 {
+        let sqrt_m1 = BigUint::from(2u32).modpow(&((m - 1u32) >> 2), m);
+        x = x * sqrt_m1 % m;
+    }
+    if &x * &x % m != x2 {
+        return None;
+    }
+    let zero = BigUint::from(0u32);
+    if x == zero && sign == 1 {
+        return None;
+    }
+    if x.bit(0) != (sign == 1) {
+        x = m - x;
+    }
+    Some(x)
+}
@@ -0,0 +1,70 @@
+//! The errors of the runs of a program: those which stop it before it exits, and its panics.
+
+use std::fmt::{Display, Formatter};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExecutionError {
+    /// The run went past the `max_cycles` of its [`ExecutorOpts`], at `pc` after `cycles`
+    /// cycles.
+    ///
+    /// [`ExecutorOpts`]: crate::executor::ExecutorOpts
+    CycleLimitExceeded { pc: u32, cycles: u64 },
+    /// The program panicked with `message`, at `location` in its sources, and halted at `pc`.
+    GuestPanicked {
+        message: String,
+        location: Option<String>,
+        pc: u32,
+    },
+}
+
+impl ExecutionError {
+    /// The panic of the message `message` written by a panic hook, as the one of the runtime
+    /// or of std: `panicked at src/main.rs:4:5:\nthe message`, `None` if it is not one.
+    pub fn from_panic_message(message: &[u8], pc: u32) -> Option<Self> {
+        let message = String::from_utf8_lossy(message);
+        let message = message.trim_end();
+        // The hook of std names the thread first.
+        let message = match message.strip_prefix("thread '") {
+            Some(rest) => rest.split_once("' ")?.1,
+            None => message,
+        };
+        let panic = message.strip_prefix("panicked at ")?;
+        let (location, message) = match panic.split_once(":\n") {
+            Some((location, message)) => (Some(location.to_string()), message),
+            None => (None, panic),
+        };
+        Some(Self::GuestPanicked {
+            message: message.to_string(),
+            location,
+            pc,
+        })
+    }
+}
+
+impl Display for ExecutionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CycleLimitExceeded { pc, cycles } => write!(
+                f,
+                "the run exceeded its cycle limit at pc 0x{:08x}, after {} cycles",
+                pc, cycles
+            ),
+            Self::GuestPanicked {
+                message,
+                location: Some(location),
+                pc,
+            } => write!(
+                f,
+                "the program panicked at {} (pc 0x{:08x}): {}",
+                location, pc, message
+            ),
+            Self::GuestPanicked {
+                message,
+                location: None,
+                pc,
+            } => write!(f, "the program panicked at pc 0x{:08x}: {}", pc, message),
+        }
+    }
+}
+
+impl std::error::Error for ExecutionError {}
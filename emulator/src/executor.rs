@@ -0,0 +1,205 @@
+//! Runs a program without proving it, to iterate on it quickly.
+
+use crate::error::ExecutionError;
+use crate::gdb::GdbStub;
+use crate::profiler::Profiler;
+use crate::report::ExecutionReport;
+use crate::state::{InstrumentedState, State};
+use crate::trace::{trace_registers, Divergence, ExecutionTrace, TraceStep};
+use crate::utils::SEGMENT_STEPS;
+use std::io;
+use std::net::TcpListener;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExecutorOpts {
+    /// The cycles of a segment, so that the cycles of the run are those of the proof.
+    pub seg_size: usize,
+    /// The cycles after which the run stops with [`ExecutionError::CycleLimitExceeded`], for
+    /// the programs which may not exit. `None` runs them to their end.
+    pub max_cycles: Option<u64>,
+}
+
+impl Default for ExecutorOpts {
+    fn default() -> Self {
+        Self {
+            seg_size: SEGMENT_STEPS,
+            max_cycles: None,
+        }
+    }
+}
+
+impl ExecutorOpts {
+    /// Whether the run of `state` went past `max_cycles`.
+    pub fn check_cycles(&self, state: &State) -> Result<(), ExecutionError> {
+        let cycles = state.total_cycle + state.cycle;
+        match self.max_cycles {
+            Some(max_cycles) if cycles > max_cycles => Err(ExecutionError::CycleLimitExceeded {
+                pc: state.pc,
+                cycles,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+pub struct Executor {
+    instrumented_state: Box<InstrumentedState>,
+    opts: ExecutorOpts,
+    profiler: Option<Profiler>,
+    trace: Option<ExecutionTrace>,
+}
+
+impl Executor {
+    /// Runs `state` in segments of `seg_size` cycles, so that the cycles are those of the proof.
+    pub fn new(state: Box<State>, seg_size: usize) -> Self {
+        Self::from_opts(
+            state,
+            ExecutorOpts {
+                seg_size,
+                ..Default::default()
+            },
+        )
+    }
+
+    pub fn from_opts(state: Box<State>, opts: ExecutorOpts) -> Self {
+        Self {
+            instrumented_state: InstrumentedState::new(state, String::new()),
+            opts,
+            profiler: None,
+            trace: None,
+        }
+    }
+
+    /// Records the trace of the run, see [`Executor::take_trace`].
+    pub fn with_trace(mut self) -> Self {
+        self.trace = Some(ExecutionTrace::default());
+        self
+    }
+
+    /// Profiles the cycles of the run with `profiler`, see [`Executor::take_profiler`].
+    pub fn with_profiler(mut self, profiler: Profiler) -> Self {
+        self.profiler = Some(profiler);
+        self
+    }
+
+    /// Runs the program to its end, without writing the segments, and returns what it used,
+    /// or [`ExecutionError::GuestPanicked`] if it panicked.
+    pub fn run(&mut self) -> Result<ExecutionReport, ExecutionError> {
+        self.split_segment();
+        while !self.instrumented_state.state.exited {
+            self.step();
+            self.check_cycles()?;
+        }
+        self.split_segment();
+        if let Some(panic) = self.instrumented_state.state.guest_panic() {
+            return Err(panic);
+        }
+        Ok(self.instrumented_state.state.report())
+    }
+
+    /// Runs the program under the GDB stub, for the first debugger connecting to `listener`,
+    /// see [`crate::gdb`].
+    pub fn debug(&mut self, listener: TcpListener) -> io::Result<ExecutionReport> {
+        let (stream, _) = listener.accept()?;
+        self.split_segment();
+        GdbStub::new(self, stream).serve()?;
+        self.split_segment();
+        Ok(self.instrumented_state.state.report())
+    }
+
+    /// Runs the program again, checking each instruction against `trace`, the one of a
+    /// previous run.
+    pub fn replay(&mut self, trace: &ExecutionTrace) -> Result<ExecutionReport, Divergence> {
+        self.trace = Some(ExecutionTrace::default());
+        self.split_segment();
+        for (step, expected) in (0..).zip(&trace.steps) {
+            let found = self.next_trace_step();
+            if found.as_ref() != Some(expected) {
+                return Err(Divergence {
+                    step,
+                    expected: Some(expected.clone()),
+                    found,
+                });
+            }
+        }
+        if let Some(found) = self.next_trace_step() {
+            return Err(Divergence {
+                step: trace.steps.len() as u64,
+                expected: None,
+                found: Some(found),
+            });
+        }
+        self.trace = None;
+        self.split_segment();
+        Ok(self.instrumented_state.state.report())
+    }
+
+    /// Executes the next instruction and returns its step, `None` if the program exited.
+    fn next_trace_step(&mut self) -> Option<TraceStep> {
+        if self.instrumented_state.state.exited {
+            return None;
+        }
+        self.step();
+        self.trace.as_mut()?.steps.pop()
+    }
+
+    /// Executes the next instruction, and starts a new segment once this one is full.
+    pub(crate) fn step(&mut self) {
+        let state = &mut self.instrumented_state.state;
+        let (pc, cycle) = (state.pc, state.total_cycle + state.cycle);
+        let insn = match self.profiler {
+            Some(_) => state.memory.get_memory(pc),
+            None => 0,
+        };
+        let registers = self.trace.as_ref().map(|_| {
+            state.memory.start_access_log();
+            trace_registers(state)
+        });
+        let cycles = self.instrumented_state.step();
+        if let Some(profiler) = &mut self.profiler {
+            let state = &self.instrumented_state.state;
+            profiler.record(pc, insn, state.total_cycle + state.cycle - cycle);
+        }
+        if let (Some(trace), Some(before)) = (&mut self.trace, registers) {
+            let state = &mut self.instrumented_state.state;
+            let mut memory = state.memory.take_access_log();
+            // The first word read is the instruction.
+            let insn = match memory.first() {
+                Some(op) if op.addr == pc && !op.is_write => memory.remove(0).value,
+                _ => 0,
+            };
+            let after = trace_registers(state);
+            trace
+                .steps
+                .push(TraceStep::new(pc, insn, &before, &after, memory));
+        }
+        if cycles > (self.opts.seg_size as isize - 1) as u64 {
+            self.split_segment();
+        }
+    }
+
+    /// Whether the run went past the `max_cycles` of its options.
+    pub(crate) fn check_cycles(&self) -> Result<(), ExecutionError> {
+        self.opts.check_cycles(&self.instrumented_state.state)
+    }
+
+    fn split_segment(&mut self) {
+        let new_writer = |_: &str| -> Option<std::fs::File> { None };
+        self.instrumented_state.split_segment(false, "", new_writer);
+    }
+
+    /// The trace of the run after [`Executor::run`], if it was recorded.
+    pub fn take_trace(&mut self) -> Option<ExecutionTrace> {
+        self.trace.take()
+    }
+
+    /// The profile of the run after [`Executor::run`], if it was profiled.
+    pub fn take_profiler(&mut self) -> Option<Profiler> {
+        self.profiler.take()
+    }
+
+    /// The state of the program, to read its public values after [`Executor::run`].
+    pub fn state(&mut self) -> &mut State {
+        &mut self.instrumented_state.state
+    }
+}
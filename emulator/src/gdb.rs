@@ -0,0 +1,334 @@
+//! A stub of the GDB remote serial protocol, to debug a guest with `gdb-multiarch`, run by
+//! [`Executor::debug`]:
+//!
+//! ```text
+//! zkm execute guest.elf --debug 127.0.0.1:9000
+//! gdb-multiarch guest.elf -ex "target remote 127.0.0.1:9000"
+//! ```
+//!
+//! It supports the breakpoints, single-stepping, interrupting a run with Ctrl-C, and reading and
+//! writing the registers and the memory. The breakpoints are kept by the stub, not written to the
+//! code. There is no FPU, so its registers read as zero. A run past the `max_cycles` of the
+//! executor stops with `SIGXCPU`.
+//!
+//! [`Executor::debug`]: crate::executor::Executor::debug
+
+use crate::executor::Executor;
+use log::{debug, warn};
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::TcpStream;
+
+/// The number of registers of the target description: the CPU and CP0 ones, then the 32 FPU
+/// registers, FCSR and FIR.
+const NUM_REGISTERS: usize = 72;
+
+/// The number of instructions run between two checks of an interrupt from the debugger.
+const INTERRUPT_CHECK_STEPS: u64 = 1 << 16;
+
+const SIGINT: u8 = 2;
+const SIGTRAP: u8 = 5;
+const SIGXCPU: u8 = 24;
+
+fn target_xml() -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\"?><!DOCTYPE target SYSTEM \"gdb-target.dtd\">\
+         <target version=\"1.0\"><architecture>mips</architecture>",
+    );
+    let reg = |xml: &mut String, name: &str, regnum: usize| {
+        let _ = write!(
+            xml,
+            "<reg name=\"{}\" bitsize=\"32\" regnum=\"{}\"/>",
+            name, regnum
+        );
+    };
+    xml.push_str("<feature name=\"org.gnu.gdb.mips.cpu\">");
+    for i in 0..32 {
+        reg(&mut xml, &format!("r{}", i), i);
+    }
+    reg(&mut xml, "lo", 33);
+    reg(&mut xml, "hi", 34);
+    reg(&mut xml, "pc", 37);
+    xml.push_str("</feature><feature name=\"org.gnu.gdb.mips.cp0\">");
+    reg(&mut xml, "status", 32);
+    reg(&mut xml, "badvaddr", 35);
+    reg(&mut xml, "cause", 36);
+    xml.push_str("</feature><feature name=\"org.gnu.gdb.mips.fpu\">");
+    for i in 0..32 {
+        reg(&mut xml, &format!("f{}", i), 38 + i);
+    }
+    reg(&mut xml, "fcsr", 70);
+    reg(&mut xml, "fir", 71);
+    xml.push_str("</feature></target>");
+    xml
+}
+
+fn checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte))
+}
+
+fn parse_hex(hex: &str) -> Option<u32> {
+    u32::from_str_radix(hex, 16).ok()
+}
+
+fn parse_addr_len(args: &str) -> Option<(u32, usize)> {
+    let (addr, len) = args.split_once(',')?;
+    Some((parse_hex(addr)?, parse_hex(len)? as usize))
+}
+
+/// Why the guest stopped running.
+enum Stop {
+    Signal(u8),
+    Exited(u8),
+}
+
+impl Stop {
+    fn reply(&self) -> String {
+        match self {
+            Self::Signal(signal) => format!("S{:02x}", signal),
+            Self::Exited(exit_code) => format!("W{:02x}", exit_code),
+        }
+    }
+}
+
+pub struct GdbStub<'a> {
+    executor: &'a mut Executor,
+    stream: TcpStream,
+    breakpoints: BTreeSet<u32>,
+}
+
+impl<'a> GdbStub<'a> {
+    pub fn new(executor: &'a mut Executor, stream: TcpStream) -> Self {
+        Self {
+            executor,
+            stream,
+            breakpoints: BTreeSet::new(),
+        }
+    }
+
+    /// Answers the debugger until it kills the guest or detaches, or the guest exits. After a
+    /// detach the guest runs to its end.
+    pub fn serve(&mut self) -> io::Result<()> {
+        loop {
+            let Some(packet) = self.read_packet()? else {
+                // The debugger is gone.
+                return Ok(());
+            };
+            debug!("gdb: {}", packet);
+            match packet.as_bytes().first() {
+                Some(b'k') => return Ok(()),
+                Some(b'D') => {
+                    self.write_packet("OK")?;
+                    while !self.executor.state().exited && self.executor.check_cycles().is_ok() {
+                        self.executor.step();
+                    }
+                    return Ok(());
+                }
+                Some(b'c' | b's') => {
+                    let stop = self.resume(packet.starts_with('s'))?;
+                    self.write_packet(&stop.reply())?;
+                    if let Stop::Exited(_) = stop {
+                        return Ok(());
+                    }
+                }
+                _ => {
+                    let reply = self.handle(&packet);
+                    self.write_packet(&reply)?;
+                }
+            }
+        }
+    }
+
+    /// The reply to a packet which does not run the guest, empty if it is not supported.
+    fn handle(&mut self, packet: &str) -> String {
+        if packet.is_empty() {
+            return String::new();
+        }
+        let state = self.executor.state();
+        let (command, args) = packet.split_at(1);
+        match command {
+            "?" if state.exited => Stop::Exited(state.exit_code).reply(),
+            "?" => Stop::Signal(SIGTRAP).reply(),
+            "g" => (0..NUM_REGISTERS)
+                .map(|n| format!("{:08x}", state.gdb_register(n).unwrap_or(0)))
+                .collect(),
+            "G" => {
+                for n in 0..NUM_REGISTERS.min(args.len() / 8) {
+                    if let Some(value) = parse_hex(&args[n * 8..n * 8 + 8]) {
+                        state.set_gdb_register(n, value);
+                    }
+                }
+                "OK".to_string()
+            }
+            "p" => match parse_hex(args) {
+                Some(n) if (n as usize) < NUM_REGISTERS => {
+                    format!("{:08x}", state.gdb_register(n as usize).unwrap_or(0))
+                }
+                _ => "E01".to_string(),
+            },
+            "P" => {
+                let register = args
+                    .split_once('=')
+                    .and_then(|(n, value)| Some((parse_hex(n)? as usize, parse_hex(value)?)));
+                match register {
+                    Some((n, value)) if n < NUM_REGISTERS => {
+                        // The FPU registers are ignored.
+                        state.set_gdb_register(n, value);
+                        "OK".to_string()
+                    }
+                    _ => "E01".to_string(),
+                }
+            }
+            "m" => match parse_addr_len(args) {
+                Some((addr, len)) => (0..len as u32)
+                    .map(|i| format!("{:02x}", state.memory.peek(addr.wrapping_add(i))))
+                    .collect(),
+                None => "E01".to_string(),
+            },
+            "M" => {
+                let write = args.split_once(':').and_then(|(addr_len, data)| {
+                    let (addr, len) = parse_addr_len(addr_len)?;
+                    let bytes = (0..len)
+                        .map(|i| u8::from_str_radix(data.get(2 * i..2 * i + 2)?, 16).ok())
+                        .collect::<Option<Vec<u8>>>()?;
+                    Some((addr, bytes))
+                });
+                match write {
+                    Some((addr, bytes)) => {
+                        for (addr, byte) in (addr..).zip(bytes) {
+                            let word_addr = addr & !3;
+                            let mut word = state.memory.get_memory(word_addr).to_be_bytes();
+                            word[(addr & 3) as usize] = byte;
+                            state.memory.set_memory(word_addr, u32::from_be_bytes(word));
+                        }
+                        "OK".to_string()
+                    }
+                    None => "E01".to_string(),
+                }
+            }
+            "Z" | "z" => {
+                // Software and hardware breakpoints alike, but no watchpoints.
+                let breakpoint = args
+                    .split(',')
+                    .nth(1)
+                    .and_then(parse_hex)
+                    .filter(|_| args.starts_with('0') || args.starts_with('1'));
+                match breakpoint {
+                    Some(addr) if command == "Z" => {
+                        self.breakpoints.insert(addr);
+                        "OK".to_string()
+                    }
+                    Some(addr) => {
+                        self.breakpoints.remove(&addr);
+                        "OK".to_string()
+                    }
+                    None => String::new(),
+                }
+            }
+            "H" => "OK".to_string(),
+            "q" if args.starts_with("Supported") => {
+                "PacketSize=4000;qXfer:features:read+".to_string()
+            }
+            "q" if args == "Attached" => "1".to_string(),
+            "q" if args.starts_with("Xfer:features:read:target.xml:") => {
+                let range = args.rsplit(':').next().and_then(parse_addr_len);
+                match range {
+                    Some((offset, len)) => {
+                        let xml = target_xml();
+                        let start = (offset as usize).min(xml.len());
+                        let end = (start + len).min(xml.len());
+                        let more = if end < xml.len() { 'm' } else { 'l' };
+                        format!("{}{}", more, &xml[start..end])
+                    }
+                    None => "E01".to_string(),
+                }
+            }
+            _ => String::new(),
+        }
+    }
+
+    /// Runs the guest for one instruction, or until it hits a breakpoint, exits, or the debugger
+    /// interrupts it.
+    fn resume(&mut self, single_step: bool) -> io::Result<Stop> {
+        let mut steps = 0u64;
+        loop {
+            if self.executor.state().exited {
+                return Ok(Stop::Exited(self.executor.state().exit_code));
+            }
+            self.executor.step();
+            steps += 1;
+            let state = self.executor.state();
+            if state.exited {
+                return Ok(Stop::Exited(state.exit_code));
+            }
+            if single_step || self.breakpoints.contains(&state.pc) {
+                return Ok(Stop::Signal(SIGTRAP));
+            }
+            if self.executor.check_cycles().is_err() {
+                return Ok(Stop::Signal(SIGXCPU));
+            }
+            if steps % INTERRUPT_CHECK_STEPS == 0 && self.interrupted()? {
+                return Ok(Stop::Signal(SIGINT));
+            }
+        }
+    }
+
+    /// Whether the debugger sent an interrupt, without waiting for it.
+    fn interrupted(&mut self) -> io::Result<bool> {
+        self.stream.set_nonblocking(true)?;
+        let mut byte = [0u8];
+        let read = self.stream.read(&mut byte);
+        self.stream.set_nonblocking(false)?;
+        match read {
+            Ok(1) => Ok(byte[0] == 0x03),
+            Ok(_) => Ok(false),
+            Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Reads the next packet, acknowledging it. `None` once the connection is closed.
+    fn read_packet(&mut self) -> io::Result<Option<String>> {
+        let mut byte = [0u8];
+        loop {
+            // Skip the acknowledgements and the interrupts sent while the guest was stopped.
+            loop {
+                if self.stream.read(&mut byte)? == 0 {
+                    return Ok(None);
+                }
+                if byte[0] == b'$' {
+                    break;
+                }
+            }
+            let mut data = Vec::new();
+            loop {
+                if self.stream.read(&mut byte)? == 0 {
+                    return Ok(None);
+                }
+                if byte[0] == b'#' {
+                    break;
+                }
+                data.push(byte[0]);
+            }
+            let mut sum = [0u8; 2];
+            self.stream.read_exact(&mut sum)?;
+            let expected = std::str::from_utf8(&sum)
+                .ok()
+                .and_then(|sum| u8::from_str_radix(sum, 16).ok());
+            if expected == Some(checksum(&data)) {
+                self.stream.write_all(b"+")?;
+                return Ok(Some(String::from_utf8_lossy(&data).into_owned()));
+            }
+            warn!("gdb: invalid checksum of packet {:?}", data);
+            self.stream.write_all(b"-")?;
+        }
+    }
+
+    fn write_packet(&mut self, data: &str) -> io::Result<()> {
+        debug!("gdb reply: {}", data);
+        let packet = format!("${}#{:02x}", data, checksum(data.as_bytes()));
+        self.stream.write_all(packet.as_bytes())?;
+        self.stream.flush()
+    }
+}
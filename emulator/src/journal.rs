@@ -0,0 +1,57 @@
+//! The journal of a program, the values it committed with `zkm_runtime::io::commit`.
+
+use crate::memory::poseidon;
+use crate::sha256;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// The hash of the digest of a journal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JournalHash {
+    /// SHA-256, which the program computes with `zkm_runtime::io::committed_digest`.
+    Sha256,
+    /// Poseidon over Goldilocks, as the pages of the memory, with the limbs of the digest in
+    /// little endian.
+    Poseidon,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Journal {
+    pub bytes: Vec<u8>,
+    /// The position of the next value to decode.
+    pub ptr: usize,
+}
+
+impl Journal {
+    pub fn new(bytes: Vec<u8>) -> Self {
+        Self { bytes, ptr: 0 }
+    }
+
+    /// Decodes the next value committed by the program, in the order it committed them.
+    pub fn decode<T: DeserializeOwned>(&mut self) -> bincode::Result<T> {
+        let mut rest = &self.bytes[self.ptr..];
+        let value = bincode::deserialize_from(&mut rest)?;
+        self.ptr = self.bytes.len() - rest.len();
+        Ok(value)
+    }
+
+    /// Whether all the values have been decoded.
+    pub fn is_empty(&self) -> bool {
+        self.ptr == self.bytes.len()
+    }
+
+    /// The digest of all the bytes of the journal, which stands for the values committed.
+    pub fn digest(&self, hash: JournalHash) -> [u8; 32] {
+        match hash {
+            JournalHash::Sha256 => sha256::digest(&self.bytes),
+            JournalHash::Poseidon => {
+                let limbs = poseidon(&self.bytes);
+                let mut digest = [0u8; 32];
+                for (bytes, limb) in digest.chunks_exact_mut(8).zip(limbs) {
+                    bytes.copy_from_slice(&limb.to_le_bytes());
+                }
+                digest
+            }
+        }
+    }
+}
@@ -1,8 +1,32 @@
 #![allow(dead_code)]
 
+pub mod blake;
+pub mod bls12_381;
+pub mod bn254;
+pub mod cannon;
+pub mod chacha;
+pub mod cp0;
+pub mod cycle_tracker;
+pub mod ed25519;
+pub mod error;
+pub mod executor;
+pub mod gdb;
+pub mod journal;
+pub mod loader;
 pub mod memory;
+pub mod modexp;
 pub mod opcode_id;
+pub mod p256;
 pub mod page;
+pub mod profiler;
+pub mod report;
+pub mod secp256k1;
+pub mod sha256;
 pub mod state;
+pub mod stdin;
+pub mod syscall;
 pub mod tests;
+pub mod trace;
+pub mod uint256;
 pub mod utils;
+pub mod weierstrass;
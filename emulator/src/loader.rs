@@ -0,0 +1,274 @@
+//! The checks of the ELF files of the programs, before [`State::load_elf`] maps them.
+//!
+//! A program must be a statically linked MIPS32 big-endian executable, position dependent or
+//! not. Its `PT_LOAD` segments are mapped at their addresses, the bytes past their file size
+//! zeroed, and the stack is set up by [`State::patch_stack_with_env`].
+//!
+//! [`PreprocessedProgram`] keeps the result of the loading, to start runs of the same program
+//! without parsing its ELF nor hashing its image again.
+//!
+//! [`State::load_elf`]: crate::state::State::load_elf
+//! [`State::patch_stack_with_env`]: crate::state::State::patch_stack_with_env
+
+use crate::memory::INIT_SP;
+use crate::page::PAGE_ADDR_MASK;
+use crate::report::ExecutionReport;
+use crate::state::State;
+use crate::syscall::MMAP_START;
+use crate::utils::compute_image_id;
+use elf::abi::{EM_MIPS, ET_DYN, ET_EXEC, PT_DYNAMIC, PT_INTERP, PT_LOAD, PT_TLS};
+use elf::endian::AnyEndian;
+use elf::file::Class;
+use elf::ElfBytes;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// The type of the `.MIPS.abiflags` segment, which is not loaded.
+pub const PT_MIPS_ABIFLAGS: u32 = 0x70000003;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ElfError {
+    /// The file is not an ELF file.
+    Parse(String),
+    /// The file is not for a 32-bit big-endian MIPS.
+    UnsupportedArch {
+        machine: u16,
+        is_32: bool,
+        is_be: bool,
+    },
+    /// The file is neither an executable nor a position independent one.
+    UnsupportedType(u16),
+    /// The file is dynamically linked.
+    DynamicallyLinked,
+    /// The file has no segment to load.
+    NoLoadSegment,
+    /// A segment has more bytes in the file than in memory, or is not loaded but zero-filled.
+    InvalidSegment {
+        p_type: u32,
+        filesz: u64,
+        memsz: u64,
+    },
+    /// A segment ends past 4 GiB.
+    OutOfRange { vaddr: u64, memsz: u64 },
+    /// The memory layout does not fit the program.
+    InvalidLayout(&'static str),
+}
+
+impl Display for ElfError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Parse(err) => write!(f, "invalid ELF file: {}", err),
+            Self::UnsupportedArch {
+                machine,
+                is_32,
+                is_be,
+            } => write!(
+                f,
+                "not a MIPS32 big-endian program: machine {}, {}-bit, {} endian",
+                machine,
+                if *is_32 { 32 } else { 64 },
+                if *is_be { "big" } else { "little" }
+            ),
+            Self::UnsupportedType(e_type) => write!(f, "not an executable: ELF type {}", e_type),
+            Self::DynamicallyLinked => write!(f, "dynamically linked programs are not supported"),
+            Self::NoLoadSegment => write!(f, "no segment to load"),
+            Self::InvalidSegment {
+                p_type,
+                filesz,
+                memsz,
+            } => write!(
+                f,
+                "invalid segment of type 0x{:x}: file size 0x{:x}, memory size 0x{:x}",
+                p_type, filesz, memsz
+            ),
+            Self::OutOfRange { vaddr, memsz } => write!(
+                f,
+                "segment out of the 32-bit memory: 0x{:x}, size 0x{:x}",
+                vaddr, memsz
+            ),
+            Self::InvalidLayout(reason) => write!(f, "invalid memory layout: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ElfError {}
+
+/// Parses `elf`, and checks it can be loaded.
+pub fn parse_elf(elf: &[u8]) -> Result<ElfBytes<'_, AnyEndian>, ElfError> {
+    let file =
+        ElfBytes::<AnyEndian>::minimal_parse(elf).map_err(|e| ElfError::Parse(e.to_string()))?;
+    check_elf(&file)?;
+    Ok(file)
+}
+
+pub fn check_elf(f: &ElfBytes<AnyEndian>) -> Result<(), ElfError> {
+    let is_32 = f.ehdr.class == Class::ELF32;
+    let is_be = f.ehdr.endianness == AnyEndian::Big;
+    if f.ehdr.e_machine != EM_MIPS || !is_32 || !is_be {
+        return Err(ElfError::UnsupportedArch {
+            machine: f.ehdr.e_machine,
+            is_32,
+            is_be,
+        });
+    }
+    if f.ehdr.e_type != ET_EXEC && f.ehdr.e_type != ET_DYN {
+        return Err(ElfError::UnsupportedType(f.ehdr.e_type));
+    }
+
+    let segments = f
+        .segments()
+        .ok_or_else(|| ElfError::Parse("no program headers".to_string()))?;
+    let mut loaded = false;
+    for segment in segments {
+        match segment.p_type {
+            PT_INTERP | PT_DYNAMIC => return Err(ElfError::DynamicallyLinked),
+            PT_MIPS_ABIFLAGS => continue,
+            PT_LOAD => loaded = true,
+            _ => {}
+        }
+        // Only the loaded segments are zero-filled.
+        let zero_filled = segment.p_type == PT_LOAD || segment.p_type == PT_TLS;
+        if segment.p_filesz > segment.p_memsz
+            || (segment.p_filesz != segment.p_memsz && !zero_filled)
+        {
+            return Err(ElfError::InvalidSegment {
+                p_type: segment.p_type,
+                filesz: segment.p_filesz,
+                memsz: segment.p_memsz,
+            });
+        }
+        let end = segment.p_vaddr.checked_add(segment.p_memsz);
+        if !matches!(end, Some(end) if end < 1u64 << 32) {
+            return Err(ElfError::OutOfRange {
+                vaddr: segment.p_vaddr,
+                memsz: segment.p_memsz,
+            });
+        }
+    }
+    if !loaded {
+        return Err(ElfError::NoLoadSegment);
+    }
+    Ok(())
+}
+
+/// A program loaded from its ELF, before its stack is set up.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PreprocessedProgram {
+    pub entry: u32,
+    pub brk: u32,
+    /// The pages of the code and data, by page index.
+    pub pages: Vec<(u32, Vec<u8>)>,
+    /// The image id of the program run without arguments nor environment variables.
+    pub image_id: [u8; 32],
+}
+
+impl PreprocessedProgram {
+    pub fn preprocess(elf: &[u8]) -> Result<Self, ElfError> {
+        let file = parse_elf(elf)?;
+        let mut state = State::load_elf(&file);
+        state.patch_elf(&file);
+
+        let program = Self {
+            entry: state.pc,
+            brk: state.brk(),
+            pages: state
+                .memory
+                .pages()
+                .map(|(page_index, data)| (page_index, data.to_vec()))
+                .collect(),
+            image_id: [0; 32],
+        };
+        state.patch_stack_with_env(vec![], &[]);
+        Ok(Self {
+            image_id: compute_image_id(state),
+            ..program
+        })
+    }
+
+    /// The state the program starts with, with the arguments `args` and the environment
+    /// variables `envs`.
+    pub fn load(&self, args: Vec<&str>, envs: &[(&str, &str)]) -> Box<State> {
+        let mut state = State::from_preprocessed(self);
+        state.patch_stack_with_env(args, envs);
+        state
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        bincode::serialize(self).expect("serialization failed")
+    }
+
+    pub fn deserialize(bytes: &[u8]) -> bincode::Result<Self> {
+        bincode::deserialize(bytes)
+    }
+}
+
+/// The size of the stack of [`MemoryLayout::default`].
+pub const DEFAULT_STACK_SIZE: u32 = 0x1000000;
+
+/// Where the heap and the stack of a program are, see [`State::set_memory_layout`].
+///
+/// The stack starts at `INIT_SP`, where the runtime reads the arguments, and the program break
+/// stays below `MMAP_START`, as the prover assumes: only the start of the mmap region, which
+/// the heap is allocated from, and the size of the stack can be chosen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryLayout {
+    pub heap_start: u32,
+    pub stack_size: u32,
+}
+
+impl Default for MemoryLayout {
+    fn default() -> Self {
+        Self {
+            heap_start: MMAP_START,
+            stack_size: DEFAULT_STACK_SIZE,
+        }
+    }
+}
+
+impl MemoryLayout {
+    /// The bottom of the stack, which the heap must stay below.
+    pub fn heap_end(&self) -> u32 {
+        INIT_SP.saturating_sub(self.stack_size)
+    }
+
+    /// Checks the layout for a program whose break starts at `brk`.
+    pub fn validate(&self, brk: u32) -> Result<(), ElfError> {
+        let aligned = |addr: u32| addr & PAGE_ADDR_MASK as u32 == 0;
+        if !aligned(self.heap_start) || !aligned(self.stack_size) {
+            return Err(ElfError::InvalidLayout("not aligned to pages"));
+        }
+        if self.heap_start < MMAP_START {
+            return Err(ElfError::InvalidLayout(
+                "the heap starts below the program break",
+            ));
+        }
+        if brk > MMAP_START {
+            return Err(ElfError::InvalidLayout(
+                "the program ends above the program break",
+            ));
+        }
+        if self.stack_size >= INIT_SP || self.heap_start >= self.heap_end() {
+            return Err(ElfError::InvalidLayout("the heap starts in the stack"));
+        }
+        Ok(())
+    }
+
+    /// Checks that a run stayed within the layout.
+    pub fn check(&self, report: &ExecutionReport) -> Result<(), String> {
+        if report.heap_end > self.heap_end() {
+            return Err(format!(
+                "the heap grew to 0x{:x}, into the stack above 0x{:x}",
+                report.heap_end,
+                self.heap_end()
+            ));
+        }
+        if report.stack_size() > self.stack_size {
+            return Err(format!(
+                "the stack grew to 0x{:x} bytes, more than 0x{:x}",
+                report.stack_size(),
+                self.stack_size
+            ));
+        }
+        Ok(())
+    }
+}
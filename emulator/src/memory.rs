@@ -2,8 +2,12 @@
 use std::cell::RefCell;
 pub const WORD_SIZE: usize = core::mem::size_of::<u32>();
 pub const INIT_SP: u32 = 0x7fffd000;
+/// Where the host stores the seed of the random bytes of the guest, in the last page below the
+/// hashes.
+pub const RANDOM_SEED_ADDRESS: u32 = 0x7ffff000;
 use super::page::MAX_MEMORY;
 use crate::page::{CachedPage, PAGE_ADDR_MASK, PAGE_ADDR_SIZE, PAGE_SIZE};
+use crate::trace::MemoryOp;
 use itertools::Itertools;
 use lazy_static::lazy_static;
 use plonky2::field::goldilocks_field::GoldilocksField;
@@ -133,6 +137,9 @@ pub struct Memory {
 
     rtrace: BTreeMap<u32, [u8; PAGE_SIZE]>,
     wtrace: [BTreeMap<u32, Rc<RefCell<CachedPage>>>; 3],
+
+    /// The words read and written, while an execution trace is recorded.
+    access_log: Option<Vec<MemoryOp>>,
 }
 
 pub fn hash_cached_page(page: &Rc<RefCell<CachedPage>>) -> [u8; 32] {
@@ -158,6 +165,7 @@ impl Memory {
             count: 0,
             rtrace: BTreeMap::new(),
             wtrace: [BTreeMap::new(), BTreeMap::new(), BTreeMap::new()],
+            access_log: None,
         }
     }
 
@@ -177,6 +185,13 @@ impl Memory {
         Ok(())
     }
 
+    /// The data of the pages allocated, by page index.
+    pub fn pages(&self) -> impl Iterator<Item = (u32, [u8; PAGE_SIZE])> + '_ {
+        self.pages
+            .iter()
+            .map(|(&page_index, page)| (page_index, page.borrow().data))
+    }
+
     fn page_lookup(&mut self, page_index: u32) -> Option<Rc<RefCell<CachedPage>>> {
         // find cache first
         if Some(page_index) == self.last_page_keys[0] {
@@ -219,6 +234,18 @@ impl Memory {
     }
 
     pub fn get_memory(&mut self, addr: u32) -> u32 {
+        let value = self.read_word(addr);
+        if let Some(log) = &mut self.access_log {
+            log.push(MemoryOp {
+                addr,
+                value,
+                is_write: false,
+            });
+        }
+        value
+    }
+
+    fn read_word(&mut self, addr: u32) -> u32 {
         // addr must be aligned to 4 bytes
         if addr & 0x3 != 0 {
             panic!("unaligned memory access: {:x?}", addr);
@@ -256,6 +283,13 @@ impl Memory {
         word.to_be_bytes()[(addr & 3) as usize]
     }
 
+    /// The byte at `addr`, read without tracing the page, as a debugger reads it.
+    pub fn peek(&self, addr: u32) -> u8 {
+        self.pages
+            .get(&(addr >> PAGE_ADDR_SIZE))
+            .map_or(0, |page| page.borrow().data[addr as usize & PAGE_ADDR_MASK])
+    }
+
     fn alloc_page(&mut self, page_index: u32) -> Rc<RefCell<CachedPage>> {
         let cached_page = Rc::new(RefCell::new(CachedPage::new()));
         self.pages.insert(page_index, cached_page.clone());
@@ -296,6 +330,27 @@ impl Memory {
 
         let mut cached_page = cached_page.borrow_mut();
         cached_page.data[page_addr..page_addr + 4].copy_from_slice(&v.to_be_bytes());
+        if let Some(log) = &mut self.access_log {
+            log.push(MemoryOp {
+                addr,
+                value: v,
+                is_write: true,
+            });
+        }
+    }
+
+    /// Logs the words read and written from now on, until [`Memory::take_access_log`].
+    pub fn start_access_log(&mut self) {
+        self.access_log = Some(Vec::new());
+    }
+
+    pub fn take_access_log(&mut self) -> Vec<MemoryOp> {
+        self.access_log.take().unwrap_or_default()
+    }
+
+    /// The size of the memory the program touched, in whole pages.
+    pub fn touched_bytes(&self) -> u64 {
+        (self.pages.len() * PAGE_SIZE) as u64
     }
 
     pub fn usage(&self) -> String {
@@ -0,0 +1,35 @@
+//! Modular exponentiation of integers of up to 2048 bits, as computed by the `MODEXP` syscall,
+//! for the verification of RSA signatures and the EVM `0x05` precompile.
+//!
+//! An integer is a slice of little-endian words, which need not be reduced. The base and the
+//! modulus have the same number of words, at most `MODEXP_MAX_WORDS`, and the exponent at most
+//! `MODEXP_MAX_EXP_WORDS`. Results are reduced, with as many words as the modulus.
+
+use num_bigint::BigUint;
+
+/// The largest number of words of the base and the modulus.
+pub const MODEXP_MAX_WORDS: usize = 64;
+
+/// The largest number of words of the exponent.
+pub const MODEXP_MAX_EXP_WORDS: usize = 256;
+
+/// Computes `base^exp mod modulus`, or `None` for a zero modulus or lengths out of range.
+pub fn modexp(base: &[u32], exp: &[u32], modulus: &[u32]) -> Option<Vec<u32>> {
+    let len = modulus.len();
+    if base.len() != len || !(1..=MODEXP_MAX_WORDS).contains(&len) {
+        return None;
+    }
+    if !(1..=MODEXP_MAX_EXP_WORDS).contains(&exp.len()) {
+        return None;
+    }
+    let m = BigUint::from_slice(modulus);
+    if m.bits() == 0 {
+        return None;
+    }
+    let r = BigUint::from_slice(base).modpow(&BigUint::from_slice(exp), &m);
+    let mut words = vec![0u32; len];
+    for (word, digit) in words.iter_mut().zip(r.to_u32_digits()) {
+        *word = digit;
+    }
+    Some(words)
+}
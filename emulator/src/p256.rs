@@ -0,0 +1,16 @@
+//! The P-256 curve `y^2 = x^3 - 3x + b`, also known as secp256r1, of the `SECP256R1_ADD` and
+//! `SECP256R1_DOUBLE` syscalls.
+
+use lazy_static::lazy_static;
+use num_bigint::BigUint;
+
+lazy_static! {
+    /// The modulus of the base field, `2^256 - 2^224 + 2^192 + 2^96 - 1`.
+    pub static ref MODULUS: BigUint = BigUint::parse_bytes(
+        b"ffffffff00000001000000000000000000000000ffffffffffffffffffffffff",
+        16
+    )
+    .unwrap();
+    /// `a = -3`.
+    pub static ref A: BigUint = &*MODULUS - 3u32;
+}
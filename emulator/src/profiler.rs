@@ -0,0 +1,173 @@
+//! A profile of the cycles of a program by function, from the symbols of its ELF.
+//!
+//! Every instruction is counted: its cycles go to the stack of the functions running, tracked
+//! from the calls (`jal`, `jalr`, `bgezal`) and the returns to the instruction after their delay
+//! slot. [`Profiler::write_collapsed`] writes the folded stacks which `inferno-flamegraph` and
+//! speedscope read. The functions are resolved from the symbol table only, without line
+//! information, so the ELF must not be stripped.
+
+use crate::loader::{parse_elf, ElfError};
+use elf::abi::STT_FUNC;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// A function of the program, at `start..end`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Symbol {
+    pub start: u32,
+    pub end: u32,
+    pub name: String,
+}
+
+/// The functions of a program, by address.
+#[derive(Clone, Debug, Default)]
+pub struct Symbols {
+    symbols: Vec<Symbol>,
+}
+
+impl Symbols {
+    /// The functions of the symbol table of `elf`, with their names demangled.
+    pub fn from_elf(elf: &[u8]) -> Result<Self, ElfError> {
+        let file = parse_elf(elf)?;
+        let Some((table, names)) = file
+            .symbol_table()
+            .map_err(|e| ElfError::Parse(e.to_string()))?
+        else {
+            return Ok(Self::default());
+        };
+
+        let mut symbols = Vec::new();
+        for symbol in table.iter() {
+            if symbol.st_symtype() != STT_FUNC || symbol.st_value == 0 {
+                continue;
+            }
+            let name = names
+                .get(symbol.st_name as usize)
+                .map_err(|e| ElfError::Parse(e.to_string()))?;
+            symbols.push(Symbol {
+                start: symbol.st_value as u32,
+                end: (symbol.st_value + symbol.st_size) as u32,
+                name: format!("{:#}", rustc_demangle::demangle(name)),
+            });
+        }
+        Ok(Self::new(symbols))
+    }
+
+    pub fn new(mut symbols: Vec<Symbol>) -> Self {
+        symbols.sort_by_key(|symbol| symbol.start);
+        symbols.dedup_by_key(|symbol| symbol.start);
+        // The functions of the assembly often have no size: they end where the next one starts.
+        let mut next_start = u32::MAX;
+        for symbol in symbols.iter_mut().rev() {
+            if symbol.end <= symbol.start {
+                symbol.end = next_start;
+            }
+            next_start = symbol.start;
+        }
+        Self { symbols }
+    }
+
+    /// The index of the function at `pc`.
+    pub fn lookup(&self, pc: u32) -> Option<usize> {
+        let i = self.symbols.partition_point(|symbol| symbol.start <= pc);
+        i.checked_sub(1).filter(|&i| pc < self.symbols[i].end)
+    }
+
+    pub fn get(&self, index: usize) -> &Symbol {
+        &self.symbols[index]
+    }
+}
+
+/// A call not returned from yet: the function which made it, and where it returns to.
+#[derive(Clone, Copy, Debug)]
+struct Frame {
+    caller: Option<usize>,
+    return_pc: u32,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Profiler {
+    symbols: Symbols,
+    /// The calls running, the first one first.
+    stack: Vec<Frame>,
+    /// The last call, while its delay slot runs.
+    call: Option<(u32, Frame)>,
+    /// The cycles by stack of functions.
+    samples: HashMap<Vec<Option<usize>>, u64>,
+}
+
+impl Profiler {
+    pub fn new(symbols: Symbols) -> Self {
+        Self {
+            symbols,
+            ..Default::default()
+        }
+    }
+
+    /// Counts the `cycles` of the instruction `insn` executed at `pc`.
+    pub fn record(&mut self, pc: u32, insn: u32, cycles: u64) {
+        if let Some((delay_slot, frame)) = self.call {
+            if pc != delay_slot {
+                self.call = None;
+                self.stack.push(frame);
+            }
+        }
+        // A return may skip frames, as longjmp or an unwinding panic do.
+        if let Some(depth) = self.stack.iter().rposition(|frame| frame.return_pc == pc) {
+            self.stack.truncate(depth);
+        }
+
+        // The function running is the one at pc, even if it was entered with a jump.
+        let function = self.symbols.lookup(pc);
+        let stack = self.stack.iter().map(|frame| frame.caller);
+        *self
+            .samples
+            .entry(stack.chain([function]).collect())
+            .or_default() += cycles;
+
+        if is_call(insn) {
+            let frame = Frame {
+                caller: function,
+                return_pc: pc.wrapping_add(8),
+            };
+            self.call = Some((pc.wrapping_add(4), frame));
+        }
+    }
+
+    /// The cycles by stack of function names, the caller first.
+    pub fn stacks(&self) -> Vec<(Vec<&str>, u64)> {
+        let mut stacks: Vec<_> = self
+            .samples
+            .iter()
+            .map(|(stack, &cycles)| {
+                let names = stack
+                    .iter()
+                    .map(|function| match function {
+                        Some(index) => self.symbols.get(*index).name.as_str(),
+                        None => "[unknown]",
+                    })
+                    .collect();
+                (names, cycles)
+            })
+            .collect();
+        stacks.sort();
+        stacks
+    }
+
+    /// Writes the profile in the collapsed stack format, one `caller;callee cycles` line by
+    /// stack.
+    pub fn write_collapsed(&self, mut out: impl Write) -> std::io::Result<()> {
+        for (names, cycles) in self.stacks() {
+            writeln!(out, "{} {}", names.join(";"), cycles)?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether `insn` links to the instruction after its delay slot: `jal`, `jalr` or `bgezal`.
+fn is_call(insn: u32) -> bool {
+    let opcode = insn >> 26;
+    opcode == 3
+        || (opcode == 0 && insn & 0x3f == 9 && (insn >> 11) & 0x1f != 0)
+        || (opcode == 1 && matches!((insn >> 16) & 0x1f, 0x10 | 0x11))
+}
@@ -0,0 +1,202 @@
+//! What the execution of a program used, to size the segments and the memory layout.
+
+use crate::memory::INIT_SP;
+use crate::syscall::is_precompile;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutionReport {
+    pub exit_code: u8,
+    /// The number of instructions executed.
+    pub total_steps: u64,
+    /// The number of cycles, counting the rows of the precompiles and of the page hashes.
+    pub total_cycles: u64,
+    /// The end of the memory mapped for the heap.
+    pub heap_end: u32,
+    /// The end of the memory allocated by the program, which it passes to HALT. `None` if it
+    /// exited otherwise, or if its allocator does not keep track of it.
+    pub heap_pointer: Option<u32>,
+    /// The program break.
+    pub brk: u32,
+    /// The lowest value of the stack pointer.
+    pub min_stack_pointer: u32,
+    /// The number of calls of each syscall, by number.
+    pub syscall_counts: BTreeMap<u32, u64>,
+    /// The number of instructions executed of each opcode, by [`opcode_key`].
+    pub opcode_counts: BTreeMap<u32, u64>,
+    /// The size of the pages of memory the program touched, in bytes.
+    pub touched_memory: u64,
+}
+
+impl ExecutionReport {
+    /// The largest size of the stack, in bytes.
+    pub fn stack_size(&self) -> u32 {
+        INIT_SP.saturating_sub(self.min_stack_pointer)
+    }
+
+    /// The number of calls of each precompile, by syscall number.
+    pub fn precompile_counts(&self) -> BTreeMap<u32, u64> {
+        self.syscall_counts
+            .iter()
+            .filter(|(&num, _)| is_precompile(num))
+            .map(|(&num, &count)| (num, count))
+            .collect()
+    }
+
+    /// The number of instructions executed of each opcode, by mnemonic, the most executed first.
+    pub fn opcode_histogram(&self) -> Vec<(String, u64)> {
+        let mut histogram: Vec<_> = self
+            .opcode_counts
+            .iter()
+            .map(|(&key, &count)| (opcode_name(key), count))
+            .collect();
+        histogram.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        histogram
+    }
+}
+
+/// The number of [`opcode_key`]s.
+pub const NUM_OPCODE_KEYS: usize = 1 << 12;
+
+/// Identifies the opcode of `insn`: its 6-bit primary opcode, followed by its function for the
+/// SPECIAL, SPECIAL2 and SPECIAL3 instructions, or by its `rt` field for the REGIMM ones.
+pub fn opcode_key(insn: u32) -> u32 {
+    let opcode = insn >> 26;
+    let sub = match opcode {
+        0 | 0x1c | 0x1f => insn & 0x3f,
+        1 => (insn >> 16) & 0x1f,
+        _ => 0,
+    };
+    opcode << 6 | sub
+}
+
+/// The mnemonic of the opcode `key`, or its number if it is not one that the emulator executes.
+pub fn opcode_name(key: u32) -> String {
+    let (opcode, sub) = (key >> 6, key & 0x3f);
+    let name = match (opcode, sub) {
+        (0, 0x00) => "sll",
+        (0, 0x02) => "srl",
+        (0, 0x03) => "sra",
+        (0, 0x04) => "sllv",
+        (0, 0x06) => "srlv",
+        (0, 0x07) => "srav",
+        (0, 0x08) => "jr",
+        (0, 0x09) => "jalr",
+        (0, 0x0a) => "movz",
+        (0, 0x0b) => "movn",
+        (0, 0x0c) => "syscall",
+        (0, 0x0d) => "break",
+        (0, 0x0f) => "sync",
+        (0, 0x10) => "mfhi",
+        (0, 0x11) => "mthi",
+        (0, 0x12) => "mflo",
+        (0, 0x13) => "mtlo",
+        (0, 0x18) => "mult",
+        (0, 0x19) => "multu",
+        (0, 0x1a) => "div",
+        (0, 0x1b) => "divu",
+        (0, 0x20) => "add",
+        (0, 0x21) => "addu",
+        (0, 0x22) => "sub",
+        (0, 0x23) => "subu",
+        (0, 0x24) => "and",
+        (0, 0x25) => "or",
+        (0, 0x26) => "xor",
+        (0, 0x27) => "nor",
+        (0, 0x2a) => "slt",
+        (0, 0x2b) => "sltu",
+        (0, 0x30) => "tge",
+        (0, 0x31) => "tgeu",
+        (0, 0x32) => "tlt",
+        (0, 0x33) => "tltu",
+        (0, 0x34) => "teq",
+        (0, 0x36) => "tne",
+        (1, 0x00) => "bltz",
+        (1, 0x01) => "bgez",
+        (1, 0x02) => "bltzl",
+        (1, 0x03) => "bgezl",
+        (1, 0x10) => "bltzal",
+        (1, 0x11) => "bgezal",
+        (2, _) => "j",
+        (3, _) => "jal",
+        (4, _) => "beq",
+        (5, _) => "bne",
+        (6, _) => "blez",
+        (7, _) => "bgtz",
+        (8, _) => "addi",
+        (9, _) => "addiu",
+        (0xa, _) => "slti",
+        (0xb, _) => "sltiu",
+        (0xc, _) => "andi",
+        (0xd, _) => "ori",
+        (0xe, _) => "xori",
+        (0xf, _) => "lui",
+        (0x10, _) => "cop0",
+        (0x14, _) => "beql",
+        (0x15, _) => "bnel",
+        (0x16, _) => "blezl",
+        (0x17, _) => "bgtzl",
+        (0x1c, 0x00) => "madd",
+        (0x1c, 0x01) => "maddu",
+        (0x1c, 0x02) => "mul",
+        (0x1c, 0x04) => "msub",
+        (0x1c, 0x05) => "msubu",
+        (0x1c, 0x20) => "clz",
+        (0x1c, 0x21) => "clo",
+        (0x1f, 0x00) => "ext",
+        (0x1f, 0x04) => "ins",
+        (0x1f, 0x20) => "bshfl",
+        (0x1f, 0x3b) => "rdhwr",
+        (0x20, _) => "lb",
+        (0x21, _) => "lh",
+        (0x22, _) => "lwl",
+        (0x23, _) => "lw",
+        (0x24, _) => "lbu",
+        (0x25, _) => "lhu",
+        (0x26, _) => "lwr",
+        (0x28, _) => "sb",
+        (0x29, _) => "sh",
+        (0x2a, _) => "swl",
+        (0x2b, _) => "sw",
+        (0x2e, _) => "swr",
+        (0x30, _) => "ll",
+        (0x33, _) => "pref",
+        (0x38, _) => "sc",
+        _ => return format!("opcode 0x{:x}/0x{:x}", opcode, sub),
+    };
+    name.to_string()
+}
+
+impl Display for ExecutionReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "exit code: {}", self.exit_code)?;
+        writeln!(f, "steps: {}", self.total_steps)?;
+        writeln!(f, "cycles: {}", self.total_cycles)?;
+        match self.heap_pointer {
+            Some(ptr) => writeln!(f, "heap: 0x{:x}, mapped up to 0x{:x}", ptr, self.heap_end)?,
+            None => writeln!(f, "heap: mapped up to 0x{:x}", self.heap_end)?,
+        }
+        writeln!(f, "brk: 0x{:x}", self.brk)?;
+        writeln!(
+            f,
+            "stack: 0x{:x} bytes, down to 0x{:x}",
+            self.stack_size(),
+            self.min_stack_pointer
+        )?;
+        writeln!(f, "touched memory: 0x{:x} bytes", self.touched_memory)?;
+        for (num, count) in &self.syscall_counts {
+            let kind = if is_precompile(*num) {
+                "precompile"
+            } else {
+                "syscall"
+            };
+            writeln!(f, "{} 0x{:x}: {} calls", kind, num, count)?;
+        }
+        for (name, count) in self.opcode_histogram() {
+            writeln!(f, "{}: {}", name, count)?;
+        }
+        Ok(())
+    }
+}
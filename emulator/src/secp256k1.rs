@@ -0,0 +1,15 @@
+//! The secp256k1 curve `y^2 = x^3 + 7`, of the `SECP256K1_ADD` and `SECP256K1_DOUBLE` syscalls.
+
+use lazy_static::lazy_static;
+use num_bigint::BigUint;
+
+lazy_static! {
+    /// The modulus of the base field, `2^256 - 2^32 - 977`.
+    pub static ref MODULUS: BigUint = BigUint::parse_bytes(
+        b"fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f",
+        16
+    )
+    .unwrap();
+    /// `a = 0`.
+    pub static ref A: BigUint = BigUint::from(0u32);
+}
@@ -1,8 +1,29 @@
-use crate::memory::{Memory, INIT_SP, POSEIDON_RATE_BYTES};
-use crate::page::{PAGE_ADDR_MASK, PAGE_SIZE};
+use crate::blake;
+use crate::bls12_381::{self, FpOp};
+use crate::bn254;
+use crate::cannon::{CannonPage, CannonState};
+use crate::chacha;
+use crate::cp0::{Cp0, CP0_BADVADDR, CP0_CAUSE, CP0_STATUS, ERET};
+use crate::cycle_tracker::CycleTracker;
+use crate::ed25519;
+use crate::error::ExecutionError;
+use crate::journal::Journal;
+use crate::loader::{ElfError, MemoryLayout, PreprocessedProgram, PT_MIPS_ABIFLAGS};
+use crate::memory::{Memory, INIT_SP, POSEIDON_RATE_BYTES, RANDOM_SEED_ADDRESS};
+use crate::modexp;
+use crate::p256;
+use crate::page::{MAX_MEMORY, PAGE_ADDR_MASK, PAGE_ADDR_SIZE, PAGE_SIZE};
+use crate::report::{opcode_key, ExecutionReport, NUM_OPCODE_KEYS};
+use crate::secp256k1;
+use crate::sha256;
+use crate::stdin::Stdin;
+use crate::syscall::*;
+use crate::uint256;
+use crate::weierstrass;
 use elf::abi::{PT_LOAD, PT_TLS};
 use elf::endian::AnyEndian;
 use log::{trace, warn};
+use num_bigint::BigUint;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
@@ -10,13 +31,30 @@ use std::fmt::{Display, Formatter};
 use std::fs;
 use std::io::{stderr, stdout, Read, Write};
 use std::path::Path;
+use tiny_keccak::{Hasher, Keccak};
 
 pub const FD_STDIN: u32 = 0;
 pub const FD_STDOUT: u32 = 1;
 pub const FD_STDERR: u32 = 2;
 pub const FD_PUBLIC_VALUES: u32 = 3;
 pub const FD_HINT: u32 = 4;
+pub const FD_CYCLE_TRACKER: u32 = 5;
+
+/// Computes the inputs returned to the program for a hint it wrote.
+pub type HintHandler = Box<dyn FnMut(&[u8]) -> Vec<Vec<u8>>>;
+pub const MIPS_ENOENT: u32 = 2;
 pub const MIPS_EBADF: u32 = 9;
+pub const MIPS_EACCES: u32 = 13;
+pub const MIPS_EINVAL: u32 = 22;
+pub const MIPS_ENOTTY: u32 = 25;
+
+// Exception codes (as in the CP0 Cause register) reported as the exit code of a trapped program.
+pub const EXC_TLB_LOAD: u8 = 2;
+pub const EXC_TLB_STORE: u8 = 3;
+pub const EXC_BREAKPOINT: u8 = 9;
+pub const EXC_COPROCESSOR_UNUSABLE: u8 = 11;
+pub const EXC_OVERFLOW: u8 = 12;
+pub const EXC_TRAP: u8 = 13;
 
 pub const REGISTERS_START: u32 = 0x81020400u32;
 pub const PAGE_LOAD_CYCLES: u64 = 128;
@@ -42,6 +80,19 @@ pub struct Segment {
     pub input_stream_ptr: usize,
     pub public_values_stream: Vec<u8>,
     pub public_values_stream_ptr: usize,
+    #[serde(default)]
+    pub fds: FdTable,
+    /// The number of steps executed before this segment.
+    #[serde(default)]
+    pub total_step: u64,
+    #[serde(default)]
+    pub epoch: u64,
+    #[serde(default)]
+    pub guard_pages: GuardPages,
+    #[serde(default)]
+    pub oracle: PreimageOracle,
+    #[serde(default)]
+    pub cp0: Cp0,
 }
 
 pub struct State {
@@ -67,6 +118,13 @@ pub struct State {
     /// tlb addr
     local_user: u32,
 
+    /// The lowest value of the stack pointer, and the heap pointer passed to HALT.
+    min_sp: u32,
+    heap_pointer: Option<u32>,
+    /// The number of calls of each syscall, and of instructions of each opcode, by opcode key.
+    syscall_counts: BTreeMap<u32, u64>,
+    opcode_counts: Vec<u64>,
+
     /// step tracks the total step has been executed.
     pub step: u64,
     pub total_step: u64,
@@ -87,6 +145,31 @@ pub struct State {
     /// A ptr to the current position in the public values stream, incremented when reading from public_values_stream.
     pub public_values_stream_ptr: usize,
 
+    /// The file descriptors opened by the program, and the input files it can open.
+    pub fds: FdTable,
+
+    /// The time at which the program starts, in seconds since the Unix epoch. The time syscalls
+    /// return it plus a duration derived from the step count, so that they are deterministic.
+    pub epoch: u64,
+
+    /// The pages a load or a store faults on.
+    pub guard_pages: GuardPages,
+
+    /// The preimages the program can fetch by key.
+    pub oracle: PreimageOracle,
+
+    /// The coprocessor 0 registers, which the exception handler of the guest works with.
+    pub cp0: Cp0,
+
+    /// The cycles spent in the sections the program marks.
+    pub cycle_tracker: CycleTracker,
+
+    /// Answers the hints written by the program, see [`State::set_hint_handler`].
+    hint_handler: Option<HintHandler>,
+
+    /// The last write of the program to stderr, the message of its panic if it panicked.
+    last_stderr_write: Vec<u8>,
+
     pub exited: bool,
     pub exit_code: u8,
     dump_info: bool,
@@ -122,6 +205,10 @@ impl State {
             lo: 0,
             heap: 0,
             local_user: 0,
+            min_sp: u32::MAX,
+            heap_pointer: None,
+            syscall_counts: BTreeMap::new(),
+            opcode_counts: vec![0; NUM_OPCODE_KEYS],
             step: 0,
             total_step: 0,
             cycle: 0,
@@ -131,6 +218,14 @@ impl State {
             input_stream_ptr: 0,
             public_values_stream: Vec::new(),
             public_values_stream_ptr: 0,
+            fds: FdTable::default(),
+            epoch: 0,
+            guard_pages: GuardPages::default(),
+            oracle: PreimageOracle::default(),
+            cp0: Cp0::default(),
+            cycle_tracker: CycleTracker::default(),
+            hint_handler: None,
+            last_stderr_write: Vec::new(),
             exited: false,
             exit_code: 0,
             dump_info: false,
@@ -147,8 +242,12 @@ impl State {
 
             hi: 0,
             lo: 0,
-            heap: 0x20000000,
+            heap: MMAP_START,
             local_user: 0,
+            min_sp: u32::MAX,
+            heap_pointer: None,
+            syscall_counts: BTreeMap::new(),
+            opcode_counts: vec![0; NUM_OPCODE_KEYS],
             step: 0,
             total_step: 0,
             cycle: 0,
@@ -158,6 +257,14 @@ impl State {
             input_stream_ptr: 0,
             public_values_stream: Vec::new(),
             public_values_stream_ptr: 0,
+            fds: FdTable::default(),
+            epoch: 0,
+            guard_pages: GuardPages::default(),
+            oracle: PreimageOracle::default(),
+            cp0: Cp0::default(),
+            cycle_tracker: CycleTracker::default(),
+            hint_handler: None,
+            last_stderr_write: Vec::new(),
             exited: false,
             exit_code: 0,
             dump_info: false,
@@ -168,7 +275,7 @@ impl State {
             .segments()
             .expect("invalid ELF cause failed to parse segments.");
         for segment in segments {
-            if segment.p_type == 0x70000003 {
+            if segment.p_type == PT_MIPS_ABIFLAGS {
                 continue;
             }
 
@@ -179,15 +286,18 @@ impl State {
 
             if segment.p_filesz != segment.p_memsz {
                 if segment.p_type == PT_LOAD || segment.p_type == PT_TLS {
-                    if segment.p_filesz < segment.p_memsz {
-                        let diff = (segment.p_memsz - segment.p_filesz) as usize;
-                        r.extend_from_slice(vec![0u8; diff].as_slice());
-                    } else {
+                    if segment.p_filesz > segment.p_memsz {
                         panic!(
                             "invalid PT_LOAD program segment, file size ({}) > mem size ({})",
                             segment.p_filesz, segment.p_memsz
                         );
                     }
+                    // The BSS of a loaded segment is left unallocated, the memory reads it as
+                    // zeros: a Go program has megabytes of it.
+                    if segment.p_type == PT_TLS {
+                        let diff = (segment.p_memsz - segment.p_filesz) as usize;
+                        r.extend_from_slice(vec![0u8; diff].as_slice());
+                    }
                 } else {
                     panic!("has different file size ({}) than mem size ({}): filling for non PT_LOAD segments is not supported",
                            segment.p_filesz, segment.p_memsz);
@@ -215,6 +325,81 @@ impl State {
         s
     }
 
+    /// Loads a Cannon snapshot, to resume its execution.
+    pub fn from_cannon(cannon: &CannonState) -> Result<Box<Self>, String> {
+        let mut s = Self::new();
+        for page in &cannon.memory {
+            let data = page.decode()?;
+            let r: Box<&[u8]> = Box::new(data.as_slice());
+            s.memory
+                .set_memory_range(page.index << PAGE_ADDR_SIZE, r)
+                .map_err(|e| format!("failed to set page 0x{:x}: {:?}", page.index, e))?;
+        }
+        s.registers = cannon.registers;
+        s.pc = cannon.pc;
+        s.next_pc = cannon.next_pc;
+        s.lo = cannon.lo;
+        s.hi = cannon.hi;
+        s.heap = cannon.heap;
+        s.exit_code = cannon.exit_code;
+        s.exited = cannon.exited;
+        s.total_step = cannon.step;
+        Ok(s)
+    }
+
+    /// Takes a Cannon snapshot of the state. The pages of the page hashes of zkm, above
+    /// `MAX_MEMORY`, are left out, and no preimage is being read.
+    pub fn to_cannon(&self) -> CannonState {
+        let memory = self
+            .memory
+            .pages()
+            .filter(|(page_index, _)| ((*page_index as usize) << PAGE_ADDR_SIZE) < MAX_MEMORY)
+            .map(|(page_index, data)| CannonPage::encode(page_index, &data))
+            .collect();
+        CannonState {
+            memory,
+            preimage_key: format!("0x{}", hex::encode([0u8; 32])),
+            preimage_offset: 0,
+            pc: self.pc,
+            next_pc: self.next_pc,
+            lo: self.lo,
+            hi: self.hi,
+            heap: self.heap,
+            exit_code: self.exit_code,
+            exited: self.exited,
+            step: self.total_step + self.step,
+            registers: self.registers,
+        }
+    }
+
+    /// The state of `program` as [`State::load_elf`] and [`State::patch_elf`] leave it.
+    pub fn from_preprocessed(program: &PreprocessedProgram) -> Box<Self> {
+        let mut s = Self::new();
+        s.pc = program.entry;
+        s.next_pc = program.entry + 4;
+        s.heap = MMAP_START;
+        s.brk = program.brk;
+        for (page_index, data) in &program.pages {
+            let r: Box<&[u8]> = Box::new(data.as_slice());
+            s.memory
+                .set_memory_range(page_index << PAGE_ADDR_SIZE, r)
+                .expect("failed to set memory range");
+        }
+        s
+    }
+
+    pub fn brk(&self) -> u32 {
+        self.brk
+    }
+
+    /// Allocates the heap of the program from `layout.heap_start`. The program must not have
+    /// started yet.
+    pub fn set_memory_layout(&mut self, layout: &MemoryLayout) -> Result<(), ElfError> {
+        layout.validate(self.brk)?;
+        self.heap = layout.heap_start;
+        Ok(())
+    }
+
     pub fn patch_elf(&mut self, f: &elf::ElfBytes<AnyEndian>) {
         let symbols = f
             .symbol_table()
@@ -275,6 +460,13 @@ impl State {
 
     /// We define the input[0] as the public input, and input[1] as the private input
     pub fn patch_stack(&mut self, input: Vec<&str>) {
+        self.patch_stack_with_env(input, &[]);
+    }
+
+    /// Lays out the arguments `input` and the environment variables `envs` on the stack, as
+    /// `argc, argv, 0, envp, 0, auxv`, with the strings above. They are part of the initial
+    /// memory, so of the image id.
+    pub fn patch_stack_with_env(&mut self, input: Vec<&str>, envs: &[(&str, &str)]) {
         assert!(input.len() <= 2);
         // TODO: check the arg size should less than one page??
         // setup stack pointer
@@ -313,7 +505,9 @@ impl State {
         let mut cur_sp = sp + 4 * (index + 1);
         store_mem(cur_sp, 0x00); // argv[n] = 0 (terminating argv)
         cur_sp += 4;
-        store_mem(cur_sp, 0x00); // envp[term] = 0 (no env vars)
+        let envp = cur_sp;
+        cur_sp += 4 * envs.len() as u32;
+        store_mem(cur_sp, 0x00); // envp[term] = 0 (terminating envp)
         cur_sp += 4;
 
         store_mem(cur_sp, 0x06); // auxv[0] = _AT_PAGESZ = 6 (key)
@@ -374,6 +568,11 @@ impl State {
             store_mem_str(sp + 4 * (index + 1), cur_sp, inp);
             cur_sp += inp.len() as u32 + 1;
         }
+        for (index, (key, value)) in envs.iter().enumerate() {
+            let var = format!("{key}={value}");
+            store_mem_str(envp + 4 * index as u32, cur_sp, &var);
+            cur_sp += var.len() as u32 + 1;
+        }
     }
 
     pub fn add_input_stream<T: Serialize>(&mut self, input: &T) {
@@ -382,6 +581,72 @@ impl State {
         self.input_stream.push(buf);
     }
 
+    /// Append the items of `stdin` to the inputs of the program.
+    pub fn add_stdin(&mut self, stdin: &Stdin) {
+        self.input_stream.extend(stdin.buffer.iter().cloned());
+    }
+
+    /// Set the seed of the bytes returned by `getrandom`, which the runtime expands in the guest.
+    ///
+    /// The seed is stored at `RANDOM_SEED_ADDRESS`, so it is part of the initial memory, and so of
+    /// the image id.
+    pub fn set_random_seed(&mut self, seed: [u8; 32]) {
+        self.memory
+            .set_memory_range(RANDOM_SEED_ADDRESS, Box::new(seed.as_slice()))
+            .expect("failed to set memory range");
+    }
+
+    /// Register the preimage of `key`, which the program can fetch with the preimage syscalls.
+    pub fn add_preimage(&mut self, key: [u8; PREIMAGE_KEY_SIZE], data: Vec<u8>) {
+        self.oracle.insert(key, data);
+    }
+
+    /// Answer the hints the program writes with `handler`: the items it returns are appended to
+    /// the inputs, so that the program reads them next. Without a handler a hint is appended
+    /// as is.
+    pub fn set_hint_handler(&mut self, handler: impl FnMut(&[u8]) -> Vec<Vec<u8>> + 'static) {
+        self.hint_handler = Some(Box::new(handler));
+    }
+
+    /// Register an input file, which the program can open and read by its path.
+    pub fn add_input_file(&mut self, path: &str, data: Vec<u8>) {
+        self.fds.add_file(path, data);
+    }
+
+    /// What the execution used so far.
+    pub fn report(&self) -> ExecutionReport {
+        ExecutionReport {
+            exit_code: self.exit_code,
+            total_steps: self.total_step + self.step,
+            total_cycles: self.total_cycle + self.cycle,
+            heap_end: self.heap,
+            heap_pointer: self.heap_pointer,
+            brk: self.brk,
+            min_stack_pointer: self.min_sp,
+            syscall_counts: self.syscall_counts.clone(),
+            opcode_counts: (0..)
+                .zip(&self.opcode_counts)
+                .filter(|(_, &count)| count > 0)
+                .map(|(key, &count)| (key, count))
+                .collect(),
+            touched_memory: self.memory.touched_bytes(),
+        }
+    }
+
+    /// The panic the program exited with: the runtime writes its message to stderr, then halts
+    /// with exit code 1.
+    pub fn guest_panic(&self) -> Option<ExecutionError> {
+        if !self.exited || self.exit_code != 1 {
+            return None;
+        }
+        ExecutionError::from_panic_message(&self.last_stderr_write, self.pc)
+    }
+
+    /// The values committed by the program so far.
+    pub fn journal(&self) -> Journal {
+        Journal::new(self.public_values_stream.clone())
+    }
+
     pub fn read_public_values<T: DeserializeOwned>(&mut self) -> T {
         let result = bincode::deserialize_from::<_, T>(self);
         result.unwrap()
@@ -469,6 +734,42 @@ impl State {
         regs_bytes_be[38 * 4..38 * 4 + 4].copy_from_slice(&self.local_user.to_be_bytes());
         regs_bytes_be
     }
+
+    /// The register `n`, numbered as GDB does for MIPS: the general purpose registers, then
+    /// Status, LO, HI, BadVAddr, Cause and the pc.
+    pub fn gdb_register(&self, n: usize) -> Option<u32> {
+        match n {
+            0..=31 => Some(self.registers[n]),
+            32 => Some(self.cp0.regs[CP0_STATUS]),
+            33 => Some(self.lo),
+            34 => Some(self.hi),
+            35 => Some(self.cp0.regs[CP0_BADVADDR]),
+            36 => Some(self.cp0.regs[CP0_CAUSE]),
+            37 => Some(self.pc),
+            _ => None,
+        }
+    }
+
+    /// Sets the register `n`, numbered as in [`State::gdb_register`]. Setting the pc resumes
+    /// there, out of any delay slot. Returns whether there is such a register.
+    pub fn set_gdb_register(&mut self, n: usize, value: u32) -> bool {
+        match n {
+            // $zero stays zero.
+            0 => {}
+            1..=31 => self.registers[n] = value,
+            32 => self.cp0.regs[CP0_STATUS] = value,
+            33 => self.lo = value,
+            34 => self.hi = value,
+            35 => self.cp0.regs[CP0_BADVADDR] = value,
+            36 => self.cp0.regs[CP0_CAUSE] = value,
+            37 => {
+                self.pc = value;
+                self.next_pc = value.wrapping_add(4);
+            }
+            _ => return false,
+        }
+        true
+    }
 }
 
 pub struct InstrumentedState {
@@ -485,10 +786,12 @@ pub struct InstrumentedState {
     pre_image_id: [u8; 32],
     pre_hash_root: [u8; 32],
     block_path: String,
-    pre_input: Vec<Vec<u8>>,
     pre_input_ptr: usize,
     pre_public_values: Vec<u8>,
     pre_public_values_ptr: usize,
+    pre_fds: FdTable,
+    pre_guard_pages: GuardPages,
+    pre_cp0: Cp0,
 }
 
 impl Display for InstrumentedState {
@@ -508,216 +811,665 @@ impl InstrumentedState {
             pre_image_id: [0u8; 32],
             pre_hash_root: [0u8; 32],
             pre_segment_id: 0u32,
-            pre_input: Vec::new(),
             pre_input_ptr: 0,
             pre_public_values: Vec::new(),
             pre_public_values_ptr: 0,
+            pre_fds: FdTable::default(),
+            pre_guard_pages: GuardPages::default(),
+            pre_cp0: Cp0::default(),
         })
     }
 
-    fn handle_syscall(&mut self) {
-        let syscall_num = self.state.registers[2]; // v0
-        let mut v0 = 0u32;
-        let mut v1 = 0u32;
+    /// Capture the stdout of the program, instead of forwarding it to the host's.
+    pub fn set_stdout(&mut self, writer: Box<dyn Write>) {
+        self.stdout_writer = writer;
+    }
 
-        let a0 = self.state.registers[4];
-        let a1 = self.state.registers[5];
-        let a2 = self.state.registers[6];
+    /// Capture the stderr of the program, instead of forwarding it to the host's.
+    pub fn set_stderr(&mut self, writer: Box<dyn Write>) {
+        self.stderr_writer = writer;
+    }
+
+    fn handle_syscall(&mut self) {
+        let args = SyscallArgs::from_registers(&self.state.registers);
 
         self.state.dump_info = true;
+        *self.state.syscall_counts.entry(args.num).or_default() += 1;
+
+        log::debug!(
+            "syscall {} {} {} {} {}",
+            args.num,
+            args.a0,
+            args.a1,
+            args.a2,
+            args.a3
+        );
 
-        log::debug!("syscall {} {} {} {}", syscall_num, a0, a1, a2);
+        let Some(ret) = self.syscall(args) else {
+            self.handle_trap(EXC_TRAP, None);
+            return;
+        };
+        ret.write_registers(&mut self.state.registers);
 
-        match syscall_num {
-            0xF0 => {
-                if self.state.input_stream_ptr >= self.state.input_stream.len() {
-                    panic!("not enough vecs in hint input stream");
-                }
-                log::debug!(
-                    "hint len {:X}",
-                    self.state.input_stream[self.state.input_stream_ptr].len()
-                );
-                v0 = self.state.input_stream[self.state.input_stream_ptr].len() as u32
-            }
-            0xF1 => {
-                log::debug!("{:X} {:X} {:X}", a0, a1, a2);
-                if self.state.input_stream_ptr >= self.state.input_stream.len() {
-                    warn!("not enough vecs in hint input stream");
-                }
+        self.state.pc = self.state.next_pc;
+        self.state.next_pc += 4;
+    }
 
-                let vec: &Vec<u8> = &self.state.input_stream[self.state.input_stream_ptr];
-                self.state.input_stream_ptr += 1;
-                assert_eq!(
-                    vec.len() as u32,
-                    a1,
-                    "hint input stream read length mismatch"
-                );
-                log::debug!("input: {:?}", vec);
-                assert_eq!(a0 % 4, 0, "hint read address not aligned to 4 bytes");
-                if a1 >= 1 {
-                    self.state.cycle += (a1 as u64 + 31) / 32;
-                }
-                for i in (0..a1).step_by(4) {
-                    // Get each byte in the chunk
-                    let b1 = vec[i as usize];
-                    // In case the vec is not a multiple of 4, right-pad with 0s. This is fine because we
-                    // are assuming the word is uninitialized, so filling it with 0s makes sense.
-                    let b2 = vec.get(i as usize + 1).copied().unwrap_or(0);
-                    let b3 = vec.get(i as usize + 2).copied().unwrap_or(0);
-                    let b4 = vec.get(i as usize + 3).copied().unwrap_or(0);
-                    let word = u32::from_be_bytes([b1, b2, b3, b4]);
-
-                    // Save the data into runtime state so the runtime will use the desired data instead of
-                    // 0 when first reading/writing from this address.
-                    self.state.memory.set_memory(a0 + i, word);
-                }
-                v0 = a2
+    /// Runs the syscall of `args`, or returns `None` if it is a precompile call with invalid
+    /// inputs, which traps: the tables of the precompiles only prove valid calls.
+    fn syscall(&mut self, args: SyscallArgs) -> Option<SyscallRet> {
+        Some(match args.num {
+            SYS_HINT_LEN => self.sys_hint_len(),
+            SYS_HINT_READ => self.sys_hint_read(args),
+            SYS_PREIMAGE_LEN | SYS_PREIMAGE_READ => self.sys_preimage(args),
+            SYS_KECCAK256 => self.sys_keccak256(args)?,
+            SYS_SHA256_EXTEND => self.sys_sha256_extend(args)?,
+            SYS_SHA256_COMPRESS => self.sys_sha256_compress(args)?,
+            SYS_SECP256K1_ADD | SYS_SECP256K1_DOUBLE => {
+                let is_add = args.num == SYS_SECP256K1_ADD;
+                self.sys_weierstrass(args, &secp256k1::MODULUS, &secp256k1::A, is_add)?
             }
-            4020 => {
-                // read preimage (getpid)
-                self.state.load_preimage(self.block_path.clone())
+            SYS_BN254_ADD | SYS_BN254_DOUBLE => {
+                let is_add = args.num == SYS_BN254_ADD;
+                self.sys_weierstrass(args, &bn254::MODULUS, &bn254::A, is_add)?
             }
-            4210 | 4090 => {
-                // mmap
-                // args: a0 = heap/hint, indicates mmap heap or hint. a1 = size
-                let mut size = a1;
-                if size & (PAGE_ADDR_MASK as u32) != 0 {
-                    // adjust size to align with page size
-                    size += PAGE_SIZE as u32 - (size & (PAGE_ADDR_MASK as u32));
-                }
-                if a0 == 0 {
-                    v0 = self.state.heap;
-                    self.state.heap += size;
-                    trace!("mmap heap {:x?} size {:x?}", v0, size);
-                } else {
-                    v0 = a0;
-                    trace!("mmap hint {:x?} size {:x?}", v0, size);
-                }
+            SYS_SECP256R1_ADD | SYS_SECP256R1_DOUBLE => {
+                let is_add = args.num == SYS_SECP256R1_ADD;
+                self.sys_weierstrass(args, &p256::MODULUS, &p256::A, is_add)?
             }
-            4045 => {
-                // brk
-                if a0 > self.state.brk {
-                    v0 = a0;
-                } else {
-                    v0 = self.state.brk;
+            SYS_BLS12381_FP_ADD => self.sys_bls12381_fp(args, FpOp::Add)?,
+            SYS_BLS12381_FP_SUB => self.sys_bls12381_fp(args, FpOp::Sub)?,
+            SYS_BLS12381_FP_MUL => self.sys_bls12381_fp(args, FpOp::Mul)?,
+            SYS_UINT256_MULMOD => self.sys_uint256_mulmod(args)?,
+            SYS_BLAKE2B_COMPRESS => self.sys_blake2b_compress(args)?,
+            SYS_BLAKE3_COMPRESS => self.sys_blake3_compress(args)?,
+            SYS_CHACHA20_BLOCK => self.sys_chacha20_block(args)?,
+            SYS_MODEXP => self.sys_modexp(args)?,
+            SYS_ED_ADD => self.sys_ed_add(args)?,
+            SYS_ED_DECOMPRESS => self.sys_ed_decompress(args)?,
+            SYS_GETPID => {
+                // read preimage
+                self.state.load_preimage(self.block_path.clone());
+                SyscallRet::ok(0)
+            }
+            SYS_MMAP | SYS_MMAP2 => self.sys_mmap(args),
+            SYS_MUNMAP => match self.state.guard_pages.unmap(args.a0, args.a1) {
+                Ok(()) => SyscallRet::ok(0),
+                Err(errno) => SyscallRet::err(errno),
+            },
+            SYS_MPROTECT => match self.state.guard_pages.protect(args.a0, args.a1, args.a2) {
+                Ok(()) => SyscallRet::ok(0),
+                Err(errno) => SyscallRet::err(errno),
+            },
+            SYS_BRK => self.sys_brk(args),
+            SYS_CLONE => SyscallRet::ok(1),
+            SYS_EXIT | SYS_EXIT_GROUP => {
+                self.state.exited = true;
+                self.state.exit_code = args.a0 as u8;
+                // The runtime passes the end of its heap to HALT, other exits pass anything.
+                if (MMAP_START..=self.state.heap).contains(&args.a1) {
+                    self.state.heap_pointer = Some(args.a1);
                 }
+                SyscallRet::ok(0)
             }
-            4120 => {
-                // clone
-                v0 = 1;
+            SYS_OPEN => self.sys_open(args.a0, args.a1),
+            SYS_OPENAT => self.sys_open(args.a1, args.a2),
+            SYS_CLOSE => match self.state.fds.close(args.a0) {
+                Ok(()) => SyscallRet::ok(0),
+                Err(errno) => SyscallRet::err(errno),
+            },
+            SYS_READ => self.sys_read(args),
+            SYS_CLOCK_GETTIME | SYS_CLOCK_GETTIME64 => self.sys_time(args.num, args.a1),
+            SYS_GETTIMEOFDAY => self.sys_time(args.num, args.a0),
+            SYS_WRITE => self.sys_write(args),
+            SYS_FCNTL => self.sys_fcntl(args),
+            SYS_IOCTL => self.sys_ioctl(args),
+            SYS_SET_THREAD_AREA => {
+                log::trace!("set local user {:X} {:X} {:X}", args.a0, args.a1, args.a2);
+                self.state.local_user = args.a0;
+                SyscallRet::ok(0)
             }
-            4246 => {
-                // exit group
-                self.state.exited = true;
-                self.state.exit_code = a0 as u8;
+            SYS_CYCLE_COUNT => {
+                // the low word of the step count, the same from the prover
+                let (step, _) = self.current_step_and_cycle();
+                SyscallRet::ok(step as u32)
             }
-            4003 => {
-                // read
-                // args: a0 = fd, a1 = addr, a2 = count
-                // returns: v0 = read, v1 = err code
-                match a0 {
-                    FD_STDIN => {
-                        // leave v0 and v1 zero: read nothing, no error
-                    }
-                    _ => {
-                        v0 = 0xffffffff;
-                        v1 = MIPS_EBADF;
-                    }
-                }
+            SYS_VERIFY => {
+                log::trace!("sys_verify {:X} {:X} {:X}", args.a0, args.a1, args.a2);
+                // DO Nothing Here
+                SyscallRet::ok(0)
             }
-            4004 => {
-                // write
-                // args: a0 = fd, a1 = addr, a2 = count
-                // returns: v0 = written, v1 = err code
-                let bytes = (0..a2)
-                    .map(|i| self.state.memory.byte(a1 + i))
-                    .collect::<Vec<u8>>();
-                let slice = bytes.as_slice();
-                match a0 {
-                    // todo: track memory read
-                    FD_STDOUT => {
-                        self.state.memory.read_memory_range(a1, a2);
-                        if let Err(e) =
-                            std::io::copy(self.state.memory.as_mut(), self.stdout_writer.as_mut())
-                        {
-                            panic!("read range from memory failed {}", e);
+            _ => match runtime_syscall(&args) {
+                Some((ret, out)) => {
+                    if let Some((addr, bytes)) = out {
+                        if !bytes.is_empty() {
+                            // each word is read and written back, 4 words per row
+                            let words =
+                                (addr as u64 + bytes.len() as u64 + 3) / 4 - addr as u64 / 4;
+                            self.state.cycle += (words + 3) / 4;
                         }
-                        v0 = a2;
-                    }
-                    FD_STDERR => {
-                        self.state.memory.read_memory_range(a1, a2);
-                        if let Err(e) =
-                            std::io::copy(self.state.memory.as_mut(), self.stderr_writer.as_mut())
-                        {
-                            panic!("read range from memory failed {}", e);
+                        for (i, b) in bytes.iter().enumerate() {
+                            self.set_byte(addr + i as u32, *b);
                         }
-                        v0 = a2;
-                    }
-                    FD_PUBLIC_VALUES => {
-                        self.state.public_values_stream.extend_from_slice(slice);
-                        v0 = a2;
-                    }
-                    FD_HINT => {
-                        self.state.input_stream.push(slice.to_vec());
-                        v0 = a2;
-                    }
-                    _ => {
-                        v0 = 0xffffffff;
-                        v1 = MIPS_EBADF;
                     }
+                    ret
+                }
+                None => {
+                    log::debug!("unhandled syscall {}", args.num);
+                    SyscallRet::ok(0)
                 }
+            },
+        })
+    }
+
+    fn sys_hint_len(&mut self) -> SyscallRet {
+        if self.state.input_stream_ptr >= self.state.input_stream.len() {
+            panic!("not enough vecs in hint input stream");
+        }
+        log::debug!(
+            "hint len {:X}",
+            self.state.input_stream[self.state.input_stream_ptr].len()
+        );
+        SyscallRet::ok(self.state.input_stream[self.state.input_stream_ptr].len() as u32)
+    }
+
+    fn sys_hint_read(&mut self, args: SyscallArgs) -> SyscallRet {
+        // args: a0 = addr, a1 = len
+        let (a0, a1) = (args.a0, args.a1);
+        log::debug!("{:X} {:X} {:X}", a0, a1, args.a2);
+        if self.state.input_stream_ptr >= self.state.input_stream.len() {
+            warn!("not enough vecs in hint input stream");
+        }
+
+        let vec: &Vec<u8> = &self.state.input_stream[self.state.input_stream_ptr];
+        self.state.input_stream_ptr += 1;
+        assert_eq!(
+            vec.len() as u32,
+            a1,
+            "hint input stream read length mismatch"
+        );
+        log::debug!("input: {:?}", vec);
+        assert_eq!(a0 % 4, 0, "hint read address not aligned to 4 bytes");
+        if a1 >= 1 {
+            self.state.cycle += (a1 as u64 + 31) / 32;
+        }
+        for i in (0..a1).step_by(4) {
+            // Get each byte in the chunk
+            let b1 = vec[i as usize];
+            // In case the vec is not a multiple of 4, right-pad with 0s. This is fine because we
+            // are assuming the word is uninitialized, so filling it with 0s makes sense.
+            let b2 = vec.get(i as usize + 1).copied().unwrap_or(0);
+            let b3 = vec.get(i as usize + 2).copied().unwrap_or(0);
+            let b4 = vec.get(i as usize + 3).copied().unwrap_or(0);
+            let word = u32::from_be_bytes([b1, b2, b3, b4]);
+
+            // Save the data into runtime state so the runtime will use the desired data instead of
+            // 0 when first reading/writing from this address.
+            self.state.memory.set_memory(a0 + i, word);
+        }
+        SyscallRet::ok(args.a2)
+    }
+
+    fn sys_preimage(&mut self, args: SyscallArgs) -> SyscallRet {
+        // args: a0 = key addr; for PREIMAGE_READ a1 = addr, a2 = len
+        let key = (0..PREIMAGE_KEY_SIZE as u32)
+            .map(|i| self.state.memory.byte(args.a0 + i))
+            .collect::<Vec<u8>>();
+        // the key is read 8 words per row
+        let words = (args.a0 as u64 + PREIMAGE_KEY_SIZE as u64 + 3) / 4 - args.a0 as u64 / 4;
+        self.state.cycle += (words + 7) / 8;
+        let data = match self.state.oracle.get(&key) {
+            Ok(data) => data,
+            Err(errno) => {
+                warn!("no preimage for key {}", hex::encode(key));
+                return SyscallRet::err(errno);
             }
-            4055 => {
-                // fcntl
-                // args: a0 = fd, a1 = cmd
-                if a1 == 3 {
-                    // F_GETFL: get file descriptor flags
-                    match a0 {
-                        FD_STDIN => {
-                            v0 = 0 // O_RDONLY
-                        }
-                        FD_STDOUT | FD_STDERR => {
-                            v0 = 1 // O_WRONLY
-                        }
-                        _ => {
-                            v0 = 0xffffffff;
-                            v1 = MIPS_EBADF;
-                        }
-                    }
-                } else if a1 == 1 {
-                    // GET_FD
-                    match a0 {
-                        FD_STDIN | FD_STDOUT | FD_STDERR => v0 = a0,
-                        _ => {
-                            v0 = 0xffffffff;
-                            v1 = MIPS_EBADF;
-                        }
-                    }
-                } else {
-                    v0 = 0xffffffff;
-                    v1 = MIPS_EBADF;
+        };
+        if args.num == SYS_PREIMAGE_LEN {
+            return SyscallRet::ok(data.len() as u32);
+        }
+
+        let bytes = data[..data.len().min(args.a2 as usize)].to_vec();
+        if !bytes.is_empty() {
+            // each word is read and written back, 4 words per row
+            let words = (args.a1 as u64 + bytes.len() as u64 + 3) / 4 - args.a1 as u64 / 4;
+            self.state.cycle += (words + 3) / 4;
+        }
+        for (i, b) in bytes.iter().enumerate() {
+            self.set_byte(args.a1 + i as u32, *b);
+        }
+        SyscallRet::ok(bytes.len() as u32)
+    }
+
+    fn sys_keccak256(&mut self, args: SyscallArgs) -> Option<SyscallRet> {
+        // args: a0 = input addr, a1 = input len, a2 = digest addr, all words
+        if (args.a0 | args.a2) & 3 != 0 {
+            return None;
+        }
+        let input = (0..args.a1)
+            .map(|i| self.state.memory.byte(args.a0 + i))
+            .collect::<Vec<u8>>();
+        // the sponge row and the row writing the digest, the input is read by the sponge table
+        self.state.cycle += 2;
+        let mut digest = [0u8; 32];
+        let mut keccak = Keccak::v256();
+        keccak.update(&input);
+        keccak.finalize(&mut digest);
+        for (i, b) in digest.iter().enumerate() {
+            self.set_byte(args.a2 + i as u32, *b);
+        }
+        Some(SyscallRet::ok(0))
+    }
+
+    fn sys_sha256_extend(&mut self, args: SyscallArgs) -> Option<SyscallRet> {
+        // args: a0 = w addr, a word
+        if args.a0 & 3 != 0 {
+            return None;
+        }
+        let mut w = [0u32; 64];
+        for (i, word) in w.iter_mut().enumerate().take(16) {
+            *word = self.state.memory.get_memory(args.a0 + 4 * i as u32);
+        }
+        // the row looking up the extend table, which reads and writes w
+        self.state.cycle += 1;
+        sha256::extend(&mut w);
+        for (i, word) in w.iter().enumerate().skip(16) {
+            self.state.memory.set_memory(args.a0 + 4 * i as u32, *word);
+        }
+        Some(SyscallRet::ok(0))
+    }
+
+    fn sys_sha256_compress(&mut self, args: SyscallArgs) -> Option<SyscallRet> {
+        // args: a0 = w addr, a1 = hash state addr, both words
+        if (args.a0 | args.a1) & 3 != 0 {
+            return None;
+        }
+        let w: [u32; 64] =
+            core::array::from_fn(|i| self.state.memory.get_memory(args.a0 + 4 * i as u32));
+        let mut h: [u32; 8] =
+            core::array::from_fn(|i| self.state.memory.get_memory(args.a1 + 4 * i as u32));
+        // the row looking up the compress table, which reads w and h and writes h
+        self.state.cycle += 1;
+        sha256::compress(&mut h, &w);
+        for (i, word) in h.iter().enumerate() {
+            self.state.memory.set_memory(args.a1 + 4 * i as u32, *word);
+        }
+        Some(SyscallRet::ok(0))
+    }
+
+    fn sys_weierstrass(
+        &mut self,
+        args: SyscallArgs,
+        m: &BigUint,
+        a: &BigUint,
+        is_add: bool,
+    ) -> Option<SyscallRet> {
+        // args: a0 = p addr, a1 = q addr for an addition, both words. The result replaces p.
+        if args.a0 & 3 != 0 || (is_add && args.a1 & 3 != 0) {
+            return None;
+        }
+        let mut read_point = |addr: u32| -> weierstrass::Point {
+            core::array::from_fn(|i| self.state.memory.get_memory(addr + 4 * i as u32))
+        };
+        let p = read_point(args.a0);
+        let r = if is_add {
+            weierstrass::add(m, &p, &read_point(args.a1))
+        } else {
+            weierstrass::double(m, a, &p)
+        }?;
+        // the row looking up the curve's table, which reads the points and writes the result
+        self.state.cycle += 1;
+        for (i, word) in r.iter().enumerate() {
+            self.state.memory.set_memory(args.a0 + 4 * i as u32, *word);
+        }
+        Some(SyscallRet::ok(0))
+    }
+
+    fn sys_bls12381_fp(&mut self, args: SyscallArgs, op: FpOp) -> Option<SyscallRet> {
+        // args: a0 = x addr, a1 = y addr, both words. The result replaces x.
+        if (args.a0 | args.a1) & 3 != 0 {
+            return None;
+        }
+        let mut read_element = |addr: u32| -> bls12_381::Element {
+            core::array::from_fn(|i| self.state.memory.get_memory(addr + 4 * i as u32))
+        };
+        let (x, y) = (read_element(args.a0), read_element(args.a1));
+        let r = bls12_381::apply(op, &x, &y);
+        // the row looking up the BLS12-381 field table, which reads the elements and writes the
+        // result
+        self.state.cycle += 1;
+        for (i, word) in r.iter().enumerate() {
+            self.state.memory.set_memory(args.a0 + 4 * i as u32, *word);
+        }
+        Some(SyscallRet::ok(0))
+    }
+
+    fn sys_uint256_mulmod(&mut self, args: SyscallArgs) -> Option<SyscallRet> {
+        // args: a0 = x addr, a1 = addr of y then of the modulus, both words. The result replaces
+        // x.
+        if (args.a0 | args.a1) & 3 != 0 {
+            return None;
+        }
+        let mut read_uint = |addr: u32| -> uint256::Uint256 {
+            core::array::from_fn(|i| self.state.memory.get_memory(addr + 4 * i as u32))
+        };
+        let (x, y, m) = (
+            read_uint(args.a0),
+            read_uint(args.a1),
+            read_uint(args.a1 + 32),
+        );
+        let r = uint256::mulmod(&x, &y, &m)?;
+        // the row looking up the uint256 table, which reads the operands and writes the result
+        self.state.cycle += 1;
+        for (i, word) in r.iter().enumerate() {
+            self.state.memory.set_memory(args.a0 + 4 * i as u32, *word);
+        }
+        Some(SyscallRet::ok(0))
+    }
+
+    fn sys_blake2b_compress(&mut self, args: SyscallArgs) -> Option<SyscallRet> {
+        // args: a0 = h addr, a1 = addr of m, then of t, f and the number of rounds, all words.
+        // The 64-bit words of h, m and t are stored high word first.
+        if (args.a0 | args.a1) & 3 != 0 {
+            return None;
+        }
+        let memory = &mut self.state.memory;
+        let mut read_u64 = |addr: u32| -> u64 {
+            (memory.get_memory(addr) as u64) << 32 | memory.get_memory(addr + 4) as u64
+        };
+        let mut h: [u64; 8] = core::array::from_fn(|i| read_u64(args.a0 + 8 * i as u32));
+        let m: [u64; 16] = core::array::from_fn(|i| read_u64(args.a1 + 8 * i as u32));
+        let t = [read_u64(args.a1 + 128), read_u64(args.a1 + 136)];
+        let f = memory.get_memory(args.a1 + 144);
+        let rounds = memory.get_memory(args.a1 + 148);
+        if f > 1 {
+            return None;
+        }
+        // the row looking up the blake table, which reads h and the block and writes h
+        self.state.cycle += 1;
+        blake::blake2b_compress(rounds, &mut h, &m, t, f == 1);
+        for (i, word) in h.iter().enumerate() {
+            let addr = args.a0 + 8 * i as u32;
+            self.state.memory.set_memory(addr, (word >> 32) as u32);
+            self.state.memory.set_memory(addr + 4, *word as u32);
+        }
+        Some(SyscallRet::ok(0))
+    }
+
+    fn sys_blake3_compress(&mut self, args: SyscallArgs) -> Option<SyscallRet> {
+        // args: a0 = cv addr, a1 = addr of the block, then of the low and high words of the
+        // counter, the block length and the flags, all words
+        if (args.a0 | args.a1) & 3 != 0 {
+            return None;
+        }
+        let memory = &mut self.state.memory;
+        let mut cv: [u32; 8] = core::array::from_fn(|i| memory.get_memory(args.a0 + 4 * i as u32));
+        let block: [u32; 16] = core::array::from_fn(|i| memory.get_memory(args.a1 + 4 * i as u32));
+        let counter =
+            (memory.get_memory(args.a1 + 68) as u64) << 32 | memory.get_memory(args.a1 + 64) as u64;
+        let block_len = memory.get_memory(args.a1 + 72);
+        let flags = memory.get_memory(args.a1 + 76);
+        // the row looking up the blake table, which reads the chaining value and the block and
+        // writes the chaining value
+        self.state.cycle += 1;
+        blake::blake3_compress(&mut cv, &block, counter, block_len, flags);
+        for (i, word) in cv.iter().enumerate() {
+            self.state.memory.set_memory(args.a0 + 4 * i as u32, *word);
+        }
+        Some(SyscallRet::ok(0))
+    }
+
+    fn sys_chacha20_block(&mut self, args: SyscallArgs) -> Option<SyscallRet> {
+        // args: a0 = state addr, word aligned. The keystream block replaces the state.
+        if args.a0 & 3 != 0 {
+            return None;
+        }
+        let memory = &mut self.state.memory;
+        let state: [u32; 16] = core::array::from_fn(|i| memory.get_memory(args.a0 + 4 * i as u32));
+        // the row looking up the chacha table, which reads the state and writes the block
+        self.state.cycle += 1;
+        for (i, word) in chacha::chacha20_block(&state).iter().enumerate() {
+            self.state.memory.set_memory(args.a0 + 4 * i as u32, *word);
+        }
+        Some(SyscallRet::ok(0))
+    }
+
+    fn sys_modexp(&mut self, args: SyscallArgs) -> Option<SyscallRet> {
+        // args: a0 = base addr, a1 = addr of the number of words of the base and the modulus,
+        // then of the exponent, then of the modulus and the exponent, all words. The result
+        // replaces the base.
+        if (args.a0 | args.a1) & 3 != 0 {
+            return None;
+        }
+        let memory = &mut self.state.memory;
+        let len = memory.get_memory(args.a1) as usize;
+        let exp_len = memory.get_memory(args.a1 + 4) as usize;
+        if len > modexp::MODEXP_MAX_WORDS || exp_len > modexp::MODEXP_MAX_EXP_WORDS {
+            return None;
+        }
+        let mut read_words = |addr: u32, len: usize| -> Vec<u32> {
+            (0..len)
+                .map(|i| memory.get_memory(addr + 4 * i as u32))
+                .collect()
+        };
+        let base = read_words(args.a0, len);
+        let modulus = read_words(args.a1 + 8, len);
+        let exp = read_words(args.a1 + 8 + 4 * len as u32, exp_len);
+        let r = modexp::modexp(&base, &exp, &modulus)?;
+        // the row looking up the modexp table, which reads the operands and writes the result
+        self.state.cycle += 1;
+        for (i, word) in r.iter().enumerate() {
+            self.state.memory.set_memory(args.a0 + 4 * i as u32, *word);
+        }
+        Some(SyscallRet::ok(0))
+    }
+
+    fn sys_ed_add(&mut self, args: SyscallArgs) -> Option<SyscallRet> {
+        // args: a0 = p addr, a1 = q addr, both words. The sum replaces p.
+        if (args.a0 | args.a1) & 3 != 0 {
+            return None;
+        }
+        let mut read_point = |addr: u32| -> ed25519::Point {
+            core::array::from_fn(|i| self.state.memory.get_memory(addr + 4 * i as u32))
+        };
+        let (p, q) = (read_point(args.a0), read_point(args.a1));
+        let r = ed25519::add(&p, &q)?;
+        // the row looking up the ed25519 table, which reads the points and writes the sum
+        self.state.cycle += 1;
+        for (i, word) in r.iter().enumerate() {
+            self.state.memory.set_memory(args.a0 + 4 * i as u32, *word);
+        }
+        Some(SyscallRet::ok(0))
+    }
+
+    fn sys_ed_decompress(&mut self, args: SyscallArgs) -> Option<SyscallRet> {
+        // args: a0 = addr of 64 bytes, word aligned, holding the compressed point in the last 32.
+        // x is written over the first 32, in little-endian bytes.
+        if args.a0 & 3 != 0 {
+            return None;
+        }
+        let mut compressed = [0u8; 32];
+        for (i, chunk) in compressed.chunks_exact_mut(4).enumerate() {
+            let word = self.state.memory.get_memory(args.a0 + 32 + 4 * i as u32);
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        let x = ed25519::decompress(&compressed)?;
+        // the row looking up the ed25519 table, which reads y and writes x
+        self.state.cycle += 1;
+        let mut bytes = x.to_bytes_le();
+        bytes.resize(32, 0);
+        for (i, chunk) in bytes.chunks_exact(4).enumerate() {
+            let word = u32::from_be_bytes(chunk.try_into().unwrap());
+            self.state.memory.set_memory(args.a0 + 4 * i as u32, word);
+        }
+        Some(SyscallRet::ok(0))
+    }
+
+    fn sys_mmap(&mut self, args: SyscallArgs) -> SyscallRet {
+        // args: a0 = heap/hint, indicates mmap heap or hint. a1 = size
+        let mut size = args.a1;
+        if size & (PAGE_ADDR_MASK as u32) != 0 {
+            // adjust size to align with page size
+            size += PAGE_SIZE as u32 - (size & (PAGE_ADDR_MASK as u32));
+        }
+        if args.a0 == 0 {
+            let v0 = self.state.heap;
+            self.state.heap += size;
+            trace!("mmap heap {:x?} size {:x?}", v0, size);
+            self.state.guard_pages.map(v0, size);
+            SyscallRet::ok(v0)
+        } else {
+            trace!("mmap hint {:x?} size {:x?}", args.a0, size);
+            self.state.guard_pages.map(args.a0, size);
+            SyscallRet::ok(args.a0)
+        }
+    }
+
+    fn sys_brk(&mut self, args: SyscallArgs) -> SyscallRet {
+        // args: a0 = new program break
+        // The break only grows, and stays below the mmap region. A request which can't be
+        // satisfied leaves it unchanged: like Linux, brk returns the current break in both cases.
+        if args.a0 > self.state.brk && args.a0 <= MMAP_START {
+            self.state.brk = args.a0;
+        }
+        SyscallRet::ok(self.state.brk)
+    }
+
+    fn sys_open(&mut self, path_addr: u32, flags: u32) -> SyscallRet {
+        // openat: the directory fd is ignored, the registered files are looked up by path.
+        let path = self.read_c_string(path_addr);
+        self.state.cycle += (path.len() as u64 + 4) / 32 + 1;
+        log::debug!("open {} {:X}", path, flags);
+        match self.state.fds.open(&path, flags) {
+            Ok(fd) => SyscallRet::ok(fd),
+            Err(errno) => SyscallRet::err(errno),
+        }
+    }
+
+    fn sys_read(&mut self, args: SyscallArgs) -> SyscallRet {
+        // args: a0 = fd, a1 = addr, a2 = count
+        let bytes = match self.state.fds.read(
+            args.a0,
+            args.a2 as usize,
+            &self.state.input_stream,
+            &mut self.state.input_stream_ptr,
+        ) {
+            Ok(bytes) => bytes,
+            Err(errno) => return SyscallRet::err(errno),
+        };
+        if !bytes.is_empty() {
+            // each word is read and written back, 4 words per row
+            let words = (args.a1 as u64 + bytes.len() as u64 + 3) / 4 - args.a1 as u64 / 4;
+            self.state.cycle += (words + 3) / 4;
+        }
+        for (i, b) in bytes.iter().enumerate() {
+            self.set_byte(args.a1 + i as u32, *b);
+        }
+        SyscallRet::ok(bytes.len() as u32)
+    }
+
+    /// The number of steps executed before the current one and the cycles spent so far, since
+    /// the start of the program.
+    fn current_step_and_cycle(&self) -> (u64, u64) {
+        // the current instruction is not counted: step was already incremented for it
+        let step = self.state.total_step + self.state.step - 1;
+        (step, self.state.total_cycle + self.state.cycle)
+    }
+
+    fn sys_time(&mut self, num: u32, addr: u32) -> SyscallRet {
+        let (steps, _) = self.current_step_and_cycle();
+        let time = time_struct(num, guest_time_nanos(self.state.epoch, steps));
+        self.state.cycle += 1;
+        for (i, b) in time.iter().enumerate() {
+            self.set_byte(addr + i as u32, *b);
+        }
+        SyscallRet::ok(0)
+    }
+
+    fn read_c_string(&mut self, addr: u32) -> String {
+        let bytes = (addr..)
+            .map(|a| self.state.memory.byte(a))
+            .take_while(|b| *b != 0)
+            .collect::<Vec<u8>>();
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    fn set_byte(&mut self, addr: u32, b: u8) {
+        let word_addr = addr & 0xFFFFFFFC;
+        let shift = 24 - (addr & 3) * 8;
+        let word = self.state.memory.get_memory(word_addr);
+        let word = (word & !(0xFF << shift)) | ((b as u32) << shift);
+        self.state.memory.set_memory(word_addr, word);
+    }
+
+    fn sys_write(&mut self, args: SyscallArgs) -> SyscallRet {
+        // args: a0 = fd, a1 = addr, a2 = count
+        let (a1, a2) = (args.a1, args.a2);
+        let bytes = (0..a2)
+            .map(|i| self.state.memory.byte(a1 + i))
+            .collect::<Vec<u8>>();
+        let slice = bytes.as_slice();
+        match args.a0 {
+            // todo: track memory read
+            FD_STDOUT => {
+                self.state.memory.read_memory_range(a1, a2);
+                if let Err(e) =
+                    std::io::copy(self.state.memory.as_mut(), self.stdout_writer.as_mut())
+                {
+                    panic!("read range from memory failed {}", e);
+                }
+            }
+            FD_STDERR => {
+                self.state.last_stderr_write = slice.to_vec();
+                self.state.memory.read_memory_range(a1, a2);
+                if let Err(e) =
+                    std::io::copy(self.state.memory.as_mut(), self.stderr_writer.as_mut())
+                {
+                    panic!("read range from memory failed {}", e);
                 }
             }
-            4283 => {
-                log::trace!("set local user {:X} {:X} {:X}", a0, a1, a2);
-                self.state.local_user = a0;
+            FD_PUBLIC_VALUES => {
+                self.state.public_values_stream.extend_from_slice(slice);
             }
-            0xF2 => {
-                log::trace!("sys_verify {:X} {:X} {:X}", a0, a1, a2);
-                // DO Nothing Here
+            FD_HINT => match self.state.hint_handler.as_mut() {
+                Some(handler) => {
+                    let answer = handler(slice);
+                    self.state.input_stream.extend(answer);
+                }
+                None => self.state.input_stream.push(slice.to_vec()),
+            },
+            FD_CYCLE_TRACKER => {
+                let (step, cycle) = self.current_step_and_cycle();
+                self.state.cycle_tracker.mark(slice, step, cycle);
             }
-            _ => {}
+            _ => return SyscallRet::err(MIPS_EBADF),
         }
+        SyscallRet::ok(a2)
+    }
 
-        self.state.registers[2] = v0;
-        self.state.registers[7] = v1;
+    fn sys_fcntl(&mut self, args: SyscallArgs) -> SyscallRet {
+        // args: a0 = fd, a1 = cmd
+        match self.state.fds.fcntl(args.a0, args.a1) {
+            Ok(flags) => SyscallRet::ok(flags),
+            Err(errno) => SyscallRet::err(errno),
+        }
+    }
 
-        self.state.pc = self.state.next_pc;
-        self.state.next_pc += 4;
+    fn sys_ioctl(&mut self, args: SyscallArgs) -> SyscallRet {
+        // args: a0 = fd, a1 = request, a2 = argp; nothing is written to argp
+        match self.state.fds.ioctl(args.a0) {
+            Ok(v) => SyscallRet::ok(v),
+            Err(errno) => SyscallRet::err(errno),
+        }
     }
 
     fn handle_branch(&mut self, opcode: u32, insn: u32, rt_reg: u32, rs: u32) {
         self.state.dump_info = true;
+        // branch-likely: beql/bnel/blezl/bgtzl are beq/bne/blez/bgtz with bit 4 set,
+        // bltzl/bgezl are bltz/bgez with bit 1 set in rt
+        let rtv = (insn >> 16) & 0x1F;
+        let likely = opcode >= 0x14 || (opcode == 1 && (rtv == 2 || rtv == 3));
+        let opcode = opcode & !0x10;
         let should_branch = match opcode {
             4 | 5 => {
                 // beq/bne
@@ -734,17 +1486,20 @@ impl InstrumentedState {
             }
             1 => {
                 // reqimm
-                let rtv = (insn >> 16) & 0x1F;
-                if rtv == 0 {
-                    // bltz
+                if rtv == 0 || rtv == 2 {
+                    // bltz, bltzl
                     (rs as i32) < 0
-                } else if rtv == 1 {
-                    // 1 -> bgez
+                } else if rtv == 1 || rtv == 3 {
+                    // 1 -> bgez, 3 -> bgezl
                     (rs as i32) >= 0
-                } else if rtv == 0b10001 {
-                    // bal  000001 00000 10001 offset
+                } else if rtv == 0b10000 || rtv == 0b10001 {
+                    // bltzal, bgezal (bal when rs is $zero), always link
                     self.state.registers[31] = self.state.pc + 8;
-                    true
+                    if rtv == 0b10000 {
+                        (rs as i32) < 0
+                    } else {
+                        (rs as i32) >= 0
+                    }
                 } else {
                     false
                 }
@@ -760,6 +1515,10 @@ impl InstrumentedState {
             // then continue with the instruction the branch jumps to.
             self.state.next_pc =
                 (prev_pc as u64 + 4u64 + (sign_extension(insn & 0xFFFF, 16) << 2) as u64) as u32;
+        } else if likely {
+            // the delay slot is nullified when a branch-likely is not taken
+            self.state.pc = self.state.next_pc + 4;
+            self.state.next_pc = self.state.pc + 4;
         } else {
             self.state.next_pc += 4;
         }
@@ -777,14 +1536,68 @@ impl InstrumentedState {
         self.state.dump_info = true;
     }
 
-    fn handle_trap(&mut self) {
-        // do nothing currently
+    /// Raises an exception: enters the handler of the guest if it has one, else stops.
+    /// `bad_vaddr` is the faulting address of a TLB exception.
+    fn handle_trap(&mut self, exc_code: u8, bad_vaddr: Option<u32>) {
+        if self.state.cp0.delivers() {
+            log::debug!(
+                "exception {} at pc {:X}, entering handler",
+                exc_code,
+                self.state.pc
+            );
+            let vector =
+                self.state
+                    .cp0
+                    .enter(exc_code, self.state.pc, self.state.next_pc, bad_vaddr);
+            self.state.pc = vector;
+            self.state.next_pc = vector + 4;
+            return;
+        }
+        log::warn!(
+            "trap at pc {:X}, exception code {}",
+            self.state.pc,
+            exc_code
+        );
+        self.state.exited = true;
+        self.state.exit_code = exc_code;
         self.state.dump_info = true;
     }
 
+    fn handle_cop0(&mut self, insn: u32) {
+        let rt_reg = (insn >> 16) & 0x1f;
+        let rd = ((insn >> 11) & 0x1f) as usize;
+        if insn == ERET {
+            let epc = self.state.cp0.eret();
+            self.state.pc = epc;
+            self.state.next_pc = epc + 4;
+            return;
+        }
+        if insn & 0x7ff != 0 {
+            panic!("invalid cop0 instruction {:X} at {:X}", insn, self.state.pc);
+        }
+        match (insn >> 21) & 0x1f {
+            // mfc0
+            0 => self.handle_rd(rt_reg, self.state.cp0.regs[rd], true),
+            // mtc0
+            4 => {
+                self.state.cp0.regs[rd] = self.state.registers[rt_reg as usize];
+                self.handle_rd(0, 0, false);
+            }
+            _ => panic!("invalid cop0 instruction {:X} at {:X}", insn, self.state.pc),
+        }
+    }
+
     fn handle_hilo(&mut self, fun: u32, rs: u32, rt: u32, store_reg: u32) {
         let mut val = 0u32;
         match fun {
+            0x00 => {
+                // madd
+                let mul = ((rs as i32) as i64).wrapping_mul((rt as i32) as i64) as u64;
+                let hilo = ((self.state.hi as u64) << 32) | (self.state.lo as u64);
+                let acc = hilo.wrapping_add(mul);
+                self.state.hi = (acc >> 32) as u32;
+                self.state.lo = acc as u32;
+            }
             0x01 => {
                 // maddu
                 let mut acc = (rs as u64).wrapping_mul(rt as u64);
@@ -793,6 +1606,22 @@ impl InstrumentedState {
                 self.state.hi = (acc >> 32) as u32;
                 self.state.lo = acc as u32;
             }
+            0x04 => {
+                // msub
+                let mul = ((rs as i32) as i64).wrapping_mul((rt as i32) as i64) as u64;
+                let hilo = ((self.state.hi as u64) << 32) | (self.state.lo as u64);
+                let acc = hilo.wrapping_sub(mul);
+                self.state.hi = (acc >> 32) as u32;
+                self.state.lo = acc as u32;
+            }
+            0x05 => {
+                // msubu
+                let mul = (rs as u64).wrapping_mul(rt as u64);
+                let hilo = ((self.state.hi as u64) << 32) | (self.state.lo as u64);
+                let acc = hilo.wrapping_sub(mul);
+                self.state.hi = (acc >> 32) as u32;
+                self.state.lo = acc as u32;
+            }
             0x10 => {
                 // mfhi
                 val = self.state.hi;
@@ -822,14 +1651,24 @@ impl InstrumentedState {
                 self.state.lo = acc as u32;
             }
             0x1a => {
-                // div
-                self.state.hi = ((rs as i32) % (rt as i32)) as u32;
-                self.state.lo = ((rs as i32) / (rt as i32)) as u32;
+                // div, x / 0 gives quotient 0 and remainder x, as the prover does
+                if rt == 0 {
+                    self.state.hi = rs;
+                    self.state.lo = 0;
+                } else {
+                    self.state.hi = (rs as i32).wrapping_rem(rt as i32) as u32;
+                    self.state.lo = (rs as i32).wrapping_div(rt as i32) as u32;
+                }
             }
             0x1b => {
                 // divu
-                self.state.hi = rs % rt;
-                self.state.lo = rs / rt;
+                if rt == 0 {
+                    self.state.hi = rs;
+                    self.state.lo = 0;
+                } else {
+                    self.state.hi = rs % rt;
+                    self.state.lo = rs / rt;
+                }
             }
             n => {
                 panic!("invalid fun when process hi lo, fun: {}", n);
@@ -868,9 +1707,21 @@ impl InstrumentedState {
         // fetch instruction
         let insn = self.state.memory.get_memory(self.state.pc);
         let opcode = insn >> 26; // 6-bits
+        self.state.opcode_counts[opcode_key(insn) as usize] += 1;
 
         log::trace!("pc: {:X}, insn: {:X}", self.state.pc, insn);
 
+        if opcode == 0x10 {
+            self.handle_cop0(insn);
+            return;
+        }
+
+        // cop1, cop1x, lwc1, ldc1, swc1, sdc1: no FPU, guests must use soft-float
+        if matches!(opcode, 0x11 | 0x13 | 0x31 | 0x35 | 0x39 | 0x3D) {
+            self.handle_trap(EXC_COPROCESSOR_UNUSABLE, None);
+            return;
+        }
+
         // j-type j/jal
         if opcode == 2 || opcode == 3 {
             let link_reg = match opcode {
@@ -878,7 +1729,9 @@ impl InstrumentedState {
                 _ => 0,
             };
 
-            self.handle_jump(link_reg, sign_extension(insn & 0x03ffffff, 26) << 2);
+            // the target stays in the 256MB region of the delay slot
+            let region = self.state.pc.wrapping_add(4) & 0xf0000000;
+            self.handle_jump(link_reg, region | ((insn & 0x03ffffff) << 2));
             return;
         }
 
@@ -911,7 +1764,7 @@ impl InstrumentedState {
             rd_reg = rt_reg;
         }
 
-        if (4..8).contains(&opcode) || opcode == 1 {
+        if (4..8).contains(&opcode) || (0x14..0x18).contains(&opcode) || opcode == 1 {
             self.handle_branch(opcode, insn, rt_reg, rs);
             return;
         }
@@ -924,6 +1777,17 @@ impl InstrumentedState {
             // M[R[rs]+SignExtImm]
             rs = (rs as u64 + sign_extension(insn & 0xffff, 16) as u64) as u32;
             let addr = rs & 0xfffffffc;
+            if self.state.guard_pages.contains(addr) && opcode != 0x33 {
+                // a page fault, reported as a TLB miss
+                let is_store = opcode >= 0x28 && opcode != 0x30;
+                let exc_code = if is_store {
+                    EXC_TLB_STORE
+                } else {
+                    EXC_TLB_LOAD
+                };
+                self.handle_trap(exc_code, Some(rs));
+                return;
+            }
             mem = self.state.memory.get_memory(addr);
             if opcode >= 0x28 && opcode != 0x30 {
                 // store
@@ -957,6 +1821,12 @@ impl InstrumentedState {
                 return;
             }
 
+            if fun == 0xd {
+                // break
+                self.handle_trap(EXC_BREAKPOINT, None);
+                return;
+            }
+
             // syscall (can read/write)
             if fun == 0xc {
                 self.handle_syscall();
@@ -972,14 +1842,33 @@ impl InstrumentedState {
             }
         }
 
-        if opcode == 0x1C && fun == 0x1 {
-            // maddu
+        if opcode == 0x1C && matches!(fun, 0x0 | 0x1 | 0x4 | 0x5) {
+            // madd, maddu, msub, msubu
             self.handle_hilo(fun, rs, rt, rd_reg);
             return;
         }
 
-        if opcode == 0 && fun == 0x34 && val == 1 {
-            self.handle_trap();
+        if (opcode == 0 && matches!(fun, 0x20 | 0x22)) || opcode == 0x08 {
+            // add, sub, addi: trap instead of wrapping on signed overflow
+            let overflow = if opcode == 0 && fun == 0x22 {
+                (rs as i32).checked_sub(rt as i32).is_none()
+            } else {
+                (rs as i32).checked_add(rt as i32).is_none()
+            };
+            if overflow {
+                self.handle_trap(EXC_OVERFLOW, None);
+                return;
+            }
+        }
+
+        if opcode == 0 && matches!(fun, 0x30..=0x34 | 0x36) {
+            // tge, tgeu, tlt, tltu, teq, tne
+            if val == 1 {
+                self.handle_trap(EXC_TRAP, None);
+            } else {
+                self.handle_rd(0, val, false);
+            }
+            return;
         }
 
         // stupid sc, write a 1 to rt
@@ -1049,18 +1938,21 @@ impl InstrumentedState {
                         return rt << shamt; // sll
                     } else if fun == 0x02 {
                         if (insn >> 21) & 0x1F == 1 {
-                            return rt >> shamt | rt << (32 - shamt); // ror
+                            return rt.rotate_right(shamt); // ror
                         } else if (insn >> 21) & 0x1F == 0 {
                             return rt >> shamt; // srl
                         }
                     } else if fun == 0x03 {
-                        return sign_extension(rt >> shamt, 32 - shamt); // sra
+                        return ((rt as i32) >> shamt) as u32; // sra
                     } else if fun == 0x04 {
                         return rt << (rs & 0x1f); // sllv
                     } else if fun == 0x06 {
+                        if shamt == 1 {
+                            return rt.rotate_right(rs & 0x1f); // rorv
+                        }
                         return rt >> (rs & 0x1f); // srlv
                     } else if fun == 0x07 {
-                        return sign_extension(rt >> rs, 32 - rs); // srav
+                        return ((rt as i32) >> (rs & 0x1f)) as u32; // srav
                     }
                 }
 
@@ -1099,12 +1991,23 @@ impl InstrumentedState {
                             0
                         };
                     }
+                    0x30 => {
+                        return ((rs as i32) >= (rt as i32)) as u32; // tge
+                    }
+                    0x31 => {
+                        return (rs >= rt) as u32; // tgeu
+                    }
+                    0x32 => {
+                        return ((rs as i32) < (rt as i32)) as u32; // tlt
+                    }
+                    0x33 => {
+                        return (rs < rt) as u32; // tltu
+                    }
                     0x34 => {
-                        return if rs == rt {
-                            1 // teq
-                        } else {
-                            0
-                        };
+                        return (rs == rt) as u32; // teq
+                    }
+                    0x36 => {
+                        return (rs != rt) as u32; // tne
                     }
                     _ => {}
                 }
@@ -1112,8 +2015,8 @@ impl InstrumentedState {
                 return rt << 16; // lui
             } else if opcode == 0x1c {
                 // SPECIAL2
-                if fun == 1 {
-                    //maddu: do nothing here
+                if matches!(fun, 0x0 | 0x1 | 0x4 | 0x5) {
+                    //madd/maddu/msub/msubu: do nothing here
                     return rs;
                 }
                 if fun == 2 {
@@ -1153,6 +2056,8 @@ impl InstrumentedState {
                     let rd = (insn >> 11) & 0x1F;
                     if rd == 0 {
                         return 1; // cpu number
+                    } else if rd == 3 {
+                        return 1; // cycle counter resolution, the counter itself reads as 0
                     } else if rd == 29 {
                         log::trace!("pc: {:X} rdhwr {:X}", self.state.pc, self.state.local_user);
                         //return 0x946490;  // a pointer to a thread-specific storage block
@@ -1245,9 +2150,6 @@ impl InstrumentedState {
         } else if opcode == 0x38 {
             // sc
             return rt;
-        } else if opcode == 0x3D {
-            // sdc1
-            return 0;
         }
 
         panic!(
@@ -1261,6 +2163,7 @@ impl InstrumentedState {
         self.state.dump_info = false;
 
         self.mips_step();
+        self.state.min_sp = self.state.min_sp.min(self.state.registers[REG_SP]);
         if dump {
             log::trace!(
                 "pc: {:X} regs: {:X?}\n",
@@ -1272,6 +2175,12 @@ impl InstrumentedState {
         self.state.cycle + (self.state.memory.page_count() + 1) * PAGE_CYCLES + RESERVE_CYCLES
     }
 
+    /// The image id of the state at the start of the current segment, the commitment to its
+    /// memory, registers and pc. Before the first step, it identifies the program.
+    pub fn image_id(&self) -> [u8; 32] {
+        self.pre_image_id
+    }
+
     /// the caller should provide a write to write segemnt if proof is true
     pub fn split_segment<W: Write>(
         &mut self,
@@ -1304,10 +2213,18 @@ impl InstrumentedState {
                 end_pc: self.state.pc,
                 step: self.state.step,
                 page_hash_root,
-                input_stream: self.pre_input.clone(),
+                // The inputs are only appended to, and the ones appended by the hints of this
+                // segment have to be there for the prover.
+                input_stream: self.state.input_stream.clone(),
                 input_stream_ptr: self.pre_input_ptr,
                 public_values_stream: self.pre_public_values.clone(),
                 public_values_stream_ptr: self.pre_public_values_ptr,
+                fds: self.pre_fds.clone(),
+                total_step: self.state.total_step - self.state.step,
+                epoch: self.state.epoch,
+                guard_pages: self.pre_guard_pages.clone(),
+                oracle: self.state.oracle.clone(),
+                cp0: self.pre_cp0,
             };
             let name = format!("{output}/{}", self.pre_segment_id);
             log::debug!("split: file {}", name);
@@ -1317,10 +2234,12 @@ impl InstrumentedState {
             self.pre_segment_id += 1;
         }
 
-        self.pre_input = self.state.input_stream.clone();
         self.pre_input_ptr = self.state.input_stream_ptr;
         self.pre_public_values = self.state.public_values_stream.clone();
         self.pre_public_values_ptr = self.state.public_values_stream_ptr;
+        self.pre_fds = self.state.fds.clone();
+        self.pre_guard_pages = self.state.guard_pages.clone();
+        self.pre_cp0 = self.state.cp0;
         self.pre_pc = self.state.pc;
         self.pre_image_id = image_id;
         self.pre_hash_root = page_hash_root;
@@ -0,0 +1,61 @@
+//! The inputs of a program, built on the host.
+//!
+//! Each `write` pushes one item, which the program reads in the same order: values written with
+//! [`Stdin::write`] with `zkm_runtime::io::read::<T>()`, and byte strings written with
+//! [`Stdin::write_slice`] with `zkm_runtime::io::read_vec()`.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Stdin {
+    /// The items, each the bytes returned by one read of the program.
+    pub buffer: Vec<Vec<u8>>,
+    /// The index of the next item returned by [`Stdin::read`].
+    pub ptr: usize,
+}
+
+impl Stdin {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inputs made of a single byte string.
+    pub fn from_slice(data: &[u8]) -> Self {
+        let mut stdin = Self::new();
+        stdin.write_slice(data);
+        stdin
+    }
+
+    /// Pushes `value`, serialized with bincode.
+    pub fn write<T: Serialize>(&mut self, value: &T) -> &mut Self {
+        let buf = bincode::serialize(value).expect("serialization failed");
+        self.buffer.push(buf);
+        self
+    }
+
+    /// Pushes the raw bytes `data`.
+    pub fn write_slice(&mut self, data: &[u8]) -> &mut Self {
+        self.buffer.push(data.to_vec());
+        self
+    }
+
+    /// Pushes the raw bytes `data`, without copying them.
+    pub fn write_vec(&mut self, data: Vec<u8>) -> &mut Self {
+        self.buffer.push(data);
+        self
+    }
+
+    /// Reads back the next item as a `T`, as the program would.
+    pub fn read<T: DeserializeOwned>(&mut self) -> T {
+        let item = &self.buffer[self.ptr];
+        self.ptr += 1;
+        bincode::deserialize(item).expect("deserialization failed")
+    }
+
+    /// Reads back the next item as raw bytes, as the program would.
+    pub fn read_slice(&mut self) -> &[u8] {
+        self.ptr += 1;
+        &self.buffer[self.ptr - 1]
+    }
+}
@@ -0,0 +1,410 @@
+//! The system calls serviced by the emulator kernel.
+//!
+//! Syscalls follow the Linux o32 calling convention: the syscall number is passed in `$v0` and
+//! the arguments in `$a0`-`$a3`. On return `$v0` holds the result and `$a3` the error code, which
+//! is 0 on success. A failing syscall sets `$v0` to `-1`.
+
+use crate::page::{PAGE_ADDR_MASK, PAGE_ADDR_SIZE};
+use crate::state::{
+    FD_HINT, FD_STDIN, MIPS_EACCES, MIPS_EBADF, MIPS_EINVAL, MIPS_ENOENT, MIPS_ENOTTY,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+pub const SYS_EXIT: u32 = 4001;
+pub const SYS_READ: u32 = 4003;
+pub const SYS_WRITE: u32 = 4004;
+pub const SYS_OPEN: u32 = 4005;
+pub const SYS_CLOSE: u32 = 4006;
+pub const SYS_GETPID: u32 = 4020;
+pub const SYS_BRK: u32 = 4045;
+pub const SYS_GETGID: u32 = 4047;
+pub const SYS_IOCTL: u32 = 4054;
+pub const SYS_GETTIMEOFDAY: u32 = 4078;
+pub const SYS_FCNTL: u32 = 4055;
+pub const SYS_MMAP: u32 = 4090;
+pub const SYS_MUNMAP: u32 = 4091;
+pub const SYS_CLONE: u32 = 4120;
+pub const SYS_MPROTECT: u32 = 4125;
+pub const SYS_SCHED_YIELD: u32 = 4162;
+pub const SYS_NANOSLEEP: u32 = 4166;
+pub const SYS_RT_SIGACTION: u32 = 4194;
+pub const SYS_RT_SIGPROCMASK: u32 = 4195;
+pub const SYS_SIGALTSTACK: u32 = 4206;
+pub const SYS_MMAP2: u32 = 4210;
+pub const SYS_MADVISE: u32 = 4218;
+pub const SYS_GETTID: u32 = 4222;
+pub const SYS_FUTEX: u32 = 4238;
+pub const SYS_SCHED_GETAFFINITY: u32 = 4240;
+pub const SYS_EXIT_GROUP: u32 = 4246;
+pub const SYS_SET_TID_ADDRESS: u32 = 4252;
+pub const SYS_CLOCK_GETTIME: u32 = 4263;
+pub const SYS_TGKILL: u32 = 4266;
+pub const SYS_SET_THREAD_AREA: u32 = 4283;
+pub const SYS_OPENAT: u32 = 4288;
+pub const SYS_SET_ROBUST_LIST: u32 = 4309;
+pub const SYS_CLOCK_GETTIME64: u32 = 4403;
+
+// zkMIPS specific syscalls, outside of the o32 range.
+pub const SYS_HINT_LEN: u32 = 0xF0;
+pub const SYS_HINT_READ: u32 = 0xF1;
+pub const SYS_VERIFY: u32 = 0xF2;
+pub const SYS_PREIMAGE_LEN: u32 = 0xF3;
+pub const SYS_PREIMAGE_READ: u32 = 0xF4;
+pub const SYS_CYCLE_COUNT: u32 = 0xF5;
+
+// Precompiles, proven by their own tables instead of by the instructions of the guest.
+pub const SYS_KECCAK256: u32 = 0x00_01_01_09;
+pub const SYS_SHA256_EXTEND: u32 = 0x00_30_01_05;
+pub const SYS_SHA256_COMPRESS: u32 = 0x00_01_01_06;
+pub const SYS_SECP256K1_ADD: u32 = 0x00_01_01_0A;
+pub const SYS_SECP256K1_DOUBLE: u32 = 0x00_00_01_0B;
+pub const SYS_ED_ADD: u32 = 0x00_01_01_07;
+pub const SYS_ED_DECOMPRESS: u32 = 0x00_00_01_08;
+pub const SYS_BN254_ADD: u32 = 0x00_01_01_0E;
+pub const SYS_BN254_DOUBLE: u32 = 0x00_00_01_0F;
+pub const SYS_BLS12381_FP_ADD: u32 = 0x00_01_01_71;
+pub const SYS_BLS12381_FP_SUB: u32 = 0x00_01_01_72;
+pub const SYS_BLS12381_FP_MUL: u32 = 0x00_01_01_73;
+pub const SYS_UINT256_MULMOD: u32 = 0x00_01_01_1D;
+pub const SYS_BLAKE2B_COMPRESS: u32 = 0x00_01_01_1E;
+pub const SYS_BLAKE3_COMPRESS: u32 = 0x00_01_01_1F;
+pub const SYS_CHACHA20_BLOCK: u32 = 0x00_01_01_20;
+pub const SYS_SECP256R1_ADD: u32 = 0x00_01_01_2C;
+pub const SYS_SECP256R1_DOUBLE: u32 = 0x00_00_01_2D;
+pub const SYS_MODEXP: u32 = 0x00_01_01_2E;
+
+/// Whether the syscall `num` is a precompile, proven by its own table.
+pub fn is_precompile(num: u32) -> bool {
+    (num >> 8) & 0xFF == 0x01
+}
+
+/// Start of the region anonymous mappings are allocated from. The program break grows up to it.
+pub const MMAP_START: u32 = 0x20000000;
+
+pub const REG_V0: usize = 2;
+pub const REG_A0: usize = 4;
+pub const REG_A1: usize = 5;
+pub const REG_A2: usize = 6;
+pub const REG_A3: usize = 7;
+pub const REG_SP: usize = 29;
+
+/// The syscall number and arguments, as passed in the registers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SyscallArgs {
+    pub num: u32,
+    pub a0: u32,
+    pub a1: u32,
+    pub a2: u32,
+    pub a3: u32,
+}
+
+impl SyscallArgs {
+    pub fn from_registers(registers: &[u32; 32]) -> Self {
+        Self {
+            num: registers[REG_V0],
+            a0: registers[REG_A0],
+            a1: registers[REG_A1],
+            a2: registers[REG_A2],
+            a3: registers[REG_A3],
+        }
+    }
+}
+
+/// The result of a syscall, written back to `$v0` and `$a3`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SyscallRet {
+    pub v0: u32,
+    pub errno: u32,
+}
+
+impl SyscallRet {
+    pub fn ok(v0: u32) -> Self {
+        Self { v0, errno: 0 }
+    }
+
+    pub fn err(errno: u32) -> Self {
+        Self {
+            v0: 0xffffffff,
+            errno,
+        }
+    }
+
+    pub fn write_registers(&self, registers: &mut [u32; 32]) {
+        registers[REG_V0] = self.v0;
+        registers[REG_A3] = self.errno;
+    }
+}
+
+const PROT_NONE: u32 = 0;
+
+/// The pages the guest can't access, because they were unmapped or protected with `PROT_NONE`.
+/// A load or a store to one of them raises a TLB exception, instead of returning zeros.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct GuardPages {
+    pub pages: BTreeSet<u32>,
+}
+
+impl GuardPages {
+    pub fn contains(&self, addr: u32) -> bool {
+        self.pages.contains(&(addr >> PAGE_ADDR_SIZE))
+    }
+
+    fn range(addr: u32, len: u32) -> Result<std::ops::Range<u32>, u32> {
+        if addr & PAGE_ADDR_MASK as u32 != 0 {
+            return Err(MIPS_EINVAL);
+        }
+        let end = (addr as u64 + len as u64 + PAGE_ADDR_MASK as u64) >> PAGE_ADDR_SIZE;
+        Ok(addr >> PAGE_ADDR_SIZE..end as u32)
+    }
+
+    /// Makes the pages of a new mapping accessible.
+    pub fn map(&mut self, addr: u32, len: u32) {
+        if let Ok(pages) = Self::range(addr & !(PAGE_ADDR_MASK as u32), len) {
+            pages.for_each(|page| {
+                self.pages.remove(&page);
+            });
+        }
+    }
+
+    pub fn unmap(&mut self, addr: u32, len: u32) -> Result<(), u32> {
+        self.pages.extend(Self::range(addr, len)?);
+        Ok(())
+    }
+
+    pub fn protect(&mut self, addr: u32, len: u32, prot: u32) -> Result<(), u32> {
+        if prot == PROT_NONE {
+            self.unmap(addr, len)
+        } else {
+            self.map(addr, len);
+            Ok(())
+        }
+    }
+}
+
+/// The thread id of the only thread of the guest.
+const GUEST_TID: u32 = 1;
+const SS_DISABLE: u32 = 2;
+
+/// The thread, signal and scheduling syscalls made by the Rust and Go runtimes, which a
+/// single-threaded guest can ignore. Returns `None` for the other syscalls, else the result and
+/// the bytes the syscall writes back to the guest along with their address, if any.
+///
+/// No other thread can wake a futex, so waiting on one returns at once, as on a spurious wakeup.
+/// Sleeping returns at once too, and memory advice is ignored. The guest runs on a single CPU.
+/// No signal is ever delivered: the previous actions and masks are reported as empty, and the
+/// alternate signal stack as disabled.
+pub fn runtime_syscall(args: &SyscallArgs) -> Option<(SyscallRet, Option<(u32, Vec<u8>)>)> {
+    let ret = match args.num {
+        SYS_FUTEX | SYS_SET_ROBUST_LIST | SYS_SCHED_YIELD => (SyscallRet::ok(0), None),
+        SYS_NANOSLEEP | SYS_MADVISE | SYS_TGKILL => (SyscallRet::ok(0), None),
+        SYS_GETTID | SYS_SET_TID_ADDRESS => (SyscallRet::ok(GUEST_TID), None),
+        // args: a0 = pid, a1 = size of the mask, a2 = mask
+        SYS_SCHED_GETAFFINITY => {
+            if args.a1 < 4 {
+                (SyscallRet::err(MIPS_EINVAL), None)
+            } else {
+                let mask = 1u32.to_be_bytes().to_vec();
+                (SyscallRet::ok(4), Some((args.a2, mask)))
+            }
+        }
+        // args: a0 = signal, a1 = new action, a2 = old action, a3 = size of the mask
+        SYS_RT_SIGACTION => {
+            let old = vec![0u8; 8 + args.a3 as usize];
+            (SyscallRet::ok(0), (args.a2 != 0).then_some((args.a2, old)))
+        }
+        // args: a0 = how, a1 = new mask, a2 = old mask, a3 = size of the mask
+        SYS_RT_SIGPROCMASK => {
+            let old = vec![0u8; args.a3 as usize];
+            (SyscallRet::ok(0), (args.a2 != 0).then_some((args.a2, old)))
+        }
+        // args: a0 = new stack, a1 = old stack
+        SYS_SIGALTSTACK => {
+            let old = [0u32, 0, SS_DISABLE]
+                .iter()
+                .flat_map(|w| w.to_be_bytes())
+                .collect::<Vec<u8>>();
+            (SyscallRet::ok(0), (args.a1 != 0).then_some((args.a1, old)))
+        }
+        _ => return None,
+    };
+    Some(ret)
+}
+
+/// The time the guest sees advances by this much for each executed instruction.
+pub const NANOS_PER_STEP: u64 = 10;
+
+/// The time reported by the time syscalls, in nanoseconds since the Unix epoch: `epoch` is the
+/// time at which the program started, in seconds, and `steps` the number of instructions executed
+/// since. Both clocks, realtime and monotonic, return it. The proof doesn't check it.
+pub fn guest_time_nanos(epoch: u64, steps: u64) -> u64 {
+    epoch * 1_000_000_000 + steps * NANOS_PER_STEP
+}
+
+/// The struct written by a time syscall, as the bytes stored at its pointer argument: a
+/// `timespec` for `clock_gettime`, a `timeval` for `gettimeofday`.
+pub fn time_struct(num: u32, nanos: u64) -> Vec<u8> {
+    let (secs, nsecs) = (nanos / 1_000_000_000, nanos % 1_000_000_000);
+    match num {
+        SYS_CLOCK_GETTIME64 => [secs.to_be_bytes(), nsecs.to_be_bytes()].concat(),
+        SYS_GETTIMEOFDAY => [
+            (secs as u32).to_be_bytes(),
+            ((nsecs / 1000) as u32).to_be_bytes(),
+        ]
+        .concat(),
+        _ => [(secs as u32).to_be_bytes(), (nsecs as u32).to_be_bytes()].concat(),
+    }
+}
+
+const O_ACCMODE: u32 = 3;
+const O_RDONLY: u32 = 0;
+const O_WRONLY: u32 = 1;
+
+const F_GETFD: u32 = 1;
+const F_SETFD: u32 = 2;
+const F_GETFL: u32 = 3;
+const F_SETFL: u32 = 4;
+
+/// The size of the keys of the preimage oracle.
+pub const PREIMAGE_KEY_SIZE: usize = 32;
+
+/// The data the guest fetches on demand by key, e.g. the nodes of a Merkle tree by their hash,
+/// instead of reading all of it upfront from the input stream.
+///
+/// Nothing ties a preimage to its key in the proof: a guest which takes the key for a hash has
+/// to check the data it gets against it.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct PreimageOracle {
+    /// The preimages, by hex encoded key.
+    pub preimages: BTreeMap<String, Vec<u8>>,
+}
+
+impl PreimageOracle {
+    pub fn insert(&mut self, key: [u8; PREIMAGE_KEY_SIZE], data: Vec<u8>) {
+        self.preimages.insert(hex::encode(key), data);
+    }
+
+    /// Fails with `ENOENT` if the host has no preimage for the key.
+    pub fn get(&self, key: &[u8]) -> Result<&[u8], u32> {
+        self.preimages
+            .get(&hex::encode(key))
+            .map(|data| data.as_slice())
+            .ok_or(MIPS_ENOENT)
+    }
+}
+
+/// An input file opened by the guest.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct OpenFile {
+    pub path: String,
+    pub offset: usize,
+}
+
+/// The file descriptors of the guest.
+///
+/// Reading stdin consumes the input stream: a read returns the next bytes of the current entry,
+/// and moves on to the next entry once it is exhausted. The other readable files are registered
+/// by the host by path, and opened by the guest with `open` or `openat`.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct FdTable {
+    pub files: BTreeMap<String, Vec<u8>>,
+    pub open_files: BTreeMap<u32, OpenFile>,
+    /// Offset of the next byte of stdin in the current input stream entry.
+    pub stdin_offset: usize,
+}
+
+impl FdTable {
+    pub fn add_file(&mut self, path: &str, data: Vec<u8>) {
+        self.files.insert(path.to_string(), data);
+    }
+
+    /// Open a registered file for reading, returning the lowest free descriptor.
+    pub fn open(&mut self, path: &str, flags: u32) -> Result<u32, u32> {
+        if !self.files.contains_key(path) {
+            return Err(MIPS_ENOENT);
+        }
+        if flags & O_ACCMODE != O_RDONLY {
+            return Err(MIPS_EACCES);
+        }
+        let fd = (FD_HINT + 1..)
+            .find(|fd| !self.open_files.contains_key(fd))
+            .unwrap();
+        self.open_files.insert(
+            fd,
+            OpenFile {
+                path: path.to_string(),
+                offset: 0,
+            },
+        );
+        Ok(fd)
+    }
+
+    fn is_open(&self, fd: u32) -> bool {
+        fd <= FD_HINT || self.open_files.contains_key(&fd)
+    }
+
+    /// Only the flags can be queried: the descriptors are never closed on exec, stdin and the
+    /// input files are read-only and the other standard descriptors write-only. Setting flags
+    /// is accepted and ignored.
+    pub fn fcntl(&self, fd: u32, cmd: u32) -> Result<u32, u32> {
+        if !self.is_open(fd) {
+            return Err(MIPS_EBADF);
+        }
+        match cmd {
+            F_GETFD | F_SETFD | F_SETFL => Ok(0),
+            F_GETFL if fd == FD_STDIN || fd > FD_HINT => Ok(O_RDONLY),
+            F_GETFL => Ok(O_WRONLY),
+            _ => Err(MIPS_EINVAL),
+        }
+    }
+
+    /// No descriptor is a terminal, so that `isatty` is false and the window size unknown.
+    pub fn ioctl(&self, fd: u32) -> Result<u32, u32> {
+        if self.is_open(fd) {
+            Err(MIPS_ENOTTY)
+        } else {
+            Err(MIPS_EBADF)
+        }
+    }
+
+    pub fn close(&mut self, fd: u32) -> Result<(), u32> {
+        if fd <= FD_HINT || self.open_files.remove(&fd).is_some() {
+            Ok(())
+        } else {
+            Err(MIPS_EBADF)
+        }
+    }
+
+    /// Read up to `count` bytes from `fd`. An empty result means the end of the file.
+    pub fn read(
+        &mut self,
+        fd: u32,
+        count: usize,
+        input_stream: &[Vec<u8>],
+        input_stream_ptr: &mut usize,
+    ) -> Result<Vec<u8>, u32> {
+        if fd == FD_STDIN {
+            while *input_stream_ptr < input_stream.len()
+                && self.stdin_offset == input_stream[*input_stream_ptr].len()
+            {
+                *input_stream_ptr += 1;
+                self.stdin_offset = 0;
+            }
+            let entry = match input_stream.get(*input_stream_ptr) {
+                Some(entry) => entry,
+                None => return Ok(Vec::new()),
+            };
+            let end = entry.len().min(self.stdin_offset + count);
+            let bytes = entry[self.stdin_offset..end].to_vec();
+            self.stdin_offset = end;
+            return Ok(bytes);
+        }
+
+        let file = self.open_files.get_mut(&fd).ok_or(MIPS_EBADF)?;
+        let data = &self.files[&file.path];
+        let end = data.len().min(file.offset + count);
+        let bytes = data[file.offset.min(end)..end].to_vec();
+        file.offset = end;
+        Ok(bytes)
+    }
+}
@@ -2,12 +2,48 @@
 #[cfg(test)]
 mod tests {
     use std::{
+        cell::RefCell,
         fs,
+        io::{Read, Write},
+        net::{TcpListener, TcpStream},
         path::{Path, PathBuf},
+        rc::Rc,
     };
 
-    use crate::state::{InstrumentedState, State};
-    use crate::utils::{get_block_path, load_elf_with_patch, split_prog_into_segs, SEGMENT_STEPS};
+    use base64::Engine;
+
+    use crate::blake::{BLAKE2B_IV, BLAKE3_IV};
+    use crate::cannon::{CannonPage, CannonState};
+    use crate::chacha::CHACHA_CONSTANTS;
+    use crate::cp0::{CP0_CAUSE, CP0_STATUS, EXCEPTION_VECTOR, STATUS_IE};
+    use crate::cycle_tracker::SectionCycles;
+    use crate::error::ExecutionError;
+    use crate::executor::{Executor, ExecutorOpts};
+    use crate::journal::{Journal, JournalHash};
+    use crate::loader::{ElfError, MemoryLayout, PreprocessedProgram};
+    use crate::memory::INIT_SP;
+    use crate::page::PAGE_SIZE;
+    use crate::profiler::{Profiler, Symbol, Symbols};
+    use crate::report::{opcode_key, opcode_name, ExecutionReport};
+    use crate::sha256;
+    use crate::state::{
+        InstrumentedState, State, EXC_BREAKPOINT, EXC_COPROCESSOR_UNUSABLE, EXC_TRAP,
+        FD_CYCLE_TRACKER, FD_HINT, FD_STDERR,
+    };
+    use crate::stdin::Stdin;
+    use crate::syscall::{
+        REG_A0, REG_A1, REG_A2, REG_SP, REG_V0, SYS_BLAKE2B_COMPRESS, SYS_BLAKE3_COMPRESS,
+        SYS_BLS12381_FP_ADD, SYS_BLS12381_FP_MUL, SYS_BLS12381_FP_SUB, SYS_BN254_ADD,
+        SYS_BN254_DOUBLE, SYS_CHACHA20_BLOCK, SYS_CYCLE_COUNT, SYS_ED_ADD, SYS_ED_DECOMPRESS,
+        SYS_EXIT_GROUP, SYS_HINT_LEN, SYS_KECCAK256, SYS_MODEXP, SYS_SECP256K1_ADD,
+        SYS_SECP256K1_DOUBLE, SYS_SECP256R1_ADD, SYS_SECP256R1_DOUBLE, SYS_SHA256_COMPRESS,
+        SYS_SHA256_EXTEND, SYS_UINT256_MULMOD, SYS_WRITE,
+    };
+    use crate::trace::{ExecutionTrace, MemoryOp, TraceStep};
+    use crate::utils::{
+        compute_image_id, get_block_path, load_elf_with_patch, split_prog_into_segs,
+        try_load_elf_from_bytes, SEGMENT_STEPS,
+    };
 
     const END_ADDR: u32 = 0xa7ef00d0;
     const OUTPUT: &str = "/tmp/segment";
@@ -63,6 +99,744 @@ mod tests {
         }
     }
 
+    /// A writer the test keeps a handle on, to check what the program printed.
+    #[derive(Clone, Default)]
+    struct SharedBuf(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_boot_go_hello() {
+        let state = load_elf_with_patch("test-vectors/hello", vec!["aab", "ccd"]);
+
+        let mut instrumented_state = InstrumentedState::new(state, String::from(""));
+        let stdout = SharedBuf::default();
+        instrumented_state.set_stdout(Box::new(stdout.clone()));
+
+        for _ in 0..40000000 {
+            if instrumented_state.state.exited {
+                break;
+            }
+            instrumented_state.step();
+        }
+
+        assert!(instrumented_state.state.exited);
+        assert_eq!(instrumented_state.state.exit_code, 0);
+        let output = String::from_utf8(stdout.0.borrow().clone()).unwrap();
+        assert!(output.starts_with("hello world\n"), "{output}");
+    }
+
+    #[test]
+    fn test_exception_enters_handler() {
+        let mut state = State::new();
+        let program = [
+            0x34080001, // ori $t0, $zero, 1
+            0x40886000, // mtc0 $t0, $12 (Status.IE)
+            0x0000000d, // break
+            0x34090007, // ori $t1, $zero, 7
+        ];
+        let handler = [
+            0x401a7000, // mfc0 $k0, $14 (EPC)
+            0x275a0004, // addiu $k0, $k0, 4
+            0x409a7000, // mtc0 $k0, $14
+            0x42000018, // eret
+        ];
+        for (i, insn) in program.into_iter().enumerate() {
+            state.memory.set_memory(4 * i as u32, insn);
+        }
+        for (i, insn) in handler.into_iter().enumerate() {
+            state
+                .memory
+                .set_memory(EXCEPTION_VECTOR + 4 * i as u32, insn);
+        }
+
+        let mut instrumented_state = InstrumentedState::new(state, String::from(""));
+        for _ in 0..8 {
+            instrumented_state.step();
+        }
+
+        let state = &instrumented_state.state;
+        assert!(!state.exited);
+        assert_eq!(state.registers[9], 7);
+        assert_eq!(state.cp0.regs[CP0_CAUSE], (EXC_BREAKPOINT as u32) << 2);
+        assert_eq!(state.cp0.regs[CP0_STATUS], STATUS_IE);
+        assert_eq!(state.pc, 16);
+    }
+
+    #[test]
+    fn test_fpu_store_traps() {
+        let mut state = State::new();
+        let program = [
+            0x34080001, // ori $t0, $zero, 1
+            0x40886000, // mtc0 $t0, $12 (Status.IE)
+            0xf4000100, // sdc1 $f0, 0x100($zero)
+        ];
+        for (i, insn) in program.into_iter().enumerate() {
+            state.memory.set_memory(4 * i as u32, insn);
+        }
+        state.memory.set_memory(0x100, 0xdeadbeef);
+
+        let mut instrumented_state = InstrumentedState::new(state, String::from(""));
+        for _ in 0..3 {
+            instrumented_state.step();
+        }
+
+        let state = &mut instrumented_state.state;
+        assert_eq!(
+            (state.cp0.regs[CP0_CAUSE] >> 2) & 0x1f,
+            EXC_COPROCESSOR_UNUSABLE as u32
+        );
+        assert_eq!(state.pc, EXCEPTION_VECTOR);
+        assert_eq!(state.memory.get_memory(0x100), 0xdeadbeef);
+    }
+
+    #[test]
+    fn test_keccak256_syscall() {
+        let mut state = State::new();
+        state.memory.set_memory(0, 0x0000000c); // syscall
+        state.memory.set_memory(0x1000, 0x61626300); // "abc"
+        state.registers[REG_V0] = SYS_KECCAK256;
+        state.registers[REG_A0] = 0x1000;
+        state.registers[REG_A1] = 3;
+        state.registers[REG_A2] = 0x2000;
+
+        let mut instrumented_state = InstrumentedState::new(state, String::from(""));
+        instrumented_state.step();
+
+        let digest = (0..32)
+            .map(|i| instrumented_state.state.memory.byte(0x2000 + i))
+            .collect::<Vec<u8>>();
+        assert_eq!(
+            hex::encode(digest),
+            "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+        );
+        assert_eq!(instrumented_state.state.registers[REG_V0], 0);
+    }
+
+    #[test]
+    fn test_sha256_syscalls() {
+        let mut state = State::new();
+        state.memory.set_memory(0, 0x0000000c); // syscall
+        state.memory.set_memory(4, 0x0000000c); // syscall
+
+        // the padded block of "abc"
+        state.memory.set_memory(0x1000, 0x61626380);
+        state.memory.set_memory(0x1000 + 15 * 4, 0x18);
+        let iv = [
+            0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+            0x5be0cd19,
+        ];
+        for (i, word) in iv.into_iter().enumerate() {
+            state.memory.set_memory(0x2000 + 4 * i as u32, word);
+        }
+        state.registers[REG_V0] = SYS_SHA256_EXTEND;
+        state.registers[REG_A0] = 0x1000;
+
+        let mut instrumented_state = InstrumentedState::new(state, String::from(""));
+        instrumented_state.step();
+        assert_eq!(
+            instrumented_state.state.memory.get_memory(0x1000 + 63 * 4),
+            0x12b1edeb
+        );
+
+        instrumented_state.state.registers[REG_V0] = SYS_SHA256_COMPRESS;
+        instrumented_state.state.registers[REG_A1] = 0x2000;
+        instrumented_state.step();
+
+        let digest = (0..32)
+            .map(|i| instrumented_state.state.memory.byte(0x2000 + i))
+            .collect::<Vec<u8>>();
+        assert_eq!(
+            hex::encode(digest),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    /// A point as the guest lays it out: the little-endian words of x, then of y.
+    fn point(x: &str, y: &str) -> [u32; 16] {
+        let mut words = [0u32; 16];
+        for (i, c) in [x, y].iter().enumerate() {
+            let bytes = hex::decode(c).unwrap();
+            for (j, chunk) in bytes.rchunks(4).enumerate() {
+                words[8 * i + j] = u32::from_be_bytes(chunk.try_into().unwrap());
+            }
+        }
+        words
+    }
+
+    #[test]
+    fn test_secp256k1_syscalls() {
+        let g = point(
+            "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            "483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8",
+        );
+        let g2 = point(
+            "c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5",
+            "1ae168fea63dc339a3c58419466ceaeef7f632653266d0e1236431a950cfe52a",
+        );
+        let g3 = point(
+            "f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9",
+            "388f7b0f632de8140fe337e62a37f3566500a99934c2231b6cb9fd7584b8e672",
+        );
+
+        let mut state = State::new();
+        state.memory.set_memory(0, 0x0000000c); // syscall
+        state.memory.set_memory(4, 0x0000000c); // syscall
+        for (i, (&p, &q)) in g.iter().zip(&g).enumerate() {
+            state.memory.set_memory(0x1000 + 4 * i as u32, p);
+            state.memory.set_memory(0x2000 + 4 * i as u32, q);
+        }
+        state.registers[REG_V0] = SYS_SECP256K1_DOUBLE;
+        state.registers[REG_A0] = 0x1000;
+
+        let mut instrumented_state = InstrumentedState::new(state, String::from(""));
+        instrumented_state.step();
+        let read_point = |state: &mut State, addr: u32| -> [u32; 16] {
+            core::array::from_fn(|i| state.memory.get_memory(addr + 4 * i as u32))
+        };
+        assert_eq!(read_point(&mut instrumented_state.state, 0x1000), g2);
+
+        instrumented_state.state.registers[REG_V0] = SYS_SECP256K1_ADD;
+        instrumented_state.state.registers[REG_A1] = 0x2000;
+        instrumented_state.step();
+        assert_eq!(read_point(&mut instrumented_state.state, 0x1000), g3);
+    }
+
+    #[test]
+    fn test_secp256r1_syscalls() {
+        let g = point(
+            "6b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296",
+            "4fe342e2fe1a7f9b8ee7eb4a7c0f9e162bce33576b315ececbb6406837bf51f5",
+        );
+        let g2 = point(
+            "7cf27b188d034f7e8a52380304b51ac3c08969e277f21b35a60b48fc47669978",
+            "07775510db8ed040293d9ac69f7430dbba7dade63ce982299e04b79d227873d1",
+        );
+        let g3 = point(
+            "5ecbe4d1a6330a44c8f7ef951d4bf165e6c6b721efada985fb41661bc6e7fd6c",
+            "8734640c4998ff7e374b06ce1a64a2ecd82ab036384fb83d9a79b127a27d5032",
+        );
+
+        let mut state = State::new();
+        state.memory.set_memory(0, 0x0000000c); // syscall
+        state.memory.set_memory(4, 0x0000000c); // syscall
+        for (i, &word) in g.iter().enumerate() {
+            state.memory.set_memory(0x1000 + 4 * i as u32, word);
+            state.memory.set_memory(0x2000 + 4 * i as u32, word);
+        }
+        state.registers[REG_V0] = SYS_SECP256R1_DOUBLE;
+        state.registers[REG_A0] = 0x1000;
+
+        let mut instrumented_state = InstrumentedState::new(state, String::from(""));
+        instrumented_state.step();
+        let read_point = |state: &mut State, addr: u32| -> [u32; 16] {
+            core::array::from_fn(|i| state.memory.get_memory(addr + 4 * i as u32))
+        };
+        assert_eq!(read_point(&mut instrumented_state.state, 0x1000), g2);
+
+        instrumented_state.state.registers[REG_V0] = SYS_SECP256R1_ADD;
+        instrumented_state.state.registers[REG_A1] = 0x2000;
+        instrumented_state.step();
+        assert_eq!(read_point(&mut instrumented_state.state, 0x1000), g3);
+    }
+
+    #[test]
+    fn test_bn254_syscalls() {
+        // the generator (1, 2), its double and its triple
+        let mut g = [0u32; 16];
+        g[0] = 1;
+        g[8] = 2;
+        let g2 = [
+            0x6d87cfd3, 0xd3c208c1, 0x16871ca8, 0xd97816a9, 0x68181585, 0x9b85045b, 0x2e131a02,
+            0x030644e7, 0x5a18a2c4, 0xff3ebf7a, 0xe3538fc7, 0x68a6a449, 0xb2ae9c0a, 0xe7845f96,
+            0x0e0a7c92, 0x15ed738c,
+        ];
+        let g3 = [
+            0x1915abf0, 0xf2d35596, 0x15b8e679, 0x9315d847, 0xb1b6bd15, 0xf40232bc, 0xc56bea3f,
+            0x0769bf9a, 0xd9fe2261, 0xcdf1ff3d, 0x0b9c5b57, 0x319e63b4, 0x8d086475, 0x554fdb7c,
+            0xe0489429, 0x2ab799be,
+        ];
+
+        let mut state = State::new();
+        state.memory.set_memory(0, 0x0000000c); // syscall
+        state.memory.set_memory(4, 0x0000000c); // syscall
+        for (i, &word) in g.iter().enumerate() {
+            state.memory.set_memory(0x1000 + 4 * i as u32, word);
+            state.memory.set_memory(0x2000 + 4 * i as u32, word);
+        }
+        state.registers[REG_V0] = SYS_BN254_DOUBLE;
+        state.registers[REG_A0] = 0x1000;
+
+        let mut instrumented_state = InstrumentedState::new(state, String::from(""));
+        instrumented_state.step();
+        let read_point = |state: &mut State, addr: u32| -> [u32; 16] {
+            core::array::from_fn(|i| state.memory.get_memory(addr + 4 * i as u32))
+        };
+        assert_eq!(read_point(&mut instrumented_state.state, 0x1000), g2);
+
+        instrumented_state.state.registers[REG_V0] = SYS_BN254_ADD;
+        instrumented_state.state.registers[REG_A1] = 0x2000;
+        instrumented_state.step();
+        assert_eq!(read_point(&mut instrumented_state.state, 0x1000), g3);
+    }
+
+    #[test]
+    fn test_bls12381_fp_syscalls() {
+        // p - 1 and p - 2, as little-endian words
+        let minus_one = [
+            0xffffaaaa, 0xb9feffff, 0xb153ffff, 0x1eabfffe, 0xf6b0f624, 0x6730d2a0, 0xf38512bf,
+            0x64774b84, 0x434bacd7, 0x4b1ba7b6, 0x397fe69a, 0x1a0111ea,
+        ];
+        let minus_two = [
+            0xffffaaa9, 0xb9feffff, 0xb153ffff, 0x1eabfffe, 0xf6b0f624, 0x6730d2a0, 0xf38512bf,
+            0x64774b84, 0x434bacd7, 0x4b1ba7b6, 0x397fe69a, 0x1a0111ea,
+        ];
+        let mut one = [0u32; 12];
+        one[0] = 1;
+
+        let mut state = State::new();
+        for i in 0..3 {
+            state.memory.set_memory(4 * i, 0x0000000c); // syscall
+        }
+        for (i, &word) in minus_one.iter().enumerate() {
+            state.memory.set_memory(0x1000 + 4 * i as u32, word);
+        }
+        state.memory.set_memory(0x2000, 2);
+        state.registers[REG_V0] = SYS_BLS12381_FP_ADD;
+        state.registers[REG_A0] = 0x1000;
+        state.registers[REG_A1] = 0x2000;
+
+        let mut instrumented_state = InstrumentedState::new(state, String::from(""));
+        let read_element = |state: &mut State| -> [u32; 12] {
+            core::array::from_fn(|i| state.memory.get_memory(0x1000 + 4 * i as u32))
+        };
+        instrumented_state.step();
+        assert_eq!(read_element(&mut instrumented_state.state), one);
+
+        instrumented_state.state.registers[REG_V0] = SYS_BLS12381_FP_SUB;
+        instrumented_state.step();
+        assert_eq!(read_element(&mut instrumented_state.state), minus_one);
+
+        instrumented_state.state.registers[REG_V0] = SYS_BLS12381_FP_MUL;
+        instrumented_state.step();
+        assert_eq!(read_element(&mut instrumented_state.state), minus_two);
+    }
+
+    #[test]
+    fn test_uint256_mulmod_syscall() {
+        let mut state = State::new();
+        state.memory.set_memory(0, 0x0000000c); // syscall
+        state.memory.set_memory(4, 0x0000000c); // syscall
+
+        // x = y = 2^256 - 1, and the modulus 2^255 - 19 right after y
+        for i in 0..8 {
+            state.memory.set_memory(0x1000 + 4 * i, u32::MAX);
+            state.memory.set_memory(0x2000 + 4 * i, u32::MAX);
+            state.memory.set_memory(0x2020 + 4 * i, u32::MAX >> (i / 7));
+        }
+        state.memory.set_memory(0x2020, 0xffffffed);
+        state.registers[REG_V0] = SYS_UINT256_MULMOD;
+        state.registers[REG_A0] = 0x1000;
+        state.registers[REG_A1] = 0x2000;
+
+        let mut instrumented_state = InstrumentedState::new(state, String::from(""));
+        let read_uint = |state: &mut State, addr: u32| -> [u32; 8] {
+            core::array::from_fn(|i| state.memory.get_memory(addr + 4 * i as u32))
+        };
+        instrumented_state.step();
+        // 37^2, as 2^256 - 1 = 37 mod 2^255 - 19
+        assert_eq!(
+            read_uint(&mut instrumented_state.state, 0x1000),
+            [0x559, 0, 0, 0, 0, 0, 0, 0]
+        );
+
+        // a zero modulus traps, without writing x
+        instrumented_state.state.registers[REG_V0] = SYS_UINT256_MULMOD;
+        instrumented_state.state.registers[REG_A1] = 0x3000;
+        instrumented_state.step();
+        assert!(instrumented_state.state.exited);
+        assert_eq!(instrumented_state.state.exit_code, EXC_TRAP);
+        assert_eq!(instrumented_state.state.pc, 4);
+        assert_eq!(
+            read_uint(&mut instrumented_state.state, 0x1000),
+            [0x559, 0, 0, 0, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn test_modexp_syscall() {
+        let mut state = State::new();
+        state.memory.set_memory(0, 0x0000000c); // syscall
+        state.memory.set_memory(4, 0x0000000c); // syscall
+
+        // 0x0123456789abcdef^65537 modulo 2^64 - 59, the lengths then the modulus and the
+        // exponent after them
+        state.memory.set_memory(0x1000, 0x89abcdef);
+        state.memory.set_memory(0x1004, 0x01234567);
+        state.memory.set_memory(0x2000, 2);
+        state.memory.set_memory(0x2004, 1);
+        state.memory.set_memory(0x2008, 0xffffffc5);
+        state.memory.set_memory(0x200c, 0xffffffff);
+        state.memory.set_memory(0x2010, 65537);
+        state.registers[REG_V0] = SYS_MODEXP;
+        state.registers[REG_A0] = 0x1000;
+        state.registers[REG_A1] = 0x2000;
+
+        let mut instrumented_state = InstrumentedState::new(state, String::from(""));
+        instrumented_state.step();
+        let memory = &mut instrumented_state.state.memory;
+        assert_eq!(
+            [memory.get_memory(0x1000), memory.get_memory(0x1004)],
+            [0x901a4710, 0xd3097250]
+        );
+
+        // an empty exponent traps
+        instrumented_state.state.memory.set_memory(0x2004, 0);
+        instrumented_state.state.registers[REG_V0] = SYS_MODEXP;
+        instrumented_state.step();
+        assert!(instrumented_state.state.exited);
+        assert_eq!(instrumented_state.state.exit_code, EXC_TRAP);
+    }
+
+    #[test]
+    fn test_blake_compress_syscalls() {
+        let mut state = State::new();
+        state.memory.set_memory(0, 0x0000000c); // syscall
+        state.memory.set_memory(4, 0x0000000c); // syscall
+
+        // the single block of BLAKE2b-512("abc"): the parameter block folded into the IV, and
+        // 3 bytes in the last block, in 12 rounds
+        for (i, word) in BLAKE2B_IV.iter().enumerate() {
+            let word = if i == 0 { word ^ 0x01010040 } else { *word };
+            state
+                .memory
+                .set_memory(0x1000 + 8 * i as u32, (word >> 32) as u32);
+            state.memory.set_memory(0x1004 + 8 * i as u32, word as u32);
+        }
+        state.memory.set_memory(0x2004, 0x00636261);
+        state.memory.set_memory(0x2084, 3);
+        state.memory.set_memory(0x2090, 1);
+        state.memory.set_memory(0x2094, 12);
+        state.registers[REG_V0] = SYS_BLAKE2B_COMPRESS;
+        state.registers[REG_A0] = 0x1000;
+        state.registers[REG_A1] = 0x2000;
+
+        let mut instrumented_state = InstrumentedState::new(state, String::from(""));
+        instrumented_state.step();
+        let h: [u64; 8] = core::array::from_fn(|i| {
+            let memory = &mut instrumented_state.state.memory;
+            let addr = 0x1000 + 8 * i as u32;
+            (memory.get_memory(addr) as u64) << 32 | memory.get_memory(addr + 4) as u64
+        });
+        assert_eq!(h[0], 0x0d4d1c983fa580ba);
+        assert_eq!(h[7], 0x239900d4ed8623b9);
+
+        // the single chunk of BLAKE3("abc"), flagged as the start and end of the chunk and the
+        // root
+        let state = &mut instrumented_state.state;
+        for (i, word) in BLAKE3_IV.iter().enumerate() {
+            state.memory.set_memory(0x3000 + 4 * i as u32, *word);
+        }
+        state.memory.set_memory(0x4000, 0x00636261);
+        state.memory.set_memory(0x4048, 3);
+        state.memory.set_memory(0x404c, 11);
+        state.registers[REG_V0] = SYS_BLAKE3_COMPRESS;
+        state.registers[REG_A0] = 0x3000;
+        state.registers[REG_A1] = 0x4000;
+        instrumented_state.step();
+        let state = &mut instrumented_state.state;
+        assert_eq!(state.memory.get_memory(0x3000), 0xacb33764);
+        assert_eq!(state.memory.get_memory(0x301c), 0x859dbdd5);
+    }
+
+    #[test]
+    fn test_chacha20_block_syscall() {
+        let mut state = State::new();
+        state.memory.set_memory(0, 0x0000000c); // syscall
+
+        // the block function test vector of RFC 8439, section 2.3.2: the key is the bytes 0 to
+        // 31, the block counter 1
+        let key = (0..8).map(|i| 0x03020100 + 0x04040404 * i);
+        let words = CHACHA_CONSTANTS
+            .into_iter()
+            .chain(key)
+            .chain([1, 0x09000000, 0x4a000000, 0]);
+        for (i, word) in words.enumerate() {
+            state.memory.set_memory(0x1000 + 4 * i as u32, word);
+        }
+        state.registers[REG_V0] = SYS_CHACHA20_BLOCK;
+        state.registers[REG_A0] = 0x1000;
+
+        let mut instrumented_state = InstrumentedState::new(state, String::from(""));
+        instrumented_state.step();
+        let state = &mut instrumented_state.state;
+        assert_eq!(state.memory.get_memory(0x1000), 0xe4e7f110);
+        assert_eq!(state.memory.get_memory(0x103c), 0x4e3c50a2);
+    }
+
+    #[test]
+    fn test_cycle_tracker() {
+        let mut state = State::new();
+        for pc in [0, 4, 8] {
+            state.memory.set_memory(pc, 0x0000000c); // syscall
+        }
+        state
+            .memory
+            .set_memory(0x1000, u32::from_be_bytes([0, b'f', b'i', b'b']));
+        state
+            .memory
+            .set_memory(0x2000, u32::from_be_bytes([1, b'f', b'i', b'b']));
+
+        let mut instrumented_state = InstrumentedState::new(state, String::from(""));
+        let mark = |instrumented_state: &mut InstrumentedState, addr| {
+            let registers = &mut instrumented_state.state.registers;
+            registers[REG_V0] = SYS_WRITE;
+            registers[REG_A0] = FD_CYCLE_TRACKER;
+            registers[REG_A1] = addr;
+            registers[REG_A2] = 4;
+            instrumented_state.step();
+        };
+        mark(&mut instrumented_state, 0x1000);
+        instrumented_state.state.registers[REG_V0] = SYS_CYCLE_COUNT;
+        instrumented_state.step();
+        assert_eq!(instrumented_state.state.registers[REG_V0], 1);
+        mark(&mut instrumented_state, 0x2000);
+
+        let report = instrumented_state.state.cycle_tracker.report();
+        assert_eq!(report.len(), 1);
+        let SectionCycles { count, steps, .. } = report["fib"];
+        assert_eq!((count, steps), (1, 2));
+    }
+
+    #[test]
+    fn test_execution_report() {
+        let mut state = State::new();
+        state.memory.set_memory(0, 0x0000000c); // syscall
+        state.registers[REG_V0] = SYS_EXIT_GROUP;
+        state.registers[REG_A0] = 3;
+        state.registers[REG_SP] = INIT_SP - 0x100;
+
+        let mut instrumented_state = InstrumentedState::new(state, String::from(""));
+        instrumented_state.step();
+        let report = instrumented_state.state.report();
+        assert_eq!(report.exit_code, 3);
+        assert_eq!(report.total_steps, 1);
+        assert_eq!(report.heap_pointer, None);
+        assert_eq!(report.stack_size(), 0x100);
+    }
+
+    #[test]
+    fn test_executor() {
+        let mut state = State::new();
+        let program = [
+            0x3c020001, // lui $v0, 0x1
+            0x34420109, // ori $v0, $v0, 0x109 (SYS_KECCAK256)
+            0x34041000, // ori $a0, $zero, 0x1000
+            0x34050003, // ori $a1, $zero, 3
+            0x34062000, // ori $a2, $zero, 0x2000
+            0x0000000c, // syscall
+            0x340200f5, // ori $v0, $zero, 0xf5 (SYS_CYCLE_COUNT)
+            0x0000000c, // syscall
+            0x34021096, // ori $v0, $zero, 0x1096 (SYS_EXIT_GROUP)
+            0x0000000c, // syscall
+        ];
+        for (i, insn) in program.into_iter().enumerate() {
+            state.memory.set_memory(4 * i as u32, insn);
+        }
+        state.memory.set_memory(0x1000, 0x61626300); // "abc"
+
+        let mut executor = Executor::new(state, SEGMENT_STEPS);
+        let report = executor.run().unwrap();
+        assert_eq!(report.total_steps, 10);
+        assert_eq!(report.syscall_counts.len(), 3);
+        assert_eq!(report.syscall_counts[&SYS_CYCLE_COUNT], 1);
+        assert_eq!(
+            report.precompile_counts().into_iter().collect::<Vec<_>>(),
+            vec![(SYS_KECCAK256, 1)]
+        );
+        assert!(report.touched_memory > 0);
+        assert_eq!(report.opcode_counts[&opcode_key(0x0000000c)], 3);
+        assert_eq!(
+            report.opcode_histogram(),
+            vec![
+                ("ori".to_string(), 6),
+                ("syscall".to_string(), 3),
+                ("lui".to_string(), 1)
+            ]
+        );
+        assert_eq!(opcode_name(opcode_key(0x00851021)), "addu");
+        assert_eq!(opcode_name(opcode_key(0x04110000)), "bgezal");
+    }
+
+    #[test]
+    fn test_image_id() {
+        let program = |insn| {
+            let mut state = State::new();
+            state.memory.set_memory(0, insn);
+            state
+        };
+        let image_id = compute_image_id(program(0x0000000c));
+        assert_eq!(image_id, compute_image_id(program(0x0000000c)));
+        assert_ne!(image_id, compute_image_id(program(0x34020001)));
+
+        let mut state = program(0x0000000c);
+        state.add_input_stream(&vec![1u8]);
+        assert_eq!(image_id, compute_image_id(state));
+
+        // The random seed is part of the initial memory.
+        let mut state = program(0x0000000c);
+        state.set_random_seed([1; 32]);
+        assert_ne!(image_id, compute_image_id(state));
+    }
+
+    #[test]
+    fn test_hint_handler() {
+        let mut state = State::new();
+        for pc in [0, 4] {
+            state.memory.set_memory(pc, 0x0000000c); // syscall
+        }
+        state.memory.set_memory(0x1000, 0x01020304);
+        state.set_hint_handler(|query| vec![query.iter().rev().copied().collect()]);
+
+        let mut instrumented_state = InstrumentedState::new(state, String::from(""));
+        let registers = &mut instrumented_state.state.registers;
+        registers[REG_V0] = SYS_WRITE;
+        registers[REG_A0] = FD_HINT;
+        registers[REG_A1] = 0x1000;
+        registers[REG_A2] = 3;
+        instrumented_state.step();
+        instrumented_state.state.registers[REG_V0] = SYS_HINT_LEN;
+        instrumented_state.step();
+
+        let state = &instrumented_state.state;
+        assert_eq!(state.registers[REG_V0], 3);
+        assert_eq!(state.input_stream, [vec![3, 2, 1]]);
+    }
+
+    #[test]
+    fn test_ed25519_syscalls() {
+        // the base point, compressed as in RFC 8032, after 32 bytes for x
+        let compressed =
+            hex::decode("5866666666666666666666666666666666666666666666666666666666666666")
+                .unwrap();
+        let mut state = State::new();
+        state.memory.set_memory(0, 0x0000000c); // syscall
+        state.memory.set_memory(4, 0x0000000c); // syscall
+        for (i, chunk) in compressed.chunks(4).enumerate() {
+            let word = u32::from_be_bytes(chunk.try_into().unwrap());
+            state.memory.set_memory(0x1020 + 4 * i as u32, word);
+        }
+        state.registers[REG_V0] = SYS_ED_DECOMPRESS;
+        state.registers[REG_A0] = 0x1000;
+
+        let mut instrumented_state = InstrumentedState::new(state, String::from(""));
+        instrumented_state.step();
+        let x = (0..32)
+            .map(|i| instrumented_state.state.memory.byte(0x1000 + i))
+            .rev()
+            .collect::<Vec<u8>>();
+        assert_eq!(
+            hex::encode(x),
+            "216936d3cd6e53fec0a4e231fdd6dc5c692cc7609525a7b2c9562d608f25d51a"
+        );
+
+        // the base point as words, doubled by adding it to itself
+        let g = [
+            0x8f25d51a, 0xc9562d60, 0x9525a7b2, 0x692cc760, 0xfdd6dc5c, 0xc0a4e231, 0xcd6e53fe,
+            0x216936d3, 0x66666658, 0x66666666, 0x66666666, 0x66666666, 0x66666666, 0x66666666,
+            0x66666666, 0x66666666,
+        ];
+        let g2 = [
+            0x2843ce0e, 0x83c5a14e, 0x15d7a45f, 0x080d8e45, 0x1833e7ac, 0x3d043b7d, 0x9f5a046c,
+            0x36ab384c, 0x6af8a3c9, 0x0e5f46ae, 0x64385156, 0x97390f51, 0xc9a21f56, 0x1da25ee8,
+            0x092329c2, 0x2260cdf3,
+        ];
+        for (i, &word) in g.iter().enumerate() {
+            instrumented_state
+                .state
+                .memory
+                .set_memory(0x2000 + 4 * i as u32, word);
+            instrumented_state
+                .state
+                .memory
+                .set_memory(0x3000 + 4 * i as u32, word);
+        }
+        instrumented_state.state.registers[REG_V0] = SYS_ED_ADD;
+        instrumented_state.state.registers[REG_A0] = 0x2000;
+        instrumented_state.state.registers[REG_A1] = 0x3000;
+        instrumented_state.step();
+        let sum: [u32; 16] = core::array::from_fn(|i| {
+            instrumented_state
+                .state
+                .memory
+                .get_memory(0x2000 + 4 * i as u32)
+        });
+        assert_eq!(sum, g2);
+    }
+
+    #[test]
+    fn test_patch_stack_with_env() {
+        let mut state = State::new();
+        state.patch_stack_with_env(vec!["prog"], &[("MODE", "fast")]);
+
+        let c_string = |state: &mut State, addr: u32| {
+            let bytes = (addr..)
+                .map(|a| state.memory.byte(a))
+                .take_while(|b| *b != 0)
+                .collect::<Vec<u8>>();
+            String::from_utf8(bytes).unwrap()
+        };
+        assert_eq!(state.memory.get_memory(INIT_SP), 1); // argc
+        let argv0 = state.memory.get_memory(INIT_SP + 4);
+        assert_eq!(c_string(&mut state, argv0), "prog");
+        assert_eq!(state.memory.get_memory(INIT_SP + 8), 0);
+        let envp0 = state.memory.get_memory(INIT_SP + 12);
+        assert_eq!(c_string(&mut state, envp0), "MODE=fast");
+        assert_eq!(state.memory.get_memory(INIT_SP + 16), 0);
+    }
+
+    #[test]
+    fn test_journal() {
+        let mut bytes = bincode::serialize(&7u32).unwrap();
+        bytes.extend(bincode::serialize(&String::from("fib")).unwrap());
+        let mut journal = Journal::new(bytes.clone());
+
+        assert_eq!(journal.decode::<u32>().unwrap(), 7);
+        assert_eq!(journal.decode::<String>().unwrap(), "fib");
+        assert!(journal.is_empty());
+        assert!(journal.decode::<u32>().is_err());
+        assert_eq!(journal.digest(JournalHash::Sha256), sha256::digest(&bytes));
+    }
+
+    #[test]
+    fn test_stdin() {
+        let mut stdin = Stdin::new();
+        stdin
+            .write(&(7u32, String::from("zkm")))
+            .write_slice(&[1, 2, 3])
+            .write(&vec![4u8, 5]);
+
+        let mut state = State::new();
+        state.add_input_stream(&vec![4u8, 5]);
+        state.add_stdin(&stdin);
+        assert_eq!(state.input_stream.len(), 4);
+        assert_eq!(state.input_stream[0], state.input_stream[3]);
+        assert_eq!(state.input_stream[2], [1, 2, 3]);
+
+        assert_eq!(stdin.read::<(u32, String)>(), (7, String::from("zkm")));
+        assert_eq!(stdin.read_slice(), [1, 2, 3]);
+        assert_eq!(stdin.read::<Vec<u8>>(), [4, 5]);
+    }
+
     #[test]
     fn test_execute_rust_fib() {
         let state = load_elf_with_patch("test-vectors/rust_fib", vec![]);
@@ -93,4 +867,362 @@ mod tests {
         let state = load_elf_with_patch("test-vectors/hello", vec![]);
         let _ = split_prog_into_segs(state, OUTPUT, "", SEGMENT_STEPS);
     }
+
+    #[test]
+    fn test_load_elf_errors() {
+        let elf = fs::read("test-vectors/hello").unwrap();
+        let state = try_load_elf_from_bytes(&elf, vec![], &[]).unwrap();
+        assert_eq!(state.pc, 0x8dc70);
+
+        assert!(matches!(
+            try_load_elf_from_bytes(&elf[..16], vec![], &[]),
+            Err(ElfError::Parse(_))
+        ));
+
+        // x86
+        let mut x86 = elf.clone();
+        x86[18..20].copy_from_slice(&3u16.to_be_bytes());
+        assert_eq!(
+            try_load_elf_from_bytes(&x86, vec![], &[]).err(),
+            Some(ElfError::UnsupportedArch {
+                machine: 3,
+                is_32: true,
+                is_be: true
+            })
+        );
+
+        // A relocatable object.
+        let mut object = elf;
+        object[16..18].copy_from_slice(&1u16.to_be_bytes());
+        assert_eq!(
+            try_load_elf_from_bytes(&object, vec![], &[]).err(),
+            Some(ElfError::UnsupportedType(1))
+        );
+    }
+
+    #[test]
+    fn test_cannon_state() {
+        let state = load_elf_with_patch("test-vectors/hello", vec![]);
+        let cannon = state.to_cannon();
+        assert_eq!(cannon.pc, state.pc);
+
+        let json = serde_json::to_string(&cannon).unwrap();
+        assert!(json.contains("\"nextPC\":"));
+        let cannon: CannonState = serde_json::from_str(&json).unwrap();
+        let resumed = State::from_cannon(&cannon).unwrap();
+        assert_eq!(resumed.to_cannon(), cannon);
+
+        // Pages of zeros are not part of the tree, and may be uncompressed.
+        let mut zeros = CannonState::default();
+        let empty_root = zeros.memory_root().unwrap();
+        zeros.memory.push(CannonPage::encode(5, &[0; PAGE_SIZE]));
+        zeros.memory.push(CannonPage {
+            index: 0x7ffff,
+            data: base64::engine::general_purpose::STANDARD.encode([0; PAGE_SIZE]),
+        });
+        assert_eq!(zeros.memory_root().unwrap(), empty_root);
+
+        let mut page = [0; PAGE_SIZE];
+        page[7] = 1;
+        zeros.memory.push(CannonPage::encode(6, &page));
+        assert_ne!(zeros.memory_root().unwrap(), empty_root);
+        assert_eq!(zeros.memory[2].decode().unwrap(), page);
+    }
+
+    #[test]
+    fn test_load_elf_sparse_bss() {
+        // The data segment of hello is mapped at 0x150000, with 0x160c0 bytes in the file and
+        // 0x29a48 in memory.
+        let mut state = load_elf_with_patch("test-vectors/hello", vec![]);
+        let pages = state
+            .memory
+            .pages()
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        assert!(pages.contains(&0x166));
+        assert!(!pages.contains(&0x179));
+        assert_eq!(state.memory.get_memory(0x179a44), 0);
+    }
+
+    #[test]
+    fn test_preprocessed_program() {
+        let elf = fs::read("test-vectors/hello").unwrap();
+        let program = PreprocessedProgram::preprocess(&elf).unwrap();
+        let program = PreprocessedProgram::deserialize(&program.serialize()).unwrap();
+
+        let state = load_elf_with_patch("test-vectors/hello", vec!["aab"]);
+        let loaded = program.load(vec!["aab"], &[]);
+        assert_eq!(loaded.to_cannon(), state.to_cannon());
+        assert_eq!(loaded.brk(), state.brk());
+
+        let state = load_elf_with_patch("test-vectors/hello", vec![]);
+        assert_eq!(program.image_id, compute_image_id(state));
+        assert_eq!(
+            compute_image_id(program.load(vec![], &[])),
+            program.image_id
+        );
+    }
+
+    #[test]
+    fn test_memory_layout() {
+        let mut state = load_elf_with_patch("test-vectors/hello", vec![]);
+        let default_id = compute_image_id(load_elf_with_patch("test-vectors/hello", vec![]));
+        assert!(state.set_memory_layout(&MemoryLayout::default()).is_ok());
+        assert_eq!(compute_image_id(state), default_id);
+
+        let mut state = load_elf_with_patch("test-vectors/hello", vec![]);
+        let layout = MemoryLayout {
+            heap_start: 0x40000000,
+            stack_size: 0x100000,
+        };
+        assert!(state.set_memory_layout(&layout).is_ok());
+        assert_ne!(compute_image_id(state), default_id);
+
+        let invalid = [
+            MemoryLayout {
+                heap_start: 0x40000010,
+                ..layout
+            },
+            MemoryLayout {
+                heap_start: 0x10000000,
+                ..layout
+            },
+            MemoryLayout {
+                heap_start: 0x7ff00000,
+                ..layout
+            },
+            MemoryLayout {
+                stack_size: INIT_SP,
+                ..layout
+            },
+        ];
+        for layout in invalid {
+            assert!(matches!(
+                layout.validate(0x200000),
+                Err(ElfError::InvalidLayout(_))
+            ));
+        }
+
+        let mut report = ExecutionReport {
+            heap_end: 0x40100000,
+            min_stack_pointer: INIT_SP - 0x1000,
+            ..Default::default()
+        };
+        assert!(layout.check(&report).is_ok());
+        report.min_stack_pointer = INIT_SP - 0x200000;
+        assert!(layout.check(&report).is_err());
+    }
+
+    #[test]
+    fn test_profiler() {
+        let mut state = State::new();
+        let program = [
+            (0x0, 0x0c000040),   // jal 0x100
+            (0x4, 0x00000000),   // nop
+            (0x8, 0x34021096),   // ori $v0, $zero, 0x1096 (SYS_EXIT_GROUP)
+            (0xc, 0x0000000c),   // syscall
+            (0x100, 0x00000000), // nop
+            (0x104, 0x03e00008), // jr $ra
+            (0x108, 0x00000000), // nop
+        ];
+        for (addr, insn) in program {
+            state.memory.set_memory(addr, insn);
+        }
+        let symbols = Symbols::new(vec![
+            Symbol {
+                start: 0,
+                end: 0x10,
+                name: "main".to_string(),
+            },
+            Symbol {
+                start: 0x100,
+                end: 0,
+                name: "f".to_string(),
+            },
+        ]);
+        assert_eq!(symbols.lookup(0x10), None);
+        assert_eq!(symbols.lookup(0x200), Some(1));
+
+        let mut executor =
+            Executor::new(state, SEGMENT_STEPS).with_profiler(Profiler::new(symbols));
+        executor.run().unwrap();
+        let profiler = executor.take_profiler().unwrap();
+        let stacks = profiler.stacks();
+        assert_eq!(stacks.len(), 2);
+        assert_eq!(stacks[0].0, vec!["main"]);
+        assert_eq!(stacks[1], (vec!["main", "f"], 3));
+
+        let mut collapsed = Vec::new();
+        profiler.write_collapsed(&mut collapsed).unwrap();
+        assert!(String::from_utf8(collapsed)
+            .unwrap()
+            .ends_with("main;f 3\n"));
+    }
+
+    #[test]
+    fn test_gdb_stub() {
+        let mut state = State::new();
+        let program = [
+            0x34040007, // ori $a0, $zero, 7
+            0x34840001, // ori $a0, $a0, 1
+            0x34021096, // ori $v0, $zero, 0x1096 (SYS_EXIT_GROUP)
+            0x0000000c, // syscall
+        ];
+        for (i, insn) in program.into_iter().enumerate() {
+            state.memory.set_memory(4 * i as u32, insn);
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let debugger = std::thread::spawn(move || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            let mut request = |packet: &str| {
+                let sum = packet.bytes().fold(0u8, |sum, byte| sum.wrapping_add(byte));
+                write!(stream, "${}#{:02x}", packet, sum).unwrap();
+                // The acknowledgement, then the reply.
+                let mut reply = Vec::new();
+                let mut byte = [0u8];
+                while reply.len() < 3 || reply[reply.len() - 3] != b'#' {
+                    stream.read_exact(&mut byte).unwrap();
+                    reply.push(byte[0]);
+                }
+                let reply = String::from_utf8(reply).unwrap();
+                reply[2..reply.len() - 3].to_string()
+            };
+            assert_eq!(request("?"), "S05");
+            assert_eq!(request("Z0,8,4"), "OK");
+            assert_eq!(request("c"), "S05");
+            assert_eq!(request("p25"), "00000008");
+            assert_eq!(request("p4"), "00000007");
+            assert_eq!(request("s"), "S05");
+            assert_eq!(request("p25"), "0000000c");
+            assert_eq!(request("m0,4"), "34040007");
+            assert_eq!(request("P4=2a"), "OK");
+            assert!(request("qXfer:features:read:target.xml:0,40").starts_with('m'));
+            assert_eq!(request("c"), "W2a");
+        });
+
+        let mut executor = Executor::new(state, SEGMENT_STEPS);
+        let report = executor.debug(listener).unwrap();
+        debugger.join().unwrap();
+        assert_eq!(report.exit_code, 0x2a);
+    }
+
+    #[test]
+    fn test_execution_trace() {
+        let program = || {
+            let mut state = State::new();
+            let program = [
+                0x34040007, // ori $a0, $zero, 7
+                0xac040100, // sw $a0, 0x100($zero)
+                0x8c050100, // lw $a1, 0x100($zero)
+                0x34021096, // ori $v0, $zero, 0x1096 (SYS_EXIT_GROUP)
+                0x0000000c, // syscall
+            ];
+            for (i, insn) in program.into_iter().enumerate() {
+                state.memory.set_memory(4 * i as u32, insn);
+            }
+            state
+        };
+
+        let mut executor = Executor::new(program(), SEGMENT_STEPS).with_trace();
+        executor.run().unwrap();
+        let trace = executor.take_trace().unwrap();
+        assert_eq!(trace.steps.len(), 5);
+        assert_eq!(
+            trace.steps[0],
+            TraceStep {
+                pc: 0,
+                insn: 0x34040007,
+                registers: vec![(REG_A0 as u8, 7)],
+                memory: vec![],
+            }
+        );
+        let store = MemoryOp {
+            addr: 0x100,
+            value: 7,
+            is_write: true,
+        };
+        assert!(trace.steps[1].memory.contains(&store));
+        assert_eq!(trace.steps[2].registers, vec![(REG_A1 as u8, 7)]);
+
+        let path = std::env::temp_dir().join("zkm_test_execution_trace");
+        trace.save(&path).unwrap();
+        assert_eq!(ExecutionTrace::load(&path).unwrap(), trace);
+        fs::remove_file(&path).unwrap();
+
+        let mut executor = Executor::new(program(), SEGMENT_STEPS);
+        assert_eq!(executor.replay(&trace).unwrap().exit_code, 7);
+
+        let mut diverging = trace.clone();
+        diverging.steps[2].registers = vec![(REG_A1 as u8, 8)];
+        let mut executor = Executor::new(program(), SEGMENT_STEPS);
+        assert_eq!(executor.replay(&diverging).unwrap_err().step, 2);
+
+        let mut short = trace.clone();
+        short.steps.truncate(4);
+        let mut executor = Executor::new(program(), SEGMENT_STEPS);
+        let divergence = executor.replay(&short).unwrap_err();
+        assert_eq!((divergence.step, divergence.expected), (4, None));
+    }
+
+    #[test]
+    fn test_max_cycles() {
+        let mut state = State::new();
+        state.memory.set_memory(0, 0x1000ffff); // b 0
+        state.memory.set_memory(4, 0x00000000); // nop
+
+        let opts = ExecutorOpts {
+            max_cycles: Some(1000),
+            ..Default::default()
+        };
+        let mut executor = Executor::from_opts(state, opts);
+        match executor.run() {
+            Err(ExecutionError::CycleLimitExceeded { pc, cycles }) => {
+                assert!(pc == 0 || pc == 4);
+                assert!(cycles > 1000 && cycles < 1010);
+            }
+            result => panic!("the loop ran past its cycle limit: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_guest_panic() {
+        let message = "panicked at src/main.rs:4:5:\nboom\n";
+        let mut state = State::new();
+        state.memory.set_memory(0, 0x0000000c); // syscall
+        state.memory.set_memory(4, 0x34040001); // ori $a0, $zero, 1
+        state.memory.set_memory(8, 0x34021096); // ori $v0, $zero, 0x1096 (SYS_EXIT_GROUP)
+        state.memory.set_memory(12, 0x0000000c); // syscall
+        state
+            .memory
+            .set_memory_range(0x1000, Box::new(message.as_bytes()))
+            .unwrap();
+        state.registers[REG_V0] = SYS_WRITE;
+        state.registers[REG_A0] = FD_STDERR;
+        state.registers[REG_A1] = 0x1000;
+        state.registers[REG_A2] = message.len() as u32;
+
+        let mut executor = Executor::new(state, SEGMENT_STEPS);
+        match executor.run() {
+            Err(ExecutionError::GuestPanicked {
+                message, location, ..
+            }) => {
+                assert_eq!(message, "boom");
+                assert_eq!(location.as_deref(), Some("src/main.rs:4:5"));
+            }
+            result => panic!("the panic is not reported: {:?}", result),
+        }
+
+        let std_panic = b"thread 'main' panicked at src/lib.rs:1:1:\noops";
+        assert_eq!(
+            ExecutionError::from_panic_message(std_panic, 0),
+            Some(ExecutionError::GuestPanicked {
+                message: "oops".to_string(),
+                location: Some("src/lib.rs:1:1".to_string()),
+                pc: 0,
+            })
+        );
+        assert_eq!(ExecutionError::from_panic_message(b"exit 1", 0), None);
+    }
 }
@@ -0,0 +1,109 @@
+//! A trace of the execution of a program, instruction by instruction, recorded by
+//! [`Executor::with_trace`] and replayed by [`Executor::replay`], to find where two runs of the
+//! same program, or the emulator and the witness generation of the prover, diverge.
+//!
+//! Each step has the pc and the instruction, the registers it wrote and the words of memory it
+//! read and wrote, the fetch of the instruction left out. The registers are numbered as in
+//! [`State::gdb_register`], without the pc.
+//!
+//! [`Executor::with_trace`]: crate::executor::Executor::with_trace
+//! [`Executor::replay`]: crate::executor::Executor::replay
+//! [`State::gdb_register`]: crate::state::State::gdb_register
+
+use crate::state::State;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::path::Path;
+
+/// The number of registers of the trace: the general purpose ones, Status, LO, HI, BadVAddr and
+/// Cause.
+pub const TRACE_REGISTERS: usize = 37;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryOp {
+    pub addr: u32,
+    /// The word read, or written.
+    pub value: u32,
+    pub is_write: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceStep {
+    pub pc: u32,
+    pub insn: u32,
+    /// The registers whose value changed, with their new value.
+    pub registers: Vec<(u8, u32)>,
+    pub memory: Vec<MemoryOp>,
+}
+
+impl TraceStep {
+    /// The step which executed `insn` at `pc`, from the registers before and after it.
+    pub fn new(
+        pc: u32,
+        insn: u32,
+        before: &[u32; TRACE_REGISTERS],
+        after: &[u32; TRACE_REGISTERS],
+        memory: Vec<MemoryOp>,
+    ) -> Self {
+        let registers = (0..)
+            .zip(before.iter().zip(after))
+            .filter(|(_, (before, after))| before != after)
+            .map(|(n, (_, &after))| (n, after))
+            .collect();
+        Self {
+            pc,
+            insn,
+            registers,
+            memory,
+        }
+    }
+}
+
+/// The registers of `state` which a step of the trace records.
+pub fn trace_registers(state: &State) -> [u32; TRACE_REGISTERS] {
+    std::array::from_fn(|n| state.gdb_register(n).unwrap())
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutionTrace {
+    pub steps: Vec<TraceStep>,
+}
+
+impl ExecutionTrace {
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        bincode::serialize_into(writer, self).map_err(io::Error::other)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        bincode::deserialize_from(reader).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// The first step at which a run differs from its trace.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Divergence {
+    pub step: u64,
+    /// The step of the trace, `None` if the run went on past its end.
+    pub expected: Option<TraceStep>,
+    /// The step of the run, `None` if it exited before the end of the trace.
+    pub found: Option<TraceStep>,
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the run diverges from its trace at step {}: ", self.step)?;
+        match (&self.expected, &self.found) {
+            (Some(expected), Some(found)) => {
+                write!(f, "expected {:x?}, found {:x?}", expected, found)
+            }
+            (Some(_), None) => write!(f, "the run exited before the end of the trace"),
+            (None, _) => write!(f, "the run goes on past the end of the trace"),
+        }
+    }
+}
+
+impl std::error::Error for Divergence {}
@@ -0,0 +1,30 @@
+//! Multiplication of 256-bit integers modulo a 256-bit modulus, as computed by the
+//! `UINT256_MULMOD` syscall.
+//!
+//! An integer is eight words, the little-endian words of an integer below `2^256`, which need
+//! not be reduced. Results are reduced.
+
+use num_bigint::BigUint;
+
+pub type Uint256 = [u32; 8];
+
+pub fn from_words(x: &Uint256) -> BigUint {
+    BigUint::from_slice(x)
+}
+
+pub fn to_words(x: &BigUint) -> Uint256 {
+    let mut words = [0u32; 8];
+    for (word, digit) in words.iter_mut().zip(x.to_u32_digits()) {
+        *word = digit;
+    }
+    words
+}
+
+/// Computes `x * y mod m`, or `None` for a zero modulus.
+pub fn mulmod(x: &Uint256, y: &Uint256, m: &Uint256) -> Option<Uint256> {
+    let m = from_words(m);
+    if m.bits() == 0 {
+        return None;
+    }
+    Some(to_words(&(from_words(x) * from_words(y) % m)))
+}
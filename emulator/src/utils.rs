@@ -1,5 +1,5 @@
+use crate::loader::{parse_elf, ElfError};
 use crate::state::{InstrumentedState, State};
-use elf::{endian::AnyEndian, ElfBytes};
 use std::fs;
 use std::fs::File;
 
@@ -11,13 +11,42 @@ pub fn get_block_path(basedir: &str, block: &str, file: &str) -> String {
 }
 
 pub fn load_elf_with_patch(elf_path: &str, args: Vec<&str>) -> Box<State> {
+    load_elf_with_env(elf_path, args, &[])
+}
+
+/// Loads the program at `elf_path`, started with the arguments `args` and the environment
+/// variables `envs`, which it reads with `zkm_runtime::env`.
+pub fn load_elf_with_env(elf_path: &str, args: Vec<&str>, envs: &[(&str, &str)]) -> Box<State> {
     let data = fs::read(elf_path).expect("could not read file");
-    let file =
-        ElfBytes::<AnyEndian>::minimal_parse(data.as_slice()).expect("opening elf file failed");
+    load_elf_from_bytes(&data, args, envs)
+}
+
+/// Loads the program `elf`, see [`load_elf_with_env`].
+pub fn load_elf_from_bytes(elf: &[u8], args: Vec<&str>, envs: &[(&str, &str)]) -> Box<State> {
+    try_load_elf_from_bytes(elf, args, envs).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// Loads the program `elf`, or returns why it cannot be loaded.
+pub fn try_load_elf_from_bytes(
+    elf: &[u8],
+    args: Vec<&str>,
+    envs: &[(&str, &str)],
+) -> Result<Box<State>, ElfError> {
+    let file = parse_elf(elf)?;
     let mut state = State::load_elf(&file);
     state.patch_elf(&file);
-    state.patch_stack(args);
-    state
+    state.patch_stack_with_env(args, envs);
+    Ok(state)
+}
+
+/// Computes the image id of the program loaded in `state`, the `roots_before` of the proof of
+/// its first segment. It covers the code and data of the program, its entry point, and its
+/// arguments and environment variables, but not its inputs.
+pub fn compute_image_id(state: Box<State>) -> [u8; 32] {
+    let mut instrumented_state = InstrumentedState::new(state, String::new());
+    let new_writer = |_: &str| -> Option<std::fs::File> { None };
+    instrumented_state.split_segment(false, "", new_writer);
+    instrumented_state.image_id()
 }
 
 pub fn split_prog_into_segs(
@@ -25,6 +54,18 @@ pub fn split_prog_into_segs(
     seg_path: &str,
     block_path: &str,
     seg_size: usize,
+) -> (usize, usize, Box<State>) {
+    split_prog_into_segs_with(state, seg_path, block_path, seg_size, |_, _| true)
+}
+
+/// [`split_prog_into_segs`], calling `on_segment` with the state and the number of segments
+/// written after each segment. The run stops early when it returns `false`.
+pub fn split_prog_into_segs_with(
+    state: Box<State>,
+    seg_path: &str,
+    block_path: &str,
+    seg_size: usize,
+    mut on_segment: impl FnMut(&State, usize) -> bool,
 ) -> (usize, usize, Box<State>) {
     let mut instrumented_state = InstrumentedState::new(state, block_path.to_string());
     std::fs::create_dir_all(seg_path).unwrap();
@@ -38,6 +79,10 @@ pub fn split_prog_into_segs(
         let cycles = instrumented_state.step();
         if cycles > (seg_size as isize - 1) as u64 {
             instrumented_state.split_segment(true, seg_path, new_writer);
+            let segments = instrumented_state.pre_segment_id as usize;
+            if !on_segment(&instrumented_state.state, segments) {
+                break;
+            }
         }
     }
     instrumented_state.split_segment(true, seg_path, new_writer);
@@ -47,6 +92,11 @@ pub fn split_prog_into_segs(
         instrumented_state.state.total_cycle
     );
 
+    log::info!("execution report:\n{}", instrumented_state.state.report());
+    if !instrumented_state.state.cycle_tracker.is_empty() {
+        log::info!("cycle tracker:\n{}", instrumented_state.state.cycle_tracker);
+    }
+
     instrumented_state.dump_memory();
     (
         instrumented_state.state.total_step as usize,
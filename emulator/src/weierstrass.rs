@@ -0,0 +1,72 @@
+//! Affine point addition and doubling on the short Weierstrass curves `y^2 = x^3 + a x + b` over
+//! a prime field of at most 256 bits, as computed by the `*_ADD` and `*_DOUBLE` syscalls of
+//! secp256k1, BN254 and P-256.
+//!
+//! A point is sixteen words: the little-endian words of `x`, then those of `y`.
+
+use num_bigint::BigUint;
+
+pub type Point = [u32; 16];
+
+pub fn to_coordinates(p: &Point) -> (BigUint, BigUint) {
+    (BigUint::from_slice(&p[..8]), BigUint::from_slice(&p[8..]))
+}
+
+pub fn from_coordinates(x: &BigUint, y: &BigUint) -> Point {
+    let mut p = [0u32; 16];
+    for (words, c) in [(&mut p[..8], x), (&mut p[8..], y)] {
+        for (word, digit) in words.iter_mut().zip(c.to_u32_digits()) {
+            *word = digit;
+        }
+    }
+    p
+}
+
+fn inverse(x: &BigUint, m: &BigUint) -> BigUint {
+    x.modpow(&(m - 2u32), m)
+}
+
+/// Completes a step with the slope `lambda` of the line through `p` and the other point, whose
+/// `x` is `x2`: the third point on the line is `(lambda^2 - x1 - x2, lambda (x1 - x3) - y1)`.
+fn finish(m: &BigUint, x1: &BigUint, y1: &BigUint, x2: &BigUint, lambda: &BigUint) -> Point {
+    let x3 = (lambda * lambda + 2u32 * m - x1 - x2) % m;
+    let y3 = (lambda * (x1 + m - &x3) + m - y1 % m) % m;
+    from_coordinates(&x3, &y3)
+}
+
+/// The slope of the line through `p` and `q`, modulo `m`, if they have different `x`.
+pub fn add_slope(m: &BigUint, p: &Point, q: &Point) -> Option<BigUint> {
+    let ((x1, y1), (x2, y2)) = (to_coordinates(p), to_coordinates(q));
+    let dx = (&x2 + m - &x1 % m) % m;
+    if dx == BigUint::from(0u32) {
+        return None;
+    }
+    let dy = (&y2 + m - &y1 % m) % m;
+    Some(dy * inverse(&dx, m) % m)
+}
+
+/// The slope of the tangent at `p` on the curve of coefficient `a`, modulo `m`, if `y` is not
+/// zero.
+pub fn double_slope(m: &BigUint, a: &BigUint, p: &Point) -> Option<BigUint> {
+    let (x, y) = to_coordinates(p);
+    let dy = 2u32 * &y % m;
+    if dy == BigUint::from(0u32) {
+        return None;
+    }
+    Some((3u32 * &x * &x + a) * inverse(&dy, m) % m)
+}
+
+/// Computes `p + q` modulo `m`, unless they have the same `x`, in which case the formula does
+/// not apply.
+pub fn add(m: &BigUint, p: &Point, q: &Point) -> Option<Point> {
+    let lambda = add_slope(m, p, q)?;
+    let ((x1, y1), (x2, _)) = (to_coordinates(p), to_coordinates(q));
+    Some(finish(m, &x1, &y1, &x2, &lambda))
+}
+
+/// Computes `2p` on the curve of coefficient `a`, modulo `m`, unless `p` has order two.
+pub fn double(m: &BigUint, a: &BigUint, p: &Point) -> Option<Point> {
+    let lambda = double_slope(m, a, p)?;
+    let (x, y) = to_coordinates(p);
+    Some(finish(m, &x, &y, &x, &lambda))
+}
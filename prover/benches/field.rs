@@ -0,0 +1,64 @@
+//! The Goldilocks arithmetic and the trace commitment, with the packing of the target.
+//!
+//! plonky2 packs the Goldilocks field in AVX2 or AVX-512 vectors when the target has them, so
+//! comparing a run with `RUSTFLAGS="-C target-cpu=native"` (`make bench`) to a run without shows
+//! the gain of the SIMD arithmetic.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use plonky2::field::goldilocks_field::GoldilocksField;
+use plonky2::field::packable::Packable;
+use plonky2::field::packed::PackedField;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::{Field, Sample};
+use plonky2::fri::oracle::PolynomialBatch;
+use plonky2::plonk::config::PoseidonGoldilocksConfig;
+use plonky2::util::timing::TimingTree;
+
+type F = GoldilocksField;
+type P = <F as Packable>::Packing;
+type C = PoseidonGoldilocksConfig;
+
+const LEN: usize = 1 << 16;
+
+fn bench_mul(c: &mut Criterion) {
+    let xs = F::rand_vec(LEN);
+    let ys = F::rand_vec(LEN);
+    c.bench_function("mul scalar", |b| {
+        b.iter(|| {
+            let mut acc = F::ONE;
+            for (&x, &y) in xs.iter().zip(&ys) {
+                acc *= x * y;
+            }
+            black_box(acc)
+        })
+    });
+    c.bench_function(&format!("mul packed x{}", P::WIDTH), |b| {
+        let (xs, ys) = (P::pack_slice(&xs), P::pack_slice(&ys));
+        b.iter(|| {
+            let mut acc = P::ONES;
+            for (&x, &y) in xs.iter().zip(ys) {
+                acc *= x * y;
+            }
+            black_box(acc)
+        })
+    });
+}
+
+fn bench_commit(c: &mut Criterion) {
+    let columns = (0..64)
+        .map(|_| PolynomialValues::new(F::rand_vec(LEN)))
+        .collect::<Vec<_>>();
+    c.bench_function("commit 64 columns of 2^16 rows", |b| {
+        b.iter_batched(
+            || columns.clone(),
+            |columns| {
+                let mut timing = TimingTree::default();
+                PolynomialBatch::<F, C, 2>::from_values(columns, 2, false, 4, &mut timing, None)
+            },
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+criterion_group!(benches, bench_mul, bench_commit);
+criterion_main!(benches);
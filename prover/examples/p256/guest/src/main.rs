@@ -0,0 +1,63 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+use p256::ecdsa::{Signature, VerifyingKey};
+use p256::elliptic_curve::ops::Reduce;
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use p256::{AffinePoint, FieldBytes, Scalar, U256};
+use zkm_runtime::precompiles::secp256r1::Secp256r1Point;
+
+zkm_runtime::entrypoint!(main);
+
+pub fn main() {
+    let public_key: Vec<u8> = zkm_runtime::io::read();
+    let msg_hash: Vec<u8> = zkm_runtime::io::read();
+    let signature: Vec<u8> = zkm_runtime::io::read();
+
+    assert!(verify(&public_key, &msg_hash, &signature));
+
+    zkm_runtime::io::commit::<Vec<u8>>(&public_key);
+}
+
+/// Verifies the ECDSA signature of `msg_hash` by the SEC1 key `public_key`, as
+/// `VerifyingKey::verify_prehash` does, but with `u1 G + u2 Q` computed by the secp256r1
+/// precompiles.
+fn verify(public_key: &[u8], msg_hash: &[u8], signature: &[u8]) -> bool {
+    let key = VerifyingKey::from_sec1_bytes(public_key).unwrap();
+    let signature = Signature::from_slice(signature).unwrap();
+    let (r, s) = signature.split_scalars();
+    let (r, s) = (*r, *s);
+
+    let z = <Scalar as Reduce<U256>>::reduce_bytes(FieldBytes::from_slice(msg_hash));
+    let s_inv = s.invert().unwrap();
+    let u1 = z * s_inv;
+    let u2 = r * s_inv;
+
+    let mut point = Secp256r1Point::generator_in_affine();
+    point.mul_assign(&scalar_words(&u1));
+    let mut rhs = to_precompile_point(key.as_affine());
+    rhs.mul_assign(&scalar_words(&u2));
+    point.add_assign(&rhs);
+
+    let mut x = point.to_le_bytes()[..32].to_vec();
+    x.reverse();
+    <Scalar as Reduce<U256>>::reduce_bytes(FieldBytes::from_slice(&x)) == r
+}
+
+fn scalar_words(scalar: &Scalar) -> [u32; 8] {
+    let mut bytes = scalar.to_bytes();
+    bytes.reverse();
+    core::array::from_fn(|i| u32::from_le_bytes(bytes[4 * i..4 * i + 4].try_into().unwrap()))
+}
+
+fn to_precompile_point(point: &AffinePoint) -> Secp256r1Point {
+    let encoded = point.to_encoded_point(false);
+    let mut x = encoded.x().unwrap().to_vec();
+    let mut y = encoded.y().unwrap().to_vec();
+    x.reverse();
+    y.reverse();
+    Secp256r1Point::from(&x, &y)
+}
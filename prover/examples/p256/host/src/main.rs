@@ -0,0 +1,81 @@
+use std::env;
+use std::fs::File;
+use std::io::BufReader;
+use std::time::Duration;
+
+use p256::ecdsa::signature::hazmat::PrehashSigner;
+use p256::ecdsa::{Signature, SigningKey};
+use plonky2::field::goldilocks_field::GoldilocksField;
+use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+use plonky2::util::timing::TimingTree;
+use sha2::{Digest, Sha256};
+
+use zkm_emulator::utils::{load_elf_from_bytes, split_prog_into_segs};
+use zkm_prover::all_stark::AllStark;
+use zkm_prover::config::StarkConfig;
+use zkm_prover::cpu::kernel::assembler::segment_kernel;
+use zkm_prover::include_elf;
+use zkm_prover::proof;
+use zkm_prover::prover::prove;
+use zkm_prover::verifier::verify_proof;
+
+const D: usize = 2;
+type C = PoseidonGoldilocksConfig;
+type F = <C as GenericConfig<D>>::F;
+
+const ELF: &[u8] = include_elf!("p256-verify");
+
+fn prove_single_seg_common(seg_file: &str) {
+    let seg_reader = BufReader::new(File::open(seg_file).unwrap());
+    let kernel = segment_kernel("", "", "", seg_reader);
+
+    let allstark: AllStark<F, D> = AllStark::default();
+    let config = StarkConfig::standard_fast_config();
+    let mut timing = TimingTree::new("prove", log::Level::Info);
+    let allproof: proof::AllProof<GoldilocksField, C, D> =
+        prove(&allstark, &kernel, &config, &mut timing).unwrap();
+    timing.filter(Duration::from_millis(100)).print();
+    verify_proof(&allstark, allproof, &config).unwrap();
+    log::info!("Prove done");
+}
+
+/// Signs an assertion the way a WebAuthn authenticator does, over the authenticator data and the
+/// hash of the client data, and proves its verification.
+fn prove_webauthn_assertion() {
+    let seg_path = env::var("SEG_OUTPUT").expect("Segment output path is missing");
+    let client_data = env::var("ARGS").unwrap_or("data-to-sign".to_string());
+    // The hash of the relying party id, the flags and the signature counter.
+    let authenticator_data = [0x49u8; 37];
+
+    let signing_key = SigningKey::from_slice(&[0x42; 32]).unwrap();
+    let public_key = signing_key
+        .verifying_key()
+        .to_encoded_point(false)
+        .as_bytes()
+        .to_vec();
+    let mut message = authenticator_data.to_vec();
+    message.extend(Sha256::digest(client_data.as_bytes()));
+    let msg_hash = Sha256::digest(&message);
+    let signature: Signature = signing_key.sign_prehash(&msg_hash).unwrap();
+
+    let mut state = load_elf_from_bytes(ELF, vec![], &[]);
+    log::info!("expected public value: {}", hex::encode(&public_key));
+    state.add_input_stream(&public_key);
+    state.add_input_stream(&msg_hash.to_vec());
+    state.add_input_stream(&signature.to_bytes().to_vec());
+
+    let (total_steps, seg_num, mut state) = split_prog_into_segs(state, &seg_path, "", 0);
+    log::info!("total steps: {}", total_steps);
+
+    let value = state.read_public_values::<Vec<u8>>();
+    log::info!("public value: {} in hex", hex::encode(value));
+
+    for i in 0..seg_num {
+        prove_single_seg_common(&format!("{seg_path}/{i}"));
+    }
+}
+
+fn main() {
+    env_logger::try_init().unwrap_or_default();
+    prove_webauthn_assertion();
+}
@@ -25,7 +25,27 @@ const D: usize = 2;
 type C = PoseidonGoldilocksConfig;
 type F = <C as GenericConfig<D>>::F;
 
-const DEGREE_BITS_RANGE: [Range<usize>; 6] = [10..21, 12..22, 12..21, 8..21, 6..21, 13..23];
+const DEGREE_BITS_RANGE: [Range<usize>; 19] = [
+    10..21,
+    12..22,
+    12..21,
+    8..21,
+    6..21,
+    6..21,
+    6..21,
+    6..21,
+    8..21,
+    8..21,
+    8..21,
+    8..21,
+    8..21,
+    8..21,
+    8..21,
+    8..21,
+    8..21,
+    6..21,
+    13..23,
+];
 
 fn prove_single_seg_common(seg_file: &str, basedir: &str, block: &str, file: &str) {
     let seg_reader = BufReader::new(File::open(seg_file).unwrap());
@@ -102,6 +122,8 @@ fn prove_multi_seg_common(
             roots_before: updated_agg_public_values.roots_before,
             roots_after: public_values.roots_after,
             userdata: public_values.userdata,
+            exit_code: public_values.exit_code,
+            journal_digest: public_values.journal_digest,
         };
         timing = TimingTree::new("prove aggression", log::Level::Info);
         // We can duplicate the proofs here because the state hasn't mutated.
@@ -148,6 +170,8 @@ fn prove_multi_seg_common(
             roots_before: first_public_values.roots_before,
             roots_after: public_values.roots_after,
             userdata: public_values.userdata,
+            exit_code: public_values.exit_code,
+            journal_digest: public_values.journal_digest,
         };
         timing = TimingTree::new("prove aggression", log::Level::Info);
         // We can duplicate the proofs here because the state hasn't mutated.
@@ -166,6 +190,8 @@ fn prove_multi_seg_common(
             roots_before: updated_agg_public_values.roots_before,
             roots_after: new_updated_agg_public_values.roots_after,
             userdata: new_updated_agg_public_values.userdata,
+            exit_code: new_updated_agg_public_values.exit_code,
+            journal_digest: new_updated_agg_public_values.journal_digest,
         };
         timing = TimingTree::new("prove nested aggression", log::Level::Info);
 
@@ -199,6 +225,7 @@ fn prove_multi_seg_common(
     circuit.set_data(all_circuits.block.circuit);
     let mut bit_size = vec![32usize; 16];
     bit_size.extend(vec![8; 32]);
+    bit_size.push(32);
     bit_size.extend(vec![64; 68]);
     let wrapped_circuit = WrappedCircuit::<InnerParameters, OuterParameters, D>::build(
         circuit,
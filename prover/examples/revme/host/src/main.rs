@@ -13,17 +13,38 @@ use plonky2x::backend::wrapper::wrap::WrappedCircuit;
 use plonky2x::frontend::builder::CircuitBuilder as WrapperBuilder;
 use plonky2x::prelude::DefaultParameters;
 
-use zkm_emulator::utils::{load_elf_with_patch, split_prog_into_segs};
+use zkm_emulator::utils::{load_elf_from_bytes, split_prog_into_segs};
 use zkm_prover::all_stark::AllStark;
 use zkm_prover::config::StarkConfig;
 use zkm_prover::cpu::kernel::assembler::segment_kernel;
 use zkm_prover::fixed_recursive_verifier::AllRecursiveCircuits;
+use zkm_prover::include_elf;
 use zkm_prover::proof;
 use zkm_prover::proof::PublicValues;
 use zkm_prover::prover::prove;
 use zkm_prover::verifier::verify_proof;
 
-const DEGREE_BITS_RANGE: [Range<usize>; 6] = [10..21, 12..22, 12..21, 8..21, 6..21, 13..23];
+const DEGREE_BITS_RANGE: [Range<usize>; 19] = [
+    10..21,
+    12..22,
+    12..21,
+    8..21,
+    6..21,
+    6..21,
+    6..21,
+    6..21,
+    8..21,
+    8..21,
+    8..21,
+    8..21,
+    8..21,
+    8..21,
+    8..21,
+    8..21,
+    8..21,
+    6..21,
+    13..23,
+];
 
 const D: usize = 2;
 type C = PoseidonGoldilocksConfig;
@@ -104,6 +125,8 @@ fn prove_multi_seg_common(
             roots_before: updated_agg_public_values.roots_before,
             roots_after: public_values.roots_after,
             userdata: public_values.userdata,
+            exit_code: public_values.exit_code,
+            journal_digest: public_values.journal_digest,
         };
         timing = TimingTree::new("prove aggression", log::Level::Info);
         // We can duplicate the proofs here because the state hasn't mutated.
@@ -150,6 +173,8 @@ fn prove_multi_seg_common(
             roots_before: first_public_values.roots_before,
             roots_after: public_values.roots_after,
             userdata: public_values.userdata,
+            exit_code: public_values.exit_code,
+            journal_digest: public_values.journal_digest,
         };
         timing = TimingTree::new("prove aggression", log::Level::Info);
         // We can duplicate the proofs here because the state hasn't mutated.
@@ -168,6 +193,8 @@ fn prove_multi_seg_common(
             roots_before: updated_agg_public_values.roots_before,
             roots_after: new_updated_agg_public_values.roots_after,
             userdata: new_updated_agg_public_values.userdata,
+            exit_code: new_updated_agg_public_values.exit_code,
+            journal_digest: new_updated_agg_public_values.journal_digest,
         };
         timing = TimingTree::new("prove nested aggression", log::Level::Info);
 
@@ -201,6 +228,7 @@ fn prove_multi_seg_common(
     circuit.set_data(all_circuits.block.circuit);
     let mut bit_size = vec![32usize; 16];
     bit_size.extend(vec![8; 32]);
+    bit_size.push(32);
     bit_size.extend(vec![64; 68]);
     let wrapped_circuit = WrappedCircuit::<InnerParameters, OuterParameters, D>::build(
         circuit,
@@ -215,7 +243,7 @@ fn prove_multi_seg_common(
     result
 }
 
-const ELF_PATH: &str = "../guest/elf/mips-zkm-zkvm-elf";
+const ELF: &[u8] = include_elf!("evm");
 
 fn prove_revm() {
     // 1. split ELF into segs
@@ -227,7 +255,7 @@ fn prove_revm() {
     let mut data = vec![];
     f.read_to_end(&mut data).unwrap();
 
-    let mut state = load_elf_with_patch(ELF_PATH, vec![]);
+    let mut state = load_elf_from_bytes(ELF, vec![], &[]);
     // load input
     state.input_stream.push(data);
 
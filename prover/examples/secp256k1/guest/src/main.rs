@@ -0,0 +1,71 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+use k256::ecdsa::{RecoveryId, Signature};
+use k256::elliptic_curve::ops::Reduce;
+use k256::elliptic_curve::point::DecompressPoint;
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use k256::elliptic_curve::subtle::Choice;
+use k256::{AffinePoint, FieldBytes, Scalar, U256};
+use zkm_runtime::precompiles::secp256k1::Secp256k1Point;
+
+zkm_runtime::entrypoint!(main);
+
+pub fn main() {
+    let public_key: Vec<u8> = zkm_runtime::io::read();
+    let msg_hash: Vec<u8> = zkm_runtime::io::read();
+    let signature: Vec<u8> = zkm_runtime::io::read();
+    let recid: u8 = zkm_runtime::io::read();
+
+    let recovered = recover(&msg_hash, &signature, recid);
+    assert_eq!(recovered, public_key);
+
+    zkm_runtime::io::commit::<Vec<u8>>(&recovered);
+}
+
+/// Recovers the uncompressed SEC1 key that signed `msg_hash`, as `VerifyingKey::recover_from_prehash`
+/// does, but with `u1 G + u2 R` computed by the secp256k1 precompiles.
+fn recover(msg_hash: &[u8], signature: &[u8], recid: u8) -> Vec<u8> {
+    let signature = Signature::from_slice(signature).unwrap();
+    let recid = RecoveryId::from_byte(recid).unwrap();
+    let (r, s) = signature.split_scalars();
+    let (r, s) = (*r, *s);
+
+    let big_r =
+        AffinePoint::decompress(&r.to_bytes(), Choice::from(recid.is_y_odd() as u8)).unwrap();
+    let z = <Scalar as Reduce<U256>>::reduce_bytes(FieldBytes::from_slice(msg_hash));
+    let r_inv = r.invert().unwrap();
+    let u1 = -(z * r_inv);
+    let u2 = s * r_inv;
+
+    let mut key = Secp256k1Point::generator_in_affine();
+    key.mul_assign(&scalar_words(&u1));
+    let mut rhs = to_precompile_point(&big_r);
+    rhs.mul_assign(&scalar_words(&u2));
+    key.add_assign(&rhs);
+
+    let bytes = key.to_le_bytes();
+    let mut encoded = Vec::with_capacity(65);
+    encoded.push(0x04);
+    encoded.extend(bytes[..32].iter().rev());
+    encoded.extend(bytes[32..].iter().rev());
+    encoded
+}
+
+fn scalar_words(scalar: &Scalar) -> [u32; 8] {
+    let mut bytes = scalar.to_bytes();
+    bytes.reverse();
+    core::array::from_fn(|i| u32::from_le_bytes(bytes[4 * i..4 * i + 4].try_into().unwrap()))
+}
+
+fn to_precompile_point(point: &AffinePoint) -> Secp256k1Point {
+    let encoded = point.to_encoded_point(false);
+    let mut x = encoded.x().unwrap().to_vec();
+    let mut y = encoded.y().unwrap().to_vec();
+    x.reverse();
+    y.reverse();
+    Secp256k1Point::from(&x, &y)
+}
@@ -23,7 +23,27 @@ use zkm_prover::proof::PublicValues;
 use zkm_prover::prover::prove;
 use zkm_prover::verifier::verify_proof;
 
-const DEGREE_BITS_RANGE: [Range<usize>; 6] = [10..21, 12..22, 12..21, 8..21, 6..21, 13..23];
+const DEGREE_BITS_RANGE: [Range<usize>; 19] = [
+    10..21,
+    12..22,
+    12..21,
+    8..21,
+    6..21,
+    6..21,
+    6..21,
+    6..21,
+    8..21,
+    8..21,
+    8..21,
+    8..21,
+    8..21,
+    8..21,
+    8..21,
+    8..21,
+    8..21,
+    6..21,
+    13..23,
+];
 
 const D: usize = 2;
 type C = PoseidonGoldilocksConfig;
@@ -104,6 +124,8 @@ fn prove_multi_seg_common(
             roots_before: updated_agg_public_values.roots_before,
             roots_after: public_values.roots_after,
             userdata: public_values.userdata,
+            exit_code: public_values.exit_code,
+            journal_digest: public_values.journal_digest,
         };
         timing = TimingTree::new("prove aggression", log::Level::Info);
         // We can duplicate the proofs here because the state hasn't mutated.
@@ -150,6 +172,8 @@ fn prove_multi_seg_common(
             roots_before: first_public_values.roots_before,
             roots_after: public_values.roots_after,
             userdata: public_values.userdata,
+            exit_code: public_values.exit_code,
+            journal_digest: public_values.journal_digest,
         };
         timing = TimingTree::new("prove aggression", log::Level::Info);
         // We can duplicate the proofs here because the state hasn't mutated.
@@ -168,6 +192,8 @@ fn prove_multi_seg_common(
             roots_before: updated_agg_public_values.roots_before,
             roots_after: new_updated_agg_public_values.roots_after,
             userdata: new_updated_agg_public_values.userdata,
+            exit_code: new_updated_agg_public_values.exit_code,
+            journal_digest: new_updated_agg_public_values.journal_digest,
         };
         timing = TimingTree::new("prove nested aggression", log::Level::Info);
 
@@ -201,6 +227,7 @@ fn prove_multi_seg_common(
     circuit.set_data(all_circuits.block.circuit);
     let mut bit_size = vec![32usize; 16];
     bit_size.extend(vec![8; 32]);
+    bit_size.push(32);
     bit_size.extend(vec![64; 68]);
     let wrapped_circuit = WrappedCircuit::<InnerParameters, OuterParameters, D>::build(
         circuit,
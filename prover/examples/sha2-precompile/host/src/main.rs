@@ -7,20 +7,41 @@ use std::time::Duration;
 use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
 use plonky2::util::timing::TimingTree;
 
-use zkm_emulator::utils::{load_elf_with_patch, split_prog_into_segs};
+use zkm_emulator::utils::{load_elf_from_bytes, load_elf_with_patch, split_prog_into_segs};
 use zkm_prover::all_stark::AllStark;
 use zkm_prover::config::StarkConfig;
 use zkm_prover::cpu::kernel::assembler::segment_kernel;
 use zkm_prover::fixed_recursive_verifier::AllRecursiveCircuits;
 use zkm_prover::generation::state::{AssumptionReceipt, AssumptionReceipts, Receipt};
-
-const DEGREE_BITS_RANGE: [Range<usize>; 6] = [10..21, 12..22, 12..21, 8..21, 6..21, 13..23];
+use zkm_prover::include_elf;
+
+const DEGREE_BITS_RANGE: [Range<usize>; 19] = [
+    10..21,
+    12..22,
+    12..21,
+    8..21,
+    6..21,
+    6..21,
+    6..21,
+    6..21,
+    8..21,
+    8..21,
+    8..21,
+    8..21,
+    8..21,
+    8..21,
+    8..21,
+    8..21,
+    8..21,
+    6..21,
+    13..23,
+];
 
 const D: usize = 2;
 type C = PoseidonGoldilocksConfig;
 type F = <C as GenericConfig<D>>::F;
 
-const ELF_PATH: &str = "../guest/elf/mips-zkm-zkvm-elf";
+const ELF: &[u8] = include_elf!("sha2-precompile");
 
 fn u32_array_to_u8_vec(u32_array: &[u32; 8]) -> Vec<u8> {
     let mut u8_vec = Vec::with_capacity(u32_array.len() * 4);
@@ -72,6 +93,7 @@ fn prove_sha_5_precompile(
         proof: agg_proof,
         root_before: u32_array_to_u8_vec(&updated_agg_public_values.roots_before.root),
         userdata: updated_agg_public_values.userdata.clone(),
+        journal: state.journal().bytes,
     }
 }
 
@@ -91,7 +113,7 @@ fn prove_sha2_precompile() {
     let image_id = receipt.root_before.clone();
     receipts.push(receipt.into());
 
-    let mut state = load_elf_with_patch(&ELF_PATH, vec![]);
+    let mut state = load_elf_from_bytes(ELF, vec![], &[]);
 
     let public_input: [u8; 32] = [
         91, 15, 50, 181, 63, 91, 186, 46, 9, 26, 167, 190, 200, 232, 40, 101, 149, 181, 253, 89,
@@ -1,5 +1,8 @@
 use crate::arithmetic::arithmetic_stark;
 use crate::arithmetic::arithmetic_stark::ArithmeticStark;
+use crate::blake::compress_stark::BlakeCompressStark;
+use crate::bls12_381::fp_stark::Bls12381FpStark;
+use crate::chacha::block_stark::ChaChaBlockStark;
 use crate::config::StarkConfig;
 use crate::cpu::cpu_stark;
 use crate::cpu::cpu_stark::CpuStark;
@@ -9,16 +12,28 @@ use plonky2::field::extension::Extendable;
 use plonky2::field::types::Field;
 use plonky2::hash::hash_types::RichField;
 
+use crate::ed25519::ed25519_stark::Ed25519Stark;
+use crate::keccak::keccak_stark;
+use crate::keccak::keccak_stark::KeccakStark;
+use crate::keccak_sponge::columns::KECCAK_RATE_BYTES;
+use crate::keccak_sponge::keccak_sponge_stark;
+use crate::keccak_sponge::keccak_sponge_stark::KeccakSpongeStark;
 use crate::logic;
 use crate::logic::LogicStark;
 use crate::memory::memory_stark;
 use crate::memory::memory_stark::MemoryStark;
+use crate::modexp::modexp_stark::ModExpStark;
 use crate::poseidon::poseidon_stark;
 use crate::poseidon::poseidon_stark::PoseidonStark;
 use crate::poseidon_sponge::columns::POSEIDON_RATE_BYTES;
 use crate::poseidon_sponge::poseidon_sponge_stark;
 use crate::poseidon_sponge::poseidon_sponge_stark::PoseidonSpongeStark;
+use crate::precompile::PrecompileRegistry;
+use crate::sha256::sha_compress_stark::ShaCompressStark;
+use crate::sha256::sha_extend_stark::ShaExtendStark;
 use crate::stark::Stark;
+use crate::uint256::mulmod_stark::Uint256MulModStark;
+use crate::weierstrass::weierstrass_stark::{Bn254Stark, Secp256k1Stark, Secp256r1Stark};
 
 #[derive(Clone)]
 pub struct AllStark<F: RichField + Extendable<D>, const D: usize> {
@@ -26,6 +41,19 @@ pub struct AllStark<F: RichField + Extendable<D>, const D: usize> {
     pub cpu_stark: CpuStark<F, D>,
     pub poseidon_stark: PoseidonStark<F, D>,
     pub poseidon_sponge_stark: PoseidonSpongeStark<F, D>,
+    pub keccak_stark: KeccakStark<F, D>,
+    pub keccak_sponge_stark: KeccakSpongeStark<F, D>,
+    pub sha_extend_stark: ShaExtendStark<F, D>,
+    pub sha_compress_stark: ShaCompressStark<F, D>,
+    pub secp256k1_stark: Secp256k1Stark<F, D>,
+    pub ed25519_stark: Ed25519Stark<F, D>,
+    pub bn254_stark: Bn254Stark<F, D>,
+    pub bls12381_fp_stark: Bls12381FpStark<F, D>,
+    pub uint256_stark: Uint256MulModStark<F, D>,
+    pub blake_stark: BlakeCompressStark<F, D>,
+    pub chacha_stark: ChaChaBlockStark<F, D>,
+    pub secp256r1_stark: Secp256r1Stark<F, D>,
+    pub modexp_stark: ModExpStark<F, D>,
     pub logic_stark: LogicStark<F, D>,
     pub memory_stark: MemoryStark<F, D>,
     pub cross_table_lookups: Vec<CrossTableLookup<F>>,
@@ -38,6 +66,19 @@ impl<F: RichField + Extendable<D>, const D: usize> Default for AllStark<F, D> {
             cpu_stark: CpuStark::default(),
             poseidon_stark: PoseidonStark::default(),
             poseidon_sponge_stark: PoseidonSpongeStark::default(),
+            keccak_stark: KeccakStark::default(),
+            keccak_sponge_stark: KeccakSpongeStark::default(),
+            sha_extend_stark: ShaExtendStark::default(),
+            sha_compress_stark: ShaCompressStark::default(),
+            secp256k1_stark: Secp256k1Stark::default(),
+            ed25519_stark: Ed25519Stark::default(),
+            bn254_stark: Bn254Stark::default(),
+            bls12381_fp_stark: Bls12381FpStark::default(),
+            uint256_stark: Uint256MulModStark::default(),
+            blake_stark: BlakeCompressStark::default(),
+            chacha_stark: ChaChaBlockStark::default(),
+            secp256r1_stark: Secp256r1Stark::default(),
+            modexp_stark: ModExpStark::default(),
             logic_stark: LogicStark::default(),
             memory_stark: MemoryStark::default(),
             cross_table_lookups: all_cross_table_lookups(),
@@ -52,6 +93,19 @@ impl<F: RichField + Extendable<D>, const D: usize> AllStark<F, D> {
             self.cpu_stark.num_lookup_helper_columns(config),
             self.poseidon_stark.num_lookup_helper_columns(config),
             self.poseidon_sponge_stark.num_lookup_helper_columns(config),
+            self.keccak_stark.num_lookup_helper_columns(config),
+            self.keccak_sponge_stark.num_lookup_helper_columns(config),
+            self.sha_extend_stark.num_lookup_helper_columns(config),
+            self.sha_compress_stark.num_lookup_helper_columns(config),
+            self.secp256k1_stark.num_lookup_helper_columns(config),
+            self.ed25519_stark.num_lookup_helper_columns(config),
+            self.bn254_stark.num_lookup_helper_columns(config),
+            self.bls12381_fp_stark.num_lookup_helper_columns(config),
+            self.uint256_stark.num_lookup_helper_columns(config),
+            self.blake_stark.num_lookup_helper_columns(config),
+            self.chacha_stark.num_lookup_helper_columns(config),
+            self.secp256r1_stark.num_lookup_helper_columns(config),
+            self.modexp_stark.num_lookup_helper_columns(config),
             self.logic_stark.num_lookup_helper_columns(config),
             self.memory_stark.num_lookup_helper_columns(config),
         ]
@@ -64,8 +118,21 @@ pub enum Table {
     Cpu = 1,
     Poseidon = 2,
     PoseidonSponge = 3,
-    Logic = 4,
-    Memory = 5,
+    Keccak = 4,
+    KeccakSponge = 5,
+    ShaExtend = 6,
+    ShaCompress = 7,
+    Secp256k1 = 8,
+    Ed25519 = 9,
+    Bn254 = 10,
+    Bls12381Fp = 11,
+    Uint256 = 12,
+    Blake = 13,
+    ChaCha = 14,
+    Secp256r1 = 15,
+    ModExp = 16,
+    Logic = 17,
+    Memory = 18,
 }
 
 pub(crate) const NUM_TABLES: usize = Table::Memory as usize + 1;
@@ -80,6 +147,19 @@ impl Table {
             Self::Cpu,
             Self::Poseidon,
             Self::PoseidonSponge,
+            Self::Keccak,
+            Self::KeccakSponge,
+            Self::ShaExtend,
+            Self::ShaCompress,
+            Self::Secp256k1,
+            Self::Ed25519,
+            Self::Bn254,
+            Self::Bls12381Fp,
+            Self::Uint256,
+            Self::Blake,
+            Self::ChaCha,
+            Self::Secp256r1,
+            Self::ModExp,
             Self::Logic,
             Self::Memory,
         ]
@@ -87,14 +167,36 @@ impl Table {
 }
 
 pub(crate) fn all_cross_table_lookups<F: Field>() -> Vec<CrossTableLookup<F>> {
-    vec![
+    let precompiles = PrecompileRegistry::builtin();
+    let mut ctls = vec![
         ctl_arithmetic(),
         ctl_poseidon_sponge(),
         ctl_poseidon_inputs(),
         ctl_poseidon_outputs(),
-        ctl_logic(),
-        ctl_memory(),
-    ]
+        ctl_keccak_sponge(),
+        ctl_keccak_inputs(),
+        ctl_keccak_outputs(),
+    ];
+    ctls.extend(precompiles.cross_table_lookups());
+    ctls.push(ctl_logic());
+    ctls.push(ctl_memory(&precompiles));
+    ctls.push(ctl_public_values());
+    ctls
+}
+
+/// The exit code and the image id hashed by the exit kernel in the last row of the CPU trace, and
+/// the image id hashed by the bootstrap, looked up by the verifier from the public values, see
+/// [`crate::verifier::get_public_values_extra_looking_sum`]. It is the only lookup of the CPU
+/// table.
+fn ctl_public_values<F: Field>() -> CrossTableLookup<F> {
+    CrossTableLookup::new(
+        vec![],
+        TableWithColumns::new(
+            Table::Cpu,
+            cpu_stark::ctl_data_public_values(),
+            Some(cpu_stark::ctl_filter_public_values()),
+        ),
+    )
 }
 
 fn ctl_arithmetic<F: Field>() -> CrossTableLookup<F> {
@@ -102,6 +204,7 @@ fn ctl_arithmetic<F: Field>() -> CrossTableLookup<F> {
         vec![
             cpu_stark::ctl_arithmetic_base_rows(),
             cpu_stark::ctl_arithmetic_imm_base_rows(),
+            cpu_stark::ctl_arithmetic_trap_rows(),
         ],
         arithmetic_stark::ctl_arithmetic_rows(),
     )
@@ -152,19 +255,79 @@ fn ctl_poseidon_sponge<F: Field>() -> CrossTableLookup<F> {
     CrossTableLookup::new(vec![cpu_looking], poseidon_sponge_looked)
 }
 
+// As for `PoseidonStark`, the inputs and the outputs of `KeccakStark` are looked up separately.
+fn ctl_keccak_inputs<F: Field>() -> CrossTableLookup<F> {
+    let keccak_sponge_looking = TableWithColumns::new(
+        Table::KeccakSponge,
+        keccak_sponge_stark::ctl_looking_keccak_inputs(),
+        Some(keccak_sponge_stark::ctl_looking_keccak_filter()),
+    );
+    let keccak_looked = TableWithColumns::new(
+        Table::Keccak,
+        keccak_stark::ctl_data_inputs(),
+        Some(keccak_stark::ctl_filter_inputs()),
+    );
+    CrossTableLookup::new(vec![keccak_sponge_looking], keccak_looked)
+}
+
+fn ctl_keccak_outputs<F: Field>() -> CrossTableLookup<F> {
+    let keccak_sponge_looking = TableWithColumns::new(
+        Table::KeccakSponge,
+        keccak_sponge_stark::ctl_looking_keccak_outputs(),
+        Some(keccak_sponge_stark::ctl_looking_keccak_filter()),
+    );
+    let keccak_looked = TableWithColumns::new(
+        Table::Keccak,
+        keccak_stark::ctl_data_outputs(),
+        Some(keccak_stark::ctl_filter_outputs()),
+    );
+    CrossTableLookup::new(vec![keccak_sponge_looking], keccak_looked)
+}
+
+fn ctl_keccak_sponge<F: Field>() -> CrossTableLookup<F> {
+    let cpu_looking = TableWithColumns::new(
+        Table::Cpu,
+        cpu_stark::ctl_data_keccak_sponge(),
+        Some(cpu_stark::ctl_filter_keccak_sponge()),
+    );
+    let keccak_sponge_looked = TableWithColumns::new(
+        Table::KeccakSponge,
+        keccak_sponge_stark::ctl_looked_data(),
+        Some(keccak_sponge_stark::ctl_looked_filter()),
+    );
+    CrossTableLookup::new(vec![cpu_looking], keccak_sponge_looked)
+}
+
 pub(crate) fn ctl_logic<F: Field>() -> CrossTableLookup<F> {
     let cpu_looking = TableWithColumns::new(
         Table::Cpu,
         cpu_stark::ctl_data_logic(),
         Some(cpu_stark::ctl_filter_logic()),
     );
+    let cpu_imm_looking = TableWithColumns::new(
+        Table::Cpu,
+        cpu_stark::ctl_data_logic_imm(),
+        Some(cpu_stark::ctl_filter_logic_imm()),
+    );
+    // The sponge XORs each block into the rate, one word per lookup.
+    let keccak_sponge_looking = (0..keccak_sponge_stark::num_logic_ctls()).map(|i| {
+        TableWithColumns::new(
+            Table::KeccakSponge,
+            keccak_sponge_stark::ctl_looking_logic(i),
+            Some(keccak_sponge_stark::ctl_looking_logic_filter()),
+        )
+    });
+    let all_lookers = [cpu_looking, cpu_imm_looking]
+        .into_iter()
+        .chain(keccak_sponge_looking)
+        .collect();
     let logic_looked =
         TableWithColumns::new(Table::Logic, logic::ctl_data(), Some(logic::ctl_filter()));
 
-    CrossTableLookup::new(vec![cpu_looking], logic_looked)
+    CrossTableLookup::new(all_lookers, logic_looked)
 }
 
-fn ctl_memory<F: Field>() -> CrossTableLookup<F> {
+fn ctl_memory<F: Field>(precompiles: &PrecompileRegistry<F>) -> CrossTableLookup<F> {
     let cpu_memory_gp_ops = (0..NUM_GP_CHANNELS).map(|channel| {
         TableWithColumns::new(
             Table::Cpu,
@@ -179,10 +342,19 @@ fn ctl_memory<F: Field>() -> CrossTableLookup<F> {
             Some(poseidon_sponge_stark::ctl_looking_memory_filter(i)),
         )
     });
+    let keccak_sponge_reads = (0..KECCAK_RATE_BYTES).map(|i| {
+        TableWithColumns::new(
+            Table::KeccakSponge,
+            keccak_sponge_stark::ctl_looking_memory(i),
+            Some(keccak_sponge_stark::ctl_looking_memory_filter(i)),
+        )
+    });
     let all_lookers = []
         .into_iter()
         .chain(cpu_memory_gp_ops)
         .chain(poseidon_sponge_reads)
+        .chain(keccak_sponge_reads)
+        .chain(precompiles.memory_lookers())
         .collect();
     let memory_looked = TableWithColumns::new(
         Table::Memory,
@@ -1,6 +1,6 @@
-//! Support for MIPS instructions ADD, SUB, LT and GT
+//! Support for MIPS instructions ADD, ADDU, SUB, SUBU, LT and GT
 //!
-//! This crate verifies MIPS instructions ADD, SUB, LT and GT (i.e. for
+//! This crate verifies MIPS instructions ADD, ADDU, SUB, SUBU, LT and GT (i.e. for
 //! unsigned inputs). Each of these instructions can be verified using
 //! the "add with carry out" equation
 //!
@@ -33,26 +33,39 @@ pub(crate) fn generate<F: PrimeField64>(lv: &mut [F], filter: usize, left_in: u3
     u32_to_array(&mut lv[INPUT_REGISTER_2], 0);
 
     match filter {
-        IS_ADD | IS_ADDI => {
+        IS_ADD | IS_ADDI | IS_ADDU | IS_ADDIU => {
             let (result, cy) = left_in.overflowing_add(right_in);
             u32_to_array(&mut lv[AUX_INPUT_REGISTER_0], cy as u32);
             u32_to_array(&mut lv[OUTPUT_REGISTER], result);
+            if filter == IS_ADD || filter == IS_ADDI {
+                generate_sign_aux(lv, [left_in, right_in, result]);
+            }
         }
         IS_SUB | IS_SUBU => {
             let (diff, cy) = left_in.overflowing_sub(right_in);
             u32_to_array(&mut lv[AUX_INPUT_REGISTER_0], cy as u32);
             u32_to_array(&mut lv[OUTPUT_REGISTER], diff);
-        }
-        IS_ADDU | IS_ADDIU => {
-            // FIXME: add constraints
-            let (result, cy) = left_in.overflowing_add(right_in);
-            u32_to_array(&mut lv[AUX_INPUT_REGISTER_0], cy as u32);
-            u32_to_array(&mut lv[OUTPUT_REGISTER], result);
+            if filter == IS_SUB {
+                // right_in + diff = left_in
+                generate_sign_aux(lv, [right_in, diff, left_in]);
+            }
         }
         _ => panic!("unexpected operation filter"),
     };
 }
 
+/// Fill the columns used by the signed-overflow check of `x + y = z`.
+fn generate_sign_aux<F: PrimeField64>(lv: &mut [F], xyz: [u32; 3]) {
+    let mut signs = [0i64; 3];
+    for ((&col, sign), v) in ADDCY_SIGN_AUX.iter().zip(signs.iter_mut()).zip(xyz) {
+        let hi = v >> LIMB_BITS;
+        *sign = (v >> 31) as i64;
+        lv[col] = F::from_canonical_u32((2 * hi) & 0xffff);
+    }
+    let diff = signs[0] + signs[1] - 2 * signs[2];
+    lv[ADDCY_SIGN_DIFF_SQ] = F::from_canonical_u64((diff * diff) as u64);
+}
+
 /// 2^-16 mod (2^64 - 2^32 + 1)
 const GOLDILOCKS_INVERSE_65536: u64 = 18446462594437939201;
 
@@ -139,12 +152,42 @@ pub(crate) fn eval_packed_generic_addcy<P: PackedField>(
     }
 }
 
+/// Constrains `x + y = z` not to overflow as a signed operation, assuming filter != 0
+/// and that the addition itself is checked by `eval_packed_generic_addcy`.
+///
+/// The sign bit of each operand is recovered from its top limb `hi` and the range-checked
+/// `t = 2*hi - sign*2^16`. The signed addition overflows exactly when `x` and `y` have the
+/// same sign and `z` the other one, i.e. when `v = sign(x) + sign(y) - 2*sign(z)` is `2`
+/// or `-2`, so we check that `v` is one of `-1`, `0` or `1` through `w = v^2` and `v*w = v`.
+fn eval_packed_signed_overflow<P: PackedField>(
+    lv: &[P; NUM_ARITH_COLUMNS],
+    yield_constr: &mut ConstraintConsumer<P>,
+    filter: P,
+    x: &[P],
+    y: &[P],
+    z: &[P],
+) {
+    let overflow_inv = P::Scalar::from_canonical_u64(GOLDILOCKS_INVERSE_65536);
+
+    let mut signs = [P::ZEROS; 3];
+    let his = [x[N_LIMBS - 1], y[N_LIMBS - 1], z[N_LIMBS - 1]];
+    for ((sign, &col), hi) in signs.iter_mut().zip(ADDCY_SIGN_AUX.iter()).zip(his) {
+        *sign = (hi * P::Scalar::TWO - lv[col]) * overflow_inv;
+        yield_constr.constraint(filter * *sign * (P::ONES - *sign));
+    }
+
+    let v = signs[0] + signs[1] - signs[2] * P::Scalar::TWO;
+    let w = lv[ADDCY_SIGN_DIFF_SQ];
+    yield_constr.constraint(filter * (w - v * v));
+    yield_constr.constraint(filter * (v * w - v));
+}
+
 pub fn eval_packed_generic<P: PackedField>(
     lv: &[P; NUM_ARITH_COLUMNS],
     yield_constr: &mut ConstraintConsumer<P>,
 ) {
-    let is_add = lv[IS_ADD];
-    let is_sub = lv[IS_SUB];
+    let is_add = lv[IS_ADD] + lv[IS_ADDU];
+    let is_sub = lv[IS_SUB] + lv[IS_SUBU];
     let is_addi = lv[IS_ADDI];
     let is_addiu = lv[IS_ADDIU];
 
@@ -158,6 +201,11 @@ pub fn eval_packed_generic<P: PackedField>(
     eval_packed_generic_addcy(yield_constr, is_sub, in1, out, in0, aux, false);
     eval_packed_generic_addcy(yield_constr, is_addi, in0, in1, out, aux, false);
     eval_packed_generic_addcy(yield_constr, is_addiu, in0, in1, out, aux, false);
+
+    // ADD, ADDI and SUB raise an exception on signed overflow instead.
+    let is_signed_add = lv[IS_ADD] + lv[IS_ADDI];
+    eval_packed_signed_overflow(lv, yield_constr, is_signed_add, in0, in1, out);
+    eval_packed_signed_overflow(lv, yield_constr, lv[IS_SUB], in1, out, in0);
 }
 
 #[allow(clippy::needless_collect)]
@@ -226,13 +274,47 @@ pub(crate) fn eval_ext_circuit_addcy<F: RichField + Extendable<D>, const D: usiz
     }
 }
 
+fn eval_ext_circuit_signed_overflow<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    lv: &[ExtensionTarget<D>; NUM_ARITH_COLUMNS],
+    yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+    filter: ExtensionTarget<D>,
+    x: &[ExtensionTarget<D>],
+    y: &[ExtensionTarget<D>],
+    z: &[ExtensionTarget<D>],
+) {
+    let overflow_inv = F::from_canonical_u64(GOLDILOCKS_INVERSE_65536);
+    let one = builder.one_extension();
+
+    let mut signs = [builder.zero_extension(); 3];
+    let his = [x[N_LIMBS - 1], y[N_LIMBS - 1], z[N_LIMBS - 1]];
+    for ((sign, &col), hi) in signs.iter_mut().zip(ADDCY_SIGN_AUX.iter()).zip(his) {
+        let t = builder.mul_const_extension(F::TWO, hi);
+        let t = builder.sub_extension(t, lv[col]);
+        *sign = builder.mul_const_extension(overflow_inv, t);
+        let not_sign = builder.sub_extension(one, *sign);
+        let constr = builder.mul_many_extension([filter, *sign, not_sign]);
+        yield_constr.constraint(builder, constr);
+    }
+
+    let v = builder.add_extension(signs[0], signs[1]);
+    let v = builder.arithmetic_extension(-F::TWO, F::ONE, one, signs[2], v);
+    let w = lv[ADDCY_SIGN_DIFF_SQ];
+    let constr = builder.mul_sub_extension(v, v, w);
+    let constr = builder.mul_extension(filter, constr);
+    yield_constr.constraint(builder, constr);
+    let constr = builder.mul_sub_extension(v, w, v);
+    let constr = builder.mul_extension(filter, constr);
+    yield_constr.constraint(builder, constr);
+}
+
 pub fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
     builder: &mut CircuitBuilder<F, D>,
     lv: &[ExtensionTarget<D>; NUM_ARITH_COLUMNS],
     yield_constr: &mut RecursiveConstraintConsumer<F, D>,
 ) {
-    let is_add = lv[IS_ADD];
-    let is_sub = lv[IS_SUB];
+    let is_add = builder.add_extension(lv[IS_ADD], lv[IS_ADDU]);
+    let is_sub = builder.add_extension(lv[IS_SUB], lv[IS_SUBU]);
     //let is_lt = lv[IS_LT];
     //let is_gt = lv[IS_GT];
     let is_addi = lv[IS_ADDI];
@@ -247,6 +329,10 @@ pub fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
     eval_ext_circuit_addcy(builder, yield_constr, is_sub, in1, out, in0, aux, false);
     eval_ext_circuit_addcy(builder, yield_constr, is_addi, in0, in1, out, aux, false);
     eval_ext_circuit_addcy(builder, yield_constr, is_addiu, in0, in1, out, aux, false);
+
+    let is_signed_add = builder.add_extension(lv[IS_ADD], lv[IS_ADDI]);
+    eval_ext_circuit_signed_overflow(builder, lv, yield_constr, is_signed_add, in0, in1, out);
+    eval_ext_circuit_signed_overflow(builder, lv, yield_constr, lv[IS_SUB], in1, out, in0);
 }
 
 #[cfg(test)]
@@ -270,7 +356,9 @@ mod tests {
         // should be met even if all values are
         // garbage.
         lv[IS_ADD] = F::ZERO;
+        lv[IS_ADDU] = F::ZERO;
         lv[IS_SUB] = F::ZERO;
+        lv[IS_SUBU] = F::ZERO;
         // lv[IS_LT] = F::ZERO;
         // lv[IS_GT] = F::ZERO;
         lv[IS_ADDI] = F::ZERO;
@@ -296,7 +384,7 @@ mod tests {
         const N_ITERS: usize = 1000;
 
         for _ in 0..N_ITERS {
-            for op_filter in [IS_ADD, IS_SUB, IS_ADDI, IS_ADDIU] {
+            for op_filter in [IS_ADD, IS_ADDU, IS_SUB, IS_SUBU, IS_ADDI, IS_ADDIU] {
                 // set entire row to random 16-bit values
                 let mut lv = [F::default(); NUM_ARITH_COLUMNS]
                     .map(|_| F::from_canonical_u16(rng.gen::<u16>()));
@@ -306,13 +394,25 @@ mod tests {
                 // operation filters to zero since all are treated by
                 // the call.
                 lv[IS_ADD] = F::ZERO;
+                lv[IS_ADDU] = F::ZERO;
                 lv[IS_SUB] = F::ZERO;
+                lv[IS_SUBU] = F::ZERO;
                 lv[IS_ADDI] = F::ZERO;
                 lv[IS_ADDIU] = F::ZERO;
                 lv[op_filter] = F::ONE;
 
-                let left_in = rng.gen::<u32>();
-                let right_in = rng.gen::<u32>();
+                // ADD, ADDI and SUB rows only exist for inputs that don't overflow.
+                let (left_in, right_in) = loop {
+                    let (l, r) = (rng.gen::<u32>(), rng.gen::<u32>());
+                    let overflows = match op_filter {
+                        IS_ADD | IS_ADDI => (l as i32).checked_add(r as i32).is_none(),
+                        IS_SUB => (l as i32).checked_sub(r as i32).is_none(),
+                        _ => false,
+                    };
+                    if !overflows {
+                        break (l, r);
+                    }
+                };
 
                 generate(&mut lv, op_filter, left_in, right_in);
 
@@ -328,8 +428,8 @@ mod tests {
                 }
 
                 let expected = match op_filter {
-                    IS_ADD | IS_ADDI | IS_ADDIU => left_in.overflowing_add(right_in).0,
-                    IS_SUB => left_in.overflowing_sub(right_in).0,
+                    IS_ADD | IS_ADDU | IS_ADDI | IS_ADDIU => left_in.overflowing_add(right_in).0,
+                    IS_SUB | IS_SUBU => left_in.overflowing_sub(right_in).0,
                     _ => panic!("unrecognised operation"),
                 };
 
@@ -342,4 +442,33 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn signed_overflow_addcy() {
+        type F = GoldilocksField;
+
+        let cases = [
+            (IS_ADD, i32::MAX as u32, 1),
+            (IS_ADDI, i32::MIN as u32, u32::MAX),
+            (IS_SUB, i32::MIN as u32, 1),
+            (IS_SUB, i32::MAX as u32, u32::MAX),
+        ];
+        for (op_filter, left_in, right_in) in cases {
+            let mut lv = [F::ZERO; NUM_ARITH_COLUMNS];
+            lv[op_filter] = F::ONE;
+            generate(&mut lv, op_filter, left_in, right_in);
+
+            let mut constrant_consumer = ConstraintConsumer::new(
+                vec![GoldilocksField(2), GoldilocksField(3), GoldilocksField(5)],
+                F::ONE,
+                F::ONE,
+                F::ONE,
+            );
+            eval_packed_generic(&lv, &mut constrant_consumer);
+            assert!(constrant_consumer
+                .constraint_accs
+                .iter()
+                .any(|&acc| acc != F::ZERO));
+        }
+    }
 }
@@ -116,6 +116,16 @@ pub(crate) const RC_FREQUENCIES: usize = RANGE_COUNTER + 1;
 // These counter columns only used in SRA(V) and DIV, and do not check range
 pub(crate) const AUX_EXTRA: Range<usize> = RC_FREQUENCIES + 1..RC_FREQUENCIES + 9;
 
+// Signed-overflow check of ADD, ADDI and SUB: for each operand x, y, z of the identity
+// x + y = z + cy*2^32, the (range-checked) value 2*hi - sign*2^16 of its top limb, and
+// v^2 where v = sign(x) + sign(y) - 2*sign(z).
+pub(crate) const ADDCY_SIGN_AUX: [usize; 3] = [
+    AUX_INPUT_REGISTER_1.start,
+    AUX_INPUT_REGISTER_1.start + 1,
+    AUX_INPUT_REGISTER_2.start,
+];
+pub(crate) const ADDCY_SIGN_DIFF_SQ: usize = AUX_EXTRA.start;
+
 pub const NUM_ARITH_COLUMNS: usize = START_SHARED_COLS + NUM_SHARED_COLS + 10;
 
 // These counters are only be used in mul and div that use LO and HI.
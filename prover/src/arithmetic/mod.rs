@@ -120,9 +120,12 @@ impl BinaryOperator {
                 let out = input0 as u64 * input1 as u64;
                 (out as u32, (out >> 32) as u32) //lo,hi
             }
+            // The division table defines x / 0 as quotient 0 and remainder x.
+            BinaryOperator::DIV if input1 == 0 => (0, input0),
+            BinaryOperator::DIVU if input1 == 0 => (0, input0),
             BinaryOperator::DIV => (
-                ((input0 as i32) / (input1 as i32)) as u32, // lo
-                ((input0 as i32) % (input1 as i32)) as u32, // hi
+                (input0 as i32).wrapping_div(input1 as i32) as u32, // lo
+                (input0 as i32).wrapping_rem(input1 as i32) as u32, // hi
             ),
             BinaryOperator::DIVU => (input0 / input1, input0 % input1), //lo,hi
             BinaryOperator::MFHI
@@ -132,6 +135,20 @@ impl BinaryOperator {
         }
     }
 
+    /// Whether ADD, ADDI or SUB overflows as a signed operation, in which case MIPS raises an
+    /// integer overflow exception instead of writing the result. Always false for other operators.
+    pub(crate) fn signed_overflow(&self, input0: u32, input1: u32) -> bool {
+        match self {
+            BinaryOperator::ADD => (input0 as i32).checked_add(input1 as i32).is_none(),
+            BinaryOperator::ADDI => {
+                let sein = sign_extend::<16>(input1);
+                (input0 as i32).checked_add(sein as i32).is_none()
+            }
+            BinaryOperator::SUB => (input0 as i32).checked_sub(input1 as i32).is_none(),
+            _ => false,
+        }
+    }
+
     pub(crate) fn row_filter(&self) -> usize {
         match self {
             BinaryOperator::ADD => columns::IS_ADD,
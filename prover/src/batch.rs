@@ -0,0 +1,253 @@
+//! Batches of the proofs of different programs.
+//!
+//! The compressed proofs of all programs are block proofs of the same circuit, so a batch circuit
+//! verifies any of them. It commits to what each proof proved with the Merkle root of their
+//! leaves, the hashes of the image id, the userdata, the exit code and the journal digest of each,
+//! so that a rollup operator posts a single proof for heterogeneous workloads.
+
+use anyhow::{ensure, Result};
+use itertools::Itertools;
+use plonky2::field::extension::Extendable;
+use plonky2::hash::hash_types::{HashOut, HashOutTarget, RichField};
+use plonky2::iop::witness::{PartialWitness, WitnessWrite};
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::plonk::circuit_data::{CircuitConfig, CircuitData};
+use plonky2::plonk::config::{AlgebraicHasher, GenericConfig};
+use plonky2::plonk::proof::{ProofWithPublicInputs, ProofWithPublicInputsTarget};
+use serde::{Deserialize, Serialize};
+
+use crate::all_stark::NUM_PUBLIC_INPUT_USERDATA;
+use crate::proof::PublicValuesTarget;
+
+/// What the block proof of a program proved, which the batch commits to.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchEntry {
+    pub image_id: [u8; 32],
+    pub userdata: Vec<u8>,
+    pub exit_code: u32,
+    pub journal_digest: [u8; 32],
+}
+
+/// The public inputs of a block proof hashed into its leaf: the roots before, which are the image
+/// id, then the userdata, the exit code and the journal digest.
+fn leaf_inputs<T: Copy>(public_inputs: &[T]) -> Vec<T> {
+    public_inputs[..8]
+        .iter()
+        .chain(&public_inputs[16..PublicValuesTarget::SIZE])
+        .copied()
+        .collect()
+}
+
+impl BatchEntry {
+    pub fn from_proof<F, C, const D: usize>(proof: &ProofWithPublicInputs<F, C, D>) -> Result<Self>
+    where
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+    {
+        Self::from_public_inputs(&proof.public_inputs)
+    }
+
+    /// Reads the entry from the public inputs of a block proof.
+    pub fn from_public_inputs<F: RichField>(pis: &[F]) -> Result<Self> {
+        ensure!(
+            pis.len() >= PublicValuesTarget::SIZE,
+            "the proof has {} public inputs, fewer than the {} of a block proof",
+            pis.len(),
+            PublicValuesTarget::SIZE
+        );
+        let mut image_id = [0u8; 32];
+        for (bytes, limb) in image_id.chunks_exact_mut(4).zip(&pis[..8]) {
+            bytes.copy_from_slice(&(limb.to_canonical_u64() as u32).to_le_bytes());
+        }
+        let mut journal_digest = [0u8; 32];
+        for (bytes, limb) in journal_digest
+            .chunks_exact_mut(4)
+            .zip(&pis[17 + NUM_PUBLIC_INPUT_USERDATA..PublicValuesTarget::SIZE])
+        {
+            bytes.copy_from_slice(&(limb.to_canonical_u64() as u32).to_le_bytes());
+        }
+        Ok(Self {
+            image_id,
+            userdata: pis[16..16 + NUM_PUBLIC_INPUT_USERDATA]
+                .iter()
+                .map(|byte| byte.to_canonical_u64() as u8)
+                .collect(),
+            exit_code: pis[16 + NUM_PUBLIC_INPUT_USERDATA].to_canonical_u64() as u32,
+            journal_digest,
+        })
+    }
+
+    pub fn leaf<F: RichField, H: AlgebraicHasher<F>>(&self) -> HashOut<F> {
+        let limbs = |digest: &[u8; 32]| {
+            digest
+                .chunks_exact(4)
+                .map(|bytes| F::from_canonical_u32(u32::from_le_bytes(bytes.try_into().unwrap())))
+                .collect_vec()
+        };
+        let inputs = limbs(&self.image_id)
+            .into_iter()
+            .chain(self.userdata.iter().map(|&byte| F::from_canonical_u8(byte)))
+            .chain([F::from_canonical_u32(self.exit_code)])
+            .chain(limbs(&self.journal_digest))
+            .collect_vec();
+        H::hash_no_pad(&inputs)
+    }
+}
+
+/// The root of the Merkle tree of `leaves`, where the last node of a level with an odd number of
+/// nodes is promoted to the next level.
+fn merkle_root<T>(mut level: Vec<T>, mut hash: impl FnMut(T, T) -> T) -> T {
+    while level.len() > 1 {
+        let mut next = Vec::with_capacity((level.len() + 1) / 2);
+        let mut nodes = level.into_iter();
+        while let Some(lhs) = nodes.next() {
+            match nodes.next() {
+                Some(rhs) => next.push(hash(lhs, rhs)),
+                None => next.push(lhs),
+            }
+        }
+        level = next;
+    }
+    level.pop().unwrap()
+}
+
+/// The root the batch proof of `entries` has as public inputs.
+pub fn batch_root<F: RichField, H: AlgebraicHasher<F>>(entries: &[BatchEntry]) -> HashOut<F> {
+    let leaves = entries.iter().map(BatchEntry::leaf::<F, H>).collect();
+    merkle_root(leaves, |lhs, rhs| {
+        H::hash_no_pad(&[lhs.elements, rhs.elements].concat())
+    })
+}
+
+pub struct BatchCircuitData<F, C, const D: usize>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    pub circuit: CircuitData<F, C, D>,
+    proofs: Vec<ProofWithPublicInputsTarget<D>>,
+}
+
+impl<F, C, const D: usize> BatchCircuitData<F, C, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F> + 'static,
+    C::Hasher: AlgebraicHasher<F>,
+{
+    /// Builds the circuit verifying `num_proofs` proofs of the block circuit `block`.
+    pub fn new(block: &CircuitData<F, C, D>, num_proofs: usize) -> Self {
+        assert!(num_proofs > 0, "a batch has at least one proof");
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let block_vk = builder.constant_verifier_data(&block.verifier_only);
+
+        let mut proofs = Vec::with_capacity(num_proofs);
+        let mut leaves = Vec::with_capacity(num_proofs);
+        for _ in 0..num_proofs {
+            let proof = builder.add_virtual_proof_with_pis(&block.common);
+            builder.verify_proof::<C>(&proof, &block_vk, &block.common);
+            // A block proof verifies its parent with the verifier data of its public inputs,
+            // which must be those of the block circuit.
+            let expected_vk = block_vk.circuit_digest.elements.into_iter().chain(
+                block_vk
+                    .constants_sigmas_cap
+                    .0
+                    .iter()
+                    .flat_map(|hash| hash.elements),
+            );
+            for (&input, expected) in proof.public_inputs[PublicValuesTarget::SIZE..]
+                .iter()
+                .zip_eq(expected_vk)
+            {
+                builder.connect(input, expected);
+            }
+            leaves.push(
+                builder.hash_n_to_hash_no_pad::<C::Hasher>(leaf_inputs(&proof.public_inputs)),
+            );
+            proofs.push(proof);
+        }
+        let root = merkle_root(leaves, |lhs: HashOutTarget, rhs| {
+            builder.hash_n_to_hash_no_pad::<C::Hasher>([lhs.elements, rhs.elements].concat())
+        });
+        builder.register_public_inputs(&root.elements);
+
+        Self {
+            circuit: builder.build::<C>(),
+            proofs,
+        }
+    }
+
+    /// Proves the block proofs `proofs`, in order.
+    pub fn prove(
+        &self,
+        proofs: &[ProofWithPublicInputs<F, C, D>],
+    ) -> Result<ProofWithPublicInputs<F, C, D>> {
+        ensure!(
+            proofs.len() == self.proofs.len(),
+            "the batch circuit takes {} proofs",
+            self.proofs.len()
+        );
+        let mut inputs = PartialWitness::new();
+        for (target, proof) in self.proofs.iter().zip(proofs) {
+            inputs.set_proof_with_pis_target(target, proof);
+        }
+        self.circuit.prove(inputs)
+    }
+
+    /// Verifies the batch `proof`, and that it commits to `entries`.
+    pub fn verify(
+        &self,
+        proof: &ProofWithPublicInputs<F, C, D>,
+        entries: &[BatchEntry],
+    ) -> Result<()> {
+        ensure!(
+            proof.public_inputs == batch_root::<F, C::Hasher>(entries).elements,
+            "the batch does not commit to these entries"
+        );
+        self.circuit.verify(proof.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2::field::types::Field;
+    use plonky2::hash::poseidon::PoseidonHash;
+    use plonky2::plonk::config::{Hasher, PoseidonGoldilocksConfig};
+
+    use super::*;
+
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<2>>::F;
+
+    #[test]
+    fn test_batch_root() {
+        let mut public_inputs: Vec<F> = (0..PublicValuesTarget::SIZE as u64)
+            .map(F::from_canonical_u64)
+            .collect();
+        public_inputs[0] = F::from_canonical_u32(0xdeadbeef);
+        let entry = BatchEntry::from_public_inputs(&public_inputs).unwrap();
+        assert_eq!(entry.image_id[..4], 0xdeadbeefu32.to_le_bytes());
+        assert_eq!(entry.exit_code, 48);
+        assert_eq!(entry.journal_digest[..4], 49u32.to_le_bytes());
+        let leaf = entry.leaf::<F, PoseidonHash>();
+        assert_eq!(
+            leaf,
+            PoseidonHash::hash_no_pad(&leaf_inputs(&public_inputs))
+        );
+
+        let other = BatchEntry {
+            exit_code: 1,
+            ..entry.clone()
+        };
+        let hash = |lhs: HashOut<F>, rhs: HashOut<F>| {
+            PoseidonHash::hash_no_pad(&[lhs.elements, rhs.elements].concat())
+        };
+        let other_leaf = other.leaf::<F, PoseidonHash>();
+        assert_eq!(batch_root::<F, PoseidonHash>(&[entry.clone()]), leaf);
+        assert_eq!(
+            batch_root::<F, PoseidonHash>(&[entry.clone(), other.clone(), entry.clone()]),
+            hash(hash(leaf, other_leaf), leaf)
+        );
+
+        assert!(BatchEntry::from_public_inputs(&public_inputs[..16]).is_err());
+    }
+}
@@ -0,0 +1,67 @@
+use crate::util::impl_columns_view;
+use zkm_emulator::blake::BLAKE3_ROUNDS;
+
+/// The message schedules of BLAKE2b, round `r` using schedule `r % 10`.
+pub(crate) const NUM_BLAKE2B_SCHEDULES: usize = 10;
+
+/// Each round takes two rows, one mixing the columns of the state and one its diagonals.
+pub(crate) const NUM_BLAKE2B_STEPS: usize = 2 * NUM_BLAKE2B_SCHEDULES;
+pub(crate) const NUM_BLAKE3_STEPS: usize = 2 * BLAKE3_ROUNDS;
+
+/// The words computed by a call to `G`, in order: `a`, `d`, `c`, `b`, then `a`, `d`, `c`, `b`
+/// again.
+pub(crate) const NUM_MIX_WORDS: usize = 8;
+
+/// The carries of the four additions of a call to `G`, the low halves before the high halves:
+/// two bits each for `a`, which adds three terms, and one for `c`.
+pub(crate) const NUM_MIX_CARRIES: usize = 12;
+
+#[repr(C)]
+#[derive(Eq, PartialEq, Debug)]
+pub(crate) struct BlakeColumnsView<T: Copy> {
+    /// The rows of a BLAKE2b round with message schedule `s` have `blake2b_step[2 s]` set when
+    /// mixing the columns and `blake2b_step[2 s + 1]` when mixing the diagonals. Likewise for
+    /// round `r` of BLAKE3 and `blake3_step`. The last row of a call and padding rows have none.
+    pub blake2b_step: [T; NUM_BLAKE2B_STEPS],
+    pub blake3_step: [T; NUM_BLAKE3_STEPS],
+    /// 1 on the last row of a call, which holds the new hash state.
+    pub is_final: T,
+    /// 1 on the first row of a call, which reads the hash state and the block.
+    pub is_first: T,
+    /// Which of the two compression functions the call is.
+    pub is_blake2b: T,
+    pub is_blake3: T,
+
+    /// The timestamp of the CPU row making the call. The inputs are read at this timestamp, and
+    /// the new hash state written right after it.
+    pub timestamp: T,
+    /// The address of the hash state, for BLAKE3 the chaining value.
+    pub h_ptr: T,
+    /// The address of the block, which the other inputs follow.
+    pub input_ptr: T,
+
+    /// The words of the hash state read, as in memory: the 64-bit words of BLAKE2b high word
+    /// first, and for BLAKE3 the first eight.
+    pub h: [T; 16],
+    /// The words of the block read, laid out the same way.
+    pub m: [T; 32],
+
+    /// The rounds left, including the one of the row. Zero on the last row.
+    pub rounds_left: T,
+    pub rounds_left_inv: T,
+
+    /// The little-endian bits of the 16 words of the state at the start of the row, BLAKE3
+    /// using the low 32. They are ordered so that the call to `G` in slot `g` mixes words `g`,
+    /// `4 + g`, `8 + g` and `12 + g`: as they are on rows mixing the columns, with the
+    /// diagonals lined up on rows mixing the diagonals. On the last row, the final state in
+    /// order.
+    pub state: [[T; 64]; 16],
+
+    /// The little-endian bits of the words computed by the four calls to `G` of the row. On the
+    /// last row, the XOR of the two halves of the final state, the hash state read for BLAKE2b
+    /// or zero for BLAKE3, and the new hash state, eight words each.
+    pub mix: [[[T; 64]; NUM_MIX_WORDS]; 4],
+    pub mix_carries: [[T; NUM_MIX_CARRIES]; 4],
+}
+
+impl_columns_view!(BlakeColumnsView, NUM_BLAKE_COLUMNS, BLAKE_COL_MAP);
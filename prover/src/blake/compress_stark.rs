@@ -0,0 +1,1188 @@
+use std::borrow::{Borrow, BorrowMut};
+use std::marker::PhantomData;
+use std::ops::Range;
+
+use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::field::packed::PackedField;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::Field;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::timed;
+use plonky2::util::timing::TimingTree;
+use zkm_emulator::blake::{
+    BLAKE2B_IV, BLAKE2B_ROTATIONS, BLAKE2B_SIGMA, BLAKE3_IV, BLAKE3_ROTATIONS, BLAKE3_ROUNDS,
+    BLAKE3_SCHEDULE, MIX_INDICES,
+};
+
+use crate::blake::columns::*;
+use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::cross_table_lookup::{Column, Filter};
+use crate::evaluation_frame::{StarkEvaluationFrame, StarkFrame};
+use crate::keccak::logic::{xor_gen, xor_gen_circuit};
+use crate::memory::segments::Segment;
+use crate::stark::Stark;
+use crate::util::{limb_from_bits_le, limb_from_bits_le_recursive, trace_rows_to_poly_values};
+
+/// The memory accesses of a call. On its first row: the words of the hash state and of the
+/// block, then the BLAKE2b `t`, `f` and number of rounds, followed by the BLAKE3 counter, block
+/// length and flags. On its last row, the words of the new hash state, for BLAKE2b then BLAKE3.
+const H_READS: Range<usize> = 0..16;
+const M_READS: Range<usize> = 16..48;
+const BLAKE2B_PARAMS: Range<usize> = 48..54;
+const BLAKE3_PARAMS: Range<usize> = 54..58;
+const BLAKE2B_WRITES: Range<usize> = 58..74;
+pub(crate) const NUM_MEMORY_CTLS: usize = 82;
+
+/// Where the inputs following the block are: the BLAKE2b `t` after its 16 64-bit words, and
+/// the BLAKE3 counter after its 16 words.
+const BLAKE2B_PARAMS_OFFSET: usize = 128;
+const BLAKE3_PARAMS_OFFSET: usize = 64;
+
+/// The words a call to `G` works on: its inputs `a`, `b`, `c` and `d`, then the words of
+/// `mix` it computes.
+const A: usize = 0;
+const B: usize = 1;
+const C: usize = 2;
+const D: usize = 3;
+const A1: usize = 4;
+const D1: usize = 5;
+const C1: usize = 6;
+const B1: usize = 7;
+const A2: usize = 8;
+const D2: usize = 9;
+const C2: usize = 10;
+const B2: usize = 11;
+
+/// The outputs of a call to `G`, in the order of its inputs.
+const OUTPUTS: [usize; 4] = [A2, B2, C2, D2];
+
+/// An addition of `G`, modulo the word size.
+struct Addition {
+    sum: usize,
+    terms: [usize; 2],
+    /// The message word also added, `x` or `y`, to give `a`.
+    message: Option<usize>,
+    /// Where its carries start in `mix_carries`, the low half before the high one.
+    carry_start: usize,
+    carry_bits: usize,
+}
+
+const ADDITIONS: [Addition; 4] = [
+    Addition {
+        sum: A1,
+        terms: [A, B],
+        message: Some(0),
+        carry_start: 0,
+        carry_bits: 2,
+    },
+    Addition {
+        sum: C1,
+        terms: [C, D1],
+        message: None,
+        carry_start: 4,
+        carry_bits: 1,
+    },
+    Addition {
+        sum: A2,
+        terms: [A1, B1],
+        message: Some(1),
+        carry_start: 6,
+        carry_bits: 2,
+    },
+    Addition {
+        sum: C2,
+        terms: [C1, D2],
+        message: None,
+        carry_start: 10,
+        carry_bits: 1,
+    },
+];
+
+/// The XORs of `G`, as the word computed then the two words XORed, each rotated by the next
+/// rotation of the compression function.
+const XORS: [[usize; 3]; 4] = [[D1, D, A1], [B1, B, C1], [D2, D1, A2], [B2, B1, C2]];
+
+/// The words of `mix` holding, on the last row of a call, the XOR of the two halves of the
+/// final state, the hash state XORed into it, and the new hash state.
+const HALVES: usize = 0;
+const HASH: usize = 1;
+const NEW_HASH: usize = 2;
+
+pub(crate) fn ctl_looked_data<F: Field>() -> Vec<Column<F>> {
+    let cols = BLAKE_COL_MAP;
+    Column::singles([cols.h_ptr, cols.input_ptr, cols.timestamp]).collect()
+}
+
+pub(crate) fn ctl_looked_filter_blake2b<F: Field>() -> Filter<F> {
+    let cols = BLAKE_COL_MAP;
+    Filter::new(
+        vec![(
+            Column::single(cols.is_first),
+            Column::single(cols.is_blake2b),
+        )],
+        vec![],
+    )
+}
+
+pub(crate) fn ctl_looked_filter_blake3<F: Field>() -> Filter<F> {
+    let cols = BLAKE_COL_MAP;
+    Filter::new(
+        vec![(
+            Column::single(cols.is_first),
+            Column::single(cols.is_blake3),
+        )],
+        vec![],
+    )
+}
+
+/// The value of a 32-bit half of a word from its bits, `half` 1 being the high one, each bit
+/// first flipped where `mask` has it set.
+fn half_word<F: Field>(bits: &[usize; 64], half: usize, mask: u32) -> Column<F> {
+    Column::linear_combination_with_constant(
+        bits[32 * half..32 * half + 32]
+            .iter()
+            .enumerate()
+            .map(|(k, &c)| {
+                let coeff = F::from_canonical_u64(1 << k);
+                (c, if (mask >> k) & 1 == 1 { -coeff } else { coeff })
+            }),
+        F::from_canonical_u32(mask),
+    )
+}
+
+pub(crate) fn ctl_looking_memory<F: Field>(i: usize) -> Vec<Column<F>> {
+    let cols = BLAKE_COL_MAP;
+
+    let (is_read, ptr, offset, value) = if H_READS.contains(&i) {
+        let j = i - H_READS.start;
+        (true, cols.h_ptr, 4 * j, Column::single(cols.h[j]))
+    } else if M_READS.contains(&i) {
+        let j = i - M_READS.start;
+        (true, cols.input_ptr, 4 * j, Column::single(cols.m[j]))
+    } else if BLAKE2B_PARAMS.contains(&i) {
+        // `t` is XORed into words 12 and 13 of the initial state, high words first, and `f`
+        // flips all the bits of word 14. The number of rounds starts the round counter.
+        let j = i - BLAKE2B_PARAMS.start;
+        let value = match j {
+            0..=3 => {
+                let iv = BLAKE2B_IV[4 + j / 2];
+                let half = 1 - j % 2;
+                half_word(&cols.state[12 + j / 2], half, (iv >> (32 * half)) as u32)
+            }
+            4 => Column::linear_combination_with_constant([(cols.state[14][0], -F::ONE)], F::ONE),
+            _ => Column::single(cols.rounds_left),
+        };
+        (true, cols.input_ptr, BLAKE2B_PARAMS_OFFSET + 4 * j, value)
+    } else if BLAKE3_PARAMS.contains(&i) {
+        // They are words 12 to 15 of the initial state.
+        let j = i - BLAKE3_PARAMS.start;
+        let value = half_word(&cols.state[12 + j], 0, 0);
+        (true, cols.input_ptr, BLAKE3_PARAMS_OFFSET + 4 * j, value)
+    } else if BLAKE2B_WRITES.contains(&i) {
+        let j = i - BLAKE2B_WRITES.start;
+        let value = half_word(&cols.mix[NEW_HASH][j / 2], 1 - j % 2, 0);
+        (false, cols.h_ptr, 4 * j, value)
+    } else {
+        let j = i - BLAKE2B_WRITES.end;
+        let value = half_word(&cols.mix[NEW_HASH][j], 0, 0);
+        (false, cols.h_ptr, 4 * j, value)
+    };
+
+    let res = vec![
+        Column::constant(F::from_bool(is_read)),
+        Column::zero(), // context
+        Column::constant(F::from_canonical_usize(Segment::Code as usize)),
+        Column::linear_combination_with_constant([(ptr, F::ONE)], F::from_canonical_usize(offset)),
+        value,
+        Column::linear_combination_with_constant(
+            [(cols.timestamp, F::ONE)],
+            F::from_bool(!is_read),
+        ),
+    ];
+
+    assert_eq!(
+        res.len(),
+        crate::memory::memory_stark::ctl_data::<F>().len()
+    );
+    res
+}
+
+pub(crate) fn ctl_looking_memory_filter<F: Field>(i: usize) -> Filter<F> {
+    let cols = BLAKE_COL_MAP;
+    // The first eight words of the hash state and the first sixteen of the block are read by
+    // both compression functions.
+    if i < H_READS.start + 8 || (M_READS.start..M_READS.start + 16).contains(&i) {
+        return Filter::new_simple(Column::single(cols.is_first));
+    }
+    let row = if i < BLAKE2B_WRITES.start {
+        cols.is_first
+    } else {
+        cols.is_final
+    };
+    let is_blake3 = BLAKE3_PARAMS.contains(&i) || i >= BLAKE2B_WRITES.end;
+    let kind = if is_blake3 {
+        cols.is_blake3
+    } else {
+        cols.is_blake2b
+    };
+    Filter::new(vec![(Column::single(row), Column::single(kind))], vec![])
+}
+
+/// The inputs of a compression.
+#[derive(Clone, Debug)]
+pub(crate) enum BlakeCompression {
+    Blake2b {
+        rounds: u32,
+        h: [u64; 8],
+        m: [u64; 16],
+        t: [u64; 2],
+        f: bool,
+    },
+    Blake3 {
+        cv: [u32; 8],
+        block: [u32; 16],
+        counter: u64,
+        block_len: u32,
+        flags: u32,
+    },
+}
+
+/// Information about a `BLAKE2B_COMPRESS` or `BLAKE3_COMPRESS` call.
+#[derive(Clone, Debug)]
+pub(crate) struct BlakeCompressOp {
+    /// The address of the hash state.
+    pub(crate) h_ptr: usize,
+    /// The address of the block and the inputs following it.
+    pub(crate) input_ptr: usize,
+    /// The timestamp of the CPU row making the call.
+    pub(crate) timestamp: usize,
+    pub(crate) compression: BlakeCompression,
+}
+
+impl BlakeCompressOp {
+    /// The rows of the call: two per round, then the one holding the new hash state.
+    pub(crate) fn num_rows(&self) -> usize {
+        let rounds = match self.compression {
+            BlakeCompression::Blake2b { rounds, .. } => rounds as usize,
+            BlakeCompression::Blake3 { .. } => BLAKE3_ROUNDS,
+        };
+        2 * rounds + 1
+    }
+}
+
+fn u64_to_bits_le<F: Field>(x: u64) -> [F; 64] {
+    core::array::from_fn(|i| F::from_bool((x >> i) & 1 == 1))
+}
+
+fn rotate_right(x: u64, n: u32, word_bits: u32) -> u64 {
+    if word_bits == 64 {
+        x.rotate_right(n)
+    } else {
+        (x as u32).rotate_right(n) as u64
+    }
+}
+
+/// Adds the terms modulo `2^word_bits`, with the carries out of the low and high halves.
+fn add(terms: [u64; 3], word_bits: u32) -> (u64, [u64; 2]) {
+    let lo: u64 = terms.iter().map(|&x| x as u32 as u64).sum();
+    let hi: u64 = terms.iter().map(|&x| x >> 32).sum::<u64>() + (lo >> 32);
+    if word_bits == 64 {
+        (
+            (hi as u32 as u64) << 32 | lo as u32 as u64,
+            [lo >> 32, hi >> 32],
+        )
+    } else {
+        (lo as u32 as u64, [lo >> 32, 0])
+    }
+}
+
+fn low_half<P: PackedField>(word: &[P; 64]) -> P {
+    limb_from_bits_le(word[..32].iter().copied())
+}
+
+fn high_half<P: PackedField>(word: &[P; 64]) -> P {
+    limb_from_bits_le(word[32..].iter().copied())
+}
+
+fn low_half_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    word: &[ExtensionTarget<D>; 64],
+) -> ExtensionTarget<D> {
+    limb_from_bits_le_recursive(builder, word[..32].iter().copied())
+}
+
+fn high_half_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    word: &[ExtensionTarget<D>; 64],
+) -> ExtensionTarget<D> {
+    limb_from_bits_le_recursive(builder, word[32..].iter().copied())
+}
+
+/// The index in the message of the word `x` (`i` 0) or `y` (`i` 1) of the call to `G` in slot
+/// `g`, on a row mixing the columns (`half` 0) or the diagonals (`half` 1).
+fn message_index(schedule: &[usize; 16], half: usize, g: usize, i: usize) -> usize {
+    schedule[8 * half + 2 * g + i]
+}
+
+/// The low and high halves of the message word `x` or `y` of the call to `G` in slot `g`,
+/// selected by the step flags.
+fn message_word<P: PackedField>(lv: &BlakeColumnsView<P>, g: usize, i: usize) -> (P, P) {
+    let mut lo = P::ZEROS;
+    let mut hi = P::ZEROS;
+    for (s, schedule) in BLAKE2B_SIGMA.iter().enumerate() {
+        for half in 0..2 {
+            let step = lv.blake2b_step[2 * s + half];
+            let j = message_index(schedule, half, g, i);
+            lo += step * lv.m[2 * j + 1];
+            hi += step * lv.m[2 * j];
+        }
+    }
+    for (r, schedule) in BLAKE3_SCHEDULE.iter().enumerate() {
+        for half in 0..2 {
+            let step = lv.blake3_step[2 * r + half];
+            lo += step * lv.m[message_index(schedule, half, g, i)];
+        }
+    }
+    (lo, hi)
+}
+
+fn message_word_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    lv: &BlakeColumnsView<ExtensionTarget<D>>,
+    g: usize,
+    i: usize,
+) -> (ExtensionTarget<D>, ExtensionTarget<D>) {
+    let mut lo = builder.zero_extension();
+    let mut hi = builder.zero_extension();
+    for (s, schedule) in BLAKE2B_SIGMA.iter().enumerate() {
+        for half in 0..2 {
+            let step = lv.blake2b_step[2 * s + half];
+            let j = message_index(schedule, half, g, i);
+            lo = builder.mul_add_extension(step, lv.m[2 * j + 1], lo);
+            hi = builder.mul_add_extension(step, lv.m[2 * j], hi);
+        }
+    }
+    for (r, schedule) in BLAKE3_SCHEDULE.iter().enumerate() {
+        for half in 0..2 {
+            let step = lv.blake3_step[2 * r + half];
+            let j = message_index(schedule, half, g, i);
+            lo = builder.mul_add_extension(step, lv.m[j], lo);
+        }
+    }
+    (lo, hi)
+}
+
+/// The words the call to `G` in slot `g` works on, indexed as `A` to `B2`.
+fn mix_words<T: Copy>(lv: &BlakeColumnsView<T>, g: usize) -> [&[T; 64]; 12] {
+    core::array::from_fn(|i| {
+        if i < 4 {
+            &lv.state[4 * i + g]
+        } else {
+            &lv.mix[g][i - 4]
+        }
+    })
+}
+
+#[derive(Copy, Clone, Default)]
+pub struct BlakeCompressStark<F, const D: usize> {
+    f: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> BlakeCompressStark<F, D> {
+    pub(crate) fn generate_trace(
+        &self,
+        operations: Vec<BlakeCompressOp>,
+        min_rows: usize,
+        timing: &mut TimingTree,
+    ) -> Vec<PolynomialValues<F>> {
+        let trace_rows = timed!(
+            timing,
+            "generate trace rows",
+            self.generate_trace_rows(operations, min_rows)
+        );
+        timed!(
+            timing,
+            "convert to PolynomialValues",
+            trace_rows_to_poly_values(trace_rows)
+        )
+    }
+
+    fn generate_trace_rows(
+        &self,
+        operations: Vec<BlakeCompressOp>,
+        min_rows: usize,
+    ) -> Vec<[F; NUM_BLAKE_COLUMNS]> {
+        let num_rows = operations
+            .iter()
+            .map(BlakeCompressOp::num_rows)
+            .sum::<usize>()
+            .max(min_rows)
+            .next_power_of_two();
+        let mut rows = Vec::with_capacity(num_rows);
+        for op in operations {
+            rows.extend(self.generate_rows_for_op(op));
+        }
+        rows.resize(num_rows, [F::ZERO; NUM_BLAKE_COLUMNS]);
+        rows
+    }
+
+    fn generate_rows_for_op(&self, op: BlakeCompressOp) -> Vec<[F; NUM_BLAKE_COLUMNS]> {
+        let mut template = BlakeColumnsView::default();
+        template.timestamp = F::from_canonical_usize(op.timestamp);
+        template.h_ptr = F::from_canonical_usize(op.h_ptr);
+        template.input_ptr = F::from_canonical_usize(op.input_ptr);
+
+        // The initial state, the hash state to XOR into the final one, the word size, the
+        // rotations and the message schedule of round `r`.
+        let num_rounds = (op.num_rows() - 1) / 2;
+        let mut v = [0u64; 16];
+        let (hash, word_bits, rotations, schedules): ([u64; 8], u32, [u32; 4], &[[usize; 16]]) =
+            match &op.compression {
+                BlakeCompression::Blake2b { h, m, t, f, .. } => {
+                    template.is_blake2b = F::ONE;
+                    for (words, x) in template.h.chunks_mut(2).zip(h) {
+                        words[0] = F::from_canonical_u32((x >> 32) as u32);
+                        words[1] = F::from_canonical_u32(*x as u32);
+                    }
+                    for (words, x) in template.m.chunks_mut(2).zip(m) {
+                        words[0] = F::from_canonical_u32((x >> 32) as u32);
+                        words[1] = F::from_canonical_u32(*x as u32);
+                    }
+                    v[..8].copy_from_slice(h);
+                    v[8..].copy_from_slice(&BLAKE2B_IV);
+                    v[12] ^= t[0];
+                    v[13] ^= t[1];
+                    if *f {
+                        v[14] = !v[14];
+                    }
+                    (*h, 64, BLAKE2B_ROTATIONS, &BLAKE2B_SIGMA)
+                }
+                BlakeCompression::Blake3 {
+                    cv,
+                    block,
+                    counter,
+                    block_len,
+                    flags,
+                } => {
+                    template.is_blake3 = F::ONE;
+                    for (word, &x) in template.h.iter_mut().zip(cv) {
+                        *word = F::from_canonical_u32(x);
+                    }
+                    for (word, &x) in template.m.iter_mut().zip(block) {
+                        *word = F::from_canonical_u32(x);
+                    }
+                    for (j, &x) in cv.iter().enumerate() {
+                        v[j] = x as u64;
+                    }
+                    for (j, &x) in BLAKE3_IV[..4].iter().enumerate() {
+                        v[8 + j] = x as u64;
+                    }
+                    v[12] = *counter as u32 as u64;
+                    v[13] = counter >> 32;
+                    v[14] = *block_len as u64;
+                    v[15] = *flags as u64;
+                    ([0; 8], 32, BLAKE3_ROTATIONS, &BLAKE3_SCHEDULE)
+                }
+            };
+        let template: [F; NUM_BLAKE_COLUMNS] = template.into();
+        let message: [u64; 16] = match &op.compression {
+            BlakeCompression::Blake2b { m, .. } => *m,
+            BlakeCompression::Blake3 { block, .. } => block.map(|x| x as u64),
+        };
+
+        let mut rows = Vec::with_capacity(op.num_rows());
+        for r in 0..num_rounds {
+            for half in 0..2 {
+                let mut row = template;
+                let view: &mut BlakeColumnsView<F> = row.borrow_mut();
+                if word_bits == 64 {
+                    view.blake2b_step[2 * (r % NUM_BLAKE2B_SCHEDULES) + half] = F::ONE;
+                } else {
+                    view.blake3_step[2 * r + half] = F::ONE;
+                }
+                view.rounds_left = F::from_canonical_usize(num_rounds - r);
+                view.rounds_left_inv = view.rounds_left.inverse();
+
+                for g in 0..4 {
+                    let indices = MIX_INDICES[4 * half + g];
+                    let mut words = [0u64; 12];
+                    for (k, &index) in indices.iter().enumerate() {
+                        words[k] = v[index];
+                        view.state[4 * k + g] = u64_to_bits_le(v[index]);
+                    }
+                    let schedule = &schedules[r % schedules.len()];
+                    let x = message[message_index(schedule, half, g, 0)];
+                    let y = message[message_index(schedule, half, g, 1)];
+
+                    // The steps of `G` alternate between an addition and an XOR.
+                    for (addition, (xor, rotation)) in
+                        ADDITIONS.iter().zip(XORS.iter().zip(rotations))
+                    {
+                        let m = match addition.message {
+                            Some(0) => x,
+                            Some(_) => y,
+                            None => 0,
+                        };
+                        let [t0, t1] = addition.terms.map(|t| words[t]);
+                        let (sum, carries) = add([t0, t1, m], word_bits);
+                        words[addition.sum] = sum;
+                        for (k, carry) in carries.into_iter().enumerate() {
+                            for b in 0..addition.carry_bits {
+                                view.mix_carries[g]
+                                    [addition.carry_start + k * addition.carry_bits + b] =
+                                    F::from_bool((carry >> b) & 1 == 1);
+                            }
+                        }
+
+                        let [out, x0, x1] = *xor;
+                        words[out] = rotate_right(words[x0] ^ words[x1], rotation, word_bits);
+                    }
+
+                    for (j, &word) in words[4..].iter().enumerate() {
+                        view.mix[g][j] = u64_to_bits_le(word);
+                    }
+                    for (&index, output) in indices.iter().zip(OUTPUTS) {
+                        v[index] = words[output];
+                    }
+                }
+                rows.push(row);
+            }
+        }
+
+        let mut row = template;
+        let view: &mut BlakeColumnsView<F> = row.borrow_mut();
+        view.is_final = F::ONE;
+        view.state = v.map(u64_to_bits_le);
+        for i in 0..8 {
+            let halves = v[i] ^ v[i + 8];
+            view.mix[HALVES][i] = u64_to_bits_le(halves);
+            view.mix[HASH][i] = u64_to_bits_le(hash[i]);
+            view.mix[NEW_HASH][i] = u64_to_bits_le(hash[i] ^ halves);
+        }
+        rows.push(row);
+
+        let first: &mut BlakeColumnsView<F> = rows[0].borrow_mut();
+        first.is_first = F::ONE;
+        rows
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for BlakeCompressStark<F, D> {
+    type EvaluationFrame<FE, P, const D2: usize>
+        = StarkFrame<P, NUM_BLAKE_COLUMNS>
+    where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>;
+
+    type EvaluationFrameTarget = StarkFrame<ExtensionTarget<D>, NUM_BLAKE_COLUMNS>;
+
+    fn eval_packed_generic<FE, P, const D2: usize>(
+        &self,
+        vars: &Self::EvaluationFrame<FE, P, D2>,
+        yield_constr: &mut ConstraintConsumer<P>,
+    ) where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>,
+    {
+        let local_values: &[P; NUM_BLAKE_COLUMNS] = vars.get_local_values().try_into().unwrap();
+        let lv: &BlakeColumnsView<P> = local_values.borrow();
+        let next_values: &[P; NUM_BLAKE_COLUMNS] = vars.get_next_values().try_into().unwrap();
+        let nv: &BlakeColumnsView<P> = next_values.borrow();
+
+        let two_pow_32 = FE::from_canonical_u64(1 << 32);
+
+        let blake2b_rounds: P = lv.blake2b_step.iter().copied().sum();
+        let blake3_rounds: P = lv.blake3_step.iter().copied().sum();
+        let is_round = blake2b_rounds + blake3_rounds;
+        let is_columns: P = lv
+            .blake2b_step
+            .iter()
+            .chain(&lv.blake3_step)
+            .step_by(2)
+            .copied()
+            .sum();
+        let is_diagonals = is_round - is_columns;
+        let is_real = is_round + lv.is_final;
+        let next_is_round: P = nv.blake2b_step.iter().chain(&nv.blake3_step).copied().sum();
+        let next_is_real = next_is_round + nv.is_final;
+
+        // The flags are boolean, at most one step is set, and the kind matches the steps.
+        for &flag in lv.blake2b_step.iter().chain(&lv.blake3_step).chain([
+            &lv.is_final,
+            &lv.is_first,
+            &lv.is_blake2b,
+            &lv.is_blake3,
+        ]) {
+            yield_constr.constraint(flag * (flag - P::ONES));
+        }
+        yield_constr.constraint(is_real * (is_real - P::ONES));
+        yield_constr.constraint(lv.is_blake2b + lv.is_blake3 - is_real);
+        yield_constr.constraint(blake2b_rounds * lv.is_blake3);
+        yield_constr.constraint(blake3_rounds * lv.is_blake2b);
+        yield_constr.constraint(lv.is_first * (is_real - P::ONES));
+
+        // A call starts on the first row of the trace or right after another, with its first
+        // round if any, and the rows of its rounds follow each other up to its last row. Padding
+        // rows come last.
+        yield_constr.constraint_first_row(lv.is_first - is_real);
+        yield_constr.constraint_last_row(is_round);
+        yield_constr.constraint(lv.is_first * (lv.blake2b_step[0] + lv.blake3_step[0] - is_round));
+        for s in 0..NUM_BLAKE2B_SCHEDULES {
+            yield_constr.constraint_transition(nv.blake2b_step[2 * s + 1] - lv.blake2b_step[2 * s]);
+            let next = nv.blake2b_step[2 * ((s + 1) % NUM_BLAKE2B_SCHEDULES)];
+            yield_constr.constraint_transition(lv.blake2b_step[2 * s + 1] * (next - next_is_round));
+        }
+        for r in 0..BLAKE3_ROUNDS {
+            yield_constr.constraint_transition(nv.blake3_step[2 * r + 1] - lv.blake3_step[2 * r]);
+            if r + 1 < BLAKE3_ROUNDS {
+                let next = nv.blake3_step[2 * r + 2];
+                yield_constr
+                    .constraint_transition(lv.blake3_step[2 * r + 1] * (next - next_is_round));
+            }
+        }
+        yield_constr.constraint_transition(is_round * (next_is_real - P::ONES));
+        yield_constr.constraint_transition(is_round * nv.is_first);
+        yield_constr.constraint_transition(lv.is_final * (next_is_real - nv.is_first));
+        yield_constr.constraint_transition((P::ONES - is_real) * next_is_real);
+
+        // The round counter is nonzero on the rows of the rounds, drops by one after the
+        // diagonals, and is zero on the last row. BLAKE3 has seven rounds.
+        yield_constr.constraint(is_round * (lv.rounds_left * lv.rounds_left_inv - P::ONES));
+        yield_constr.constraint(lv.is_final * lv.rounds_left);
+        yield_constr.constraint_transition(is_columns * (nv.rounds_left - lv.rounds_left));
+        yield_constr
+            .constraint_transition(is_diagonals * (nv.rounds_left - lv.rounds_left + P::ONES));
+        let num_rounds = FE::from_canonical_usize(BLAKE3_ROUNDS);
+        yield_constr.constraint(lv.is_first * lv.is_blake3 * (lv.rounds_left - num_rounds));
+
+        // Within a call, the addresses, the timestamp, the kind and the inputs read stay the
+        // same.
+        for (&next, &local) in [
+            (&nv.timestamp, &lv.timestamp),
+            (&nv.h_ptr, &lv.h_ptr),
+            (&nv.input_ptr, &lv.input_ptr),
+            (&nv.is_blake2b, &lv.is_blake2b),
+        ]
+        .into_iter()
+        .chain(nv.h.iter().zip(&lv.h))
+        .chain(nv.m.iter().zip(&lv.m))
+        {
+            yield_constr.constraint_transition(is_round * (next - local));
+        }
+
+        for &bit in lv
+            .state
+            .iter()
+            .flatten()
+            .chain(lv.mix.iter().flatten().flatten())
+            .chain(lv.mix_carries.iter().flatten())
+        {
+            yield_constr.constraint(bit * (bit - P::ONES));
+        }
+
+        // The initial state: the hash state read, then the IV. For BLAKE2b, `t` and `f` are
+        // XORed into words 12 to 14, and read from their bits. For BLAKE3, words 12 to 15 are the
+        // inputs following the block, read from their bits.
+        let first_blake2b = lv.is_first * lv.is_blake2b;
+        for i in 0..8 {
+            let h = lv.is_blake2b * lv.h[2 * i + 1] + lv.is_blake3 * lv.h[i];
+            yield_constr.constraint(lv.is_first * (low_half(&lv.state[i]) - h));
+            yield_constr.constraint(first_blake2b * (high_half(&lv.state[i]) - lv.h[2 * i]));
+        }
+        for i in 8..12 {
+            let iv = lv.is_blake2b * FE::from_canonical_u32(BLAKE2B_IV[i - 8] as u32)
+                + lv.is_blake3 * FE::from_canonical_u32(BLAKE3_IV[i - 8]);
+            yield_constr.constraint(lv.is_first * (low_half(&lv.state[i]) - iv));
+            let iv_hi = FE::from_canonical_u32((BLAKE2B_IV[i - 8] >> 32) as u32);
+            yield_constr.constraint(first_blake2b * (high_half(&lv.state[i]) - iv_hi));
+        }
+        let f = P::ONES - lv.state[14][0];
+        for (half, word) in [low_half(&lv.state[14]), high_half(&lv.state[14])]
+            .into_iter()
+            .enumerate()
+        {
+            let iv = (BLAKE2B_IV[6] >> (32 * half)) as u32;
+            let flipped = FE::from_canonical_u32(!iv) - FE::from_canonical_u32(iv);
+            let iv = FE::from_canonical_u32(iv);
+            yield_constr.constraint(first_blake2b * (word - iv - f * flipped));
+        }
+        for (half, word) in [low_half(&lv.state[15]), high_half(&lv.state[15])]
+            .into_iter()
+            .enumerate()
+        {
+            let iv = FE::from_canonical_u32((BLAKE2B_IV[7] >> (32 * half)) as u32);
+            yield_constr.constraint(first_blake2b * (word - iv));
+        }
+
+        // The outputs of `G` go to the next row, with the diagonals lined up after the columns
+        // and back in order after the diagonals.
+        for g in 0..4 {
+            for (k, &output) in OUTPUTS.iter().enumerate() {
+                let output = mix_words(lv, g)[output];
+                let after_columns = &nv.state[4 * k + (g + 4 - k) % 4];
+                let after_diagonals = &nv.state[4 * k + (g + k) % 4];
+                for i in 0..64 {
+                    yield_constr.constraint_transition(is_columns * (after_columns[i] - output[i]));
+                    yield_constr
+                        .constraint_transition(is_diagonals * (after_diagonals[i] - output[i]));
+                }
+            }
+        }
+
+        // The four calls to `G` of the row.
+        for g in 0..4 {
+            let words = mix_words(lv, g);
+            let carries = &lv.mix_carries[g];
+            let messages = [message_word(lv, g, 0), message_word(lv, g, 1)];
+            for addition in ADDITIONS.iter() {
+                let carry = |k: usize| {
+                    let start = addition.carry_start + k * addition.carry_bits;
+                    limb_from_bits_le(carries[start..start + addition.carry_bits].iter().copied())
+                };
+                let (m_lo, m_hi) = addition
+                    .message
+                    .map_or((P::ZEROS, P::ZEROS), |i| messages[i]);
+                let [x, y] = addition.terms.map(|t| words[t]);
+                let sum = words[addition.sum];
+
+                let lo = low_half(sum) + carry(0) * two_pow_32 - low_half(x) - low_half(y) - m_lo;
+                yield_constr.constraint(is_round * lo);
+                let hi = high_half(sum) + carry(1) * two_pow_32
+                    - high_half(x)
+                    - high_half(y)
+                    - m_hi
+                    - carry(0);
+                yield_constr.constraint(blake2b_rounds * hi);
+            }
+            for (k, &[out, x, y]) in XORS.iter().enumerate() {
+                let [out, x, y] = [words[out], words[x], words[y]];
+                for i in 0..64 {
+                    let j = (i + BLAKE2B_ROTATIONS[k] as usize) % 64;
+                    yield_constr.constraint(blake2b_rounds * (out[i] - xor_gen(x[j], y[j])));
+                }
+                for i in 0..32 {
+                    let j = (i + BLAKE3_ROTATIONS[k] as usize) % 32;
+                    yield_constr.constraint(blake3_rounds * (out[i] - xor_gen(x[j], y[j])));
+                }
+            }
+        }
+
+        // The new hash state: the two halves of the final state XORed together, and with the
+        // hash state read for BLAKE2b.
+        let [halves, hash, new_hash] = [&lv.mix[HALVES], &lv.mix[HASH], &lv.mix[NEW_HASH]];
+        for i in 0..8 {
+            for b in 0..64 {
+                let xor = xor_gen(lv.state[i][b], lv.state[i + 8][b]);
+                yield_constr.constraint(lv.is_final * (halves[i][b] - xor));
+                let xor = xor_gen(hash[i][b], halves[i][b]);
+                yield_constr.constraint(lv.is_final * (new_hash[i][b] - xor));
+            }
+            let h_lo = lv.is_blake2b * lv.h[2 * i + 1];
+            yield_constr.constraint(lv.is_final * (low_half(&hash[i]) - h_lo));
+            let h_hi = lv.is_blake2b * lv.h[2 * i];
+            yield_constr.constraint(lv.is_final * (high_half(&hash[i]) - h_hi));
+        }
+    }
+
+    fn eval_ext_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: &Self::EvaluationFrameTarget,
+        yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+    ) {
+        let local_values: &[ExtensionTarget<D>; NUM_BLAKE_COLUMNS] =
+            vars.get_local_values().try_into().unwrap();
+        let lv: &BlakeColumnsView<ExtensionTarget<D>> = local_values.borrow();
+        let next_values: &[ExtensionTarget<D>; NUM_BLAKE_COLUMNS] =
+            vars.get_next_values().try_into().unwrap();
+        let nv: &BlakeColumnsView<ExtensionTarget<D>> = next_values.borrow();
+
+        let two_pow_32 = F::from_canonical_u64(1 << 32);
+        let one = builder.one_extension();
+
+        let blake2b_rounds = builder.add_many_extension(lv.blake2b_step);
+        let blake3_rounds = builder.add_many_extension(lv.blake3_step);
+        let is_round = builder.add_extension(blake2b_rounds, blake3_rounds);
+        let is_columns = builder.add_many_extension(
+            lv.blake2b_step
+                .iter()
+                .chain(&lv.blake3_step)
+                .step_by(2)
+                .copied(),
+        );
+        let is_diagonals = builder.sub_extension(is_round, is_columns);
+        let is_real = builder.add_extension(is_round, lv.is_final);
+        let next_is_round =
+            builder.add_many_extension(nv.blake2b_step.iter().chain(&nv.blake3_step).copied());
+        let next_is_real = builder.add_extension(next_is_round, nv.is_final);
+
+        for &flag in lv.blake2b_step.iter().chain(&lv.blake3_step).chain([
+            &lv.is_final,
+            &lv.is_first,
+            &lv.is_blake2b,
+            &lv.is_blake3,
+        ]) {
+            let constraint = builder.mul_sub_extension(flag, flag, flag);
+            yield_constr.constraint(builder, constraint);
+        }
+        let constraint = builder.mul_sub_extension(is_real, is_real, is_real);
+        yield_constr.constraint(builder, constraint);
+        let kinds = builder.add_extension(lv.is_blake2b, lv.is_blake3);
+        let constraint = builder.sub_extension(kinds, is_real);
+        yield_constr.constraint(builder, constraint);
+        let constraint = builder.mul_extension(blake2b_rounds, lv.is_blake3);
+        yield_constr.constraint(builder, constraint);
+        let constraint = builder.mul_extension(blake3_rounds, lv.is_blake2b);
+        yield_constr.constraint(builder, constraint);
+        let constraint = builder.mul_sub_extension(lv.is_first, is_real, lv.is_first);
+        yield_constr.constraint(builder, constraint);
+
+        let constraint = builder.sub_extension(lv.is_first, is_real);
+        yield_constr.constraint_first_row(builder, constraint);
+        yield_constr.constraint_last_row(builder, is_round);
+        let first_steps = builder.add_extension(lv.blake2b_step[0], lv.blake3_step[0]);
+        let diff = builder.sub_extension(first_steps, is_round);
+        let constraint = builder.mul_extension(lv.is_first, diff);
+        yield_constr.constraint(builder, constraint);
+        for s in 0..NUM_BLAKE2B_SCHEDULES {
+            let constraint =
+                builder.sub_extension(nv.blake2b_step[2 * s + 1], lv.blake2b_step[2 * s]);
+            yield_constr.constraint_transition(builder, constraint);
+            let next = nv.blake2b_step[2 * ((s + 1) % NUM_BLAKE2B_SCHEDULES)];
+            let diff = builder.sub_extension(next, next_is_round);
+            let constraint = builder.mul_extension(lv.blake2b_step[2 * s + 1], diff);
+            yield_constr.constraint_transition(builder, constraint);
+        }
+        for r in 0..BLAKE3_ROUNDS {
+            let constraint =
+                builder.sub_extension(nv.blake3_step[2 * r + 1], lv.blake3_step[2 * r]);
+            yield_constr.constraint_transition(builder, constraint);
+            if r + 1 < BLAKE3_ROUNDS {
+                let diff = builder.sub_extension(nv.blake3_step[2 * r + 2], next_is_round);
+                let constraint = builder.mul_extension(lv.blake3_step[2 * r + 1], diff);
+                yield_constr.constraint_transition(builder, constraint);
+            }
+        }
+        let constraint = builder.mul_sub_extension(is_round, next_is_real, is_round);
+        yield_constr.constraint_transition(builder, constraint);
+        let constraint = builder.mul_extension(is_round, nv.is_first);
+        yield_constr.constraint_transition(builder, constraint);
+        let diff = builder.sub_extension(next_is_real, nv.is_first);
+        let constraint = builder.mul_extension(lv.is_final, diff);
+        yield_constr.constraint_transition(builder, constraint);
+        let is_padding = builder.sub_extension(one, is_real);
+        let constraint = builder.mul_extension(is_padding, next_is_real);
+        yield_constr.constraint_transition(builder, constraint);
+
+        let constraint = builder.mul_sub_extension(lv.rounds_left, lv.rounds_left_inv, one);
+        let constraint = builder.mul_extension(is_round, constraint);
+        yield_constr.constraint(builder, constraint);
+        let constraint = builder.mul_extension(lv.is_final, lv.rounds_left);
+        yield_constr.constraint(builder, constraint);
+        let diff = builder.sub_extension(nv.rounds_left, lv.rounds_left);
+        let constraint = builder.mul_extension(is_columns, diff);
+        yield_constr.constraint_transition(builder, constraint);
+        let diff = builder.add_extension(diff, one);
+        let constraint = builder.mul_extension(is_diagonals, diff);
+        yield_constr.constraint_transition(builder, constraint);
+        let num_rounds =
+            builder.constant_extension(F::Extension::from_canonical_usize(BLAKE3_ROUNDS));
+        let diff = builder.sub_extension(lv.rounds_left, num_rounds);
+        let constraint = builder.mul_many_extension([lv.is_first, lv.is_blake3, diff]);
+        yield_constr.constraint(builder, constraint);
+
+        for (&next, &local) in [
+            (&nv.timestamp, &lv.timestamp),
+            (&nv.h_ptr, &lv.h_ptr),
+            (&nv.input_ptr, &lv.input_ptr),
+            (&nv.is_blake2b, &lv.is_blake2b),
+        ]
+        .into_iter()
+        .chain(nv.h.iter().zip(&lv.h))
+        .chain(nv.m.iter().zip(&lv.m))
+        {
+            let diff = builder.sub_extension(next, local);
+            let constraint = builder.mul_extension(is_round, diff);
+            yield_constr.constraint_transition(builder, constraint);
+        }
+
+        for &bit in lv
+            .state
+            .iter()
+            .flatten()
+            .chain(lv.mix.iter().flatten().flatten())
+            .chain(lv.mix_carries.iter().flatten())
+        {
+            let constraint = builder.mul_sub_extension(bit, bit, bit);
+            yield_constr.constraint(builder, constraint);
+        }
+
+        let first_blake2b = builder.mul_extension(lv.is_first, lv.is_blake2b);
+        for i in 0..8 {
+            let h = builder.mul_extension(lv.is_blake2b, lv.h[2 * i + 1]);
+            let h = builder.mul_add_extension(lv.is_blake3, lv.h[i], h);
+            let word = low_half_circuit(builder, &lv.state[i]);
+            let diff = builder.sub_extension(word, h);
+            let constraint = builder.mul_extension(lv.is_first, diff);
+            yield_constr.constraint(builder, constraint);
+            let word = high_half_circuit(builder, &lv.state[i]);
+            let diff = builder.sub_extension(word, lv.h[2 * i]);
+            let constraint = builder.mul_extension(first_blake2b, diff);
+            yield_constr.constraint(builder, constraint);
+        }
+        for i in 8..12 {
+            let iv = builder.mul_const_extension(
+                F::from_canonical_u32(BLAKE2B_IV[i - 8] as u32),
+                lv.is_blake2b,
+            );
+            let iv = builder.mul_const_add_extension(
+                F::from_canonical_u32(BLAKE3_IV[i - 8]),
+                lv.is_blake3,
+                iv,
+            );
+            let word = low_half_circuit(builder, &lv.state[i]);
+            let diff = builder.sub_extension(word, iv);
+            let constraint = builder.mul_extension(lv.is_first, diff);
+            yield_constr.constraint(builder, constraint);
+            let iv_hi = builder.constant_extension(F::Extension::from_canonical_u32(
+                (BLAKE2B_IV[i - 8] >> 32) as u32,
+            ));
+            let word = high_half_circuit(builder, &lv.state[i]);
+            let diff = builder.sub_extension(word, iv_hi);
+            let constraint = builder.mul_extension(first_blake2b, diff);
+            yield_constr.constraint(builder, constraint);
+        }
+        let f = builder.sub_extension(one, lv.state[14][0]);
+        let words = [
+            low_half_circuit(builder, &lv.state[14]),
+            high_half_circuit(builder, &lv.state[14]),
+        ];
+        for (half, word) in words.into_iter().enumerate() {
+            let iv = (BLAKE2B_IV[6] >> (32 * half)) as u32;
+            let flipped = F::from_canonical_u32(!iv) - F::from_canonical_u32(iv);
+            let iv = builder.constant_extension(F::Extension::from_canonical_u32(iv));
+            let diff = builder.sub_extension(word, iv);
+            let diff = builder.mul_const_add_extension(-flipped, f, diff);
+            let constraint = builder.mul_extension(first_blake2b, diff);
+            yield_constr.constraint(builder, constraint);
+        }
+        let words = [
+            low_half_circuit(builder, &lv.state[15]),
+            high_half_circuit(builder, &lv.state[15]),
+        ];
+        for (half, word) in words.into_iter().enumerate() {
+            let iv = builder.constant_extension(F::Extension::from_canonical_u32(
+                (BLAKE2B_IV[7] >> (32 * half)) as u32,
+            ));
+            let diff = builder.sub_extension(word, iv);
+            let constraint = builder.mul_extension(first_blake2b, diff);
+            yield_constr.constraint(builder, constraint);
+        }
+
+        for g in 0..4 {
+            for (k, &output) in OUTPUTS.iter().enumerate() {
+                let output = mix_words(lv, g)[output];
+                let after_columns = &nv.state[4 * k + (g + 4 - k) % 4];
+                let after_diagonals = &nv.state[4 * k + (g + k) % 4];
+                for i in 0..64 {
+                    let diff = builder.sub_extension(after_columns[i], output[i]);
+                    let constraint = builder.mul_extension(is_columns, diff);
+                    yield_constr.constraint_transition(builder, constraint);
+                    let diff = builder.sub_extension(after_diagonals[i], output[i]);
+                    let constraint = builder.mul_extension(is_diagonals, diff);
+                    yield_constr.constraint_transition(builder, constraint);
+                }
+            }
+        }
+
+        for g in 0..4 {
+            let words = mix_words(lv, g);
+            let carries = &lv.mix_carries[g];
+            let messages = [
+                message_word_circuit(builder, lv, g, 0),
+                message_word_circuit(builder, lv, g, 1),
+            ];
+            let zero = builder.zero_extension();
+            for addition in ADDITIONS.iter() {
+                let [carry_lo, carry_hi] = [0, 1].map(|k| {
+                    let start = addition.carry_start + k * addition.carry_bits;
+                    limb_from_bits_le_recursive(
+                        builder,
+                        carries[start..start + addition.carry_bits].iter().copied(),
+                    )
+                });
+                let (m_lo, m_hi) = addition.message.map_or((zero, zero), |i| messages[i]);
+                let [x, y] = addition.terms.map(|t| words[t]);
+                let sum = words[addition.sum];
+
+                let sum_lo = low_half_circuit(builder, sum);
+                let x_lo = low_half_circuit(builder, x);
+                let y_lo = low_half_circuit(builder, y);
+                let lo = builder.mul_const_add_extension(two_pow_32, carry_lo, sum_lo);
+                let lo = builder.sub_extension(lo, x_lo);
+                let lo = builder.sub_extension(lo, y_lo);
+                let lo = builder.sub_extension(lo, m_lo);
+                let constraint = builder.mul_extension(is_round, lo);
+                yield_constr.constraint(builder, constraint);
+
+                let sum_hi = high_half_circuit(builder, sum);
+                let x_hi = high_half_circuit(builder, x);
+                let y_hi = high_half_circuit(builder, y);
+                let hi = builder.mul_const_add_extension(two_pow_32, carry_hi, sum_hi);
+                let hi = builder.sub_extension(hi, x_hi);
+                let hi = builder.sub_extension(hi, y_hi);
+                let hi = builder.sub_extension(hi, m_hi);
+                let hi = builder.sub_extension(hi, carry_lo);
+                let constraint = builder.mul_extension(blake2b_rounds, hi);
+                yield_constr.constraint(builder, constraint);
+            }
+            for (k, &[out, x, y]) in XORS.iter().enumerate() {
+                let [out, x, y] = [words[out], words[x], words[y]];
+                for i in 0..64 {
+                    let j = (i + BLAKE2B_ROTATIONS[k] as usize) % 64;
+                    let xor = xor_gen_circuit(builder, x[j], y[j]);
+                    let diff = builder.sub_extension(out[i], xor);
+                    let constraint = builder.mul_extension(blake2b_rounds, diff);
+                    yield_constr.constraint(builder, constraint);
+                }
+                for i in 0..32 {
+                    let j = (i + BLAKE3_ROTATIONS[k] as usize) % 32;
+                    let xor = xor_gen_circuit(builder, x[j], y[j]);
+                    let diff = builder.sub_extension(out[i], xor);
+                    let constraint = builder.mul_extension(blake3_rounds, diff);
+                    yield_constr.constraint(builder, constraint);
+                }
+            }
+        }
+
+        let [halves, hash, new_hash] = [&lv.mix[HALVES], &lv.mix[HASH], &lv.mix[NEW_HASH]];
+        for i in 0..8 {
+            for b in 0..64 {
+                let xor = xor_gen_circuit(builder, lv.state[i][b], lv.state[i + 8][b]);
+                let diff = builder.sub_extension(halves[i][b], xor);
+                let constraint = builder.mul_extension(lv.is_final, diff);
+                yield_constr.constraint(builder, constraint);
+                let xor = xor_gen_circuit(builder, hash[i][b], halves[i][b]);
+                let diff = builder.sub_extension(new_hash[i][b], xor);
+                let constraint = builder.mul_extension(lv.is_final, diff);
+                yield_constr.constraint(builder, constraint);
+            }
+            let word = low_half_circuit(builder, &hash[i]);
+            let h = builder.mul_extension(lv.is_blake2b, lv.h[2 * i + 1]);
+            let diff = builder.sub_extension(word, h);
+            let constraint = builder.mul_extension(lv.is_final, diff);
+            yield_constr.constraint(builder, constraint);
+            let word = high_half_circuit(builder, &hash[i]);
+            let h = builder.mul_extension(lv.is_blake2b, lv.h[2 * i]);
+            let diff = builder.sub_extension(word, h);
+            let constraint = builder.mul_extension(lv.is_final, diff);
+            yield_constr.constraint(builder, constraint);
+        }
+    }
+
+    fn constraint_degree(&self) -> usize {
+        3
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::{Borrow, BorrowMut};
+
+    use anyhow::Result;
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::types::PrimeField64;
+    use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use zkm_emulator::blake::{BLAKE2B_IV, BLAKE3_IV};
+
+    use crate::blake::columns::{BlakeColumnsView, NUM_BLAKE_COLUMNS};
+    use crate::blake::compress_stark::{BlakeCompressOp, BlakeCompressStark, BlakeCompression};
+    use crate::stark_testing::{test_stark_circuit_constraints, test_stark_low_degree};
+    use crate::util::limb_from_bits_le;
+
+    #[test]
+    fn test_stark_degree() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = BlakeCompressStark<F, D>;
+
+        let stark = S::default();
+        test_stark_low_degree(stark)
+    }
+
+    #[test]
+    fn test_stark_circuit() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = BlakeCompressStark<F, D>;
+
+        let stark = S::default();
+        test_stark_circuit_constraints::<F, C, S, D>(stark)
+    }
+
+    #[test]
+    fn test_generation() -> Result<()> {
+        const D: usize = 2;
+        type F = GoldilocksField;
+        type S = BlakeCompressStark<F, D>;
+
+        let new_hash = |rows: Vec<[F; NUM_BLAKE_COLUMNS]>| -> [u64; 8] {
+            let last_row: &BlakeColumnsView<F> = rows.last().unwrap().borrow();
+            last_row.mix[2].map(|bits| {
+                let lo = limb_from_bits_le(bits[..32].iter().copied()).to_canonical_u64();
+                let hi = limb_from_bits_le(bits[32..].iter().copied()).to_canonical_u64();
+                hi << 32 | lo
+            })
+        };
+        let stark = S::default();
+
+        // The single block of BLAKE2b-512("abc").
+        let mut h = BLAKE2B_IV;
+        h[0] ^= 0x01010040;
+        let mut m = [0; 16];
+        m[0] = 0x636261;
+        let op = BlakeCompressOp {
+            h_ptr: 0x1000,
+            input_ptr: 0x2000,
+            timestamp: 0,
+            compression: BlakeCompression::Blake2b {
+                rounds: 12,
+                h,
+                m,
+                t: [3, 0],
+                f: true,
+            },
+        };
+        let rows = stark.generate_rows_for_op(op);
+        assert_eq!(rows.len(), 25);
+        let h = new_hash(rows);
+        assert_eq!(h[0], 0x0d4d1c983fa580ba);
+        assert_eq!(h[7], 0x239900d4ed8623b9);
+
+        // The single chunk of BLAKE3("abc").
+        let mut block = [0; 16];
+        block[0] = 0x636261;
+        let op = BlakeCompressOp {
+            h_ptr: 0x1000,
+            input_ptr: 0x2000,
+            timestamp: 0,
+            compression: BlakeCompression::Blake3 {
+                cv: BLAKE3_IV,
+                block,
+                counter: 0,
+                block_len: 3,
+                flags: 11,
+            },
+        };
+        let rows = stark.generate_rows_for_op(op);
+        assert_eq!(rows.len(), 15);
+        let cv = new_hash(rows);
+        assert_eq!(cv[0], 0xacb33764);
+        assert_eq!(cv[7], 0x859dbdd5);
+        Ok(())
+    }
+}
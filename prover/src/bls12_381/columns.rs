@@ -0,0 +1,57 @@
+use crate::modular::{IdentityColumns, ReducedColumns};
+use crate::util::impl_columns_view;
+
+/// The number of byte limbs of an element of the base field, of 381 bits.
+pub(crate) const FP_LIMBS: usize = 48;
+
+/// A call to `BLS12381_FP_ADD`, `BLS12381_FP_SUB` or `BLS12381_FP_MUL`, computing `x op y`
+/// reduced modulo the prime.
+#[repr(C)]
+#[derive(Eq, PartialEq, Debug)]
+pub(crate) struct Bls12381FpColumnsView<T: Copy> {
+    /// 1 iff the row is an addition, a subtraction or a multiplication. Padding rows are none.
+    pub is_add: T,
+    pub is_sub: T,
+    pub is_mul: T,
+
+    /// The timestamp of the CPU row making the call. The operands are read at this timestamp,
+    /// and the result written right after it.
+    pub timestamp: T,
+    /// The address of `x`, which the result replaces.
+    pub x_ptr: T,
+    /// The address of `y`.
+    pub y_ptr: T,
+
+    /// The limbs of the operands, which need not be reduced, and of the result.
+    pub x: [T; FP_LIMBS],
+    pub y: [T; FP_LIMBS],
+    pub result: [T; FP_LIMBS],
+
+    /// `x op y = result`.
+    pub identity: IdentityColumns<T, { FP_LIMBS + 1 }, { 2 * FP_LIMBS - 1 }>,
+    /// The result is reduced.
+    pub result_reduced: ReducedColumns<T, FP_LIMBS, { FP_LIMBS - 1 }>,
+
+    /// The bytes range checked by the table.
+    pub range_counter: T,
+    pub range_frequencies: T,
+}
+
+impl_columns_view!(
+    Bls12381FpColumnsView,
+    NUM_BLS12381_FP_COLUMNS,
+    BLS12381_FP_COL_MAP
+);
+
+impl<T: Copy> Bls12381FpColumnsView<T> {
+    /// The columns range checked in bytes.
+    pub(crate) fn bytes(&self) -> impl Iterator<Item = T> + '_ {
+        self.x
+            .iter()
+            .chain(&self.y)
+            .chain(&self.result)
+            .copied()
+            .chain(self.identity.bytes())
+            .chain(self.result_reduced.bytes())
+    }
+}
@@ -0,0 +1,408 @@
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use itertools::Itertools;
+use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::field::packed::PackedField;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::{Field, PrimeField64};
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::timed;
+use plonky2::util::timing::TimingTree;
+use zkm_emulator::bls12_381::{self, Element, FpOp};
+
+use crate::bls12_381::columns::*;
+use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::cross_table_lookup::{Column, Filter};
+use crate::evaluation_frame::{StarkEvaluationFrame, StarkFrame};
+use crate::lookup::Lookup;
+use crate::memory::segments::Segment;
+use crate::modular::*;
+use crate::stark::Stark;
+use crate::util::trace_rows_to_poly_values;
+
+/// The limbs of the modulus of the base field.
+pub(crate) const MODULUS: [u8; FP_LIMBS] = [
+    0xab, 0xaa, 0xff, 0xff, 0xff, 0xff, 0xfe, 0xb9, 0xff, 0xff, 0x53, 0xb1, 0xfe, 0xff, 0xab, 0x1e,
+    0x24, 0xf6, 0xb0, 0xf6, 0xa0, 0xd2, 0x30, 0x67, 0xbf, 0x12, 0x85, 0xf3, 0x84, 0x4b, 0x77, 0x64,
+    0xd7, 0xac, 0x4b, 0x43, 0xb6, 0xa7, 0x1b, 0x4b, 0x9a, 0xe6, 0x7f, 0x39, 0xea, 0x11, 0x01, 0x1a,
+];
+
+/// The number of words of an element.
+const FP_WORDS: usize = FP_LIMBS / 4;
+
+/// The words of the elements read and written, twelve each: `x`, `y`, then the result.
+pub(crate) const NUM_MEMORY_CTLS: usize = 3 * FP_WORDS;
+
+/// Strict upper bound of the range checks.
+const RANGE_MAX: usize = 1 << LIMB_BITS;
+
+pub(crate) fn ctl_looked_data<F: Field>() -> Vec<Column<F>> {
+    let cols = BLS12381_FP_COL_MAP;
+    Column::singles([cols.x_ptr, cols.y_ptr, cols.timestamp]).collect()
+}
+
+pub(crate) fn ctl_looked_filter_add<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(BLS12381_FP_COL_MAP.is_add))
+}
+
+pub(crate) fn ctl_looked_filter_sub<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(BLS12381_FP_COL_MAP.is_sub))
+}
+
+pub(crate) fn ctl_looked_filter_mul<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(BLS12381_FP_COL_MAP.is_mul))
+}
+
+pub(crate) fn ctl_looking_memory<F: Field>(i: usize) -> Vec<Column<F>> {
+    let cols = BLS12381_FP_COL_MAP;
+
+    let (is_read, ptr, element, timestamp) = match i / FP_WORDS {
+        0 => (true, cols.x_ptr, &cols.x, Column::single(cols.timestamp)),
+        1 => (true, cols.y_ptr, &cols.y, Column::single(cols.timestamp)),
+        _ => (
+            false,
+            cols.x_ptr,
+            &cols.result,
+            Column::linear_combination_with_constant([(cols.timestamp, F::ONE)], F::ONE),
+        ),
+    };
+    let j = i % FP_WORDS;
+    let word = Column::linear_combination(
+        element[4 * j..4 * j + 4]
+            .iter()
+            .enumerate()
+            .map(|(k, &c)| (c, F::from_canonical_u64(1 << (LIMB_BITS * k)))),
+    );
+    let res = vec![
+        Column::constant(F::from_bool(is_read)),
+        Column::zero(), // context
+        Column::constant(F::from_canonical_usize(Segment::Code as usize)),
+        Column::linear_combination_with_constant([(ptr, F::ONE)], F::from_canonical_usize(4 * j)),
+        word,
+        timestamp,
+    ];
+
+    assert_eq!(
+        res.len(),
+        crate::memory::memory_stark::ctl_data::<F>().len()
+    );
+    res
+}
+
+pub(crate) fn ctl_looking_memory_filter<F: Field>(_i: usize) -> Filter<F> {
+    let cols = BLS12381_FP_COL_MAP;
+    Filter::new_simple(Column::sum([cols.is_add, cols.is_sub, cols.is_mul]))
+}
+
+/// Information about a `BLS12381_FP_ADD`, `BLS12381_FP_SUB` or `BLS12381_FP_MUL` call.
+#[derive(Clone, Debug)]
+pub(crate) struct Bls12381FpOp {
+    pub(crate) op: FpOp,
+    /// The address of `x`.
+    pub(crate) x_ptr: usize,
+    /// The address of `y`.
+    pub(crate) y_ptr: usize,
+    /// The timestamp of the CPU row making the call.
+    pub(crate) timestamp: usize,
+    /// The elements read, as twelve words each.
+    pub(crate) x: Element,
+    pub(crate) y: Element,
+}
+
+/// The limbs of an element, from its words.
+fn element_limbs(x: &Element) -> [u8; FP_LIMBS] {
+    let bytes = x.iter().flat_map(|word| word.to_le_bytes()).collect_vec();
+    bytes.try_into().unwrap()
+}
+
+#[derive(Copy, Clone, Default)]
+pub struct Bls12381FpStark<F, const D: usize> {
+    f: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Bls12381FpStark<F, D> {
+    pub(crate) fn generate_trace(
+        &self,
+        operations: Vec<Bls12381FpOp>,
+        min_rows: usize,
+        timing: &mut TimingTree,
+    ) -> Vec<PolynomialValues<F>> {
+        let trace_rows = timed!(
+            timing,
+            "generate trace rows",
+            self.generate_trace_rows(operations, min_rows)
+        );
+        timed!(
+            timing,
+            "convert to PolynomialValues",
+            trace_rows_to_poly_values(trace_rows)
+        )
+    }
+
+    fn generate_trace_rows(
+        &self,
+        operations: Vec<Bls12381FpOp>,
+        min_rows: usize,
+    ) -> Vec<[F; NUM_BLS12381_FP_COLUMNS]> {
+        // The range checks need at least `RANGE_MAX` rows.
+        let num_rows = operations
+            .len()
+            .max(min_rows)
+            .max(RANGE_MAX)
+            .next_power_of_two();
+        let mut rows = Vec::with_capacity(num_rows);
+        for op in operations {
+            rows.push(self.generate_row_for_op(op));
+        }
+        rows.resize(num_rows, [F::ZERO; NUM_BLS12381_FP_COLUMNS]);
+        self.generate_range_checks(&mut rows);
+        rows
+    }
+
+    fn generate_range_checks(&self, rows: &mut [[F; NUM_BLS12381_FP_COLUMNS]]) {
+        let cols = BLS12381_FP_COL_MAP;
+        let mut frequencies = vec![0usize; RANGE_MAX];
+        for row in rows.iter() {
+            for c in cols.bytes() {
+                let x = row[c].to_canonical_u64() as usize;
+                assert!(
+                    x < RANGE_MAX,
+                    "column value {} exceeds the max range value {}",
+                    x,
+                    RANGE_MAX
+                );
+                frequencies[x] += 1;
+            }
+        }
+        for (i, row) in rows.iter_mut().enumerate() {
+            row[cols.range_counter] = F::from_canonical_usize(i.min(RANGE_MAX - 1));
+            row[cols.range_frequencies] =
+                F::from_canonical_usize(frequencies.get(i).copied().unwrap_or(0));
+        }
+    }
+
+    fn generate_row_for_op(&self, op: Bls12381FpOp) -> [F; NUM_BLS12381_FP_COLUMNS] {
+        let mut row = Bls12381FpColumnsView::default();
+        match op.op {
+            FpOp::Add => row.is_add = F::ONE,
+            FpOp::Sub => row.is_sub = F::ONE,
+            FpOp::Mul => row.is_mul = F::ONE,
+        }
+        row.timestamp = F::from_canonical_usize(op.timestamp);
+        row.x_ptr = F::from_canonical_usize(op.x_ptr);
+        row.y_ptr = F::from_canonical_usize(op.y_ptr);
+
+        let result = bls12_381::apply(op.op, &op.x, &op.y);
+        let (x, y, r) = (
+            element_limbs(&op.x),
+            element_limbs(&op.y),
+            element_limbs(&result),
+        );
+        row.x = limbs_to_field(&x);
+        row.y = limbs_to_field(&y);
+        row.result = limbs_to_field(&r);
+        generate_reduced(&mut row.result_reduced, &r, &MODULUS);
+
+        let (x, y, r) = (limbs_to_i64(&x), limbs_to_i64(&y), limbs_to_i64(&r));
+        let e = match op.op {
+            FpOp::Add => pol_sub(&pol_add(&x, &y), &r),
+            FpOp::Sub => pol_sub(&pol_sub(&x, &y), &r),
+            FpOp::Mul => pol_sub(&pol_mul(&x, &y), &r),
+        };
+        generate_identity(&mut row.identity, &e, &MODULUS);
+
+        row.into()
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for Bls12381FpStark<F, D> {
+    type EvaluationFrame<FE, P, const D2: usize>
+        = StarkFrame<P, NUM_BLS12381_FP_COLUMNS>
+    where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>;
+
+    type EvaluationFrameTarget = StarkFrame<ExtensionTarget<D>, NUM_BLS12381_FP_COLUMNS>;
+
+    fn eval_packed_generic<FE, P, const D2: usize>(
+        &self,
+        vars: &Self::EvaluationFrame<FE, P, D2>,
+        yield_constr: &mut ConstraintConsumer<P>,
+    ) where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>,
+    {
+        let local_values: &[P; NUM_BLS12381_FP_COLUMNS] =
+            vars.get_local_values().try_into().unwrap();
+        let lv: &Bls12381FpColumnsView<P> = local_values.borrow();
+        let next_values: &[P; NUM_BLS12381_FP_COLUMNS] = vars.get_next_values().try_into().unwrap();
+        let nv: &Bls12381FpColumnsView<P> = next_values.borrow();
+
+        // The range counter starts at 0, increments by 0 or 1, and ends at `RANGE_MAX - 1`.
+        yield_constr.constraint_first_row(lv.range_counter);
+        let incr = nv.range_counter - lv.range_counter;
+        yield_constr.constraint_transition(incr * incr - incr);
+        let range_max = P::Scalar::from_canonical_u64((RANGE_MAX - 1) as u64);
+        yield_constr.constraint_last_row(lv.range_counter - range_max);
+
+        let is_real = lv.is_add + lv.is_sub + lv.is_mul;
+        for flag in [lv.is_add, lv.is_sub, lv.is_mul, is_real] {
+            yield_constr.constraint(flag * (flag - P::ONES));
+        }
+
+        let e = pol_add(
+            &pol_add(
+                &pol_scale(&pol_add(&lv.x, &lv.y), lv.is_add),
+                &pol_scale(&pol_sub(&lv.x, &lv.y), lv.is_sub),
+            ),
+            &pol_scale(&pol_mul(&lv.x, &lv.y), lv.is_mul),
+        );
+        let e = pol_sub(&e, &pol_scale(&lv.result, is_real));
+        eval_identity(yield_constr, is_real, &e, &lv.identity, &MODULUS);
+        eval_reduced(
+            yield_constr,
+            is_real,
+            &lv.result,
+            &lv.result_reduced,
+            &MODULUS,
+        );
+    }
+
+    fn eval_ext_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: &Self::EvaluationFrameTarget,
+        yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+    ) {
+        let local_values: &[ExtensionTarget<D>; NUM_BLS12381_FP_COLUMNS] =
+            vars.get_local_values().try_into().unwrap();
+        let lv: &Bls12381FpColumnsView<ExtensionTarget<D>> = local_values.borrow();
+        let next_values: &[ExtensionTarget<D>; NUM_BLS12381_FP_COLUMNS] =
+            vars.get_next_values().try_into().unwrap();
+        let nv: &Bls12381FpColumnsView<ExtensionTarget<D>> = next_values.borrow();
+
+        yield_constr.constraint_first_row(builder, lv.range_counter);
+        let incr = builder.sub_extension(nv.range_counter, lv.range_counter);
+        let constraint = builder.mul_sub_extension(incr, incr, incr);
+        yield_constr.constraint_transition(builder, constraint);
+        let range_max =
+            builder.constant_extension(F::Extension::from_canonical_usize(RANGE_MAX - 1));
+        let constraint = builder.sub_extension(lv.range_counter, range_max);
+        yield_constr.constraint_last_row(builder, constraint);
+
+        let is_real = builder.add_many_extension([lv.is_add, lv.is_sub, lv.is_mul]);
+        for flag in [lv.is_add, lv.is_sub, lv.is_mul, is_real] {
+            let constraint = builder.mul_sub_extension(flag, flag, flag);
+            yield_constr.constraint(builder, constraint);
+        }
+
+        let scale = |builder: &mut CircuitBuilder<F, D>, a: &[ExtensionTarget<D>], c| {
+            a.iter().map(|&x| builder.mul_extension(c, x)).collect_vec()
+        };
+        let sum = pol_add_ext_circuit(builder, &lv.x, &lv.y);
+        let e_add = scale(builder, &sum, lv.is_add);
+        let diff = pol_sub_ext_circuit(builder, &lv.x, &lv.y);
+        let e_sub = scale(builder, &diff, lv.is_sub);
+        let prod = pol_mul_ext_circuit(builder, &lv.x, &lv.y);
+        let e_mul = scale(builder, &prod, lv.is_mul);
+        let result = scale(builder, &lv.result, is_real);
+        let e = pol_add_ext_circuit(builder, &e_add, &e_sub);
+        let e = pol_add_ext_circuit(builder, &e, &e_mul);
+        let e = pol_sub_ext_circuit(builder, &e, &result);
+        eval_identity_ext_circuit(builder, yield_constr, is_real, &e, &lv.identity, &MODULUS);
+        eval_reduced_ext_circuit(
+            builder,
+            yield_constr,
+            is_real,
+            &lv.result,
+            &lv.result_reduced,
+            &MODULUS,
+        );
+    }
+
+    fn constraint_degree(&self) -> usize {
+        3
+    }
+
+    fn lookups(&self) -> Vec<Lookup<F>> {
+        let cols = BLS12381_FP_COL_MAP;
+        let columns = Column::singles(cols.bytes()).collect_vec();
+        let filter_columns = vec![None; columns.len()];
+        vec![Lookup {
+            columns,
+            table_column: Column::single(cols.range_counter),
+            frequencies_column: Column::single(cols.range_frequencies),
+            filter_columns,
+        }]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Borrow;
+
+    use anyhow::Result;
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::types::PrimeField64;
+    use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use zkm_emulator::bls12_381::FpOp;
+
+    use crate::bls12_381::columns::Bls12381FpColumnsView;
+    use crate::bls12_381::fp_stark::{Bls12381FpOp, Bls12381FpStark};
+    use crate::stark_testing::{test_stark_circuit_constraints, test_stark_low_degree};
+
+    #[test]
+    fn test_stark_degree() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = Bls12381FpStark<F, D>;
+
+        let stark = S::default();
+        test_stark_low_degree(stark)
+    }
+
+    #[test]
+    fn test_stark_circuit() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = Bls12381FpStark<F, D>;
+
+        let stark = S::default();
+        test_stark_circuit_constraints::<F, C, S, D>(stark)
+    }
+
+    #[test]
+    fn test_generation() -> Result<()> {
+        const D: usize = 2;
+        type F = GoldilocksField;
+        type S = Bls12381FpStark<F, D>;
+
+        // `x = p - 1`, whose square is 1, and `y = 2^384 - 1`, which is not reduced.
+        let x = [
+            0xffffaaaa, 0xb9feffff, 0xb153ffff, 0x1eabfffe, 0xf6b0f624, 0x6730d2a0, 0xf38512bf,
+            0x64774b84, 0x434bacd7, 0x4b1ba7b6, 0x397fe69a, 0x1a0111ea,
+        ];
+        let y = [u32::MAX; 12];
+
+        let stark = S::default();
+        for (op, y, low_limb) in [(FpOp::Mul, x, 1), (FpOp::Sub, x, 0), (FpOp::Add, y, 0xfb)] {
+            let op = Bls12381FpOp {
+                op,
+                x_ptr: 0x1000,
+                y_ptr: 0x2000,
+                timestamp: 0,
+                x,
+                y,
+            };
+            let row = stark.generate_row_for_op(op);
+            let row: &Bls12381FpColumnsView<F> = row.borrow();
+            assert_eq!(row.result[0].to_canonical_u64(), low_limb);
+        }
+        Ok(())
+    }
+}
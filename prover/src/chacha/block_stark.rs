@@ -0,0 +1,548 @@
+use std::borrow::{Borrow, BorrowMut};
+use std::marker::PhantomData;
+
+use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::field::packed::PackedField;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::Field;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::timed;
+use plonky2::util::timing::TimingTree;
+use zkm_emulator::blake::MIX_INDICES;
+use zkm_emulator::chacha::CHACHA_ROTATIONS;
+
+use crate::chacha::columns::*;
+use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::cross_table_lookup::{Column, Filter};
+use crate::evaluation_frame::{StarkEvaluationFrame, StarkFrame};
+use crate::keccak::logic::{xor_gen, xor_gen_circuit};
+use crate::memory::segments::Segment;
+use crate::sha256::logic::u32_to_bits_le;
+use crate::stark::Stark;
+use crate::util::{limb_from_bits_le, limb_from_bits_le_recursive, trace_rows_to_poly_values};
+
+/// The words of the state are read on the first row of a call, and the words of the block
+/// written over them on its last row.
+pub(crate) const NUM_MEMORY_CTLS: usize = 32;
+
+/// The words a quarter round works on: its inputs `a`, `b`, `c` and `d`, then the words of
+/// `mix` it computes.
+const A: usize = 0;
+const B: usize = 1;
+const C: usize = 2;
+const D: usize = 3;
+const A1: usize = 4;
+const D1: usize = 5;
+const C1: usize = 6;
+const B1: usize = 7;
+const A2: usize = 8;
+const D2: usize = 9;
+const C2: usize = 10;
+const B2: usize = 11;
+
+/// The outputs of a quarter round, in the order of its inputs.
+const OUTPUTS: [usize; 4] = [A2, B2, C2, D2];
+
+/// The additions of a quarter round, modulo 2^32, as the sum then its two terms. Their carries
+/// are in `mix_carries` in this order.
+const ADDITIONS: [[usize; 3]; 4] = [[A1, A, B], [C1, C, D1], [A2, A1, B1], [C2, C1, D2]];
+
+/// The XORs of a quarter round, as the word computed then the two words XORed, each rotated by
+/// the next rotation of `CHACHA_ROTATIONS`.
+const XORS: [[usize; 3]; 4] = [[D1, D, A1], [B1, B, C1], [D2, D1, A2], [B2, B1, C2]];
+
+pub(crate) fn ctl_looked_data<F: Field>() -> Vec<Column<F>> {
+    let cols = CHACHA_COL_MAP;
+    Column::singles([cols.state_ptr, cols.timestamp]).collect()
+}
+
+pub(crate) fn ctl_looked_filter<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(CHACHA_COL_MAP.step[0]))
+}
+
+pub(crate) fn ctl_looking_memory<F: Field>(i: usize) -> Vec<Column<F>> {
+    let cols = CHACHA_COL_MAP;
+
+    let (is_read, j, value) = if i < 16 {
+        (true, i, Column::single(cols.input[i]))
+    } else {
+        let j = i - 16;
+        (false, j, Column::le_bits(cols.mix[j / 8][j % 8]))
+    };
+
+    let res = vec![
+        Column::constant(F::from_bool(is_read)),
+        Column::zero(), // context
+        Column::constant(F::from_canonical_usize(Segment::Code as usize)),
+        Column::linear_combination_with_constant(
+            [(cols.state_ptr, F::ONE)],
+            F::from_canonical_usize(4 * j),
+        ),
+        value,
+        Column::linear_combination_with_constant(
+            [(cols.timestamp, F::ONE)],
+            F::from_bool(!is_read),
+        ),
+    ];
+
+    assert_eq!(
+        res.len(),
+        crate::memory::memory_stark::ctl_data::<F>().len()
+    );
+    res
+}
+
+pub(crate) fn ctl_looking_memory_filter<F: Field>(i: usize) -> Filter<F> {
+    let cols = CHACHA_COL_MAP;
+    if i < 16 {
+        Filter::new_simple(Column::single(cols.step[0]))
+    } else {
+        Filter::new_simple(Column::single(cols.is_final))
+    }
+}
+
+/// Information about a `CHACHA20_BLOCK` call.
+#[derive(Clone, Debug)]
+pub(crate) struct ChaChaBlockOp {
+    /// The address of the state.
+    pub(crate) state_ptr: usize,
+    /// The timestamp of the CPU row making the call.
+    pub(crate) timestamp: usize,
+    /// The state: the constants, the key, the block counter and the nonce.
+    pub(crate) state: [u32; 16],
+}
+
+fn word<P: PackedField>(bits: &[P; 32]) -> P {
+    limb_from_bits_le(bits.iter().copied())
+}
+
+fn word_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    bits: &[ExtensionTarget<D>; 32],
+) -> ExtensionTarget<D> {
+    limb_from_bits_le_recursive(builder, bits.iter().copied())
+}
+
+/// The words the quarter round in slot `g` works on, indexed as `A` to `B2`.
+fn mix_words<T: Copy>(lv: &ChaChaColumnsView<T>, g: usize) -> [&[T; 32]; 12] {
+    core::array::from_fn(|i| {
+        if i < 4 {
+            &lv.state[4 * i + g]
+        } else {
+            &lv.mix[g][i - 4]
+        }
+    })
+}
+
+#[derive(Copy, Clone, Default)]
+pub struct ChaChaBlockStark<F, const D: usize> {
+    f: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> ChaChaBlockStark<F, D> {
+    pub(crate) fn generate_trace(
+        &self,
+        operations: Vec<ChaChaBlockOp>,
+        min_rows: usize,
+        timing: &mut TimingTree,
+    ) -> Vec<PolynomialValues<F>> {
+        let trace_rows = timed!(
+            timing,
+            "generate trace rows",
+            self.generate_trace_rows(operations, min_rows)
+        );
+        timed!(
+            timing,
+            "convert to PolynomialValues",
+            trace_rows_to_poly_values(trace_rows)
+        )
+    }
+
+    fn generate_trace_rows(
+        &self,
+        operations: Vec<ChaChaBlockOp>,
+        min_rows: usize,
+    ) -> Vec<[F; NUM_CHACHA_COLUMNS]> {
+        let num_rows = (operations.len() * NUM_CHACHA_ROWS)
+            .max(min_rows)
+            .next_power_of_two();
+        let mut rows = Vec::with_capacity(num_rows);
+        for op in operations {
+            rows.extend(self.generate_rows_for_op(op));
+        }
+        rows.resize(num_rows, [F::ZERO; NUM_CHACHA_COLUMNS]);
+        rows
+    }
+
+    fn generate_rows_for_op(&self, op: ChaChaBlockOp) -> Vec<[F; NUM_CHACHA_COLUMNS]> {
+        let mut template = ChaChaColumnsView::default();
+        template.timestamp = F::from_canonical_usize(op.timestamp);
+        template.state_ptr = F::from_canonical_usize(op.state_ptr);
+        template.input = op.state.map(F::from_canonical_u32);
+        let template: [F; NUM_CHACHA_COLUMNS] = template.into();
+
+        let mut v = op.state;
+        let mut rows = Vec::with_capacity(NUM_CHACHA_ROWS);
+        for step in 0..NUM_CHACHA_STEPS {
+            let half = step % 2;
+            let mut row = template;
+            let view: &mut ChaChaColumnsView<F> = row.borrow_mut();
+            view.step[step] = F::ONE;
+
+            for g in 0..4 {
+                let indices = MIX_INDICES[4 * half + g];
+                let mut words = [0u32; 12];
+                for (k, &index) in indices.iter().enumerate() {
+                    words[k] = v[index];
+                    view.state[4 * k + g] = u32_to_bits_le(v[index]);
+                }
+
+                // The steps of the quarter round alternate between an addition and an XOR.
+                for (k, ([sum, x, y], [out, x0, x1])) in ADDITIONS.iter().zip(XORS).enumerate() {
+                    let (s, carry) = words[*x].overflowing_add(words[*y]);
+                    words[*sum] = s;
+                    view.mix_carries[g][k] = F::from_bool(carry);
+                    words[out] = (words[x0] ^ words[x1]).rotate_left(CHACHA_ROTATIONS[k]);
+                }
+
+                for (j, &word) in words[4..].iter().enumerate() {
+                    view.mix[g][j] = u32_to_bits_le(word);
+                }
+                for (&index, output) in indices.iter().zip(OUTPUTS) {
+                    v[index] = words[output];
+                }
+            }
+            rows.push(row);
+        }
+
+        let mut row = template;
+        let view: &mut ChaChaColumnsView<F> = row.borrow_mut();
+        view.is_final = F::ONE;
+        view.state = v.map(u32_to_bits_le);
+        for (k, (&x, &y)) in v.iter().zip(&op.state).enumerate() {
+            let (block, carry) = x.overflowing_add(y);
+            view.mix[k / 8][k % 8] = u32_to_bits_le(block);
+            view.mix_carries[k / 4][k % 4] = F::from_bool(carry);
+        }
+        rows.push(row);
+        rows
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for ChaChaBlockStark<F, D> {
+    type EvaluationFrame<FE, P, const D2: usize>
+        = StarkFrame<P, NUM_CHACHA_COLUMNS>
+    where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>;
+
+    type EvaluationFrameTarget = StarkFrame<ExtensionTarget<D>, NUM_CHACHA_COLUMNS>;
+
+    fn eval_packed_generic<FE, P, const D2: usize>(
+        &self,
+        vars: &Self::EvaluationFrame<FE, P, D2>,
+        yield_constr: &mut ConstraintConsumer<P>,
+    ) where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>,
+    {
+        let local_values: &[P; NUM_CHACHA_COLUMNS] = vars.get_local_values().try_into().unwrap();
+        let lv: &ChaChaColumnsView<P> = local_values.borrow();
+        let next_values: &[P; NUM_CHACHA_COLUMNS] = vars.get_next_values().try_into().unwrap();
+        let nv: &ChaChaColumnsView<P> = next_values.borrow();
+
+        let two_pow_32 = FE::from_canonical_u64(1 << 32);
+
+        let is_round: P = lv.step.iter().copied().sum();
+        let is_columns: P = lv.step.iter().step_by(2).copied().sum();
+        let is_diagonals = is_round - is_columns;
+        let is_real = is_round + lv.is_final;
+        let next_is_real = nv.step.iter().copied().sum::<P>() + nv.is_final;
+
+        // The flags are boolean, and at most one is set.
+        for &flag in lv.step.iter().chain([&lv.is_final]) {
+            yield_constr.constraint(flag * (flag - P::ONES));
+        }
+        yield_constr.constraint(is_real * (is_real - P::ONES));
+
+        // A call starts on the first row of the trace or right after another, and its steps
+        // follow each other up to its last row. Padding rows come last.
+        yield_constr.constraint_first_row(lv.step[0] - is_real);
+        yield_constr.constraint_last_row(is_round);
+        for i in 0..NUM_CHACHA_STEPS - 1 {
+            yield_constr.constraint_transition(nv.step[i + 1] - lv.step[i]);
+        }
+        yield_constr.constraint_transition(nv.is_final - lv.step[NUM_CHACHA_STEPS - 1]);
+        yield_constr.constraint_transition(lv.is_final * (next_is_real - nv.step[0]));
+        yield_constr.constraint_transition((P::ONES - is_real) * next_is_real);
+
+        // Within a call, the address, the timestamp and the state read stay the same.
+        for (&next, &local) in [
+            (&nv.timestamp, &lv.timestamp),
+            (&nv.state_ptr, &lv.state_ptr),
+        ]
+        .into_iter()
+        .chain(nv.input.iter().zip(&lv.input))
+        {
+            yield_constr.constraint_transition(is_round * (next - local));
+        }
+
+        for &bit in lv
+            .state
+            .iter()
+            .flatten()
+            .chain(lv.mix.iter().flatten().flatten())
+            .chain(lv.mix_carries.iter().flatten())
+        {
+            yield_constr.constraint(bit * (bit - P::ONES));
+        }
+
+        // The initial state is the state read.
+        for (bits, &input) in lv.state.iter().zip(&lv.input) {
+            yield_constr.constraint(lv.step[0] * (word(bits) - input));
+        }
+
+        // The outputs of the quarter rounds go to the next row, with the diagonals lined up
+        // after the columns and back in order after the diagonals.
+        for g in 0..4 {
+            for (k, &output) in OUTPUTS.iter().enumerate() {
+                let output = mix_words(lv, g)[output];
+                let after_columns = &nv.state[4 * k + (g + 4 - k) % 4];
+                let after_diagonals = &nv.state[4 * k + (g + k) % 4];
+                for i in 0..32 {
+                    yield_constr.constraint_transition(is_columns * (after_columns[i] - output[i]));
+                    yield_constr
+                        .constraint_transition(is_diagonals * (after_diagonals[i] - output[i]));
+                }
+            }
+        }
+
+        // The four quarter rounds of the row.
+        for g in 0..4 {
+            let words = mix_words(lv, g);
+            for (k, &[sum, x, y]) in ADDITIONS.iter().enumerate() {
+                let carry = lv.mix_carries[g][k];
+                let diff = word(words[sum]) + carry * two_pow_32 - word(words[x]) - word(words[y]);
+                yield_constr.constraint(is_round * diff);
+            }
+            for (k, &[out, x, y]) in XORS.iter().enumerate() {
+                let [out, x, y] = [words[out], words[x], words[y]];
+                for i in 0..32 {
+                    let j = (i + 32 - CHACHA_ROTATIONS[k] as usize) % 32;
+                    yield_constr.constraint(is_round * (out[i] - xor_gen(x[j], y[j])));
+                }
+            }
+        }
+
+        // The block: the state read added to the final state.
+        for k in 0..16 {
+            let block = word(&lv.mix[k / 8][k % 8]);
+            let carry = lv.mix_carries[k / 4][k % 4];
+            let diff = block + carry * two_pow_32 - word(&lv.state[k]) - lv.input[k];
+            yield_constr.constraint(lv.is_final * diff);
+        }
+    }
+
+    fn eval_ext_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: &Self::EvaluationFrameTarget,
+        yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+    ) {
+        let local_values: &[ExtensionTarget<D>; NUM_CHACHA_COLUMNS] =
+            vars.get_local_values().try_into().unwrap();
+        let lv: &ChaChaColumnsView<ExtensionTarget<D>> = local_values.borrow();
+        let next_values: &[ExtensionTarget<D>; NUM_CHACHA_COLUMNS] =
+            vars.get_next_values().try_into().unwrap();
+        let nv: &ChaChaColumnsView<ExtensionTarget<D>> = next_values.borrow();
+
+        let two_pow_32 = F::from_canonical_u64(1 << 32);
+        let one = builder.one_extension();
+
+        let is_round = builder.add_many_extension(lv.step);
+        let is_columns = builder.add_many_extension(lv.step.iter().step_by(2).copied());
+        let is_diagonals = builder.sub_extension(is_round, is_columns);
+        let is_real = builder.add_extension(is_round, lv.is_final);
+        let next_is_round = builder.add_many_extension(nv.step);
+        let next_is_real = builder.add_extension(next_is_round, nv.is_final);
+
+        for &flag in lv.step.iter().chain([&lv.is_final]) {
+            let constraint = builder.mul_sub_extension(flag, flag, flag);
+            yield_constr.constraint(builder, constraint);
+        }
+        let constraint = builder.mul_sub_extension(is_real, is_real, is_real);
+        yield_constr.constraint(builder, constraint);
+
+        let constraint = builder.sub_extension(lv.step[0], is_real);
+        yield_constr.constraint_first_row(builder, constraint);
+        yield_constr.constraint_last_row(builder, is_round);
+        for i in 0..NUM_CHACHA_STEPS - 1 {
+            let constraint = builder.sub_extension(nv.step[i + 1], lv.step[i]);
+            yield_constr.constraint_transition(builder, constraint);
+        }
+        let constraint = builder.sub_extension(nv.is_final, lv.step[NUM_CHACHA_STEPS - 1]);
+        yield_constr.constraint_transition(builder, constraint);
+        let diff = builder.sub_extension(next_is_real, nv.step[0]);
+        let constraint = builder.mul_extension(lv.is_final, diff);
+        yield_constr.constraint_transition(builder, constraint);
+        let is_padding = builder.sub_extension(one, is_real);
+        let constraint = builder.mul_extension(is_padding, next_is_real);
+        yield_constr.constraint_transition(builder, constraint);
+
+        for (&next, &local) in [
+            (&nv.timestamp, &lv.timestamp),
+            (&nv.state_ptr, &lv.state_ptr),
+        ]
+        .into_iter()
+        .chain(nv.input.iter().zip(&lv.input))
+        {
+            let diff = builder.sub_extension(next, local);
+            let constraint = builder.mul_extension(is_round, diff);
+            yield_constr.constraint_transition(builder, constraint);
+        }
+
+        for &bit in lv
+            .state
+            .iter()
+            .flatten()
+            .chain(lv.mix.iter().flatten().flatten())
+            .chain(lv.mix_carries.iter().flatten())
+        {
+            let constraint = builder.mul_sub_extension(bit, bit, bit);
+            yield_constr.constraint(builder, constraint);
+        }
+
+        for (bits, &input) in lv.state.iter().zip(&lv.input) {
+            let word = word_circuit(builder, bits);
+            let diff = builder.sub_extension(word, input);
+            let constraint = builder.mul_extension(lv.step[0], diff);
+            yield_constr.constraint(builder, constraint);
+        }
+
+        for g in 0..4 {
+            for (k, &output) in OUTPUTS.iter().enumerate() {
+                let output = mix_words(lv, g)[output];
+                let after_columns = &nv.state[4 * k + (g + 4 - k) % 4];
+                let after_diagonals = &nv.state[4 * k + (g + k) % 4];
+                for i in 0..32 {
+                    let diff = builder.sub_extension(after_columns[i], output[i]);
+                    let constraint = builder.mul_extension(is_columns, diff);
+                    yield_constr.constraint_transition(builder, constraint);
+                    let diff = builder.sub_extension(after_diagonals[i], output[i]);
+                    let constraint = builder.mul_extension(is_diagonals, diff);
+                    yield_constr.constraint_transition(builder, constraint);
+                }
+            }
+        }
+
+        for g in 0..4 {
+            let words = mix_words(lv, g);
+            for (k, &[sum, x, y]) in ADDITIONS.iter().enumerate() {
+                let sum = word_circuit(builder, words[sum]);
+                let x = word_circuit(builder, words[x]);
+                let y = word_circuit(builder, words[y]);
+                let diff = builder.mul_const_add_extension(two_pow_32, lv.mix_carries[g][k], sum);
+                let diff = builder.sub_extension(diff, x);
+                let diff = builder.sub_extension(diff, y);
+                let constraint = builder.mul_extension(is_round, diff);
+                yield_constr.constraint(builder, constraint);
+            }
+            for (k, &[out, x, y]) in XORS.iter().enumerate() {
+                let [out, x, y] = [words[out], words[x], words[y]];
+                for i in 0..32 {
+                    let j = (i + 32 - CHACHA_ROTATIONS[k] as usize) % 32;
+                    let xor = xor_gen_circuit(builder, x[j], y[j]);
+                    let diff = builder.sub_extension(out[i], xor);
+                    let constraint = builder.mul_extension(is_round, diff);
+                    yield_constr.constraint(builder, constraint);
+                }
+            }
+        }
+
+        for k in 0..16 {
+            let block = word_circuit(builder, &lv.mix[k / 8][k % 8]);
+            let state = word_circuit(builder, &lv.state[k]);
+            let carry = lv.mix_carries[k / 4][k % 4];
+            let diff = builder.mul_const_add_extension(two_pow_32, carry, block);
+            let diff = builder.sub_extension(diff, state);
+            let diff = builder.sub_extension(diff, lv.input[k]);
+            let constraint = builder.mul_extension(lv.is_final, diff);
+            yield_constr.constraint(builder, constraint);
+        }
+    }
+
+    fn constraint_degree(&self) -> usize {
+        3
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Borrow;
+
+    use anyhow::Result;
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::types::PrimeField64;
+    use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use zkm_emulator::chacha::CHACHA_CONSTANTS;
+
+    use crate::chacha::block_stark::{ChaChaBlockOp, ChaChaBlockStark};
+    use crate::chacha::columns::{ChaChaColumnsView, NUM_CHACHA_ROWS};
+    use crate::stark_testing::{test_stark_circuit_constraints, test_stark_low_degree};
+    use crate::util::limb_from_bits_le;
+
+    #[test]
+    fn test_stark_degree() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = ChaChaBlockStark<F, D>;
+
+        let stark = S::default();
+        test_stark_low_degree(stark)
+    }
+
+    #[test]
+    fn test_stark_circuit() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = ChaChaBlockStark<F, D>;
+
+        let stark = S::default();
+        test_stark_circuit_constraints::<F, C, S, D>(stark)
+    }
+
+    #[test]
+    fn test_generation() -> Result<()> {
+        const D: usize = 2;
+        type F = GoldilocksField;
+        type S = ChaChaBlockStark<F, D>;
+
+        // The block function test vector of RFC 8439, section 2.3.2.
+        let mut state = [0; 16];
+        state[..4].copy_from_slice(&CHACHA_CONSTANTS);
+        for (i, word) in state[4..12].iter_mut().enumerate() {
+            *word = 0x03020100 + 0x04040404 * i as u32;
+        }
+        state[12..].copy_from_slice(&[1, 0x09000000, 0x4a000000, 0]);
+        let op = ChaChaBlockOp {
+            state_ptr: 0x1000,
+            timestamp: 0,
+            state,
+        };
+
+        let stark = S::default();
+        let rows = stark.generate_rows_for_op(op);
+        assert_eq!(rows.len(), NUM_CHACHA_ROWS);
+        let last_row: &ChaChaColumnsView<F> = rows.last().unwrap().borrow();
+        let block = |k: usize| {
+            limb_from_bits_le(last_row.mix[k / 8][k % 8].iter().copied()).to_canonical_u64()
+        };
+        assert_eq!(block(0), 0xe4e7f110);
+        assert_eq!(block(15), 0x4e3c50a2);
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+use crate::util::impl_columns_view;
+use zkm_emulator::chacha::CHACHA_DOUBLE_ROUNDS;
+
+/// Each double round takes two rows, one mixing the columns of the state and one its diagonals.
+pub(crate) const NUM_CHACHA_STEPS: usize = 2 * CHACHA_DOUBLE_ROUNDS;
+
+/// The rows of a call: its steps, then the one holding the keystream block.
+pub(crate) const NUM_CHACHA_ROWS: usize = NUM_CHACHA_STEPS + 1;
+
+/// The words computed by a quarter round, in order: `a`, `d`, `c`, `b`, then `a`, `d`, `c`, `b`
+/// again.
+pub(crate) const NUM_MIX_WORDS: usize = 8;
+
+#[repr(C)]
+#[derive(Eq, PartialEq, Debug)]
+pub(crate) struct ChaChaColumnsView<T: Copy> {
+    /// The rows of double round `r` have `step[2 r]` set when mixing the columns and
+    /// `step[2 r + 1]` when mixing the diagonals. The first row of a call is the one with
+    /// `step[0]` set. The last row of a call and padding rows have none.
+    pub step: [T; NUM_CHACHA_STEPS],
+    /// 1 on the last row of a call, which holds the keystream block.
+    pub is_final: T,
+
+    /// The timestamp of the CPU row making the call. The state is read at this timestamp, and
+    /// the block written right after it.
+    pub timestamp: T,
+    /// The address of the state.
+    pub state_ptr: T,
+
+    /// The words of the state read.
+    pub input: [T; 16],
+
+    /// The little-endian bits of the 16 words of the state at the start of the row. They are
+    /// ordered so that the quarter round in slot `g` mixes words `g`, `4 + g`, `8 + g` and
+    /// `12 + g`: as they are on rows mixing the columns, with the diagonals lined up on rows
+    /// mixing the diagonals. On the last row, the final state in order.
+    pub state: [[T; 32]; 16],
+
+    /// The little-endian bits of the words computed by the four quarter rounds of the row. On
+    /// the last row, the words of the block, eight per slot.
+    pub mix: [[[T; 32]; NUM_MIX_WORDS]; 4],
+    /// The carries of the four additions of each quarter round. On the last row, those of the
+    /// additions of the input to the final state, four per slot.
+    pub mix_carries: [[T; 4]; 4],
+}
+
+impl_columns_view!(ChaChaColumnsView, NUM_CHACHA_COLUMNS, CHACHA_COL_MAP);
@@ -0,0 +1,2 @@
+pub mod block_stark;
+pub mod columns;
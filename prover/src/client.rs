@@ -0,0 +1,740 @@
+//! A single entry point to execute, prove and verify programs, without the tables, circuits and
+//! configs behind it.
+
+use std::cell::{Cell, OnceCell};
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::net::{TcpListener, ToSocketAddrs};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, ensure, Result};
+use itertools::Itertools;
+use plonky2::plonk::circuit_data::VerifierCircuitData;
+use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+use plonky2::plonk::proof::ProofWithPublicInputs;
+use plonky2::recursion::cyclic_recursion::check_cyclic_proof_verifier_data;
+use plonky2::util::serialization::DefaultGateSerializer;
+use plonky2::util::timing::TimingTree;
+use serde::{Deserialize, Serialize};
+use zkm_emulator::executor::{Executor, ExecutorOpts};
+use zkm_emulator::journal::{Journal, JournalHash};
+use zkm_emulator::loader::MemoryLayout;
+use zkm_emulator::profiler::{Profiler, Symbols};
+use zkm_emulator::report::ExecutionReport;
+use zkm_emulator::state::State;
+use zkm_emulator::stdin::Stdin;
+use zkm_emulator::trace::ExecutionTrace;
+use zkm_emulator::utils::{
+    compute_image_id, split_prog_into_segs_with, try_load_elf_from_bytes, SEGMENT_STEPS,
+};
+
+use crate::all_stark::{AllStark, NUM_TABLES};
+use crate::batch::{BatchCircuitData, BatchEntry};
+use crate::config::{ProverOpts, StarkConfig, StarkHasher};
+use crate::continuation::compress;
+use crate::cpu::kernel::assembler::segment_kernel;
+use crate::error::{ProvingError, VerificationError};
+use crate::fixed_recursive_verifier::AllRecursiveCircuits;
+use crate::generation::replay_trace;
+use crate::generation::state::Receipt;
+use crate::groth16::Groth16Wrapper;
+use crate::key_cache::KeyCache;
+use crate::progress::{CancellationToken, Progress, ProgressCallback, ProgressMonitor};
+use crate::proof::{AllProof, PublicValues};
+use crate::prover::prove;
+use crate::segment_prover::{aggregate_segments, SegmentJob, SegmentProver};
+use crate::serialization::{self, Kind};
+use crate::verifier::verify_proof;
+
+pub const D: usize = 2;
+pub type C = PoseidonGoldilocksConfig;
+pub type F = <C as GenericConfig<D>>::F;
+
+/// The range of the degrees of the tables that the recursive circuits support, by table.
+pub const DEGREE_BITS_RANGE: [Range<usize>; NUM_TABLES] = [
+    10..21,
+    12..22,
+    12..21,
+    8..21,
+    6..21,
+    6..21,
+    6..21,
+    6..21,
+    8..21,
+    8..21,
+    8..21,
+    8..21,
+    8..21,
+    8..21,
+    8..21,
+    8..21,
+    8..21,
+    6..21,
+    13..23,
+];
+
+/// The STARK proofs of the segments of a run, in order.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ZkmProof {
+    pub segments: Vec<AllProof<F, C, D>>,
+    pub journal: Journal,
+    pub hasher: StarkHasher,
+}
+
+impl ZkmProof {
+    pub fn serialize(&self) -> Result<Vec<u8>> {
+        serialization::serialize(Kind::StarkProofs, self)
+    }
+
+    pub fn deserialize(bytes: &[u8]) -> Result<Self> {
+        serialization::deserialize(Kind::StarkProofs, bytes)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        Ok(fs::write(path, self.serialize()?)?)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::deserialize(&fs::read(path)?)
+    }
+}
+
+/// A single recursive proof of a run, of the same size however long the run, which other
+/// programs can verify with `zkm_verifier`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ZkmCompressedProof {
+    /// The block proof wrapping the aggregation of the segments.
+    pub receipt: Receipt<F, C, D>,
+    pub hasher: StarkHasher,
+}
+
+impl ZkmCompressedProof {
+    /// The image id, userdata and exit code the proof commits to, read from its public inputs.
+    pub fn public_values(&self) -> Result<BatchEntry> {
+        BatchEntry::from_proof(&self.receipt.proof).map_err(|e| invalid_proof(None, e))
+    }
+
+    /// The image id of the program proven, see [`ProverClient::image_id`].
+    pub fn image_id(&self) -> Result<[u8; 32]> {
+        Ok(self.public_values()?.image_id)
+    }
+
+    /// Fails if the image id, the userdata or the journal of the receipt are not those of its
+    /// proof.
+    fn check_receipt(&self) -> Result<()> {
+        let public_values = self.public_values()?;
+        ensure!(
+            self.receipt.root_before == public_values.image_id
+                && self.receipt.userdata == public_values.userdata,
+            VerificationError::InvalidProof {
+                segment: None,
+                reason: "the receipt is not that of its proof".to_string(),
+            }
+        );
+        ensure!(
+            self.receipt.journal().digest(JournalHash::Sha256) == public_values.journal_digest,
+            VerificationError::WrongJournal
+        );
+        Ok(())
+    }
+
+    pub fn serialize(&self) -> Result<Vec<u8>> {
+        serialization::serialize(Kind::Proof, self)
+    }
+
+    pub fn deserialize(bytes: &[u8]) -> Result<Self> {
+        serialization::deserialize(Kind::Proof, bytes)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        Ok(fs::write(path, self.serialize()?)?)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::deserialize(&fs::read(path)?)
+    }
+}
+
+/// A single proof of the compressed proofs of several programs, which commits to the entry of
+/// each, in order.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ZkmBatchProof {
+    pub proof: ProofWithPublicInputs<F, C, D>,
+    pub entries: Vec<BatchEntry>,
+}
+
+/// What verifies the compressed proofs, without building the recursive circuits.
+#[derive(Clone, Debug)]
+pub struct ZkmVerifyingKey {
+    pub block: VerifierCircuitData<F, C, D>,
+}
+
+impl ZkmVerifyingKey {
+    pub fn verify(&self, proof: &ZkmCompressedProof) -> Result<()> {
+        check_hasher(StarkHasher::Poseidon, proof.hasher)?;
+        proof.check_receipt()?;
+        let proof = proof.receipt.proof.clone();
+        let circuit = &self.block;
+        check_cyclic_proof_verifier_data(&proof, &circuit.verifier_only, &circuit.common)
+            .and_then(|()| circuit.verify(proof))
+            .map_err(|e| invalid_proof(None, e))
+    }
+
+    /// Verifies `proof`, and that it is a proof of the program `image_id`.
+    pub fn verify_program(&self, proof: &ZkmCompressedProof, image_id: &[u8; 32]) -> Result<()> {
+        let found = proof.image_id()?;
+        ensure!(
+            found == *image_id,
+            VerificationError::WrongProgram {
+                expected: *image_id,
+                found,
+            }
+        );
+        self.verify(proof)
+    }
+
+    pub fn serialize(&self) -> Result<Vec<u8>> {
+        let block = self
+            .block
+            .to_bytes(&DefaultGateSerializer)
+            .map_err(|_| anyhow!("failed to serialize the verifying key"))?;
+        serialization::serialize(Kind::VerifyingKey, &block)
+    }
+
+    pub fn deserialize(bytes: &[u8]) -> Result<Self> {
+        let block: Vec<u8> = serialization::deserialize(Kind::VerifyingKey, bytes)?;
+        let block = VerifierCircuitData::from_bytes(block, &DefaultGateSerializer)
+            .map_err(|_| anyhow!("invalid verifying key"))?;
+        Ok(Self { block })
+    }
+}
+
+pub struct ProverClient {
+    all_stark: AllStark<F, D>,
+    config: StarkConfig,
+    seg_size: usize,
+    seg_dir: PathBuf,
+    /// Built on the first compressed proof, as it takes minutes.
+    circuits: OnceCell<AllRecursiveCircuits<F, C, D>>,
+    key_cache: Option<KeyCache>,
+    groth16: OnceCell<Groth16Wrapper>,
+    hasher: StarkHasher,
+    layout: MemoryLayout,
+    max_cycles: Option<u64>,
+    monitor: ProgressMonitor,
+}
+
+/// Configures a [`ProverClient`].
+#[derive(Clone, Debug, Default)]
+pub struct ProverClientBuilder {
+    opts: Option<ProverOpts>,
+    seg_size: Option<usize>,
+    seg_dir: Option<PathBuf>,
+    key_cache: Option<PathBuf>,
+    layout: Option<MemoryLayout>,
+    max_cycles: Option<u64>,
+    progress: Option<ProgressCallback>,
+    cancellation: Option<CancellationToken>,
+}
+
+impl ProverClientBuilder {
+    /// See [`ProverClient::with_opts`].
+    pub fn opts(mut self, opts: ProverOpts) -> Self {
+        self.opts = Some(opts);
+        self
+    }
+
+    /// See [`ProverClient::with_seg_size`].
+    pub fn seg_size(mut self, seg_size: usize) -> Self {
+        self.seg_size = Some(seg_size);
+        self
+    }
+
+    /// See [`ProverClient::with_seg_dir`].
+    pub fn seg_dir(mut self, seg_dir: impl Into<PathBuf>) -> Self {
+        self.seg_dir = Some(seg_dir.into());
+        self
+    }
+
+    /// See [`ProverClient::with_key_cache`].
+    pub fn key_cache(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.key_cache = Some(dir.into());
+        self
+    }
+
+    /// See [`ProverClient::with_memory_layout`].
+    pub fn memory_layout(mut self, layout: MemoryLayout) -> Self {
+        self.layout = Some(layout);
+        self
+    }
+
+    /// See [`ProverClient::with_max_cycles`].
+    pub fn max_cycles(mut self, max_cycles: u64) -> Self {
+        self.max_cycles = Some(max_cycles);
+        self
+    }
+
+    /// See [`ProverClient::with_progress`].
+    pub fn progress(mut self, callback: impl Fn(&Progress) + Send + Sync + 'static) -> Self {
+        self.progress = Some(ProgressCallback::new(callback));
+        self
+    }
+
+    /// See [`ProverClient::with_cancellation`].
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    pub fn build(self) -> ProverClient {
+        let mut client = ProverClient::new();
+        if let Some(opts) = self.opts {
+            client = client.with_opts(opts);
+        }
+        if let Some(seg_size) = self.seg_size {
+            client = client.with_seg_size(seg_size);
+        }
+        if let Some(seg_dir) = self.seg_dir {
+            client = client.with_seg_dir(seg_dir);
+        }
+        if let Some(dir) = self.key_cache {
+            client = client.with_key_cache(dir);
+        }
+        if let Some(layout) = self.layout {
+            client = client.with_memory_layout(layout);
+        }
+        if let Some(max_cycles) = self.max_cycles {
+            client = client.with_max_cycles(max_cycles);
+        }
+        client.monitor = ProgressMonitor::new(self.progress, self.cancellation);
+        client
+    }
+}
+
+impl Default for ProverClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProverClient {
+    pub fn new() -> Self {
+        Self {
+            all_stark: AllStark::default(),
+            config: StarkConfig::standard_fast_config(),
+            seg_size: SEGMENT_STEPS,
+            seg_dir: std::env::temp_dir().join(format!("zkm-segments-{}", std::process::id())),
+            circuits: OnceCell::new(),
+            key_cache: None,
+            groth16: OnceCell::new(),
+            hasher: StarkHasher::Poseidon,
+            layout: MemoryLayout::default(),
+            max_cycles: None,
+            monitor: ProgressMonitor::default(),
+        }
+    }
+
+    pub fn builder() -> ProverClientBuilder {
+        ProverClientBuilder::default()
+    }
+
+    /// Proves with the FRI parameters of `opts`, instead of [`ProverOpts::fast`].
+    pub fn with_opts(mut self, opts: ProverOpts) -> Self {
+        self.config = StarkConfig::from_opts(&opts);
+        self.hasher = opts.hasher;
+        self
+    }
+
+    /// Splits the runs in segments of `seg_size` cycles.
+    pub fn with_seg_size(mut self, seg_size: usize) -> Self {
+        self.seg_size = seg_size;
+        self
+    }
+
+    /// Writes the segments of the runs to prove to `seg_dir`, instead of a temporary directory.
+    pub fn with_seg_dir(mut self, seg_dir: impl Into<PathBuf>) -> Self {
+        self.seg_dir = seg_dir.into();
+        self
+    }
+
+    /// Keeps the recursive circuits in `dir`, to load them instead of building them again in
+    /// the next runs.
+    pub fn with_key_cache(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.key_cache = Some(KeyCache::new(dir));
+        self
+    }
+
+    /// Runs the programs with the heap and the stack of `layout`. The start of the heap is part
+    /// of the image id.
+    pub fn with_memory_layout(mut self, layout: MemoryLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Stops the runs past `max_cycles` cycles with [`ExecutionError::CycleLimitExceeded`], for
+    /// the programs and inputs which are not trusted to exit. The runs split in segments to
+    /// prove are checked at the end of each segment.
+    ///
+    /// [`ExecutionError::CycleLimitExceeded`]: zkm_emulator::error::ExecutionError::CycleLimitExceeded
+    pub fn with_max_cycles(mut self, max_cycles: u64) -> Self {
+        self.max_cycles = Some(max_cycles);
+        self
+    }
+
+    /// Calls `callback` with the progress of the proofs: the cycles executed, the tables
+    /// committed to and proven, and the segments proven. It is called from the threads proving.
+    pub fn with_progress(self, callback: impl Fn(&Progress) + Send + Sync + 'static) -> Self {
+        let cancellation = self.monitor.cancellation().cloned();
+        Self {
+            monitor: ProgressMonitor::new(Some(ProgressCallback::new(callback)), cancellation),
+            ..self
+        }
+    }
+
+    /// Stops the proofs with [`ProvingError::Cancelled`] once `token` is cancelled.
+    pub fn with_cancellation(self, token: CancellationToken) -> Self {
+        let callback = self.monitor.callback().cloned();
+        Self {
+            monitor: ProgressMonitor::new(callback, Some(token)),
+            ..self
+        }
+    }
+
+    /// The image id of the program `elf`, which its compressed proofs commit to.
+    pub fn image_id(&self, elf: &[u8]) -> Result<[u8; 32]> {
+        Ok(compute_image_id(self.load(elf, &Stdin::default())?))
+    }
+
+    /// Runs the program `elf` on `stdin` without proving it, and checks it stayed within the
+    /// memory layout.
+    pub fn execute(&self, elf: &[u8], stdin: &Stdin) -> Result<(Journal, ExecutionReport)> {
+        let mut executor = self.executor(elf, stdin)?;
+        let report = executor.run()?;
+        self.layout.check(&report).map_err(anyhow::Error::msg)?;
+        Ok((executor.state().journal(), report))
+    }
+
+    /// Runs the program `elf` on `stdin` as [`ProverClient::execute`] does, and profiles its
+    /// cycles by function.
+    pub fn profile(&self, elf: &[u8], stdin: &Stdin) -> Result<(ExecutionReport, Profiler)> {
+        let symbols = Symbols::from_elf(elf)?;
+        let mut executor = self
+            .executor(elf, stdin)?
+            .with_profiler(Profiler::new(symbols));
+        let report = executor.run()?;
+        self.layout.check(&report).map_err(anyhow::Error::msg)?;
+        Ok((report, executor.take_profiler().unwrap()))
+    }
+
+    /// Runs the program `elf` on `stdin` as [`ProverClient::execute`] does, and records its
+    /// execution trace.
+    pub fn trace(&self, elf: &[u8], stdin: &Stdin) -> Result<(ExecutionReport, ExecutionTrace)> {
+        let mut executor = self.executor(elf, stdin)?.with_trace();
+        let report = executor.run()?;
+        self.layout.check(&report).map_err(anyhow::Error::msg)?;
+        Ok((report, executor.take_trace().unwrap()))
+    }
+
+    /// Runs the program `elf` on `stdin` again, and checks each instruction against `trace`.
+    pub fn replay(
+        &self,
+        elf: &[u8],
+        stdin: &Stdin,
+        trace: &ExecutionTrace,
+    ) -> Result<ExecutionReport> {
+        let mut executor = self.executor(elf, stdin)?;
+        Ok(executor.replay(trace)?)
+    }
+
+    /// Splits the run of the program `elf` on `stdin` in segments, as [`ProverClient::prove`]
+    /// does, and checks the witness generation of each segment against `trace`, without proving.
+    pub fn replay_witness(&self, elf: &[u8], stdin: &Stdin, trace: &ExecutionTrace) -> Result<()> {
+        let (segments, _) = self.split(elf, stdin, 1)?;
+        for segment in segments {
+            let kernel = segment_kernel("", "", "", BufReader::new(File::open(segment)?));
+            replay_trace::<F, C, D>(&kernel, trace)?;
+        }
+        Ok(())
+    }
+
+    /// Runs the program `elf` on `stdin` under the GDB stub, for the debugger connecting to
+    /// `addr`, see [`zkm_emulator::gdb`].
+    pub fn debug(
+        &self,
+        elf: &[u8],
+        stdin: &Stdin,
+        addr: impl ToSocketAddrs,
+    ) -> Result<ExecutionReport> {
+        let listener = TcpListener::bind(addr)?;
+        let mut executor = self.executor(elf, stdin)?;
+        Ok(executor.debug(listener)?)
+    }
+
+    /// Proves the run of the program `elf` on `stdin`, with a STARK proof by segment.
+    pub fn prove(&self, elf: &[u8], stdin: &Stdin) -> Result<ZkmProof> {
+        let (segments, journal) = self.split(elf, stdin, 1)?;
+        let mut proofs = Vec::with_capacity(segments.len());
+        for (i, segment) in segments.into_iter().enumerate() {
+            let kernel = segment_kernel("", "", "", BufReader::new(File::open(segment)?));
+            let mut timing = TimingTree::new("prove segment", log::Level::Info);
+            let monitor = self.monitor.for_segment(i);
+            proofs.push(
+                monitor.in_scope(|| prove(&self.all_stark, &kernel, &self.config, &mut timing))?,
+            );
+            monitor.report(Progress::SegmentProven { segment: i })?;
+        }
+        Ok(ZkmProof {
+            segments: proofs,
+            journal,
+            hasher: self.hasher,
+        })
+    }
+
+    /// Proves the run of the program `elf` on `stdin`, with a single recursive proof.
+    pub fn prove_compressed(&self, elf: &[u8], stdin: &Stdin) -> Result<ZkmCompressedProof> {
+        // The block circuit wraps an aggregation, so the run needs at least two segments.
+        let (segments, journal) = self.split(elf, stdin, 2)?;
+        let circuits = self.circuits();
+
+        let jobs = (0..segments.len())
+            .map(|i| SegmentJob::read(&self.seg_dir, i))
+            .collect::<Result<Vec<_>>>()?;
+        let proven = phase("prove segments").in_scope(|| {
+            self.monitor.in_scope(|| {
+                SegmentProver::new(&self.all_stark, &self.config, circuits).prove_all(&jobs)
+            })
+        })?;
+        self.monitor.check()?;
+        let aggregation = phase("aggregate").in_scope(|| aggregate_segments(circuits, proven))?;
+        self.monitor.check()?;
+        let (proof, public_values) =
+            phase("compress").in_scope(|| compress(circuits, &aggregation))?;
+        let root_before = public_values
+            .roots_before
+            .root
+            .iter()
+            .flat_map(|limb| limb.to_le_bytes())
+            .collect();
+        Ok(ZkmCompressedProof {
+            receipt: Receipt {
+                proof,
+                root_before,
+                userdata: public_values.userdata,
+                journal: journal.bytes,
+            },
+            hasher: self.hasher,
+        })
+    }
+
+    /// Verifies the proof of each segment, that each segment starts where the previous one
+    /// ended, and that the journal is the one the last segment commits to.
+    pub fn verify(&self, proof: &ZkmProof) -> Result<()> {
+        check_hasher(self.hasher, proof.hasher)?;
+        for (i, segment) in proof.segments.iter().enumerate() {
+            verify_proof(&self.all_stark, segment.clone(), &self.config)
+                .map_err(|e| invalid_proof(Some(i), e))?;
+        }
+        for (i, pair) in proof.segments.windows(2).enumerate() {
+            ensure!(
+                pair[0].public_values.roots_after.root == pair[1].public_values.roots_before.root,
+                VerificationError::NotContiguous { segment: i + 1 }
+            );
+        }
+        let last = proof
+            .segments
+            .last()
+            .ok_or_else(|| invalid_proof(None, anyhow!("the proof has no segment")))?;
+        ensure!(
+            last.public_values.journal_digest
+                == PublicValues::journal_digest_of(&proof.journal.bytes),
+            VerificationError::WrongJournal
+        );
+        Ok(())
+    }
+
+    pub fn verify_compressed(&self, proof: &ZkmCompressedProof) -> Result<()> {
+        check_hasher(self.hasher, proof.hasher)?;
+        proof.check_receipt()?;
+        self.circuits()
+            .verify_block(&proof.receipt.proof)
+            .map_err(|e| invalid_proof(None, e))
+    }
+
+    /// Proves the compressed `proofs`, of any programs, with a single proof.
+    pub fn prove_batch(&self, proofs: &[ZkmCompressedProof]) -> Result<ZkmBatchProof> {
+        ensure!(!proofs.is_empty(), "the batch has no proof");
+        let block_proofs = proofs
+            .iter()
+            .map(|proof| proof.receipt.proof.clone())
+            .collect_vec();
+        let circuit = BatchCircuitData::new(&self.circuits().block.circuit, proofs.len());
+        Ok(ZkmBatchProof {
+            proof: circuit.prove(&block_proofs)?,
+            entries: block_proofs
+                .iter()
+                .map(BatchEntry::from_proof)
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    pub fn verify_batch(&self, proof: &ZkmBatchProof) -> Result<()> {
+        ensure!(!proof.entries.is_empty(), "the batch has no proof");
+        let circuit = BatchCircuitData::new(&self.circuits().block.circuit, proof.entries.len());
+        circuit.verify(&proof.proof, &proof.entries)
+    }
+
+    /// Wraps the compressed `proof` for the Groth16 prover, writing the wrapped circuit and proof
+    /// to `dir`. The wrapper is built on the first call.
+    pub fn wrap_groth16(&self, proof: &ZkmCompressedProof, dir: impl AsRef<Path>) -> Result<()> {
+        let wrapper = self
+            .groth16
+            .get_or_init(|| Groth16Wrapper::new(self.circuits()));
+        wrapper.wrap_to(proof, dir)
+    }
+
+    /// The key verifying the compressed proofs of this client.
+    pub fn verifying_key(&self) -> ZkmVerifyingKey {
+        let circuits = self.circuits();
+        ZkmVerifyingKey {
+            block: circuits.block.circuit.verifier_data(),
+        }
+    }
+
+    fn circuits(&self) -> &AllRecursiveCircuits<F, C, D> {
+        self.circuits.get_or_init(|| {
+            let build =
+                || AllRecursiveCircuits::new(&self.all_stark, &DEGREE_BITS_RANGE, &self.config);
+            match &self.key_cache {
+                Some(cache) => {
+                    let fingerprint = KeyCache::fingerprint(&self.config, &DEGREE_BITS_RANGE);
+                    cache.load_or_build(&fingerprint, build)
+                }
+                None => build(),
+            }
+        })
+    }
+
+    /// Runs the program and writes its segments, returning their paths. A run with fewer than
+    /// `min_segments` segments is split again in smaller ones.
+    fn split(
+        &self,
+        elf: &[u8],
+        stdin: &Stdin,
+        min_segments: usize,
+    ) -> Result<(Vec<PathBuf>, Journal)> {
+        fs::create_dir_all(&self.seg_dir)?;
+        let seg_dir = self
+            .seg_dir
+            .to_str()
+            .ok_or_else(|| anyhow!("invalid segment directory {:?}", self.seg_dir))?;
+        let _span = phase("execute").entered();
+        let mut seg_size = self.seg_size;
+        let opts = self.executor_opts();
+        let limit_exceeded = Cell::new(None);
+        let on_segment = |state: &State, segments: usize| {
+            if let Err(err) = opts.check_cycles(state) {
+                limit_exceeded.set(Some(err));
+                return false;
+            }
+            let cycles = state.total_cycle;
+            self.monitor
+                .report(Progress::Executed { cycles, segments })
+                .is_ok()
+        };
+        let (seg_num, state) = loop {
+            let (total_steps, seg_num, state) = split_prog_into_segs_with(
+                self.load(elf, stdin)?,
+                seg_dir,
+                "",
+                seg_size,
+                on_segment,
+            );
+            if let Some(err) = limit_exceeded.take() {
+                return Err(err.into());
+            }
+            self.monitor.check()?;
+            if seg_num >= min_segments || total_steps < min_segments || seg_size <= 1 {
+                break (seg_num, state);
+            }
+            seg_size /= 2;
+        };
+        ensure!(
+            seg_num >= min_segments,
+            ProvingError::TooFewSegments {
+                segments: seg_num,
+                min_segments,
+            }
+        );
+        let segments = (0..seg_num)
+            .map(|i| self.seg_dir.join(i.to_string()))
+            .collect();
+        Ok((segments, state.journal()))
+    }
+
+    fn executor(&self, elf: &[u8], stdin: &Stdin) -> Result<Executor> {
+        Ok(Executor::from_opts(
+            self.load(elf, stdin)?,
+            self.executor_opts(),
+        ))
+    }
+
+    fn executor_opts(&self) -> ExecutorOpts {
+        ExecutorOpts {
+            seg_size: self.seg_size,
+            max_cycles: self.max_cycles,
+        }
+    }
+
+    fn load(&self, elf: &[u8], stdin: &Stdin) -> Result<Box<State>> {
+        let mut state = try_load_elf_from_bytes(elf, vec![], &[])?;
+        state.set_memory_layout(&self.layout)?;
+        state.add_stdin(stdin);
+        Ok(state)
+    }
+}
+
+/// A `tracing` span of a phase of the proof, as the ones of the prover.
+fn phase(name: &'static str) -> tracing::Span {
+    tracing::info_span!("phase", name)
+}
+
+fn check_hasher(expected: StarkHasher, found: StarkHasher) -> Result<()> {
+    ensure!(
+        expected == found,
+        VerificationError::HasherMismatch { expected, found }
+    );
+    Ok(())
+}
+
+fn invalid_proof(segment: Option<usize>, err: anyhow::Error) -> anyhow::Error {
+    VerificationError::InvalidProof {
+        segment,
+        reason: err.to_string(),
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zkm_emulator::loader::ElfError;
+
+    #[test]
+    fn test_hasher() {
+        assert!(check_hasher(StarkHasher::Poseidon, StarkHasher::Poseidon).is_ok());
+        // A proof declaring a hasher this version does not know is not decoded.
+        assert!(bincode::deserialize::<StarkHasher>(&1u32.to_le_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_invalid_elf() {
+        let client = ProverClient::new();
+        let err = client.image_id(&[0x7f, b'E', b'L', b'F']).unwrap_err();
+        assert!(
+            matches!(err.downcast_ref(), Some(ElfError::Parse(_))),
+            "{err}"
+        );
+        assert!(client.execute(&[], &Stdin::default()).is_err());
+    }
+}
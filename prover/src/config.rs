@@ -1,5 +1,6 @@
 use plonky2::fri::reduction_strategies::FriReductionStrategy;
 use plonky2::fri::{FriConfig, FriParams};
+use serde::{Deserialize, Serialize};
 
 pub struct StarkConfig {
     pub security_bits: usize,
@@ -28,7 +29,115 @@ impl StarkConfig {
         }
     }
 
+    /// The configuration of the FRI parameters of `opts`.
+    pub fn from_opts(opts: &ProverOpts) -> Self {
+        Self {
+            security_bits: opts.security_bits(),
+            num_challenges: 2,
+            fri_config: FriConfig {
+                rate_bits: opts.rate_bits,
+                cap_height: 4,
+                proof_of_work_bits: opts.proof_of_work_bits,
+                reduction_strategy: FriReductionStrategy::ConstantArityBits(4, 5),
+                num_query_rounds: opts.num_query_rounds,
+            },
+        }
+    }
+
     pub(crate) fn fri_params(&self, degree_bits: usize) -> FriParams {
         self.fri_config.fri_params(degree_bits, false)
     }
 }
+
+/// The hash of the transcripts and of the Merkle trees of the proofs, which the proofs declare
+/// so that a verifier rejects those made with another. Poseidon is the only one for now.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum StarkHasher {
+    #[default]
+    Poseidon,
+}
+
+/// The FRI parameters of the STARK proofs. A higher rate makes the proving slower, but needs fewer
+/// queries for the same security, which makes the proofs smaller.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ProverOpts {
+    /// The log of the blowup factor of the low-degree extensions.
+    pub rate_bits: usize,
+    pub num_query_rounds: usize,
+    /// The grinding of the query phase.
+    pub proof_of_work_bits: u32,
+    pub hasher: StarkHasher,
+}
+
+impl Default for ProverOpts {
+    fn default() -> Self {
+        Self::fast()
+    }
+}
+
+impl ProverOpts {
+    /// The parameters of [`StarkConfig::standard_fast_config`]: the fastest proving, and the
+    /// largest proofs.
+    pub fn fast() -> Self {
+        Self {
+            rate_bits: 2,
+            num_query_rounds: 84,
+            proof_of_work_bits: 16,
+            hasher: StarkHasher::Poseidon,
+        }
+    }
+
+    /// The security of [`ProverOpts::fast`], with proofs about a third smaller, but proving about
+    /// twice slower.
+    pub fn balanced() -> Self {
+        Self {
+            rate_bits: 3,
+            num_query_rounds: 56,
+            proof_of_work_bits: 16,
+            hasher: StarkHasher::Poseidon,
+        }
+    }
+
+    /// The security of [`ProverOpts::fast`], with proofs about half the size, but proving about
+    /// four times slower.
+    pub fn small_proof() -> Self {
+        Self {
+            rate_bits: 4,
+            num_query_rounds: 42,
+            proof_of_work_bits: 16,
+            hasher: StarkHasher::Poseidon,
+        }
+    }
+
+    /// The conjectured security of FRI, in bits: each query contributes the rate bits, and the
+    /// grinding its bits.
+    pub fn security_bits(&self) -> usize {
+        self.rate_bits * self.num_query_rounds + self.proof_of_work_bits as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prover_opts() {
+        let fast = StarkConfig::standard_fast_config();
+        let config = StarkConfig::from_opts(&ProverOpts::fast());
+        assert_eq!(config.fri_config.rate_bits, fast.fri_config.rate_bits);
+        assert_eq!(
+            config.fri_config.num_query_rounds,
+            fast.fri_config.num_query_rounds
+        );
+        assert_eq!(
+            config.fri_config.proof_of_work_bits,
+            fast.fri_config.proof_of_work_bits
+        );
+
+        let security = ProverOpts::fast().security_bits();
+        assert_eq!(security, 184);
+        assert_eq!(ProverOpts::balanced().security_bits(), security);
+        assert_eq!(ProverOpts::small_proof().security_bits(), security);
+    }
+}
@@ -0,0 +1,116 @@
+//! The proof of a whole run from the proofs of its segments.
+//!
+//! The emulator splits a run into segments of a bounded number of cycles, each starting from
+//! the image id where the previous one ended, which commits to the registers, the pc and the
+//! memory. The root proof of a segment has these image ids as its `roots_before` and
+//! `roots_after`, the digests hashed by the bootstrap and the exit kernel of the CPU table, and
+//! the aggregation circuit checks that the `roots_after` of its left proof is the `roots_before`
+//! of its right one, so aggregating the root proofs pairwise proves the run, however many
+//! segments it has.
+//!
+//! Both kernels hash the pages of the segment, but only the witness generation checks that the
+//! page hashes and the memory root they find in memory are the digests of these pages.
+
+use anyhow::{ensure, Result};
+use plonky2::field::extension::Extendable;
+use plonky2::hash::hash_types::RichField;
+use plonky2::plonk::config::{AlgebraicHasher, GenericConfig};
+use plonky2::plonk::proof::ProofWithPublicInputs;
+use serde::{Deserialize, Serialize};
+
+use crate::fixed_recursive_verifier::AllRecursiveCircuits;
+use crate::proof::PublicValues;
+
+/// A root proof of a segment, or an aggregation proof of contiguous segments.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct RecursiveProof<F, C, const D: usize>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    pub proof: ProofWithPublicInputs<F, C, D>,
+    pub public_values: PublicValues,
+    pub is_aggregation: bool,
+}
+
+/// Aggregates the proofs of two contiguous runs into a proof of the run of both.
+pub fn aggregate_pair<F, C, const D: usize>(
+    circuits: &AllRecursiveCircuits<F, C, D>,
+    lhs: &RecursiveProof<F, C, D>,
+    rhs: &RecursiveProof<F, C, D>,
+) -> Result<RecursiveProof<F, C, D>>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F> + 'static,
+    C::Hasher: AlgebraicHasher<F>,
+{
+    ensure!(
+        lhs.public_values.roots_after.root == rhs.public_values.roots_before.root,
+        "the segments are not contiguous"
+    );
+    let public_values = PublicValues {
+        roots_before: lhs.public_values.roots_before.clone(),
+        roots_after: rhs.public_values.roots_after.clone(),
+        userdata: rhs.public_values.userdata.clone(),
+        exit_code: rhs.public_values.exit_code,
+        journal_digest: rhs.public_values.journal_digest,
+    };
+    let (proof, public_values) = circuits.prove_aggregation(
+        lhs.is_aggregation,
+        &lhs.proof,
+        rhs.is_aggregation,
+        &rhs.proof,
+        public_values,
+    )?;
+    Ok(RecursiveProof {
+        proof,
+        public_values,
+        is_aggregation: true,
+    })
+}
+
+/// Aggregates the proofs of the segments of a run, in order, into a proof of the run. They are
+/// aggregated as a balanced tree, so that the depth of recursion is logarithmic in their number.
+pub fn aggregate<F, C, const D: usize>(
+    circuits: &AllRecursiveCircuits<F, C, D>,
+    mut proofs: Vec<RecursiveProof<F, C, D>>,
+) -> Result<RecursiveProof<F, C, D>>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F> + 'static,
+    C::Hasher: AlgebraicHasher<F>,
+{
+    ensure!(!proofs.is_empty(), "the run has no segment");
+    while proofs.len() > 1 {
+        let mut level = Vec::with_capacity((proofs.len() + 1) / 2);
+        let mut proofs_iter = proofs.into_iter();
+        while let Some(lhs) = proofs_iter.next() {
+            match proofs_iter.next() {
+                Some(rhs) => level.push(aggregate_pair(circuits, &lhs, &rhs)?),
+                None => level.push(lhs),
+            }
+        }
+        proofs = level;
+    }
+    Ok(proofs.pop().unwrap())
+}
+
+/// Wraps the aggregation proof of a run into a block proof. The block circuit is the same
+/// however many segments were aggregated, so a single verifying key checks the proofs of all
+/// runs.
+pub fn compress<F, C, const D: usize>(
+    circuits: &AllRecursiveCircuits<F, C, D>,
+    proof: &RecursiveProof<F, C, D>,
+) -> Result<(ProofWithPublicInputs<F, C, D>, PublicValues)>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F> + 'static,
+    C::Hasher: AlgebraicHasher<F>,
+{
+    ensure!(
+        proof.is_aggregation,
+        "the block circuit only wraps aggregation proofs, of at least two segments"
+    );
+    circuits.prove_block(None, &proof.proof, proof.public_values.clone())
+}
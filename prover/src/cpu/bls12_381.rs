@@ -0,0 +1,63 @@
+use itertools::Itertools;
+use plonky2::field::extension::Extendable;
+use plonky2::field::packed::PackedField;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+
+use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::cpu::columns::CpuColumnsView;
+
+/// A BLS12-381 field row follows the syscall row, and looks up the addresses of `x` and `y` in
+/// its GP channels 0 and 1. They are the arguments read by the syscall row on its channels 1 and
+/// 2.
+pub fn eval_packed<P: PackedField>(
+    lv: &CpuColumnsView<P>,
+    nv: &CpuColumnsView<P>,
+    yield_constr: &mut ConstraintConsumer<P>,
+) {
+    for filter in [
+        lv.is_bls12381_fp_add,
+        lv.is_bls12381_fp_sub,
+        lv.is_bls12381_fp_mul,
+    ] {
+        yield_constr.constraint(filter * (filter - P::ONES));
+    }
+
+    let is_call = [
+        nv.is_bls12381_fp_add,
+        nv.is_bls12381_fp_sub,
+        nv.is_bls12381_fp_mul,
+    ];
+    for (filter, channel) in is_call.into_iter().cartesian_product([0, 1]) {
+        let arg = lv.mem_channels[channel + 1].value;
+        yield_constr.constraint_transition(filter * (nv.mem_channels[channel].value - arg));
+    }
+}
+
+pub fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut plonky2::plonk::circuit_builder::CircuitBuilder<F, D>,
+    lv: &CpuColumnsView<ExtensionTarget<D>>,
+    nv: &CpuColumnsView<ExtensionTarget<D>>,
+    yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+) {
+    for filter in [
+        lv.is_bls12381_fp_add,
+        lv.is_bls12381_fp_sub,
+        lv.is_bls12381_fp_mul,
+    ] {
+        let constr = builder.mul_sub_extension(filter, filter, filter);
+        yield_constr.constraint(builder, constr);
+    }
+
+    let is_call = [
+        nv.is_bls12381_fp_add,
+        nv.is_bls12381_fp_sub,
+        nv.is_bls12381_fp_mul,
+    ];
+    for (filter, channel) in is_call.into_iter().cartesian_product([0, 1]) {
+        let arg = lv.mem_channels[channel + 1].value;
+        let constr = builder.sub_extension(nv.mem_channels[channel].value, arg);
+        let constr = builder.mul_extension(filter, constr);
+        yield_constr.constraint_transition(builder, constr);
+    }
+}
@@ -9,6 +9,7 @@ use plonky2::plonk::config::GenericConfig;
 
 use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
 use crate::cpu::columns::CpuColumnsView;
+use crate::cpu::exit_kernel::{exit_kernel_row, pad_exit_kernel};
 use crate::cpu::kernel::assembler::Kernel;
 use crate::generation::state::GenerationState;
 use crate::memory::segments::Segment;
@@ -23,6 +24,13 @@ use zkm_emulator::memory::{
 };
 use zkm_emulator::page::{PAGE_ADDR_MASK, PAGE_SIZE};
 
+/// The length in bytes of the preimage of the image id: the memory root and the entry pc.
+pub(crate) const IMAGE_ID_LEN: usize = (END_PC_ADDRESS - ROOT_HASH_ADDRESS_BASE) as usize + 4;
+/// The address of the last block of the preimage of the image id, as the Poseidon sponge CTL
+/// takes it.
+pub(crate) const IMAGE_ID_VIRT: u32 =
+    ROOT_HASH_ADDRESS_BASE + 4 * (IMAGE_ID_LEN / 4 / SPONGE_RATE * SPONGE_RATE) as u32;
+
 pub(crate) fn generate_bootstrap_kernel<
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
@@ -109,14 +117,14 @@ pub(crate) fn check_image_id<
 
     let mut root_hash_addr = Vec::new();
     for chunk in &root_hash_addr_value.iter().chunks(8) {
-        let mut cpu_row = CpuColumnsView::default();
-        cpu_row.clock = F::from_canonical_usize(state.traces.clock());
-        if post {
-            cpu_row.is_exit_kernel = F::ONE;
-            cpu_row.program_counter = F::from_canonical_usize(state.registers.program_counter);
+        let mut cpu_row = if post {
+            exit_kernel_row(state)
         } else {
+            let mut cpu_row = CpuColumnsView::default();
+            cpu_row.clock = F::from_canonical_usize(state.traces.clock());
             cpu_row.is_bootstrap_kernel = F::ONE;
-        }
+            cpu_row
+        };
 
         // Write this chunk to memory, while simultaneously packing its bytes into a u32 word.
         for (channel, (addr, val)) in chunk.enumerate() {
@@ -131,14 +139,17 @@ pub(crate) fn check_image_id<
         state.traces.push_cpu(cpu_row);
     }
 
-    let mut cpu_row = CpuColumnsView::default();
-    cpu_row.clock = F::from_canonical_usize(state.traces.clock());
-    if post {
-        cpu_row.is_exit_kernel = F::ONE;
-        cpu_row.program_counter = F::from_canonical_usize(state.registers.program_counter);
+    // The image id row is the last row of the trace, which gives the image id the segment ends
+    // with to the public values in `ctl_public_values`.
+    let mut cpu_row = if post {
+        pad_exit_kernel(state);
+        exit_kernel_row(state)
     } else {
+        let mut cpu_row = CpuColumnsView::default();
+        cpu_row.clock = F::from_canonical_usize(state.traces.clock());
         cpu_row.is_bootstrap_kernel = F::ONE;
-    }
+        cpu_row
+    };
 
     cpu_row.is_poseidon_sponge = F::ONE;
 
@@ -190,6 +201,7 @@ pub(crate) fn check_memory_page_hash<
     update: bool,
 ) {
     log::trace!("check page hash, addr: {:X}", addr);
+    let checkpoint = state.traces.checkpoint();
     assert_eq!(addr & PAGE_ADDR_MASK as u32, 0u32);
     let page_data_addr_value: Vec<_> = (addr..addr + PAGE_SIZE as u32)
         .step_by(4)
@@ -238,10 +250,7 @@ pub(crate) fn check_memory_page_hash<
         let hash_addr_value: Vec<_> = hash_addr_value.iter().zip(code_hash_be).collect();
 
         for chunk in &hash_addr_value.iter().chunks(8) {
-            let mut cpu_row = CpuColumnsView::default();
-            cpu_row.clock = F::from_canonical_usize(state.traces.clock());
-            cpu_row.is_exit_kernel = F::ONE;
-            cpu_row.program_counter = F::from_canonical_usize(state.registers.program_counter);
+            let mut cpu_row = exit_kernel_row(state);
 
             // Write this chunk to memory, while simultaneously packing its bytes into a u32 word.
             for (channel, (addr, val)) in chunk.enumerate() {
@@ -274,14 +283,14 @@ pub(crate) fn check_memory_page_hash<
         assert_eq!(code_hash_bytes, expected_hash_byte);
     }
 
-    let mut cpu_row = CpuColumnsView::default();
-    cpu_row.clock = F::from_canonical_usize(state.traces.clock());
-    if update {
-        cpu_row.is_exit_kernel = F::ONE;
-        cpu_row.program_counter = F::from_canonical_usize(state.registers.program_counter);
+    let mut cpu_row = if update {
+        exit_kernel_row(state)
     } else {
+        let mut cpu_row = CpuColumnsView::default();
+        cpu_row.clock = F::from_canonical_usize(state.traces.clock());
         cpu_row.is_bootstrap_kernel = F::ONE;
-    }
+        cpu_row
+    };
     cpu_row.is_poseidon_sponge = F::ONE;
 
     // The Poseidon sponge CTL uses memory value columns for its inputs and outputs.
@@ -301,7 +310,9 @@ pub(crate) fn check_memory_page_hash<
     poseidon_sponge_log(state, page_data_addr, page_addr_value_byte_be);
     state.traces.push_cpu(cpu_row);
     if update {
-        state.memory.apply_ops(&state.traces.memory_ops);
+        state
+            .memory
+            .apply_ops(state.traces.mem_ops_since(checkpoint));
     }
 }
 
@@ -338,6 +349,20 @@ pub(crate) fn eval_bootstrap_kernel_packed<F: Field, P: PackedField<Scalar = F>>
     for channel in local_values.mem_channels.iter() {
         yield_constr.constraint_transition(delta_is_bootstrap * channel.used);
     }
+
+    // The final bootstrap row hashes the memory root and the entry pc into the image id, which
+    // the CPU table gives to the public values in `ctl_public_values`.
+    yield_constr
+        .constraint_transition(delta_is_bootstrap * (local_values.is_poseidon_sponge - P::ONES));
+    let image_id_input = [
+        F::ZERO,
+        code_segment,
+        F::from_canonical_u32(IMAGE_ID_VIRT),
+        F::from_canonical_usize(IMAGE_ID_LEN),
+    ];
+    for (channel, expected) in local_values.mem_channels.iter().zip(image_id_input) {
+        yield_constr.constraint_transition(delta_is_bootstrap * (channel.value - expected));
+    }
     /*
     for (&expected, actual) in KERNEL
         .code_hash
@@ -400,6 +425,27 @@ pub(crate) fn eval_bootstrap_kernel_ext_circuit<F: RichField + Extendable<D>, co
         let constraint = builder.mul_extension(delta_is_bootstrap, channel.used);
         yield_constr.constraint_transition(builder, constraint);
     }
+
+    // The final bootstrap row hashes the memory root and the entry pc into the image id, which
+    // the CPU table gives to the public values in `ctl_public_values`.
+    let constraint = builder.mul_sub_extension(
+        delta_is_bootstrap,
+        local_values.is_poseidon_sponge,
+        delta_is_bootstrap,
+    );
+    yield_constr.constraint_transition(builder, constraint);
+    let image_id_input = [
+        0,
+        Segment::Code as u64,
+        IMAGE_ID_VIRT as u64,
+        IMAGE_ID_LEN as u64,
+    ];
+    for (channel, expected) in local_values.mem_channels.iter().zip(image_id_input) {
+        let expected = builder.constant_extension(F::Extension::from_canonical_u64(expected));
+        let diff = builder.sub_extension(channel.value, expected);
+        let constraint = builder.mul_extension(delta_is_bootstrap, diff);
+        yield_constr.constraint_transition(builder, constraint);
+    }
     /*
     for (&expected, actual) in KERNEL
         .code_hash
@@ -0,0 +1,39 @@
+use plonky2::field::extension::Extendable;
+use plonky2::field::packed::PackedField;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+
+use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::cpu::columns::CpuColumnsView;
+
+/// A ChaCha row follows the syscall row, and looks up the address of the state in its GP
+/// channel 0. It is the argument read by the syscall row on its channel 1.
+pub fn eval_packed<P: PackedField>(
+    lv: &CpuColumnsView<P>,
+    nv: &CpuColumnsView<P>,
+    yield_constr: &mut ConstraintConsumer<P>,
+) {
+    let filter = lv.is_chacha_block;
+    yield_constr.constraint(filter * (filter - P::ONES));
+
+    let filter = nv.is_chacha_block;
+    let arg = lv.mem_channels[1].value;
+    yield_constr.constraint_transition(filter * (nv.mem_channels[0].value - arg));
+}
+
+pub fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut plonky2::plonk::circuit_builder::CircuitBuilder<F, D>,
+    lv: &CpuColumnsView<ExtensionTarget<D>>,
+    nv: &CpuColumnsView<ExtensionTarget<D>>,
+    yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+) {
+    let filter = lv.is_chacha_block;
+    let constr = builder.mul_sub_extension(filter, filter, filter);
+    yield_constr.constraint(builder, constr);
+
+    let filter = nv.is_chacha_block;
+    let arg = lv.mem_channels[1].value;
+    let constr = builder.sub_extension(nv.mem_channels[0].value, arg);
+    let constr = builder.mul_extension(filter, constr);
+    yield_constr.constraint_transition(builder, constr);
+}
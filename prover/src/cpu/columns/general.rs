@@ -2,6 +2,9 @@ use std::borrow::{Borrow, BorrowMut};
 use std::fmt::{Debug, Formatter};
 use std::mem::{size_of, transmute};
 
+use crate::cpu::misc::NUM_HW_REGISTERS;
+use crate::cpu::syscall::NUM_PRECOMPILE_CALLS;
+
 /// General purpose columns, which can have different meanings depending on what CTL or other
 /// operation is occurring at this row.
 #[derive(Clone, Copy)]
@@ -11,7 +14,10 @@ pub(crate) union CpuGeneralColumnsView<T: Copy> {
     shift: CpuShiftView<T>,
     io: CpuIOAuxView<T>,
     hash: CpuHashView<T>,
+    keccak: CpuKeccakView<T>,
     misc: CpuMiscView<T>,
+    madd: CpuMaddView<T>,
+    jumps: CpuJumpsView<T>,
 }
 
 impl<T: Copy> CpuGeneralColumnsView<T> {
@@ -25,6 +31,16 @@ impl<T: Copy> CpuGeneralColumnsView<T> {
         unsafe { &mut self.hash }
     }
 
+    // SAFETY: Each view is a valid interpretation of the underlying array.
+    pub(crate) fn keccak(&self) -> &CpuKeccakView<T> {
+        unsafe { &self.keccak }
+    }
+
+    // SAFETY: Each view is a valid interpretation of the underlying array.
+    pub(crate) fn keccak_mut(&mut self) -> &mut CpuKeccakView<T> {
+        unsafe { &mut self.keccak }
+    }
+
     // SAFETY: Each view is a valid interpretation of the underlying array.
     pub(crate) fn syscall(&self) -> &CpuSyscallView<T> {
         unsafe { &self.syscall }
@@ -65,6 +81,26 @@ impl<T: Copy> CpuGeneralColumnsView<T> {
         unsafe { &mut self.misc }
     }
 
+    // SAFETY: Each view is a valid interpretation of the underlying array.
+    pub(crate) fn madd(&self) -> &CpuMaddView<T> {
+        unsafe { &self.madd }
+    }
+
+    // SAFETY: Each view is a valid interpretation of the underlying array.
+    pub(crate) fn madd_mut(&mut self) -> &mut CpuMaddView<T> {
+        unsafe { &mut self.madd }
+    }
+
+    // SAFETY: Each view is a valid interpretation of the underlying array.
+    pub(crate) fn jumps(&self) -> &CpuJumpsView<T> {
+        unsafe { &self.jumps }
+    }
+
+    // SAFETY: Each view is a valid interpretation of the underlying array.
+    pub(crate) fn jumps_mut(&mut self) -> &mut CpuJumpsView<T> {
+        unsafe { &mut self.jumps }
+    }
+
     // SAFETY: Each view is a valid interpretation of the underlying array.
     pub(crate) fn io(&self) -> &CpuIOAuxView<T> {
         unsafe { &self.io }
@@ -111,6 +147,16 @@ pub(crate) struct CpuSyscallView<T: Copy> {
     pub(crate) sysnum: [T; 12],
     pub(crate) a0: [T; 3],
     pub(crate) a1: T,
+    // Whether the syscall is the `i`-th precompile call, and the inverse of the difference of
+    // the syscall number and that of the call otherwise.
+    pub(crate) precompile: [T; NUM_PRECOMPILE_CALLS],
+    pub(crate) precompile_inv: [T; NUM_PRECOMPILE_CALLS],
+    // Whether the syscall is exit or exit_group, and the inverse of the difference of the syscall
+    // number and that of the call otherwise.
+    pub(crate) exit: [T; 2],
+    pub(crate) exit_inv: [T; 2],
+    // The bits of a0 for the exit syscalls, whose low byte is the exit code.
+    pub(crate) exit_code_bits: [T; 32],
 }
 
 #[derive(Copy, Clone)]
@@ -121,9 +167,32 @@ pub(crate) struct CpuMiscView<T: Copy> {
     pub(crate) auxm: T,
     pub(crate) auxl: T,
     pub(crate) auxs: T,
-    pub(crate) rd_index: T,
-    pub(crate) rd_index_eq_0: T,
-    pub(crate) rd_index_eq_29: T,
+    // For RDHWR, whether rd is each of the hardware registers, and the products of its first
+    // 2, 3 and 4 bit literals for that register.
+    pub(crate) rd_eq: [T; NUM_HW_REGISTERS],
+    pub(crate) rd_prefix_eq: [[T; 3]; NUM_HW_REGISTERS],
+}
+
+#[derive(Copy, Clone)]
+pub(crate) struct CpuMaddView<T: Copy> {
+    pub(crate) rs_bits: [T; 32],
+    pub(crate) rt_bits: [T; 32],
+    // Whether rs and rt are negative, for MADD/MSUB only.
+    pub(crate) rs_neg: T,
+    pub(crate) rt_neg: T,
+    // The bits of hi and lo written.
+    pub(crate) hi_bits: [T; 32],
+    pub(crate) lo_bits: [T; 32],
+    // The carry from the low word into the high word, and the carry out of the high word plus 2.
+    pub(crate) carry_lo_bits: [T; 17],
+    pub(crate) carry_hi_bits: [T; 2],
+}
+
+#[derive(Copy, Clone)]
+pub(crate) struct CpuJumpsView<T: Copy> {
+    // Bits of the delay slot address (pc + 4), whose top 4 bits select the 256MB region of a
+    // J/JAL target.
+    pub(crate) delay_slot_bits: [T; 32],
 }
 
 #[derive(Copy, Clone)]
@@ -152,5 +221,11 @@ pub(crate) struct CpuHashView<T: Copy> {
     pub(crate) value: [T; 4],
 }
 
+#[derive(Copy, Clone)]
+pub(crate) struct CpuKeccakView<T: Copy> {
+    // The digest, as the words written to memory on the next row.
+    pub(crate) value: [T; 8],
+}
+
 // `u8` is guaranteed to have a `size_of` of 1.
 pub const NUM_SHARED_COLUMNS: usize = size_of::<CpuGeneralColumnsView<u8>>();
@@ -27,6 +27,10 @@ pub struct CpuBranchView<T: Copy> {
     pub is_ge: T,
     pub is_le: T,
     pub is_ne: T,
+    // Branch-likely: the delay slot is nullified when the branch is not taken.
+    pub is_likely: T,
+    // BLTZAL/BGEZAL: $31 is written with the return address.
+    pub is_link: T,
 }
 
 #[repr(C)]
@@ -58,7 +62,6 @@ pub struct MemIOView<T: Copy> {
     pub(crate) is_swr: T,
     pub(crate) is_ll: T,
     pub(crate) is_sc: T,
-    pub(crate) is_sdc1: T,
     pub(crate) is_lb: T,
     pub(crate) aux_filter: T,
 }
@@ -69,6 +72,16 @@ pub struct CpuColumnsView<T: Copy> {
     /// Filter. 1 if the row is part of bootstrapping the kernel code, 0 otherwise.
     pub is_bootstrap_kernel: T,
     pub is_exit_kernel: T,
+    /// Filter. 1 if the row enters the exception handler of the guest, 0 otherwise.
+    pub is_exception: T,
+    /// Filter. 1 if the row stops the run at an exception the guest does not handle, 0
+    /// otherwise.
+    pub is_trap: T,
+
+    /// The exit code of the run: 0 until it exits, then the low byte of the code passed to the
+    /// exit syscall, or the code of the exception which stopped it. The last row has the exit
+    /// code of the public values.
+    pub exit_code: T,
 
     /// If CPU cycle: Current context.
     // TODO: this is currently unconstrained
@@ -103,6 +116,63 @@ pub struct CpuColumnsView<T: Copy> {
     /// Filter. 1 iff a Poseidon sponge lookup is performed on this row.
     pub is_poseidon_sponge: T,
 
+    /// Filter. 1 iff a Keccak sponge lookup is performed on this row.
+    pub is_keccak_sponge: T,
+
+    /// Filter. 1 iff a SHA-256 extend lookup is performed on this row.
+    pub is_sha_extend: T,
+
+    /// Filter. 1 iff a SHA-256 compress lookup is performed on this row.
+    pub is_sha_compress: T,
+
+    /// Filter. 1 iff a secp256k1 addition lookup is performed on this row.
+    pub is_secp256k1_add: T,
+
+    /// Filter. 1 iff a secp256k1 doubling lookup is performed on this row.
+    pub is_secp256k1_double: T,
+
+    /// Filter. 1 iff an ed25519 addition lookup is performed on this row.
+    pub is_ed25519_add: T,
+
+    /// Filter. 1 iff an ed25519 decompression lookup is performed on this row.
+    pub is_ed25519_decompress: T,
+
+    /// Filter. 1 iff a BN254 addition lookup is performed on this row.
+    pub is_bn254_add: T,
+
+    /// Filter. 1 iff a BN254 doubling lookup is performed on this row.
+    pub is_bn254_double: T,
+
+    /// Filter. 1 iff a BLS12-381 field addition lookup is performed on this row.
+    pub is_bls12381_fp_add: T,
+
+    /// Filter. 1 iff a BLS12-381 field subtraction lookup is performed on this row.
+    pub is_bls12381_fp_sub: T,
+
+    /// Filter. 1 iff a BLS12-381 field multiplication lookup is performed on this row.
+    pub is_bls12381_fp_mul: T,
+
+    /// Filter. 1 iff a uint256 modular multiplication lookup is performed on this row.
+    pub is_uint256_mulmod: T,
+
+    /// Filter. 1 iff a BLAKE2b compression lookup is performed on this row.
+    pub is_blake2b_compress: T,
+
+    /// Filter. 1 iff a BLAKE3 compression lookup is performed on this row.
+    pub is_blake3_compress: T,
+
+    /// Filter. 1 iff a ChaCha20 block lookup is performed on this row.
+    pub is_chacha_block: T,
+
+    /// Filter. 1 iff a secp256r1 addition lookup is performed on this row.
+    pub is_secp256r1_add: T,
+
+    /// Filter. 1 iff a secp256r1 doubling lookup is performed on this row.
+    pub is_secp256r1_double: T,
+
+    /// Filter. 1 iff a modular exponentiation lookup is performed on this row.
+    pub is_modexp: T,
+
     pub(crate) general: CpuGeneralColumnsView<T>,
 
     pub(crate) memio: MemIOView<T>,
@@ -32,13 +32,19 @@ pub struct OpsColumnsView<T: Copy> {
     pub nop: T,
     pub ext: T,
     pub ins: T,
-    pub maddu: T,
+    pub madd: T, // Combines MADD, MADDU, MSUB, MSUBU flags.
     pub rdhwr: T,
+    pub mfc0: T,
+    pub mtc0: T,
+    pub eret: T,
     pub signext8: T,
     pub signext16: T,
     pub swaphalf: T,
     pub teq: T,
+    pub tne: T,
+    pub trap_cmp: T, // Combines TGE, TGEU, TLT, TLTU flags.
     pub ror: T,
+    pub rorv: T,
 
     pub syscall: T,
 }
@@ -0,0 +1,357 @@
+use plonky2::field::extension::Extendable;
+use plonky2::field::packed::PackedField;
+use plonky2::field::types::Field;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+use zkm_emulator::cp0::{
+    CP0_BADVADDR, CP0_CAUSE, CP0_EPC, CP0_REGISTERS_START, CP0_STATUS, EXCEPTION_VECTOR,
+};
+
+use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::cpu::columns::CpuColumnsView;
+use crate::memory::segments::Segment;
+use crate::util::{limb_from_bits_le, limb_from_bits_le_recursive};
+
+/// The CP0 registers an exception row reads or writes, by channel, and whether it reads them.
+const EXCEPTION_CHANNELS: [(usize, bool); 5] = [
+    (CP0_STATUS, true),
+    (CP0_CAUSE, false),
+    (CP0_EPC, false),
+    (CP0_STATUS, false),
+    (CP0_BADVADDR, false),
+];
+
+/// The trap row reads Status on channel 0.
+const TRAP_CHANNELS: [(usize, bool); 1] = [(CP0_STATUS, true)];
+
+/// MFC0 moves the CP0 register `rd`, read on channel 0, to `rt`, written on channel 1; MTC0 moves
+/// `rt` to the CP0 register. The CP0 registers are in the register file, after the others.
+/// ERET reads EPC on channel 0 and Status on channel 1, writes Status back with EXL cleared on
+/// channel 2, and continues at EPC, without a delay slot.
+pub fn eval_packed<P: PackedField>(
+    lv: &CpuColumnsView<P>,
+    nv: &CpuColumnsView<P>,
+    yield_constr: &mut ConstraintConsumer<P>,
+) {
+    let cp0_start = P::Scalar::from_canonical_usize(CP0_REGISTERS_START);
+    let rt = limb_from_bits_le(lv.rt_bits);
+    let cp0_reg = limb_from_bits_le(lv.rd_bits) + cp0_start;
+
+    for (filter, src, dst) in [(lv.op.mfc0, cp0_reg, rt), (lv.op.mtc0, rt, cp0_reg)] {
+        let (src_channel, dst_channel) = (lv.mem_channels[0], lv.mem_channels[1]);
+        yield_constr.constraint(filter * (src_channel.addr_virtual - src));
+        yield_constr.constraint(filter * (dst_channel.addr_virtual - dst));
+        yield_constr.constraint(filter * (dst_channel.value - src_channel.value));
+    }
+
+    let filter = lv.op.eret;
+    let epc_reg = P::Scalar::from_canonical_usize(CP0_REGISTERS_START + CP0_EPC);
+    let status_reg = P::Scalar::from_canonical_usize(CP0_REGISTERS_START + CP0_STATUS);
+    let epc = lv.mem_channels[0];
+    let status_in = lv.mem_channels[1];
+    let status_out = lv.mem_channels[2];
+    yield_constr.constraint(filter * (epc.addr_virtual - epc_reg));
+    yield_constr.constraint(filter * (status_in.addr_virtual - status_reg));
+    yield_constr.constraint(filter * (status_out.addr_virtual - status_reg));
+
+    let status_bits = lv.general.misc().rs_bits;
+    for bit in status_bits {
+        yield_constr.constraint(filter * bit * (bit - P::ONES));
+    }
+    yield_constr.constraint(filter * (status_in.value - limb_from_bits_le(status_bits)));
+    let exl = status_bits[1];
+    yield_constr.constraint(
+        filter * (status_out.value - status_in.value + exl * P::Scalar::from_canonical_u8(2)),
+    );
+
+    yield_constr.constraint(filter * (lv.next_program_counter - epc.value));
+    yield_constr.constraint_transition(
+        filter * (nv.next_program_counter - epc.value - P::Scalar::from_canonical_u64(4)),
+    );
+
+    eval_packed_exception(lv, nv, yield_constr);
+    eval_packed_trap(lv, nv, yield_constr);
+}
+
+/// An exception is entered in a row of its own, in place of the faulting instruction, with its pc
+/// and next pc. The row reads Status on channel 0, and writes Cause, EPC, Status with EXL set and
+/// BadVAddr on channels 1 to 4. EPC is the pc of the instruction, or that of its branch if it is
+/// in a delay slot, which sets Cause.BD. The handler follows at the exception vector. The
+/// exception code and BadVAddr depend on the fault, and are not checked.
+fn eval_packed_exception<P: PackedField>(
+    lv: &CpuColumnsView<P>,
+    nv: &CpuColumnsView<P>,
+    yield_constr: &mut ConstraintConsumer<P>,
+) {
+    let filter = lv.is_exception;
+    yield_constr.constraint(filter * (filter - P::ONES));
+
+    eval_packed_channels(filter, lv, &EXCEPTION_CHANNELS, yield_constr);
+    let [status_in, cause, epc, status_out, _] = core::array::from_fn(|i| lv.mem_channels[i].value);
+
+    // BD is set iff the next pc is not the one after the pc, the instruction being in a delay
+    // slot.
+    let misc = lv.general.misc();
+    let bd = misc.auxm;
+    let not_seq = lv.next_program_counter - lv.program_counter - P::Scalar::from_canonical_u64(4);
+    yield_constr.constraint(filter * (not_seq * misc.auxl - bd));
+    yield_constr.constraint(filter * (P::ONES - bd) * not_seq);
+    yield_constr
+        .constraint(filter * (epc - lv.program_counter + bd * P::Scalar::from_canonical_u64(4)));
+
+    // Exceptions are only delivered with IE set and EXL clear.
+    let status_bits = misc.rs_bits;
+    let cause_bits = misc.is_msb;
+    for bit in status_bits.into_iter().chain(cause_bits) {
+        yield_constr.constraint(filter * bit * (bit - P::ONES));
+    }
+    yield_constr.constraint(filter * (status_in - limb_from_bits_le(status_bits)));
+    yield_constr.constraint(filter * (status_bits[0] - P::ONES));
+    yield_constr.constraint(filter * status_bits[1]);
+    yield_constr.constraint(filter * (status_out - status_in - P::Scalar::from_canonical_u64(2)));
+    yield_constr.constraint(filter * (cause - limb_from_bits_le(cause_bits)));
+    yield_constr.constraint(filter * (cause_bits[31] - bd));
+
+    let vector = P::Scalar::from_canonical_u32(EXCEPTION_VECTOR);
+    yield_constr.constraint_transition(filter * (nv.program_counter - vector));
+    yield_constr.constraint_transition(
+        filter * (nv.next_program_counter - vector - P::Scalar::from_canonical_u64(4)),
+    );
+}
+
+/// An exception the guest cannot handle, with IE clear or EXL set, stops the run in a row of its
+/// own, in place of the faulting instruction. The row reads Status on channel 0, and is followed
+/// by the padding rows. The exit code of the run is the exception code, which is not checked.
+fn eval_packed_trap<P: PackedField>(
+    lv: &CpuColumnsView<P>,
+    nv: &CpuColumnsView<P>,
+    yield_constr: &mut ConstraintConsumer<P>,
+) {
+    let filter = lv.is_trap;
+    yield_constr.constraint(filter * (filter - P::ONES));
+    eval_packed_channels(filter, lv, &TRAP_CHANNELS, yield_constr);
+
+    let status_bits = lv.general.misc().rs_bits;
+    for bit in status_bits {
+        yield_constr.constraint(filter * bit * (bit - P::ONES));
+    }
+    yield_constr.constraint(filter * (lv.mem_channels[0].value - limb_from_bits_le(status_bits)));
+    yield_constr.constraint(filter * status_bits[0] * (P::ONES - status_bits[1]));
+
+    yield_constr.constraint_transition(filter * (nv.is_exit_kernel - P::ONES));
+}
+
+/// The row reads or writes the CP0 register of `channels` on each of the first channels, and
+/// uses no other channel.
+fn eval_packed_channels<P: PackedField>(
+    filter: P,
+    lv: &CpuColumnsView<P>,
+    channels: &[(usize, bool)],
+    yield_constr: &mut ConstraintConsumer<P>,
+) {
+    let register_file = P::Scalar::from_canonical_usize(Segment::RegisterFile as usize);
+    for (channel, &(reg, is_read)) in lv.mem_channels.iter().zip(channels) {
+        let reg = P::Scalar::from_canonical_usize(CP0_REGISTERS_START + reg);
+        yield_constr.constraint(filter * (channel.used - P::ONES));
+        yield_constr.constraint(filter * (channel.is_read - P::Scalar::from_bool(is_read)));
+        yield_constr.constraint(filter * channel.addr_context);
+        yield_constr.constraint(filter * (channel.addr_segment - register_file));
+        yield_constr.constraint(filter * (channel.addr_virtual - reg));
+    }
+    for channel in &lv.mem_channels[channels.len()..] {
+        yield_constr.constraint(filter * channel.used);
+    }
+}
+
+pub fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut plonky2::plonk::circuit_builder::CircuitBuilder<F, D>,
+    lv: &CpuColumnsView<ExtensionTarget<D>>,
+    nv: &CpuColumnsView<ExtensionTarget<D>>,
+    yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+) {
+    let rt = limb_from_bits_le_recursive(builder, lv.rt_bits);
+    let rd = limb_from_bits_le_recursive(builder, lv.rd_bits);
+    let cp0_reg = builder.add_const_extension(rd, F::from_canonical_usize(CP0_REGISTERS_START));
+
+    for (filter, src, dst) in [(lv.op.mfc0, cp0_reg, rt), (lv.op.mtc0, rt, cp0_reg)] {
+        let (src_channel, dst_channel) = (lv.mem_channels[0], lv.mem_channels[1]);
+        for (actual, expected) in [
+            (src_channel.addr_virtual, src),
+            (dst_channel.addr_virtual, dst),
+            (dst_channel.value, src_channel.value),
+        ] {
+            let constr = builder.sub_extension(actual, expected);
+            let constr = builder.mul_extension(filter, constr);
+            yield_constr.constraint(builder, constr);
+        }
+    }
+
+    let filter = lv.op.eret;
+    let epc_reg = builder.constant_extension(F::Extension::from_canonical_usize(
+        CP0_REGISTERS_START + CP0_EPC,
+    ));
+    let status_reg = builder.constant_extension(F::Extension::from_canonical_usize(
+        CP0_REGISTERS_START + CP0_STATUS,
+    ));
+    let epc = lv.mem_channels[0];
+    let status_in = lv.mem_channels[1];
+    let status_out = lv.mem_channels[2];
+    for (actual, expected) in [
+        (epc.addr_virtual, epc_reg),
+        (status_in.addr_virtual, status_reg),
+        (status_out.addr_virtual, status_reg),
+    ] {
+        let constr = builder.sub_extension(actual, expected);
+        let constr = builder.mul_extension(filter, constr);
+        yield_constr.constraint(builder, constr);
+    }
+
+    let status_bits = lv.general.misc().rs_bits;
+    for bit in status_bits {
+        let constr = builder.mul_sub_extension(bit, bit, bit);
+        let constr = builder.mul_extension(filter, constr);
+        yield_constr.constraint(builder, constr);
+    }
+    let status = limb_from_bits_le_recursive(builder, status_bits);
+    let constr = builder.sub_extension(status_in.value, status);
+    let constr = builder.mul_extension(filter, constr);
+    yield_constr.constraint(builder, constr);
+
+    let exl = status_bits[1];
+    let constr = builder.sub_extension(status_out.value, status_in.value);
+    let constr = builder.mul_const_add_extension(F::from_canonical_u8(2), exl, constr);
+    let constr = builder.mul_extension(filter, constr);
+    yield_constr.constraint(builder, constr);
+
+    let constr = builder.sub_extension(lv.next_program_counter, epc.value);
+    let constr = builder.mul_extension(filter, constr);
+    yield_constr.constraint(builder, constr);
+    let constr = builder.sub_extension(nv.next_program_counter, epc.value);
+    let constr = builder.add_const_extension(constr, -F::from_canonical_u64(4));
+    let constr = builder.mul_extension(filter, constr);
+    yield_constr.constraint_transition(builder, constr);
+
+    eval_ext_circuit_exception(builder, lv, nv, yield_constr);
+    eval_ext_circuit_trap(builder, lv, nv, yield_constr);
+}
+
+fn eval_ext_circuit_exception<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut plonky2::plonk::circuit_builder::CircuitBuilder<F, D>,
+    lv: &CpuColumnsView<ExtensionTarget<D>>,
+    nv: &CpuColumnsView<ExtensionTarget<D>>,
+    yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+) {
+    let filter = lv.is_exception;
+    let constr = builder.mul_sub_extension(filter, filter, filter);
+    yield_constr.constraint(builder, constr);
+
+    eval_ext_circuit_channels(builder, filter, lv, &EXCEPTION_CHANNELS, yield_constr);
+    let [status_in, cause, epc, status_out, _] = core::array::from_fn(|i| lv.mem_channels[i].value);
+
+    let misc = lv.general.misc();
+    let bd = misc.auxm;
+    let not_seq = builder.sub_extension(lv.next_program_counter, lv.program_counter);
+    let not_seq = builder.add_const_extension(not_seq, -F::from_canonical_u64(4));
+    let constr = builder.mul_sub_extension(not_seq, misc.auxl, bd);
+    let constr = builder.mul_extension(filter, constr);
+    yield_constr.constraint(builder, constr);
+    let constr = builder.mul_sub_extension(bd, not_seq, not_seq);
+    let constr = builder.mul_extension(filter, constr);
+    yield_constr.constraint(builder, constr);
+    let constr = builder.sub_extension(epc, lv.program_counter);
+    let constr = builder.mul_const_add_extension(F::from_canonical_u64(4), bd, constr);
+    let constr = builder.mul_extension(filter, constr);
+    yield_constr.constraint(builder, constr);
+
+    let status_bits = misc.rs_bits;
+    let cause_bits = misc.is_msb;
+    for bit in status_bits.into_iter().chain(cause_bits) {
+        let constr = builder.mul_sub_extension(bit, bit, bit);
+        let constr = builder.mul_extension(filter, constr);
+        yield_constr.constraint(builder, constr);
+    }
+    let status = limb_from_bits_le_recursive(builder, status_bits);
+    let cause_value = limb_from_bits_le_recursive(builder, cause_bits);
+    let status_exl = builder.add_const_extension(status_in, F::from_canonical_u64(2));
+    for (actual, expected) in [
+        (status_in, status),
+        (status_out, status_exl),
+        (cause, cause_value),
+        (cause_bits[31], bd),
+    ] {
+        let constr = builder.sub_extension(actual, expected);
+        let constr = builder.mul_extension(filter, constr);
+        yield_constr.constraint(builder, constr);
+    }
+    let constr = builder.mul_sub_extension(filter, status_bits[0], filter);
+    yield_constr.constraint(builder, constr);
+    let constr = builder.mul_extension(filter, status_bits[1]);
+    yield_constr.constraint(builder, constr);
+
+    let vector = F::from_canonical_u32(EXCEPTION_VECTOR);
+    for (actual, expected) in [
+        (nv.program_counter, vector),
+        (nv.next_program_counter, vector + F::from_canonical_u64(4)),
+    ] {
+        let constr = builder.add_const_extension(actual, -expected);
+        let constr = builder.mul_extension(filter, constr);
+        yield_constr.constraint_transition(builder, constr);
+    }
+}
+
+fn eval_ext_circuit_trap<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut plonky2::plonk::circuit_builder::CircuitBuilder<F, D>,
+    lv: &CpuColumnsView<ExtensionTarget<D>>,
+    nv: &CpuColumnsView<ExtensionTarget<D>>,
+    yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+) {
+    let filter = lv.is_trap;
+    let constr = builder.mul_sub_extension(filter, filter, filter);
+    yield_constr.constraint(builder, constr);
+    eval_ext_circuit_channels(builder, filter, lv, &TRAP_CHANNELS, yield_constr);
+
+    let status_bits = lv.general.misc().rs_bits;
+    for bit in status_bits {
+        let constr = builder.mul_sub_extension(bit, bit, bit);
+        let constr = builder.mul_extension(filter, constr);
+        yield_constr.constraint(builder, constr);
+    }
+    let status = limb_from_bits_le_recursive(builder, status_bits);
+    let constr = builder.sub_extension(lv.mem_channels[0].value, status);
+    let constr = builder.mul_extension(filter, constr);
+    yield_constr.constraint(builder, constr);
+    let delivers = builder.mul_sub_extension(status_bits[0], status_bits[1], status_bits[0]);
+    let constr = builder.mul_extension(filter, delivers);
+    yield_constr.constraint(builder, constr);
+
+    let constr = builder.mul_sub_extension(filter, nv.is_exit_kernel, filter);
+    yield_constr.constraint_transition(builder, constr);
+}
+
+fn eval_ext_circuit_channels<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut plonky2::plonk::circuit_builder::CircuitBuilder<F, D>,
+    filter: ExtensionTarget<D>,
+    lv: &CpuColumnsView<ExtensionTarget<D>>,
+    channels: &[(usize, bool)],
+    yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+) {
+    let register_file = F::from_canonical_usize(Segment::RegisterFile as usize);
+    for (channel, &(reg, is_read)) in lv.mem_channels.iter().zip(channels) {
+        let reg = F::from_canonical_usize(CP0_REGISTERS_START + reg);
+        for (actual, expected) in [
+            (channel.used, F::ONE),
+            (channel.is_read, F::from_bool(is_read)),
+            (channel.addr_context, F::ZERO),
+            (channel.addr_segment, register_file),
+            (channel.addr_virtual, reg),
+        ] {
+            let constr = builder.add_const_extension(actual, -expected);
+            let constr = builder.mul_extension(filter, constr);
+            yield_constr.constraint(builder, constr);
+        }
+    }
+    for channel in &lv.mem_channels[channels.len()..] {
+        let constr = builder.mul_extension(filter, channel.used);
+        yield_constr.constraint(builder, constr);
+    }
+}
@@ -15,7 +15,9 @@ use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer
 use crate::cpu::columns::{COL_MAP, NUM_CPU_COLUMNS};
 //use crate::cpu::membus::NUM_GP_CHANNELS;
 use crate::cpu::{
-    bits, bootstrap_kernel, count, decode, jumps, membus, memio, misc, shift, syscall,
+    bits, blake, bls12_381, bn254, bootstrap_kernel, chacha, cop0, count, decode, ed25519,
+    exit_kernel, jumps, keccak, membus, memio, misc, modexp, secp256k1, secp256r1, sha256, shift,
+    syscall, uint256,
 };
 use crate::cross_table_lookup::{Column, Filter, TableWithColumns};
 use crate::evaluation_frame::{StarkEvaluationFrame, StarkFrame};
@@ -47,6 +49,296 @@ pub fn ctl_filter_poseidon_sponge<F: Field>() -> Filter<F> {
     Filter::new_simple(Column::single(COL_MAP.is_poseidon_sponge))
 }
 
+pub fn ctl_data_keccak_sponge<F: Field>() -> Vec<Column<F>> {
+    // A Keccak sponge row uses the GP memory channels 0 to 3 as the Poseidon sponge rows do, for
+    // the context, segment, virt and len of the input. The sponge table lists the digest words
+    // from the last.
+    let context = Column::single(COL_MAP.mem_channels[0].value);
+    let segment = Column::single(COL_MAP.mem_channels[1].value);
+    let virt = Column::single(COL_MAP.mem_channels[2].value);
+    let len = Column::single(COL_MAP.mem_channels[3].value);
+
+    let num_channels = F::from_canonical_usize(NUM_CHANNELS);
+    let timestamp = Column::linear_combination([(COL_MAP.clock, num_channels)]);
+
+    let mut cols = vec![context, segment, virt, len, timestamp];
+    cols.extend(
+        COL_MAP
+            .general
+            .keccak()
+            .value
+            .iter()
+            .rev()
+            .map(|&c| Column::single(c)),
+    );
+    cols
+}
+
+pub fn ctl_filter_keccak_sponge<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(COL_MAP.is_keccak_sponge))
+}
+
+pub fn ctl_data_sha_extend<F: Field>() -> Vec<Column<F>> {
+    // The address of `w` is in the value of GP channel 0, unused otherwise on the row.
+    let w_ptr = Column::single(COL_MAP.mem_channels[0].value);
+    let num_channels = F::from_canonical_usize(NUM_CHANNELS);
+    let timestamp = Column::linear_combination([(COL_MAP.clock, num_channels)]);
+    vec![w_ptr, timestamp]
+}
+
+pub fn ctl_filter_sha_extend<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(COL_MAP.is_sha_extend))
+}
+
+pub fn ctl_data_sha_compress<F: Field>() -> Vec<Column<F>> {
+    // The addresses of `w` and of the hash state are in the values of GP channels 0 and 1.
+    let w_ptr = Column::single(COL_MAP.mem_channels[0].value);
+    let h_ptr = Column::single(COL_MAP.mem_channels[1].value);
+    let num_channels = F::from_canonical_usize(NUM_CHANNELS);
+    let timestamp = Column::linear_combination([(COL_MAP.clock, num_channels)]);
+    vec![w_ptr, h_ptr, timestamp]
+}
+
+pub fn ctl_filter_sha_compress<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(COL_MAP.is_sha_compress))
+}
+
+pub fn ctl_data_secp256k1_add<F: Field>() -> Vec<Column<F>> {
+    // The addresses of `p` and `q` are in the values of GP channels 0 and 1.
+    let p_ptr = Column::single(COL_MAP.mem_channels[0].value);
+    let q_ptr = Column::single(COL_MAP.mem_channels[1].value);
+    let num_channels = F::from_canonical_usize(NUM_CHANNELS);
+    let timestamp = Column::linear_combination([(COL_MAP.clock, num_channels)]);
+    vec![p_ptr, q_ptr, timestamp]
+}
+
+pub fn ctl_filter_secp256k1_add<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(COL_MAP.is_secp256k1_add))
+}
+
+pub fn ctl_data_secp256k1_double<F: Field>() -> Vec<Column<F>> {
+    // The address of `p` is in the value of GP channel 0.
+    let p_ptr = Column::single(COL_MAP.mem_channels[0].value);
+    let num_channels = F::from_canonical_usize(NUM_CHANNELS);
+    let timestamp = Column::linear_combination([(COL_MAP.clock, num_channels)]);
+    vec![p_ptr, timestamp]
+}
+
+pub fn ctl_filter_secp256k1_double<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(COL_MAP.is_secp256k1_double))
+}
+
+pub fn ctl_data_ed25519_add<F: Field>() -> Vec<Column<F>> {
+    // The addresses of `p` and `q` are in the values of GP channels 0 and 1.
+    let p_ptr = Column::single(COL_MAP.mem_channels[0].value);
+    let q_ptr = Column::single(COL_MAP.mem_channels[1].value);
+    let num_channels = F::from_canonical_usize(NUM_CHANNELS);
+    let timestamp = Column::linear_combination([(COL_MAP.clock, num_channels)]);
+    vec![p_ptr, q_ptr, timestamp]
+}
+
+pub fn ctl_filter_ed25519_add<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(COL_MAP.is_ed25519_add))
+}
+
+pub fn ctl_data_ed25519_decompress<F: Field>() -> Vec<Column<F>> {
+    // The address of the point is in the value of GP channel 0.
+    let ptr = Column::single(COL_MAP.mem_channels[0].value);
+    let num_channels = F::from_canonical_usize(NUM_CHANNELS);
+    let timestamp = Column::linear_combination([(COL_MAP.clock, num_channels)]);
+    vec![ptr, timestamp]
+}
+
+pub fn ctl_filter_ed25519_decompress<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(COL_MAP.is_ed25519_decompress))
+}
+
+pub fn ctl_data_bn254_add<F: Field>() -> Vec<Column<F>> {
+    // The addresses of `p` and `q` are in the values of GP channels 0 and 1.
+    let p_ptr = Column::single(COL_MAP.mem_channels[0].value);
+    let q_ptr = Column::single(COL_MAP.mem_channels[1].value);
+    let num_channels = F::from_canonical_usize(NUM_CHANNELS);
+    let timestamp = Column::linear_combination([(COL_MAP.clock, num_channels)]);
+    vec![p_ptr, q_ptr, timestamp]
+}
+
+pub fn ctl_filter_bn254_add<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(COL_MAP.is_bn254_add))
+}
+
+pub fn ctl_data_bn254_double<F: Field>() -> Vec<Column<F>> {
+    // The address of `p` is in the value of GP channel 0.
+    let p_ptr = Column::single(COL_MAP.mem_channels[0].value);
+    let num_channels = F::from_canonical_usize(NUM_CHANNELS);
+    let timestamp = Column::linear_combination([(COL_MAP.clock, num_channels)]);
+    vec![p_ptr, timestamp]
+}
+
+pub fn ctl_filter_bn254_double<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(COL_MAP.is_bn254_double))
+}
+
+pub fn ctl_data_bls12381_fp_add<F: Field>() -> Vec<Column<F>> {
+    // The addresses of `x` and `y` are in the values of GP channels 0 and 1.
+    let x_ptr = Column::single(COL_MAP.mem_channels[0].value);
+    let y_ptr = Column::single(COL_MAP.mem_channels[1].value);
+    let num_channels = F::from_canonical_usize(NUM_CHANNELS);
+    let timestamp = Column::linear_combination([(COL_MAP.clock, num_channels)]);
+    vec![x_ptr, y_ptr, timestamp]
+}
+
+pub fn ctl_filter_bls12381_fp_add<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(COL_MAP.is_bls12381_fp_add))
+}
+
+pub fn ctl_data_bls12381_fp_sub<F: Field>() -> Vec<Column<F>> {
+    // The addresses of `x` and `y` are in the values of GP channels 0 and 1.
+    let x_ptr = Column::single(COL_MAP.mem_channels[0].value);
+    let y_ptr = Column::single(COL_MAP.mem_channels[1].value);
+    let num_channels = F::from_canonical_usize(NUM_CHANNELS);
+    let timestamp = Column::linear_combination([(COL_MAP.clock, num_channels)]);
+    vec![x_ptr, y_ptr, timestamp]
+}
+
+pub fn ctl_filter_bls12381_fp_sub<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(COL_MAP.is_bls12381_fp_sub))
+}
+
+pub fn ctl_data_bls12381_fp_mul<F: Field>() -> Vec<Column<F>> {
+    // The addresses of `x` and `y` are in the values of GP channels 0 and 1.
+    let x_ptr = Column::single(COL_MAP.mem_channels[0].value);
+    let y_ptr = Column::single(COL_MAP.mem_channels[1].value);
+    let num_channels = F::from_canonical_usize(NUM_CHANNELS);
+    let timestamp = Column::linear_combination([(COL_MAP.clock, num_channels)]);
+    vec![x_ptr, y_ptr, timestamp]
+}
+
+pub fn ctl_filter_bls12381_fp_mul<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(COL_MAP.is_bls12381_fp_mul))
+}
+
+pub fn ctl_data_uint256_mulmod<F: Field>() -> Vec<Column<F>> {
+    // The addresses of `x` and `y` are in the values of GP channels 0 and 1.
+    let x_ptr = Column::single(COL_MAP.mem_channels[0].value);
+    let y_ptr = Column::single(COL_MAP.mem_channels[1].value);
+    let num_channels = F::from_canonical_usize(NUM_CHANNELS);
+    let timestamp = Column::linear_combination([(COL_MAP.clock, num_channels)]);
+    vec![x_ptr, y_ptr, timestamp]
+}
+
+pub fn ctl_filter_uint256_mulmod<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(COL_MAP.is_uint256_mulmod))
+}
+
+pub fn ctl_data_blake2b_compress<F: Field>() -> Vec<Column<F>> {
+    // The addresses of the hash state and the block are in the values of GP channels 0 and 1.
+    let h_ptr = Column::single(COL_MAP.mem_channels[0].value);
+    let input_ptr = Column::single(COL_MAP.mem_channels[1].value);
+    let num_channels = F::from_canonical_usize(NUM_CHANNELS);
+    let timestamp = Column::linear_combination([(COL_MAP.clock, num_channels)]);
+    vec![h_ptr, input_ptr, timestamp]
+}
+
+pub fn ctl_filter_blake2b_compress<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(COL_MAP.is_blake2b_compress))
+}
+
+pub fn ctl_data_blake3_compress<F: Field>() -> Vec<Column<F>> {
+    // The addresses of the chaining value and the block are in the values of GP channels 0 and 1.
+    let h_ptr = Column::single(COL_MAP.mem_channels[0].value);
+    let input_ptr = Column::single(COL_MAP.mem_channels[1].value);
+    let num_channels = F::from_canonical_usize(NUM_CHANNELS);
+    let timestamp = Column::linear_combination([(COL_MAP.clock, num_channels)]);
+    vec![h_ptr, input_ptr, timestamp]
+}
+
+pub fn ctl_filter_blake3_compress<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(COL_MAP.is_blake3_compress))
+}
+
+pub fn ctl_data_chacha_block<F: Field>() -> Vec<Column<F>> {
+    // The address of the state is in the value of GP channel 0.
+    let state_ptr = Column::single(COL_MAP.mem_channels[0].value);
+    let num_channels = F::from_canonical_usize(NUM_CHANNELS);
+    let timestamp = Column::linear_combination([(COL_MAP.clock, num_channels)]);
+    vec![state_ptr, timestamp]
+}
+
+pub fn ctl_filter_chacha_block<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(COL_MAP.is_chacha_block))
+}
+
+pub fn ctl_data_secp256r1_add<F: Field>() -> Vec<Column<F>> {
+    // The addresses of `p` and `q` are in the values of GP channels 0 and 1.
+    let p_ptr = Column::single(COL_MAP.mem_channels[0].value);
+    let q_ptr = Column::single(COL_MAP.mem_channels[1].value);
+    let num_channels = F::from_canonical_usize(NUM_CHANNELS);
+    let timestamp = Column::linear_combination([(COL_MAP.clock, num_channels)]);
+    vec![p_ptr, q_ptr, timestamp]
+}
+
+pub fn ctl_filter_secp256r1_add<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(COL_MAP.is_secp256r1_add))
+}
+
+pub fn ctl_data_secp256r1_double<F: Field>() -> Vec<Column<F>> {
+    // The address of `p` is in the value of GP channel 0.
+    let p_ptr = Column::single(COL_MAP.mem_channels[0].value);
+    let num_channels = F::from_canonical_usize(NUM_CHANNELS);
+    let timestamp = Column::linear_combination([(COL_MAP.clock, num_channels)]);
+    vec![p_ptr, timestamp]
+}
+
+pub fn ctl_filter_secp256r1_double<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(COL_MAP.is_secp256r1_double))
+}
+
+pub fn ctl_data_modexp<F: Field>() -> Vec<Column<F>> {
+    // The addresses of the base and of the parameters are in the values of GP channels 0 and 1.
+    let base_ptr = Column::single(COL_MAP.mem_channels[0].value);
+    let params_ptr = Column::single(COL_MAP.mem_channels[1].value);
+    let num_channels = F::from_canonical_usize(NUM_CHANNELS);
+    let timestamp = Column::linear_combination([(COL_MAP.clock, num_channels)]);
+    vec![base_ptr, params_ptr, timestamp]
+}
+
+pub fn ctl_filter_modexp<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(COL_MAP.is_modexp))
+}
+
+/// The exit code, then the image id as the four limbs of the Poseidon digest.
+pub fn ctl_data_public_values<F: Field>() -> Vec<Column<F>> {
+    let mut cols = vec![Column::single(COL_MAP.exit_code)];
+    cols.extend(COL_MAP.general.hash().value.map(Column::single));
+    cols
+}
+
+/// The last row, the only exit kernel row followed by a row which is not, as it wraps around to
+/// the first row, and the final bootstrap row. They hash the image ids the segment ends with and
+/// starts from, and the exit code of the latter is 0.
+pub fn ctl_filter_public_values<F: Field>() -> Filter<F> {
+    let next_is_not_exit = Column::linear_combination_and_next_row_with_constant(
+        vec![],
+        vec![(COL_MAP.is_exit_kernel, -F::ONE)],
+        F::ONE,
+    );
+    let next_is_not_bootstrap = Column::linear_combination_and_next_row_with_constant(
+        vec![],
+        vec![(COL_MAP.is_bootstrap_kernel, -F::ONE)],
+        F::ONE,
+    );
+    Filter::new(
+        vec![
+            (Column::single(COL_MAP.is_exit_kernel), next_is_not_exit),
+            (
+                Column::single(COL_MAP.is_bootstrap_kernel),
+                next_is_not_bootstrap,
+            ),
+        ],
+        vec![],
+    )
+}
+
 /// Create the vector of Columns corresponding to the two inputs and
 /// one output of a binary operation.
 /// FIXME: the column is unchecked. The in0 should starts from column 4 in looked table, and in1
@@ -74,6 +366,26 @@ pub fn ctl_filter_logic<F: Field>() -> Filter<F> {
     Filter::new_simple(Column::single(COL_MAP.op.logic_op))
 }
 
+pub fn ctl_data_logic_imm<F: Field>() -> Vec<Column<F>> {
+    // ANDI, ORI and XORI share the logic table rows with AND, OR and XOR, whose
+    // func codes are exactly the immediate opcodes plus 0b011000. Rebuild the
+    // R-type code from the opcode bits so both lookups agree on the operation.
+    let mut res = vec![Column::linear_combination_with_constant(
+        COL_MAP
+            .opcode_bits
+            .into_iter()
+            .enumerate()
+            .map(|(i, c)| (c, F::from_canonical_u64(1 << (i + 6)))),
+        F::from_canonical_u64(0b011000 << 6),
+    )];
+    res.extend(ctl_data_binops());
+    res
+}
+
+pub fn ctl_filter_logic_imm<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(COL_MAP.op.logic_imm_op))
+}
+
 // If an arithmetic operation is happening on the CPU side, the CTL
 // will enforce that the reconstructed opcode value from the
 // opcode bits matches.
@@ -114,6 +426,21 @@ pub fn ctl_arithmetic_imm_base_rows<F: Field>() -> TableWithColumns<F> {
     )
 }
 
+// TGE/TGEU/TLT/TLTU look up the SLT/SLTU rows, whose func codes are 0b101010 + func[0].
+pub fn ctl_arithmetic_trap_rows<F: Field>() -> TableWithColumns<F> {
+    let mut columns = vec![Column::linear_combination_with_constant(
+        [(COL_MAP.func_bits[0], F::from_canonical_u64(1 << 6))],
+        F::from_canonical_u64(0b101010 << 6),
+    )];
+    columns.extend(ctl_data_binops());
+
+    TableWithColumns::new(
+        Table::Cpu,
+        columns,
+        Some(Filter::new_simple(Column::single(COL_MAP.op.trap_cmp))),
+    )
+}
+
 pub fn ctl_data_byte_packing<F: Field>() -> Vec<Column<F>> {
     ctl_data_poseidon_sponge()
 }
@@ -211,9 +538,23 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for CpuStark<F, D
         shift::eval_packed(local_values, yield_constr);
         count::eval_packed(local_values, yield_constr);
         syscall::eval_packed(local_values, yield_constr);
+        syscall::eval_packed_precompile_calls(local_values, next_values, yield_constr);
+        syscall::eval_packed_exit(local_values, next_values, yield_constr);
         bits::eval_packed(local_values, yield_constr);
         misc::eval_packed(local_values, yield_constr);
-        //exit_kernel::eval_exit_kernel_packed(local_values, next_values, yield_constr);
+        cop0::eval_packed(local_values, next_values, yield_constr);
+        keccak::eval_packed(local_values, next_values, yield_constr);
+        sha256::eval_packed(local_values, next_values, yield_constr);
+        secp256k1::eval_packed(local_values, next_values, yield_constr);
+        ed25519::eval_packed(local_values, next_values, yield_constr);
+        bn254::eval_packed(local_values, next_values, yield_constr);
+        bls12_381::eval_packed(local_values, next_values, yield_constr);
+        uint256::eval_packed(local_values, next_values, yield_constr);
+        blake::eval_packed(local_values, next_values, yield_constr);
+        chacha::eval_packed(local_values, next_values, yield_constr);
+        secp256r1::eval_packed(local_values, next_values, yield_constr);
+        modexp::eval_packed(local_values, next_values, yield_constr);
+        exit_kernel::eval_exit_kernel_packed(local_values, next_values, yield_constr);
     }
 
     fn eval_ext_circuit(
@@ -243,9 +584,28 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for CpuStark<F, D
         shift::eval_ext_circuit(builder, local_values, yield_constr);
         count::eval_ext_circuit(builder, local_values, yield_constr);
         syscall::eval_ext_circuit(builder, local_values, yield_constr);
+        syscall::eval_ext_circuit_precompile_calls(
+            builder,
+            local_values,
+            next_values,
+            yield_constr,
+        );
+        syscall::eval_ext_circuit_exit(builder, local_values, next_values, yield_constr);
         bits::eval_ext_circuit(builder, local_values, yield_constr);
         misc::eval_ext_circuit(builder, local_values, yield_constr);
-        //exit_kernel::eval_exit_kernel_ext_circuit(builder, local_values, next_values, yield_constr);
+        cop0::eval_ext_circuit(builder, local_values, next_values, yield_constr);
+        keccak::eval_ext_circuit(builder, local_values, next_values, yield_constr);
+        sha256::eval_ext_circuit(builder, local_values, next_values, yield_constr);
+        secp256k1::eval_ext_circuit(builder, local_values, next_values, yield_constr);
+        ed25519::eval_ext_circuit(builder, local_values, next_values, yield_constr);
+        bn254::eval_ext_circuit(builder, local_values, next_values, yield_constr);
+        bls12_381::eval_ext_circuit(builder, local_values, next_values, yield_constr);
+        uint256::eval_ext_circuit(builder, local_values, next_values, yield_constr);
+        blake::eval_ext_circuit(builder, local_values, next_values, yield_constr);
+        chacha::eval_ext_circuit(builder, local_values, next_values, yield_constr);
+        secp256r1::eval_ext_circuit(builder, local_values, next_values, yield_constr);
+        modexp::eval_ext_circuit(builder, local_values, next_values, yield_constr);
+        exit_kernel::eval_exit_kernel_ext_circuit(builder, local_values, next_values, yield_constr);
     }
 
     fn constraint_degree(&self) -> usize {
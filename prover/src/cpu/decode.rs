@@ -1,66 +1,109 @@
 use plonky2::field::extension::Extendable;
 use plonky2::field::packed::PackedField;
-
+use plonky2::field::types::Field;
 use plonky2::hash::hash_types::RichField;
 use plonky2::iop::ext_target::ExtensionTarget;
 
 use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
 use crate::cpu::columns::{CpuColumnsView, COL_MAP};
+use crate::cpu::membus::NUM_GP_CHANNELS;
+use crate::memory::segments::Segment;
+
+/// List of instruction encodings, for the flags which don't go through a cross-table lookup.
+///  Each entry is `(flag column, value, mask)`: when the flag is set, the bits of the fetched
+/// instruction selected by `mask` must be equal to those of `value`.
+///  Flags whose operation is sent to another table (arithmetic, logic, ...) are checked there, as
+/// the opcode and function code are part of the lookup. Branches, memory operations, traps and
+/// MADD/MSUB check their own encoding next to the rest of their constraints.
+const ENCODINGS: [(usize, u32, u32); 19] = [
+    (COL_MAP.op.movz_op, 0x0000_000a, 0xfc00_003f), // MOVZ
+    (COL_MAP.op.movn_op, 0x0000_000b, 0xfc00_003f), // MOVN
+    (COL_MAP.op.clz_op, 0x7000_0020, 0xfc00_003f),  // CLZ
+    (COL_MAP.op.clo_op, 0x7000_0021, 0xfc00_003f),  // CLO
+    (COL_MAP.op.jumps, 0x0000_0008, 0xfc00_003e),   // JR, JALR
+    (COL_MAP.op.jumpi, 0x0800_0000, 0xf800_0000),   // J, JAL
+    (COL_MAP.op.jumpdirect, 0x0411_0000, 0xffff_0000), // BAL
+    (COL_MAP.op.ext, 0x7c00_0000, 0xfc00_003f),     // EXT
+    (COL_MAP.op.ins, 0x7c00_0004, 0xfc00_003f),     // INS
+    (COL_MAP.op.rdhwr, 0x7c00_003b, 0xfc00_003f),   // RDHWR
+    (COL_MAP.op.mfc0, 0x4000_0000, 0xffe0_07ff),    // MFC0
+    (COL_MAP.op.mtc0, 0x4080_0000, 0xffe0_07ff),    // MTC0
+    (COL_MAP.op.eret, 0x4200_0018, 0xffff_ffff),    // ERET
+    (COL_MAP.op.signext8, 0x7c00_0420, 0xfc00_07ff), // SEB
+    (COL_MAP.op.signext16, 0x7c00_0620, 0xfc00_07ff), // SEH
+    (COL_MAP.op.swaphalf, 0x7c00_00a0, 0xfc00_07ff), // WSBH
+    (COL_MAP.op.ror, 0x0020_0002, 0xffe0_003f),     // ROTR
+    (COL_MAP.op.rorv, 0x0000_0046, 0xfc00_07ff),    // ROTRV
+    (COL_MAP.op.syscall, 0x0000_000c, 0xfc00_003f), // SYSCALL
+];
 
-/// List of opcode blocks
-///  Each block corresponds to exactly one flag, and each flag corresponds to exactly one block.
-///  Each block of opcodes:
-/// - is contiguous,
-/// - has a length that is a power of 2, and
-/// - its start index is a multiple of its length (it is aligned).
-///  These properties permit us to check if an opcode belongs to a block of length 2^n by checking
-/// its top 8-n bits.
-///  Additionally, each block can be made available only to the user, only to the kernel, or to
-/// both. This is mainly useful for making some instructions kernel-only, while still decoding to
-/// invalid for the user. We do this by making one kernel-only block and another user-only block.
-/// The exception is the PANIC instruction which is user-only without a corresponding kernel block.
-/// This makes the proof unverifiable when PANIC is executed in kernel mode, which is the intended
-/// behavior.
-/// Note: invalid opcodes are not represented here. _Any_ opcode is permitted to decode to
-/// `is_invalid`. The kernel then verifies that the opcode was _actually_ invalid.
-/// FIXME: stephen
-const OPCODES: [(u32, usize, bool, usize); 8] = [
-    // (start index of block, number of top bits to check (log2), kernel-only, flag column)
-    // ADD, MUL, SUB, DIV, MOD, LT, GT and BYTE flags are handled partly manually here, and partly through the Arithmetic table CTL.
-    // ADDMOD, MULMOD and SUBMOD flags are handled partly manually here, and partly through the Arithmetic table CTL.
-    (0x7, 1, false, COL_MAP.op.eq_iszero),
-    // AND, OR and XOR flags are handled partly manually here, and partly through the Logic table CTL.
-    // SHL and SHR flags are handled partly manually here, and partly through the Logic table CTL.
-    (0x0B, 0, true, COL_MAP.op.keccak_general),
-    (0x0D, 1, false, COL_MAP.op.jumps), // 0x56-0x57
-    (0x0E, 0, false, COL_MAP.op.branch),
-    (0x0F, 0, false, COL_MAP.op.pc),
-    (0x12, 0, true, COL_MAP.op.get_context),
-    (0x13, 0, true, COL_MAP.op.set_context),
-    (0x16, 0, true, COL_MAP.op.exit_kernel),
-    // MLOAD_GENERAL and MSTORE_GENERAL flags are handled manually here.
+/// The encodings of the instructions run as a no-op, as `(value, mask)`: the NOP flag needs one of
+/// them to match.
+const NOP_ENCODINGS: [(u32, u32); 2] = [
+    (0x0000_000f, 0xfc00_003f), // SYNC
+    (0xcc00_0000, 0xfc00_0000), // PREF
 ];
 
-/// List of combined opcodes requiring a special handling.
-/// Each index in the list corresponds to an arbitrary combination
-/// of opcodes defined in evm/src/cpu/columns/ops.rs.
-const COMBINED_OPCODES: [usize; 7] = [
-    COL_MAP.op.logic_op,
-    COL_MAP.op.binary_op,
-    COL_MAP.op.binary_imm_op,
-    COL_MAP.op.shift,
-    COL_MAP.op.shift_imm,
-    COL_MAP.op.m_op_load,
-    COL_MAP.op.m_op_store,
+/// The flags of the operations which no MIPS instruction decodes to. They must be zero.
+const UNUSED_FLAGS: [usize; 6] = [
+    COL_MAP.op.eq_iszero,
+    COL_MAP.op.keccak_general,
+    COL_MAP.op.pc,
+    COL_MAP.op.get_context,
+    COL_MAP.op.set_context,
+    COL_MAP.op.exit_kernel,
 ];
 
 /// Break up an opcode (which is 32 bits long) into its 32 bits.
 fn bits_from_opcode(opcode: u32) -> [bool; 32] {
-    let mut insn = [false; 32];
-    for i in 0..32 {
-        insn[i] = opcode & (1 << i) != 0;
+    core::array::from_fn(|i| opcode & (1 << i) != 0)
+}
+
+/// The instruction bits, least significant first.
+fn insn_bits<T: Copy>(lv: &CpuColumnsView<T>) -> impl Iterator<Item = T> {
+    lv.func_bits
+        .into_iter()
+        .chain(lv.shamt_bits)
+        .chain(lv.rd_bits)
+        .chain(lv.rt_bits)
+        .chain(lv.rs_bits)
+        .chain(lv.opcode_bits)
+}
+
+/// The number of bits of the instruction selected by `mask` which differ from those of `value`.
+/// The bits are binary, so it is zero iff they all match.
+fn mismatches<P: PackedField>(lv: &CpuColumnsView<P>, value: u32, mask: u32) -> P {
+    let expected = bits_from_opcode(value);
+    let checked = bits_from_opcode(mask);
+    insn_bits(lv)
+        .zip(expected.into_iter().zip(checked))
+        .filter(|&(_, (_, checked))| checked)
+        .map(|(bit, (expected, _))| if expected { P::ONES - bit } else { bit })
+        .sum()
+}
+
+fn mismatches_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut plonky2::plonk::circuit_builder::CircuitBuilder<F, D>,
+    lv: &CpuColumnsView<ExtensionTarget<D>>,
+    value: u32,
+    mask: u32,
+) -> ExtensionTarget<D> {
+    let one = builder.one_extension();
+    let expected = bits_from_opcode(value);
+    let checked = bits_from_opcode(mask);
+    let mut mismatches = builder.zero_extension();
+    for (bit, (expected, checked)) in insn_bits(lv).zip(expected.into_iter().zip(checked)) {
+        if !checked {
+            continue;
+        }
+        let mismatch = if expected {
+            builder.sub_extension(one, bit)
+        } else {
+            bit
+        };
+        mismatches = builder.add_extension(mismatches, mismatch);
     }
-    insn
+    mismatches
 }
 
 pub fn eval_packed_generic<P: PackedField>(
@@ -71,32 +114,48 @@ pub fn eval_packed_generic<P: PackedField>(
     let kernel_mode = lv.is_kernel_mode;
     yield_constr.constraint(kernel_mode * (kernel_mode - P::ONES));
 
-    // Ensure that the opcode bits are valid: each has to be either 0 or 1.
-    for bit in lv.opcode_bits {
+    // Ensure that the instruction bits are valid: each has to be either 0 or 1.
+    for bit in insn_bits(lv) {
         yield_constr.constraint(bit * (bit - P::ONES));
     }
 
     // Check that the instruction flags are valid.
     // First, check that they are all either 0 or 1.
-    for (_, _, _, flag_col) in OPCODES {
-        let flag = lv[flag_col];
+    for &flag in lv.op.iter() {
         yield_constr.constraint(flag * (flag - P::ONES));
     }
-    // Also check that the combined instruction flags are valid.
-    for flag_idx in COMBINED_OPCODES {
-        yield_constr.constraint(lv[flag_idx] * (lv[flag_idx] - P::ONES));
-    }
 
-    // Now check that they sum to 0 or 1, including the combined flags.
-    let flag_sum: P = OPCODES
-        .into_iter()
-        .map(|(_, _, _, flag_col)| lv[flag_col])
-        .chain(COMBINED_OPCODES.map(|op| lv[op]))
-        .sum::<P>();
-    yield_constr.constraint(flag_sum * (flag_sum - P::ONES));
+    // Now check that they sum to 0 or 1.
+    let is_cpu_cycle: P = lv.op.iter().copied().sum();
+    yield_constr.constraint(is_cpu_cycle * (is_cpu_cycle - P::ONES));
+
+    // The instruction bits are those read from the code segment at the program counter; the
+    // fetch uses the last general-purpose channel.
+    {
+        let channel = lv.mem_channels[NUM_GP_CHANNELS - 1];
+        let code_segment = P::Scalar::from_canonical_usize(Segment::Code as usize);
+        let insn: P = insn_bits(lv)
+            .enumerate()
+            .map(|(i, bit)| bit * P::Scalar::from_canonical_u64(1 << i))
+            .sum();
+        yield_constr.constraint(is_cpu_cycle * (channel.used - P::ONES));
+        yield_constr.constraint(is_cpu_cycle * (channel.is_read - P::ONES));
+        yield_constr.constraint(is_cpu_cycle * (channel.addr_context - lv.code_context));
+        yield_constr.constraint(is_cpu_cycle * (channel.addr_segment - code_segment));
+        yield_constr.constraint(is_cpu_cycle * (channel.addr_virtual - lv.program_counter));
+        yield_constr.constraint(is_cpu_cycle * (channel.value - insn));
+    }
 
-    // Finally, classify all opcodes, together with the kernel flag, into blocks
-    // TODO
+    // Finally, check the encoding of the instructions selected by each flag.
+    for (flag_col, value, mask) in ENCODINGS {
+        yield_constr.constraint(lv[flag_col] * mismatches(lv, value, mask));
+    }
+    let [(sync, sync_mask), (pref, pref_mask)] = NOP_ENCODINGS;
+    yield_constr
+        .constraint(lv.op.nop * mismatches(lv, sync, sync_mask) * mismatches(lv, pref, pref_mask));
+    for flag_col in UNUSED_FLAGS {
+        yield_constr.constraint(lv[flag_col]);
+    }
 }
 
 pub fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
@@ -113,36 +172,117 @@ pub fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
         yield_constr.constraint(builder, constr);
     }
 
-    // Ensure that the opcode bits are valid: each has to be either 0 or 1.
-    for bit in lv.opcode_bits {
+    // Ensure that the instruction bits are valid: each has to be either 0 or 1.
+    for bit in insn_bits(lv) {
         let constr = builder.mul_sub_extension(bit, bit, bit);
         yield_constr.constraint(builder, constr);
     }
 
     // Check that the instruction flags are valid.
     // First, check that they are all either 0 or 1.
-    for (_, _, _, flag_col) in OPCODES {
-        let flag = lv[flag_col];
+    for &flag in lv.op.iter() {
         let constr = builder.mul_sub_extension(flag, flag, flag);
         yield_constr.constraint(builder, constr);
     }
-    // Also check that the combined instruction flags are valid.
-    for flag_idx in COMBINED_OPCODES {
-        let constr = builder.mul_sub_extension(lv[flag_idx], lv[flag_idx], lv[flag_idx]);
+
+    // Now check that they sum to 0 or 1.
+    let is_cpu_cycle = builder.add_many_extension(lv.op.iter().copied());
+    {
+        let constr = builder.mul_sub_extension(is_cpu_cycle, is_cpu_cycle, is_cpu_cycle);
         yield_constr.constraint(builder, constr);
     }
 
-    // Now check that they sum to 0 or 1, including the combined flags.
+    // The instruction bits are those read from the code segment at the program counter.
     {
-        let mut flag_sum =
-            builder.add_many_extension(COMBINED_OPCODES.into_iter().map(|idx| lv[idx]));
-        for (_, _, _, flag_col) in OPCODES {
-            let flag = lv[flag_col];
-            flag_sum = builder.add_extension(flag_sum, flag);
+        let channel = lv.mem_channels[NUM_GP_CHANNELS - 1];
+        let code_segment =
+            builder.constant_extension(F::Extension::from_canonical_usize(Segment::Code as usize));
+        let mut insn = builder.zero_extension();
+        for (i, bit) in insn_bits(lv).enumerate() {
+            insn = builder.mul_const_add_extension(F::from_canonical_u64(1 << i), bit, insn);
+        }
+
+        let constr = builder.mul_sub_extension(is_cpu_cycle, channel.used, is_cpu_cycle);
+        yield_constr.constraint(builder, constr);
+        let constr = builder.mul_sub_extension(is_cpu_cycle, channel.is_read, is_cpu_cycle);
+        yield_constr.constraint(builder, constr);
+        for (actual, expected) in [
+            (channel.addr_context, lv.code_context),
+            (channel.addr_segment, code_segment),
+            (channel.addr_virtual, lv.program_counter),
+            (channel.value, insn),
+        ] {
+            let constr = builder.sub_extension(actual, expected);
+            let constr = builder.mul_extension(is_cpu_cycle, constr);
+            yield_constr.constraint(builder, constr);
         }
-        let constr = builder.mul_sub_extension(flag_sum, flag_sum, flag_sum);
+    }
+
+    // Finally, check the encoding of the instructions selected by each flag.
+    for (flag_col, value, mask) in ENCODINGS {
+        let mismatches = mismatches_circuit(builder, lv, value, mask);
+        let constr = builder.mul_extension(lv[flag_col], mismatches);
         yield_constr.constraint(builder, constr);
     }
+    let [(sync, sync_mask), (pref, pref_mask)] = NOP_ENCODINGS;
+    let sync = mismatches_circuit(builder, lv, sync, sync_mask);
+    let pref = mismatches_circuit(builder, lv, pref, pref_mask);
+    let constr = builder.mul_many_extension([lv.op.nop, sync, pref]);
+    yield_constr.constraint(builder, constr);
+    for flag_col in UNUSED_FLAGS {
+        yield_constr.constraint(builder, lv[flag_col]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2::field::goldilocks_field::GoldilocksField;
+
+    use super::*;
+
+    type F = GoldilocksField;
+
+    /// A row fetching `insn` at pc 0, with the flag `flag_col` set.
+    fn row(insn: u32, flag_col: usize) -> CpuColumnsView<F> {
+        let mut lv = CpuColumnsView::default();
+        let bits = lv
+            .func_bits
+            .iter_mut()
+            .chain(lv.shamt_bits.iter_mut())
+            .chain(lv.rd_bits.iter_mut())
+            .chain(lv.rt_bits.iter_mut())
+            .chain(lv.rs_bits.iter_mut())
+            .chain(lv.opcode_bits.iter_mut());
+        for (i, bit) in bits.enumerate() {
+            *bit = F::from_bool(insn & (1 << i) != 0);
+        }
+        let channel = &mut lv.mem_channels[NUM_GP_CHANNELS - 1];
+        channel.used = F::ONE;
+        channel.is_read = F::ONE;
+        channel.addr_segment = F::from_canonical_usize(Segment::Code as usize);
+        channel.value = F::from_canonical_u32(insn);
+        lv[flag_col] = F::ONE;
+        lv
+    }
 
-    // Finally, classify all opcodes, together with the kernel flag, into blocks
+    fn is_valid(lv: &CpuColumnsView<F>) -> bool {
+        let mut consumer = ConstraintConsumer::new(vec![F::TWO], F::ONE, F::ONE, F::ONE);
+        eval_packed_generic(lv, &mut consumer);
+        consumer.accumulators() == [F::ZERO]
+    }
+
+    #[test]
+    fn test_flag_encodings() {
+        assert!(is_valid(&row(0x0000_000c, COL_MAP.op.syscall)));
+        assert!(is_valid(&row(0x0000_000f, COL_MAP.op.nop))); // sync
+        assert!(is_valid(&row(0xcc43_0004, COL_MAP.op.nop))); // pref 3, 4($v0)
+
+        // An add, or any other instruction, cannot be run as a syscall or a no-op.
+        assert!(!is_valid(&row(0x0082_1820, COL_MAP.op.syscall)));
+        assert!(!is_valid(&row(0x0082_1820, COL_MAP.op.nop)));
+        assert!(!is_valid(&row(0x0000_000c, COL_MAP.op.nop)));
+        for flag_col in UNUSED_FLAGS {
+            assert!(!is_valid(&row(0x0000_000c, flag_col)));
+        }
+    }
 }
@@ -0,0 +1,52 @@
+use plonky2::field::extension::Extendable;
+use plonky2::field::packed::PackedField;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+
+use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::cpu::columns::CpuColumnsView;
+
+/// An ed25519 row follows the syscall row, and looks up the address of `p` in its GP channel 0,
+/// and that of `q` in channel 1 for an addition. They are the arguments read by the syscall row
+/// on its channels 1 and 2.
+pub fn eval_packed<P: PackedField>(
+    lv: &CpuColumnsView<P>,
+    nv: &CpuColumnsView<P>,
+    yield_constr: &mut ConstraintConsumer<P>,
+) {
+    for filter in [lv.is_ed25519_add, lv.is_ed25519_decompress] {
+        yield_constr.constraint(filter * (filter - P::ONES));
+    }
+
+    for (filter, channel) in [
+        (nv.is_ed25519_add, 0),
+        (nv.is_ed25519_add, 1),
+        (nv.is_ed25519_decompress, 0),
+    ] {
+        let arg = lv.mem_channels[channel + 1].value;
+        yield_constr.constraint_transition(filter * (nv.mem_channels[channel].value - arg));
+    }
+}
+
+pub fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut plonky2::plonk::circuit_builder::CircuitBuilder<F, D>,
+    lv: &CpuColumnsView<ExtensionTarget<D>>,
+    nv: &CpuColumnsView<ExtensionTarget<D>>,
+    yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+) {
+    for filter in [lv.is_ed25519_add, lv.is_ed25519_decompress] {
+        let constr = builder.mul_sub_extension(filter, filter, filter);
+        yield_constr.constraint(builder, constr);
+    }
+
+    for (filter, channel) in [
+        (nv.is_ed25519_add, 0),
+        (nv.is_ed25519_add, 1),
+        (nv.is_ed25519_decompress, 0),
+    ] {
+        let arg = lv.mem_channels[channel + 1].value;
+        let constr = builder.sub_extension(nv.mem_channels[channel].value, arg);
+        let constr = builder.mul_extension(filter, constr);
+        yield_constr.constraint_transition(builder, constr);
+    }
+}
@@ -7,18 +7,21 @@ use plonky2::iop::ext_target::ExtensionTarget;
 use plonky2::plonk::circuit_builder::CircuitBuilder;
 
 use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
-use crate::cpu::bootstrap_kernel::{check_image_id, check_memory_page_hash};
+use crate::cpu::bootstrap_kernel::{
+    check_image_id, check_memory_page_hash, IMAGE_ID_LEN, IMAGE_ID_VIRT,
+};
 use crate::cpu::columns::CpuColumnsView;
 use crate::cpu::kernel::assembler::Kernel;
 use crate::generation::state::GenerationState;
 use crate::memory::segments::Segment;
 use crate::witness::memory::MemoryAddress;
 use crate::witness::util::mem_write_gp_log_and_fill;
-use crate::witness::util::reg_zero_write_with_log;
 use plonky2::plonk::config::GenericConfig;
 use zkm_emulator::page::PAGE_ADDR_MASK;
 use zkm_emulator::state::REGISTERS_START;
 
+/// Writes the registers to memory, updates the page hashes of the memory the segment ends with
+/// and hashes its image id, which is the last row of the trace.
 pub(crate) fn generate_exit_kernel<
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
@@ -29,17 +32,9 @@ pub(crate) fn generate_exit_kernel<
 ) {
     //  check exit pc = end pc
     assert_eq!(kernel.program.end_pc, state.registers.program_counter);
-    let mut cpu_row = CpuColumnsView::default();
-    cpu_row.clock = F::from_canonical_usize(state.traces.clock());
-    cpu_row.is_kernel_mode = F::ONE;
-    cpu_row.program_counter = F::from_canonical_usize(state.registers.program_counter);
-    cpu_row.next_program_counter = F::from_canonical_usize(state.registers.next_pc);
-
-    let log_end_pc = reg_zero_write_with_log(0, kernel.program.end_pc, state, &mut cpu_row);
-    state.traces.push_memory(log_end_pc);
-    state.traces.push_cpu(cpu_row);
 
     // sync registers to memory
+    let checkpoint = state.traces.checkpoint();
     let registers_addr: Vec<_> = (REGISTERS_START..=REGISTERS_START + (39 << 2) - 1)
         .step_by(4)
         .collect::<Vec<u32>>();
@@ -57,10 +52,7 @@ pub(crate) fn generate_exit_kernel<
 
     let register_addr_value: Vec<_> = registers_addr.iter().zip(registers_value).collect();
     for chunk in &register_addr_value.iter().chunks(8) {
-        let mut cpu_row = CpuColumnsView::default();
-        cpu_row.clock = F::from_canonical_usize(state.traces.clock());
-        cpu_row.is_exit_kernel = F::ONE;
-        cpu_row.program_counter = F::from_canonical_usize(state.registers.program_counter);
+        let mut cpu_row = exit_kernel_row(state);
 
         // Write this chunk to memory, while simultaneously packing its bytes into a u32 word.
         for (channel, (addr, val)) in chunk.enumerate() {
@@ -72,7 +64,9 @@ pub(crate) fn generate_exit_kernel<
 
         state.traces.push_cpu(cpu_row);
     }
-    state.memory.apply_ops(&state.traces.memory_ops);
+    state
+        .memory
+        .apply_ops(state.traces.mem_ops_since(checkpoint));
 
     // update memory hash root
     for (addr, _) in kernel.program.image.iter() {
@@ -83,6 +77,40 @@ pub(crate) fn generate_exit_kernel<
 
     // check post image
     check_image_id(state, kernel, true);
+    log::info!("CPU trace padded to {} cycles", state.traces.clock());
+}
+
+/// A row of the exit kernel, which keeps the pc and the exit code of the segment.
+pub(crate) fn exit_kernel_row<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    state: &GenerationState<F, C, D>,
+) -> CpuColumnsView<F> {
+    let mut row = CpuColumnsView::default();
+    row.clock = F::from_canonical_usize(state.traces.clock());
+    row.context = F::from_canonical_usize(state.registers.context);
+    row.program_counter = F::from_canonical_usize(state.registers.program_counter);
+    row.next_program_counter = F::from_canonical_usize(state.registers.next_pc);
+    row.is_exit_kernel = F::ONE;
+    row.exit_code = F::from_canonical_u8(state.registers.exit_code);
+    row
+}
+
+/// Pads the trace with exit kernel rows, so that the next row is the last one of a trace whose
+/// length is a power of 2.
+pub(crate) fn pad_exit_kernel<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    state: &mut GenerationState<F, C, D>,
+) {
+    while !(state.traces.clock() + 1).is_power_of_two() {
+        let row = exit_kernel_row(state);
+        state.traces.push_cpu(row);
+    }
 }
 
 pub(crate) fn eval_exit_kernel_packed<F: Field, P: PackedField<Scalar = F>>(
@@ -99,7 +127,7 @@ pub(crate) fn eval_exit_kernel_packed<F: Field, P: PackedField<Scalar = F>>(
     yield_constr.constraint_transition(delta_is_exit * (delta_is_exit - P::ONES));
 
     // If this is a exit row and the i'th memory channel is used, it must have the right
-    // address, name context = 0, segment = Code, virt + 4 = next_virt
+    // address, name context = 0, segment = Code
     let code_segment = F::from_canonical_usize(Segment::Code as usize);
     for channel in local_values.mem_channels.iter() {
         let filter = local_is_exit * channel.used;
@@ -107,14 +135,18 @@ pub(crate) fn eval_exit_kernel_packed<F: Field, P: PackedField<Scalar = F>>(
         yield_constr.constraint(filter * (channel.addr_segment - code_segment));
     }
 
-    // for the next is exit, the current pc should be end_pc
-    // for the exit row, all the pc should be end_pc
-    let input0 = local_values.mem_channels[0].value;
-    yield_constr.constraint_transition(delta_is_exit * (input0 - local_values.program_counter));
-
-    yield_constr.constraint_transition(
-        local_is_exit * (next_values.program_counter - local_values.program_counter),
-    );
+    // The last row hashes the memory root and the end pc into the image id, which the CPU table
+    // gives to the public values in `ctl_public_values`.
+    yield_constr.constraint_last_row(local_values.is_poseidon_sponge - P::ONES);
+    let image_id_input = [
+        F::ZERO,
+        code_segment,
+        F::from_canonical_u32(IMAGE_ID_VIRT),
+        F::from_canonical_usize(IMAGE_ID_LEN),
+    ];
+    for (channel, expected) in local_values.mem_channels.iter().zip(image_id_input) {
+        yield_constr.constraint_last_row(channel.value - expected);
+    }
 }
 
 pub(crate) fn eval_exit_kernel_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
@@ -137,7 +169,7 @@ pub(crate) fn eval_exit_kernel_ext_circuit<F: RichField + Extendable<D>, const D
     yield_constr.constraint_transition(builder, constraint);
 
     // If this is a exit row and the i'th memory channel is used, it must have the right
-    // address, name context = 0, segment = Code, virt + 4 = next_virt
+    // address, name context = 0, segment = Code
     let code_segment =
         builder.constant_extension(F::Extension::from_canonical_usize(Segment::Code as usize));
     for channel in local_values.mem_channels {
@@ -150,15 +182,19 @@ pub(crate) fn eval_exit_kernel_ext_circuit<F: RichField + Extendable<D>, const D
         yield_constr.constraint(builder, constraint);
     }
 
-    // for the next is exit, the current pc should be end_pc
-    // for the exit row, all the pc should be end_pc
-    let input0 = local_values.mem_channels[0].value;
-    let pc_constr = builder.sub_extension(input0, local_values.program_counter);
-    let pc_constr = builder.mul_extension(delta_is_exit, pc_constr);
-    yield_constr.constraint_transition(builder, pc_constr);
-
-    let pc_constr =
-        builder.sub_extension(next_values.program_counter, local_values.program_counter);
-    let pc_constr = builder.mul_extension(local_values.is_exit_kernel, pc_constr);
-    yield_constr.constraint_transition(builder, pc_constr);
+    // The last row hashes the memory root and the end pc into the image id, which the CPU table
+    // gives to the public values in `ctl_public_values`.
+    let constraint = builder.sub_extension(local_values.is_poseidon_sponge, one);
+    yield_constr.constraint_last_row(builder, constraint);
+    let image_id_input = [
+        0,
+        Segment::Code as u64,
+        IMAGE_ID_VIRT as u64,
+        IMAGE_ID_LEN as u64,
+    ];
+    for (channel, expected) in local_values.mem_channels.iter().zip(image_id_input) {
+        let expected = builder.constant_extension(F::Extension::from_canonical_u64(expected));
+        let constraint = builder.sub_extension(channel.value, expected);
+        yield_constr.constraint_last_row(builder, constraint);
+    }
 }
@@ -45,7 +45,7 @@ pub fn eval_packed_jump_jumpi<P: PackedField>(
     // constraint:
     // * jump_dest =  offset << 2 + pc_remain
     // * is_jumpi * (next_program_coutner - jump_dest) == 0
-    // * where pc_remain is  pc[28..32] << 28
+    // * where pc_remain is  (pc + 4)[28..32] << 28
     {
         let mut jump_imm = [P::ZEROS; 28];
         jump_imm[2..8].copy_from_slice(&lv.func_bits);
@@ -58,6 +58,20 @@ pub fn eval_packed_jump_jumpi<P: PackedField>(
         let pc_remain = lv.mem_channels[2].value;
         let jump_dest = pc_remain + imm_dst;
         yield_constr.constraint(is_jumpi * (nv.next_program_counter - jump_dest));
+
+        // pc_remain is the top 4 bits of the delay slot address
+        let delay_slot_bits = lv.general.jumps().delay_slot_bits;
+        for bit in delay_slot_bits {
+            yield_constr.constraint(is_jumpi * bit * (bit - P::ONES));
+        }
+        let delay_slot = limb_from_bits_le(delay_slot_bits);
+        yield_constr.constraint(
+            is_jumpi * (lv.program_counter + P::Scalar::from_canonical_u64(4) - delay_slot),
+        );
+        let mut region_bits = [P::ZEROS; 32];
+        region_bits[28..32].copy_from_slice(&delay_slot_bits[28..32]);
+        let region = limb_from_bits_le(region_bits);
+        yield_constr.constraint(is_jumpi * (pc_remain - region));
     }
 
     // Check `jumpdirect target value`:
@@ -155,7 +169,7 @@ pub fn eval_ext_circuit_jump_jumpi<F: RichField + Extendable<D>, const D: usize>
     // constraint:
     // * jump_dest =  offset << 2 + pc_remain
     // * is_jumpi * (next_program_coutner - jump_dest) == 0
-    // * where pc_remain is  pc[28..32] << 28
+    // * where pc_remain is  (pc + 4)[28..32] << 28
     {
         let mut jump_imm = [zero_extension; 28];
 
@@ -171,6 +185,24 @@ pub fn eval_ext_circuit_jump_jumpi<F: RichField + Extendable<D>, const D: usize>
         let constr = builder.sub_extension(nv.next_program_counter, constr);
         let constr = builder.mul_extension(is_jumpi, constr);
         yield_constr.constraint(builder, constr);
+
+        let delay_slot_bits = lv.general.jumps().delay_slot_bits;
+        for bit in delay_slot_bits {
+            let constr = builder.mul_sub_extension(bit, bit, bit);
+            let constr = builder.mul_extension(is_jumpi, constr);
+            yield_constr.constraint(builder, constr);
+        }
+        let delay_slot = limb_from_bits_le_recursive(builder, delay_slot_bits);
+        let base_pc = builder.add_const_extension(lv.program_counter, F::from_canonical_u64(4));
+        let constr = builder.sub_extension(base_pc, delay_slot);
+        let constr = builder.mul_extension(is_jumpi, constr);
+        yield_constr.constraint(builder, constr);
+        let mut region_bits = [zero_extension; 32];
+        region_bits[28..32].copy_from_slice(&delay_slot_bits[28..32]);
+        let region = limb_from_bits_le_recursive(builder, region_bits);
+        let constr = builder.sub_extension(lv.mem_channels[2].value, region);
+        let constr = builder.mul_extension(is_jumpi, constr);
+        yield_constr.constraint(builder, constr);
     }
 
     // Check `jumpdirect target value`:
@@ -274,6 +306,43 @@ pub fn eval_packed_branch<P: PackedField>(
     yield_constr.constraint(branch_lv.should_jump * (P::ONES - branch_lv.should_jump));
     yield_constr.constraint(branch_lv.should_jump * (P::ONES - filter));
 
+    // Check `is_likely`, set for BEQL, BNEL, BLEZL, BGTZL (opcode[4]) and BLTZL, BGEZL
+    // (REGIMM, rt[1]):
+    // constraint:
+    //      (1 - is_likely) * is_likely == 0
+    //      is_likely * (1 - filter) == 0
+    //      filter * (is_likely - (opcode[4] + (1 - opcode[2]) * rt[1])) == 0
+    {
+        let is_likely = branch_lv.is_likely;
+        yield_constr.constraint(is_likely * (P::ONES - is_likely));
+        yield_constr.constraint(is_likely * (P::ONES - filter));
+        let is_regimm = P::ONES - lv.opcode_bits[2];
+        let likely_bit = lv.opcode_bits[4] + is_regimm * lv.rt_bits[1];
+        yield_constr.constraint(filter * (is_likely - likely_bit));
+    }
+
+    // Check `is_link`, set for BLTZAL, BGEZAL (REGIMM, rt[4]):
+    // constraint:
+    //      (1 - is_link) * is_link == 0
+    //      is_link * (1 - filter) == 0
+    //      filter * (is_link - (1 - opcode[2]) * rt[4]) == 0
+    //      is_link * (ret_addr - (pc + 8)) == 0
+    //      is_link * (ret_reg - 31) == 0
+    {
+        let is_link = branch_lv.is_link;
+        yield_constr.constraint(is_link * (P::ONES - is_link));
+        yield_constr.constraint(is_link * (P::ONES - filter));
+        let is_regimm = P::ONES - lv.opcode_bits[2];
+        yield_constr.constraint(filter * (is_link - is_regimm * lv.rt_bits[4]));
+
+        let ret_addr = lv.mem_channels[6].value;
+        let ret_reg = lv.mem_channels[6].addr_virtual;
+        yield_constr.constraint(
+            is_link * (ret_addr - lv.program_counter - P::Scalar::from_canonical_u64(8)),
+        );
+        yield_constr.constraint(is_link * (ret_reg - P::Scalar::from_canonical_u64(31)));
+    }
+
     // Check branch flags:
     //      filter * (1 - (is_eq + is_ne + is_le + is_gt + is_ge + is_lt)) == 0
     //      filter * (1 - (lt + gt + eq)) == 0
@@ -285,7 +354,7 @@ pub fn eval_packed_branch<P: PackedField>(
     // * aux4 = sign_extended(offset << 2)
     // * jump_dest =  sign_extended(offset << 2) + pc + 4
     // * should_jump *(next_program_coutner - jump_dest) * (next_program_coutner + 1 << 32 - jump_dest) == 0
-    // * next_addr = pc + 8
+    // * next_addr = pc + 8 + is_likely * 4
     // * filter * (1 - should_jump) * (next_program_coutner - next_pc) == 0
     {
         let mut branch_offset = [P::ZEROS; 32];
@@ -307,7 +376,9 @@ pub fn eval_packed_branch<P: PackedField>(
                 * (nv.next_program_counter + overflow - branch_dst),
         );
 
-        let next_inst = lv.program_counter + P::Scalar::from_canonical_u64(8);
+        let next_inst = lv.program_counter
+            + P::Scalar::from_canonical_u64(8)
+            + branch_lv.is_likely * P::Scalar::from_canonical_u64(4);
         yield_constr.constraint(
             filter * (P::ONES - branch_lv.should_jump) * (nv.next_program_counter - next_inst),
         );
@@ -445,6 +516,53 @@ pub fn eval_ext_circuit_branch<F: RichField + Extendable<D>, const D: usize>(
         yield_constr.constraint(builder, constr);
     }
 
+    // Check `is_likely`
+    {
+        let is_likely = branch_lv.is_likely;
+        let constr = builder.sub_extension(one_extension, is_likely);
+        let constr = builder.mul_extension(is_likely, constr);
+        yield_constr.constraint(builder, constr);
+
+        let constr = builder.sub_extension(one_extension, filter);
+        let constr = builder.mul_extension(is_likely, constr);
+        yield_constr.constraint(builder, constr);
+
+        let is_regimm = builder.sub_extension(one_extension, lv.opcode_bits[2]);
+        let likely_bit = builder.mul_add_extension(is_regimm, lv.rt_bits[1], lv.opcode_bits[4]);
+        let constr = builder.sub_extension(is_likely, likely_bit);
+        let constr = builder.mul_extension(filter, constr);
+        yield_constr.constraint(builder, constr);
+    }
+
+    // Check `is_link`
+    {
+        let is_link = branch_lv.is_link;
+        let constr = builder.sub_extension(one_extension, is_link);
+        let constr = builder.mul_extension(is_link, constr);
+        yield_constr.constraint(builder, constr);
+
+        let constr = builder.sub_extension(one_extension, filter);
+        let constr = builder.mul_extension(is_link, constr);
+        yield_constr.constraint(builder, constr);
+
+        let is_regimm = builder.sub_extension(one_extension, lv.opcode_bits[2]);
+        let link_bit = builder.mul_extension(is_regimm, lv.rt_bits[4]);
+        let constr = builder.sub_extension(is_link, link_bit);
+        let constr = builder.mul_extension(filter, constr);
+        yield_constr.constraint(builder, constr);
+
+        let ret_addr = lv.mem_channels[6].value;
+        let ret_reg = lv.mem_channels[6].addr_virtual;
+        let link_dest = builder.add_const_extension(lv.program_counter, F::from_canonical_u64(8));
+        let constr = builder.sub_extension(ret_addr, link_dest);
+        let constr = builder.mul_extension(is_link, constr);
+        yield_constr.constraint(builder, constr);
+
+        let constr = builder.add_const_extension(ret_reg, -F::from_canonical_u64(31));
+        let constr = builder.mul_extension(is_link, constr);
+        yield_constr.constraint(builder, constr);
+    }
+
     // Check branch flags:
     {
         let constr = builder.add_extension(norm_filter, special_filter);
@@ -464,7 +582,7 @@ pub fn eval_ext_circuit_branch<F: RichField + Extendable<D>, const D: usize>(
     // * aux4 = sign_extended(offset << 2)
     // * jump_dest =  sign_extended(offset << 2) + pc + 4
     // * filter * should_jump *(next_program_coutner - jump_dest) * (next_program_coutner + 1 << 32 - jump_dest) == 0
-    // * next_addr = pc + 8
+    // * next_addr = pc + 8 + is_likely * 4
     // * filter * (1 - should_jump) * (next_program_coutner - next_pc) == 0
     {
         let mut branch_offset = [zero_extension; 32];
@@ -490,6 +608,11 @@ pub fn eval_ext_circuit_branch<F: RichField + Extendable<D>, const D: usize>(
         yield_constr.constraint(builder, constr);
 
         let next_insn = builder.add_const_extension(lv.program_counter, F::from_canonical_u64(8));
+        let next_insn = builder.mul_const_add_extension(
+            F::from_canonical_u64(4),
+            branch_lv.is_likely,
+            next_insn,
+        );
         let constr_a = builder.sub_extension(one_extension, branch_lv.should_jump);
         let constr_b = builder.sub_extension(nv.next_program_counter, next_insn);
         let constr = builder.mul_extension(constr_a, constr_b);
@@ -672,6 +795,138 @@ pub fn eval_ext_circuit_branch<F: RichField + Extendable<D>, const D: usize>(
     }
 }
 
+/// Delay slot semantics: the instruction after a branch or jump always executes before control
+/// is transferred, so each cycle's `program_counter` is the previous cycle's
+/// `next_program_counter`. Only control-flow instructions may redirect `next_program_counter`,
+/// every other instruction advances it by 4. ERET has no delay slot: its `next_program_counter`
+/// is already EPC. A branch-likely that is not taken nullifies its delay slot, so execution
+/// resumes one instruction later.
+pub fn eval_packed_delay_slot<P: PackedField>(
+    lv: &CpuColumnsView<P>,
+    nv: &CpuColumnsView<P>,
+    yield_constr: &mut ConstraintConsumer<P>,
+) {
+    let is_cpu_cycle: P = lv.op.iter().copied().sum();
+    let is_control_flow = lv.op.jumps + lv.op.jumpi + lv.op.jumpdirect + lv.op.branch + lv.op.eret;
+    let is_sequential = is_cpu_cycle - is_control_flow;
+    let is_nullified = lv.branch.is_likely * (P::ONES - lv.branch.should_jump);
+
+    yield_constr.constraint_transition(
+        (is_cpu_cycle - is_nullified) * (nv.program_counter - lv.next_program_counter),
+    );
+    yield_constr.constraint_transition(
+        is_nullified
+            * (nv.program_counter - lv.next_program_counter - P::Scalar::from_canonical_u64(4)),
+    );
+    yield_constr.constraint_transition(
+        is_sequential
+            * (nv.next_program_counter
+                - lv.next_program_counter
+                - P::Scalar::from_canonical_u64(4)),
+    );
+}
+
+pub fn eval_ext_circuit_delay_slot<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut plonky2::plonk::circuit_builder::CircuitBuilder<F, D>,
+    lv: &CpuColumnsView<ExtensionTarget<D>>,
+    nv: &CpuColumnsView<ExtensionTarget<D>>,
+    yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+) {
+    let is_cpu_cycle = builder.add_many_extension(lv.op.iter().copied());
+    let is_control_flow = builder.add_many_extension([
+        lv.op.jumps,
+        lv.op.jumpi,
+        lv.op.jumpdirect,
+        lv.op.branch,
+        lv.op.eret,
+    ]);
+    let is_sequential = builder.sub_extension(is_cpu_cycle, is_control_flow);
+    let is_nullified = builder.arithmetic_extension(
+        F::NEG_ONE,
+        F::ONE,
+        lv.branch.is_likely,
+        lv.branch.should_jump,
+        lv.branch.is_likely,
+    );
+
+    let constr = builder.sub_extension(nv.program_counter, lv.next_program_counter);
+    let not_nullified = builder.sub_extension(is_cpu_cycle, is_nullified);
+    let constr_a = builder.mul_extension(not_nullified, constr);
+    yield_constr.constraint_transition(builder, constr_a);
+
+    let constr = builder.add_const_extension(constr, -F::from_canonical_u64(4));
+    let constr = builder.mul_extension(is_nullified, constr);
+    yield_constr.constraint_transition(builder, constr);
+
+    let constr = builder.sub_extension(nv.next_program_counter, lv.next_program_counter);
+    let constr = builder.add_const_extension(constr, -F::from_canonical_u64(4));
+    let constr = builder.mul_extension(is_sequential, constr);
+    yield_constr.constraint_transition(builder, constr);
+}
+
+/// The rows without an op flag inside an instruction, as the rows of the precompiles a syscall
+/// looks up, carry the pc and the next pc the instruction continues at, so that the instruction
+/// after them is chained to the instruction before. The exception and trap rows are checked by
+/// `cop0`. The padding rows at the end of the trace keep the pc of the last instruction, and are
+/// only followed by padding rows.
+pub fn eval_packed_aux_rows<P: PackedField>(
+    lv: &CpuColumnsView<P>,
+    nv: &CpuColumnsView<P>,
+    yield_constr: &mut ConstraintConsumer<P>,
+) {
+    let is_cpu_cycle: P = lv.op.iter().copied().sum();
+    let is_exit = lv.is_exit_kernel;
+    yield_constr.constraint(is_exit * (is_exit - P::ONES));
+    let is_kind = is_cpu_cycle + lv.is_bootstrap_kernel + is_exit + lv.is_exception + lv.is_trap;
+    yield_constr.constraint(is_kind * (is_kind - P::ONES));
+
+    let is_aux = P::ONES - is_kind;
+    yield_constr.constraint_transition(is_aux * (nv.program_counter - lv.program_counter));
+    yield_constr
+        .constraint_transition(is_aux * (nv.next_program_counter - lv.next_program_counter));
+
+    yield_constr.constraint_transition(is_exit * (nv.is_exit_kernel - P::ONES));
+    yield_constr.constraint_transition(is_exit * (nv.program_counter - lv.program_counter));
+}
+
+pub fn eval_ext_circuit_aux_rows<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut plonky2::plonk::circuit_builder::CircuitBuilder<F, D>,
+    lv: &CpuColumnsView<ExtensionTarget<D>>,
+    nv: &CpuColumnsView<ExtensionTarget<D>>,
+    yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+) {
+    let one = builder.one_extension();
+    let is_cpu_cycle = builder.add_many_extension(lv.op.iter().copied());
+    let is_exit = lv.is_exit_kernel;
+    let constr = builder.mul_sub_extension(is_exit, is_exit, is_exit);
+    yield_constr.constraint(builder, constr);
+    let is_kind = builder.add_many_extension([
+        is_cpu_cycle,
+        lv.is_bootstrap_kernel,
+        is_exit,
+        lv.is_exception,
+        lv.is_trap,
+    ]);
+    let constr = builder.mul_sub_extension(is_kind, is_kind, is_kind);
+    yield_constr.constraint(builder, constr);
+
+    let is_aux = builder.sub_extension(one, is_kind);
+    for (next, local) in [
+        (nv.program_counter, lv.program_counter),
+        (nv.next_program_counter, lv.next_program_counter),
+    ] {
+        let constr = builder.sub_extension(next, local);
+        let constr = builder.mul_extension(is_aux, constr);
+        yield_constr.constraint_transition(builder, constr);
+    }
+
+    let constr = builder.mul_sub_extension(is_exit, nv.is_exit_kernel, is_exit);
+    yield_constr.constraint_transition(builder, constr);
+    let constr = builder.sub_extension(nv.program_counter, lv.program_counter);
+    let constr = builder.mul_extension(is_exit, constr);
+    yield_constr.constraint_transition(builder, constr);
+}
+
 pub fn eval_packed<P: PackedField>(
     lv: &CpuColumnsView<P>,
     nv: &CpuColumnsView<P>,
@@ -680,6 +935,8 @@ pub fn eval_packed<P: PackedField>(
     //eval_packed_exit_kernel(lv, nv, yield_constr);
     eval_packed_jump_jumpi(lv, nv, yield_constr);
     eval_packed_branch(lv, nv, yield_constr);
+    eval_packed_delay_slot(lv, nv, yield_constr);
+    eval_packed_aux_rows(lv, nv, yield_constr);
 }
 
 pub fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
@@ -691,4 +948,6 @@ pub fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
     //eval_ext_circuit_exit_kernel(builder, lv, nv, yield_constr);
     eval_ext_circuit_jump_jumpi(builder, lv, nv, yield_constr);
     eval_ext_circuit_branch(builder, lv, nv, yield_constr);
+    eval_ext_circuit_delay_slot(builder, lv, nv, yield_constr);
+    eval_ext_circuit_aux_rows(builder, lv, nv, yield_constr);
 }
@@ -0,0 +1,75 @@
+use plonky2::field::extension::Extendable;
+use plonky2::field::packed::PackedField;
+use plonky2::field::types::Field;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+
+use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::cpu::columns::CpuColumnsView;
+use crate::memory::segments::Segment;
+
+/// The digest looked up by a Keccak sponge row is written to memory by the next row, one word
+/// per GP channel, at consecutive addresses.
+pub fn eval_packed<P: PackedField>(
+    lv: &CpuColumnsView<P>,
+    nv: &CpuColumnsView<P>,
+    yield_constr: &mut ConstraintConsumer<P>,
+) {
+    let filter = lv.is_keccak_sponge;
+    yield_constr.constraint(filter * (filter - P::ONES));
+
+    let code = P::Scalar::from_canonical_usize(Segment::Code as usize);
+    let base = nv.mem_channels[0].addr_virtual;
+    for (i, (channel, word)) in nv
+        .mem_channels
+        .iter()
+        .zip(lv.general.keccak().value)
+        .enumerate()
+    {
+        let offset = P::Scalar::from_canonical_usize(4 * i);
+        yield_constr.constraint_transition(filter * (channel.used - P::ONES));
+        yield_constr.constraint_transition(filter * channel.is_read);
+        yield_constr.constraint_transition(filter * channel.addr_context);
+        yield_constr.constraint_transition(filter * (channel.addr_segment - code));
+        yield_constr.constraint_transition(filter * (channel.addr_virtual - base - offset));
+        yield_constr.constraint_transition(filter * (channel.value - word));
+    }
+}
+
+pub fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut plonky2::plonk::circuit_builder::CircuitBuilder<F, D>,
+    lv: &CpuColumnsView<ExtensionTarget<D>>,
+    nv: &CpuColumnsView<ExtensionTarget<D>>,
+    yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+) {
+    let filter = lv.is_keccak_sponge;
+    let constr = builder.mul_sub_extension(filter, filter, filter);
+    yield_constr.constraint(builder, constr);
+
+    let one = builder.one_extension();
+    let code =
+        builder.constant_extension(F::Extension::from_canonical_usize(Segment::Code as usize));
+    let base = nv.mem_channels[0].addr_virtual;
+    for (i, (channel, word)) in nv
+        .mem_channels
+        .iter()
+        .zip(lv.general.keccak().value)
+        .enumerate()
+    {
+        let address = builder.add_const_extension(base, F::from_canonical_usize(4 * i));
+        for (actual, expected) in [
+            (channel.used, one),
+            (channel.addr_segment, code),
+            (channel.addr_virtual, address),
+            (channel.value, word),
+        ] {
+            let constr = builder.sub_extension(actual, expected);
+            let constr = builder.mul_extension(filter, constr);
+            yield_constr.constraint_transition(builder, constr);
+        }
+        for zero in [channel.is_read, channel.addr_context] {
+            let constr = builder.mul_extension(filter, zero);
+            yield_constr.constraint_transition(builder, constr);
+        }
+    }
+}
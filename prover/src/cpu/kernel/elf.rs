@@ -7,8 +7,10 @@ use plonky2::field::goldilocks_field::GoldilocksField;
 use serde::{Deserialize, Serialize};
 use std::fs::{self};
 use std::io::Read;
+use zkm_emulator::cp0::Cp0;
 use zkm_emulator::memory::{INIT_SP, WORD_SIZE};
 use zkm_emulator::state::{Segment, REGISTERS_START};
+use zkm_emulator::syscall::{FdTable, GuardPages, PreimageOracle, MMAP_START};
 pub const PAGE_SIZE: u32 = 4096;
 
 /// A MIPS program
@@ -35,6 +37,12 @@ pub struct Program {
     pub input_stream_ptr: usize,
     pub public_values_stream: Vec<u8>,
     pub public_values_stream_ptr: usize,
+    pub fds: FdTable,
+    pub total_step: usize,
+    pub epoch: u64,
+    pub guard_pages: GuardPages,
+    pub oracle: PreimageOracle,
+    pub cp0: Cp0,
 }
 
 impl Program {
@@ -215,7 +223,7 @@ impl Program {
 
         let lo = 0;
         let hi = 0;
-        let heap = 0x20000000;
+        let heap = MMAP_START as usize;
         let end_pc: u32 = 0;
 
         // this is just for test
@@ -260,6 +268,12 @@ impl Program {
             input_stream_ptr: 0,
             public_values_stream: Vec::new(),
             public_values_stream_ptr: 0,
+            fds: FdTable::default(),
+            total_step: 0,
+            epoch: 0,
+            guard_pages: GuardPages::default(),
+            oracle: PreimageOracle::default(),
+            cp0: Cp0::default(),
         })
     }
 
@@ -343,6 +357,12 @@ impl Program {
             input_stream_ptr: segment.input_stream_ptr,
             public_values_stream: segment.public_values_stream,
             public_values_stream_ptr: segment.public_values_stream_ptr,
+            fds: segment.fds,
+            total_step: segment.total_step as usize,
+            epoch: segment.epoch,
+            guard_pages: segment.guard_pages,
+            oracle: segment.oracle,
+            cp0: segment.cp0,
         })
     }
 }
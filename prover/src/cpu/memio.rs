@@ -169,6 +169,81 @@ fn enforce_byte_ext<F: RichField + Extendable<D>, const D: usize>(
     yield_constr.constraint(builder, fc);
 }
 
+/// Memory flags of the load instructions, together with their opcodes.
+#[inline]
+fn load_flags<T: Copy>(lv: &CpuColumnsView<T>) -> [(T, u64); 8] {
+    [
+        (lv.memio.is_lb, 0b100000),
+        (lv.memio.is_lh, 0b100001),
+        (lv.memio.is_lwl, 0b100010),
+        (lv.memio.is_lw, 0b100011),
+        (lv.memio.is_lbu, 0b100100),
+        (lv.memio.is_lhu, 0b100101),
+        (lv.memio.is_lwr, 0b100110),
+        (lv.memio.is_ll, 0b110000),
+    ]
+}
+
+/// Memory flags of the store instructions, together with their opcodes.
+#[inline]
+fn store_flags<T: Copy>(lv: &CpuColumnsView<T>) -> [(T, u64); 6] {
+    [
+        (lv.memio.is_sb, 0b101000),
+        (lv.memio.is_sh, 0b101001),
+        (lv.memio.is_swl, 0b101010),
+        (lv.memio.is_sw, 0b101011),
+        (lv.memio.is_swr, 0b101110),
+        (lv.memio.is_sc, 0b111000),
+    ]
+}
+
+/// Ensure exactly one of `flags` is set on a filtered row, and that it matches the opcode.
+#[inline]
+fn enforce_opcode_flags<P: PackedField>(
+    yield_constr: &mut ConstraintConsumer<P>,
+    lv: &CpuColumnsView<P>,
+    filter: P,
+    flags: &[(P, u64)],
+) {
+    let opcode = limb_from_bits_le(lv.opcode_bits);
+    let mut flag_sum = P::ZEROS;
+    let mut flag_opcode = P::ZEROS;
+    for &(flag, code) in flags {
+        yield_constr.constraint(flag * (flag - P::ONES));
+        flag_sum += flag;
+        flag_opcode += flag * P::Scalar::from_canonical_u64(code);
+    }
+    yield_constr.constraint(filter * (P::ONES - flag_sum));
+    yield_constr.constraint(filter * (opcode - flag_opcode));
+}
+
+#[inline]
+fn enforce_opcode_flags_ext<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+    lv: &CpuColumnsView<ExtensionTarget<D>>,
+    filter: ExtensionTarget<D>,
+    flags: &[(ExtensionTarget<D>, u64)],
+) {
+    let opcode = limb_from_bits_le_recursive(builder, lv.opcode_bits);
+    let mut flag_sum = builder.zero_extension();
+    let mut flag_opcode = builder.zero_extension();
+    for &(flag, code) in flags {
+        let constr = builder.mul_sub_extension(flag, flag, flag);
+        yield_constr.constraint(builder, constr);
+        flag_sum = builder.add_extension(flag_sum, flag);
+        flag_opcode =
+            builder.mul_const_add_extension(F::from_canonical_u64(code), flag, flag_opcode);
+    }
+    let one = builder.one_extension();
+    let constr = builder.sub_extension(one, flag_sum);
+    let constr = builder.mul_extension(filter, constr);
+    yield_constr.constraint(builder, constr);
+    let constr = builder.sub_extension(opcode, flag_opcode);
+    let constr = builder.mul_extension(filter, constr);
+    yield_constr.constraint(builder, constr);
+}
+
 /// Constant -4
 const GOLDILOCKS_INVERSE_NEG4: u64 = 18446744069414584317;
 
@@ -182,6 +257,9 @@ fn eval_packed_load<P: PackedField>(
     let aux_filter = lv.memio.aux_filter;
     yield_constr.constraint(filter * (P::ONES - aux_filter));
 
+    // Check the memory flag matches the opcode
+    enforce_opcode_flags(yield_constr, lv, filter, &load_flags(lv));
+
     // Check mem channel segment is register
     let diff = lv.mem_channels[0].addr_segment
         - P::Scalar::from_canonical_u64(Segment::RegisterFile as u64);
@@ -450,6 +528,9 @@ fn eval_ext_circuit_load<F: RichField + Extendable<D>, const D: usize>(
     let constr = builder.mul_extension(filter, constr);
     yield_constr.constraint(builder, constr);
 
+    // Check the memory flag matches the opcode
+    enforce_opcode_flags_ext(builder, yield_constr, lv, filter, &load_flags(lv));
+
     // Check mem channel segment is register
     let diff = builder.add_const_extension(
         lv.mem_channels[0].addr_segment,
@@ -744,6 +825,9 @@ fn eval_packed_store<P: PackedField>(
     let aux_filter = lv.memio.aux_filter;
     yield_constr.constraint(filter * (P::ONES - aux_filter));
 
+    // Check the memory flag matches the opcode
+    enforce_opcode_flags(yield_constr, lv, filter, &store_flags(lv));
+
     // Check mem channel segment is register
     let diff = lv.mem_channels[0].addr_segment
         - P::Scalar::from_canonical_u64(Segment::RegisterFile as u64);
@@ -943,15 +1027,16 @@ fn eval_packed_store<P: PackedField>(
     }
 
     // SC:
-    //  TODO: write back rt register
+    //  LL/SC always succeed in a single-threaded guest, so rt is written back with 1
     {
         let rt_value = limb_from_bits_le(rt_limbs);
         yield_constr.constraint(lv.memio.is_sc * (mem - rt_value));
-    }
 
-    // SDC1:
-    {
-        yield_constr.constraint(lv.memio.is_sdc1 * mem);
+        let sc_reg = lv.mem_channels[4].addr_virtual;
+        let sc_result = lv.mem_channels[4].value;
+        let rt_dst = limb_from_bits_le(lv.rt_bits);
+        yield_constr.constraint(lv.memio.is_sc * (sc_reg - rt_dst));
+        yield_constr.constraint(lv.memio.is_sc * (sc_result - P::ONES));
     }
 
     // Disable remaining memory channels, if any.
@@ -974,6 +1059,9 @@ fn eval_ext_circuit_store<F: RichField + Extendable<D>, const D: usize>(
     let constr = builder.mul_extension(filter, constr);
     yield_constr.constraint(builder, constr);
 
+    // Check the memory flag matches the opcode
+    enforce_opcode_flags_ext(builder, yield_constr, lv, filter, &store_flags(lv));
+
     // Check mem channel segment is register
     let diff = builder.add_const_extension(
         lv.mem_channels[0].addr_segment,
@@ -1198,11 +1286,16 @@ fn eval_ext_circuit_store<F: RichField + Extendable<D>, const D: usize>(
         let fc = builder.sub_extension(mem, rt_value);
         let fc = builder.mul_extension(lv.memio.is_sc, fc);
         yield_constr.constraint(builder, fc);
-    }
 
-    // SDC1
-    {
-        let fc = builder.mul_extension(lv.memio.is_sdc1, mem);
+        let sc_reg = lv.mem_channels[4].addr_virtual;
+        let sc_result = lv.mem_channels[4].value;
+        let rt_dst = limb_from_bits_le_recursive(builder, lv.rt_bits);
+        let fc = builder.sub_extension(sc_reg, rt_dst);
+        let fc = builder.mul_extension(lv.memio.is_sc, fc);
+        yield_constr.constraint(builder, fc);
+
+        let fc = builder.add_const_extension(sc_result, F::NEG_ONE);
+        let fc = builder.mul_extension(lv.memio.is_sc, fc);
         yield_constr.constraint(builder, fc);
     }
 
@@ -1,5 +1,6 @@
 use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
 use crate::cpu::columns::CpuColumnsView;
+use crate::memory::segments::Segment;
 use crate::util::{limb_from_bits_le, limb_from_bits_le_recursive};
 use plonky2::field::extension::Extendable;
 use plonky2::field::packed::PackedField;
@@ -7,11 +8,79 @@ use plonky2::field::types::Field;
 use plonky2::hash::hash_types::RichField;
 use plonky2::iop::ext_target::ExtensionTarget;
 
+/// The register file slot of ULR, the thread pointer, which the kernel writes on
+/// `set_thread_area`.
+pub(crate) const LOCAL_USER_REG: usize = 38;
+
+/// Where RDHWR reads a hardware register from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum HwRegister {
+    Const(u32),
+    /// A slot of the register file, managed by the kernel.
+    Register(usize),
+}
+
+pub(crate) const NUM_HW_REGISTERS: usize = 3;
+
+/// The hardware registers RDHWR reads, by `rd`. The others read as 0, including the cycle
+/// counter CC (2), whose value would depend on how the execution is split into segments.
+pub(crate) const HW_REGISTERS: [(u8, HwRegister); NUM_HW_REGISTERS] = [
+    (0, HwRegister::Const(1)),                  // CPUNum
+    (3, HwRegister::Const(1)),                  // CCRes
+    (29, HwRegister::Register(LOCAL_USER_REG)), // ULR
+];
+
+/// The bit `i` of `rd`, or its complement if the bit `i` of `value` is 0: the product of these is
+/// 1 iff `rd = value`.
+fn rd_literal<P: PackedField>(lv: &CpuColumnsView<P>, value: u8, i: usize) -> P {
+    if (value >> i) & 1 == 1 {
+        lv.rd_bits[i]
+    } else {
+        P::ONES - lv.rd_bits[i]
+    }
+}
+
+fn rd_literal_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut plonky2::plonk::circuit_builder::CircuitBuilder<F, D>,
+    lv: &CpuColumnsView<ExtensionTarget<D>>,
+    value: u8,
+    i: usize,
+) -> ExtensionTarget<D> {
+    if (value >> i) & 1 == 1 {
+        lv.rd_bits[i]
+    } else {
+        let one = builder.one_extension();
+        builder.sub_extension(one, lv.rd_bits[i])
+    }
+}
+
+/// Sets the selectors of `rd` which `eval_packed_rdhwr` checks.
+pub(crate) fn fill_rdhwr_selectors<F: Field>(row: &mut CpuColumnsView<F>, rd: u8) {
+    let misc = row.general.misc_mut();
+    for (k, &(reg, _)) in HW_REGISTERS.iter().enumerate() {
+        let literal = |i: usize| ((rd >> i) & 1 == (reg >> i) & 1) as u32;
+        let mut product = literal(0);
+        for i in 1..5 {
+            product *= literal(i);
+            let column = match misc.rd_prefix_eq[k].get_mut(i - 1) {
+                Some(column) => column,
+                None => &mut misc.rd_eq[k],
+            };
+            *column = F::from_canonical_u32(product);
+        }
+    }
+}
+
+/// RDHWR writes the hardware register `rd` of `HW_REGISTERS` to `rt` on channel 0, reading it
+/// from the register file on channel 1 if it is not a constant. `rd_eq[k]` is whether `rd` is
+/// the register `k`, as the product of the literals of the bits of `rd`, accumulated in
+/// `rd_prefix_eq[k]` to keep the degree down: the selectors are boolean, and at most one is set.
 pub fn eval_packed_rdhwr<P: PackedField>(
     lv: &CpuColumnsView<P>,
     yield_constr: &mut ConstraintConsumer<P>,
 ) {
     let filter = lv.op.rdhwr;
+    let misc = lv.general.misc();
 
     // Check rt Reg
     {
@@ -20,27 +89,41 @@ pub fn eval_packed_rdhwr<P: PackedField>(
         yield_constr.constraint(filter * (rt_reg - rt_src));
     }
 
-    // Check rd index
-    {
-        let rd_index = lv.general.misc().rd_index;
-        let rd_dst = limb_from_bits_le(lv.rd_bits);
-        yield_constr.constraint(filter * (rd_index - rd_dst));
-    }
-
     // Check rt value
-    {
-        let rt_val = lv.mem_channels[0].value;
-        let local_user = lv.mem_channels[1].value;
-        let rd_index = lv.general.misc().rd_index;
-        let rd_eq_0 = lv.general.misc().rd_index_eq_0;
-        let rd_eq_29 = lv.general.misc().rd_index_eq_29;
-        yield_constr.constraint(filter * rd_eq_0 * rd_index);
-        yield_constr.constraint(filter * rd_eq_0 * (rt_val - P::ONES));
-        yield_constr
-            .constraint(filter * rd_eq_29 * (rd_index - P::Scalar::from_canonical_usize(29)));
-        yield_constr.constraint(filter * rd_eq_29 * (rt_val - local_user));
-        yield_constr.constraint(filter * (P::ONES - rd_eq_29 - rd_eq_0) * rt_val);
+    let rt_val = lv.mem_channels[0].value;
+    let hw_reg = lv.mem_channels[1];
+    let register_file = P::Scalar::from_canonical_usize(Segment::RegisterFile as usize);
+    let mut is_listed = P::ZEROS;
+    let mut is_read = P::ZEROS;
+    for (k, &(rd, register)) in HW_REGISTERS.iter().enumerate() {
+        let prefix = misc.rd_prefix_eq[k];
+        let eq = misc.rd_eq[k];
+        let mut product = rd_literal(lv, rd, 0);
+        for (i, &next) in prefix.iter().chain([&eq]).enumerate() {
+            yield_constr.constraint(filter * (next - product * rd_literal(lv, rd, i + 1)));
+            product = next;
+        }
+
+        is_listed += eq;
+        match register {
+            HwRegister::Const(value) => {
+                yield_constr
+                    .constraint(filter * eq * (rt_val - P::Scalar::from_canonical_u32(value)));
+            }
+            HwRegister::Register(reg) => {
+                is_read += eq;
+                let reg = P::Scalar::from_canonical_usize(reg);
+                yield_constr.constraint(filter * eq * (hw_reg.used - P::ONES));
+                yield_constr.constraint(filter * eq * (hw_reg.is_read - P::ONES));
+                yield_constr.constraint(filter * eq * hw_reg.addr_context);
+                yield_constr.constraint(filter * eq * (hw_reg.addr_segment - register_file));
+                yield_constr.constraint(filter * eq * (hw_reg.addr_virtual - reg));
+                yield_constr.constraint(filter * eq * (rt_val - hw_reg.value));
+            }
+        }
     }
+    yield_constr.constraint(filter * (P::ONES - is_read) * hw_reg.used);
+    yield_constr.constraint(filter * (P::ONES - is_listed) * rt_val);
 }
 
 pub fn eval_ext_circuit_rdhwr<F: RichField + Extendable<D>, const D: usize>(
@@ -49,6 +132,7 @@ pub fn eval_ext_circuit_rdhwr<F: RichField + Extendable<D>, const D: usize>(
     yield_constr: &mut RecursiveConstraintConsumer<F, D>,
 ) {
     let filter = lv.op.rdhwr;
+    let misc = lv.general.misc();
 
     // Check rt Reg
     {
@@ -59,48 +143,56 @@ pub fn eval_ext_circuit_rdhwr<F: RichField + Extendable<D>, const D: usize>(
         yield_constr.constraint(builder, constr);
     }
 
-    // Check rd index
-    {
-        let rd_index = lv.general.misc().rd_index;
-        let rd_src = limb_from_bits_le_recursive(builder, lv.rd_bits);
-        let constr = builder.sub_extension(rd_index, rd_src);
-        let constr = builder.mul_extension(constr, filter);
-        yield_constr.constraint(builder, constr);
-    }
-
     // Check rt value
-    {
-        let rt_val = lv.mem_channels[0].value;
-        let local_user = lv.mem_channels[1].value;
-        let rd_index = lv.general.misc().rd_index;
-        let rd_eq_0 = lv.general.misc().rd_index_eq_0;
-        let rd_eq_29 = lv.general.misc().rd_index_eq_29;
-        let one_extension = builder.one_extension();
-
-        let constr = builder.mul_extension(rd_eq_0, rd_index);
-        let constr = builder.mul_extension(constr, filter);
-        yield_constr.constraint(builder, constr);
-
-        let constr = builder.sub_extension(rt_val, one_extension);
-        let constr = builder.mul_extension(constr, rd_eq_0);
-        let constr = builder.mul_extension(constr, filter);
-        yield_constr.constraint(builder, constr);
-
-        let constant29 = builder.constant_extension(F::Extension::from_canonical_usize(29));
-        let constr = builder.sub_extension(rd_index, constant29);
-        let constr = builder.mul_extension(rd_eq_29, constr);
-        let constr = builder.mul_extension(constr, filter);
-        yield_constr.constraint(builder, constr);
-
-        let constr = builder.sub_extension(rt_val, local_user);
-        let constr = builder.mul_extension(constr, rd_eq_29);
-        let constr = builder.mul_extension(constr, filter);
-        yield_constr.constraint(builder, constr);
+    let rt_val = lv.mem_channels[0].value;
+    let hw_reg = lv.mem_channels[1];
+    let register_file = F::from_canonical_usize(Segment::RegisterFile as usize);
+    let mut is_listed = builder.zero_extension();
+    let mut is_read = builder.zero_extension();
+    for (k, &(rd, register)) in HW_REGISTERS.iter().enumerate() {
+        let prefix = misc.rd_prefix_eq[k];
+        let eq = misc.rd_eq[k];
+        let mut product = rd_literal_circuit(builder, lv, rd, 0);
+        for (i, &next) in prefix.iter().chain([&eq]).enumerate() {
+            let literal = rd_literal_circuit(builder, lv, rd, i + 1);
+            let constr = builder.mul_sub_extension(product, literal, next);
+            let constr = builder.mul_extension(filter, constr);
+            yield_constr.constraint(builder, constr);
+            product = next;
+        }
 
-        let constr = builder.sub_extension(one_extension, rd_eq_0);
-        let constr = builder.sub_extension(constr, rd_eq_29);
-        let constr = builder.mul_extension(constr, rt_val);
-        let constr = builder.mul_extension(constr, filter);
+        is_listed = builder.add_extension(is_listed, eq);
+        let filter = builder.mul_extension(filter, eq);
+        match register {
+            HwRegister::Const(value) => {
+                let constr = builder.add_const_extension(rt_val, -F::from_canonical_u32(value));
+                let constr = builder.mul_extension(filter, constr);
+                yield_constr.constraint(builder, constr);
+            }
+            HwRegister::Register(reg) => {
+                is_read = builder.add_extension(is_read, eq);
+                for (actual, expected) in [
+                    (hw_reg.used, F::ONE),
+                    (hw_reg.is_read, F::ONE),
+                    (hw_reg.addr_context, F::ZERO),
+                    (hw_reg.addr_segment, register_file),
+                    (hw_reg.addr_virtual, F::from_canonical_usize(reg)),
+                ] {
+                    let constr = builder.add_const_extension(actual, -expected);
+                    let constr = builder.mul_extension(filter, constr);
+                    yield_constr.constraint(builder, constr);
+                }
+                let constr = builder.sub_extension(rt_val, hw_reg.value);
+                let constr = builder.mul_extension(filter, constr);
+                yield_constr.constraint(builder, constr);
+            }
+        }
+    }
+    let one = builder.one_extension();
+    for (selected, value) in [(is_read, hw_reg.used), (is_listed, rt_val)] {
+        let constr = builder.sub_extension(one, selected);
+        let constr = builder.mul_extension(constr, value);
+        let constr = builder.mul_extension(filter, constr);
         yield_constr.constraint(builder, constr);
     }
 }
@@ -141,6 +233,27 @@ pub fn eval_packed_condmov<P: PackedField>(
         yield_constr.constraint(filter * mov * no_mov);
 
         yield_constr.constraint(filter * (out - (mov * rs + no_mov * rd)));
+
+        // `p_inv0` may only be zeroed out when rt is zero:
+        // * is_movn * rt * (1 - mov) == 0
+        // * is_movz * rt * mov == 0
+        yield_constr.constraint(is_movn * rt * no_mov);
+        yield_constr.constraint(is_movz * rt * mov);
+    }
+
+    // Check register indexes
+    {
+        let rs_reg = lv.mem_channels[0].addr_virtual;
+        let rt_reg = lv.mem_channels[1].addr_virtual;
+        let rd_reg = lv.mem_channels[2].addr_virtual;
+        let out_reg = lv.mem_channels[3].addr_virtual;
+        let rs_src = limb_from_bits_le(lv.rs_bits);
+        let rt_src = limb_from_bits_le(lv.rt_bits);
+        let rd_dst = limb_from_bits_le(lv.rd_bits);
+        yield_constr.constraint(filter * (rs_reg - rs_src));
+        yield_constr.constraint(filter * (rt_reg - rt_src));
+        yield_constr.constraint(filter * (rd_reg - rd_dst));
+        yield_constr.constraint(filter * (out_reg - rd_dst));
     }
 }
 
@@ -191,14 +304,40 @@ pub fn eval_ext_circuit_condmov<F: RichField + Extendable<D>, const D: usize>(
         let constr = builder.sub_extension(out, constr);
         let constr = builder.mul_extension(filter, constr);
         yield_constr.constraint(builder, constr);
+
+        // `p_inv0` may only be zeroed out when rt is zero:
+        // * is_movn * rt * (1 - mov) == 0
+        // * is_movz * rt * mov == 0
+        let constr = builder.mul_extension(rt, no_mov);
+        let constr = builder.mul_extension(is_movn, constr);
+        yield_constr.constraint(builder, constr);
+
+        let constr = builder.mul_extension(rt, mov);
+        let constr = builder.mul_extension(is_movz, constr);
+        yield_constr.constraint(builder, constr);
+    }
+
+    // Check register indexes
+    {
+        let rs_src = limb_from_bits_le_recursive(builder, lv.rs_bits);
+        let rt_src = limb_from_bits_le_recursive(builder, lv.rt_bits);
+        let rd_dst = limb_from_bits_le_recursive(builder, lv.rd_bits);
+        for (channel, reg) in [(0, rs_src), (1, rt_src), (2, rd_dst), (3, rd_dst)] {
+            let constr = builder.sub_extension(lv.mem_channels[channel].addr_virtual, reg);
+            let constr = builder.mul_extension(filter, constr);
+            yield_constr.constraint(builder, constr);
+        }
     }
 }
 
-pub fn eval_packed_teq<P: PackedField>(
+pub fn eval_packed_trap<P: PackedField>(
     lv: &CpuColumnsView<P>,
     yield_constr: &mut ConstraintConsumer<P>,
 ) {
-    let filter = lv.op.teq;
+    let is_teq = lv.op.teq;
+    let is_tne = lv.op.tne;
+    let is_cmp = lv.op.trap_cmp;
+    let filter = is_teq + is_tne + is_cmp;
 
     // Check rt Reg
     {
@@ -214,22 +353,45 @@ pub fn eval_packed_teq<P: PackedField>(
         yield_constr.constraint(filter * (rs_reg - rs_dst));
     }
 
-    // Check rs_val != rt_val, Otherwise trap will be triggered
+    // Check the instruction encoding
+    // teq: 0b110100, tne: 0b110110, tge/tgeu/tlt/tltu: 0b1100xx
+    {
+        let code = limb_from_bits_le(lv.opcode_bits.into_iter().chain(lv.func_bits));
+        let code_of = |func: u64| P::Scalar::from_canonical_u64(func << 6);
+        yield_constr.constraint(is_teq * (code - code_of(0b110100)));
+        yield_constr.constraint(is_tne * (code - code_of(0b110110)));
+        let cmp_code = lv.func_bits[0] * code_of(1) + lv.func_bits[1] * code_of(2);
+        yield_constr.constraint(is_cmp * (code - code_of(0b110000) - cmp_code));
+    }
+
+    // Check the trap condition does not hold, otherwise the trap will be triggered
+    // teq: rs_val != rt_val
+    // tne: rs_val == rt_val
+    // tge/tgeu: slt(rs_val, rt_val) == 1
+    // tlt/tltu: slt(rs_val, rt_val) == 0
     {
         let rs_val = lv.mem_channels[0].value;
         let rt_val = lv.mem_channels[1].value;
         let p_inv0 = lv.general.logic().diff_pinv;
         let is_ne = (rs_val - rt_val) * p_inv0;
-        yield_constr.constraint(filter * (P::ONES - is_ne));
+        yield_constr.constraint(is_teq * (P::ONES - is_ne));
+        yield_constr.constraint(is_tne * (rs_val - rt_val));
+
+        let is_lt = lv.mem_channels[2].value;
+        yield_constr.constraint(is_cmp * (is_lt - (P::ONES - lv.func_bits[1])));
     }
 }
 
-pub fn eval_ext_circuit_teq<F: RichField + Extendable<D>, const D: usize>(
+pub fn eval_ext_circuit_trap<F: RichField + Extendable<D>, const D: usize>(
     builder: &mut plonky2::plonk::circuit_builder::CircuitBuilder<F, D>,
     lv: &CpuColumnsView<ExtensionTarget<D>>,
     yield_constr: &mut RecursiveConstraintConsumer<F, D>,
 ) {
-    let filter = lv.op.teq;
+    let is_teq = lv.op.teq;
+    let is_tne = lv.op.tne;
+    let is_cmp = lv.op.trap_cmp;
+    let filter = builder.add_many_extension([is_teq, is_tne, is_cmp]);
+    let one_extension = builder.one_extension();
 
     // Check rt Reg
     {
@@ -249,16 +411,53 @@ pub fn eval_ext_circuit_teq<F: RichField + Extendable<D>, const D: usize>(
         yield_constr.constraint(builder, constr);
     }
 
-    // Check rs_val != rt_val, Otherwise trap will be triggered
+    // Check the instruction encoding
+    // teq: 0b110100, tne: 0b110110, tge/tgeu/tlt/tltu: 0b1100xx
+    {
+        let code =
+            limb_from_bits_le_recursive(builder, lv.opcode_bits.into_iter().chain(lv.func_bits));
+        let code_of = |func: u64| F::from_canonical_u64(func << 6);
+
+        let teq_code = builder.constant_extension(F::Extension::from_canonical_u64(0b110100 << 6));
+        let constr = builder.sub_extension(code, teq_code);
+        let constr = builder.mul_extension(is_teq, constr);
+        yield_constr.constraint(builder, constr);
+
+        let tne_code = builder.constant_extension(F::Extension::from_canonical_u64(0b110110 << 6));
+        let constr = builder.sub_extension(code, tne_code);
+        let constr = builder.mul_extension(is_tne, constr);
+        yield_constr.constraint(builder, constr);
+
+        let cmp_code = builder.constant_extension(F::Extension::from_canonical_u64(0b110000 << 6));
+        let cmp_code = builder.mul_const_add_extension(code_of(1), lv.func_bits[0], cmp_code);
+        let cmp_code = builder.mul_const_add_extension(code_of(2), lv.func_bits[1], cmp_code);
+        let constr = builder.sub_extension(code, cmp_code);
+        let constr = builder.mul_extension(is_cmp, constr);
+        yield_constr.constraint(builder, constr);
+    }
+
+    // Check the trap condition does not hold, otherwise the trap will be triggered
+    // teq: rs_val != rt_val
+    // tne: rs_val == rt_val
+    // tge/tgeu: slt(rs_val, rt_val) == 1
+    // tlt/tltu: slt(rs_val, rt_val) == 0
     {
         let rs_val = lv.mem_channels[0].value;
         let rt_val = lv.mem_channels[1].value;
         let p_inv0 = lv.general.logic().diff_pinv;
-        let one_extension = builder.one_extension();
         let diff = builder.sub_extension(rs_val, rt_val);
         let is_ne = builder.mul_extension(diff, p_inv0);
         let constr = builder.sub_extension(one_extension, is_ne);
-        let constr = builder.mul_extension(constr, filter);
+        let constr = builder.mul_extension(constr, is_teq);
+        yield_constr.constraint(builder, constr);
+
+        let constr = builder.mul_extension(diff, is_tne);
+        yield_constr.constraint(builder, constr);
+
+        let is_lt = lv.mem_channels[2].value;
+        let expected = builder.sub_extension(one_extension, lv.func_bits[1]);
+        let constr = builder.sub_extension(is_lt, expected);
+        let constr = builder.mul_extension(constr, is_cmp);
         yield_constr.constraint(builder, constr);
     }
 }
@@ -580,6 +779,26 @@ pub fn eval_packed_ror<P: PackedField>(
         yield_constr.constraint(filter * (rt_reg - rt_dst));
     }
 
+    // Check rt value bits
+    {
+        let rt_bits = lv.general.misc().rs_bits;
+        for bit in rt_bits {
+            yield_constr.constraint(filter * bit * (bit - P::ONES));
+        }
+        let rt_val = lv.mem_channels[0].value;
+        yield_constr.constraint(filter * (limb_from_bits_le(rt_bits) - rt_val));
+    }
+
+    // Check exactly one shift amount is selected
+    {
+        let is_lsb = lv.general.misc().is_lsb;
+        for is_sa in is_lsb {
+            yield_constr.constraint(filter * is_sa * (is_sa - P::ONES));
+        }
+        let sum: P = is_lsb.into_iter().sum();
+        yield_constr.constraint(filter * (sum - P::ONES));
+    }
+
     // Check ror result
     {
         let rt_bits = lv.general.misc().rs_bits;
@@ -627,6 +846,35 @@ pub fn eval_ext_circuit_ror<F: RichField + Extendable<D>, const D: usize>(
         yield_constr.constraint(builder, constr);
     }
 
+    // Check rt value bits
+    {
+        let rt_bits = lv.general.misc().rs_bits;
+        for bit in rt_bits {
+            let constr = builder.mul_sub_extension(bit, bit, bit);
+            let constr = builder.mul_extension(constr, filter);
+            yield_constr.constraint(builder, constr);
+        }
+        let rt_val = lv.mem_channels[0].value;
+        let rt_from_bits = limb_from_bits_le_recursive(builder, rt_bits);
+        let constr = builder.sub_extension(rt_from_bits, rt_val);
+        let constr = builder.mul_extension(constr, filter);
+        yield_constr.constraint(builder, constr);
+    }
+
+    // Check exactly one shift amount is selected
+    {
+        let is_lsb = lv.general.misc().is_lsb;
+        for is_sa in is_lsb {
+            let constr = builder.mul_sub_extension(is_sa, is_sa, is_sa);
+            let constr = builder.mul_extension(constr, filter);
+            yield_constr.constraint(builder, constr);
+        }
+        let sum = builder.add_many_extension(is_lsb);
+        let constr = builder.add_const_extension(sum, -F::ONE);
+        let constr = builder.mul_extension(constr, filter);
+        yield_constr.constraint(builder, constr);
+    }
+
     // Check ror result
     {
         let rt_bits = lv.general.misc().rs_bits;
@@ -656,11 +904,20 @@ pub fn eval_ext_circuit_ror<F: RichField + Extendable<D>, const D: usize>(
     }
 }
 
-pub fn eval_packed_maddu<P: PackedField>(
+pub fn eval_packed_madd<P: PackedField>(
     lv: &CpuColumnsView<P>,
     yield_constr: &mut ConstraintConsumer<P>,
 ) {
-    let filter = lv.op.maddu;
+    let filter = lv.op.madd;
+
+    // Check the SPECIAL2 encoding: func is one of 0b000000, 0b000001, 0b000100, 0b000101
+    {
+        let opcode = limb_from_bits_le(lv.opcode_bits);
+        yield_constr.constraint(filter * (opcode - P::Scalar::from_canonical_u8(0b011100)));
+        for i in [1, 3, 4, 5] {
+            yield_constr.constraint(filter * lv.func_bits[i]);
+        }
+    }
 
     // Check rs Reg
     // addr(channels[0]) == rs
@@ -700,36 +957,93 @@ pub fn eval_packed_maddu<P: PackedField>(
         yield_constr.constraint(filter * (lo_reg_write - lo_src));
     }
 
-    // Check maddu result
-    // carry = overflow << 32
-    // scale = 1 << 32
-    // carry * (carry - scale) == 0
-    // result +  (overflow << 32) == (hi,lo) + rs * rt
+    // Check madd result, with the accumulator X = (hi,lo) before the operation and Y = (hi,lo)
+    // after it for MADD(U), and the other way around for MSUB(U), so that both check
+    //   X + rs_ext * rt_ext == Y (mod 2^64).
+    // The words of Y written are range checked by their bits, and the product is split in 16-bit
+    // limbs so that each 32-bit word identity below holds over the integers:
+    //   X_lo + a0*b0 + (a0*b1 + a1*b0) << 16 == Y_lo + c_lo << 32
+    //   X_hi + a1*b1 + c_lo - (rs_neg * rt + rt_neg * rs) == Y_hi + (c_hi - 2) << 32
+    // where rs = a1 << 16 + a0, rt = b1 << 16 + b0, c_lo < 2^17 and c_hi - 2 in [-2, 1]. The
+    // signed product is the unsigned one minus (rs_neg * rt + rt_neg * rs) << 32, mod 2^64.
     {
+        let madd = lv.general.madd();
         let rs = lv.mem_channels[0].value;
         let rt = lv.mem_channels[1].value;
         let hi = lv.mem_channels[2].value;
         let lo = lv.mem_channels[3].value;
-        let hi_result: P = lv.mem_channels[4].value;
+        let hi_result = lv.mem_channels[4].value;
         let lo_result = lv.mem_channels[5].value;
-        let carry = lv.general.misc().auxm;
-        let scale = P::Scalar::from_canonical_usize(1 << 32);
-        let result = hi_result * scale + lo_result;
-        let mul = rs * rt;
-        let addend = hi * scale + lo;
-        let overflow = carry * scale;
+        let is_signed = P::ONES - lv.func_bits[0];
+        let is_sub = lv.func_bits[2];
+        let limb = P::Scalar::from_canonical_u64(1 << 16);
+        let word = P::Scalar::from_canonical_u64(1 << 32);
+
+        for bit in madd
+            .rs_bits
+            .into_iter()
+            .chain(madd.rt_bits)
+            .chain(madd.hi_bits)
+            .chain(madd.lo_bits)
+            .chain(madd.carry_lo_bits)
+            .chain(madd.carry_hi_bits)
+        {
+            yield_constr.constraint(filter * bit * (P::ONES - bit));
+        }
+        for (bits, val) in [
+            (madd.rs_bits, rs),
+            (madd.rt_bits, rt),
+            (madd.hi_bits, hi_result),
+            (madd.lo_bits, lo_result),
+        ] {
+            yield_constr.constraint(filter * (limb_from_bits_le(bits) - val));
+        }
 
-        yield_constr.constraint(filter * carry * (carry - scale));
-        yield_constr.constraint(filter * (mul + addend - overflow - result));
+        yield_constr.constraint(filter * (madd.rs_neg - is_signed * madd.rs_bits[31]));
+        yield_constr.constraint(filter * (madd.rt_neg - is_signed * madd.rt_bits[31]));
+
+        let a0 = limb_from_bits_le(madd.rs_bits[..16].iter().copied());
+        let a1 = limb_from_bits_le(madd.rs_bits[16..].iter().copied());
+        let b0 = limb_from_bits_le(madd.rt_bits[..16].iter().copied());
+        let b1 = limb_from_bits_le(madd.rt_bits[16..].iter().copied());
+        let carry_lo = limb_from_bits_le(madd.carry_lo_bits);
+        let carry_hi = limb_from_bits_le(madd.carry_hi_bits) - P::Scalar::TWO;
+
+        let x_lo = lo + is_sub * (lo_result - lo);
+        let y_lo = lo_result - is_sub * (lo_result - lo);
+        let x_hi = hi + is_sub * (hi_result - hi);
+        let y_hi = hi_result - is_sub * (hi_result - hi);
+
+        yield_constr.constraint(
+            filter * (x_lo + a0 * b0 + (a0 * b1 + a1 * b0) * limb - y_lo - carry_lo * word),
+        );
+        let sign_correction = madd.rs_neg * rt + madd.rt_neg * rs;
+        yield_constr.constraint(
+            filter * (x_hi + a1 * b1 + carry_lo - sign_correction - y_hi - carry_hi * word),
+        );
     }
 }
 
-pub fn eval_ext_circuit_maddu<F: RichField + Extendable<D>, const D: usize>(
+pub fn eval_ext_circuit_madd<F: RichField + Extendable<D>, const D: usize>(
     builder: &mut plonky2::plonk::circuit_builder::CircuitBuilder<F, D>,
     lv: &CpuColumnsView<ExtensionTarget<D>>,
     yield_constr: &mut RecursiveConstraintConsumer<F, D>,
 ) {
-    let filter = lv.op.maddu;
+    let filter = lv.op.madd;
+    let one = builder.one_extension();
+
+    // Check the SPECIAL2 encoding: func is one of 0b000000, 0b000001, 0b000100, 0b000101
+    {
+        let opcode = limb_from_bits_le_recursive(builder, lv.opcode_bits);
+        let special2 = builder.constant_extension(F::Extension::from_canonical_u8(0b011100));
+        let constr = builder.sub_extension(opcode, special2);
+        let constr = builder.mul_extension(constr, filter);
+        yield_constr.constraint(builder, constr);
+        for i in [1, 3, 4, 5] {
+            let constr = builder.mul_extension(filter, lv.func_bits[i]);
+            yield_constr.constraint(builder, constr);
+        }
+    }
 
     // Check rs Reg
     // addr(channels[0]) == rs
@@ -783,39 +1097,299 @@ pub fn eval_ext_circuit_maddu<F: RichField + Extendable<D>, const D: usize>(
         yield_constr.constraint(builder, constr);
     }
 
-    // Check maddu result
-    // carry = overflow << 32
-    // scale = 1 << 32
-    // carry * (carry - scale) == 0
-    // result +  (overflow << 32) == (hi,lo) + rs * rt
+    // Check madd result, as in `eval_packed_madd`.
     {
+        let madd = lv.general.madd();
         let rs = lv.mem_channels[0].value;
         let rt = lv.mem_channels[1].value;
         let hi = lv.mem_channels[2].value;
         let lo = lv.mem_channels[3].value;
         let hi_result = lv.mem_channels[4].value;
         let lo_result = lv.mem_channels[5].value;
-        let carry = lv.general.misc().auxm;
-        let scale = builder.constant_extension(F::Extension::from_canonical_usize(1 << 32));
-        let result = builder.mul_extension(hi_result, scale);
-        let result = builder.add_extension(result, lo_result);
-        let mul = builder.mul_extension(rs, rt);
-        let addend = builder.mul_extension(hi, scale);
-        let addend = builder.add_extension(addend, lo);
-
-        let overflow = builder.mul_extension(carry, scale);
-
-        let constr = builder.sub_extension(carry, scale);
-        let constr = builder.mul_extension(constr, carry);
+        let is_signed = builder.sub_extension(one, lv.func_bits[0]);
+        let is_sub = lv.func_bits[2];
+        let limb = F::from_canonical_u64(1 << 16);
+        let word = F::from_canonical_u64(1 << 32);
+
+        for bit in madd
+            .rs_bits
+            .into_iter()
+            .chain(madd.rt_bits)
+            .chain(madd.hi_bits)
+            .chain(madd.lo_bits)
+            .chain(madd.carry_lo_bits)
+            .chain(madd.carry_hi_bits)
+        {
+            let constr = builder.sub_extension(one, bit);
+            let constr = builder.mul_extension(bit, constr);
+            let constr = builder.mul_extension(filter, constr);
+            yield_constr.constraint(builder, constr);
+        }
+        for (bits, val) in [
+            (madd.rs_bits, rs),
+            (madd.rt_bits, rt),
+            (madd.hi_bits, hi_result),
+            (madd.lo_bits, lo_result),
+        ] {
+            let limb = limb_from_bits_le_recursive(builder, bits);
+            let constr = builder.sub_extension(limb, val);
+            let constr = builder.mul_extension(filter, constr);
+            yield_constr.constraint(builder, constr);
+        }
+
+        for (neg, sign) in [
+            (madd.rs_neg, madd.rs_bits[31]),
+            (madd.rt_neg, madd.rt_bits[31]),
+        ] {
+            let expected = builder.mul_extension(is_signed, sign);
+            let constr = builder.sub_extension(neg, expected);
+            let constr = builder.mul_extension(filter, constr);
+            yield_constr.constraint(builder, constr);
+        }
+
+        let a0 = limb_from_bits_le_recursive(builder, madd.rs_bits[..16].iter().copied());
+        let a1 = limb_from_bits_le_recursive(builder, madd.rs_bits[16..].iter().copied());
+        let b0 = limb_from_bits_le_recursive(builder, madd.rt_bits[..16].iter().copied());
+        let b1 = limb_from_bits_le_recursive(builder, madd.rt_bits[16..].iter().copied());
+        let carry_lo = limb_from_bits_le_recursive(builder, madd.carry_lo_bits);
+        let carry_hi = limb_from_bits_le_recursive(builder, madd.carry_hi_bits);
+        let carry_hi = builder.add_const_extension(carry_hi, -F::TWO);
+
+        let diff_lo = builder.sub_extension(lo_result, lo);
+        let x_lo = builder.mul_add_extension(is_sub, diff_lo, lo);
+        let y_lo = builder.arithmetic_extension(F::NEG_ONE, F::ONE, is_sub, diff_lo, lo_result);
+        let diff_hi = builder.sub_extension(hi_result, hi);
+        let x_hi = builder.mul_add_extension(is_sub, diff_hi, hi);
+        let y_hi = builder.arithmetic_extension(F::NEG_ONE, F::ONE, is_sub, diff_hi, hi_result);
+
+        let cross = builder.mul_extension(a0, b1);
+        let cross = builder.mul_add_extension(a1, b0, cross);
+        let constr = builder.mul_add_extension(a0, b0, x_lo);
+        let constr = builder.mul_const_add_extension(limb, cross, constr);
+        let constr = builder.sub_extension(constr, y_lo);
+        let constr = builder.mul_const_add_extension(-word, carry_lo, constr);
+        let constr = builder.mul_extension(filter, constr);
+        yield_constr.constraint(builder, constr);
+
+        let sign_correction = builder.mul_extension(madd.rs_neg, rt);
+        let sign_correction = builder.mul_add_extension(madd.rt_neg, rs, sign_correction);
+        let constr = builder.mul_add_extension(a1, b1, x_hi);
+        let constr = builder.add_extension(constr, carry_lo);
+        let constr = builder.sub_extension(constr, sign_correction);
+        let constr = builder.sub_extension(constr, y_hi);
+        let constr = builder.mul_const_add_extension(-word, carry_hi, constr);
+        let constr = builder.mul_extension(filter, constr);
+        yield_constr.constraint(builder, constr);
+    }
+}
+
+pub fn eval_packed_logic_imm<P: PackedField>(
+    lv: &CpuColumnsView<P>,
+    yield_constr: &mut ConstraintConsumer<P>,
+) {
+    let filter = lv.op.logic_imm_op;
+
+    // Check rs Reg
+    {
+        let rs_reg = lv.mem_channels[0].addr_virtual;
+        let rs_src = limb_from_bits_le(lv.rs_bits);
+        yield_constr.constraint(filter * (rs_reg - rs_src));
+    }
+
+    // Check the zero-extended immediate
+    {
+        let imm_val = lv.mem_channels[1].value;
+        let mut imm_bits = [P::ZEROS; 16];
+        imm_bits[0..6].copy_from_slice(&lv.func_bits); // 6 bits
+        imm_bits[6..11].copy_from_slice(&lv.shamt_bits); // 5 bits
+        imm_bits[11..16].copy_from_slice(&lv.rd_bits); // 5 bits
+        let imm = limb_from_bits_le(imm_bits);
+        yield_constr.constraint(filter * (imm_val - imm));
+    }
+
+    // Check rt Reg
+    {
+        let rt_reg = lv.mem_channels[2].addr_virtual;
+        let rt_dst = limb_from_bits_le(lv.rt_bits);
+        yield_constr.constraint(filter * (rt_reg - rt_dst));
+    }
+}
+
+pub fn eval_ext_circuit_logic_imm<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut plonky2::plonk::circuit_builder::CircuitBuilder<F, D>,
+    lv: &CpuColumnsView<ExtensionTarget<D>>,
+    yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+) {
+    let filter = lv.op.logic_imm_op;
+
+    // Check rs Reg
+    {
+        let rs_reg = lv.mem_channels[0].addr_virtual;
+        let rs_src = limb_from_bits_le_recursive(builder, lv.rs_bits);
+        let constr = builder.sub_extension(rs_reg, rs_src);
+        let constr = builder.mul_extension(constr, filter);
+        yield_constr.constraint(builder, constr);
+    }
+
+    // Check the zero-extended immediate
+    {
+        let imm_val = lv.mem_channels[1].value;
+        let mut imm_bits = [builder.zero_extension(); 16];
+        imm_bits[0..6].copy_from_slice(&lv.func_bits); // 6 bits
+        imm_bits[6..11].copy_from_slice(&lv.shamt_bits); // 5 bits
+        imm_bits[11..16].copy_from_slice(&lv.rd_bits); // 5 bits
+        let imm = limb_from_bits_le_recursive(builder, imm_bits);
+        let constr = builder.sub_extension(imm_val, imm);
+        let constr = builder.mul_extension(constr, filter);
+        yield_constr.constraint(builder, constr);
+    }
+
+    // Check rt Reg
+    {
+        let rt_reg = lv.mem_channels[2].addr_virtual;
+        let rt_dst = limb_from_bits_le_recursive(builder, lv.rt_bits);
+        let constr = builder.sub_extension(rt_reg, rt_dst);
+        let constr = builder.mul_extension(constr, filter);
+        yield_constr.constraint(builder, constr);
+    }
+}
+
+pub fn eval_packed_rorv<P: PackedField>(
+    lv: &CpuColumnsView<P>,
+    yield_constr: &mut ConstraintConsumer<P>,
+) {
+    let filter = lv.op.rorv;
+
+    // Check rt, rs and rd Regs
+    {
+        let rt_reg = lv.mem_channels[0].addr_virtual;
+        yield_constr.constraint(filter * (rt_reg - limb_from_bits_le(lv.rt_bits)));
+        let rs_reg = lv.mem_channels[1].addr_virtual;
+        yield_constr.constraint(filter * (rs_reg - limb_from_bits_le(lv.rs_bits)));
+        let rd_reg = lv.mem_channels[2].addr_virtual;
+        yield_constr.constraint(filter * (rd_reg - limb_from_bits_le(lv.rd_bits)));
+    }
+
+    // Check rt and rs value bits
+    {
+        let rt_bits = lv.general.misc().rs_bits;
+        let rs_bits = lv.general.misc().is_msb;
+        for bit in rt_bits.into_iter().chain(rs_bits) {
+            yield_constr.constraint(filter * bit * (bit - P::ONES));
+        }
+        let rt_val = lv.mem_channels[0].value;
+        yield_constr.constraint(filter * (limb_from_bits_le(rt_bits) - rt_val));
+        let rs_val = lv.mem_channels[1].value;
+        yield_constr.constraint(filter * (limb_from_bits_le(rs_bits) - rs_val));
+    }
+
+    // Check exactly one shift amount is selected
+    {
+        let is_lsb = lv.general.misc().is_lsb;
+        for is_sa in is_lsb {
+            yield_constr.constraint(filter * is_sa * (is_sa - P::ONES));
+        }
+        let sum: P = is_lsb.into_iter().sum();
+        yield_constr.constraint(filter * (sum - P::ONES));
+    }
+
+    // Check rorv result, rotated by rs[4:0]
+    {
+        let rt_bits = lv.general.misc().rs_bits;
+        let sa = limb_from_bits_le(lv.general.misc().is_msb[0..5].to_vec());
+
+        let rd_result = lv.mem_channels[2].value;
+
+        let mut rd_bits = [P::ZEROS; 32];
+        for i in 0..32 {
+            rd_bits[0..32 - i].copy_from_slice(&rt_bits[i..32]);
+            rd_bits[32 - i..32].copy_from_slice(&rt_bits[0..i]);
+
+            let rd_val = limb_from_bits_le(rd_bits.to_vec());
+
+            let is_sa = lv.general.misc().is_lsb[i];
+            let cur_index = P::Scalar::from_canonical_usize(i);
+            yield_constr.constraint(filter * is_sa * (sa - cur_index));
+            yield_constr.constraint(filter * is_sa * (rd_result - rd_val));
+        }
+    }
+}
+
+pub fn eval_ext_circuit_rorv<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut plonky2::plonk::circuit_builder::CircuitBuilder<F, D>,
+    lv: &CpuColumnsView<ExtensionTarget<D>>,
+    yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+) {
+    let filter = lv.op.rorv;
+
+    // Check rt, rs and rd Regs
+    for (channel, reg_bits) in [(0, lv.rt_bits), (1, lv.rs_bits), (2, lv.rd_bits)] {
+        let reg = lv.mem_channels[channel].addr_virtual;
+        let reg_src = limb_from_bits_le_recursive(builder, reg_bits);
+        let constr = builder.sub_extension(reg, reg_src);
         let constr = builder.mul_extension(constr, filter);
         yield_constr.constraint(builder, constr);
+    }
+
+    // Check rt and rs value bits
+    {
+        let rt_bits = lv.general.misc().rs_bits;
+        let rs_bits = lv.general.misc().is_msb;
+        for bit in rt_bits.into_iter().chain(rs_bits) {
+            let constr = builder.mul_sub_extension(bit, bit, bit);
+            let constr = builder.mul_extension(constr, filter);
+            yield_constr.constraint(builder, constr);
+        }
+        for (channel, bits) in [(0, rt_bits), (1, rs_bits)] {
+            let val = lv.mem_channels[channel].value;
+            let val_from_bits = limb_from_bits_le_recursive(builder, bits);
+            let constr = builder.sub_extension(val_from_bits, val);
+            let constr = builder.mul_extension(constr, filter);
+            yield_constr.constraint(builder, constr);
+        }
+    }
 
-        let constr = builder.add_extension(mul, addend);
-        let constr = builder.sub_extension(constr, overflow);
-        let constr = builder.sub_extension(constr, result);
+    // Check exactly one shift amount is selected
+    {
+        let is_lsb = lv.general.misc().is_lsb;
+        for is_sa in is_lsb {
+            let constr = builder.mul_sub_extension(is_sa, is_sa, is_sa);
+            let constr = builder.mul_extension(constr, filter);
+            yield_constr.constraint(builder, constr);
+        }
+        let sum = builder.add_many_extension(is_lsb);
+        let constr = builder.add_const_extension(sum, -F::ONE);
         let constr = builder.mul_extension(constr, filter);
         yield_constr.constraint(builder, constr);
     }
+
+    // Check rorv result, rotated by rs[4:0]
+    {
+        let rt_bits = lv.general.misc().rs_bits;
+        let sa = limb_from_bits_le_recursive(builder, lv.general.misc().is_msb[0..5].to_vec());
+        let rd_result = lv.mem_channels[2].value;
+
+        let mut rd_bits = [builder.zero_extension(); 32];
+        for i in 0..32 {
+            rd_bits[0..32 - i].copy_from_slice(&rt_bits[i..32]);
+            rd_bits[32 - i..32].copy_from_slice(&rt_bits[0..i]);
+
+            let rd_val = limb_from_bits_le_recursive(builder, rd_bits);
+
+            let is_sa = lv.general.misc().is_lsb[i];
+            let cur_index = builder.constant_extension(F::Extension::from_canonical_usize(i));
+
+            let constr_sa = builder.mul_extension(filter, is_sa);
+
+            let constr = builder.sub_extension(sa, cur_index);
+            let constr = builder.mul_extension(constr, constr_sa);
+            yield_constr.constraint(builder, constr);
+
+            let constr = builder.sub_extension(rd_result, rd_val);
+            let constr = builder.mul_extension(constr, constr_sa);
+            yield_constr.constraint(builder, constr);
+        }
+    }
 }
 
 pub fn eval_packed<P: PackedField>(
@@ -824,11 +1398,13 @@ pub fn eval_packed<P: PackedField>(
 ) {
     eval_packed_rdhwr(lv, yield_constr);
     eval_packed_condmov(lv, yield_constr);
-    eval_packed_teq(lv, yield_constr);
+    eval_packed_trap(lv, yield_constr);
     eval_packed_extract(lv, yield_constr);
     eval_packed_ror(lv, yield_constr);
+    eval_packed_rorv(lv, yield_constr);
     eval_packed_insert(lv, yield_constr);
-    eval_packed_maddu(lv, yield_constr);
+    eval_packed_madd(lv, yield_constr);
+    eval_packed_logic_imm(lv, yield_constr);
 }
 
 pub fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
@@ -838,9 +1414,73 @@ pub fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
 ) {
     eval_ext_circuit_rdhwr(builder, lv, yield_constr);
     eval_ext_circuit_condmov(builder, lv, yield_constr);
-    eval_ext_circuit_teq(builder, lv, yield_constr);
+    eval_ext_circuit_trap(builder, lv, yield_constr);
     eval_ext_circuit_extract(builder, lv, yield_constr);
     eval_ext_circuit_ror(builder, lv, yield_constr);
+    eval_ext_circuit_rorv(builder, lv, yield_constr);
     eval_ext_circuit_insert(builder, lv, yield_constr);
-    eval_ext_circuit_maddu(builder, lv, yield_constr);
+    eval_ext_circuit_madd(builder, lv, yield_constr);
+    eval_ext_circuit_logic_imm(builder, lv, yield_constr);
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2::field::goldilocks_field::GoldilocksField;
+
+    use super::*;
+
+    type F = GoldilocksField;
+
+    /// The row of `rdhwr $3, rd` writing `value`, which reads ULR as `value` if `read_ulr`.
+    fn row(rd: u8, value: u32, read_ulr: bool) -> CpuColumnsView<F> {
+        let mut lv = CpuColumnsView::default();
+        lv.op.rdhwr = F::ONE;
+        for (i, bit) in lv.rd_bits.iter_mut().enumerate() {
+            *bit = F::from_bool((rd >> i) & 1 == 1);
+        }
+        lv.rt_bits[..2].fill(F::ONE);
+        fill_rdhwr_selectors(&mut lv, rd);
+
+        let rt = &mut lv.mem_channels[0];
+        rt.used = F::ONE;
+        rt.addr_segment = F::from_canonical_usize(Segment::RegisterFile as usize);
+        rt.addr_virtual = F::from_canonical_u8(3);
+        rt.value = F::from_canonical_u32(value);
+        if read_ulr {
+            let ulr = &mut lv.mem_channels[1];
+            ulr.used = F::ONE;
+            ulr.is_read = F::ONE;
+            ulr.addr_segment = F::from_canonical_usize(Segment::RegisterFile as usize);
+            ulr.addr_virtual = F::from_canonical_usize(LOCAL_USER_REG);
+            ulr.value = F::from_canonical_u32(value);
+        }
+        lv
+    }
+
+    fn is_valid(lv: &CpuColumnsView<F>) -> bool {
+        let mut consumer = ConstraintConsumer::new(vec![F::TWO], F::ONE, F::ONE, F::ONE);
+        eval_packed_rdhwr(lv, &mut consumer);
+        consumer.accumulators() == [F::ZERO]
+    }
+
+    #[test]
+    fn test_rdhwr() {
+        assert!(is_valid(&row(0, 1, false)));
+        assert!(is_valid(&row(3, 1, false)));
+        assert!(is_valid(&row(29, 0x1234, true)));
+        assert!(is_valid(&row(2, 0, false)));
+
+        assert!(!is_valid(&row(3, 0, false)));
+        assert!(!is_valid(&row(2, 1, false)));
+        // ULR is read from the register file.
+        assert!(!is_valid(&row(29, 0x1234, false)));
+        let mut lv = row(29, 0x1234, true);
+        lv.mem_channels[1].addr_virtual = F::from_canonical_u8(37);
+        assert!(!is_valid(&lv));
+
+        // The selectors of rd cannot be cleared to read ULR as 0.
+        let mut lv = row(29, 0, false);
+        lv.general.misc_mut().rd_eq = [F::ZERO; NUM_HW_REGISTERS];
+        assert!(!is_valid(&lv));
+    }
 }
@@ -1,14 +1,26 @@
 pub(crate) mod bits;
+pub(crate) mod blake;
+pub(crate) mod bls12_381;
+pub(crate) mod bn254;
 pub(crate) mod bootstrap_kernel;
+pub(crate) mod chacha;
 pub mod columns;
+pub(crate) mod cop0;
 pub(crate) mod count;
 pub mod cpu_stark;
 pub(crate) mod decode;
+pub(crate) mod ed25519;
 pub(crate) mod exit_kernel;
 pub(crate) mod jumps;
+pub(crate) mod keccak;
 pub mod kernel;
 pub(crate) mod membus;
 pub(crate) mod memio;
 pub(crate) mod misc;
+pub(crate) mod modexp;
+pub(crate) mod secp256k1;
+pub(crate) mod secp256r1;
+pub(crate) mod sha256;
 pub(crate) mod shift;
 pub(crate) mod syscall;
+pub(crate) mod uint256;
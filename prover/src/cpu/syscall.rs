@@ -1,5 +1,6 @@
 use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
-use crate::cpu::columns::CpuColumnsView;
+use crate::cpu::columns::{CpuColumnsView, COL_MAP};
+use crate::util::{limb_from_bits_le, limb_from_bits_le_recursive};
 
 use crate::witness::operation::*;
 
@@ -9,6 +10,192 @@ use plonky2::field::types::Field;
 use plonky2::hash::hash_types::RichField;
 use plonky2::iop::ext_target::ExtensionTarget;
 
+pub(crate) const NUM_PRECOMPILE_CALLS: usize = 19;
+
+/// The syscalls proven by a precompile table, and the filter of the row looking up the table,
+/// which follows the syscall row.
+pub(crate) const PRECOMPILE_CALLS: [(usize, usize); NUM_PRECOMPILE_CALLS] = [
+    (SYSKECCAK256, COL_MAP.is_keccak_sponge),
+    (SYSSHA256EXTEND, COL_MAP.is_sha_extend),
+    (SYSSHA256COMPRESS, COL_MAP.is_sha_compress),
+    (SYSSECP256K1ADD, COL_MAP.is_secp256k1_add),
+    (SYSSECP256K1DOUBLE, COL_MAP.is_secp256k1_double),
+    (SYSEDADD, COL_MAP.is_ed25519_add),
+    (SYSEDDECOMPRESS, COL_MAP.is_ed25519_decompress),
+    (SYSBN254ADD, COL_MAP.is_bn254_add),
+    (SYSBN254DOUBLE, COL_MAP.is_bn254_double),
+    (SYSBLS12381FPADD, COL_MAP.is_bls12381_fp_add),
+    (SYSBLS12381FPSUB, COL_MAP.is_bls12381_fp_sub),
+    (SYSBLS12381FPMUL, COL_MAP.is_bls12381_fp_mul),
+    (SYSUINT256MULMOD, COL_MAP.is_uint256_mulmod),
+    (SYSBLAKE2BCOMPRESS, COL_MAP.is_blake2b_compress),
+    (SYSBLAKE3COMPRESS, COL_MAP.is_blake3_compress),
+    (SYSCHACHA20BLOCK, COL_MAP.is_chacha_block),
+    (SYSSECP256R1ADD, COL_MAP.is_secp256r1_add),
+    (SYSSECP256R1DOUBLE, COL_MAP.is_secp256r1_double),
+    (SYSMODEXP, COL_MAP.is_modexp),
+];
+
+/// The row after a syscall looks up the table of a precompile iff the syscall number, read on
+/// channel 0, is that of the precompile. The `precompile` flag of a call is set iff the number
+/// is that of the call, the inverse proving the difference nonzero otherwise.
+///
+/// A call returns 0 in v0 and a3, written on channels 4 and 5. A call with invalid inputs, which
+/// its table cannot prove, traps instead of making the syscall.
+pub fn eval_packed_precompile_calls<P: PackedField>(
+    lv: &CpuColumnsView<P>,
+    nv: &CpuColumnsView<P>,
+    yield_constr: &mut ConstraintConsumer<P>,
+) {
+    let filter = lv.op.syscall;
+    let sys_num = lv.mem_channels[0].value;
+    let syscall = lv.general.syscall();
+
+    let mut is_call = P::ZEROS;
+    for (i, (num, col)) in PRECOMPILE_CALLS.into_iter().enumerate() {
+        let call = syscall.precompile[i];
+        let diff = sys_num - P::Scalar::from_canonical_usize(num);
+        yield_constr.constraint(filter * call * (call - P::ONES));
+        yield_constr.constraint(filter * call * diff);
+        yield_constr.constraint(filter * (diff * syscall.precompile_inv[i] + call - P::ONES));
+        yield_constr.constraint_transition(nv[col] - filter * call);
+        is_call += call;
+    }
+
+    let result_v0 = lv.mem_channels[4].value;
+    let result_v1 = lv.mem_channels[5].value;
+    yield_constr.constraint(filter * is_call * result_v0);
+    yield_constr.constraint(filter * is_call * result_v1);
+}
+
+pub fn eval_ext_circuit_precompile_calls<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut plonky2::plonk::circuit_builder::CircuitBuilder<F, D>,
+    lv: &CpuColumnsView<ExtensionTarget<D>>,
+    nv: &CpuColumnsView<ExtensionTarget<D>>,
+    yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+) {
+    let filter = lv.op.syscall;
+    let sys_num = lv.mem_channels[0].value;
+    let syscall = lv.general.syscall();
+
+    let mut is_call = builder.zero_extension();
+    for (i, (num, col)) in PRECOMPILE_CALLS.into_iter().enumerate() {
+        let call = syscall.precompile[i];
+        let diff = builder.add_const_extension(sys_num, -F::from_canonical_usize(num));
+        let filtered_call = builder.mul_extension(filter, call);
+
+        let constr = builder.mul_sub_extension(filtered_call, call, filtered_call);
+        yield_constr.constraint(builder, constr);
+        let constr = builder.mul_extension(filtered_call, diff);
+        yield_constr.constraint(builder, constr);
+        let constr = builder.mul_add_extension(diff, syscall.precompile_inv[i], call);
+        let constr = builder.mul_sub_extension(filter, constr, filter);
+        yield_constr.constraint(builder, constr);
+        let constr = builder.sub_extension(nv[col], filtered_call);
+        yield_constr.constraint_transition(builder, constr);
+
+        is_call = builder.add_extension(is_call, call);
+    }
+
+    let result_v0 = lv.mem_channels[4].value;
+    let result_v1 = lv.mem_channels[5].value;
+    let filtered_call = builder.mul_extension(filter, is_call);
+    for result in [result_v0, result_v1] {
+        let constr = builder.mul_extension(filtered_call, result);
+        yield_constr.constraint(builder, constr);
+    }
+}
+
+/// The syscalls which exit the run.
+pub(crate) const EXIT_CALLS: [usize; 2] = [SYSEXIT, SYSEXITGROUP];
+
+/// The exit code is 0 on the first row, and only changes after an exit syscall, to the low byte
+/// of its a0, read on channel 1, or after a trap row, to the code of its exception.
+pub fn eval_packed_exit<P: PackedField>(
+    lv: &CpuColumnsView<P>,
+    nv: &CpuColumnsView<P>,
+    yield_constr: &mut ConstraintConsumer<P>,
+) {
+    let filter = lv.op.syscall;
+    let sys_num = lv.mem_channels[0].value;
+    let a0 = lv.mem_channels[1].value;
+    let syscall = lv.general.syscall();
+
+    let mut is_exit = P::ZEROS;
+    for (i, num) in EXIT_CALLS.into_iter().enumerate() {
+        let call = syscall.exit[i];
+        let diff = sys_num - P::Scalar::from_canonical_usize(num);
+        yield_constr.constraint(filter * call * (call - P::ONES));
+        yield_constr.constraint(filter * call * diff);
+        yield_constr.constraint(filter * (diff * syscall.exit_inv[i] + call - P::ONES));
+        is_exit += call;
+    }
+
+    let bits = syscall.exit_code_bits;
+    for bit in bits {
+        yield_constr.constraint(filter * bit * (bit - P::ONES));
+    }
+    yield_constr.constraint(filter * is_exit * (a0 - limb_from_bits_le(bits)));
+    let code = limb_from_bits_le(bits[..8].iter().copied());
+
+    yield_constr.constraint_first_row(lv.exit_code);
+    yield_constr.constraint_transition(
+        (P::ONES - lv.is_trap) * (nv.exit_code - lv.exit_code)
+            - filter * is_exit * (code - lv.exit_code),
+    );
+}
+
+pub fn eval_ext_circuit_exit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut plonky2::plonk::circuit_builder::CircuitBuilder<F, D>,
+    lv: &CpuColumnsView<ExtensionTarget<D>>,
+    nv: &CpuColumnsView<ExtensionTarget<D>>,
+    yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+) {
+    let one = builder.one_extension();
+    let filter = lv.op.syscall;
+    let sys_num = lv.mem_channels[0].value;
+    let a0 = lv.mem_channels[1].value;
+    let syscall = lv.general.syscall();
+
+    let mut is_exit = builder.zero_extension();
+    for (i, num) in EXIT_CALLS.into_iter().enumerate() {
+        let call = syscall.exit[i];
+        let diff = builder.add_const_extension(sys_num, -F::from_canonical_usize(num));
+        let filtered_call = builder.mul_extension(filter, call);
+
+        let constr = builder.mul_sub_extension(filtered_call, call, filtered_call);
+        yield_constr.constraint(builder, constr);
+        let constr = builder.mul_extension(filtered_call, diff);
+        yield_constr.constraint(builder, constr);
+        let constr = builder.mul_add_extension(diff, syscall.exit_inv[i], call);
+        let constr = builder.mul_sub_extension(filter, constr, filter);
+        yield_constr.constraint(builder, constr);
+
+        is_exit = builder.add_extension(is_exit, call);
+    }
+    let filtered_exit = builder.mul_extension(filter, is_exit);
+
+    let bits = syscall.exit_code_bits;
+    for bit in bits {
+        let constr = builder.mul_sub_extension(bit, bit, bit);
+        let constr = builder.mul_extension(filter, constr);
+        yield_constr.constraint(builder, constr);
+    }
+    let value = limb_from_bits_le_recursive(builder, bits);
+    let constr = builder.sub_extension(a0, value);
+    let constr = builder.mul_extension(filtered_exit, constr);
+    yield_constr.constraint(builder, constr);
+    let code = limb_from_bits_le_recursive(builder, bits[..8].iter().copied());
+
+    yield_constr.constraint_first_row(builder, lv.exit_code);
+    let not_trap = builder.sub_extension(one, lv.is_trap);
+    let delta = builder.sub_extension(nv.exit_code, lv.exit_code);
+    let new_code = builder.sub_extension(code, lv.exit_code);
+    let new_code = builder.mul_extension(filtered_exit, new_code);
+    let constr = builder.mul_sub_extension(not_trap, delta, new_code);
+    yield_constr.constraint_transition(builder, constr);
+}
+
 pub fn eval_packed<P: PackedField>(
     lv: &CpuColumnsView<P>,
     yield_constr: &mut ConstraintConsumer<P>,
@@ -18,7 +205,6 @@ pub fn eval_packed<P: PackedField>(
     let a0 = lv.mem_channels[1].value;
     let a1 = lv.mem_channels[2].value;
     let a2 = lv.mem_channels[3].value;
-    let v0 = P::ZEROS;
     let v1 = P::ZEROS;
     let syscall = lv.general.syscall();
     let result_v0 = lv.mem_channels[4].value;
@@ -98,6 +284,7 @@ pub fn eval_packed<P: PackedField>(
     let is_sysbrk_gt = syscall.cond[10];
     let is_sysbrk_le = syscall.cond[11];
     let initial_brk = lv.mem_channels[6].value;
+    let result_brk = lv.mem_channels[7].value;
     //check:
     //1 is_syscall
     //2 sysnum==sysbrk
@@ -106,6 +293,8 @@ pub fn eval_packed<P: PackedField>(
     yield_constr.constraint(filter * is_sysbrk_gt * (a0 - result_v0));
     yield_constr.constraint(filter * is_sysbrk_le * (initial_brk - result_v0));
     yield_constr.constraint(filter * is_sysbrk * (v1 - result_v1));
+    //4 the program break is updated to the returned value
+    yield_constr.constraint(filter * is_sysbrk * (result_brk - result_v0));
 
     //sysclone
     let is_sysclone = syscall.sysnum[3];
@@ -138,10 +327,9 @@ pub fn eval_packed<P: PackedField>(
     //check:
     //1 is_syscall
     //2 sysnum==sysread
-    //3 v0&v1 are right
-    //4 a0 == fd_stdin
+    //3 v1 is right
+    //4 a0 is readable: v0 is the number of bytes read, given by the host
     yield_constr.constraint(filter * (is_sysread_a0_stdin - is_sysread * a0_is_fd_stdin));
-    yield_constr.constraint(filter * is_sysread_a0_stdin * (v0 - result_v0));
     yield_constr.constraint(filter * is_sysread_a0_stdin * (v1 - result_v1));
 
     //syswrite
@@ -189,8 +377,6 @@ pub fn eval_packed<P: PackedField>(
     let a0_is_else = syscall.a0[2];
     let v0_in_a0_is_not_fd_stdout_and_fd_stderr_and_fd_stdin =
         P::Scalar::from_canonical_usize(0xFFFFFFFF);
-    let v1_in_a0_is_not_fd_stdin_and_fd_stderr_and_fd_stdin =
-        P::Scalar::from_canonical_usize(MIPSEBADF);
 
     yield_constr.constraint(filter * (is_sysfcntl_a0_stdin - is_sysfcntl * a0_is_fd_stdin));
 
@@ -217,12 +403,7 @@ pub fn eval_packed<P: PackedField>(
             * (is_sysfcntl - is_sysfcntl_a0_stdin - is_sysfcntl_a0_stdout_or_err)
             * (v0_in_a0_is_not_fd_stdout_and_fd_stderr_and_fd_stdin - result_v0),
     );
-
-    yield_constr.constraint(
-        filter
-            * (is_sysfcntl - is_sysfcntl_a0_stdin - is_sysfcntl_a0_stdout_or_err)
-            * (v1_in_a0_is_not_fd_stdin_and_fd_stderr_and_fd_stdin - result_v1),
-    );
+    // The error code of a failing fcntl, EBADF or EINVAL, is given by the host.
 
     //syssetthreadarea
     let is_syssetthreadarea = syscall.sysnum[8];
@@ -240,7 +421,6 @@ pub fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
     let a0 = lv.mem_channels[1].value;
     let a1 = lv.mem_channels[2].value;
     let a2 = lv.mem_channels[3].value;
-    let v0 = builder.zero_extension();
     let v1 = builder.zero_extension();
     let syscall = lv.general.syscall();
     let result_v0 = lv.mem_channels[4].value;
@@ -318,6 +498,7 @@ pub fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
     let is_sysbrk_gt = syscall.cond[10];
     let is_sysbrk_le = syscall.cond[11];
     let initial_brk = lv.mem_channels[6].value;
+    let result_brk = lv.mem_channels[7].value;
 
     let constr_1 = builder.mul_extension(filter, is_sysbrk);
     let constr_2 = builder.add_extension(is_sysbrk_gt, is_sysbrk_le);
@@ -340,6 +521,10 @@ pub fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
     let constr = builder.mul_extension(constr_1, constr_2);
     yield_constr.constraint(builder, constr);
 
+    let constr_2 = builder.sub_extension(result_brk, result_v0);
+    let constr = builder.mul_extension(constr_1, constr_2);
+    yield_constr.constraint(builder, constr);
+
     //sysclone
     let is_sysclone = syscall.sysnum[3];
     let v0_in_sysclone = builder.one_extension();
@@ -383,10 +568,6 @@ pub fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
     yield_constr.constraint(builder, constr);
 
     let constr_1 = builder.mul_extension(filter, is_sysread_a0_stdin);
-    let constr_2 = builder.sub_extension(v0, result_v0);
-    let constr = builder.mul_extension(constr_1, constr_2);
-    yield_constr.constraint(builder, constr);
-
     let constr_2 = builder.sub_extension(v1, result_v1);
     let constr = builder.mul_extension(constr_1, constr_2);
     yield_constr.constraint(builder, constr);
@@ -437,8 +618,6 @@ pub fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
     let a0_is_else = syscall.a0[2];
     let v0_in_a0_is_not_fd_stdout_and_fd_stderr_and_fd_stdin =
         builder.constant_extension(F::Extension::from_canonical_usize(0xFFFFFFFF));
-    let v1_in_a0_is_not_fd_stdin_and_fd_stderr_and_fd_stdin =
-        builder.constant_extension(F::Extension::from_canonical_usize(MIPSEBADF));
 
     let filter_1 = builder.mul_extension(is_sysfcntl, a0_is_fd_stdin);
     let constr = builder.sub_extension(is_sysfcntl_a0_stdin, filter_1);
@@ -483,13 +662,6 @@ pub fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
     let constr = builder.mul_extension(constr_1, constr_2);
     yield_constr.constraint(builder, constr);
 
-    let constr_2 = builder.sub_extension(
-        v1_in_a0_is_not_fd_stdin_and_fd_stderr_and_fd_stdin,
-        result_v1,
-    );
-    let constr = builder.mul_extension(constr_1, constr_2);
-    yield_constr.constraint(builder, constr);
-
     //syssetthreadarea
     let is_syssetthreadarea = syscall.sysnum[8];
     let threadarea = lv.mem_channels[6].value;
@@ -21,6 +21,7 @@ use plonky2::plonk::plonk_common::{
 };
 use plonky2::util::serialization::{Buffer, IoResult, Read, Write};
 use plonky2_util::ceil_div_usize;
+use serde::{Deserialize, Serialize};
 
 use crate::all_stark::{Table, NUM_TABLES};
 use crate::config::StarkConfig;
@@ -482,7 +483,7 @@ impl<F: Field> CtlData<'_, F> {
 }
 
 /// Randomness for a single instance of a permutation check protocol.
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub(crate) struct GrandProductChallenge<T: Copy + Eq + PartialEq + Debug> {
     /// Randomness used to combine multiple columns into one.
     pub(crate) beta: T,
@@ -528,7 +529,7 @@ impl GrandProductChallenge<Target> {
 }
 
 /// Like `PermutationChallenge`, but with `num_challenges` copies to boost soundness.
-#[derive(Clone, Eq, PartialEq, Debug)]
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub(crate) struct GrandProductChallengeSet<T: Copy + Eq + PartialEq + Debug> {
     pub(crate) challenges: Vec<GrandProductChallenge<T>>,
 }
@@ -1505,7 +1506,11 @@ pub(crate) mod testutils {
         cross_table_lookups: &[CrossTableLookup<F>],
     ) {
         for (i, ctl) in cross_table_lookups.iter().enumerate() {
-            check_ctl(trace_poly_values, ctl, i);
+            // The lookups without a looking table are those of the public values, which the
+            // verifier adds.
+            if !ctl.looking_tables.is_empty() {
+                check_ctl(trace_poly_values, ctl, i);
+            }
         }
     }
 
@@ -0,0 +1,65 @@
+use crate::modular::{IdentityColumns, ReducedColumns, N_LIMBS};
+use crate::util::impl_columns_view;
+
+/// The number of identities of a row: six for an addition, four for a decompression.
+pub(crate) const NUM_IDENTITIES: usize = 6;
+
+/// A call to `ED_ADD` or `ED_DECOMPRESS`.
+///
+/// An addition computes the products `f = x_p x_q`, `g = y_p y_q`, `h = f g` and `k = d h`, then
+/// `x (1 + k) = x_p y_q + y_p x_q` and `y (1 - k) = g + f`. A decompression of `y` computes
+/// `u = y^2`, `v = d u` and `w = x^2`, then checks `w (v + 1) = u - 1`, with `x` of the sign read.
+#[repr(C)]
+#[derive(Eq, PartialEq, Debug)]
+pub(crate) struct Ed25519ColumnsView<T: Copy> {
+    /// 1 iff the row is an addition.
+    pub is_add: T,
+    /// 1 iff the row is a decompression. Padding rows are neither.
+    pub is_decompress: T,
+
+    /// The timestamp of the CPU row making the call. The inputs are read at this timestamp, and
+    /// the result written right after it.
+    pub timestamp: T,
+    /// The address of `p`, which the result replaces. A decompression reads `y` after `x`.
+    pub p_ptr: T,
+    /// The address of `q`, for an addition.
+    pub q_ptr: T,
+
+    /// The limbs of the coordinates of the points. A decompression only reads `y` of `p`, whose
+    /// top bit is `sign`.
+    pub p: [[T; N_LIMBS]; 2],
+    pub q: [[T; N_LIMBS]; 2],
+    pub sign: T,
+    /// `f`, `g`, `h` and `k` for an addition, `u`, `v` and `w` for a decompression.
+    pub products: [[T; N_LIMBS]; 4],
+    /// The sum, or `x` only for a decompression.
+    pub result: [[T; N_LIMBS]; 2],
+    /// The low limb of `x` is `2 x_half + sign`, for a decompression.
+    pub x_half: T,
+
+    pub identities: [IdentityColumns<T>; NUM_IDENTITIES],
+    /// The coordinates of the result are reduced.
+    pub result_reduced: [ReducedColumns<T>; 2],
+
+    /// The bytes range checked by the table.
+    pub range_counter: T,
+    pub range_frequencies: T,
+}
+
+impl_columns_view!(Ed25519ColumnsView, NUM_ED25519_COLUMNS, ED25519_COL_MAP);
+
+impl<T: Copy> Ed25519ColumnsView<T> {
+    /// The columns range checked in bytes.
+    pub(crate) fn bytes(&self) -> impl Iterator<Item = T> + '_ {
+        self.p
+            .iter()
+            .chain(&self.q)
+            .chain(&self.products)
+            .chain(&self.result)
+            .flatten()
+            .copied()
+            .chain([self.x_half])
+            .chain(self.identities.iter().flat_map(|cols| cols.bytes()))
+            .chain(self.result_reduced.iter().flat_map(|cols| cols.bytes()))
+    }
+}
@@ -0,0 +1,648 @@
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use itertools::Itertools;
+use num_bigint::BigUint;
+use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::field::packed::PackedField;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::{Field, PrimeField64};
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::timed;
+use plonky2::util::timing::TimingTree;
+use zkm_emulator::ed25519;
+
+use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::cross_table_lookup::{Column, Filter};
+use crate::ed25519::columns::*;
+use crate::evaluation_frame::{StarkEvaluationFrame, StarkFrame};
+use crate::lookup::Lookup;
+use crate::memory::segments::Segment;
+use crate::modular::*;
+use crate::stark::Stark;
+use crate::util::trace_rows_to_poly_values;
+
+/// The limbs of the modulus of the base field, `2^255 - 19`.
+pub(crate) const MODULUS: [u8; N_LIMBS] = [
+    0xed, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f,
+];
+
+/// The limbs of the curve constant `d = -121665 / 121666`.
+const D_LIMBS: [u8; N_LIMBS] = [
+    0xa3, 0x78, 0x59, 0x13, 0xca, 0x4d, 0xeb, 0x75, 0xab, 0xd8, 0x41, 0x41, 0x4d, 0x0a, 0x70, 0x00,
+    0x98, 0xe8, 0x79, 0x77, 0x79, 0x40, 0xc7, 0x8c, 0x73, 0xfe, 0x6f, 0x2b, 0xee, 0x6c, 0x03, 0x52,
+];
+
+/// The words read and written by an addition, sixteen each: `p`, `q`, then the sum.
+const NUM_ADD_MEMORY_CTLS: usize = 48;
+/// Then those of a decompression, eight each: `y`, then `x`.
+pub(crate) const NUM_MEMORY_CTLS: usize = NUM_ADD_MEMORY_CTLS + 16;
+
+/// Strict upper bound of the range checks.
+const RANGE_MAX: usize = 1 << LIMB_BITS;
+
+pub(crate) fn ctl_looked_data_add<F: Field>() -> Vec<Column<F>> {
+    let cols = ED25519_COL_MAP;
+    Column::singles([cols.p_ptr, cols.q_ptr, cols.timestamp]).collect()
+}
+
+pub(crate) fn ctl_looked_filter_add<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(ED25519_COL_MAP.is_add))
+}
+
+pub(crate) fn ctl_looked_data_decompress<F: Field>() -> Vec<Column<F>> {
+    let cols = ED25519_COL_MAP;
+    Column::singles([cols.p_ptr, cols.timestamp]).collect()
+}
+
+pub(crate) fn ctl_looked_filter_decompress<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(ED25519_COL_MAP.is_decompress))
+}
+
+/// The word `i` of a coordinate, from its limbs. The words of a point are its integer words,
+/// while a decompression reads and writes bytes, which make big-endian words.
+fn coordinate_word<F: Field>(c: &[usize; N_LIMBS], i: usize, big_endian: bool) -> Column<F> {
+    let limbs = &c[4 * i..4 * i + 4];
+    Column::linear_combination(limbs.iter().enumerate().map(|(j, &l)| {
+        let shift = if big_endian { 3 - j } else { j };
+        (l, F::from_canonical_u64(1 << (LIMB_BITS * shift)))
+    }))
+}
+
+pub(crate) fn ctl_looking_memory<F: Field>(i: usize) -> Vec<Column<F>> {
+    let cols = ED25519_COL_MAP;
+    let read_timestamp = Column::single(cols.timestamp);
+    let write_timestamp =
+        Column::linear_combination_with_constant([(cols.timestamp, F::ONE)], F::ONE);
+
+    let (is_read, ptr, offset, value, timestamp) = if i < NUM_ADD_MEMORY_CTLS {
+        let j = i % 16;
+        let (is_read, ptr, point, timestamp) = match i / 16 {
+            0 => (true, cols.p_ptr, &cols.p, read_timestamp),
+            1 => (true, cols.q_ptr, &cols.q, read_timestamp),
+            _ => (false, cols.p_ptr, &cols.result, write_timestamp),
+        };
+        let value = coordinate_word(&point[j / 8], j % 8, false);
+        (is_read, ptr, 4 * j, value, timestamp)
+    } else {
+        let j = i - NUM_ADD_MEMORY_CTLS;
+        if j < 8 {
+            let value = coordinate_word(&cols.p[1], j, true);
+            (true, cols.p_ptr, 32 + 4 * j, value, read_timestamp)
+        } else {
+            let value = coordinate_word(&cols.result[0], j - 8, true);
+            (false, cols.p_ptr, 4 * (j - 8), value, write_timestamp)
+        }
+    };
+    let res = vec![
+        Column::constant(F::from_bool(is_read)),
+        Column::zero(), // context
+        Column::constant(F::from_canonical_usize(Segment::Code as usize)),
+        Column::linear_combination_with_constant([(ptr, F::ONE)], F::from_canonical_usize(offset)),
+        value,
+        timestamp,
+    ];
+
+    assert_eq!(
+        res.len(),
+        crate::memory::memory_stark::ctl_data::<F>().len()
+    );
+    res
+}
+
+pub(crate) fn ctl_looking_memory_filter<F: Field>(i: usize) -> Filter<F> {
+    let cols = ED25519_COL_MAP;
+    if i < NUM_ADD_MEMORY_CTLS {
+        Filter::new_simple(Column::single(cols.is_add))
+    } else {
+        Filter::new_simple(Column::single(cols.is_decompress))
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Ed25519OpKind {
+    Add,
+    Decompress,
+}
+
+/// Information about an `ED_ADD` or `ED_DECOMPRESS` call.
+#[derive(Clone, Debug)]
+pub(crate) struct Ed25519Op {
+    pub(crate) kind: Ed25519OpKind,
+    /// The address of `p`.
+    pub(crate) p_ptr: usize,
+    /// The address of `q`, for an addition.
+    pub(crate) q_ptr: usize,
+    /// The timestamp of the CPU row making the call.
+    pub(crate) timestamp: usize,
+    /// The words read, sixteen for each point. A decompression only reads the words of the
+    /// compressed `y`, into the second half of `p`.
+    pub(crate) p: ed25519::Point,
+    pub(crate) q: ed25519::Point,
+}
+
+/// The limbs of the coordinates of a point, from its words.
+fn point_limbs(point: &ed25519::Point) -> [[u8; N_LIMBS]; 2] {
+    core::array::from_fn(|i| {
+        let bytes = point[8 * i..8 * i + 8]
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect_vec();
+        bytes.try_into().unwrap()
+    })
+}
+
+/// The limbs of a reduced integer.
+fn biguint_limbs(x: &BigUint) -> [u8; N_LIMBS] {
+    let mut limbs = [0u8; N_LIMBS];
+    for (limb, byte) in limbs.iter_mut().zip(x.to_bytes_le()) {
+        *limb = byte;
+    }
+    limbs
+}
+
+#[derive(Copy, Clone, Default)]
+pub struct Ed25519Stark<F, const D: usize> {
+    f: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Ed25519Stark<F, D> {
+    pub(crate) fn generate_trace(
+        &self,
+        operations: Vec<Ed25519Op>,
+        min_rows: usize,
+        timing: &mut TimingTree,
+    ) -> Vec<PolynomialValues<F>> {
+        let trace_rows = timed!(
+            timing,
+            "generate trace rows",
+            self.generate_trace_rows(operations, min_rows)
+        );
+        timed!(
+            timing,
+            "convert to PolynomialValues",
+            trace_rows_to_poly_values(trace_rows)
+        )
+    }
+
+    fn generate_trace_rows(
+        &self,
+        operations: Vec<Ed25519Op>,
+        min_rows: usize,
+    ) -> Vec<[F; NUM_ED25519_COLUMNS]> {
+        // The range checks need at least `RANGE_MAX` rows.
+        let num_rows = operations
+            .len()
+            .max(min_rows)
+            .max(RANGE_MAX)
+            .next_power_of_two();
+        let mut rows = Vec::with_capacity(num_rows);
+        for op in operations {
+            rows.push(self.generate_row_for_op(op));
+        }
+        rows.resize(num_rows, [F::ZERO; NUM_ED25519_COLUMNS]);
+        self.generate_range_checks(&mut rows);
+        rows
+    }
+
+    fn generate_range_checks(&self, rows: &mut [[F; NUM_ED25519_COLUMNS]]) {
+        let cols = ED25519_COL_MAP;
+        let mut frequencies = vec![0usize; RANGE_MAX];
+        for row in rows.iter() {
+            let mut values = cols.bytes().map(|c| row[c]).collect_vec();
+            if row[cols.is_decompress] == F::ONE {
+                values.push(sign_check(row));
+            }
+            for x in values {
+                let x = x.to_canonical_u64() as usize;
+                assert!(
+                    x < RANGE_MAX,
+                    "column value {} exceeds the max range value {}",
+                    x,
+                    RANGE_MAX
+                );
+                frequencies[x] += 1;
+            }
+        }
+        for (i, row) in rows.iter_mut().enumerate() {
+            row[cols.range_counter] = F::from_canonical_usize(i.min(RANGE_MAX - 1));
+            row[cols.range_frequencies] =
+                F::from_canonical_usize(frequencies.get(i).copied().unwrap_or(0));
+        }
+    }
+
+    fn generate_row_for_op(&self, op: Ed25519Op) -> [F; NUM_ED25519_COLUMNS] {
+        let mut row = Ed25519ColumnsView::default();
+        row.timestamp = F::from_canonical_usize(op.timestamp);
+        row.p_ptr = F::from_canonical_usize(op.p_ptr);
+        row.q_ptr = F::from_canonical_usize(op.q_ptr);
+        match op.kind {
+            Ed25519OpKind::Add => {
+                row.is_add = F::ONE;
+                self.generate_add(&mut row, &op.p, &op.q);
+            }
+            Ed25519OpKind::Decompress => {
+                row.is_decompress = F::ONE;
+                self.generate_decompress(&mut row, &op.p);
+            }
+        }
+        row.into()
+    }
+
+    fn generate_add(
+        &self,
+        row: &mut Ed25519ColumnsView<F>,
+        p: &ed25519::Point,
+        q: &ed25519::Point,
+    ) {
+        let sum = ed25519::add(p, q).expect("the points are off the curve");
+        let (f, g, k) = ed25519::add_products(p, q);
+        let h = &f * &g % &*ed25519::MODULUS;
+
+        let p = point_limbs(p);
+        let q = point_limbs(q);
+        let r = point_limbs(&sum);
+        let products = [f, g, h, k].map(|c| biguint_limbs(&c));
+        row.p = p.map(|c| limbs_to_field(&c));
+        row.q = q.map(|c| limbs_to_field(&c));
+        row.products = products.map(|c| limbs_to_field(&c));
+        row.result = r.map(|c| limbs_to_field(&c));
+
+        let [x1, y1] = p.map(|c| limbs_to_i64(&c));
+        let [x2, y2] = q.map(|c| limbs_to_i64(&c));
+        let [x3, y3] = r.map(|c| limbs_to_i64(&c));
+        let [f, g, h, k] = products.map(|c| limbs_to_i64(&c));
+        let d = limbs_to_i64(&D_LIMBS);
+        let es = [
+            pol_sub(&pol_mul(&x1, &x2), &f),
+            pol_sub(&pol_mul(&y1, &y2), &g),
+            pol_sub(&pol_mul(&f, &g), &h),
+            pol_sub(&pol_mul(&d, &h), &k),
+            pol_sub(
+                &pol_add(&x3, &pol_mul(&x3, &k)),
+                &pol_add(&pol_mul(&x1, &y2), &pol_mul(&y1, &x2)),
+            ),
+            pol_sub(&pol_sub(&y3, &pol_mul(&y3, &k)), &pol_add(&g, &f)),
+        ];
+        for (cols, e) in row.identities.iter_mut().zip(&es) {
+            generate_identity(cols, e, &MODULUS);
+        }
+        for (cols, c) in row.result_reduced.iter_mut().zip(&r) {
+            generate_reduced(cols, c, &MODULUS);
+        }
+    }
+
+    fn generate_decompress(&self, row: &mut Ed25519ColumnsView<F>, p: &ed25519::Point) {
+        let mut compressed = [0u8; N_LIMBS];
+        for (bytes, word) in compressed.chunks_exact_mut(4).zip(&p[8..]) {
+            bytes.copy_from_slice(&word.to_be_bytes());
+        }
+        let x = ed25519::decompress(&compressed).expect("the point is off the curve");
+        let sign = compressed[N_LIMBS - 1] >> 7;
+        let mut y = compressed;
+        y[N_LIMBS - 1] &= 0x7f;
+
+        let m = &*ed25519::MODULUS;
+        let y_int = BigUint::from_bytes_le(&y);
+        let u = &y_int * &y_int % m;
+        let v = &*ed25519::D * &u % m;
+        let w = &x * &x % m;
+        let x = biguint_limbs(&x);
+        let products = [u, v, w].map(|c| biguint_limbs(&c));
+        row.p[1] = limbs_to_field(&compressed);
+        row.sign = F::from_canonical_u8(sign);
+        for (cols, c) in row.products.iter_mut().zip(&products) {
+            *cols = limbs_to_field(c);
+        }
+        row.result[0] = limbs_to_field(&x);
+        row.x_half = F::from_canonical_u8(x[0] >> 1);
+
+        let [u, v, w] = products.map(|c| limbs_to_i64(&c));
+        let (x, y) = (limbs_to_i64(&x), limbs_to_i64(&y));
+        let d = limbs_to_i64(&D_LIMBS);
+        let es = [
+            pol_sub(&pol_mul(&y, &y), &u),
+            pol_sub(&pol_mul(&d, &u), &v),
+            pol_sub(&pol_mul(&x, &x), &w),
+            pol_sub(&pol_add(&pol_mul(&w, &v), &w), &pol_sub(&u, &[1])),
+        ];
+        for (cols, e) in row.identities.iter_mut().zip(&es) {
+            generate_identity(cols, e, &MODULUS);
+        }
+        generate_reduced(&mut row.result_reduced[0], &x, &MODULUS);
+    }
+}
+
+/// Twice the top limb of a compressed `y` without its sign bit, which is a byte iff that limb is
+/// below 128.
+fn sign_check<F: Field>(row: &[F; NUM_ED25519_COLUMNS]) -> F {
+    let cols = ED25519_COL_MAP;
+    row[cols.p[1][N_LIMBS - 1]] * F::TWO - row[cols.sign] * F::from_canonical_u64(256)
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for Ed25519Stark<F, D> {
+    type EvaluationFrame<FE, P, const D2: usize>
+        = StarkFrame<P, NUM_ED25519_COLUMNS>
+    where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>;
+
+    type EvaluationFrameTarget = StarkFrame<ExtensionTarget<D>, NUM_ED25519_COLUMNS>;
+
+    fn eval_packed_generic<FE, P, const D2: usize>(
+        &self,
+        vars: &Self::EvaluationFrame<FE, P, D2>,
+        yield_constr: &mut ConstraintConsumer<P>,
+    ) where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>,
+    {
+        let local_values: &[P; NUM_ED25519_COLUMNS] = vars.get_local_values().try_into().unwrap();
+        let lv: &Ed25519ColumnsView<P> = local_values.borrow();
+        let next_values: &[P; NUM_ED25519_COLUMNS] = vars.get_next_values().try_into().unwrap();
+        let nv: &Ed25519ColumnsView<P> = next_values.borrow();
+
+        // The range counter starts at 0, increments by 0 or 1, and ends at `RANGE_MAX - 1`.
+        yield_constr.constraint_first_row(lv.range_counter);
+        let incr = nv.range_counter - lv.range_counter;
+        yield_constr.constraint_transition(incr * incr - incr);
+        let range_max = P::Scalar::from_canonical_u64((RANGE_MAX - 1) as u64);
+        yield_constr.constraint_last_row(lv.range_counter - range_max);
+
+        let is_real = lv.is_add + lv.is_decompress;
+        for flag in [lv.is_add, lv.is_decompress, is_real, lv.sign] {
+            yield_constr.constraint(flag * (flag - P::ONES));
+        }
+
+        let d = D_LIMBS.map(|l| P::from(FE::from_canonical_u8(l)));
+        let [x1, y1] = &lv.p;
+        let [x2, y2] = &lv.q;
+        let [x3, y3] = &lv.result;
+        let [f, g, h, k] = &lv.products;
+        let e_add = [
+            pol_sub(&pol_mul(x1, x2), f),
+            pol_sub(&pol_mul(y1, y2), g),
+            pol_sub(&pol_mul(f, g), h),
+            pol_sub(&pol_mul(&d, h), k),
+            pol_sub(
+                &pol_add(x3, &pol_mul(x3, k)),
+                &pol_add(&pol_mul(x1, y2), &pol_mul(y1, x2)),
+            ),
+            pol_sub(&pol_sub(y3, &pol_mul(y3, k)), &pol_add(g, f)),
+        ];
+
+        // A decompression reads `y` with the sign of `x` in its top bit, and computes `x` in the
+        // place of that of the sum.
+        let mut y = *y1;
+        y[N_LIMBS - 1] -= lv.sign * FE::from_canonical_u64(128);
+        let [u, v, w, _] = &lv.products;
+        let x = x3;
+        let e_decompress = [
+            pol_sub(&pol_mul(&y, &y), u),
+            pol_sub(&pol_mul(&d, u), v),
+            pol_sub(&pol_mul(x, x), w),
+            pol_sub(&pol_add(&pol_mul(w, v), w), &pol_sub(u, &[P::ONES])),
+        ];
+
+        for (i, cols) in lv.identities.iter().enumerate() {
+            let e = pol_scale(&e_add[i], lv.is_add);
+            let (e, filter) = match e_decompress.get(i) {
+                Some(e_decompress) => (
+                    pol_add(&e, &pol_scale(e_decompress, lv.is_decompress)),
+                    is_real,
+                ),
+                None => (e, lv.is_add),
+            };
+            eval_identity(yield_constr, filter, &e, cols, &MODULUS);
+        }
+
+        // `x` has the sign read, and the result is reduced.
+        let two = FE::TWO;
+        yield_constr.constraint(lv.is_decompress * (x[0] - lv.x_half * two - lv.sign));
+        eval_reduced(yield_constr, is_real, x3, &lv.result_reduced[0], &MODULUS);
+        eval_reduced(yield_constr, lv.is_add, y3, &lv.result_reduced[1], &MODULUS);
+    }
+
+    fn eval_ext_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: &Self::EvaluationFrameTarget,
+        yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+    ) {
+        let local_values: &[ExtensionTarget<D>; NUM_ED25519_COLUMNS] =
+            vars.get_local_values().try_into().unwrap();
+        let lv: &Ed25519ColumnsView<ExtensionTarget<D>> = local_values.borrow();
+        let next_values: &[ExtensionTarget<D>; NUM_ED25519_COLUMNS] =
+            vars.get_next_values().try_into().unwrap();
+        let nv: &Ed25519ColumnsView<ExtensionTarget<D>> = next_values.borrow();
+
+        yield_constr.constraint_first_row(builder, lv.range_counter);
+        let incr = builder.sub_extension(nv.range_counter, lv.range_counter);
+        let constraint = builder.mul_sub_extension(incr, incr, incr);
+        yield_constr.constraint_transition(builder, constraint);
+        let range_max =
+            builder.constant_extension(F::Extension::from_canonical_usize(RANGE_MAX - 1));
+        let constraint = builder.sub_extension(lv.range_counter, range_max);
+        yield_constr.constraint_last_row(builder, constraint);
+
+        let is_real = builder.add_extension(lv.is_add, lv.is_decompress);
+        for flag in [lv.is_add, lv.is_decompress, is_real, lv.sign] {
+            let constraint = builder.mul_sub_extension(flag, flag, flag);
+            yield_constr.constraint(builder, constraint);
+        }
+
+        let d = D_LIMBS.map(F::from_canonical_u8);
+        let [x1, y1] = &lv.p;
+        let [x2, y2] = &lv.q;
+        let [x3, y3] = &lv.result;
+        let [f, g, h, k] = &lv.products;
+
+        let x1_x2 = pol_mul_ext_circuit(builder, x1, x2);
+        let y1_y2 = pol_mul_ext_circuit(builder, y1, y2);
+        let f_g = pol_mul_ext_circuit(builder, f, g);
+        let d_h = pol_mul_const_ext_circuit(builder, &d, h);
+        let x3_k = pol_mul_ext_circuit(builder, x3, k);
+        let x1_y2 = pol_mul_ext_circuit(builder, x1, y2);
+        let y1_x2 = pol_mul_ext_circuit(builder, y1, x2);
+        let y3_k = pol_mul_ext_circuit(builder, y3, k);
+        let x_lhs = pol_add_ext_circuit(builder, x3, &x3_k);
+        let x_rhs = pol_add_ext_circuit(builder, &x1_y2, &y1_x2);
+        let y_lhs = pol_sub_ext_circuit(builder, y3, &y3_k);
+        let y_rhs = pol_add_ext_circuit(builder, g, f);
+        let e_add = [
+            pol_sub_ext_circuit(builder, &x1_x2, f),
+            pol_sub_ext_circuit(builder, &y1_y2, g),
+            pol_sub_ext_circuit(builder, &f_g, h),
+            pol_sub_ext_circuit(builder, &d_h, k),
+            pol_sub_ext_circuit(builder, &x_lhs, &x_rhs),
+            pol_sub_ext_circuit(builder, &y_lhs, &y_rhs),
+        ];
+
+        let mut y = *y1;
+        y[N_LIMBS - 1] =
+            builder.mul_const_add_extension(-F::from_canonical_u64(128), lv.sign, y[N_LIMBS - 1]);
+        let [u, v, w, _] = &lv.products;
+        let x = x3;
+        let y_y = pol_mul_ext_circuit(builder, &y, &y);
+        let d_u = pol_mul_const_ext_circuit(builder, &d, u);
+        let x_x = pol_mul_ext_circuit(builder, x, x);
+        let w_v = pol_mul_ext_circuit(builder, w, v);
+        let lhs = pol_add_ext_circuit(builder, &w_v, w);
+        let one = builder.one_extension();
+        let rhs = pol_sub_ext_circuit(builder, u, &[one]);
+        let e_decompress = [
+            pol_sub_ext_circuit(builder, &y_y, u),
+            pol_sub_ext_circuit(builder, &d_u, v),
+            pol_sub_ext_circuit(builder, &x_x, w),
+            pol_sub_ext_circuit(builder, &lhs, &rhs),
+        ];
+
+        for (i, cols) in lv.identities.iter().enumerate() {
+            let e = e_add[i]
+                .iter()
+                .map(|&c| builder.mul_extension(lv.is_add, c))
+                .collect_vec();
+            let (e, filter) = match e_decompress.get(i) {
+                Some(e_decompress) => {
+                    let e_decompress = e_decompress
+                        .iter()
+                        .map(|&c| builder.mul_extension(lv.is_decompress, c))
+                        .collect_vec();
+                    (pol_add_ext_circuit(builder, &e, &e_decompress), is_real)
+                }
+                None => (e, lv.is_add),
+            };
+            eval_identity_ext_circuit(builder, yield_constr, filter, &e, cols, &MODULUS);
+        }
+
+        let x_odd = builder.mul_const_add_extension(-F::TWO, lv.x_half, x[0]);
+        let x_odd = builder.sub_extension(x_odd, lv.sign);
+        let constraint = builder.mul_extension(lv.is_decompress, x_odd);
+        yield_constr.constraint(builder, constraint);
+        eval_reduced_ext_circuit(
+            builder,
+            yield_constr,
+            is_real,
+            x3,
+            &lv.result_reduced[0],
+            &MODULUS,
+        );
+        eval_reduced_ext_circuit(
+            builder,
+            yield_constr,
+            lv.is_add,
+            y3,
+            &lv.result_reduced[1],
+            &MODULUS,
+        );
+    }
+
+    fn constraint_degree(&self) -> usize {
+        3
+    }
+
+    fn lookups(&self) -> Vec<Lookup<F>> {
+        let cols = ED25519_COL_MAP;
+        let mut columns = Column::singles(cols.bytes()).collect_vec();
+        let mut filter_columns = vec![None; columns.len()];
+        columns.push(Column::linear_combination([
+            (cols.p[1][N_LIMBS - 1], F::TWO),
+            (cols.sign, -F::from_canonical_u64(256)),
+        ]));
+        filter_columns.push(Some(Filter::new_simple(Column::single(cols.is_decompress))));
+        vec![Lookup {
+            columns,
+            table_column: Column::single(cols.range_counter),
+            frequencies_column: Column::single(cols.range_frequencies),
+            filter_columns,
+        }]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Borrow;
+
+    use anyhow::Result;
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::types::PrimeField64;
+    use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    use crate::ed25519::columns::Ed25519ColumnsView;
+    use crate::ed25519::ed25519_stark::{Ed25519Op, Ed25519OpKind, Ed25519Stark};
+    use crate::stark_testing::{test_stark_circuit_constraints, test_stark_low_degree};
+
+    #[test]
+    fn test_stark_degree() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = Ed25519Stark<F, D>;
+
+        let stark = S::default();
+        test_stark_low_degree(stark)
+    }
+
+    #[test]
+    fn test_stark_circuit() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = Ed25519Stark<F, D>;
+
+        let stark = S::default();
+        test_stark_circuit_constraints::<F, C, S, D>(stark)
+    }
+
+    #[test]
+    fn test_generation() -> Result<()> {
+        const D: usize = 2;
+        type F = GoldilocksField;
+        type S = Ed25519Stark<F, D>;
+
+        // The base point, and its double.
+        let g = [
+            0x8f25d51a, 0xc9562d60, 0x9525a7b2, 0x692cc760, 0xfdd6dc5c, 0xc0a4e231, 0xcd6e53fe,
+            0x216936d3, 0x66666658, 0x66666666, 0x66666666, 0x66666666, 0x66666666, 0x66666666,
+            0x66666666, 0x66666666,
+        ];
+        let g2_x_low = 0x2843ce0e;
+
+        let stark = S::default();
+        let op = Ed25519Op {
+            kind: Ed25519OpKind::Add,
+            p_ptr: 0x1000,
+            q_ptr: 0x2000,
+            timestamp: 0,
+            p: g,
+            q: g,
+        };
+        let row = stark.generate_row_for_op(op);
+        let row: &Ed25519ColumnsView<F> = row.borrow();
+        let x_low = (0..4)
+            .map(|i| row.result[0][i].to_canonical_u64() << (8 * i))
+            .sum::<u64>();
+        assert_eq!(x_low, g2_x_low);
+
+        // The compressed base point, as the big-endian words of its bytes.
+        let mut compressed = [0u32; 16];
+        compressed[8] = 0x58666666;
+        compressed[9..].fill(0x66666666);
+        let op = Ed25519Op {
+            kind: Ed25519OpKind::Decompress,
+            p_ptr: 0x1000,
+            q_ptr: 0,
+            timestamp: 0,
+            p: compressed,
+            q: [0; 16],
+        };
+        let row = stark.generate_row_for_op(op);
+        let row: &Ed25519ColumnsView<F> = row.borrow();
+        assert_eq!(row.result[0][0].to_canonical_u64(), 0x1a);
+        assert_eq!(row.result[0][31].to_canonical_u64(), 0x21);
+        Ok(())
+    }
+}
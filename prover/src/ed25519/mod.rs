@@ -0,0 +1,2 @@
+pub mod columns;
+pub mod ed25519_stark;
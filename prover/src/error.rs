@@ -0,0 +1,147 @@
+//! The errors of the runs, proofs and verifications, for the embedders which handle them rather
+//! than print them.
+//!
+//! The API returns them in `anyhow::Error`, as it does the errors of plonky2: they are found with
+//! `err.downcast_ref::<ProvingError>()`. The errors of the emulator, as [`ExecutionError`], are
+//! returned as they are.
+
+use std::fmt::{Display, Formatter};
+
+use crate::config::StarkHasher;
+
+pub use zkm_emulator::error::ExecutionError;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProvingError {
+    /// The witness generation cannot run the instruction `opcode` at `pc`, in the segment
+    /// `segment`, `None` when the proof is not that of a segment of the client.
+    UnsupportedInstruction {
+        segment: Option<usize>,
+        pc: u32,
+        opcode: u32,
+        reason: String,
+    },
+    /// The kernel run around the guest failed at `pc`, which is a bug of the prover.
+    KernelFailure {
+        segment: Option<usize>,
+        pc: u32,
+        opcode: u32,
+        reason: String,
+    },
+    /// The run has `segments` segments, fewer than the `min_segments` the proof needs.
+    TooFewSegments {
+        segments: usize,
+        min_segments: usize,
+    },
+    /// The proof of a segment is missing from the proofs to aggregate.
+    MissingSegment { segment: usize },
+    /// The proof was stopped by its [`CancellationToken`].
+    ///
+    /// [`CancellationToken`]: crate::progress::CancellationToken
+    Cancelled,
+}
+
+impl Display for ProvingError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let segment = |segment: &Option<usize>| match segment {
+            Some(segment) => format!(" of segment {}", segment),
+            None => String::new(),
+        };
+        match self {
+            Self::UnsupportedInstruction {
+                segment: index,
+                pc,
+                opcode,
+                reason,
+            } => write!(
+                f,
+                "unsupported instruction 0x{:08x} at pc 0x{:08x}{}: {}",
+                opcode,
+                pc,
+                segment(index),
+                reason
+            ),
+            Self::KernelFailure {
+                segment: index,
+                pc,
+                opcode,
+                reason,
+            } => write!(
+                f,
+                "the kernel failed on instruction 0x{:08x} at pc 0x{:08x}{}: {}",
+                opcode,
+                pc,
+                segment(index),
+                reason
+            ),
+            Self::TooFewSegments {
+                segments,
+                min_segments,
+            } => write!(
+                f,
+                "the run has {} segments, and cannot be split in {}",
+                segments, min_segments
+            ),
+            Self::MissingSegment { segment } => {
+                write!(f, "the proof of segment {} is missing", segment)
+            }
+            Self::Cancelled => f.write_str("the proof was cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for ProvingError {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VerificationError {
+    /// The proof was made with the hasher `found`, and is verified with `expected`.
+    HasherMismatch {
+        expected: StarkHasher,
+        found: StarkHasher,
+    },
+    /// The proof is not one of the program `expected`, but of `found`.
+    WrongProgram { expected: [u8; 32], found: [u8; 32] },
+    /// The proof of the segment `segment`, or the whole proof if `None`, does not verify.
+    InvalidProof {
+        segment: Option<usize>,
+        reason: String,
+    },
+    /// The segment `segment` does not start where the previous one ended.
+    NotContiguous { segment: usize },
+    /// The journal is not the one whose digest the proof commits to.
+    WrongJournal,
+}
+
+impl Display for VerificationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::HasherMismatch { expected, found } => write!(
+                f,
+                "the proof was made with {:?}, and is verified with {:?}",
+                found, expected
+            ),
+            Self::WrongProgram { expected, found } => write!(
+                f,
+                "the proof is for the program {}, not {}",
+                hex::encode(found),
+                hex::encode(expected)
+            ),
+            Self::InvalidProof {
+                segment: Some(segment),
+                reason,
+            } => write!(f, "the proof of segment {} is invalid: {}", segment, reason),
+            Self::InvalidProof {
+                segment: None,
+                reason,
+            } => write!(f, "the proof is invalid: {}", reason),
+            Self::NotContiguous { segment } => write!(
+                f,
+                "segment {} does not start where the previous one ended",
+                segment
+            ),
+            Self::WrongJournal => write!(f, "the journal is not the one the proof commits to"),
+        }
+    }
+}
+
+impl std::error::Error for VerificationError {}
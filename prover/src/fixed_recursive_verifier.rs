@@ -390,6 +390,97 @@ where
             &all_stark.cross_table_lookups,
             stark_config,
         );
+        let keccak = RecursiveCircuitsForTable::new(
+            Table::Keccak,
+            &all_stark.keccak_stark,
+            degree_bits_ranges[Table::Keccak as usize].clone(),
+            &all_stark.cross_table_lookups,
+            stark_config,
+        );
+        let keccak_sponge = RecursiveCircuitsForTable::new(
+            Table::KeccakSponge,
+            &all_stark.keccak_sponge_stark,
+            degree_bits_ranges[Table::KeccakSponge as usize].clone(),
+            &all_stark.cross_table_lookups,
+            stark_config,
+        );
+        let sha_extend = RecursiveCircuitsForTable::new(
+            Table::ShaExtend,
+            &all_stark.sha_extend_stark,
+            degree_bits_ranges[Table::ShaExtend as usize].clone(),
+            &all_stark.cross_table_lookups,
+            stark_config,
+        );
+        let sha_compress = RecursiveCircuitsForTable::new(
+            Table::ShaCompress,
+            &all_stark.sha_compress_stark,
+            degree_bits_ranges[Table::ShaCompress as usize].clone(),
+            &all_stark.cross_table_lookups,
+            stark_config,
+        );
+        let secp256k1 = RecursiveCircuitsForTable::new(
+            Table::Secp256k1,
+            &all_stark.secp256k1_stark,
+            degree_bits_ranges[Table::Secp256k1 as usize].clone(),
+            &all_stark.cross_table_lookups,
+            stark_config,
+        );
+        let ed25519 = RecursiveCircuitsForTable::new(
+            Table::Ed25519,
+            &all_stark.ed25519_stark,
+            degree_bits_ranges[Table::Ed25519 as usize].clone(),
+            &all_stark.cross_table_lookups,
+            stark_config,
+        );
+        let bn254 = RecursiveCircuitsForTable::new(
+            Table::Bn254,
+            &all_stark.bn254_stark,
+            degree_bits_ranges[Table::Bn254 as usize].clone(),
+            &all_stark.cross_table_lookups,
+            stark_config,
+        );
+        let bls12381_fp = RecursiveCircuitsForTable::new(
+            Table::Bls12381Fp,
+            &all_stark.bls12381_fp_stark,
+            degree_bits_ranges[Table::Bls12381Fp as usize].clone(),
+            &all_stark.cross_table_lookups,
+            stark_config,
+        );
+        let uint256 = RecursiveCircuitsForTable::new(
+            Table::Uint256,
+            &all_stark.uint256_stark,
+            degree_bits_ranges[Table::Uint256 as usize].clone(),
+            &all_stark.cross_table_lookups,
+            stark_config,
+        );
+        let blake = RecursiveCircuitsForTable::new(
+            Table::Blake,
+            &all_stark.blake_stark,
+            degree_bits_ranges[Table::Blake as usize].clone(),
+            &all_stark.cross_table_lookups,
+            stark_config,
+        );
+        let chacha = RecursiveCircuitsForTable::new(
+            Table::ChaCha,
+            &all_stark.chacha_stark,
+            degree_bits_ranges[Table::ChaCha as usize].clone(),
+            &all_stark.cross_table_lookups,
+            stark_config,
+        );
+        let secp256r1 = RecursiveCircuitsForTable::new(
+            Table::Secp256r1,
+            &all_stark.secp256r1_stark,
+            degree_bits_ranges[Table::Secp256r1 as usize].clone(),
+            &all_stark.cross_table_lookups,
+            stark_config,
+        );
+        let modexp = RecursiveCircuitsForTable::new(
+            Table::ModExp,
+            &all_stark.modexp_stark,
+            degree_bits_ranges[Table::ModExp as usize].clone(),
+            &all_stark.cross_table_lookups,
+            stark_config,
+        );
         let logic = RecursiveCircuitsForTable::new(
             Table::Logic,
             &all_stark.logic_stark,
@@ -405,7 +496,27 @@ where
             stark_config,
         );
 
-        let by_table = [arithmetic, cpu, poseidon, poseidon_sponge, logic, memory];
+        let by_table = [
+            arithmetic,
+            cpu,
+            poseidon,
+            poseidon_sponge,
+            keccak,
+            keccak_sponge,
+            sha_extend,
+            sha_compress,
+            secp256k1,
+            ed25519,
+            bn254,
+            bls12381_fp,
+            uint256,
+            blake,
+            chacha,
+            secp256r1,
+            modexp,
+            logic,
+            memory,
+        ];
         let root = Self::create_root_circuit(&by_table, stark_config);
         let aggregation = Self::create_aggregation_circuit(&root);
         let block = Self::create_block_circuit(&aggregation);
@@ -481,9 +592,29 @@ where
         }
 
         // Extra sums to add to the looked last value.
-        // Only necessary for the Memory values.
-        let extra_looking_sums =
-            vec![vec![builder.zero(); stark_config.num_challenges]; NUM_TABLES];
+        // Only necessary for the exit code and the image ids, looked up in the CPU table.
+        let zero = builder.zero();
+        let two_32 = builder.constant(F::from_canonical_u64(1 << 32));
+        let mut exit = vec![public_values.exit_code];
+        for limbs in public_values.roots_after.root.chunks(2) {
+            exit.push(builder.mul_add(limbs[1], two_32, limbs[0]));
+        }
+        let mut image_id = vec![zero];
+        for limbs in public_values.roots_before.root.chunks(2) {
+            image_id.push(builder.mul_add(limbs[1], two_32, limbs[0]));
+        }
+        let mut extra_looking_sums = vec![vec![zero; stark_config.num_challenges]; NUM_TABLES];
+        extra_looking_sums[Table::Cpu as usize] = ctl_challenges
+            .challenges
+            .iter()
+            .map(|challenge| {
+                let combined = challenge.combine_base_circuit(&mut builder, &exit);
+                let exit = builder.inverse(combined);
+                let combined = challenge.combine_base_circuit(&mut builder, &image_id);
+                let image_id = builder.inverse(combined);
+                builder.add(exit, image_id)
+            })
+            .collect();
 
         // Verify the CTL checks.
         verify_cross_table_lookups_circuit::<F, D>(
@@ -587,6 +718,18 @@ where
             builder.connect(*limb0, *limb1);
         }
 
+        // The exit code is the one of the last segment.
+        builder.connect(public_values.exit_code, rhs_public_values.exit_code);
+
+        // The journal is only appended to, so its digest is the one of the last segment too.
+        for (&limb0, &limb1) in public_values
+            .journal_digest
+            .iter()
+            .zip_eq(&rhs_public_values.journal_digest)
+        {
+            builder.connect(limb0, limb1);
+        }
+
         // Pad to match the root circuit's degree.
         while log2_ceil(builder.num_gates()) < root.circuit.common.degree_bits() {
             builder.add_gate(NoopGate, vec![]);
@@ -658,6 +801,14 @@ where
         for (&limb0, &limb1) in parent_pv.userdata.iter().zip_eq(&agg_pv.userdata) {
             builder.connect(limb0, limb1);
         }
+        builder.connect(public_values.exit_code, agg_pv.exit_code);
+        for (&limb0, &limb1) in public_values
+            .journal_digest
+            .iter()
+            .zip_eq(&agg_pv.journal_digest)
+        {
+            builder.connect(limb0, limb1);
+        }
 
         let cyclic_vk = builder.add_verifier_data_public_inputs();
         builder
@@ -694,6 +845,12 @@ where
         }
     }
 
+    /// Builds the block circuit again, for the wrappers which need to own it. Circuits are built
+    /// deterministically, so it has the verifier data of `self.block.circuit`.
+    pub fn block_circuit_data(&self) -> CircuitData<F, C, D> {
+        Self::create_block_circuit(&self.aggregation).circuit
+    }
+
     /// Create a proof for each STARK, then combine them, eventually culminating in a root proof.
     pub fn prove_root(
         &self,
@@ -2,23 +2,26 @@ pub(crate) mod outputs;
 pub mod state;
 use crate::generation::state::{AssumptionReceipts, AssumptionUsage};
 use crate::proof::{MemRoots, PublicValues};
-use anyhow::anyhow;
+use crate::util::traced;
+use anyhow::{anyhow, ensure};
 use plonky2::field::extension::Extendable;
 use plonky2::field::polynomial::PolynomialValues;
 use plonky2::hash::hash_types::RichField;
 use plonky2::plonk::config::GenericConfig;
-use plonky2::timed;
 use plonky2::util::timing::TimingTree;
 
 use crate::all_stark::NUM_PUBLIC_INPUT_USERDATA;
 use crate::all_stark::{AllStark, NUM_TABLES};
 use crate::config::StarkConfig;
 use crate::cpu::bootstrap_kernel::generate_bootstrap_kernel;
-use crate::cpu::columns::CpuColumnsView;
+use crate::cpu::exit_kernel::generate_exit_kernel;
 use crate::cpu::kernel::assembler::Kernel;
 use crate::generation::outputs::{get_outputs, GenerationOutputs};
 use crate::generation::state::GenerationState;
+use crate::witness::state::RegistersState;
 use crate::witness::transition::transition;
+use zkm_emulator::cp0::{CP0_BADVADDR, CP0_CAUSE, CP0_STATUS};
+use zkm_emulator::trace::{ExecutionTrace, TraceStep, TRACE_REGISTERS};
 
 use std::{cell::RefCell, rc::Rc};
 
@@ -38,7 +41,7 @@ pub fn generate_traces<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>,
     let mut state = GenerationState::<F, C, D>::new(kernel.program.step, kernel).unwrap();
     generate_bootstrap_kernel::<F, C, D>(&mut state, kernel);
 
-    timed!(timing, "simulate CPU", simulate_cpu(&mut state, kernel)?);
+    traced!(timing, "simulate CPU", simulate_cpu(&mut state, kernel)?);
 
     log::info!(
         "Trace lengths (before padding): {:?}",
@@ -66,8 +69,10 @@ pub fn generate_traces<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>,
             root: unsafe { std::mem::transmute::<[u8; 32], [u32; 8]>(kernel.program.image_id) },
         },
         userdata,
+        exit_code: state.registers.exit_code as u32,
+        journal_digest: PublicValues::journal_digest_of(&state.public_values_stream),
     };
-    let tables = timed!(
+    let tables = traced!(
         timing,
         "convert trace data to tables",
         state.traces.into_tables(all_stark, config, timing)
@@ -100,7 +105,7 @@ pub fn generate_traces_with_assumptions<
     }
     generate_bootstrap_kernel::<F, C, D>(&mut state, kernel);
 
-    timed!(timing, "simulate CPU", simulate_cpu(&mut state, kernel)?);
+    traced!(timing, "simulate CPU", simulate_cpu(&mut state, kernel)?);
 
     log::info!(
         "Trace lengths (before padding): {:?}",
@@ -128,8 +133,10 @@ pub fn generate_traces_with_assumptions<
             root: unsafe { std::mem::transmute::<[u8; 32], [u32; 8]>(kernel.program.image_id) },
         },
         userdata,
+        exit_code: state.registers.exit_code as u32,
+        journal_digest: PublicValues::journal_digest_of(&state.public_values_stream),
     };
-    let tables = timed!(
+    let tables = traced!(
         timing,
         "convert trace data to tables",
         state.traces.into_tables(all_stark, config, timing)
@@ -137,6 +144,62 @@ pub fn generate_traces_with_assumptions<
     Ok((tables, public_values, outputs, state.assumptions_used))
 }
 
+/// Runs the witness generation of the segment `kernel` against `trace`, the execution trace of
+/// the whole run recorded by the emulator, and fails at the first instruction where they differ:
+/// in its pc or in the registers it wrote. The memory operations are not compared, as the prover
+/// also goes through memory for the registers.
+pub fn replay_trace<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    kernel: &Kernel,
+    trace: &ExecutionTrace,
+) -> anyhow::Result<()> {
+    let mut state = GenerationState::<F, C, D>::new(kernel.program.step, kernel).unwrap();
+    generate_bootstrap_kernel::<F, C, D>(&mut state, kernel);
+
+    for _ in 0..state.step {
+        if state.registers.exited {
+            break;
+        }
+        let step = state.total_step;
+        let expected = trace
+            .steps
+            .get(step)
+            .ok_or_else(|| anyhow!("the trace ends before step {}", step))?;
+        let pc = state.registers.program_counter as u32;
+        let before = trace_registers(&state.registers);
+        transition(&mut state, kernel)?;
+        state.total_step += 1;
+
+        let after = trace_registers(&state.registers);
+        let found = TraceStep::new(pc, expected.insn, &before, &after, vec![]);
+        ensure!(
+            found.pc == expected.pc && found.registers == expected.registers,
+            "the witness generation diverges from the trace at step {}: expected pc 0x{:x} \
+             writing {:x?}, found pc 0x{:x} writing {:x?}",
+            step,
+            expected.pc,
+            expected.registers,
+            found.pc,
+            found.registers
+        );
+    }
+    Ok(())
+}
+
+/// The registers which a step of an execution trace records, see
+/// [`zkm_emulator::trace::trace_registers`].
+fn trace_registers(registers: &RegistersState) -> [u32; TRACE_REGISTERS] {
+    let mut values = [0u32; TRACE_REGISTERS];
+    for (value, &gpr) in values.iter_mut().zip(&registers.gprs) {
+        *value = gpr as u32;
+    }
+    values[32] = registers.cp0.regs[CP0_STATUS];
+    values[33] = registers.lo as u32;
+    values[34] = registers.hi as u32;
+    values[35] = registers.cp0.regs[CP0_BADVADDR];
+    values[36] = registers.cp0.regs[CP0_CAUSE];
+    values
+}
+
 /// Perform MIPS instruction and transit state
 pub(crate) fn simulate_cpu<
     F: RichField + Extendable<D>,
@@ -155,39 +218,21 @@ pub(crate) fn simulate_cpu<
         if halt {
             log::info!("CPU halted after {} cycles", state.traces.clock());
 
-            // FIXME: should be quit if not matching
-            if step == state.step && pc != kernel.program.end_pc {
-                log::error!(
-                    "Segment split {} error at {:X} expected: {:X}",
-                    step,
-                    pc,
-                    kernel.program.end_pc
-                )
-            }
-
-            //generate_exit_kernel::<F>(state, kernel);
-
-            // Padding
-            let mut row = CpuColumnsView::<F>::default();
-            row.clock = F::from_canonical_usize(state.traces.clock());
-            row.context = F::from_canonical_usize(state.registers.context);
-            row.program_counter = F::from_canonical_usize(pc);
-            row.next_program_counter = F::from_canonical_usize(state.registers.next_pc);
-            row.is_exit_kernel = F::ONE;
-
-            loop {
-                state.traces.push_cpu(row);
-                row.clock += F::ONE;
-                if state.traces.clock().is_power_of_two() {
-                    break;
-                }
-            }
-            log::info!("CPU trace padded to {} cycles", state.traces.clock());
+            ensure!(
+                pc == kernel.program.end_pc,
+                "the segment ends at 0x{:X} after {} steps, not at its end pc 0x{:X}",
+                pc,
+                step,
+                kernel.program.end_pc
+            );
+
+            generate_exit_kernel::<F, C, D>(state, kernel);
 
             return Ok(());
         }
 
         transition(state, kernel)?;
         step += 1;
+        state.total_step += 1;
     }
 }
@@ -8,16 +8,23 @@ use plonky2::field::extension::Extendable;
 use plonky2::hash::hash_types::RichField;
 use plonky2::plonk::config::GenericConfig;
 use plonky2::plonk::proof::ProofWithPublicInputs;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::{cell::RefCell, rc::Rc};
+use zkm_emulator::journal::Journal;
+use zkm_emulator::syscall::{FdTable, GuardPages, PreimageOracle};
 
 pub const ZERO: [u8; 32] = [0u8; 32];
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Receipt<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> {
     pub proof: ProofWithPublicInputs<F, C, D>,
     pub root_before: Vec<u8>,
     pub userdata: Vec<u8>,
+    /// The values committed by the program. The proof commits to their digest, which
+    /// `ZkmCompressedProof` checks them against.
+    pub journal: Vec<u8>,
 }
 
 impl<F, C, const D: usize> Receipt<F, C, D>
@@ -33,6 +40,11 @@ where
         let digest: [u8; 32] = hasher.finalize().into();
         digest
     }
+
+    /// The values committed by the program, to decode in the order it committed them.
+    pub fn journal(&self) -> Journal {
+        Journal::new(self.journal.clone())
+    }
 }
 
 pub(crate) struct GenerationStateCheckpoint {
@@ -108,6 +120,12 @@ pub(crate) struct GenerationState<
     pub(crate) input_stream_ptr: usize,
     pub(crate) public_values_stream: Vec<u8>,
     pub(crate) public_values_stream_ptr: usize,
+    pub(crate) fds: FdTable,
+    /// The number of steps executed before the current one, since the start of the program.
+    pub(crate) total_step: usize,
+    pub(crate) epoch: u64,
+    pub(crate) guard_pages: GuardPages,
+    pub(crate) oracle: PreimageOracle,
     pub(crate) traces: Traces<F>,
     pub(crate) assumptions: Rc<RefCell<AssumptionReceipts<F, C, D>>>,
     pub(crate) assumptions_used: Rc<RefCell<AssumptionUsage<F, C, D>>>,
@@ -128,6 +146,11 @@ where
             input_stream_ptr: kernel.program.input_stream_ptr,
             public_values_stream: kernel.program.public_values_stream.clone(),
             public_values_stream_ptr: kernel.program.public_values_stream_ptr,
+            fds: kernel.program.fds.clone(),
+            total_step: kernel.program.total_step,
+            epoch: kernel.program.epoch,
+            guard_pages: kernel.program.guard_pages.clone(),
+            oracle: kernel.program.oracle.clone(),
             assumptions: Rc::new(RefCell::new(Vec::new())),
             assumptions_used: Rc::new(RefCell::new(Vec::new())),
             step,
@@ -101,6 +101,10 @@ pub(crate) fn observe_public_values<
     for elem in &public_values.userdata {
         challenger.observe_element(F::from_canonical_u8(*elem));
     }
+    challenger.observe_element(F::from_canonical_u32(public_values.exit_code));
+    for limb in public_values.journal_digest {
+        challenger.observe_element(F::from_canonical_u32(limb));
+    }
     Ok(())
 }
 
@@ -117,6 +121,8 @@ pub(crate) fn observe_public_values_target<
     observe_trie_roots_target::<F, C, D>(challenger, &public_values.roots_before);
     observe_trie_roots_target::<F, C, D>(challenger, &public_values.roots_after);
     challenger.observe_elements(&public_values.userdata);
+    challenger.observe_element(public_values.exit_code);
+    challenger.observe_elements(&public_values.journal_digest);
 }
 
 impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> AllProof<F, C, D> {
@@ -0,0 +1,57 @@
+//! Wrapping of the compressed proof of a run for verification on chain.
+//!
+//! The block proof is verified in a plonky2 circuit over BN254, hashing with Poseidon over BN254,
+//! whose proofs the gnark prover of `gnark-plonky2-verifier` turns into Groth16 proofs. The
+//! wrapped circuit is the same for all the runs, so it is built once by [`Groth16Wrapper::new`],
+//! and the gnark prover keeps the keys it derives from the circuit files next to them: wrapping
+//! to the same directory each time only sets up Groth16 once.
+
+use std::path::Path;
+
+use anyhow::Result;
+use plonky2x::backend::circuit::Groth16WrapperParameters;
+use plonky2x::backend::wrapper::wrap::{WrappedCircuit, WrappedOutput};
+use plonky2x::frontend::builder::CircuitBuilder as WrapperBuilder;
+use plonky2x::prelude::DefaultParameters;
+
+use crate::client::{ZkmCompressedProof, C, D, F};
+use crate::fixed_recursive_verifier::AllRecursiveCircuits;
+use crate::proof::PublicValuesTarget;
+
+pub struct Groth16Wrapper {
+    circuit: WrappedCircuit<DefaultParameters, Groth16WrapperParameters, D>,
+}
+
+impl Groth16Wrapper {
+    /// Builds the wrapper of the block circuit of `circuits`.
+    pub fn new(circuits: &AllRecursiveCircuits<F, C, D>) -> Self {
+        let block = circuits.block_circuit_data();
+        let num_public_inputs = block.common.num_public_inputs;
+        let mut circuit = WrapperBuilder::<DefaultParameters, D>::new().build();
+        circuit.set_data(block);
+
+        // The bit sizes of the public inputs, which the wrapper packs into BN254 elements.
+        let mut bit_size = vec![32usize; 16];
+        bit_size.extend(vec![8; 32]);
+        bit_size.push(32);
+        bit_size.extend(vec![32; 8]);
+        bit_size.extend(vec![64; num_public_inputs - PublicValuesTarget::SIZE]);
+        Self {
+            circuit: WrappedCircuit::build(circuit, Some((vec![], bit_size))),
+        }
+    }
+
+    /// Wraps the block proof of `proof`.
+    pub fn wrap(
+        &self,
+        proof: &ZkmCompressedProof,
+    ) -> Result<WrappedOutput<Groth16WrapperParameters, D>> {
+        self.circuit.prove(&proof.receipt.proof)
+    }
+
+    /// Wraps the block proof of `proof`, and writes the wrapped circuit and proof to `dir` for
+    /// the gnark prover.
+    pub fn wrap_to(&self, proof: &ZkmCompressedProof, dir: impl AsRef<Path>) -> Result<()> {
+        self.wrap(proof)?.save(dir.as_ref())
+    }
+}
@@ -0,0 +1,120 @@
+//! The recursive circuits kept on disk, as building them takes minutes.
+//!
+//! The circuits follow from the STARK config, the degree ranges of the tables and the code of the
+//! crate, so they are cached under a fingerprint of the first two and of the version of the
+//! crate. A file which does not decode is built again and overwritten.
+
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use log::{info, warn};
+use plonky2::util::serialization::{DefaultGateSerializer, DefaultGeneratorSerializer};
+use sha2::{Digest, Sha256};
+
+use crate::client::{C, D, F};
+use crate::config::StarkConfig;
+use crate::fixed_recursive_verifier::AllRecursiveCircuits;
+use crate::serialization::{self, Kind};
+
+#[derive(Clone, Debug)]
+pub struct KeyCache {
+    dir: PathBuf,
+}
+
+impl KeyCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The fingerprint of the circuits built for `config` and `degree_bits_range`.
+    pub fn fingerprint(config: &StarkConfig, degree_bits_range: &[Range<usize>]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(env!("CARGO_PKG_VERSION"));
+        hasher.update(serialization::VERSION.to_le_bytes());
+        hasher.update(format!(
+            "{} {} {:?} {:?}",
+            config.security_bits, config.num_challenges, config.fri_config, degree_bits_range
+        ));
+        hex::encode(&hasher.finalize()[..16])
+    }
+
+    pub fn path(&self, fingerprint: &str) -> PathBuf {
+        self.dir.join(format!("circuits-{}.bin", fingerprint))
+    }
+
+    /// Loads the circuits of `fingerprint`, or builds them with `build` and stores them.
+    pub fn load_or_build(
+        &self,
+        fingerprint: &str,
+        build: impl FnOnce() -> AllRecursiveCircuits<F, C, D>,
+    ) -> AllRecursiveCircuits<F, C, D> {
+        let path = self.path(fingerprint);
+        if path.exists() {
+            match load(&path) {
+                Ok(circuits) => {
+                    info!("loaded the circuits from {:?}", path);
+                    return circuits;
+                }
+                Err(err) => warn!("failed to load the circuits from {:?}: {}", path, err),
+            }
+        }
+        let circuits = build();
+        if let Err(err) = self.store(&path, &circuits) {
+            warn!("failed to store the circuits to {:?}: {}", path, err);
+        }
+        circuits
+    }
+
+    fn store(&self, path: &Path, circuits: &AllRecursiveCircuits<F, C, D>) -> Result<()> {
+        let bytes = circuits
+            .to_bytes(
+                &DefaultGateSerializer,
+                &DefaultGeneratorSerializer::<C, D>::default(),
+            )
+            .map_err(|_| anyhow!("failed to serialize the circuits"))?;
+        fs::create_dir_all(&self.dir)?;
+        // Written aside then renamed, so that a concurrent run never reads half a file.
+        let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+        fs::write(&tmp, serialization::encode(Kind::Circuits, &bytes))?;
+        fs::rename(tmp, path)?;
+        Ok(())
+    }
+}
+
+fn load(path: &Path) -> Result<AllRecursiveCircuits<F, C, D>> {
+    let bytes = fs::read(path)?;
+    AllRecursiveCircuits::from_bytes(
+        serialization::decode(Kind::Circuits, &bytes)?,
+        &DefaultGateSerializer,
+        &DefaultGeneratorSerializer::<C, D>::default(),
+    )
+    .map_err(|_| anyhow!("invalid circuits"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::DEGREE_BITS_RANGE;
+
+    #[test]
+    fn test_fingerprint() {
+        let config = StarkConfig::standard_fast_config();
+        let fingerprint = KeyCache::fingerprint(&config, &DEGREE_BITS_RANGE);
+        assert_eq!(
+            fingerprint,
+            KeyCache::fingerprint(&config, &DEGREE_BITS_RANGE)
+        );
+
+        let mut ranges = DEGREE_BITS_RANGE;
+        ranges[0].end += 1;
+        assert_ne!(fingerprint, KeyCache::fingerprint(&config, &ranges));
+        let mut other = StarkConfig::standard_fast_config();
+        other.fri_config.num_query_rounds += 1;
+        assert_ne!(
+            fingerprint,
+            KeyCache::fingerprint(&other, &DEGREE_BITS_RANGE)
+        );
+    }
+}
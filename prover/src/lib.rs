@@ -10,27 +10,56 @@
 #![allow(dead_code)]
 pub mod all_stark;
 pub mod arithmetic;
+pub mod batch;
+pub mod blake;
+pub mod bls12_381;
+pub mod chacha;
+pub mod client;
 pub mod config;
 pub mod constraint_consumer;
+pub mod continuation;
 pub mod cpu;
 pub mod cross_table_lookup;
+pub mod ed25519;
+pub mod error;
 pub mod evaluation_frame;
 pub mod fixed_recursive_verifier;
 pub mod generation;
 pub mod get_challenges;
+pub mod groth16;
 pub mod keccak;
 pub mod keccak_sponge;
+pub mod key_cache;
 pub mod logic;
 pub mod lookup;
 pub mod memory;
+pub mod modexp;
+pub mod modular;
 pub mod poseidon;
 pub mod poseidon_sponge;
+pub mod precompile;
+pub mod progress;
 pub mod proof;
 pub mod prover;
 pub mod recursive_verifier;
+pub mod segment_prover;
+pub mod serialization;
+pub mod sha256;
+pub mod solidity;
 pub mod stark;
 pub mod stark_testing;
+pub mod uint256;
 pub mod util;
 pub mod vanishing_poly;
 pub mod verifier;
+pub mod weierstrass;
 pub mod witness;
+
+/// The bytes of the ELF of the program of the package `$name`, built by the build script of the
+/// host with `zkm_build::build_program`.
+#[macro_export]
+macro_rules! include_elf {
+    ($name:literal) => {
+        include_bytes!(env!(concat!("ZKM_ELF_", $name)))
+    };
+}
@@ -40,6 +40,13 @@ pub fn ctl_filter<F: Field>() -> Filter<F> {
     Filter::new_simple(Column::single(FILTER))
 }
 
+/// The memory ops of the CPU and of the precompiles, sorted by address then timestamp, and
+/// bound to their execution-ordered log by the CTL of [`ctl_data`].
+///
+/// Consecutive rows must be in order, which the `RANGE_CHECK` column enforces, and a read must
+/// return the value of the previous op at the same address. The value of the first op at an
+/// address is free: the image is written by the bootstrap kernel and bound to the memory root by
+/// the page hashes, and the registers are read from the state the segment starts with.
 #[derive(Copy, Clone, Default)]
 pub struct MemoryStark<F, const D: usize> {
     pub(crate) f: PhantomData<F>,
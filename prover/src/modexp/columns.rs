@@ -0,0 +1,109 @@
+use zkm_emulator::modexp::MODEXP_MAX_WORDS;
+
+use crate::util::impl_columns_view;
+
+/// The number of words of the base and the modulus. Shorter ones are padded with zeros.
+pub(crate) const MODEXP_WORDS: usize = MODEXP_MAX_WORDS;
+
+/// The number of byte limbs of an integer.
+pub(crate) const MODEXP_LIMBS: usize = 4 * MODEXP_WORDS;
+
+/// The quotient of a product by the modulus is below `2^(8 MODEXP_LIMBS)`, as one factor is
+/// reduced.
+pub(crate) const QUOTIENT_LIMBS: usize = MODEXP_LIMBS;
+
+/// `q(X) n(X)` has `QUOTIENT_LIMBS + MODEXP_LIMBS - 1` coefficients, so `w(X)` has one less.
+pub(crate) const N_CARRIES: usize = QUOTIENT_LIMBS + MODEXP_LIMBS - 2;
+
+/// A step of a call to `MODEXP`, computing `base^exp mod n` by squaring and multiplying from the
+/// most significant bit of the exponent. Each row is one multiplication `x * y mod n`, of the
+/// accumulator by itself for each bit, then by the base for a set bit. The accumulator starts at
+/// 1, and is the result of the previous row after that.
+///
+/// Products are checked with the identity `x(X) y(X) - q(X) n(X) - r(X) = (X - 2^8) w(X)` of the
+/// polynomials of the limbs, as in `crate::uint256`, with a third byte in the coefficients of
+/// `w` for the longer integers.
+#[repr(C)]
+#[derive(Eq, PartialEq, Debug)]
+pub(crate) struct ModExpColumnsView<T: Copy> {
+    /// 1 iff the row squares the accumulator for the first bit of a word of the exponent.
+    pub is_word_start: T,
+    /// 1 iff the row squares the accumulator for another bit.
+    pub is_square: T,
+    /// 1 iff the row multiplies the accumulator by the base, after the square of a set bit.
+    /// Padding rows are none of the three.
+    pub is_multiply: T,
+    /// 1 iff the row is the first of a call.
+    pub is_first: T,
+    /// 1 iff the row is the last of a call, which writes the result.
+    pub is_last: T,
+
+    /// The timestamp of the CPU row making the call. The operands are read at this timestamp,
+    /// and the result written right after it.
+    pub timestamp: T,
+    /// The address of the base, which the result replaces.
+    pub base_ptr: T,
+    /// The address of the number of words of the base and the modulus, which that of the
+    /// exponent, the modulus and the exponent follow.
+    pub params_ptr: T,
+    /// 1 for each word of the base and the modulus, and 0 for the padding.
+    pub word_mask: [T; MODEXP_WORDS],
+
+    /// The bit of the exponent of the row.
+    pub bit: T,
+    /// The bits of the word of the exponent up to that of the row, the word itself after its
+    /// last bit.
+    pub exp_acc: T,
+    /// The number of bits of the word after that of the row, and 1 iff there are none.
+    pub bits_left: T,
+    pub is_word_last: T,
+    /// The address of the word of the exponent, and the number of words below it.
+    pub exp_ptr: T,
+    pub exp_words_left: T,
+
+    /// The limbs of the base, which need not be reduced, and of the modulus.
+    pub base: [T; MODEXP_LIMBS],
+    pub modulus: [T; MODEXP_LIMBS],
+    /// The limbs of the factors and of the product of the row.
+    pub x: [T; MODEXP_LIMBS],
+    pub y: [T; MODEXP_LIMBS],
+    pub result: [T; MODEXP_LIMBS],
+
+    /// The limbs of `q`.
+    pub quotient: [T; QUOTIENT_LIMBS],
+    /// The bytes of the coefficients of `w`, plus `2^23`.
+    pub carry_lo: [T; N_CARRIES],
+    pub carry_mid: [T; N_CARRIES],
+    pub carry_hi: [T; N_CARRIES],
+
+    /// The limbs of `n - 1 - r`, which shows `r < n` and so `n > 0`.
+    pub diff: [T; MODEXP_LIMBS],
+    /// The carries of the limb-wise addition `r + diff + 1 = n`, as bits.
+    pub diff_carry: [T; MODEXP_LIMBS - 1],
+
+    /// The bytes range checked by the table.
+    pub range_counter: T,
+    pub range_frequencies: T,
+}
+
+impl_columns_view!(ModExpColumnsView, NUM_MODEXP_COLUMNS, MODEXP_COL_MAP);
+
+impl<T: Copy> ModExpColumnsView<T> {
+    /// The columns range checked in bytes.
+    pub(crate) fn bytes(&self) -> impl Iterator<Item = T> + '_ {
+        [self.bits_left, self.exp_words_left].into_iter().chain(
+            self.base
+                .iter()
+                .chain(&self.modulus)
+                .chain(&self.x)
+                .chain(&self.y)
+                .chain(&self.result)
+                .chain(&self.quotient)
+                .chain(&self.carry_lo)
+                .chain(&self.carry_mid)
+                .chain(&self.carry_hi)
+                .chain(&self.diff)
+                .copied(),
+        )
+    }
+}
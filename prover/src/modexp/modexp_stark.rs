@@ -0,0 +1,907 @@
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+use std::ops::Range;
+
+use itertools::Itertools;
+use num_bigint::BigUint;
+use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::field::packed::PackedField;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::{Field, PrimeField64};
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::timed;
+use plonky2::util::timing::TimingTree;
+
+use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::cross_table_lookup::{Column, Filter};
+use crate::evaluation_frame::{StarkEvaluationFrame, StarkFrame};
+use crate::lookup::Lookup;
+use crate::memory::segments::Segment;
+use crate::modexp::columns::*;
+use crate::modular::{
+    limbs_to_field, limbs_to_i64, pol_mul, pol_mul_ext_circuit, pol_sub, pol_sub_ext_circuit,
+    LIMB_BITS,
+};
+use crate::stark::Stark;
+use crate::util::trace_rows_to_poly_values;
+
+/// The memory accesses of a call. On its first row: the words of the base and of the modulus,
+/// as many as the mask has, then the number of words of the base and of the exponent. On the
+/// row of the last bit of each word of the exponent, that word. On its last row, the words of
+/// the result.
+const BASE_READS: Range<usize> = 0..MODEXP_WORDS;
+const MODULUS_READS: Range<usize> = MODEXP_WORDS..2 * MODEXP_WORDS;
+const LEN_READ: usize = 2 * MODEXP_WORDS;
+const EXP_LEN_READ: usize = LEN_READ + 1;
+const EXP_READ: usize = LEN_READ + 2;
+pub(crate) const NUM_MEMORY_CTLS: usize = 3 * MODEXP_WORDS + 3;
+
+/// Where the modulus is, after the numbers of words of the base and of the exponent.
+const MODULUS_OFFSET: usize = 8;
+
+/// The offset making the coefficients of `w` nonnegative, so that they fit in three bytes.
+const CARRY_OFFSET: i64 = 1 << 23;
+
+/// Strict upper bound of the range checks.
+const RANGE_MAX: usize = 1 << LIMB_BITS;
+
+pub(crate) fn ctl_looked_data<F: Field>() -> Vec<Column<F>> {
+    let cols = MODEXP_COL_MAP;
+    Column::singles([cols.base_ptr, cols.params_ptr, cols.timestamp]).collect()
+}
+
+pub(crate) fn ctl_looked_filter<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(MODEXP_COL_MAP.is_first))
+}
+
+/// The word `j` of an integer, from its limbs.
+fn limbs_word<F: Field>(limbs: &[usize; MODEXP_LIMBS], j: usize) -> Column<F> {
+    Column::linear_combination(
+        limbs[4 * j..4 * j + 4]
+            .iter()
+            .enumerate()
+            .map(|(k, &c)| (c, F::from_canonical_u64(1 << (LIMB_BITS * k)))),
+    )
+}
+
+pub(crate) fn ctl_looking_memory<F: Field>(i: usize) -> Vec<Column<F>> {
+    let cols = MODEXP_COL_MAP;
+    let read_timestamp = Column::single(cols.timestamp);
+
+    let (is_read, addr, value, timestamp) = if BASE_READS.contains(&i) {
+        let j = i - BASE_READS.start;
+        (
+            true,
+            Column::linear_combination_with_constant(
+                [(cols.base_ptr, F::ONE)],
+                F::from_canonical_usize(4 * j),
+            ),
+            limbs_word(&cols.base, j),
+            read_timestamp,
+        )
+    } else if MODULUS_READS.contains(&i) {
+        let j = i - MODULUS_READS.start;
+        (
+            true,
+            Column::linear_combination_with_constant(
+                [(cols.params_ptr, F::ONE)],
+                F::from_canonical_usize(MODULUS_OFFSET + 4 * j),
+            ),
+            limbs_word(&cols.modulus, j),
+            read_timestamp,
+        )
+    } else if i == LEN_READ {
+        (
+            true,
+            Column::single(cols.params_ptr),
+            Column::sum(cols.word_mask),
+            read_timestamp,
+        )
+    } else if i == EXP_LEN_READ {
+        (
+            true,
+            Column::linear_combination_with_constant(
+                [(cols.params_ptr, F::ONE)],
+                F::from_canonical_usize(4),
+            ),
+            Column::linear_combination_with_constant([(cols.exp_words_left, F::ONE)], F::ONE),
+            read_timestamp,
+        )
+    } else if i == EXP_READ {
+        (
+            true,
+            Column::single(cols.exp_ptr),
+            Column::single(cols.exp_acc),
+            read_timestamp,
+        )
+    } else {
+        let j = i - EXP_READ - 1;
+        (
+            false,
+            Column::linear_combination_with_constant(
+                [(cols.base_ptr, F::ONE)],
+                F::from_canonical_usize(4 * j),
+            ),
+            limbs_word(&cols.result, j),
+            Column::linear_combination_with_constant([(cols.timestamp, F::ONE)], F::ONE),
+        )
+    };
+    let res = vec![
+        Column::constant(F::from_bool(is_read)),
+        Column::zero(), // context
+        Column::constant(F::from_canonical_usize(Segment::Code as usize)),
+        addr,
+        value,
+        timestamp,
+    ];
+
+    assert_eq!(
+        res.len(),
+        crate::memory::memory_stark::ctl_data::<F>().len()
+    );
+    res
+}
+
+pub(crate) fn ctl_looking_memory_filter<F: Field>(i: usize) -> Filter<F> {
+    let cols = MODEXP_COL_MAP;
+    let masked = |row: usize, j: usize| {
+        Filter::new(
+            vec![(Column::single(row), Column::single(cols.word_mask[j]))],
+            vec![],
+        )
+    };
+    if BASE_READS.contains(&i) {
+        masked(cols.is_first, i - BASE_READS.start)
+    } else if MODULUS_READS.contains(&i) {
+        masked(cols.is_first, i - MODULUS_READS.start)
+    } else if i == LEN_READ || i == EXP_LEN_READ {
+        Filter::new_simple(Column::single(cols.is_first))
+    } else if i == EXP_READ {
+        Filter::new(
+            vec![
+                (
+                    Column::single(cols.is_word_start),
+                    Column::single(cols.is_word_last),
+                ),
+                (
+                    Column::single(cols.is_square),
+                    Column::single(cols.is_word_last),
+                ),
+            ],
+            vec![],
+        )
+    } else {
+        masked(cols.is_last, i - EXP_READ - 1)
+    }
+}
+
+/// Information about a `MODEXP` call.
+#[derive(Clone, Debug)]
+pub(crate) struct ModExpOp {
+    /// The address of the base.
+    pub(crate) base_ptr: usize,
+    /// The address of the number of words of the base, which the rest of the operands follow.
+    pub(crate) params_ptr: usize,
+    /// The timestamp of the CPU row making the call.
+    pub(crate) timestamp: usize,
+    /// The integers read, as little-endian words. The base and the modulus have the same number
+    /// of words.
+    pub(crate) base: Vec<u32>,
+    pub(crate) exp: Vec<u32>,
+    pub(crate) modulus: Vec<u32>,
+}
+
+impl ModExpOp {
+    /// A square for each bit of the exponent, and a multiplication for each set one.
+    pub(crate) fn num_rows(&self) -> usize {
+        let set_bits = self
+            .exp
+            .iter()
+            .map(|w| w.count_ones() as usize)
+            .sum::<usize>();
+        32 * self.exp.len() + set_bits
+    }
+}
+
+/// The limbs of an integer below `2^(8 MODEXP_LIMBS)`.
+fn uint_limbs(x: &BigUint) -> [u8; MODEXP_LIMBS] {
+    let mut limbs = [0u8; MODEXP_LIMBS];
+    for (limb, byte) in limbs.iter_mut().zip(x.to_bytes_le()) {
+        *limb = byte;
+    }
+    limbs
+}
+
+#[derive(Copy, Clone, Default)]
+pub struct ModExpStark<F, const D: usize> {
+    f: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> ModExpStark<F, D> {
+    pub(crate) fn generate_trace(
+        &self,
+        operations: Vec<ModExpOp>,
+        min_rows: usize,
+        timing: &mut TimingTree,
+    ) -> Vec<PolynomialValues<F>> {
+        let trace_rows = timed!(
+            timing,
+            "generate trace rows",
+            self.generate_trace_rows(operations, min_rows)
+        );
+        timed!(
+            timing,
+            "convert to PolynomialValues",
+            trace_rows_to_poly_values(trace_rows)
+        )
+    }
+
+    fn generate_trace_rows(
+        &self,
+        operations: Vec<ModExpOp>,
+        min_rows: usize,
+    ) -> Vec<[F; NUM_MODEXP_COLUMNS]> {
+        // The range checks need at least `RANGE_MAX` rows.
+        let num_rows = operations
+            .iter()
+            .map(ModExpOp::num_rows)
+            .sum::<usize>()
+            .max(min_rows)
+            .max(RANGE_MAX)
+            .next_power_of_two();
+        let mut rows = Vec::with_capacity(num_rows);
+        for op in operations {
+            rows.extend(self.generate_rows_for_op(op));
+        }
+        rows.resize(num_rows, [F::ZERO; NUM_MODEXP_COLUMNS]);
+        self.generate_range_checks(&mut rows);
+        rows
+    }
+
+    fn generate_range_checks(&self, rows: &mut [[F; NUM_MODEXP_COLUMNS]]) {
+        let cols = MODEXP_COL_MAP;
+        let mut frequencies = vec![0usize; RANGE_MAX];
+        for row in rows.iter() {
+            for c in cols.bytes() {
+                let x = row[c].to_canonical_u64() as usize;
+                assert!(
+                    x < RANGE_MAX,
+                    "column value {} exceeds the max range value {}",
+                    x,
+                    RANGE_MAX
+                );
+                frequencies[x] += 1;
+            }
+        }
+        for (i, row) in rows.iter_mut().enumerate() {
+            row[cols.range_counter] = F::from_canonical_usize(i.min(RANGE_MAX - 1));
+            row[cols.range_frequencies] =
+                F::from_canonical_usize(frequencies.get(i).copied().unwrap_or(0));
+        }
+    }
+
+    fn generate_rows_for_op(&self, op: ModExpOp) -> Vec<[F; NUM_MODEXP_COLUMNS]> {
+        let len = op.modulus.len();
+        let mut template = ModExpColumnsView::default();
+        template.timestamp = F::from_canonical_usize(op.timestamp);
+        template.base_ptr = F::from_canonical_usize(op.base_ptr);
+        template.params_ptr = F::from_canonical_usize(op.params_ptr);
+        for mask in &mut template.word_mask[..len] {
+            *mask = F::ONE;
+        }
+        let base = BigUint::from_slice(&op.base);
+        let modulus = BigUint::from_slice(&op.modulus);
+        let n = uint_limbs(&modulus);
+        template.base = limbs_to_field(&uint_limbs(&base));
+        template.modulus = limbs_to_field(&n);
+
+        let num_rows = op.num_rows();
+        let mut rows = Vec::with_capacity(num_rows);
+        let mut acc = BigUint::from(1u32);
+        for k in (0..op.exp.len()).rev() {
+            let word = op.exp[k];
+            for j in 0..32 {
+                let bit = (word >> (31 - j)) & 1;
+                for is_multiply in [false, true] {
+                    if is_multiply && bit == 0 {
+                        continue;
+                    }
+                    let mut row = template;
+                    if is_multiply {
+                        row.is_multiply = F::ONE;
+                    } else if j == 0 {
+                        row.is_word_start = F::ONE;
+                    } else {
+                        row.is_square = F::ONE;
+                    }
+                    row.is_first = F::from_bool(rows.is_empty());
+                    row.is_last = F::from_bool(rows.len() == num_rows - 1);
+                    row.bit = F::from_canonical_u32(bit);
+                    row.exp_acc = F::from_canonical_u32(word >> (31 - j));
+                    row.bits_left = F::from_canonical_usize(31 - j);
+                    row.is_word_last = F::from_bool(j == 31);
+                    row.exp_ptr =
+                        F::from_canonical_usize(op.params_ptr + MODULUS_OFFSET + 4 * (len + k));
+                    row.exp_words_left = F::from_canonical_usize(k);
+
+                    let y = if is_multiply { &base } else { &acc };
+                    let product = &acc * y;
+                    let result = &product % &modulus;
+                    let quotient = (&product - &result) / &modulus;
+                    let (x, y, q, r) = (
+                        uint_limbs(&acc),
+                        uint_limbs(y),
+                        uint_limbs(&quotient),
+                        uint_limbs(&result),
+                    );
+                    row.x = limbs_to_field(&x);
+                    row.y = limbs_to_field(&y);
+                    row.quotient = limbs_to_field(&q);
+                    row.result = limbs_to_field(&r);
+                    generate_carries(&mut row, &x, &y, &q, &n, &r);
+                    generate_diff(&mut row, &n, &r);
+
+                    rows.push(row.into());
+                    acc = result;
+                }
+            }
+        }
+        rows
+    }
+}
+
+/// Fills in `w` from `c(X) = x(X) y(X) - q(X) n(X) - r(X) = (X - 2^8) w(X)`, so that
+/// `c[i] = w[i - 1] - 2^8 w[i]`.
+fn generate_carries<F: PrimeField64>(
+    row: &mut ModExpColumnsView<F>,
+    x: &[u8; MODEXP_LIMBS],
+    y: &[u8; MODEXP_LIMBS],
+    q: &[u8; QUOTIENT_LIMBS],
+    n: &[u8; MODEXP_LIMBS],
+    r: &[u8; MODEXP_LIMBS],
+) {
+    let (x, y, q, n, r) = (
+        limbs_to_i64(x),
+        limbs_to_i64(y),
+        limbs_to_i64(q),
+        limbs_to_i64(n),
+        limbs_to_i64(r),
+    );
+    let c = pol_sub(&pol_sub(&pol_mul(&x, &y), &pol_mul(&q, &n)), &r);
+    debug_assert_eq!(c.len(), N_CARRIES + 1);
+
+    let mut w = 0i64;
+    for i in 0..N_CARRIES {
+        let num = w - c[i];
+        debug_assert_eq!(num % (1 << LIMB_BITS), 0);
+        w = num >> LIMB_BITS;
+        let carry = w + CARRY_OFFSET;
+        assert!((0..1 << 24).contains(&carry), "carry out of range");
+        row.carry_lo[i] = F::from_canonical_u64(carry as u64 & 0xff);
+        row.carry_mid[i] = F::from_canonical_u64((carry as u64 >> LIMB_BITS) & 0xff);
+        row.carry_hi[i] = F::from_canonical_u64(carry as u64 >> (2 * LIMB_BITS));
+    }
+    debug_assert_eq!(c[N_CARRIES], w);
+}
+
+/// Fills in `n - 1 - r` and the carries of `r + (n - 1 - r) + 1 = n`.
+fn generate_diff<F: PrimeField64>(
+    row: &mut ModExpColumnsView<F>,
+    n: &[u8; MODEXP_LIMBS],
+    r: &[u8; MODEXP_LIMBS],
+) {
+    let diff = BigUint::from_bytes_le(n) - BigUint::from_bytes_le(r) - 1u32;
+    let d = uint_limbs(&diff);
+    row.diff = limbs_to_field(&d);
+
+    let mut carry = 1;
+    for i in 0..MODEXP_LIMBS - 1 {
+        carry = (r[i] as u64 + d[i] as u64 + carry - n[i] as u64) >> LIMB_BITS;
+        row.diff_carry[i] = F::from_canonical_u64(carry);
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for ModExpStark<F, D> {
+    type EvaluationFrame<FE, P, const D2: usize>
+        = StarkFrame<P, NUM_MODEXP_COLUMNS>
+    where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>;
+
+    type EvaluationFrameTarget = StarkFrame<ExtensionTarget<D>, NUM_MODEXP_COLUMNS>;
+
+    fn eval_packed_generic<FE, P, const D2: usize>(
+        &self,
+        vars: &Self::EvaluationFrame<FE, P, D2>,
+        yield_constr: &mut ConstraintConsumer<P>,
+    ) where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>,
+    {
+        let local_values: &[P; NUM_MODEXP_COLUMNS] = vars.get_local_values().try_into().unwrap();
+        let lv: &ModExpColumnsView<P> = local_values.borrow();
+        let next_values: &[P; NUM_MODEXP_COLUMNS] = vars.get_next_values().try_into().unwrap();
+        let nv: &ModExpColumnsView<P> = next_values.borrow();
+
+        // The range counter starts at 0, increments by 0 or 1, and ends at `RANGE_MAX - 1`.
+        yield_constr.constraint_first_row(lv.range_counter);
+        let incr = nv.range_counter - lv.range_counter;
+        yield_constr.constraint_transition(incr * incr - incr);
+        let range_max = P::Scalar::from_canonical_u64((RANGE_MAX - 1) as u64);
+        yield_constr.constraint_last_row(lv.range_counter - range_max);
+
+        let is_real = lv.is_word_start + lv.is_square + lv.is_multiply;
+        let next_is_real = nv.is_word_start + nv.is_square + nv.is_multiply;
+        for flag in [
+            lv.is_word_start,
+            lv.is_square,
+            lv.is_multiply,
+            is_real,
+            lv.is_first,
+            lv.is_last,
+            lv.bit,
+            lv.is_word_last,
+        ]
+        .into_iter()
+        .chain(lv.word_mask)
+        {
+            yield_constr.constraint(flag * (flag - P::ONES));
+        }
+        yield_constr.constraint(lv.is_first * (P::ONES - is_real));
+        yield_constr.constraint(lv.is_last * (P::ONES - is_real));
+
+        // Calls are contiguous: a row that is not the last of its call is followed by the next
+        // one, and a row that is starts the next call, if any.
+        let is_continued = is_real - lv.is_last;
+        yield_constr.constraint_first_row(lv.is_first - is_real);
+        yield_constr.constraint_last_row(is_continued);
+        yield_constr.constraint_transition(is_continued * (P::ONES - next_is_real));
+        yield_constr.constraint_transition(is_continued * nv.is_first);
+        yield_constr.constraint_transition((P::ONES - is_continued) * (nv.is_first - next_is_real));
+
+        // The operands are the same on all the rows of a call.
+        for (&next, &local) in [nv.timestamp, nv.base_ptr, nv.params_ptr]
+            .iter()
+            .chain(&nv.word_mask)
+            .chain(&nv.base)
+            .chain(&nv.modulus)
+            .zip(
+                [lv.timestamp, lv.base_ptr, lv.params_ptr]
+                    .iter()
+                    .chain(&lv.word_mask)
+                    .chain(&lv.base)
+                    .chain(&lv.modulus),
+            )
+        {
+            yield_constr.constraint_transition(is_continued * (next - local));
+        }
+
+        // The words of the base and of the modulus are those of the mask, with zeros above.
+        yield_constr.constraint(is_real * (P::ONES - lv.word_mask[0]));
+        for (&mask, &prev) in lv.word_mask[1..].iter().zip(&lv.word_mask) {
+            yield_constr.constraint(mask * (P::ONES - prev));
+        }
+        for i in 0..MODEXP_LIMBS {
+            let padding = P::ONES - lv.word_mask[i / 4];
+            yield_constr.constraint(padding * lv.base[i]);
+            yield_constr.constraint(padding * lv.modulus[i]);
+        }
+
+        // A square for each bit, which a multiplication by the base follows iff it is set.
+        let is_square = lv.is_word_start + lv.is_square;
+        yield_constr.constraint(lv.is_first * (P::ONES - lv.is_word_start));
+        yield_constr.constraint(lv.is_last * is_square * lv.bit);
+        yield_constr.constraint_transition(is_square * lv.bit * (P::ONES - nv.is_multiply));
+        yield_constr.constraint_transition(nv.is_multiply * (P::ONES - lv.bit));
+        yield_constr.constraint_transition(nv.is_multiply * lv.is_multiply);
+
+        // The accumulator starts at 1, then is the result of the previous row, and is squared or
+        // multiplied by the base.
+        for i in 0..MODEXP_LIMBS {
+            let one = if i == 0 { P::ONES } else { P::ZEROS };
+            yield_constr.constraint(lv.is_first * (lv.x[i] - one));
+            yield_constr.constraint_transition(is_continued * (nv.x[i] - lv.result[i]));
+            yield_constr.constraint(is_square * (lv.y[i] - lv.x[i]));
+            yield_constr.constraint(lv.is_multiply * (lv.y[i] - lv.base[i]));
+        }
+
+        // The bits of a word, from the most significant one, then the words from the most
+        // significant one, which the first row points to.
+        let four = P::Scalar::from_canonical_u64(4);
+        yield_constr.constraint(lv.is_word_last * lv.bits_left);
+        yield_constr
+            .constraint(lv.is_word_start * (lv.bits_left - P::Scalar::from_canonical_u64(31)));
+        yield_constr.constraint(lv.is_word_start * (lv.exp_acc - lv.bit));
+        let len = lv.word_mask.iter().copied().sum::<P>();
+        let exp_ptr = lv.params_ptr
+            + P::Scalar::from_canonical_usize(MODULUS_OFFSET)
+            + (len + lv.exp_words_left) * four;
+        yield_constr.constraint(lv.is_first * (lv.exp_ptr - exp_ptr));
+        yield_constr.constraint(lv.is_last * lv.exp_words_left);
+        yield_constr.constraint(lv.is_last * (P::ONES - lv.is_word_last));
+
+        let next_word_start = is_continued * nv.is_word_start;
+        yield_constr.constraint_transition(next_word_start * (P::ONES - lv.is_word_last));
+        yield_constr.constraint_transition(next_word_start * (nv.exp_ptr - lv.exp_ptr + four));
+        yield_constr.constraint_transition(
+            next_word_start * (nv.exp_words_left - lv.exp_words_left + P::ONES),
+        );
+        yield_constr.constraint_transition(is_continued * nv.is_square * lv.is_word_last);
+        yield_constr.constraint_transition(nv.is_square * (nv.bits_left - lv.bits_left + P::ONES));
+        yield_constr.constraint_transition(
+            nv.is_square * (nv.exp_acc - lv.exp_acc * P::Scalar::TWO - nv.bit),
+        );
+        for (next, local) in [
+            (nv.exp_ptr, lv.exp_ptr),
+            (nv.exp_words_left, lv.exp_words_left),
+        ] {
+            yield_constr.constraint_transition(nv.is_square * (next - local));
+        }
+        for (next, local) in [
+            (nv.bit, lv.bit),
+            (nv.exp_acc, lv.exp_acc),
+            (nv.bits_left, lv.bits_left),
+            (nv.is_word_last, lv.is_word_last),
+            (nv.exp_ptr, lv.exp_ptr),
+            (nv.exp_words_left, lv.exp_words_left),
+        ] {
+            yield_constr.constraint_transition(nv.is_multiply * (next - local));
+        }
+
+        // x(X) y(X) - q(X) n(X) - r(X) = (X - 2^8) w(X).
+        let base = P::Scalar::from_canonical_u64(1 << LIMB_BITS);
+        let carry_offset = P::Scalar::from_canonical_u64(CARRY_OFFSET as u64);
+        let w: [P; N_CARRIES] = core::array::from_fn(|i| {
+            lv.carry_lo[i] + (lv.carry_mid[i] + lv.carry_hi[i] * base) * base - carry_offset
+        });
+        let c = pol_sub(
+            &pol_sub(&pol_mul(&lv.x, &lv.y), &pol_mul(&lv.quotient, &lv.modulus)),
+            &lv.result,
+        );
+        for (i, &c) in c.iter().enumerate() {
+            let mut rhs = P::ZEROS;
+            if i > 0 {
+                rhs += w[i - 1];
+            }
+            if i < N_CARRIES {
+                rhs -= w[i] * base;
+            }
+            yield_constr.constraint(is_real * (c - rhs));
+        }
+
+        // r + (n - 1 - r) + 1 = n, limb by limb.
+        for &bit in lv.diff_carry.iter() {
+            yield_constr.constraint(bit * (bit - P::ONES));
+        }
+        for i in 0..MODEXP_LIMBS {
+            let mut sum = lv.result[i] + lv.diff[i] - lv.modulus[i];
+            sum += if i > 0 { lv.diff_carry[i - 1] } else { P::ONES };
+            if i < MODEXP_LIMBS - 1 {
+                sum -= lv.diff_carry[i] * base;
+            }
+            yield_constr.constraint(is_real * sum);
+        }
+    }
+
+    fn eval_ext_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: &Self::EvaluationFrameTarget,
+        yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+    ) {
+        let local_values: &[ExtensionTarget<D>; NUM_MODEXP_COLUMNS] =
+            vars.get_local_values().try_into().unwrap();
+        let lv: &ModExpColumnsView<ExtensionTarget<D>> = local_values.borrow();
+        let next_values: &[ExtensionTarget<D>; NUM_MODEXP_COLUMNS] =
+            vars.get_next_values().try_into().unwrap();
+        let nv: &ModExpColumnsView<ExtensionTarget<D>> = next_values.borrow();
+
+        yield_constr.constraint_first_row(builder, lv.range_counter);
+        let incr = builder.sub_extension(nv.range_counter, lv.range_counter);
+        let constraint = builder.mul_sub_extension(incr, incr, incr);
+        yield_constr.constraint_transition(builder, constraint);
+        let range_max =
+            builder.constant_extension(F::Extension::from_canonical_usize(RANGE_MAX - 1));
+        let constraint = builder.sub_extension(lv.range_counter, range_max);
+        yield_constr.constraint_last_row(builder, constraint);
+
+        let one = builder.one_extension();
+        let is_real = builder.add_many_extension([lv.is_word_start, lv.is_square, lv.is_multiply]);
+        let next_is_real =
+            builder.add_many_extension([nv.is_word_start, nv.is_square, nv.is_multiply]);
+        for flag in [
+            lv.is_word_start,
+            lv.is_square,
+            lv.is_multiply,
+            is_real,
+            lv.is_first,
+            lv.is_last,
+            lv.bit,
+            lv.is_word_last,
+        ]
+        .into_iter()
+        .chain(lv.word_mask)
+        {
+            let constraint = builder.mul_sub_extension(flag, flag, flag);
+            yield_constr.constraint(builder, constraint);
+        }
+        let not_real = builder.sub_extension(one, is_real);
+        for flag in [lv.is_first, lv.is_last] {
+            let constraint = builder.mul_extension(flag, not_real);
+            yield_constr.constraint(builder, constraint);
+        }
+
+        let is_continued = builder.sub_extension(is_real, lv.is_last);
+        let constraint = builder.sub_extension(lv.is_first, is_real);
+        yield_constr.constraint_first_row(builder, constraint);
+        yield_constr.constraint_last_row(builder, is_continued);
+        let constraint = builder.mul_sub_extension(is_continued, next_is_real, is_continued);
+        yield_constr.constraint_transition(builder, constraint);
+        let constraint = builder.mul_extension(is_continued, nv.is_first);
+        yield_constr.constraint_transition(builder, constraint);
+        let not_continued = builder.sub_extension(one, is_continued);
+        let diff = builder.sub_extension(nv.is_first, next_is_real);
+        let constraint = builder.mul_extension(not_continued, diff);
+        yield_constr.constraint_transition(builder, constraint);
+
+        for (&next, &local) in [nv.timestamp, nv.base_ptr, nv.params_ptr]
+            .iter()
+            .chain(&nv.word_mask)
+            .chain(&nv.base)
+            .chain(&nv.modulus)
+            .zip(
+                [lv.timestamp, lv.base_ptr, lv.params_ptr]
+                    .iter()
+                    .chain(&lv.word_mask)
+                    .chain(&lv.base)
+                    .chain(&lv.modulus),
+            )
+        {
+            let diff = builder.sub_extension(next, local);
+            let constraint = builder.mul_extension(is_continued, diff);
+            yield_constr.constraint_transition(builder, constraint);
+        }
+
+        let constraint = builder.mul_sub_extension(is_real, lv.word_mask[0], is_real);
+        yield_constr.constraint(builder, constraint);
+        for (&mask, &prev) in lv.word_mask[1..].iter().zip(&lv.word_mask) {
+            let constraint = builder.mul_sub_extension(mask, prev, mask);
+            yield_constr.constraint(builder, constraint);
+        }
+        for i in 0..MODEXP_LIMBS {
+            let mask = lv.word_mask[i / 4];
+            for limb in [lv.base[i], lv.modulus[i]] {
+                let constraint = builder.mul_sub_extension(mask, limb, limb);
+                yield_constr.constraint(builder, constraint);
+            }
+        }
+
+        let is_square = builder.add_extension(lv.is_word_start, lv.is_square);
+        let constraint = builder.mul_sub_extension(lv.is_first, lv.is_word_start, lv.is_first);
+        yield_constr.constraint(builder, constraint);
+        let square_bit = builder.mul_extension(is_square, lv.bit);
+        let constraint = builder.mul_extension(lv.is_last, square_bit);
+        yield_constr.constraint(builder, constraint);
+        let constraint = builder.mul_sub_extension(square_bit, nv.is_multiply, square_bit);
+        yield_constr.constraint_transition(builder, constraint);
+        let constraint = builder.mul_sub_extension(nv.is_multiply, lv.bit, nv.is_multiply);
+        yield_constr.constraint_transition(builder, constraint);
+        let constraint = builder.mul_extension(nv.is_multiply, lv.is_multiply);
+        yield_constr.constraint_transition(builder, constraint);
+
+        for i in 0..MODEXP_LIMBS {
+            let x = if i == 0 {
+                builder.sub_extension(lv.x[i], one)
+            } else {
+                lv.x[i]
+            };
+            let constraint = builder.mul_extension(lv.is_first, x);
+            yield_constr.constraint(builder, constraint);
+            let diff = builder.sub_extension(nv.x[i], lv.result[i]);
+            let constraint = builder.mul_extension(is_continued, diff);
+            yield_constr.constraint_transition(builder, constraint);
+            let diff = builder.sub_extension(lv.y[i], lv.x[i]);
+            let constraint = builder.mul_extension(is_square, diff);
+            yield_constr.constraint(builder, constraint);
+            let diff = builder.sub_extension(lv.y[i], lv.base[i]);
+            let constraint = builder.mul_extension(lv.is_multiply, diff);
+            yield_constr.constraint(builder, constraint);
+        }
+
+        let four = F::from_canonical_u64(4);
+        let constraint = builder.mul_extension(lv.is_word_last, lv.bits_left);
+        yield_constr.constraint(builder, constraint);
+        let thirty_one = builder.constant_extension(F::Extension::from_canonical_u64(31));
+        let diff = builder.sub_extension(lv.bits_left, thirty_one);
+        let constraint = builder.mul_extension(lv.is_word_start, diff);
+        yield_constr.constraint(builder, constraint);
+        let diff = builder.sub_extension(lv.exp_acc, lv.bit);
+        let constraint = builder.mul_extension(lv.is_word_start, diff);
+        yield_constr.constraint(builder, constraint);
+        let len = builder.add_many_extension(lv.word_mask);
+        let words = builder.add_extension(len, lv.exp_words_left);
+        let exp_ptr = builder.mul_const_add_extension(four, words, lv.params_ptr);
+        let modulus_offset =
+            builder.constant_extension(F::Extension::from_canonical_usize(MODULUS_OFFSET));
+        let exp_ptr = builder.add_extension(exp_ptr, modulus_offset);
+        let diff = builder.sub_extension(lv.exp_ptr, exp_ptr);
+        let constraint = builder.mul_extension(lv.is_first, diff);
+        yield_constr.constraint(builder, constraint);
+        let constraint = builder.mul_extension(lv.is_last, lv.exp_words_left);
+        yield_constr.constraint(builder, constraint);
+        let constraint = builder.mul_sub_extension(lv.is_last, lv.is_word_last, lv.is_last);
+        yield_constr.constraint(builder, constraint);
+
+        let next_word_start = builder.mul_extension(is_continued, nv.is_word_start);
+        let constraint =
+            builder.mul_sub_extension(next_word_start, lv.is_word_last, next_word_start);
+        yield_constr.constraint_transition(builder, constraint);
+        let diff = builder.sub_extension(nv.exp_ptr, lv.exp_ptr);
+        let four_ext = builder.constant_extension(F::Extension::from_canonical_u64(4));
+        let diff = builder.add_extension(diff, four_ext);
+        let constraint = builder.mul_extension(next_word_start, diff);
+        yield_constr.constraint_transition(builder, constraint);
+        let diff = builder.sub_extension(nv.exp_words_left, lv.exp_words_left);
+        let diff = builder.add_extension(diff, one);
+        let constraint = builder.mul_extension(next_word_start, diff);
+        yield_constr.constraint_transition(builder, constraint);
+        let next_square = builder.mul_extension(is_continued, nv.is_square);
+        let constraint = builder.mul_extension(next_square, lv.is_word_last);
+        yield_constr.constraint_transition(builder, constraint);
+        let diff = builder.sub_extension(nv.bits_left, lv.bits_left);
+        let diff = builder.add_extension(diff, one);
+        let constraint = builder.mul_extension(nv.is_square, diff);
+        yield_constr.constraint_transition(builder, constraint);
+        let exp_acc = builder.mul_const_add_extension(F::TWO, lv.exp_acc, nv.bit);
+        let diff = builder.sub_extension(nv.exp_acc, exp_acc);
+        let constraint = builder.mul_extension(nv.is_square, diff);
+        yield_constr.constraint_transition(builder, constraint);
+        for (next, local) in [
+            (nv.exp_ptr, lv.exp_ptr),
+            (nv.exp_words_left, lv.exp_words_left),
+        ] {
+            let diff = builder.sub_extension(next, local);
+            let constraint = builder.mul_extension(nv.is_square, diff);
+            yield_constr.constraint_transition(builder, constraint);
+        }
+        for (next, local) in [
+            (nv.bit, lv.bit),
+            (nv.exp_acc, lv.exp_acc),
+            (nv.bits_left, lv.bits_left),
+            (nv.is_word_last, lv.is_word_last),
+            (nv.exp_ptr, lv.exp_ptr),
+            (nv.exp_words_left, lv.exp_words_left),
+        ] {
+            let diff = builder.sub_extension(next, local);
+            let constraint = builder.mul_extension(nv.is_multiply, diff);
+            yield_constr.constraint_transition(builder, constraint);
+        }
+
+        let base = F::from_canonical_u64(1 << LIMB_BITS);
+        let carry_offset =
+            builder.constant_extension(F::Extension::from_canonical_u64(CARRY_OFFSET as u64));
+        let w: [ExtensionTarget<D>; N_CARRIES] = core::array::from_fn(|i| {
+            let high = builder.mul_const_add_extension(base, lv.carry_hi[i], lv.carry_mid[i]);
+            let carry = builder.mul_const_add_extension(base, high, lv.carry_lo[i]);
+            builder.sub_extension(carry, carry_offset)
+        });
+        let xy = pol_mul_ext_circuit(builder, &lv.x, &lv.y);
+        let qn = pol_mul_ext_circuit(builder, &lv.quotient, &lv.modulus);
+        let c = pol_sub_ext_circuit(builder, &xy, &qn);
+        let c = pol_sub_ext_circuit(builder, &c, &lv.result);
+        for (i, &c) in c.iter().enumerate() {
+            let mut rhs = builder.zero_extension();
+            if i > 0 {
+                rhs = builder.add_extension(rhs, w[i - 1]);
+            }
+            if i < N_CARRIES {
+                rhs = builder.mul_const_add_extension(-base, w[i], rhs);
+            }
+            let diff = builder.sub_extension(c, rhs);
+            let constraint = builder.mul_extension(is_real, diff);
+            yield_constr.constraint(builder, constraint);
+        }
+
+        for &bit in lv.diff_carry.iter() {
+            let constraint = builder.mul_sub_extension(bit, bit, bit);
+            yield_constr.constraint(builder, constraint);
+        }
+        for i in 0..MODEXP_LIMBS {
+            let mut sum = builder.add_extension(lv.result[i], lv.diff[i]);
+            sum = builder.sub_extension(sum, lv.modulus[i]);
+            let carry_in = if i > 0 { lv.diff_carry[i - 1] } else { one };
+            sum = builder.add_extension(sum, carry_in);
+            if i < MODEXP_LIMBS - 1 {
+                sum = builder.mul_const_add_extension(-base, lv.diff_carry[i], sum);
+            }
+            let constraint = builder.mul_extension(is_real, sum);
+            yield_constr.constraint(builder, constraint);
+        }
+    }
+
+    fn constraint_degree(&self) -> usize {
+        3
+    }
+
+    fn lookups(&self) -> Vec<Lookup<F>> {
+        let cols = MODEXP_COL_MAP;
+        let columns = Column::singles(cols.bytes()).collect_vec();
+        let filter_columns = vec![None; columns.len()];
+        vec![Lookup {
+            columns,
+            table_column: Column::single(cols.range_counter),
+            frequencies_column: Column::single(cols.range_frequencies),
+            filter_columns,
+        }]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Borrow;
+
+    use anyhow::Result;
+    use num_bigint::BigUint;
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::types::PrimeField64;
+    use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    use crate::modexp::columns::ModExpColumnsView;
+    use crate::modexp::modexp_stark::{ModExpOp, ModExpStark};
+    use crate::stark_testing::{test_stark_circuit_constraints, test_stark_low_degree};
+
+    #[test]
+    fn test_stark_degree() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = ModExpStark<F, D>;
+
+        let stark = S::default();
+        test_stark_low_degree(stark)
+    }
+
+    #[test]
+    fn test_stark_circuit() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = ModExpStark<F, D>;
+
+        let stark = S::default();
+        test_stark_circuit_constraints::<F, C, S, D>(stark)
+    }
+
+    #[test]
+    fn test_generation() -> Result<()> {
+        const D: usize = 2;
+        type F = GoldilocksField;
+        type S = ModExpStark<F, D>;
+
+        // 0x0123456789abcdef^65537 modulo 2^64 - 59, the RSA public exponent: sixteen squares
+        // of 1 and a multiplication, then sixteen squares and a multiplication.
+        let op = ModExpOp {
+            base_ptr: 0x1000,
+            params_ptr: 0x2000,
+            timestamp: 0,
+            base: vec![0x89abcdef, 0x01234567],
+            exp: vec![65537],
+            modulus: vec![0xffffffc5, 0xffffffff],
+        };
+        assert_eq!(op.num_rows(), 34);
+
+        let stark = S::default();
+        let rows = stark.generate_rows_for_op(op);
+        assert_eq!(rows.len(), 34);
+        let row: &ModExpColumnsView<F> = rows[33].borrow();
+        assert_eq!(row.is_last.to_canonical_u64(), 1);
+        let limbs = row.result.map(|limb| limb.to_canonical_u64() as u8);
+        assert_eq!(
+            BigUint::from_bytes_le(&limbs),
+            BigUint::from(0xd3097250901a4710u64)
+        );
+        Ok(())
+    }
+}
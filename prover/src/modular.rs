@@ -0,0 +1,450 @@
+//! Constraints checking identities between integers modulo a prime, for the tables of the
+//! elliptic curve and field precompiles. Integers have `N_LIMBS` limbs, for primes of at most 256
+//! bits, unless the columns and the modulus are given more.
+//!
+//! Integers are written in little-endian byte limbs, the coefficients of a polynomial `a(x)` with
+//! `a(2^8) = a`. An expression `e(x)` of such polynomials, of degree at most two, evaluates to a
+//! multiple of `p` iff there is a quotient `q` with
+//!
+//! ```text
+//! e(x) = q(x) p(x) + (x - 2^8) w(x)
+//! ```
+//!
+//! The tables supply `q` and `w`, and range check their limbs along with those of the integers
+//! in `e`. This bounds the coefficients of both sides well below the order of the field, so the
+//! identity holds over the integers, and at `x = 2^8`.
+
+use std::ops::{Add, Mul, Sub};
+
+use num::bigint::{BigInt, Sign};
+use num::Zero;
+use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::field::packed::PackedField;
+use plonky2::field::types::{Field, PrimeField64};
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+
+use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+
+pub(crate) const LIMB_BITS: usize = 8;
+/// The number of limbs of an integer below the modulus.
+pub(crate) const N_LIMBS: usize = 32;
+/// The quotient is offset by `2^262` to be nonnegative, which fits `e` with `|e| < 2^262 p`. With
+/// `n` limbs, the offset is `2^(8 n + 6)`.
+pub(crate) const N_QUOTIENT_LIMBS: usize = N_LIMBS + 1;
+const QUOTIENT_OFFSET_EXTRA_BITS: usize = 6;
+/// `q(x) p(x)` has `2 N_LIMBS` coefficients, so `w(x)` has one less.
+pub(crate) const N_CARRIES: usize = 2 * N_LIMBS - 1;
+/// The offset making the coefficients of `w` nonnegative, so that they fit in two bytes. This
+/// holds while those of `e - q p` are below `255 * 2^15` in absolute value.
+pub(crate) const CARRY_OFFSET: i64 = 1 << 15;
+
+/// The witness of an identity `e(x) = (q(x) - 2^262) p(x) + (x - 2^8) w(x)`. For integers of `n`
+/// limbs, `Q = n + 1` and `W = 2 n - 1`.
+#[repr(C)]
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub(crate) struct IdentityColumns<
+    T: Copy,
+    const Q: usize = N_QUOTIENT_LIMBS,
+    const W: usize = N_CARRIES,
+> {
+    /// The limbs of `q`.
+    pub quotient: [T; Q],
+    /// The low and high bytes of the coefficients of `w`, plus `2^15`.
+    pub carry_lo: [T; W],
+    pub carry_hi: [T; W],
+}
+
+impl<T: Copy, const Q: usize, const W: usize> IdentityColumns<T, Q, W> {
+    /// The columns to range check in bytes.
+    pub(crate) fn bytes(&self) -> impl Iterator<Item = T> + '_ {
+        self.quotient
+            .iter()
+            .chain(&self.carry_lo)
+            .chain(&self.carry_hi)
+            .copied()
+    }
+}
+
+/// The witness of `a < p`, as the limb-wise addition `a + (p - 1 - a) = p - 1`. For integers of
+/// `n` limbs, `N = n` and `C = n - 1`.
+#[repr(C)]
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub(crate) struct ReducedColumns<
+    T: Copy,
+    const N: usize = N_LIMBS,
+    const C: usize = { N_LIMBS - 1 },
+> {
+    /// The limbs of `p - 1 - a`.
+    pub diff: [T; N],
+    /// The carries of the additions of the limbs, as bits.
+    pub carry: [T; C],
+}
+
+impl<T: Copy, const N: usize, const C: usize> ReducedColumns<T, N, C> {
+    /// The columns to range check in bytes.
+    pub(crate) fn bytes(&self) -> impl Iterator<Item = T> + '_ {
+        self.diff.iter().copied()
+    }
+}
+
+pub(crate) fn limbs_to_field<F: Field, const N: usize>(limbs: &[u8; N]) -> [F; N] {
+    limbs.map(F::from_canonical_u8)
+}
+
+/// The limbs as coefficients of an `e` of `generate_identity`.
+pub(crate) fn limbs_to_i64<const N: usize>(limbs: &[u8; N]) -> Vec<i64> {
+    limbs.iter().map(|&l| l as i64).collect()
+}
+
+pub(crate) fn pol_add<T>(a: &[T], b: &[T]) -> Vec<T>
+where
+    T: Add<Output = T> + Copy + Default,
+{
+    let mut sum = vec![T::default(); a.len().max(b.len())];
+    for (i, s) in sum.iter_mut().enumerate() {
+        let a = a.get(i).copied().unwrap_or_default();
+        let b = b.get(i).copied().unwrap_or_default();
+        *s = a + b;
+    }
+    sum
+}
+
+pub(crate) fn pol_sub<T>(a: &[T], b: &[T]) -> Vec<T>
+where
+    T: Sub<Output = T> + Copy + Default,
+{
+    let mut diff = vec![T::default(); a.len().max(b.len())];
+    for (i, d) in diff.iter_mut().enumerate() {
+        let a = a.get(i).copied().unwrap_or_default();
+        let b = b.get(i).copied().unwrap_or_default();
+        *d = a - b;
+    }
+    diff
+}
+
+pub(crate) fn pol_scale<T>(a: &[T], c: T) -> Vec<T>
+where
+    T: Mul<Output = T> + Copy,
+{
+    a.iter().map(|&x| x * c).collect()
+}
+
+pub(crate) fn pol_mul<T>(a: &[T], b: &[T]) -> Vec<T>
+where
+    T: Add<Output = T> + Mul<Output = T> + Copy + Default,
+{
+    let mut prod = vec![T::default(); a.len() + b.len() - 1];
+    for (i, &x) in a.iter().enumerate() {
+        for (j, &y) in b.iter().enumerate() {
+            prod[i + j] = prod[i + j] + x * y;
+        }
+    }
+    prod
+}
+
+pub(crate) fn pol_add_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    a: &[ExtensionTarget<D>],
+    b: &[ExtensionTarget<D>],
+) -> Vec<ExtensionTarget<D>> {
+    let zero = builder.zero_extension();
+    (0..a.len().max(b.len()))
+        .map(|i| {
+            let a = a.get(i).copied().unwrap_or(zero);
+            let b = b.get(i).copied().unwrap_or(zero);
+            builder.add_extension(a, b)
+        })
+        .collect()
+}
+
+pub(crate) fn pol_sub_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    a: &[ExtensionTarget<D>],
+    b: &[ExtensionTarget<D>],
+) -> Vec<ExtensionTarget<D>> {
+    let zero = builder.zero_extension();
+    (0..a.len().max(b.len()))
+        .map(|i| {
+            let a = a.get(i).copied().unwrap_or(zero);
+            let b = b.get(i).copied().unwrap_or(zero);
+            builder.sub_extension(a, b)
+        })
+        .collect()
+}
+
+pub(crate) fn pol_scale_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    a: &[ExtensionTarget<D>],
+    c: F,
+) -> Vec<ExtensionTarget<D>> {
+    a.iter()
+        .map(|&x| builder.mul_const_extension(c, x))
+        .collect()
+}
+
+pub(crate) fn pol_mul_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    a: &[ExtensionTarget<D>],
+    b: &[ExtensionTarget<D>],
+) -> Vec<ExtensionTarget<D>> {
+    let zero = builder.zero_extension();
+    let mut prod = vec![zero; a.len() + b.len() - 1];
+    for (i, &x) in a.iter().enumerate() {
+        for (j, &y) in b.iter().enumerate() {
+            prod[i + j] = builder.mul_add_extension(x, y, prod[i + j]);
+        }
+    }
+    prod
+}
+
+/// Multiplies `b` by the polynomial of constant coefficients `a`.
+pub(crate) fn pol_mul_const_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    a: &[F],
+    b: &[ExtensionTarget<D>],
+) -> Vec<ExtensionTarget<D>> {
+    let zero = builder.zero_extension();
+    let mut prod = vec![zero; a.len() + b.len() - 1];
+    for (i, &x) in a.iter().enumerate() {
+        for (j, &y) in b.iter().enumerate() {
+            prod[i + j] = builder.mul_const_add_extension(x, y, prod[i + j]);
+        }
+    }
+    prod
+}
+
+/// The coefficients of `w` from their bytes.
+fn carries<P: PackedField, const Q: usize, const W: usize>(
+    cols: &IdentityColumns<P, Q, W>,
+) -> [P; W] {
+    let base = P::Scalar::from_canonical_u64(1 << LIMB_BITS);
+    let offset = P::Scalar::from_canonical_u64(CARRY_OFFSET as u64);
+    core::array::from_fn(|i| cols.carry_lo[i] + cols.carry_hi[i] * base - offset)
+}
+
+/// Constrains `e(x) = (q(x) - 2^262) p(x) + (x - 2^8) w(x)` on the rows where `filter` is set.
+/// `e` must already be multiplied by `filter`, so that its coefficients may be products of two
+/// limbs.
+pub(crate) fn eval_identity<P: PackedField, const N: usize, const Q: usize, const W: usize>(
+    yield_constr: &mut ConstraintConsumer<P>,
+    filter: P,
+    e: &[P],
+    cols: &IdentityColumns<P, Q, W>,
+    modulus: &[u8; N],
+) {
+    debug_assert!(Q == N + 1 && W == 2 * N - 1 && e.len() < 2 * N);
+    let modulus = modulus.map(P::Scalar::from_canonical_u8);
+    let offset = P::Scalar::from_canonical_u64(1 << QUOTIENT_OFFSET_EXTRA_BITS);
+    let base = P::Scalar::from_canonical_u64(1 << LIMB_BITS);
+    let w = carries(cols);
+
+    for i in 0..2 * N {
+        let mut rhs = P::ZEROS;
+        for (j, &q) in cols.quotient.iter().enumerate() {
+            if let Some(&m) = i.checked_sub(j).and_then(|k| modulus.get(k)) {
+                rhs += q * m;
+            }
+        }
+        if let Some(&m) = i.checked_sub(N).and_then(|k| modulus.get(k)) {
+            rhs -= offset * m;
+        }
+        if i > 0 {
+            rhs += w[i - 1];
+        }
+        if i < W {
+            rhs -= w[i] * base;
+        }
+        let lhs = e.get(i).copied().unwrap_or(P::ZEROS);
+        yield_constr.constraint(lhs - filter * rhs);
+    }
+}
+
+pub(crate) fn eval_identity_ext_circuit<
+    F: RichField + Extendable<D>,
+    const D: usize,
+    const N: usize,
+    const Q: usize,
+    const W: usize,
+>(
+    builder: &mut CircuitBuilder<F, D>,
+    yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+    filter: ExtensionTarget<D>,
+    e: &[ExtensionTarget<D>],
+    cols: &IdentityColumns<ExtensionTarget<D>, Q, W>,
+    modulus: &[u8; N],
+) {
+    debug_assert!(Q == N + 1 && W == 2 * N - 1 && e.len() < 2 * N);
+    let modulus = modulus.map(F::from_canonical_u8);
+    let offset = F::from_canonical_u64(1 << QUOTIENT_OFFSET_EXTRA_BITS);
+    let base = F::from_canonical_u64(1 << LIMB_BITS);
+    let carry_offset =
+        builder.constant_extension(F::Extension::from_canonical_u64(CARRY_OFFSET as u64));
+    let w: [ExtensionTarget<D>; W] = core::array::from_fn(|i| {
+        let carry = builder.mul_const_add_extension(base, cols.carry_hi[i], cols.carry_lo[i]);
+        builder.sub_extension(carry, carry_offset)
+    });
+
+    for i in 0..2 * N {
+        let mut rhs = builder.zero_extension();
+        for (j, &q) in cols.quotient.iter().enumerate() {
+            if let Some(&m) = i.checked_sub(j).and_then(|k| modulus.get(k)) {
+                rhs = builder.mul_const_add_extension(m, q, rhs);
+            }
+        }
+        if let Some(&m) = i.checked_sub(N).and_then(|k| modulus.get(k)) {
+            let c = builder.constant_extension(F::Extension::from_basefield(offset * m));
+            rhs = builder.sub_extension(rhs, c);
+        }
+        if i > 0 {
+            rhs = builder.add_extension(rhs, w[i - 1]);
+        }
+        if i < W {
+            rhs = builder.mul_const_add_extension(-base, w[i], rhs);
+        }
+        let lhs = match e.get(i) {
+            Some(&lhs) => lhs,
+            None => builder.zero_extension(),
+        };
+        let constraint = builder.mul_sub_extension(filter, rhs, lhs);
+        yield_constr.constraint(builder, constraint);
+    }
+}
+
+/// Fills in the witness of the identity for `e`, whose value must be a multiple of `p`.
+pub(crate) fn generate_identity<F: PrimeField64, const N: usize, const Q: usize, const W: usize>(
+    cols: &mut IdentityColumns<F, Q, W>,
+    e: &[i64],
+    modulus: &[u8; N],
+) {
+    debug_assert!(Q == N + 1 && W == 2 * N - 1 && e.len() < 2 * N);
+    let m = BigInt::from_bytes_le(Sign::Plus, modulus);
+    let value = e
+        .iter()
+        .rev()
+        .fold(BigInt::zero(), |acc, &c| (acc << LIMB_BITS) + c);
+    assert!((&value % &m).is_zero(), "the identity does not hold");
+    let quotient = value / &m + (BigInt::from(1) << (LIMB_BITS * N + QUOTIENT_OFFSET_EXTRA_BITS));
+    let (sign, quotient) = quotient.to_bytes_le();
+    assert!(sign != Sign::Minus && quotient.len() <= Q);
+
+    let mut c = vec![0i64; 2 * N];
+    for (c, &e) in c.iter_mut().zip(e) {
+        *c += e;
+    }
+    for (j, &q) in quotient.iter().enumerate() {
+        for (k, &m) in modulus.iter().enumerate() {
+            c[j + k] -= q as i64 * m as i64;
+        }
+    }
+    for (k, &m) in modulus.iter().enumerate() {
+        c[N + k] += (m as i64) << QUOTIENT_OFFSET_EXTRA_BITS;
+    }
+
+    cols.quotient = [F::ZERO; Q];
+    for (limb, q) in cols.quotient.iter_mut().zip(quotient) {
+        *limb = F::from_canonical_u8(q);
+    }
+    // c(x) = (x - 2^8) w(x), so that c[i] = w[i - 1] - 2^8 w[i].
+    let mut w = 0i64;
+    for i in 0..W {
+        let num = w - c[i];
+        debug_assert_eq!(num % (1 << LIMB_BITS), 0);
+        w = num >> LIMB_BITS;
+        let carry = w + CARRY_OFFSET;
+        assert!((0..1 << 16).contains(&carry), "carry out of range");
+        cols.carry_lo[i] = F::from_canonical_u64(carry as u64 & 0xff);
+        cols.carry_hi[i] = F::from_canonical_u64(carry as u64 >> LIMB_BITS);
+    }
+    debug_assert_eq!(c[W], w);
+}
+
+/// The limbs of `p - 1`, for an odd `p`.
+fn modulus_minus_one<const N: usize>(modulus: &[u8; N]) -> [u8; N] {
+    let mut limbs = *modulus;
+    limbs[0] -= 1;
+    limbs
+}
+
+/// Constrains `a < p` on the rows where `filter` is set.
+pub(crate) fn eval_reduced<P: PackedField, const N: usize, const C: usize>(
+    yield_constr: &mut ConstraintConsumer<P>,
+    filter: P,
+    a: &[P; N],
+    cols: &ReducedColumns<P, N, C>,
+    modulus: &[u8; N],
+) {
+    debug_assert!(C == N - 1);
+    let base = P::Scalar::from_canonical_u64(1 << LIMB_BITS);
+    for &bit in cols.carry.iter() {
+        yield_constr.constraint(bit * (bit - P::ONES));
+    }
+    for (i, &m) in modulus_minus_one(modulus).iter().enumerate() {
+        let mut sum = a[i] + cols.diff[i] - P::Scalar::from_canonical_u8(m);
+        if i > 0 {
+            sum += cols.carry[i - 1];
+        }
+        if i < C {
+            sum -= cols.carry[i] * base;
+        }
+        yield_constr.constraint(filter * sum);
+    }
+}
+
+pub(crate) fn eval_reduced_ext_circuit<
+    F: RichField + Extendable<D>,
+    const D: usize,
+    const N: usize,
+    const C: usize,
+>(
+    builder: &mut CircuitBuilder<F, D>,
+    yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+    filter: ExtensionTarget<D>,
+    a: &[ExtensionTarget<D>; N],
+    cols: &ReducedColumns<ExtensionTarget<D>, N, C>,
+    modulus: &[u8; N],
+) {
+    debug_assert!(C == N - 1);
+    let base = F::from_canonical_u64(1 << LIMB_BITS);
+    for &bit in cols.carry.iter() {
+        let constraint = builder.mul_sub_extension(bit, bit, bit);
+        yield_constr.constraint(builder, constraint);
+    }
+    for (i, &m) in modulus_minus_one(modulus).iter().enumerate() {
+        let m = builder.constant_extension(F::Extension::from_canonical_u8(m));
+        let mut sum = builder.add_extension(a[i], cols.diff[i]);
+        sum = builder.sub_extension(sum, m);
+        if i > 0 {
+            sum = builder.add_extension(sum, cols.carry[i - 1]);
+        }
+        if i < C {
+            sum = builder.mul_const_add_extension(-base, cols.carry[i], sum);
+        }
+        let constraint = builder.mul_extension(filter, sum);
+        yield_constr.constraint(builder, constraint);
+    }
+}
+
+/// Fills in the witness of `a < p`.
+pub(crate) fn generate_reduced<F: PrimeField64, const N: usize, const C: usize>(
+    cols: &mut ReducedColumns<F, N, C>,
+    a: &[u8; N],
+    modulus: &[u8; N],
+) {
+    let a_int = BigInt::from_bytes_le(Sign::Plus, a);
+    let p_minus_one = BigInt::from_bytes_le(Sign::Plus, &modulus_minus_one(modulus));
+    let (sign, diff) = (p_minus_one - a_int).to_bytes_le();
+    assert!(sign != Sign::Minus, "the integer is not reduced");
+
+    cols.diff = [F::ZERO; N];
+    for (limb, d) in cols.diff.iter_mut().zip(diff) {
+        *limb = F::from_canonical_u8(d);
+    }
+    let mut carry = 0;
+    for (i, &m) in modulus_minus_one(modulus).iter().enumerate().take(C) {
+        let sum = a[i] as u64 + cols.diff[i].to_canonical_u64() + carry;
+        carry = (sum - m as u64) >> LIMB_BITS;
+        cols.carry[i] = F::from_canonical_u64(carry);
+    }
+}
@@ -0,0 +1,551 @@
+//! The wiring of the precompile tables into the rest of the proof.
+//!
+//! A precompile is a table proving the calls of one or a few syscalls: the CPU row of each call
+//! looks up the table, which reads its operands and writes its results in memory. A
+//! `PrecompileChip` describes these lookups, and a `PrecompileRegistry` derives the cross-table
+//! lookups of all its chips, so that adding a precompile does not mean editing the lookups of
+//! the CPU and of the memory by hand.
+//!
+//! The CPU binds each syscall to the filter of its lookup in `cpu::syscall::PRECOMPILE_CALLS`,
+//! with a selector column per call, so a chip can only be registered for a syscall and a filter
+//! listed there. The filter is set on every call of the syscall: a call the table cannot prove
+//! traps instead of skipping the lookup.
+//!
+//! The registry only derives the lookups. The tables are fixed at compile time: a new chip also
+//! needs its `Table` variant, its field of `AllStark`, its trace generation from the operations
+//! logged by the witness generation of the syscall, and its entry in `PRECOMPILE_CALLS`. So the
+//! registry cannot add a precompile from outside this crate.
+
+use plonky2::field::types::Field;
+use zkm_emulator::syscall;
+
+use crate::all_stark::Table;
+use crate::blake::compress_stark;
+use crate::bls12_381::fp_stark;
+use crate::chacha::block_stark;
+use crate::cpu::columns::COL_MAP;
+use crate::cpu::cpu_stark;
+use crate::cpu::syscall::PRECOMPILE_CALLS;
+use crate::cross_table_lookup::{Column, CrossTableLookup, Filter, TableWithColumns};
+use crate::ed25519::ed25519_stark;
+use crate::modexp::modexp_stark;
+use crate::sha256::{sha_compress_stark, sha_extend_stark};
+use crate::uint256::mulmod_stark;
+use crate::weierstrass::weierstrass_stark;
+
+/// A lookup of the CPU into a precompile table, for the calls of one syscall.
+pub struct CpuLookup<F: Field> {
+    /// The columns and the filter of the CPU row making the call.
+    pub cpu: (Vec<Column<F>>, Filter<F>),
+    /// The columns and the filter of the table, on the row the call starts.
+    pub looked: (Vec<Column<F>>, Filter<F>),
+}
+
+impl<F: Field> CpuLookup<F> {
+    fn new(
+        cpu_data: fn() -> Vec<Column<F>>,
+        cpu_filter: fn() -> Filter<F>,
+        looked_data: fn() -> Vec<Column<F>>,
+        looked_filter: fn() -> Filter<F>,
+    ) -> Self {
+        CpuLookup {
+            cpu: (cpu_data(), cpu_filter()),
+            looked: (looked_data(), looked_filter()),
+        }
+    }
+}
+
+/// A precompile table and its lookups.
+pub trait PrecompileChip<F: Field> {
+    /// The name of the precompile, for errors.
+    fn name(&self) -> &'static str;
+
+    /// The table proving the calls.
+    fn table(&self) -> Table;
+
+    /// The syscalls the table proves, in the order of `cpu_lookups`.
+    fn syscalls(&self) -> Vec<u32>;
+
+    /// The CPU columns filtering the rows which look up the table, one per syscall, in the order
+    /// of `syscalls`.
+    fn cpu_filters(&self) -> Vec<usize>;
+
+    /// The lookups of the CPU into the table, one per syscall.
+    fn cpu_lookups(&self) -> Vec<CpuLookup<F>>;
+
+    /// The number of lookups of the table into the memory.
+    fn num_memory_ctls(&self) -> usize;
+
+    /// The columns and the filter of the `i`-th lookup into the memory.
+    fn memory_ctl(&self, i: usize) -> (Vec<Column<F>>, Filter<F>);
+
+    /// The cross-table lookups of the CPU into the table.
+    fn cross_table_lookups(&self) -> Vec<CrossTableLookup<F>> {
+        self.cpu_lookups()
+            .into_iter()
+            .map(|lookup| {
+                let (cpu_data, cpu_filter) = lookup.cpu;
+                let (looked_data, looked_filter) = lookup.looked;
+                let cpu_looking = TableWithColumns::new(Table::Cpu, cpu_data, Some(cpu_filter));
+                let looked = TableWithColumns::new(self.table(), looked_data, Some(looked_filter));
+                CrossTableLookup::new(vec![cpu_looking], looked)
+            })
+            .collect()
+    }
+
+    /// The lookers of the memory in the table.
+    fn memory_lookers(&self) -> Vec<TableWithColumns<F>> {
+        (0..self.num_memory_ctls())
+            .map(|i| {
+                let (data, filter) = self.memory_ctl(i);
+                TableWithColumns::new(self.table(), data, Some(filter))
+            })
+            .collect()
+    }
+}
+
+/// A chip of the precompiles of this crate, from the lookup functions of its table.
+struct BuiltinChip<F: Field> {
+    name: &'static str,
+    table: Table,
+    syscalls: &'static [u32],
+    cpu_filters: &'static [usize],
+    cpu_lookups: fn() -> Vec<CpuLookup<F>>,
+    num_memory_ctls: usize,
+    memory_data: fn(usize) -> Vec<Column<F>>,
+    memory_filter: fn(usize) -> Filter<F>,
+}
+
+impl<F: Field> PrecompileChip<F> for BuiltinChip<F> {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn table(&self) -> Table {
+        self.table
+    }
+
+    fn syscalls(&self) -> Vec<u32> {
+        self.syscalls.to_vec()
+    }
+
+    fn cpu_filters(&self) -> Vec<usize> {
+        self.cpu_filters.to_vec()
+    }
+
+    fn cpu_lookups(&self) -> Vec<CpuLookup<F>> {
+        (self.cpu_lookups)()
+    }
+
+    fn num_memory_ctls(&self) -> usize {
+        self.num_memory_ctls
+    }
+
+    fn memory_ctl(&self, i: usize) -> (Vec<Column<F>>, Filter<F>) {
+        ((self.memory_data)(i), (self.memory_filter)(i))
+    }
+}
+
+/// The precompile chips of a proof.
+pub struct PrecompileRegistry<F: Field> {
+    chips: Vec<Box<dyn PrecompileChip<F>>>,
+}
+
+impl<F: Field> PrecompileRegistry<F> {
+    /// A registry without any chip.
+    pub fn empty() -> Self {
+        PrecompileRegistry { chips: vec![] }
+    }
+
+    /// The registry of the precompiles of this crate, in the order of their tables.
+    pub fn builtin() -> Self {
+        let mut registry = Self::empty();
+        for chip in builtin_chips() {
+            registry.register(Box::new(chip));
+        }
+        registry
+    }
+
+    /// Adds a chip. Panics if one of its syscalls is already proven by another chip, or is not
+    /// bound to its filter by the CPU, or if its table is the CPU or the memory.
+    pub fn register(&mut self, chip: Box<dyn PrecompileChip<F>>) {
+        assert!(
+            !matches!(chip.table(), Table::Cpu | Table::Memory),
+            "precompile {} cannot be proven by the {:?} table",
+            chip.name(),
+            chip.table()
+        );
+        assert_eq!(
+            chip.syscalls().len(),
+            chip.cpu_lookups().len(),
+            "precompile {} needs a CPU lookup per syscall",
+            chip.name()
+        );
+        assert_eq!(
+            chip.syscalls().len(),
+            chip.cpu_filters().len(),
+            "precompile {} needs a CPU filter per syscall",
+            chip.name()
+        );
+        for (sys_num, filter) in chip.syscalls().into_iter().zip(chip.cpu_filters()) {
+            assert!(
+                PRECOMPILE_CALLS.contains(&(sys_num as usize, filter)),
+                "syscall {:#x} of precompile {} is not bound to its filter by the CPU",
+                sys_num,
+                chip.name()
+            );
+            if let Some(other) = self.chip_for_syscall(sys_num) {
+                panic!(
+                    "syscall {:#x} of precompile {} is already proven by precompile {}",
+                    sys_num,
+                    chip.name(),
+                    other.name()
+                );
+            }
+        }
+        self.chips.push(chip);
+    }
+
+    pub fn chips(&self) -> &[Box<dyn PrecompileChip<F>>] {
+        &self.chips
+    }
+
+    /// The chip proving the syscall `sys_num`, if any.
+    pub fn chip_for_syscall(&self, sys_num: u32) -> Option<&dyn PrecompileChip<F>> {
+        self.chips
+            .iter()
+            .find(|chip| chip.syscalls().contains(&sys_num))
+            .map(|chip| chip.as_ref())
+    }
+
+    /// The cross-table lookups of the CPU into the tables of all the chips.
+    pub fn cross_table_lookups(&self) -> Vec<CrossTableLookup<F>> {
+        self.chips
+            .iter()
+            .flat_map(|chip| chip.cross_table_lookups())
+            .collect()
+    }
+
+    /// The lookers of the memory in the tables of all the chips.
+    pub fn memory_lookers(&self) -> Vec<TableWithColumns<F>> {
+        self.chips
+            .iter()
+            .flat_map(|chip| chip.memory_lookers())
+            .collect()
+    }
+}
+
+fn builtin_chips<F: Field>() -> Vec<BuiltinChip<F>> {
+    vec![
+        BuiltinChip {
+            name: "sha256_extend",
+            table: Table::ShaExtend,
+            syscalls: &[syscall::SYS_SHA256_EXTEND],
+            cpu_filters: &[COL_MAP.is_sha_extend],
+            cpu_lookups: || {
+                vec![CpuLookup::new(
+                    cpu_stark::ctl_data_sha_extend,
+                    cpu_stark::ctl_filter_sha_extend,
+                    sha_extend_stark::ctl_looked_data,
+                    sha_extend_stark::ctl_looked_filter,
+                )]
+            },
+            num_memory_ctls: sha_extend_stark::NUM_MEMORY_CTLS,
+            memory_data: sha_extend_stark::ctl_looking_memory,
+            memory_filter: sha_extend_stark::ctl_looking_memory_filter,
+        },
+        BuiltinChip {
+            name: "sha256_compress",
+            table: Table::ShaCompress,
+            syscalls: &[syscall::SYS_SHA256_COMPRESS],
+            cpu_filters: &[COL_MAP.is_sha_compress],
+            cpu_lookups: || {
+                vec![CpuLookup::new(
+                    cpu_stark::ctl_data_sha_compress,
+                    cpu_stark::ctl_filter_sha_compress,
+                    sha_compress_stark::ctl_looked_data,
+                    sha_compress_stark::ctl_looked_filter,
+                )]
+            },
+            num_memory_ctls: sha_compress_stark::NUM_MEMORY_CTLS,
+            memory_data: sha_compress_stark::ctl_looking_memory,
+            memory_filter: sha_compress_stark::ctl_looking_memory_filter,
+        },
+        BuiltinChip {
+            name: "secp256k1",
+            table: Table::Secp256k1,
+            syscalls: &[syscall::SYS_SECP256K1_ADD, syscall::SYS_SECP256K1_DOUBLE],
+            cpu_filters: &[COL_MAP.is_secp256k1_add, COL_MAP.is_secp256k1_double],
+            cpu_lookups: || {
+                vec![
+                    CpuLookup::new(
+                        cpu_stark::ctl_data_secp256k1_add,
+                        cpu_stark::ctl_filter_secp256k1_add,
+                        weierstrass_stark::ctl_looked_data_add,
+                        weierstrass_stark::ctl_looked_filter_add,
+                    ),
+                    CpuLookup::new(
+                        cpu_stark::ctl_data_secp256k1_double,
+                        cpu_stark::ctl_filter_secp256k1_double,
+                        weierstrass_stark::ctl_looked_data_double,
+                        weierstrass_stark::ctl_looked_filter_double,
+                    ),
+                ]
+            },
+            num_memory_ctls: weierstrass_stark::NUM_MEMORY_CTLS,
+            memory_data: weierstrass_stark::ctl_looking_memory,
+            memory_filter: weierstrass_stark::ctl_looking_memory_filter,
+        },
+        BuiltinChip {
+            name: "ed25519",
+            table: Table::Ed25519,
+            syscalls: &[syscall::SYS_ED_ADD, syscall::SYS_ED_DECOMPRESS],
+            cpu_filters: &[COL_MAP.is_ed25519_add, COL_MAP.is_ed25519_decompress],
+            cpu_lookups: || {
+                vec![
+                    CpuLookup::new(
+                        cpu_stark::ctl_data_ed25519_add,
+                        cpu_stark::ctl_filter_ed25519_add,
+                        ed25519_stark::ctl_looked_data_add,
+                        ed25519_stark::ctl_looked_filter_add,
+                    ),
+                    CpuLookup::new(
+                        cpu_stark::ctl_data_ed25519_decompress,
+                        cpu_stark::ctl_filter_ed25519_decompress,
+                        ed25519_stark::ctl_looked_data_decompress,
+                        ed25519_stark::ctl_looked_filter_decompress,
+                    ),
+                ]
+            },
+            num_memory_ctls: ed25519_stark::NUM_MEMORY_CTLS,
+            memory_data: ed25519_stark::ctl_looking_memory,
+            memory_filter: ed25519_stark::ctl_looking_memory_filter,
+        },
+        BuiltinChip {
+            name: "bn254",
+            table: Table::Bn254,
+            syscalls: &[syscall::SYS_BN254_ADD, syscall::SYS_BN254_DOUBLE],
+            cpu_filters: &[COL_MAP.is_bn254_add, COL_MAP.is_bn254_double],
+            cpu_lookups: || {
+                vec![
+                    CpuLookup::new(
+                        cpu_stark::ctl_data_bn254_add,
+                        cpu_stark::ctl_filter_bn254_add,
+                        weierstrass_stark::ctl_looked_data_add,
+                        weierstrass_stark::ctl_looked_filter_add,
+                    ),
+                    CpuLookup::new(
+                        cpu_stark::ctl_data_bn254_double,
+                        cpu_stark::ctl_filter_bn254_double,
+                        weierstrass_stark::ctl_looked_data_double,
+                        weierstrass_stark::ctl_looked_filter_double,
+                    ),
+                ]
+            },
+            num_memory_ctls: weierstrass_stark::NUM_MEMORY_CTLS,
+            memory_data: weierstrass_stark::ctl_looking_memory,
+            memory_filter: weierstrass_stark::ctl_looking_memory_filter,
+        },
+        BuiltinChip {
+            name: "bls12381_fp",
+            table: Table::Bls12381Fp,
+            syscalls: &[
+                syscall::SYS_BLS12381_FP_ADD,
+                syscall::SYS_BLS12381_FP_SUB,
+                syscall::SYS_BLS12381_FP_MUL,
+            ],
+            cpu_filters: &[
+                COL_MAP.is_bls12381_fp_add,
+                COL_MAP.is_bls12381_fp_sub,
+                COL_MAP.is_bls12381_fp_mul,
+            ],
+            cpu_lookups: || {
+                vec![
+                    CpuLookup::new(
+                        cpu_stark::ctl_data_bls12381_fp_add,
+                        cpu_stark::ctl_filter_bls12381_fp_add,
+                        fp_stark::ctl_looked_data,
+                        fp_stark::ctl_looked_filter_add,
+                    ),
+                    CpuLookup::new(
+                        cpu_stark::ctl_data_bls12381_fp_sub,
+                        cpu_stark::ctl_filter_bls12381_fp_sub,
+                        fp_stark::ctl_looked_data,
+                        fp_stark::ctl_looked_filter_sub,
+                    ),
+                    CpuLookup::new(
+                        cpu_stark::ctl_data_bls12381_fp_mul,
+                        cpu_stark::ctl_filter_bls12381_fp_mul,
+                        fp_stark::ctl_looked_data,
+                        fp_stark::ctl_looked_filter_mul,
+                    ),
+                ]
+            },
+            num_memory_ctls: fp_stark::NUM_MEMORY_CTLS,
+            memory_data: fp_stark::ctl_looking_memory,
+            memory_filter: fp_stark::ctl_looking_memory_filter,
+        },
+        BuiltinChip {
+            name: "uint256_mulmod",
+            table: Table::Uint256,
+            syscalls: &[syscall::SYS_UINT256_MULMOD],
+            cpu_filters: &[COL_MAP.is_uint256_mulmod],
+            cpu_lookups: || {
+                vec![CpuLookup::new(
+                    cpu_stark::ctl_data_uint256_mulmod,
+                    cpu_stark::ctl_filter_uint256_mulmod,
+                    mulmod_stark::ctl_looked_data,
+                    mulmod_stark::ctl_looked_filter,
+                )]
+            },
+            num_memory_ctls: mulmod_stark::NUM_MEMORY_CTLS,
+            memory_data: mulmod_stark::ctl_looking_memory,
+            memory_filter: mulmod_stark::ctl_looking_memory_filter,
+        },
+        BuiltinChip {
+            name: "blake",
+            table: Table::Blake,
+            syscalls: &[syscall::SYS_BLAKE2B_COMPRESS, syscall::SYS_BLAKE3_COMPRESS],
+            cpu_filters: &[COL_MAP.is_blake2b_compress, COL_MAP.is_blake3_compress],
+            cpu_lookups: || {
+                vec![
+                    CpuLookup::new(
+                        cpu_stark::ctl_data_blake2b_compress,
+                        cpu_stark::ctl_filter_blake2b_compress,
+                        compress_stark::ctl_looked_data,
+                        compress_stark::ctl_looked_filter_blake2b,
+                    ),
+                    CpuLookup::new(
+                        cpu_stark::ctl_data_blake3_compress,
+                        cpu_stark::ctl_filter_blake3_compress,
+                        compress_stark::ctl_looked_data,
+                        compress_stark::ctl_looked_filter_blake3,
+                    ),
+                ]
+            },
+            num_memory_ctls: compress_stark::NUM_MEMORY_CTLS,
+            memory_data: compress_stark::ctl_looking_memory,
+            memory_filter: compress_stark::ctl_looking_memory_filter,
+        },
+        BuiltinChip {
+            name: "chacha20_block",
+            table: Table::ChaCha,
+            syscalls: &[syscall::SYS_CHACHA20_BLOCK],
+            cpu_filters: &[COL_MAP.is_chacha_block],
+            cpu_lookups: || {
+                vec![CpuLookup::new(
+                    cpu_stark::ctl_data_chacha_block,
+                    cpu_stark::ctl_filter_chacha_block,
+                    block_stark::ctl_looked_data,
+                    block_stark::ctl_looked_filter,
+                )]
+            },
+            num_memory_ctls: block_stark::NUM_MEMORY_CTLS,
+            memory_data: block_stark::ctl_looking_memory,
+            memory_filter: block_stark::ctl_looking_memory_filter,
+        },
+        BuiltinChip {
+            name: "secp256r1",
+            table: Table::Secp256r1,
+            syscalls: &[syscall::SYS_SECP256R1_ADD, syscall::SYS_SECP256R1_DOUBLE],
+            cpu_filters: &[COL_MAP.is_secp256r1_add, COL_MAP.is_secp256r1_double],
+            cpu_lookups: || {
+                vec![
+                    CpuLookup::new(
+                        cpu_stark::ctl_data_secp256r1_add,
+                        cpu_stark::ctl_filter_secp256r1_add,
+                        weierstrass_stark::ctl_looked_data_add,
+                        weierstrass_stark::ctl_looked_filter_add,
+                    ),
+                    CpuLookup::new(
+                        cpu_stark::ctl_data_secp256r1_double,
+                        cpu_stark::ctl_filter_secp256r1_double,
+                        weierstrass_stark::ctl_looked_data_double,
+                        weierstrass_stark::ctl_looked_filter_double,
+                    ),
+                ]
+            },
+            num_memory_ctls: weierstrass_stark::NUM_MEMORY_CTLS,
+            memory_data: weierstrass_stark::ctl_looking_memory,
+            memory_filter: weierstrass_stark::ctl_looking_memory_filter,
+        },
+        BuiltinChip {
+            name: "modexp",
+            table: Table::ModExp,
+            syscalls: &[syscall::SYS_MODEXP],
+            cpu_filters: &[COL_MAP.is_modexp],
+            cpu_lookups: || {
+                vec![CpuLookup::new(
+                    cpu_stark::ctl_data_modexp,
+                    cpu_stark::ctl_filter_modexp,
+                    modexp_stark::ctl_looked_data,
+                    modexp_stark::ctl_looked_filter,
+                )]
+            },
+            num_memory_ctls: modexp_stark::NUM_MEMORY_CTLS,
+            memory_data: modexp_stark::ctl_looking_memory,
+            memory_filter: modexp_stark::ctl_looking_memory_filter,
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use zkm_emulator::syscall;
+
+    use crate::all_stark::Table;
+    use crate::cpu::columns::COL_MAP;
+    use crate::cpu::syscall::PRECOMPILE_CALLS;
+    use crate::precompile::PrecompileRegistry;
+
+    type F = GoldilocksField;
+
+    #[test]
+    fn test_builtin_registry() {
+        let registry = PrecompileRegistry::<F>::builtin();
+        let tables = registry
+            .chips()
+            .iter()
+            .map(|chip| chip.table())
+            .collect::<Vec<_>>();
+        assert_eq!(tables.first(), Some(&Table::ShaExtend));
+        assert_eq!(tables.last(), Some(&Table::ModExp));
+
+        let chip = registry
+            .chip_for_syscall(syscall::SYS_BN254_DOUBLE)
+            .unwrap();
+        assert_eq!(chip.table(), Table::Bn254);
+        assert!(registry.chip_for_syscall(syscall::SYS_WRITE).is_none());
+    }
+
+    /// The chips prove the calls the CPU binds, but the Keccak ones, which have their sponge and
+    /// lookups of their own.
+    #[test]
+    fn test_builtin_registry_matches_cpu() {
+        let registry = PrecompileRegistry::<F>::builtin();
+        let mut calls = registry
+            .chips()
+            .iter()
+            .flat_map(|chip| {
+                let syscalls = chip.syscalls().into_iter().map(|num| num as usize);
+                syscalls.zip(chip.cpu_filters()).collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        calls.push((syscall::SYS_KECCAK256 as usize, COL_MAP.is_keccak_sponge));
+        calls.sort();
+        let mut expected = PRECOMPILE_CALLS.to_vec();
+        expected.sort();
+        assert_eq!(calls, expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "already proven")]
+    fn test_register_duplicate_syscall() {
+        let mut registry = PrecompileRegistry::<F>::builtin();
+        let chips = PrecompileRegistry::<F>::builtin().chips;
+        for chip in chips {
+            registry.register(chip);
+        }
+    }
+}
@@ -0,0 +1,212 @@
+//! The progress of a proof, reported to a [`ProgressCallback`], and its cancellation with a
+//! [`CancellationToken`], for the services which show a progress bar or abort long proofs.
+//!
+//! A [`ProgressMonitor`] is installed on the thread proving, as the `tracing` spans are, so that
+//! the prover reports without the callback threaded through the proving functions. The segments
+//! proven on the threads of rayon are installed there with their index. The cancellation is
+//! checked at each report: the proof then stops with [`ProvingError::Cancelled`].
+
+use std::cell::RefCell;
+use std::fmt::{Debug, Formatter};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use crate::all_stark::Table;
+use crate::error::ProvingError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Progress {
+    /// The run has executed `cycles` cycles so far, in `segments` segments.
+    Executed { cycles: u64, segments: usize },
+    /// The trace of `table` of a segment is committed to.
+    TableCommitted {
+        segment: Option<usize>,
+        table: Table,
+    },
+    /// The FRI rounds proving the openings of `table` of a segment are done, the last step of
+    /// the proof of the table.
+    FriDone {
+        segment: Option<usize>,
+        table: Table,
+    },
+    /// The proof of a segment is done.
+    SegmentProven { segment: usize },
+}
+
+/// Called with each step of a proof, from the threads proving.
+#[derive(Clone)]
+pub struct ProgressCallback(Arc<dyn Fn(&Progress) + Send + Sync>);
+
+impl ProgressCallback {
+    pub fn new(callback: impl Fn(&Progress) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(callback))
+    }
+}
+
+impl Debug for ProgressCallback {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ProgressCallback")
+    }
+}
+
+/// Cancels the proofs it is given to, from any thread. Its clones cancel the same proofs.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ProgressMonitor {
+    callback: Option<ProgressCallback>,
+    cancellation: Option<CancellationToken>,
+    /// The segment proven, if the monitor is that of a segment.
+    segment: Option<usize>,
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<ProgressMonitor>> = const { RefCell::new(None) };
+}
+
+impl ProgressMonitor {
+    pub fn new(
+        callback: Option<ProgressCallback>,
+        cancellation: Option<CancellationToken>,
+    ) -> Self {
+        Self {
+            callback,
+            cancellation,
+            segment: None,
+        }
+    }
+
+    /// The monitor installed on this thread, or one which reports nothing.
+    pub fn current() -> Self {
+        CURRENT.with(|current| current.borrow().clone().unwrap_or_default())
+    }
+
+    /// This monitor, for the proof of the segment `index`.
+    pub fn for_segment(&self, index: usize) -> Self {
+        Self {
+            segment: Some(index),
+            ..self.clone()
+        }
+    }
+
+    pub fn callback(&self) -> Option<&ProgressCallback> {
+        self.callback.as_ref()
+    }
+
+    pub fn cancellation(&self) -> Option<&CancellationToken> {
+        self.cancellation.as_ref()
+    }
+
+    pub fn segment(&self) -> Option<usize> {
+        self.segment
+    }
+
+    /// Runs `f` with this monitor installed on the thread.
+    pub fn in_scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        let previous = CURRENT.with(|current| current.replace(Some(self.clone())));
+        // Restored even if `f` panics, as the thread may be one of a pool.
+        struct Restore(Option<ProgressMonitor>);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                CURRENT.with(|current| *current.borrow_mut() = self.0.take());
+            }
+        }
+        let _restore = Restore(previous);
+        f()
+    }
+
+    /// Reports `progress`, then fails if the proof is cancelled.
+    pub fn report(&self, progress: Progress) -> Result<()> {
+        if let Some(callback) = &self.callback {
+            (callback.0)(&progress);
+        }
+        self.check()
+    }
+
+    /// Fails if the proof is cancelled.
+    pub fn check(&self) -> Result<()> {
+        match &self.cancellation {
+            Some(token) if token.is_cancelled() => Err(ProvingError::Cancelled.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Reports that the trace of `table` is committed to, to the monitor of this thread.
+pub(crate) fn table_committed(table: Table) -> Result<()> {
+    let monitor = ProgressMonitor::current();
+    monitor.report(Progress::TableCommitted {
+        segment: monitor.segment,
+        table,
+    })
+}
+
+/// Reports that the FRI rounds of `table` are done, to the monitor of this thread.
+pub(crate) fn fri_done(table: Table) -> Result<()> {
+    let monitor = ProgressMonitor::current();
+    monitor.report(Progress::FriDone {
+        segment: monitor.segment,
+        table,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_progress_monitor() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let callback = {
+            let events = events.clone();
+            ProgressCallback::new(move |progress| events.lock().unwrap().push(*progress))
+        };
+        let token = CancellationToken::new();
+        let monitor = ProgressMonitor::new(Some(callback), Some(token.clone()));
+
+        // Nothing is reported outside of the scope of the monitor.
+        table_committed(Table::Cpu).unwrap();
+        monitor.for_segment(3).in_scope(|| {
+            table_committed(Table::Cpu).unwrap();
+            token.cancel();
+            let err = fri_done(Table::Cpu).unwrap_err();
+            assert_eq!(
+                err.downcast_ref::<ProvingError>(),
+                Some(&ProvingError::Cancelled)
+            );
+        });
+        assert!(ProgressMonitor::current().check().is_ok());
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                Progress::TableCommitted {
+                    segment: Some(3),
+                    table: Table::Cpu
+                },
+                Progress::FriDone {
+                    segment: Some(3),
+                    table: Table::Cpu
+                },
+            ]
+        );
+    }
+}
@@ -1,3 +1,6 @@
+use std::fs;
+use std::path::Path;
+
 use crate::all_stark::NUM_PUBLIC_INPUT_USERDATA;
 use itertools::Itertools;
 use plonky2::field::extension::{Extendable, FieldExtension};
@@ -7,6 +10,7 @@ use plonky2::fri::structure::{
     FriOpeningBatch, FriOpeningBatchTarget, FriOpenings, FriOpeningsTarget,
 };
 use plonky2::hash::hash_types::{MerkleCapTarget, RichField};
+use plonky2::hash::hashing::PlonkyPermutation;
 use plonky2::hash::merkle_tree::MerkleCap;
 use plonky2::iop::ext_target::ExtensionTarget;
 use plonky2::iop::target::{BoolTarget, Target};
@@ -14,14 +18,17 @@ use plonky2::plonk::circuit_builder::CircuitBuilder;
 use plonky2::plonk::config::{GenericConfig, Hasher};
 use plonky2::util::serialization::{Buffer, IoResult, Read, Write};
 use plonky2_maybe_rayon::*;
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use zkm_emulator::sha256;
 
 use crate::all_stark::NUM_TABLES;
 use crate::config::StarkConfig;
 use crate::cross_table_lookup::GrandProductChallengeSet;
+use crate::serialization::{self, Kind};
 
 /// A STARK proof for each table, plus some metadata used to create recursive wrapper proofs.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct AllProof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> {
     pub stark_proofs: [StarkProofWithMetadata<F, C, D>; NUM_TABLES],
     pub(crate) ctl_challenges: GrandProductChallengeSet<F>,
@@ -32,6 +39,22 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> A
     pub fn degree_bits(&self, config: &StarkConfig) -> [usize; NUM_TABLES] {
         core::array::from_fn(|i| self.stark_proofs[i].proof.recover_degree_bits(config))
     }
+
+    pub fn serialize(&self) -> anyhow::Result<Vec<u8>> {
+        serialization::serialize(Kind::StarkProof, self)
+    }
+
+    pub fn deserialize(bytes: &[u8]) -> anyhow::Result<Self> {
+        serialization::deserialize(Kind::StarkProof, bytes)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        Ok(fs::write(path, self.serialize()?)?)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Self::deserialize(&fs::read(path)?)
+    }
 }
 
 pub(crate) struct AllProofChallenges<F: RichField + Extendable<D>, const D: usize> {
@@ -50,9 +73,36 @@ pub(crate) struct AllChallengerState<F: RichField + Extendable<D>, H: Hasher<F>,
 /// Memory values which are public.
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct PublicValues {
+    /// The image id of the memory the segment starts from, the digest of its memory root and its
+    /// entry pc, as hashed by the bootstrap of the CPU table.
     pub roots_before: MemRoots,
+    /// The image id of the memory the segment ends with, the digest of its memory root and its
+    /// end pc, as hashed by the exit kernel of the CPU table.
     pub roots_after: MemRoots,
     pub userdata: Vec<u8>,
+    /// The exit code of the program, or the exception code if it trapped. It is 0 for a segment
+    /// which doesn't end the program.
+    pub exit_code: u32,
+    /// The SHA-256 digest of the values the program committed by the end of the segment, as
+    /// `zkm_runtime::io::committed_digest` computes it, in little-endian 32-bit limbs. The CPU
+    /// table doesn't constrain the bytes the program writes to the journal.
+    pub journal_digest: [u32; 8],
+}
+
+impl PublicValues {
+    /// The limbs of the digest of the bytes of `journal`, as in `journal_digest`.
+    pub fn journal_digest_of(journal: &[u8]) -> [u32; 8] {
+        let digest = sha256::digest(journal);
+        core::array::from_fn(|i| u32::from_le_bytes(digest[4 * i..4 * i + 4].try_into().unwrap()))
+    }
+
+    pub fn serialize(&self) -> anyhow::Result<Vec<u8>> {
+        serialization::serialize(Kind::PublicValues, self)
+    }
+
+    pub fn deserialize(bytes: &[u8]) -> anyhow::Result<Self> {
+        serialization::deserialize(Kind::PublicValues, bytes)
+    }
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -67,9 +117,15 @@ pub struct PublicValuesTarget {
     pub roots_before: MemRootsTarget,
     pub roots_after: MemRootsTarget,
     pub userdata: [Target; NUM_PUBLIC_INPUT_USERDATA],
+    pub exit_code: Target,
+    pub journal_digest: [Target; 8],
 }
 
 impl PublicValuesTarget {
+    /// The number of public inputs: the roots before and after, the userdata, the exit code and
+    /// the journal digest.
+    pub const SIZE: usize = 2 * 8 + NUM_PUBLIC_INPUT_USERDATA + 1 + 8;
+
     pub fn to_buffer(&self, buffer: &mut Vec<u8>) -> IoResult<()> {
         let MemRootsTarget {
             root: state_root_before,
@@ -84,6 +140,8 @@ impl PublicValuesTarget {
         buffer.write_target_array(&state_root_after)?;
 
         buffer.write_target_array(&self.userdata)?;
+        buffer.write_target(self.exit_code)?;
+        buffer.write_target_array(&self.journal_digest)?;
         Ok(())
     }
 
@@ -97,11 +155,15 @@ impl PublicValuesTarget {
         };
 
         let userdata = buffer.read_target_array()?;
+        let exit_code = buffer.read_target()?;
+        let journal_digest = buffer.read_target_array()?;
 
         Ok(Self {
             roots_before,
             roots_after,
             userdata,
+            exit_code,
+            journal_digest,
         })
     }
 
@@ -110,6 +172,10 @@ impl PublicValuesTarget {
             roots_before: MemRootsTarget::from_public_inputs(&pis[0..8]),
             roots_after: MemRootsTarget::from_public_inputs(&pis[8..16]),
             userdata: pis[16..16 + NUM_PUBLIC_INPUT_USERDATA].try_into().unwrap(),
+            exit_code: pis[16 + NUM_PUBLIC_INPUT_USERDATA],
+            journal_digest: pis[17 + NUM_PUBLIC_INPUT_USERDATA..25 + NUM_PUBLIC_INPUT_USERDATA]
+                .try_into()
+                .unwrap(),
         }
     }
 
@@ -135,6 +201,10 @@ impl PublicValuesTarget {
             userdata: core::array::from_fn(|i| {
                 builder.select(condition, pv0.userdata[i], pv1.userdata[i])
             }),
+            exit_code: builder.select(condition, pv0.exit_code, pv1.exit_code),
+            journal_digest: core::array::from_fn(|i| {
+                builder.select(condition, pv0.journal_digest[i], pv1.journal_digest[i])
+            }),
         }
     }
 }
@@ -174,7 +244,8 @@ impl MemRootsTarget {
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct StarkProof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> {
     /// Merkle cap of LDEs of trace values.
     pub trace_cap: MerkleCap<F, C::Hasher>,
@@ -200,6 +271,38 @@ where
     pub proof: StarkProof<F, C, D>,
 }
 
+/// The permutations of plonky2 have no serde, so the challenger state is encoded as its elements.
+impl<F, C, const D: usize> Serialize for StarkProofWithMetadata<F, C, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.init_challenger_state.as_ref(), &self.proof).serialize(serializer)
+    }
+}
+
+impl<'de, F, C, const D: usize> Deserialize<'de> for StarkProofWithMetadata<F, C, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        let (state, proof) = <(Vec<F>, StarkProof<F, C, D>)>::deserialize(deserializer)?;
+        let width = <<C::Hasher as Hasher<F>>::Permutation as PlonkyPermutation<F>>::WIDTH;
+        if state.len() != width {
+            return Err(de::Error::invalid_length(
+                state.len(),
+                &"the width of the challenger state",
+            ));
+        }
+        Ok(Self {
+            init_challenger_state: PlonkyPermutation::new(state),
+            proof,
+        })
+    }
+}
+
 impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> StarkProof<F, C, D> {
     /// Recover the length of the trace from a STARK proof and a STARK config.
     pub fn recover_degree_bits(&self, config: &StarkConfig) -> usize {
@@ -279,7 +382,8 @@ pub(crate) struct StarkProofChallengesTarget<const D: usize> {
 }
 
 /// Purported values of each polynomial at the challenge point.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct StarkOpeningSet<F: RichField + Extendable<D>, const D: usize> {
     /// Openings of trace polynomials at `zeta`.
     pub local_values: Vec<F::Extension>,
@@ -1,5 +1,6 @@
 use std::any::type_name;
 
+use crate::util::traced;
 use anyhow::{ensure, Result};
 use itertools::Itertools;
 use plonky2::field::extension::Extendable;
@@ -12,7 +13,6 @@ use plonky2::fri::oracle::PolynomialBatch;
 use plonky2::hash::hash_types::RichField;
 use plonky2::iop::challenger::Challenger;
 use plonky2::plonk::config::GenericConfig;
-use plonky2::timed;
 use plonky2::util::timing::TimingTree;
 use plonky2::util::transpose;
 use plonky2_maybe_rayon::*;
@@ -32,6 +32,7 @@ use crate::generation::state::{AssumptionReceipts, AssumptionUsage};
 use crate::generation::{generate_traces, generate_traces_with_assumptions};
 use crate::get_challenges::observe_public_values;
 use crate::lookup::{lookup_helper_columns, Lookup, LookupCheckVars};
+use crate::progress;
 use crate::proof::{AllProof, PublicValues, StarkOpeningSet, StarkProof, StarkProofWithMetadata};
 use crate::stark::Stark;
 use crate::vanishing_poly::eval_vanishing_poly;
@@ -83,7 +84,7 @@ where
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
 {
-    let (traces, public_values, outputs) = timed!(
+    let (traces, public_values, outputs) = traced!(
         timing,
         "generate all traces",
         generate_traces::<F, C, D>(all_stark, kernel, config, timing)?
@@ -110,7 +111,7 @@ where
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
 {
-    let (traces, public_values, outputs, receipts) = timed!(
+    let (traces, public_values, outputs, receipts) = traced!(
         timing,
         "generate all traces",
         generate_traces_with_assumptions::<F, C, D>(
@@ -141,14 +142,14 @@ where
     let rate_bits = config.fri_config.rate_bits;
     let cap_height = config.fri_config.cap_height;
 
-    let trace_commitments = timed!(
+    let trace_commitments = traced!(
         timing,
         "compute all trace commitments",
         trace_poly_values
             .iter()
             .zip_eq(Table::all())
-            .map(|(trace, table)| {
-                timed!(
+            .map(|(trace, table)| -> Result<_> {
+                let commitment = traced!(
                     timing,
                     &format!("compute trace commitment for {:?}", table),
                     PolynomialBatch::<F, C, D>::from_values(
@@ -161,9 +162,11 @@ where
                         timing,
                         None,
                     )
-                )
+                );
+                progress::table_committed(table)?;
+                Ok(commitment)
             })
-            .collect::<Vec<_>>()
+            .collect::<Result<Vec<_>>>()?
     );
 
     log::debug!("trace_commitments: {}", trace_commitments.len());
@@ -192,7 +195,7 @@ where
         .map_err(|_| anyhow::Error::msg("Invalid conversion of public values."))?;
 
     let ctl_challenges = get_grand_product_challenge_set(&mut challenger, config.num_challenges);
-    let ctl_data_per_table = timed!(
+    let ctl_data_per_table = traced!(
         timing,
         "compute CTL data",
         cross_table_lookup_data::<F, D>(
@@ -203,7 +206,7 @@ where
         )
     );
 
-    let stark_proofs = timed!(
+    let stark_proofs = traced!(
         timing,
         "compute all proofs given commitments",
         prove_with_commitments(
@@ -250,7 +253,7 @@ where
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
 {
-    let arithmetic_proof = timed!(
+    let arithmetic_proof = traced!(
         timing,
         "prove Arithmetic STARK",
         prove_single_table(
@@ -264,7 +267,8 @@ where
             timing,
         )?
     );
-    let cpu_proof = timed!(
+    progress::fri_done(Table::Arithmetic)?;
+    let cpu_proof = traced!(
         timing,
         "prove CPU STARK",
         prove_single_table(
@@ -278,8 +282,9 @@ where
             timing,
         )?
     );
+    progress::fri_done(Table::Cpu)?;
 
-    let poseidon_proof = timed!(
+    let poseidon_proof = traced!(
         timing,
         "prove Poseidon STARK",
         prove_single_table(
@@ -293,7 +298,8 @@ where
             timing,
         )?
     );
-    let poseidon_sponge_proof = timed!(
+    progress::fri_done(Table::Poseidon)?;
+    let poseidon_sponge_proof = traced!(
         timing,
         "prove Poseidon sponge STARK",
         prove_single_table(
@@ -307,7 +313,203 @@ where
             timing,
         )?
     );
-    let logic_proof = timed!(
+    progress::fri_done(Table::PoseidonSponge)?;
+    let keccak_proof = traced!(
+        timing,
+        "prove Keccak STARK",
+        prove_single_table(
+            &all_stark.keccak_stark,
+            config,
+            &trace_poly_values[Table::Keccak as usize],
+            &trace_commitments[Table::Keccak as usize],
+            &ctl_data_per_table[Table::Keccak as usize],
+            ctl_challenges,
+            challenger,
+            timing,
+        )?
+    );
+    progress::fri_done(Table::Keccak)?;
+    let keccak_sponge_proof = traced!(
+        timing,
+        "prove Keccak sponge STARK",
+        prove_single_table(
+            &all_stark.keccak_sponge_stark,
+            config,
+            &trace_poly_values[Table::KeccakSponge as usize],
+            &trace_commitments[Table::KeccakSponge as usize],
+            &ctl_data_per_table[Table::KeccakSponge as usize],
+            ctl_challenges,
+            challenger,
+            timing,
+        )?
+    );
+    progress::fri_done(Table::KeccakSponge)?;
+    let sha_extend_proof = traced!(
+        timing,
+        "prove SHA-256 extend STARK",
+        prove_single_table(
+            &all_stark.sha_extend_stark,
+            config,
+            &trace_poly_values[Table::ShaExtend as usize],
+            &trace_commitments[Table::ShaExtend as usize],
+            &ctl_data_per_table[Table::ShaExtend as usize],
+            ctl_challenges,
+            challenger,
+            timing,
+        )?
+    );
+    progress::fri_done(Table::ShaExtend)?;
+    let sha_compress_proof = traced!(
+        timing,
+        "prove SHA-256 compress STARK",
+        prove_single_table(
+            &all_stark.sha_compress_stark,
+            config,
+            &trace_poly_values[Table::ShaCompress as usize],
+            &trace_commitments[Table::ShaCompress as usize],
+            &ctl_data_per_table[Table::ShaCompress as usize],
+            ctl_challenges,
+            challenger,
+            timing,
+        )?
+    );
+    progress::fri_done(Table::ShaCompress)?;
+    let secp256k1_proof = traced!(
+        timing,
+        "prove secp256k1 STARK",
+        prove_single_table(
+            &all_stark.secp256k1_stark,
+            config,
+            &trace_poly_values[Table::Secp256k1 as usize],
+            &trace_commitments[Table::Secp256k1 as usize],
+            &ctl_data_per_table[Table::Secp256k1 as usize],
+            ctl_challenges,
+            challenger,
+            timing,
+        )?
+    );
+    progress::fri_done(Table::Secp256k1)?;
+    let ed25519_proof = traced!(
+        timing,
+        "prove ed25519 STARK",
+        prove_single_table(
+            &all_stark.ed25519_stark,
+            config,
+            &trace_poly_values[Table::Ed25519 as usize],
+            &trace_commitments[Table::Ed25519 as usize],
+            &ctl_data_per_table[Table::Ed25519 as usize],
+            ctl_challenges,
+            challenger,
+            timing,
+        )?
+    );
+    progress::fri_done(Table::Ed25519)?;
+    let bn254_proof = traced!(
+        timing,
+        "prove bn254 STARK",
+        prove_single_table(
+            &all_stark.bn254_stark,
+            config,
+            &trace_poly_values[Table::Bn254 as usize],
+            &trace_commitments[Table::Bn254 as usize],
+            &ctl_data_per_table[Table::Bn254 as usize],
+            ctl_challenges,
+            challenger,
+            timing,
+        )?
+    );
+    progress::fri_done(Table::Bn254)?;
+    let bls12381_fp_proof = traced!(
+        timing,
+        "prove BLS12-381 field STARK",
+        prove_single_table(
+            &all_stark.bls12381_fp_stark,
+            config,
+            &trace_poly_values[Table::Bls12381Fp as usize],
+            &trace_commitments[Table::Bls12381Fp as usize],
+            &ctl_data_per_table[Table::Bls12381Fp as usize],
+            ctl_challenges,
+            challenger,
+            timing,
+        )?
+    );
+    progress::fri_done(Table::Bls12381Fp)?;
+    let uint256_proof = traced!(
+        timing,
+        "prove uint256 mulmod STARK",
+        prove_single_table(
+            &all_stark.uint256_stark,
+            config,
+            &trace_poly_values[Table::Uint256 as usize],
+            &trace_commitments[Table::Uint256 as usize],
+            &ctl_data_per_table[Table::Uint256 as usize],
+            ctl_challenges,
+            challenger,
+            timing,
+        )?
+    );
+    progress::fri_done(Table::Uint256)?;
+    let blake_proof = traced!(
+        timing,
+        "prove blake compression STARK",
+        prove_single_table(
+            &all_stark.blake_stark,
+            config,
+            &trace_poly_values[Table::Blake as usize],
+            &trace_commitments[Table::Blake as usize],
+            &ctl_data_per_table[Table::Blake as usize],
+            ctl_challenges,
+            challenger,
+            timing,
+        )?
+    );
+    progress::fri_done(Table::Blake)?;
+    let chacha_proof = traced!(
+        timing,
+        "prove ChaCha block STARK",
+        prove_single_table(
+            &all_stark.chacha_stark,
+            config,
+            &trace_poly_values[Table::ChaCha as usize],
+            &trace_commitments[Table::ChaCha as usize],
+            &ctl_data_per_table[Table::ChaCha as usize],
+            ctl_challenges,
+            challenger,
+            timing,
+        )?
+    );
+    progress::fri_done(Table::ChaCha)?;
+    let secp256r1_proof = traced!(
+        timing,
+        "prove secp256r1 STARK",
+        prove_single_table(
+            &all_stark.secp256r1_stark,
+            config,
+            &trace_poly_values[Table::Secp256r1 as usize],
+            &trace_commitments[Table::Secp256r1 as usize],
+            &ctl_data_per_table[Table::Secp256r1 as usize],
+            ctl_challenges,
+            challenger,
+            timing,
+        )?
+    );
+    progress::fri_done(Table::Secp256r1)?;
+    let modexp_proof = traced!(
+        timing,
+        "prove modexp STARK",
+        prove_single_table(
+            &all_stark.modexp_stark,
+            config,
+            &trace_poly_values[Table::ModExp as usize],
+            &trace_commitments[Table::ModExp as usize],
+            &ctl_data_per_table[Table::ModExp as usize],
+            ctl_challenges,
+            challenger,
+            timing,
+        )?
+    );
+    progress::fri_done(Table::ModExp)?;
+    let logic_proof = traced!(
         timing,
         "prove Logic STARK",
         prove_single_table(
@@ -321,7 +523,8 @@ where
             timing,
         )?
     );
-    let memory_proof = timed!(
+    progress::fri_done(Table::Logic)?;
+    let memory_proof = traced!(
         timing,
         "prove Memory STARK",
         prove_single_table(
@@ -335,12 +538,26 @@ where
             timing,
         )?
     );
+    progress::fri_done(Table::Memory)?;
 
     Ok([
         arithmetic_proof,
         cpu_proof,
         poseidon_proof,
         poseidon_sponge_proof,
+        keccak_proof,
+        keccak_sponge_proof,
+        sha_extend_proof,
+        sha_compress_proof,
+        secp256k1_proof,
+        ed25519_proof,
+        bn254_proof,
+        bls12381_fp_proof,
+        uint256_proof,
+        blake_proof,
+        chacha_proof,
+        secp256r1_proof,
+        modexp_proof,
         logic_proof,
         memory_proof,
     ])
@@ -383,7 +600,7 @@ where
             .collect::<Vec<_>>()
     });
     let lookups = stark.lookups();
-    let lookup_helper_columns = timed!(
+    let lookup_helper_columns = traced!(
         timing,
         "compute lookup helper columns",
         lookup_challenges.as_ref().map(|challenges| {
@@ -417,7 +634,7 @@ where
     };
     assert!(!auxiliary_polys.is_empty(), "No CTL?");
 
-    let auxiliary_polys_commitment = timed!(
+    let auxiliary_polys_commitment = traced!(
         timing,
         "compute auxiliary polynomials commitment",
         PolynomialBatch::from_values(
@@ -449,7 +666,7 @@ where
             &num_ctl_polys,
         );
     }
-    let quotient_polys = timed!(
+    let quotient_polys = traced!(
         timing,
         "compute quotient polys",
         compute_quotient_polys::<F, <F as Packable>::Packing, C, S, D>(
@@ -466,7 +683,7 @@ where
             config,
         )
     );
-    let all_quotient_chunks = timed!(
+    let all_quotient_chunks = traced!(
         timing,
         "split quotient polys",
         quotient_polys
@@ -482,7 +699,7 @@ where
             })
             .collect()
     );
-    let quotient_commitment = timed!(
+    let quotient_commitment = traced!(
         timing,
         "compute quotient commitment",
         PolynomialBatch::from_coeffs(
@@ -524,7 +741,7 @@ where
         &quotient_commitment,
     ];
 
-    let opening_proof = timed!(
+    let opening_proof = traced!(
         timing,
         "compute openings proof",
         PolynomialBatch::prove_openings(
@@ -500,10 +500,14 @@ pub(crate) fn add_virtual_public_values<F: RichField + Extendable<D>, const D: u
     let roots_before = add_virtual_trie_roots(builder);
     let roots_after = add_virtual_trie_roots(builder);
     let userdata = builder.add_virtual_public_input_arr();
+    let exit_code = builder.add_virtual_public_input();
+    let journal_digest = builder.add_virtual_public_input_arr();
     PublicValuesTarget {
         roots_before,
         roots_after,
         userdata,
+        exit_code,
+        journal_digest,
     }
 }
 
@@ -632,6 +636,17 @@ where
             F::from_canonical_u8(*limb),
         );
     }
+    witness.set_target(
+        public_values_target.exit_code,
+        F::from_canonical_u32(public_values.exit_code),
+    );
+    for (&target, &limb) in public_values_target
+        .journal_digest
+        .iter()
+        .zip(&public_values.journal_digest)
+    {
+        witness.set_target(target, F::from_canonical_u32(limb));
+    }
     Ok(())
 }
 
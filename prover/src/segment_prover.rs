@@ -0,0 +1,181 @@
+//! Proving the segments of a run in parallel, on the threads of this machine or on workers.
+//!
+//! A [`SegmentJob`] is a segment written by the emulator, which a worker proves with a
+//! [`SegmentProver`] built from the same circuits, into a [`ProvenSegment`]. Both serialize, so
+//! that the jobs can be sent to remote workers and their proofs sent back, to be aggregated with
+//! [`aggregate_segments`] in any order they arrive.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{ensure, Result};
+use plonky2::field::extension::Extendable;
+use plonky2::hash::hash_types::RichField;
+use plonky2::plonk::config::{AlgebraicHasher, GenericConfig};
+use plonky2::util::timing::TimingTree;
+use plonky2_maybe_rayon::*;
+use serde::{Deserialize, Serialize};
+
+use crate::all_stark::AllStark;
+use crate::config::StarkConfig;
+use crate::continuation::{aggregate, RecursiveProof};
+use crate::cpu::kernel::assembler::segment_kernel;
+use crate::error::ProvingError;
+use crate::fixed_recursive_verifier::AllRecursiveCircuits;
+use crate::progress::{Progress, ProgressMonitor};
+use crate::serialization::{self, Kind};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SegmentJob {
+    /// The position of the segment in the run.
+    pub index: usize,
+    /// The segment, as written by the emulator.
+    pub segment: Vec<u8>,
+}
+
+impl SegmentJob {
+    /// Reads the segment `index` written by the emulator to `seg_dir`.
+    pub fn read(seg_dir: impl AsRef<Path>, index: usize) -> Result<Self> {
+        let segment = fs::read(seg_dir.as_ref().join(index.to_string()))?;
+        Ok(Self { index, segment })
+    }
+
+    pub fn serialize(&self) -> Result<Vec<u8>> {
+        serialization::serialize(Kind::SegmentJob, self)
+    }
+
+    pub fn deserialize(bytes: &[u8]) -> Result<Self> {
+        serialization::deserialize(Kind::SegmentJob, bytes)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct ProvenSegment<F, C, const D: usize>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    pub index: usize,
+    /// The root proof of the segment.
+    pub proof: RecursiveProof<F, C, D>,
+}
+
+impl<F, C, const D: usize> ProvenSegment<F, C, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    pub fn serialize(&self) -> Result<Vec<u8>> {
+        serialization::serialize(Kind::SegmentProof, self)
+    }
+
+    pub fn deserialize(bytes: &[u8]) -> Result<Self> {
+        serialization::deserialize(Kind::SegmentProof, bytes)
+    }
+}
+
+pub struct SegmentProver<'a, F, C, const D: usize>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    C::Hasher: AlgebraicHasher<F>,
+{
+    all_stark: &'a AllStark<F, D>,
+    config: &'a StarkConfig,
+    circuits: &'a AllRecursiveCircuits<F, C, D>,
+}
+
+impl<'a, F, C, const D: usize> SegmentProver<'a, F, C, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F> + 'static,
+    C::Hasher: AlgebraicHasher<F>,
+{
+    pub fn new(
+        all_stark: &'a AllStark<F, D>,
+        config: &'a StarkConfig,
+        circuits: &'a AllRecursiveCircuits<F, C, D>,
+    ) -> Self {
+        Self {
+            all_stark,
+            config,
+            circuits,
+        }
+    }
+
+    /// Proves `job`, reporting to the [`ProgressMonitor`] of the thread.
+    pub fn prove(&self, job: &SegmentJob) -> Result<ProvenSegment<F, C, D>> {
+        let _span = tracing::info_span!("segment", index = job.index).entered();
+        let monitor = ProgressMonitor::current().for_segment(job.index);
+        monitor.check()?;
+        let kernel = segment_kernel("", "", "", job.segment.as_slice());
+        let mut timing = TimingTree::new("prove segment", log::Level::Info);
+        let (proof, public_values) = monitor.in_scope(|| {
+            self.circuits
+                .prove_root(self.all_stark, &kernel, self.config, &mut timing)
+        })?;
+        monitor.report(Progress::SegmentProven { segment: job.index })?;
+        Ok(ProvenSegment {
+            index: job.index,
+            proof: RecursiveProof {
+                proof,
+                public_values,
+                is_aggregation: false,
+            },
+        })
+    }
+
+    /// Proves `jobs` in parallel.
+    pub fn prove_all(&self, jobs: &[SegmentJob]) -> Result<Vec<ProvenSegment<F, C, D>>> {
+        // The segments are proven on the threads of rayon, in the span and with the monitor of
+        // the caller.
+        let span = tracing::Span::current();
+        let monitor = ProgressMonitor::current();
+        jobs.par_iter()
+            .map(|job| span.in_scope(|| monitor.in_scope(|| self.prove(job))))
+            .collect()
+    }
+}
+
+/// Aggregates the proofs of all the segments of a run, received in any order, into a proof of
+/// the run.
+pub fn aggregate_segments<F, C, const D: usize>(
+    circuits: &AllRecursiveCircuits<F, C, D>,
+    mut proven: Vec<ProvenSegment<F, C, D>>,
+) -> Result<RecursiveProof<F, C, D>>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F> + 'static,
+    C::Hasher: AlgebraicHasher<F>,
+{
+    proven.sort_by_key(|segment| segment.index);
+    for (i, segment) in proven.iter().enumerate() {
+        ensure!(
+            segment.index == i,
+            ProvingError::MissingSegment { segment: i }
+        );
+    }
+    aggregate(
+        circuits,
+        proven.into_iter().map(|segment| segment.proof).collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segment_job() {
+        let job = SegmentJob {
+            index: 7,
+            segment: b"{}".to_vec(),
+        };
+        let bytes = job.serialize().unwrap();
+        let decoded = SegmentJob::deserialize(&bytes).unwrap();
+        assert_eq!(decoded.index, 7);
+        assert_eq!(decoded.segment, job.segment);
+        assert!(serialization::deserialize::<SegmentJob>(Kind::SegmentProof, &bytes).is_err());
+    }
+}
@@ -0,0 +1,114 @@
+//! The encoding of the proofs, public values and verifying keys written to disk or sent to a
+//! verifier: a header, then the bincode encoding of the value.
+//!
+//! The header is a magic number, the version of the encoding and the kind of the value, so that
+//! a file of another version or kind is rejected instead of decoded as garbage.
+
+use anyhow::{bail, ensure, Result};
+use bincode::Options;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+pub const MAGIC: [u8; 4] = *b"ZKM\0";
+
+/// The version of the encoding, bumped whenever an encoded type changes.
+pub const VERSION: u16 = 3;
+
+const HEADER_LEN: usize = MAGIC.len() + 2 + 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Kind {
+    Proof = 1,
+    PublicValues = 2,
+    VerifyingKey = 3,
+    SegmentJob = 4,
+    SegmentProof = 5,
+    Circuits = 6,
+    StarkProof = 7,
+    StarkProofs = 8,
+}
+
+impl Kind {
+    fn from_u8(kind: u8) -> Option<Self> {
+        match kind {
+            1 => Some(Self::Proof),
+            2 => Some(Self::PublicValues),
+            3 => Some(Self::VerifyingKey),
+            4 => Some(Self::SegmentJob),
+            5 => Some(Self::SegmentProof),
+            6 => Some(Self::Circuits),
+            7 => Some(Self::StarkProof),
+            8 => Some(Self::StarkProofs),
+            _ => None,
+        }
+    }
+}
+
+fn options() -> impl Options {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .reject_trailing_bytes()
+}
+
+/// Prefixes `body` with the header of a value of `kind`.
+pub fn encode(kind: Kind, body: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN + body.len());
+    bytes.extend_from_slice(&MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.push(kind as u8);
+    bytes.extend_from_slice(body);
+    bytes
+}
+
+/// Checks the header of `bytes` for a value of `kind`, and returns the body.
+pub fn decode(kind: Kind, bytes: &[u8]) -> Result<&[u8]> {
+    ensure!(
+        bytes.len() >= HEADER_LEN && bytes[..MAGIC.len()] == MAGIC,
+        "not a zkm artifact"
+    );
+    let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+    ensure!(
+        version == VERSION,
+        "encoded with version {} of zkm, which reads version {}",
+        version,
+        VERSION
+    );
+    match Kind::from_u8(bytes[6]) {
+        Some(found) if found == kind => Ok(&bytes[HEADER_LEN..]),
+        Some(found) => bail!("expected a {:?}, found a {:?}", kind, found),
+        None => bail!("unknown kind {}", bytes[6]),
+    }
+}
+
+pub fn serialize<T: Serialize>(kind: Kind, value: &T) -> Result<Vec<u8>> {
+    Ok(encode(kind, &options().serialize(value)?))
+}
+
+pub fn deserialize<T: DeserializeOwned>(kind: Kind, bytes: &[u8]) -> Result<T> {
+    Ok(options().deserialize(decode(kind, bytes)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof::PublicValues;
+
+    #[test]
+    fn test_header() {
+        let public_values = PublicValues {
+            exit_code: 3,
+            ..Default::default()
+        };
+        let bytes = public_values.serialize().unwrap();
+        assert_eq!(PublicValues::deserialize(&bytes).unwrap().exit_code, 3);
+
+        assert!(deserialize::<PublicValues>(Kind::Proof, &bytes).is_err());
+        let mut other_version = bytes.clone();
+        other_version[4] ^= 0xff;
+        assert!(PublicValues::deserialize(&other_version).is_err());
+        let mut trailing = bytes;
+        trailing.push(0);
+        assert!(PublicValues::deserialize(&trailing).is_err());
+    }
+}
@@ -0,0 +1,72 @@
+use crate::util::impl_columns_view;
+
+/// The number of message schedule words computed by `SHA256_EXTEND`, one per row.
+pub(crate) const NUM_EXTEND_ROWS: usize = 48;
+/// The number of rounds of `SHA256_COMPRESS`, one per row, before the row adding the compressed
+/// chunk to the hash state.
+pub(crate) const NUM_COMPRESS_ROUNDS: usize = 64;
+pub(crate) const NUM_COMPRESS_ROWS: usize = NUM_COMPRESS_ROUNDS + 1;
+
+#[repr(C)]
+#[derive(Eq, PartialEq, Debug)]
+pub(crate) struct ShaExtendColumnsView<T: Copy> {
+    /// The row of the call computing `w[16 + i]` has `step[i]` set, and padding rows none.
+    pub step: [T; NUM_EXTEND_ROWS],
+
+    /// The timestamp of the CPU row making the call. The first sixteen words are read at this
+    /// timestamp, and the others written right after it.
+    pub timestamp: T,
+    /// The address of `w`.
+    pub w_ptr: T,
+
+    /// The little-endian bits of `w[i..i + 16]`, on the row computing `w[16 + i]`.
+    pub window: [[T; 32]; 16],
+    /// The little-endian bits of `w[16 + i]`.
+    pub w_i: [T; 32],
+    /// The carry of the 32-bit sum giving `w[16 + i]`, in two bits.
+    pub carry: [T; 2],
+}
+
+#[repr(C)]
+#[derive(Eq, PartialEq, Debug)]
+pub(crate) struct ShaCompressColumnsView<T: Copy> {
+    /// Round `r` has `step[r]` set, the row adding the compressed chunk to the hash state
+    /// `step[NUM_COMPRESS_ROUNDS]`, and padding rows none.
+    pub step: [T; NUM_COMPRESS_ROWS],
+
+    /// The timestamp of the CPU row making the call. `w` and the hash state are read at this
+    /// timestamp, and the new hash state written right after it.
+    pub timestamp: T,
+    /// The address of `w`.
+    pub w_ptr: T,
+    /// The address of the hash state.
+    pub h_ptr: T,
+
+    /// The hash state read, carried through the rows of the call.
+    pub h: [T; 8],
+    /// The little-endian bits of the working variables `a..h` at the start of the round. On the
+    /// last row, those of the new hash state.
+    pub state: [[T; 32]; 8],
+    /// `w[r]`, on the row of round `r`.
+    pub w: T,
+
+    /// The little-endian bits of the new `a` and `e`.
+    pub new_a: [T; 32],
+    pub new_e: [T; 32],
+    /// The carries of the 32-bit sums giving the new `a` and `e`, in three bits each.
+    pub carry_a: [T; 3],
+    pub carry_e: [T; 3],
+    /// On the last row, the carries of the additions giving the new hash state.
+    pub carry_h: [T; 8],
+}
+
+impl_columns_view!(
+    ShaExtendColumnsView,
+    NUM_SHA_EXTEND_COLUMNS,
+    SHA_EXTEND_COL_MAP
+);
+impl_columns_view!(
+    ShaCompressColumnsView,
+    NUM_SHA_COMPRESS_COLUMNS,
+    SHA_COMPRESS_COL_MAP
+);
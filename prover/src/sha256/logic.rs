@@ -0,0 +1,133 @@
+use plonky2::field::extension::Extendable;
+use plonky2::field::packed::PackedField;
+use plonky2::field::types::Field;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+
+use crate::keccak::logic::{xor3_gen, xor3_gen_circuit, xor_gen, xor_gen_circuit};
+use crate::util::{limb_from_bits_le, limb_from_bits_le_recursive};
+
+/// One of the three terms XORed together by the SHA-256 sigma functions.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Shift {
+    Rotr(u32),
+    Shr(u32),
+}
+
+pub(crate) type Sigma = [Shift; 3];
+
+pub(crate) const SMALL_SIGMA0: Sigma = [Shift::Rotr(7), Shift::Rotr(18), Shift::Shr(3)];
+pub(crate) const SMALL_SIGMA1: Sigma = [Shift::Rotr(17), Shift::Rotr(19), Shift::Shr(10)];
+pub(crate) const BIG_SIGMA0: Sigma = [Shift::Rotr(2), Shift::Rotr(13), Shift::Rotr(22)];
+pub(crate) const BIG_SIGMA1: Sigma = [Shift::Rotr(6), Shift::Rotr(11), Shift::Rotr(25)];
+
+impl Shift {
+    fn apply(self, x: u32) -> u32 {
+        match self {
+            Shift::Rotr(n) => x.rotate_right(n),
+            Shift::Shr(n) => x >> n,
+        }
+    }
+
+    /// The bit of the input landing at bit `i` of the result, if any.
+    fn source_bit(self, i: usize) -> Option<usize> {
+        match self {
+            Shift::Rotr(n) => Some((i + n as usize) % 32),
+            Shift::Shr(n) => Some(i + n as usize).filter(|&j| j < 32),
+        }
+    }
+}
+
+pub(crate) fn sigma(s: Sigma, x: u32) -> u32 {
+    s[0].apply(x) ^ s[1].apply(x) ^ s[2].apply(x)
+}
+
+pub(crate) fn ch(e: u32, f: u32, g: u32) -> u32 {
+    (e & f) ^ (!e & g)
+}
+
+pub(crate) fn maj(a: u32, b: u32, c: u32) -> u32 {
+    (a & b) ^ (a & c) ^ (b & c)
+}
+
+pub(crate) fn u32_to_bits_le<F: Field>(x: u32) -> [F; 32] {
+    core::array::from_fn(|i| F::from_bool((x >> i) & 1 == 1))
+}
+
+/// Computes the value of `sigma(x)` from the bits of `x`, as a polynomial of degree 3.
+pub(crate) fn sigma_gen<P: PackedField>(s: Sigma, x: &[P; 32]) -> P {
+    limb_from_bits_le((0..32).map(|i| {
+        let bits = s.map(|shift| shift.source_bit(i).map(|j| x[j]));
+        match bits {
+            [Some(b0), Some(b1), Some(b2)] => xor3_gen(b0, b1, b2),
+            [Some(b0), Some(b1), None] => xor_gen(b0, b1),
+            _ => unreachable!(),
+        }
+    }))
+}
+
+pub(crate) fn sigma_gen_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    s: Sigma,
+    x: &[ExtensionTarget<D>; 32],
+) -> ExtensionTarget<D> {
+    let bits = (0..32)
+        .map(|i| {
+            let bits = s.map(|shift| shift.source_bit(i).map(|j| x[j]));
+            match bits {
+                [Some(b0), Some(b1), Some(b2)] => xor3_gen_circuit(builder, b0, b1, b2),
+                [Some(b0), Some(b1), None] => xor_gen_circuit(builder, b0, b1),
+                _ => unreachable!(),
+            }
+        })
+        .collect::<Vec<_>>();
+    limb_from_bits_le_recursive(builder, bits)
+}
+
+/// Computes the value of `ch(e, f, g)` from their bits: each bit is `e f + (1 - e) g`.
+pub(crate) fn ch_gen<P: PackedField>(e: &[P; 32], f: &[P; 32], g: &[P; 32]) -> P {
+    limb_from_bits_le((0..32).map(|i| e[i] * f[i] + (P::ONES - e[i]) * g[i]))
+}
+
+pub(crate) fn ch_gen_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    e: &[ExtensionTarget<D>; 32],
+    f: &[ExtensionTarget<D>; 32],
+    g: &[ExtensionTarget<D>; 32],
+) -> ExtensionTarget<D> {
+    let bits = (0..32)
+        .map(|i| {
+            // e f - e g + g
+            let ef_plus_g = builder.mul_add_extension(e[i], f[i], g[i]);
+            builder.arithmetic_extension(F::NEG_ONE, F::ONE, e[i], g[i], ef_plus_g)
+        })
+        .collect::<Vec<_>>();
+    limb_from_bits_le_recursive(builder, bits)
+}
+
+/// Computes the value of `maj(a, b, c)` from their bits: each bit is `ab + ac + bc - 2abc`.
+pub(crate) fn maj_gen<P: PackedField>(a: &[P; 32], b: &[P; 32], c: &[P; 32]) -> P {
+    limb_from_bits_le((0..32).map(|i| {
+        let (a, b, c) = (a[i], b[i], c[i]);
+        a * b + a * c + b * c - (a * b * c).doubles()
+    }))
+}
+
+pub(crate) fn maj_gen_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    a: &[ExtensionTarget<D>; 32],
+    b: &[ExtensionTarget<D>; 32],
+    c: &[ExtensionTarget<D>; 32],
+) -> ExtensionTarget<D> {
+    let bits = (0..32)
+        .map(|i| {
+            let (a, b, c) = (a[i], b[i], c[i]);
+            let ab = builder.mul_extension(a, b);
+            let sum = builder.mul_add_extension(a, c, ab);
+            let sum = builder.mul_add_extension(b, c, sum);
+            builder.arithmetic_extension(-F::TWO, F::ONE, ab, c, sum)
+        })
+        .collect::<Vec<_>>();
+    limb_from_bits_le_recursive(builder, bits)
+}
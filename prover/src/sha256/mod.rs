@@ -0,0 +1,4 @@
+pub mod columns;
+pub mod logic;
+pub mod sha_compress_stark;
+pub mod sha_extend_stark;
@@ -0,0 +1,524 @@
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::field::packed::PackedField;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::Field;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+use plonky2::timed;
+use plonky2::util::timing::TimingTree;
+
+use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::cross_table_lookup::{Column, Filter};
+use crate::evaluation_frame::{StarkEvaluationFrame, StarkFrame};
+use crate::memory::segments::Segment;
+use crate::sha256::columns::*;
+use crate::sha256::logic::{
+    ch, ch_gen, ch_gen_circuit, maj, maj_gen, maj_gen_circuit, sigma, sigma_gen, sigma_gen_circuit,
+    u32_to_bits_le, BIG_SIGMA0, BIG_SIGMA1,
+};
+use crate::stark::Stark;
+use crate::util::{limb_from_bits_le, limb_from_bits_le_recursive, trace_rows_to_poly_values};
+use zkm_emulator::sha256::ROUND_CONSTANTS;
+
+/// `w[r]` is read on the row of each round, the hash state on the first row of a call, and the
+/// new hash state written on its last row.
+pub(crate) const NUM_MEMORY_CTLS: usize = 17;
+
+pub(crate) fn ctl_looked_data<F: Field>() -> Vec<Column<F>> {
+    let cols = SHA_COMPRESS_COL_MAP;
+    Column::singles([cols.w_ptr, cols.h_ptr, cols.timestamp]).collect()
+}
+
+pub(crate) fn ctl_looked_filter<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(SHA_COMPRESS_COL_MAP.step[0]))
+}
+
+pub(crate) fn ctl_looking_memory<F: Field>(i: usize) -> Vec<Column<F>> {
+    let cols = SHA_COMPRESS_COL_MAP;
+
+    let (is_read, virt, value, timestamp) = if i == 0 {
+        // The row of round `r` reads `w[r]`.
+        let virt = Column::linear_combination(
+            cols.step[..NUM_COMPRESS_ROUNDS]
+                .iter()
+                .enumerate()
+                .map(|(r, &c)| (c, F::from_canonical_usize(4 * r)))
+                .chain([(cols.w_ptr, F::ONE)]),
+        );
+        (F::ONE, virt, Column::single(cols.w), F::ZERO)
+    } else if i <= 8 {
+        let j = i - 1;
+        let virt = Column::linear_combination_with_constant(
+            [(cols.h_ptr, F::ONE)],
+            F::from_canonical_usize(4 * j),
+        );
+        (F::ONE, virt, Column::single(cols.h[j]), F::ZERO)
+    } else {
+        let j = i - 9;
+        let virt = Column::linear_combination_with_constant(
+            [(cols.h_ptr, F::ONE)],
+            F::from_canonical_usize(4 * j),
+        );
+        (F::ZERO, virt, Column::le_bits(cols.state[j]), F::ONE)
+    };
+
+    let res = vec![
+        Column::constant(is_read),
+        Column::zero(), // context
+        Column::constant(F::from_canonical_usize(Segment::Code as usize)),
+        virt,
+        value,
+        Column::linear_combination_with_constant([(cols.timestamp, F::ONE)], timestamp),
+    ];
+
+    assert_eq!(
+        res.len(),
+        crate::memory::memory_stark::ctl_data::<F>().len()
+    );
+    res
+}
+
+pub(crate) fn ctl_looking_memory_filter<F: Field>(i: usize) -> Filter<F> {
+    let cols = SHA_COMPRESS_COL_MAP;
+    if i == 0 {
+        Filter::new_simple(Column::sum(&cols.step[..NUM_COMPRESS_ROUNDS]))
+    } else if i <= 8 {
+        Filter::new_simple(Column::single(cols.step[0]))
+    } else {
+        Filter::new_simple(Column::single(cols.step[NUM_COMPRESS_ROUNDS]))
+    }
+}
+
+/// Information about a `SHA256_COMPRESS` call.
+#[derive(Clone, Debug)]
+pub(crate) struct ShaCompressOp {
+    /// The address of `w`.
+    pub(crate) w_ptr: usize,
+    /// The address of the hash state.
+    pub(crate) h_ptr: usize,
+    /// The timestamp of the CPU row making the call.
+    pub(crate) timestamp: usize,
+    /// The message schedule.
+    pub(crate) w: [u32; NUM_COMPRESS_ROUNDS],
+    /// The hash state before the call.
+    pub(crate) h: [u32; 8],
+}
+
+/// A round on the working variables, with the sums giving the new `a` and `e` before their
+/// reduction modulo 2^32.
+fn round(vars: [u32; 8], k: u32, w: u32) -> (u64, u64) {
+    let [a, b, c, d, e, f, g, h] = vars.map(|v| v as u64);
+    let t1 = h
+        + sigma(BIG_SIGMA1, e as u32) as u64
+        + ch(e as u32, f as u32, g as u32) as u64
+        + k as u64
+        + w as u64;
+    let t2 = sigma(BIG_SIGMA0, a as u32) as u64 + maj(a as u32, b as u32, c as u32) as u64;
+    (t1 + t2, d + t1)
+}
+
+#[derive(Copy, Clone, Default)]
+pub struct ShaCompressStark<F, const D: usize> {
+    f: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> ShaCompressStark<F, D> {
+    pub(crate) fn generate_trace(
+        &self,
+        operations: Vec<ShaCompressOp>,
+        min_rows: usize,
+        timing: &mut TimingTree,
+    ) -> Vec<PolynomialValues<F>> {
+        let trace_rows = timed!(
+            timing,
+            "generate trace rows",
+            self.generate_trace_rows(operations, min_rows)
+        );
+        timed!(
+            timing,
+            "convert to PolynomialValues",
+            trace_rows_to_poly_values(trace_rows)
+        )
+    }
+
+    fn generate_trace_rows(
+        &self,
+        operations: Vec<ShaCompressOp>,
+        min_rows: usize,
+    ) -> Vec<[F; NUM_SHA_COMPRESS_COLUMNS]> {
+        let num_rows = (operations.len() * NUM_COMPRESS_ROWS)
+            .max(min_rows)
+            .next_power_of_two();
+        let mut rows = Vec::with_capacity(num_rows);
+        for op in operations {
+            rows.extend(self.generate_rows_for_op(op));
+        }
+        rows.resize(num_rows, [F::ZERO; NUM_SHA_COMPRESS_COLUMNS]);
+        rows
+    }
+
+    fn generate_rows_for_op(&self, op: ShaCompressOp) -> Vec<[F; NUM_SHA_COMPRESS_COLUMNS]> {
+        let mut rows = Vec::with_capacity(NUM_COMPRESS_ROWS);
+        let mut vars = op.h;
+        for r in 0..NUM_COMPRESS_ROWS {
+            let mut row = ShaCompressColumnsView::default();
+            row.step[r] = F::ONE;
+            row.timestamp = F::from_canonical_usize(op.timestamp);
+            row.w_ptr = F::from_canonical_usize(op.w_ptr);
+            row.h_ptr = F::from_canonical_usize(op.h_ptr);
+            row.h = op.h.map(F::from_canonical_u32);
+
+            // The last row holds the new hash state, and goes through a round of its own that
+            // only keeps the round constraints satisfied.
+            let (k, w) = if r < NUM_COMPRESS_ROUNDS {
+                (ROUND_CONSTANTS[r], op.w[r])
+            } else {
+                for j in 0..8 {
+                    let sum = op.h[j] as u64 + vars[j] as u64;
+                    row.carry_h[j] = F::from_canonical_u64(sum >> 32);
+                    vars[j] = sum as u32;
+                }
+                (0, 0)
+            };
+            row.state = vars.map(u32_to_bits_le);
+            row.w = F::from_canonical_u32(w);
+
+            let (new_a, new_e) = round(vars, k, w);
+            row.new_a = u32_to_bits_le(new_a as u32);
+            row.new_e = u32_to_bits_le(new_e as u32);
+            row.carry_a = core::array::from_fn(|j| F::from_bool((new_a >> (32 + j)) & 1 == 1));
+            row.carry_e = core::array::from_fn(|j| F::from_bool((new_e >> (32 + j)) & 1 == 1));
+            rows.push(row.into());
+
+            let [a, b, c, _, e, f, g, _] = vars;
+            vars = [new_a as u32, a, b, c, new_e as u32, e, f, g];
+        }
+        rows
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for ShaCompressStark<F, D> {
+    type EvaluationFrame<FE, P, const D2: usize>
+        = StarkFrame<P, NUM_SHA_COMPRESS_COLUMNS>
+    where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>;
+
+    type EvaluationFrameTarget = StarkFrame<ExtensionTarget<D>, NUM_SHA_COMPRESS_COLUMNS>;
+
+    fn eval_packed_generic<FE, P, const D2: usize>(
+        &self,
+        vars: &Self::EvaluationFrame<FE, P, D2>,
+        yield_constr: &mut ConstraintConsumer<P>,
+    ) where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>,
+    {
+        let local_values: &[P; NUM_SHA_COMPRESS_COLUMNS] =
+            vars.get_local_values().try_into().unwrap();
+        let lv: &ShaCompressColumnsView<P> = local_values.borrow();
+        let next_values: &[P; NUM_SHA_COMPRESS_COLUMNS] =
+            vars.get_next_values().try_into().unwrap();
+        let nv: &ShaCompressColumnsView<P> = next_values.borrow();
+
+        let two_pow_32 = FE::from_canonical_u64(1 << 32);
+
+        // The step flags are boolean, and at most one of them is set.
+        for &step in lv.step.iter() {
+            yield_constr.constraint(step * (step - P::ONES));
+        }
+        let is_real: P = lv.step.iter().copied().sum();
+        yield_constr.constraint(is_real * (is_real - P::ONES));
+
+        // A call starts with its first round and is not cut off by the end of the trace, and
+        // the steps follow each other.
+        yield_constr.constraint_first_row(lv.step[1..].iter().copied().sum::<P>());
+        yield_constr.constraint_last_row(lv.step[..NUM_COMPRESS_ROUNDS].iter().copied().sum::<P>());
+        for r in 0..NUM_COMPRESS_ROUNDS {
+            yield_constr.constraint_transition(nv.step[r + 1] - lv.step[r]);
+        }
+
+        for &bit in lv
+            .state
+            .iter()
+            .flatten()
+            .chain(&lv.new_a)
+            .chain(&lv.new_e)
+            .chain(&lv.carry_a)
+            .chain(&lv.carry_e)
+            .chain(&lv.carry_h)
+        {
+            yield_constr.constraint(bit * (bit - P::ONES));
+        }
+
+        // The first round starts from the hash state read.
+        for j in 0..8 {
+            yield_constr.constraint(lv.step[0] * (lv.h[j] - limb_from_bits_le(lv.state[j])));
+        }
+
+        // Within a call, the addresses, the timestamp and the hash state read stay the same.
+        let in_call: P = lv.step[..NUM_COMPRESS_ROUNDS].iter().copied().sum();
+        yield_constr.constraint_transition(in_call * (nv.timestamp - lv.timestamp));
+        yield_constr.constraint_transition(in_call * (nv.w_ptr - lv.w_ptr));
+        yield_constr.constraint_transition(in_call * (nv.h_ptr - lv.h_ptr));
+        for j in 0..8 {
+            yield_constr.constraint_transition(in_call * (nv.h[j] - lv.h[j]));
+        }
+
+        // The working variables after the round: (new_a, a, b, c, new_e, e, f, g).
+        let shifted = [
+            &lv.new_a,
+            &lv.state[0],
+            &lv.state[1],
+            &lv.state[2],
+            &lv.new_e,
+            &lv.state[4],
+            &lv.state[5],
+            &lv.state[6],
+        ];
+
+        // Between the rounds, they go to the next row as they are.
+        let in_rounds: P = lv.step[..NUM_COMPRESS_ROUNDS - 1].iter().copied().sum();
+        for (next, local) in nv.state.iter().zip(shifted) {
+            for (&next_bit, &bit) in next.iter().zip(local) {
+                yield_constr.constraint_transition(in_rounds * (next_bit - bit));
+            }
+        }
+
+        // After the last round, they are added to the hash state read.
+        let last_round = lv.step[NUM_COMPRESS_ROUNDS - 1];
+        for (j, local) in shifted.into_iter().enumerate() {
+            let sum = limb_from_bits_le(nv.state[j]) + nv.carry_h[j] * two_pow_32;
+            yield_constr
+                .constraint_transition(last_round * (sum - lv.h[j] - limb_from_bits_le(*local)));
+        }
+
+        // The round itself. It also holds on the last row, with a zero round constant, and on
+        // padding rows, which are zero.
+        let [a, b, c, d, e, f, g, h] = &lv.state;
+        let k: P = lv.step[..NUM_COMPRESS_ROUNDS]
+            .iter()
+            .zip(ROUND_CONSTANTS)
+            .map(|(&step, k)| step * FE::from_canonical_u32(k))
+            .sum();
+        let t1 = limb_from_bits_le(*h) + sigma_gen(BIG_SIGMA1, e) + ch_gen(e, f, g) + k + lv.w;
+        let t2 = sigma_gen(BIG_SIGMA0, a) + maj_gen(a, b, c);
+
+        let new_a = limb_from_bits_le(lv.new_a) + limb_from_bits_le(lv.carry_a) * two_pow_32;
+        yield_constr.constraint(new_a - t1 - t2);
+        let new_e = limb_from_bits_le(lv.new_e) + limb_from_bits_le(lv.carry_e) * two_pow_32;
+        yield_constr.constraint(new_e - limb_from_bits_le(*d) - t1);
+    }
+
+    fn eval_ext_circuit(
+        &self,
+        builder: &mut plonky2::plonk::circuit_builder::CircuitBuilder<F, D>,
+        vars: &Self::EvaluationFrameTarget,
+        yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+    ) {
+        let local_values: &[ExtensionTarget<D>; NUM_SHA_COMPRESS_COLUMNS] =
+            vars.get_local_values().try_into().unwrap();
+        let lv: &ShaCompressColumnsView<ExtensionTarget<D>> = local_values.borrow();
+        let next_values: &[ExtensionTarget<D>; NUM_SHA_COMPRESS_COLUMNS] =
+            vars.get_next_values().try_into().unwrap();
+        let nv: &ShaCompressColumnsView<ExtensionTarget<D>> = next_values.borrow();
+
+        let two_pow_32 = F::from_canonical_u64(1 << 32);
+
+        for &step in lv.step.iter() {
+            let constraint = builder.mul_sub_extension(step, step, step);
+            yield_constr.constraint(builder, constraint);
+        }
+        let is_real = builder.add_many_extension(lv.step);
+        let constraint = builder.mul_sub_extension(is_real, is_real, is_real);
+        yield_constr.constraint(builder, constraint);
+
+        let constraint = builder.add_many_extension(&lv.step[1..]);
+        yield_constr.constraint_first_row(builder, constraint);
+        let constraint = builder.add_many_extension(&lv.step[..NUM_COMPRESS_ROUNDS]);
+        yield_constr.constraint_last_row(builder, constraint);
+        for r in 0..NUM_COMPRESS_ROUNDS {
+            let constraint = builder.sub_extension(nv.step[r + 1], lv.step[r]);
+            yield_constr.constraint_transition(builder, constraint);
+        }
+
+        for &bit in lv
+            .state
+            .iter()
+            .flatten()
+            .chain(&lv.new_a)
+            .chain(&lv.new_e)
+            .chain(&lv.carry_a)
+            .chain(&lv.carry_e)
+            .chain(&lv.carry_h)
+        {
+            let constraint = builder.mul_sub_extension(bit, bit, bit);
+            yield_constr.constraint(builder, constraint);
+        }
+
+        for j in 0..8 {
+            let word = limb_from_bits_le_recursive(builder, lv.state[j]);
+            let diff = builder.sub_extension(lv.h[j], word);
+            let constraint = builder.mul_extension(lv.step[0], diff);
+            yield_constr.constraint(builder, constraint);
+        }
+
+        let in_call = builder.add_many_extension(&lv.step[..NUM_COMPRESS_ROUNDS]);
+        for (next, local) in [
+            (nv.timestamp, lv.timestamp),
+            (nv.w_ptr, lv.w_ptr),
+            (nv.h_ptr, lv.h_ptr),
+        ]
+        .into_iter()
+        .chain(nv.h.into_iter().zip(lv.h))
+        {
+            let diff = builder.sub_extension(next, local);
+            let constraint = builder.mul_extension(in_call, diff);
+            yield_constr.constraint_transition(builder, constraint);
+        }
+
+        let shifted = [
+            &lv.new_a,
+            &lv.state[0],
+            &lv.state[1],
+            &lv.state[2],
+            &lv.new_e,
+            &lv.state[4],
+            &lv.state[5],
+            &lv.state[6],
+        ];
+
+        let in_rounds = builder.add_many_extension(&lv.step[..NUM_COMPRESS_ROUNDS - 1]);
+        for (next, local) in nv.state.iter().zip(shifted) {
+            for (&next_bit, &bit) in next.iter().zip(local) {
+                let diff = builder.sub_extension(next_bit, bit);
+                let constraint = builder.mul_extension(in_rounds, diff);
+                yield_constr.constraint_transition(builder, constraint);
+            }
+        }
+
+        let last_round = lv.step[NUM_COMPRESS_ROUNDS - 1];
+        for (j, local) in shifted.into_iter().enumerate() {
+            let next_word = limb_from_bits_le_recursive(builder, nv.state[j]);
+            let sum = builder.mul_const_add_extension(two_pow_32, nv.carry_h[j], next_word);
+            let word = limb_from_bits_le_recursive(builder, *local);
+            let diff = builder.sub_extension(sum, lv.h[j]);
+            let diff = builder.sub_extension(diff, word);
+            let constraint = builder.mul_extension(last_round, diff);
+            yield_constr.constraint_transition(builder, constraint);
+        }
+
+        let [a, b, c, d, e, f, g, h] = &lv.state;
+        let k = lv.step[..NUM_COMPRESS_ROUNDS]
+            .iter()
+            .zip(ROUND_CONSTANTS)
+            .fold(builder.zero_extension(), |acc, (&step, k)| {
+                builder.mul_const_add_extension(F::from_canonical_u32(k), step, acc)
+            });
+        let h = limb_from_bits_le_recursive(builder, *h);
+        let s1 = sigma_gen_circuit(builder, BIG_SIGMA1, e);
+        let ch = ch_gen_circuit(builder, e, f, g);
+        let t1 = builder.add_many_extension([h, s1, ch, k, lv.w]);
+        let s0 = sigma_gen_circuit(builder, BIG_SIGMA0, a);
+        let maj = maj_gen_circuit(builder, a, b, c);
+
+        let new_a = limb_from_bits_le_recursive(builder, lv.new_a);
+        let carry_a = limb_from_bits_le_recursive(builder, lv.carry_a);
+        let new_a = builder.mul_const_add_extension(two_pow_32, carry_a, new_a);
+        let constraint = builder.sub_extension(new_a, t1);
+        let constraint = builder.sub_extension(constraint, s0);
+        let constraint = builder.sub_extension(constraint, maj);
+        yield_constr.constraint(builder, constraint);
+
+        let new_e = limb_from_bits_le_recursive(builder, lv.new_e);
+        let carry_e = limb_from_bits_le_recursive(builder, lv.carry_e);
+        let new_e = builder.mul_const_add_extension(two_pow_32, carry_e, new_e);
+        let d = limb_from_bits_le_recursive(builder, *d);
+        let constraint = builder.sub_extension(new_e, d);
+        let constraint = builder.sub_extension(constraint, t1);
+        yield_constr.constraint(builder, constraint);
+    }
+
+    fn constraint_degree(&self) -> usize {
+        3
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Borrow;
+
+    use anyhow::Result;
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::types::PrimeField64;
+    use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    use crate::sha256::columns::ShaCompressColumnsView;
+    use crate::sha256::sha_compress_stark::{ShaCompressOp, ShaCompressStark};
+    use crate::stark_testing::{test_stark_circuit_constraints, test_stark_low_degree};
+    use crate::util::limb_from_bits_le;
+    use zkm_emulator::sha256;
+
+    #[test]
+    fn test_stark_degree() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = ShaCompressStark<F, D>;
+
+        let stark = S::default();
+        test_stark_low_degree(stark)
+    }
+
+    #[test]
+    fn test_stark_circuit() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = ShaCompressStark<F, D>;
+
+        let stark = S::default();
+        test_stark_circuit_constraints::<F, C, S, D>(stark)
+    }
+
+    #[test]
+    fn test_generation() -> Result<()> {
+        const D: usize = 2;
+        type F = GoldilocksField;
+        type S = ShaCompressStark<F, D>;
+
+        // The padded block of "abc".
+        let mut w = [0u32; 64];
+        w[0] = 0x61626380;
+        w[15] = 0x18;
+        sha256::extend(&mut w);
+
+        let op = ShaCompressOp {
+            w_ptr: 0x1000,
+            h_ptr: 0x2000,
+            timestamp: 0,
+            w,
+            h: [
+                0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+                0x5be0cd19,
+            ],
+        };
+        let stark = S::default();
+        let rows = stark.generate_rows_for_op(op);
+        let last_row: &ShaCompressColumnsView<F> = rows.last().unwrap().borrow();
+        let digest = last_row
+            .state
+            .map(|bits| limb_from_bits_le(bits).to_canonical_u64() as u32);
+        assert_eq!(
+            digest,
+            [
+                0xba7816bf, 0x8f01cfea, 0x414140de, 0x5dae2223, 0xb00361a3, 0x96177a9c, 0xb410ff61,
+                0xf20015ad,
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,359 @@
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use itertools::Itertools;
+use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::field::packed::PackedField;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::Field;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+use plonky2::timed;
+use plonky2::util::timing::TimingTree;
+
+use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::cross_table_lookup::{Column, Filter};
+use crate::evaluation_frame::{StarkEvaluationFrame, StarkFrame};
+use crate::memory::segments::Segment;
+use crate::sha256::columns::*;
+use crate::sha256::logic::{
+    sigma, sigma_gen, sigma_gen_circuit, u32_to_bits_le, SMALL_SIGMA0, SMALL_SIGMA1,
+};
+use crate::stark::Stark;
+use crate::util::{limb_from_bits_le, limb_from_bits_le_recursive, trace_rows_to_poly_values};
+
+/// The sixteen words of the chunk are read on the first row of a call, and each row writes the
+/// word it computes.
+pub(crate) const NUM_MEMORY_CTLS: usize = 17;
+
+pub(crate) fn ctl_looked_data<F: Field>() -> Vec<Column<F>> {
+    let cols = SHA_EXTEND_COL_MAP;
+    Column::singles([cols.w_ptr, cols.timestamp]).collect()
+}
+
+pub(crate) fn ctl_looked_filter<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(SHA_EXTEND_COL_MAP.step[0]))
+}
+
+pub(crate) fn ctl_looking_memory<F: Field>(i: usize) -> Vec<Column<F>> {
+    let cols = SHA_EXTEND_COL_MAP;
+
+    let res = if i < 16 {
+        vec![
+            Column::constant(F::ONE), // is_read
+            Column::zero(),           // context
+            Column::constant(F::from_canonical_usize(Segment::Code as usize)),
+            Column::linear_combination_with_constant(
+                [(cols.w_ptr, F::ONE)],
+                F::from_canonical_usize(4 * i),
+            ),
+            Column::le_bits(cols.window[i]),
+            Column::single(cols.timestamp),
+        ]
+    } else {
+        // The row of `step[j]` writes `w[16 + j]`.
+        let virt = Column::linear_combination(
+            cols.step
+                .iter()
+                .enumerate()
+                .map(|(j, &c)| (c, F::from_canonical_usize(4 * (16 + j))))
+                .chain([(cols.w_ptr, F::ONE)]),
+        );
+        vec![
+            Column::zero(), // is_read
+            Column::zero(), // context
+            Column::constant(F::from_canonical_usize(Segment::Code as usize)),
+            virt,
+            Column::le_bits(cols.w_i),
+            Column::linear_combination_with_constant([(cols.timestamp, F::ONE)], F::ONE),
+        ]
+    };
+
+    assert_eq!(
+        res.len(),
+        crate::memory::memory_stark::ctl_data::<F>().len()
+    );
+    res
+}
+
+pub(crate) fn ctl_looking_memory_filter<F: Field>(i: usize) -> Filter<F> {
+    let cols = SHA_EXTEND_COL_MAP;
+    if i < 16 {
+        Filter::new_simple(Column::single(cols.step[0]))
+    } else {
+        Filter::new_simple(Column::sum(cols.step))
+    }
+}
+
+/// Information about a `SHA256_EXTEND` call.
+#[derive(Clone, Debug)]
+pub(crate) struct ShaExtendOp {
+    /// The address of `w`.
+    pub(crate) w_ptr: usize,
+    /// The timestamp of the CPU row making the call.
+    pub(crate) timestamp: usize,
+    /// The first sixteen words of `w`.
+    pub(crate) input: [u32; 16],
+}
+
+#[derive(Copy, Clone, Default)]
+pub struct ShaExtendStark<F, const D: usize> {
+    f: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> ShaExtendStark<F, D> {
+    pub(crate) fn generate_trace(
+        &self,
+        operations: Vec<ShaExtendOp>,
+        min_rows: usize,
+        timing: &mut TimingTree,
+    ) -> Vec<PolynomialValues<F>> {
+        let trace_rows = timed!(
+            timing,
+            "generate trace rows",
+            self.generate_trace_rows(operations, min_rows)
+        );
+        timed!(
+            timing,
+            "convert to PolynomialValues",
+            trace_rows_to_poly_values(trace_rows)
+        )
+    }
+
+    fn generate_trace_rows(
+        &self,
+        operations: Vec<ShaExtendOp>,
+        min_rows: usize,
+    ) -> Vec<[F; NUM_SHA_EXTEND_COLUMNS]> {
+        let num_rows = (operations.len() * NUM_EXTEND_ROWS)
+            .max(min_rows)
+            .next_power_of_two();
+        let mut rows = Vec::with_capacity(num_rows);
+        for op in operations {
+            rows.extend(self.generate_rows_for_op(op));
+        }
+        rows.resize(num_rows, [F::ZERO; NUM_SHA_EXTEND_COLUMNS]);
+        rows
+    }
+
+    fn generate_rows_for_op(&self, op: ShaExtendOp) -> Vec<[F; NUM_SHA_EXTEND_COLUMNS]> {
+        let mut w = [0; 64];
+        w[..16].copy_from_slice(&op.input);
+        zkm_emulator::sha256::extend(&mut w);
+        (0..NUM_EXTEND_ROWS)
+            .map(|i| {
+                let mut row = ShaExtendColumnsView::default();
+                row.step[i] = F::ONE;
+                row.timestamp = F::from_canonical_usize(op.timestamp);
+                row.w_ptr = F::from_canonical_usize(op.w_ptr);
+                for (bits, &word) in row.window.iter_mut().zip(&w[i..i + 16]) {
+                    *bits = u32_to_bits_le(word);
+                }
+                row.w_i = u32_to_bits_le(w[16 + i]);
+                let sum = sigma(SMALL_SIGMA1, w[i + 14]) as u64
+                    + w[i + 9] as u64
+                    + sigma(SMALL_SIGMA0, w[i + 1]) as u64
+                    + w[i] as u64;
+                let carry = sum >> 32;
+                row.carry = [0, 1].map(|j| F::from_bool((carry >> j) & 1 == 1));
+                row.into()
+            })
+            .collect_vec()
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for ShaExtendStark<F, D> {
+    type EvaluationFrame<FE, P, const D2: usize>
+        = StarkFrame<P, NUM_SHA_EXTEND_COLUMNS>
+    where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>;
+
+    type EvaluationFrameTarget = StarkFrame<ExtensionTarget<D>, NUM_SHA_EXTEND_COLUMNS>;
+
+    fn eval_packed_generic<FE, P, const D2: usize>(
+        &self,
+        vars: &Self::EvaluationFrame<FE, P, D2>,
+        yield_constr: &mut ConstraintConsumer<P>,
+    ) where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>,
+    {
+        let local_values: &[P; NUM_SHA_EXTEND_COLUMNS] =
+            vars.get_local_values().try_into().unwrap();
+        let lv: &ShaExtendColumnsView<P> = local_values.borrow();
+        let next_values: &[P; NUM_SHA_EXTEND_COLUMNS] = vars.get_next_values().try_into().unwrap();
+        let nv: &ShaExtendColumnsView<P> = next_values.borrow();
+
+        // The step flags are boolean, and at most one of them is set.
+        for &step in lv.step.iter() {
+            yield_constr.constraint(step * (step - P::ONES));
+        }
+        let is_real: P = lv.step.iter().copied().sum();
+        yield_constr.constraint(is_real * (is_real - P::ONES));
+
+        // A call starts with its first step and is not cut off by the end of the trace, and
+        // the steps follow each other.
+        yield_constr.constraint_first_row(lv.step[1..].iter().copied().sum::<P>());
+        yield_constr.constraint_last_row(lv.step[..NUM_EXTEND_ROWS - 1].iter().copied().sum::<P>());
+        for i in 0..NUM_EXTEND_ROWS - 1 {
+            yield_constr.constraint_transition(nv.step[i + 1] - lv.step[i]);
+        }
+
+        for &bit in lv.window.iter().flatten().chain(&lv.w_i).chain(&lv.carry) {
+            yield_constr.constraint(bit * (bit - P::ONES));
+        }
+
+        // Within a call, the window slides by one word, taking in the word just computed.
+        let in_call: P = lv.step[..NUM_EXTEND_ROWS - 1].iter().copied().sum();
+        yield_constr.constraint_transition(in_call * (nv.timestamp - lv.timestamp));
+        yield_constr.constraint_transition(in_call * (nv.w_ptr - lv.w_ptr));
+        for i in 0..16 {
+            let incoming = if i < 15 { &lv.window[i + 1] } else { &lv.w_i };
+            for (&next_bit, &bit) in nv.window[i].iter().zip(incoming) {
+                yield_constr.constraint_transition(in_call * (next_bit - bit));
+            }
+        }
+
+        // w[16 + i] = sigma1(w[14 + i]) + w[9 + i] + sigma0(w[1 + i]) + w[i], modulo 2^32. This
+        // also holds on padding rows, which are zero.
+        let sum = sigma_gen(SMALL_SIGMA1, &lv.window[14])
+            + limb_from_bits_le(lv.window[9])
+            + sigma_gen(SMALL_SIGMA0, &lv.window[1])
+            + limb_from_bits_le(lv.window[0]);
+        let carry = lv.carry[0] + lv.carry[1].doubles();
+        let w_i = limb_from_bits_le(lv.w_i);
+        yield_constr.constraint(w_i + carry * FE::from_canonical_u64(1 << 32) - sum);
+    }
+
+    fn eval_ext_circuit(
+        &self,
+        builder: &mut plonky2::plonk::circuit_builder::CircuitBuilder<F, D>,
+        vars: &Self::EvaluationFrameTarget,
+        yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+    ) {
+        let local_values: &[ExtensionTarget<D>; NUM_SHA_EXTEND_COLUMNS] =
+            vars.get_local_values().try_into().unwrap();
+        let lv: &ShaExtendColumnsView<ExtensionTarget<D>> = local_values.borrow();
+        let next_values: &[ExtensionTarget<D>; NUM_SHA_EXTEND_COLUMNS] =
+            vars.get_next_values().try_into().unwrap();
+        let nv: &ShaExtendColumnsView<ExtensionTarget<D>> = next_values.borrow();
+
+        for &step in lv.step.iter() {
+            let constraint = builder.mul_sub_extension(step, step, step);
+            yield_constr.constraint(builder, constraint);
+        }
+        let is_real = builder.add_many_extension(lv.step);
+        let constraint = builder.mul_sub_extension(is_real, is_real, is_real);
+        yield_constr.constraint(builder, constraint);
+
+        let constraint = builder.add_many_extension(&lv.step[1..]);
+        yield_constr.constraint_first_row(builder, constraint);
+        let constraint = builder.add_many_extension(&lv.step[..NUM_EXTEND_ROWS - 1]);
+        yield_constr.constraint_last_row(builder, constraint);
+        for i in 0..NUM_EXTEND_ROWS - 1 {
+            let constraint = builder.sub_extension(nv.step[i + 1], lv.step[i]);
+            yield_constr.constraint_transition(builder, constraint);
+        }
+
+        for &bit in lv.window.iter().flatten().chain(&lv.w_i).chain(&lv.carry) {
+            let constraint = builder.mul_sub_extension(bit, bit, bit);
+            yield_constr.constraint(builder, constraint);
+        }
+
+        let in_call = builder.add_many_extension(&lv.step[..NUM_EXTEND_ROWS - 1]);
+        for (next, local) in [(nv.timestamp, lv.timestamp), (nv.w_ptr, lv.w_ptr)] {
+            let diff = builder.sub_extension(next, local);
+            let constraint = builder.mul_extension(in_call, diff);
+            yield_constr.constraint_transition(builder, constraint);
+        }
+        for i in 0..16 {
+            let incoming = if i < 15 { &lv.window[i + 1] } else { &lv.w_i };
+            for (&next_bit, &bit) in nv.window[i].iter().zip(incoming) {
+                let diff = builder.sub_extension(next_bit, bit);
+                let constraint = builder.mul_extension(in_call, diff);
+                yield_constr.constraint_transition(builder, constraint);
+            }
+        }
+
+        let s1 = sigma_gen_circuit(builder, SMALL_SIGMA1, &lv.window[14]);
+        let w9 = limb_from_bits_le_recursive(builder, lv.window[9]);
+        let s0 = sigma_gen_circuit(builder, SMALL_SIGMA0, &lv.window[1]);
+        let w0 = limb_from_bits_le_recursive(builder, lv.window[0]);
+        let sum = builder.add_many_extension([s1, w9, s0, w0]);
+        let carry = builder.mul_const_add_extension(F::TWO, lv.carry[1], lv.carry[0]);
+        let w_i = limb_from_bits_le_recursive(builder, lv.w_i);
+        let constraint =
+            builder.mul_const_add_extension(F::from_canonical_u64(1 << 32), carry, w_i);
+        let constraint = builder.sub_extension(constraint, sum);
+        yield_constr.constraint(builder, constraint);
+    }
+
+    fn constraint_degree(&self) -> usize {
+        3
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Borrow;
+
+    use anyhow::Result;
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::types::PrimeField64;
+    use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    use crate::sha256::columns::ShaExtendColumnsView;
+    use crate::sha256::sha_extend_stark::{ShaExtendOp, ShaExtendStark};
+    use crate::stark_testing::{test_stark_circuit_constraints, test_stark_low_degree};
+    use crate::util::limb_from_bits_le;
+
+    #[test]
+    fn test_stark_degree() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = ShaExtendStark<F, D>;
+
+        let stark = S::default();
+        test_stark_low_degree(stark)
+    }
+
+    #[test]
+    fn test_stark_circuit() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = ShaExtendStark<F, D>;
+
+        let stark = S::default();
+        test_stark_circuit_constraints::<F, C, S, D>(stark)
+    }
+
+    #[test]
+    fn test_generation() -> Result<()> {
+        const D: usize = 2;
+        type F = GoldilocksField;
+        type S = ShaExtendStark<F, D>;
+
+        // The padded block of "abc".
+        let mut input = [0u32; 16];
+        input[0] = 0x61626380;
+        input[15] = 0x18;
+
+        let op = ShaExtendOp {
+            w_ptr: 0x1000,
+            timestamp: 0,
+            input,
+        };
+        let stark = S::default();
+        let rows = stark.generate_rows_for_op(op);
+        let last_row: &ShaExtendColumnsView<F> = rows.last().unwrap().borrow();
+        let w_63 = limb_from_bits_le(last_row.w_i).to_canonical_u64();
+        assert_eq!(w_63, 0x12b1edeb);
+        let first_row: &ShaExtendColumnsView<F> = rows[0].borrow();
+        let w_16 = limb_from_bits_le(first_row.w_i).to_canonical_u64();
+        assert_eq!(w_16, 0x61626380);
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+//! A Solidity contract verifying the Groth16 proofs of a program.
+//!
+//! The verifying key is the one the gnark prover derives from the wrapped circuit, encoded as in
+//! `zkm_verifier::groth16`: `alpha_g1 || beta_g2 || gamma_g2 || delta_g2 || ic`. The public inputs
+//! of the wrapped circuit start with the eight 32-bit limbs of the image id, which the contract
+//! has as constants, so it only accepts the proofs of the program it was exported for.
+
+use std::fmt::Write;
+
+use anyhow::{ensure, Result};
+
+const G1_LEN: usize = 64;
+const G2_LEN: usize = 128;
+
+/// The number of public inputs holding the image id, one per 32-bit limb.
+pub const IMAGE_ID_INPUTS: usize = 8;
+
+fn word(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// Writes the constants of a point of G2, as the pairing precompile takes it.
+fn g2_constants(out: &mut String, name: &str, bytes: &[u8]) {
+    for (i, part) in ["X_IM", "X_RE", "Y_IM", "Y_RE"].iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "    uint256 constant {}_{} = {};",
+            name,
+            part,
+            word(&bytes[i * 32..(i + 1) * 32])
+        );
+    }
+}
+
+/// Emits the contract verifying the proofs of the program `image_id` with the Groth16 key `vk`.
+pub fn export_verifier(vk: &[u8], image_id: &[u8; 32]) -> Result<String> {
+    let ic_start = G1_LEN + 3 * G2_LEN;
+    ensure!(
+        vk.len() > ic_start && (vk.len() - ic_start) % G1_LEN == 0,
+        "invalid Groth16 verifying key"
+    );
+    let ic: Vec<&[u8]> = vk[ic_start..].chunks_exact(G1_LEN).collect();
+    ensure!(
+        ic.len() > IMAGE_ID_INPUTS,
+        "the verifying key has fewer public inputs than the image id"
+    );
+    let num_inputs = ic.len() - 1 - IMAGE_ID_INPUTS;
+
+    let mut out = String::new();
+    out.push_str("// SPDX-License-Identifier: MIT\n");
+    out.push_str("// Generated by zkm_prover::solidity::export_verifier, do not edit.\n");
+    out.push_str("pragma solidity ^0.8.20;\n\n");
+    let _ = writeln!(
+        out,
+        "/// Verifies the Groth16 proofs of the zkMIPS program {}.",
+        word(image_id)
+    );
+    out.push_str("contract ZkmVerifier {\n");
+    let _ = writeln!(
+        out,
+        "    bytes32 public constant IMAGE_ID = {};\n",
+        word(image_id)
+    );
+    out.push_str("    uint256 constant P = 0x30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd47;\n");
+    out.push_str("    uint256 constant R = 0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001;\n\n");
+    let _ = writeln!(out, "    uint256 constant ALPHA_X = {};", word(&vk[..32]));
+    let _ = writeln!(out, "    uint256 constant ALPHA_Y = {};", word(&vk[32..64]));
+    for (i, name) in ["BETA", "GAMMA", "DELTA"].iter().enumerate() {
+        let start = G1_LEN + i * G2_LEN;
+        g2_constants(&mut out, name, &vk[start..start + G2_LEN]);
+    }
+    for (i, point) in ic.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "    uint256 constant IC{}_X = {};",
+            i,
+            word(&point[..32])
+        );
+        let _ = writeln!(
+            out,
+            "    uint256 constant IC{}_Y = {};",
+            i,
+            word(&point[32..])
+        );
+    }
+    out.push_str(
+        r#"
+    error InvalidProof();
+
+    function ecAdd(uint256[2] memory p, uint256 x, uint256 y) internal view {
+        uint256[4] memory input = [p[0], p[1], x, y];
+        bool ok;
+        assembly {
+            ok := staticcall(gas(), 0x06, input, 0x80, p, 0x40)
+        }
+        if (!ok) revert InvalidProof();
+    }
+
+    function ecMulAdd(uint256[2] memory p, uint256 x, uint256 y, uint256 s) internal view {
+        if (s >= R) revert InvalidProof();
+        uint256[3] memory input = [x, y, s];
+        uint256[2] memory product;
+        bool ok;
+        assembly {
+            ok := staticcall(gas(), 0x07, input, 0x60, product, 0x40)
+        }
+        if (!ok) revert InvalidProof();
+        ecAdd(p, product[0], product[1]);
+    }
+
+"#,
+    );
+    let _ = writeln!(
+        out,
+        "    /// Reverts unless `proof`, `a || b || c`, proves a run of the program whose public inputs\n    /// after the image id are `input`."
+    );
+    let _ = writeln!(
+        out,
+        "    function verifyProof(uint256[8] calldata proof, uint256[{}] calldata input) public view {{",
+        num_inputs
+    );
+    out.push_str("        uint256[2] memory x = [IC0_X, IC0_Y];\n");
+    for i in 0..IMAGE_ID_INPUTS {
+        let limb = u32::from_le_bytes(image_id[i * 4..(i + 1) * 4].try_into().unwrap());
+        let _ = writeln!(
+            out,
+            "        ecMulAdd(x, IC{}_X, IC{}_Y, {});",
+            i + 1,
+            i + 1,
+            limb
+        );
+    }
+    for i in 0..num_inputs {
+        let j = i + 1 + IMAGE_ID_INPUTS;
+        let _ = writeln!(
+            out,
+            "        ecMulAdd(x, IC{}_X, IC{}_Y, input[{}]);",
+            j, j, i
+        );
+    }
+    out.push_str(
+        r#"
+        // e(-a, b) * e(alpha, beta) * e(x, gamma) * e(c, delta) == 1
+        uint256[24] memory pairs = [
+            proof[0], proof[1] == 0 ? uint256(0) : P - proof[1], proof[2], proof[3], proof[4], proof[5],
+            ALPHA_X, ALPHA_Y, BETA_X_IM, BETA_X_RE, BETA_Y_IM, BETA_Y_RE,
+            x[0], x[1], GAMMA_X_IM, GAMMA_X_RE, GAMMA_Y_IM, GAMMA_Y_RE,
+            proof[6], proof[7], DELTA_X_IM, DELTA_X_RE, DELTA_Y_IM, DELTA_Y_RE
+        ];
+        uint256[1] memory result;
+        bool ok;
+        assembly {
+            ok := staticcall(gas(), 0x08, pairs, 0x300, result, 0x20)
+        }
+        if (!ok || result[0] != 1) revert InvalidProof();
+    }
+}
+"#,
+    );
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_verifier() {
+        let num_inputs = 3;
+        let vk = vec![0u8; G1_LEN + 3 * G2_LEN + (1 + IMAGE_ID_INPUTS + num_inputs) * G1_LEN];
+        let mut image_id = [0u8; 32];
+        image_id[..4].copy_from_slice(&7u32.to_le_bytes());
+        let contract = export_verifier(&vk, &image_id).unwrap();
+        assert!(contract.contains(&format!("IMAGE_ID = 0x07{}", "0".repeat(62))));
+        assert!(contract.contains("ecMulAdd(x, IC1_X, IC1_Y, 7);"));
+        assert!(contract.contains("uint256[3] calldata input"));
+        assert!(contract.contains("ecMulAdd(x, IC11_X, IC11_Y, input[2]);"));
+        assert!(!contract.contains("IC12_X"));
+
+        assert!(export_verifier(&vk[1..], &image_id).is_err());
+    }
+}
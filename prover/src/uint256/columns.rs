@@ -0,0 +1,73 @@
+use crate::util::impl_columns_view;
+
+/// The number of byte limbs of an integer.
+pub(crate) const UINT256_LIMBS: usize = 32;
+
+/// The quotient of a product of two integers by a nonzero modulus is below `2^512`.
+pub(crate) const QUOTIENT_LIMBS: usize = 2 * UINT256_LIMBS;
+
+/// `q(X) n(X)` has `QUOTIENT_LIMBS + UINT256_LIMBS - 1` coefficients, so `w(X)` has one less.
+pub(crate) const N_CARRIES: usize = QUOTIENT_LIMBS + UINT256_LIMBS - 2;
+
+/// A call to `UINT256_MULMOD`, computing `x * y mod n` for a modulus `n` read from memory.
+///
+/// The result `r` is checked with the identity `x(X) y(X) - q(X) n(X) - r(X) = (X - 2^8) w(X)`
+/// of the polynomials of the limbs, as in `crate::modular`, but for a modulus in columns and a
+/// nonnegative quotient.
+#[repr(C)]
+#[derive(Eq, PartialEq, Debug)]
+pub(crate) struct Uint256MulModColumnsView<T: Copy> {
+    /// 1 iff the row is a call. Padding rows are not.
+    pub is_real: T,
+
+    /// The timestamp of the CPU row making the call. The operands are read at this timestamp,
+    /// and the result written right after it.
+    pub timestamp: T,
+    /// The address of `x`, which the result replaces.
+    pub x_ptr: T,
+    /// The address of `y`, which the modulus follows.
+    pub y_ptr: T,
+
+    /// The limbs of the operands, which need not be reduced, of the modulus and of the result.
+    pub x: [T; UINT256_LIMBS],
+    pub y: [T; UINT256_LIMBS],
+    pub modulus: [T; UINT256_LIMBS],
+    pub result: [T; UINT256_LIMBS],
+
+    /// The limbs of `q`.
+    pub quotient: [T; QUOTIENT_LIMBS],
+    /// The low and high bytes of the coefficients of `w`, plus `2^15`.
+    pub carry_lo: [T; N_CARRIES],
+    pub carry_hi: [T; N_CARRIES],
+
+    /// The limbs of `n - 1 - r`, which shows `r < n` and so `n > 0`.
+    pub diff: [T; UINT256_LIMBS],
+    /// The carries of the limb-wise addition `r + diff + 1 = n`, as bits.
+    pub diff_carry: [T; UINT256_LIMBS - 1],
+
+    /// The bytes range checked by the table.
+    pub range_counter: T,
+    pub range_frequencies: T,
+}
+
+impl_columns_view!(
+    Uint256MulModColumnsView,
+    NUM_UINT256_MULMOD_COLUMNS,
+    UINT256_MULMOD_COL_MAP
+);
+
+impl<T: Copy> Uint256MulModColumnsView<T> {
+    /// The columns range checked in bytes.
+    pub(crate) fn bytes(&self) -> impl Iterator<Item = T> + '_ {
+        self.x
+            .iter()
+            .chain(&self.y)
+            .chain(&self.modulus)
+            .chain(&self.result)
+            .chain(&self.quotient)
+            .chain(&self.carry_lo)
+            .chain(&self.carry_hi)
+            .chain(&self.diff)
+            .copied()
+    }
+}
@@ -0,0 +1,2 @@
+pub mod columns;
+pub mod mulmod_stark;
@@ -0,0 +1,488 @@
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use itertools::Itertools;
+use num_bigint::BigUint;
+use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::field::packed::PackedField;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::{Field, PrimeField64};
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::timed;
+use plonky2::util::timing::TimingTree;
+use zkm_emulator::uint256::{self, Uint256};
+
+use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::cross_table_lookup::{Column, Filter};
+use crate::evaluation_frame::{StarkEvaluationFrame, StarkFrame};
+use crate::lookup::Lookup;
+use crate::memory::segments::Segment;
+use crate::modular::{
+    limbs_to_field, limbs_to_i64, pol_mul, pol_mul_ext_circuit, pol_sub, pol_sub_ext_circuit,
+    CARRY_OFFSET, LIMB_BITS,
+};
+use crate::stark::Stark;
+use crate::uint256::columns::*;
+use crate::util::trace_rows_to_poly_values;
+
+/// The number of words of an integer.
+const UINT256_WORDS: usize = UINT256_LIMBS / 4;
+
+/// The words read and written, eight each: `x`, `y`, the modulus, then the result.
+pub(crate) const NUM_MEMORY_CTLS: usize = 4 * UINT256_WORDS;
+
+/// Strict upper bound of the range checks.
+const RANGE_MAX: usize = 1 << LIMB_BITS;
+
+pub(crate) fn ctl_looked_data<F: Field>() -> Vec<Column<F>> {
+    let cols = UINT256_MULMOD_COL_MAP;
+    Column::singles([cols.x_ptr, cols.y_ptr, cols.timestamp]).collect()
+}
+
+pub(crate) fn ctl_looked_filter<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(UINT256_MULMOD_COL_MAP.is_real))
+}
+
+pub(crate) fn ctl_looking_memory<F: Field>(i: usize) -> Vec<Column<F>> {
+    let cols = UINT256_MULMOD_COL_MAP;
+
+    let (is_read, ptr, offset, limbs, timestamp) = match i / UINT256_WORDS {
+        0 => (true, cols.x_ptr, 0, &cols.x, Column::single(cols.timestamp)),
+        1 => (true, cols.y_ptr, 0, &cols.y, Column::single(cols.timestamp)),
+        2 => (
+            true,
+            cols.y_ptr,
+            4 * UINT256_WORDS,
+            &cols.modulus,
+            Column::single(cols.timestamp),
+        ),
+        _ => (
+            false,
+            cols.x_ptr,
+            0,
+            &cols.result,
+            Column::linear_combination_with_constant([(cols.timestamp, F::ONE)], F::ONE),
+        ),
+    };
+    let j = i % UINT256_WORDS;
+    let word = Column::linear_combination(
+        limbs[4 * j..4 * j + 4]
+            .iter()
+            .enumerate()
+            .map(|(k, &c)| (c, F::from_canonical_u64(1 << (LIMB_BITS * k)))),
+    );
+    let res = vec![
+        Column::constant(F::from_bool(is_read)),
+        Column::zero(), // context
+        Column::constant(F::from_canonical_usize(Segment::Code as usize)),
+        Column::linear_combination_with_constant(
+            [(ptr, F::ONE)],
+            F::from_canonical_usize(offset + 4 * j),
+        ),
+        word,
+        timestamp,
+    ];
+
+    assert_eq!(
+        res.len(),
+        crate::memory::memory_stark::ctl_data::<F>().len()
+    );
+    res
+}
+
+pub(crate) fn ctl_looking_memory_filter<F: Field>(_i: usize) -> Filter<F> {
+    Filter::new_simple(Column::single(UINT256_MULMOD_COL_MAP.is_real))
+}
+
+/// Information about a `UINT256_MULMOD` call.
+#[derive(Clone, Debug)]
+pub(crate) struct Uint256MulModOp {
+    /// The address of `x`.
+    pub(crate) x_ptr: usize,
+    /// The address of `y`, which the modulus follows.
+    pub(crate) y_ptr: usize,
+    /// The timestamp of the CPU row making the call.
+    pub(crate) timestamp: usize,
+    /// The integers read, as eight words each.
+    pub(crate) x: Uint256,
+    pub(crate) y: Uint256,
+    pub(crate) modulus: Uint256,
+}
+
+/// The limbs of an integer, from its words.
+fn uint_limbs(x: &Uint256) -> [u8; UINT256_LIMBS] {
+    let bytes = x.iter().flat_map(|word| word.to_le_bytes()).collect_vec();
+    bytes.try_into().unwrap()
+}
+
+#[derive(Copy, Clone, Default)]
+pub struct Uint256MulModStark<F, const D: usize> {
+    f: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Uint256MulModStark<F, D> {
+    pub(crate) fn generate_trace(
+        &self,
+        operations: Vec<Uint256MulModOp>,
+        min_rows: usize,
+        timing: &mut TimingTree,
+    ) -> Vec<PolynomialValues<F>> {
+        let trace_rows = timed!(
+            timing,
+            "generate trace rows",
+            self.generate_trace_rows(operations, min_rows)
+        );
+        timed!(
+            timing,
+            "convert to PolynomialValues",
+            trace_rows_to_poly_values(trace_rows)
+        )
+    }
+
+    fn generate_trace_rows(
+        &self,
+        operations: Vec<Uint256MulModOp>,
+        min_rows: usize,
+    ) -> Vec<[F; NUM_UINT256_MULMOD_COLUMNS]> {
+        // The range checks need at least `RANGE_MAX` rows.
+        let num_rows = operations
+            .len()
+            .max(min_rows)
+            .max(RANGE_MAX)
+            .next_power_of_two();
+        let mut rows = Vec::with_capacity(num_rows);
+        for op in operations {
+            rows.push(self.generate_row_for_op(op));
+        }
+        rows.resize(num_rows, [F::ZERO; NUM_UINT256_MULMOD_COLUMNS]);
+        self.generate_range_checks(&mut rows);
+        rows
+    }
+
+    fn generate_range_checks(&self, rows: &mut [[F; NUM_UINT256_MULMOD_COLUMNS]]) {
+        let cols = UINT256_MULMOD_COL_MAP;
+        let mut frequencies = vec![0usize; RANGE_MAX];
+        for row in rows.iter() {
+            for c in cols.bytes() {
+                let x = row[c].to_canonical_u64() as usize;
+                assert!(
+                    x < RANGE_MAX,
+                    "column value {} exceeds the max range value {}",
+                    x,
+                    RANGE_MAX
+                );
+                frequencies[x] += 1;
+            }
+        }
+        for (i, row) in rows.iter_mut().enumerate() {
+            row[cols.range_counter] = F::from_canonical_usize(i.min(RANGE_MAX - 1));
+            row[cols.range_frequencies] =
+                F::from_canonical_usize(frequencies.get(i).copied().unwrap_or(0));
+        }
+    }
+
+    fn generate_row_for_op(&self, op: Uint256MulModOp) -> [F; NUM_UINT256_MULMOD_COLUMNS] {
+        let mut row = Uint256MulModColumnsView::default();
+        row.is_real = F::ONE;
+        row.timestamp = F::from_canonical_usize(op.timestamp);
+        row.x_ptr = F::from_canonical_usize(op.x_ptr);
+        row.y_ptr = F::from_canonical_usize(op.y_ptr);
+
+        let result = uint256::mulmod(&op.x, &op.y, &op.modulus).expect("zero modulus");
+        let (x, y, n, r) = (
+            uint_limbs(&op.x),
+            uint_limbs(&op.y),
+            uint_limbs(&op.modulus),
+            uint_limbs(&result),
+        );
+        row.x = limbs_to_field(&x);
+        row.y = limbs_to_field(&y);
+        row.modulus = limbs_to_field(&n);
+        row.result = limbs_to_field(&r);
+
+        let quotient = (uint256::from_words(&op.x) * uint256::from_words(&op.y)
+            - uint256::from_words(&result))
+            / uint256::from_words(&op.modulus);
+        let mut q = [0u8; QUOTIENT_LIMBS];
+        for (limb, byte) in q.iter_mut().zip(quotient.to_bytes_le()) {
+            *limb = byte;
+        }
+        row.quotient = limbs_to_field(&q);
+        generate_carries(&mut row, &x, &y, &q, &n, &r);
+        generate_diff(&mut row, &n, &r);
+
+        row.into()
+    }
+}
+
+/// Fills in `w` from `c(X) = x(X) y(X) - q(X) n(X) - r(X) = (X - 2^8) w(X)`, so that
+/// `c[i] = w[i - 1] - 2^8 w[i]`.
+fn generate_carries<F: PrimeField64>(
+    row: &mut Uint256MulModColumnsView<F>,
+    x: &[u8; UINT256_LIMBS],
+    y: &[u8; UINT256_LIMBS],
+    q: &[u8; QUOTIENT_LIMBS],
+    n: &[u8; UINT256_LIMBS],
+    r: &[u8; UINT256_LIMBS],
+) {
+    let (x, y, q, n, r) = (
+        limbs_to_i64(x),
+        limbs_to_i64(y),
+        limbs_to_i64(q),
+        limbs_to_i64(n),
+        limbs_to_i64(r),
+    );
+    let c = pol_sub(&pol_sub(&pol_mul(&x, &y), &pol_mul(&q, &n)), &r);
+    debug_assert_eq!(c.len(), N_CARRIES + 1);
+
+    let mut w = 0i64;
+    for i in 0..N_CARRIES {
+        let num = w - c[i];
+        debug_assert_eq!(num % (1 << LIMB_BITS), 0);
+        w = num >> LIMB_BITS;
+        let carry = w + CARRY_OFFSET;
+        assert!((0..1 << 16).contains(&carry), "carry out of range");
+        row.carry_lo[i] = F::from_canonical_u64(carry as u64 & 0xff);
+        row.carry_hi[i] = F::from_canonical_u64(carry as u64 >> LIMB_BITS);
+    }
+    debug_assert_eq!(c[N_CARRIES], w);
+}
+
+/// Fills in `n - 1 - r` and the carries of `r + (n - 1 - r) + 1 = n`.
+fn generate_diff<F: PrimeField64>(
+    row: &mut Uint256MulModColumnsView<F>,
+    n: &[u8; UINT256_LIMBS],
+    r: &[u8; UINT256_LIMBS],
+) {
+    let diff = BigUint::from_bytes_le(n) - BigUint::from_bytes_le(r) - 1u32;
+    let mut d = [0u8; UINT256_LIMBS];
+    for (limb, byte) in d.iter_mut().zip(diff.to_bytes_le()) {
+        *limb = byte;
+    }
+    row.diff = limbs_to_field(&d);
+
+    let mut carry = 1;
+    for i in 0..UINT256_LIMBS - 1 {
+        carry = (r[i] as u64 + d[i] as u64 + carry - n[i] as u64) >> LIMB_BITS;
+        row.diff_carry[i] = F::from_canonical_u64(carry);
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for Uint256MulModStark<F, D> {
+    type EvaluationFrame<FE, P, const D2: usize>
+        = StarkFrame<P, NUM_UINT256_MULMOD_COLUMNS>
+    where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>;
+
+    type EvaluationFrameTarget = StarkFrame<ExtensionTarget<D>, NUM_UINT256_MULMOD_COLUMNS>;
+
+    fn eval_packed_generic<FE, P, const D2: usize>(
+        &self,
+        vars: &Self::EvaluationFrame<FE, P, D2>,
+        yield_constr: &mut ConstraintConsumer<P>,
+    ) where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>,
+    {
+        let local_values: &[P; NUM_UINT256_MULMOD_COLUMNS] =
+            vars.get_local_values().try_into().unwrap();
+        let lv: &Uint256MulModColumnsView<P> = local_values.borrow();
+        let next_values: &[P; NUM_UINT256_MULMOD_COLUMNS] =
+            vars.get_next_values().try_into().unwrap();
+        let nv: &Uint256MulModColumnsView<P> = next_values.borrow();
+
+        // The range counter starts at 0, increments by 0 or 1, and ends at `RANGE_MAX - 1`.
+        yield_constr.constraint_first_row(lv.range_counter);
+        let incr = nv.range_counter - lv.range_counter;
+        yield_constr.constraint_transition(incr * incr - incr);
+        let range_max = P::Scalar::from_canonical_u64((RANGE_MAX - 1) as u64);
+        yield_constr.constraint_last_row(lv.range_counter - range_max);
+
+        yield_constr.constraint(lv.is_real * (lv.is_real - P::ONES));
+
+        // x(X) y(X) - q(X) n(X) - r(X) = (X - 2^8) w(X).
+        let base = P::Scalar::from_canonical_u64(1 << LIMB_BITS);
+        let carry_offset = P::Scalar::from_canonical_u64(CARRY_OFFSET as u64);
+        let w: [P; N_CARRIES] =
+            core::array::from_fn(|i| lv.carry_lo[i] + lv.carry_hi[i] * base - carry_offset);
+        let c = pol_sub(
+            &pol_sub(&pol_mul(&lv.x, &lv.y), &pol_mul(&lv.quotient, &lv.modulus)),
+            &lv.result,
+        );
+        for (i, &c) in c.iter().enumerate() {
+            let mut rhs = P::ZEROS;
+            if i > 0 {
+                rhs += w[i - 1];
+            }
+            if i < N_CARRIES {
+                rhs -= w[i] * base;
+            }
+            yield_constr.constraint(lv.is_real * (c - rhs));
+        }
+
+        // r + (n - 1 - r) + 1 = n, limb by limb.
+        for &bit in lv.diff_carry.iter() {
+            yield_constr.constraint(bit * (bit - P::ONES));
+        }
+        for i in 0..UINT256_LIMBS {
+            let mut sum = lv.result[i] + lv.diff[i] - lv.modulus[i];
+            sum += if i > 0 { lv.diff_carry[i - 1] } else { P::ONES };
+            if i < UINT256_LIMBS - 1 {
+                sum -= lv.diff_carry[i] * base;
+            }
+            yield_constr.constraint(lv.is_real * sum);
+        }
+    }
+
+    fn eval_ext_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: &Self::EvaluationFrameTarget,
+        yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+    ) {
+        let local_values: &[ExtensionTarget<D>; NUM_UINT256_MULMOD_COLUMNS] =
+            vars.get_local_values().try_into().unwrap();
+        let lv: &Uint256MulModColumnsView<ExtensionTarget<D>> = local_values.borrow();
+        let next_values: &[ExtensionTarget<D>; NUM_UINT256_MULMOD_COLUMNS] =
+            vars.get_next_values().try_into().unwrap();
+        let nv: &Uint256MulModColumnsView<ExtensionTarget<D>> = next_values.borrow();
+
+        yield_constr.constraint_first_row(builder, lv.range_counter);
+        let incr = builder.sub_extension(nv.range_counter, lv.range_counter);
+        let constraint = builder.mul_sub_extension(incr, incr, incr);
+        yield_constr.constraint_transition(builder, constraint);
+        let range_max =
+            builder.constant_extension(F::Extension::from_canonical_usize(RANGE_MAX - 1));
+        let constraint = builder.sub_extension(lv.range_counter, range_max);
+        yield_constr.constraint_last_row(builder, constraint);
+
+        let constraint = builder.mul_sub_extension(lv.is_real, lv.is_real, lv.is_real);
+        yield_constr.constraint(builder, constraint);
+
+        let base = F::from_canonical_u64(1 << LIMB_BITS);
+        let carry_offset =
+            builder.constant_extension(F::Extension::from_canonical_u64(CARRY_OFFSET as u64));
+        let w: [ExtensionTarget<D>; N_CARRIES] = core::array::from_fn(|i| {
+            let carry = builder.mul_const_add_extension(base, lv.carry_hi[i], lv.carry_lo[i]);
+            builder.sub_extension(carry, carry_offset)
+        });
+        let xy = pol_mul_ext_circuit(builder, &lv.x, &lv.y);
+        let qn = pol_mul_ext_circuit(builder, &lv.quotient, &lv.modulus);
+        let c = pol_sub_ext_circuit(builder, &xy, &qn);
+        let c = pol_sub_ext_circuit(builder, &c, &lv.result);
+        for (i, &c) in c.iter().enumerate() {
+            let mut rhs = builder.zero_extension();
+            if i > 0 {
+                rhs = builder.add_extension(rhs, w[i - 1]);
+            }
+            if i < N_CARRIES {
+                rhs = builder.mul_const_add_extension(-base, w[i], rhs);
+            }
+            let diff = builder.sub_extension(c, rhs);
+            let constraint = builder.mul_extension(lv.is_real, diff);
+            yield_constr.constraint(builder, constraint);
+        }
+
+        for &bit in lv.diff_carry.iter() {
+            let constraint = builder.mul_sub_extension(bit, bit, bit);
+            yield_constr.constraint(builder, constraint);
+        }
+        let one = builder.one_extension();
+        for i in 0..UINT256_LIMBS {
+            let mut sum = builder.add_extension(lv.result[i], lv.diff[i]);
+            sum = builder.sub_extension(sum, lv.modulus[i]);
+            let carry_in = if i > 0 { lv.diff_carry[i - 1] } else { one };
+            sum = builder.add_extension(sum, carry_in);
+            if i < UINT256_LIMBS - 1 {
+                sum = builder.mul_const_add_extension(-base, lv.diff_carry[i], sum);
+            }
+            let constraint = builder.mul_extension(lv.is_real, sum);
+            yield_constr.constraint(builder, constraint);
+        }
+    }
+
+    fn constraint_degree(&self) -> usize {
+        3
+    }
+
+    fn lookups(&self) -> Vec<Lookup<F>> {
+        let cols = UINT256_MULMOD_COL_MAP;
+        let columns = Column::singles(cols.bytes()).collect_vec();
+        let filter_columns = vec![None; columns.len()];
+        vec![Lookup {
+            columns,
+            table_column: Column::single(cols.range_counter),
+            frequencies_column: Column::single(cols.range_frequencies),
+            filter_columns,
+        }]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Borrow;
+
+    use anyhow::Result;
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::types::PrimeField64;
+    use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    use crate::stark_testing::{test_stark_circuit_constraints, test_stark_low_degree};
+    use crate::uint256::columns::Uint256MulModColumnsView;
+    use crate::uint256::mulmod_stark::{Uint256MulModOp, Uint256MulModStark};
+
+    #[test]
+    fn test_stark_degree() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = Uint256MulModStark<F, D>;
+
+        let stark = S::default();
+        test_stark_low_degree(stark)
+    }
+
+    #[test]
+    fn test_stark_circuit() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = Uint256MulModStark<F, D>;
+
+        let stark = S::default();
+        test_stark_circuit_constraints::<F, C, S, D>(stark)
+    }
+
+    #[test]
+    fn test_generation() -> Result<()> {
+        const D: usize = 2;
+        type F = GoldilocksField;
+        type S = Uint256MulModStark<F, D>;
+
+        // `(2^256 - 1)^2` modulo `2^255 - 19` is `37^2`, and modulo 1 is 0, for the largest
+        // quotient.
+        let mut p = [u32::MAX; 8];
+        p[0] = 0xffffffed;
+        p[7] = 0x7fffffff;
+        let one = [1, 0, 0, 0, 0, 0, 0, 0];
+
+        let stark = S::default();
+        for (modulus, low_limb) in [(p, 0x59), (one, 0)] {
+            let op = Uint256MulModOp {
+                x_ptr: 0x1000,
+                y_ptr: 0x2000,
+                timestamp: 0,
+                x: [u32::MAX; 8],
+                y: [u32::MAX; 8],
+                modulus,
+            };
+            let row = stark.generate_row_for_op(op);
+            let row: &Uint256MulModColumnsView<F> = row.borrow();
+            assert_eq!(row.result[0].to_canonical_u64(), low_limb);
+        }
+        Ok(())
+    }
+}
@@ -8,7 +8,6 @@ use plonky2::field::polynomial::PolynomialValues;
 use plonky2::field::types::Field;
 use plonky2::hash::hash_types::RichField;
 use plonky2::iop::ext_target::ExtensionTarget;
-use plonky2::util::transpose;
 
 /// Construct an integer from its constituent bits (in little-endian order)
 pub fn limb_from_bits_le<P: PackedField>(iter: impl IntoIterator<Item = P>) -> P {
@@ -36,12 +35,31 @@ pub fn limb_from_bits_le_recursive<F: RichField + Extendable<D>, const D: usize>
 pub fn trace_rows_to_poly_values<F: Field, const COLUMNS: usize>(
     trace_rows: Vec<[F; COLUMNS]>,
 ) -> Vec<PolynomialValues<F>> {
-    let trace_row_vecs = trace_rows.into_iter().map(|row| row.to_vec()).collect_vec();
-    let trace_col_vecs: Vec<Vec<F>> = transpose(&trace_row_vecs);
-    trace_col_vecs
-        .into_iter()
-        .map(|column| PolynomialValues::new(column))
-        .collect()
+    rows_to_poly_values(trace_rows)
+}
+
+/// The number of rows [`rows_to_poly_values`] moves to the columns before releasing them.
+const TRANSPOSE_BLOCK_ROWS: usize = 1 << 16;
+
+/// Transposes `rows` into the columns of a trace, a block of rows at a time from the end, and
+/// releases each block once it is copied. The peak memory is about the size of the rows plus the
+/// size of the columns, where copying the rows then transposing them needs three times the trace.
+pub fn rows_to_poly_values<F: Field, R: Into<[F; COLUMNS]>, const COLUMNS: usize>(
+    mut rows: Vec<R>,
+) -> Vec<PolynomialValues<F>> {
+    let num_rows = rows.len();
+    let mut columns = (0..COLUMNS).map(|_| vec![F::ZERO; num_rows]).collect_vec();
+    while !rows.is_empty() {
+        let start = rows.len().saturating_sub(TRANSPOSE_BLOCK_ROWS);
+        for (i, row) in rows.drain(start..).enumerate() {
+            let row: [F; COLUMNS] = row.into();
+            for (column, value) in columns.iter_mut().zip(row) {
+                column[start + i] = value;
+            }
+        }
+        rows.shrink_to_fit();
+    }
+    columns.into_iter().map(PolynomialValues::new).collect()
 }
 
 pub(crate) const fn indices_arr<const N: usize>() -> [usize; N] {
@@ -61,3 +79,95 @@ pub(crate) unsafe fn transmute_no_compile_time_size_checks<T, U>(value: T) -> U
     // Copy the bit pattern. The original value is no longer safe to use.
     transmute_copy(&value)
 }
+
+/// Implements the conversions between a `#[repr(C)]` view of the columns of a table and arrays of
+/// its columns, and defines the number of columns and the map of their indices.
+macro_rules! impl_columns_view {
+    ($view:ident, $num_columns:ident, $col_map:ident) => {
+        pub(crate) const $num_columns: usize = ::core::mem::size_of::<$view<u8>>();
+
+        impl<T: Copy> From<[T; $num_columns]> for $view<T> {
+            fn from(value: [T; $num_columns]) -> Self {
+                unsafe { $crate::util::transmute_no_compile_time_size_checks(value) }
+            }
+        }
+
+        impl<T: Copy> From<$view<T>> for [T; $num_columns] {
+            fn from(value: $view<T>) -> Self {
+                unsafe { $crate::util::transmute_no_compile_time_size_checks(value) }
+            }
+        }
+
+        impl<T: Copy> ::core::borrow::Borrow<$view<T>> for [T; $num_columns] {
+            fn borrow(&self) -> &$view<T> {
+                unsafe { ::core::mem::transmute(self) }
+            }
+        }
+
+        impl<T: Copy> ::core::borrow::BorrowMut<$view<T>> for [T; $num_columns] {
+            fn borrow_mut(&mut self) -> &mut $view<T> {
+                unsafe { ::core::mem::transmute(self) }
+            }
+        }
+
+        impl<T: Copy> ::core::borrow::Borrow<[T; $num_columns]> for $view<T> {
+            fn borrow(&self) -> &[T; $num_columns] {
+                unsafe { ::core::mem::transmute(self) }
+            }
+        }
+
+        impl<T: Copy> ::core::borrow::BorrowMut<[T; $num_columns]> for $view<T> {
+            fn borrow_mut(&mut self) -> &mut [T; $num_columns] {
+                unsafe { ::core::mem::transmute(self) }
+            }
+        }
+
+        impl<T: Copy + Default> Default for $view<T> {
+            fn default() -> Self {
+                [T::default(); $num_columns].into()
+            }
+        }
+
+        pub(crate) const $col_map: $view<usize> = {
+            let indices_arr = $crate::util::indices_arr::<$num_columns>();
+            unsafe { ::core::mem::transmute::<[usize; $num_columns], $view<usize>>(indices_arr) }
+        };
+    };
+}
+
+pub(crate) use impl_columns_view;
+
+/// `plonky2::timed!`, in a `tracing` span of the phase `$name`, to follow a proof with a
+/// `tracing` subscriber as well as with the timing tree.
+macro_rules! traced {
+    ($timing:expr, $name:expr, $body:expr) => {{
+        let _span = ::tracing::info_span!("phase", name = %$name).entered();
+        ::plonky2::timed!($timing, $name, $body)
+    }};
+}
+
+pub(crate) use traced;
+
+#[cfg(test)]
+mod tests {
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::util::transpose;
+
+    use super::*;
+
+    type F = GoldilocksField;
+
+    #[test]
+    fn test_rows_to_poly_values() {
+        let num_rows = 2 * TRANSPOSE_BLOCK_ROWS + 3;
+        let rows = (0..num_rows as u64)
+            .map(|i| [F::from_canonical_u64(i), F::from_canonical_u64(i * 3 + 1)])
+            .collect_vec();
+        let expected = transpose(&rows.iter().map(|row| row.to_vec()).collect_vec());
+        let columns = trace_rows_to_poly_values(rows);
+        assert_eq!(columns.len(), 2);
+        for (column, expected) in columns.iter().zip(expected) {
+            assert_eq!(column.values, expected);
+        }
+    }
+}
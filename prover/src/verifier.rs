@@ -10,7 +10,7 @@ use crate::evaluation_frame::StarkEvaluationFrame;
 use crate::lookup::LookupCheckVars;
 
 use crate::memory::VALUE_LIMBS;
-use crate::proof::PublicValues;
+use crate::proof::{MemRoots, PublicValues};
 use anyhow::{ensure, Result};
 use itertools::Itertools;
 use plonky2::field::extension::{Extendable, FieldExtension};
@@ -48,6 +48,19 @@ where
         cpu_stark,
         poseidon_stark,
         poseidon_sponge_stark,
+        keccak_stark,
+        keccak_sponge_stark,
+        sha_extend_stark,
+        sha_compress_stark,
+        secp256k1_stark,
+        ed25519_stark,
+        bn254_stark,
+        bls12381_fp_stark,
+        uint256_stark,
+        blake_stark,
+        chacha_stark,
+        secp256r1_stark,
+        modexp_stark,
         logic_stark,
         memory_stark,
         cross_table_lookups,
@@ -99,6 +112,110 @@ where
         &ctl_challenges,
         config,
     )?;
+    verify_stark_proof_with_challenges(
+        keccak_stark,
+        &all_proof.stark_proofs[Table::Keccak as usize].proof,
+        &stark_challenges[Table::Keccak as usize],
+        &ctl_vars_per_table[Table::Keccak as usize],
+        &ctl_challenges,
+        config,
+    )?;
+    verify_stark_proof_with_challenges(
+        keccak_sponge_stark,
+        &all_proof.stark_proofs[Table::KeccakSponge as usize].proof,
+        &stark_challenges[Table::KeccakSponge as usize],
+        &ctl_vars_per_table[Table::KeccakSponge as usize],
+        &ctl_challenges,
+        config,
+    )?;
+    verify_stark_proof_with_challenges(
+        sha_extend_stark,
+        &all_proof.stark_proofs[Table::ShaExtend as usize].proof,
+        &stark_challenges[Table::ShaExtend as usize],
+        &ctl_vars_per_table[Table::ShaExtend as usize],
+        &ctl_challenges,
+        config,
+    )?;
+    verify_stark_proof_with_challenges(
+        sha_compress_stark,
+        &all_proof.stark_proofs[Table::ShaCompress as usize].proof,
+        &stark_challenges[Table::ShaCompress as usize],
+        &ctl_vars_per_table[Table::ShaCompress as usize],
+        &ctl_challenges,
+        config,
+    )?;
+    verify_stark_proof_with_challenges(
+        secp256k1_stark,
+        &all_proof.stark_proofs[Table::Secp256k1 as usize].proof,
+        &stark_challenges[Table::Secp256k1 as usize],
+        &ctl_vars_per_table[Table::Secp256k1 as usize],
+        &ctl_challenges,
+        config,
+    )?;
+    verify_stark_proof_with_challenges(
+        ed25519_stark,
+        &all_proof.stark_proofs[Table::Ed25519 as usize].proof,
+        &stark_challenges[Table::Ed25519 as usize],
+        &ctl_vars_per_table[Table::Ed25519 as usize],
+        &ctl_challenges,
+        config,
+    )?;
+    verify_stark_proof_with_challenges(
+        bn254_stark,
+        &all_proof.stark_proofs[Table::Bn254 as usize].proof,
+        &stark_challenges[Table::Bn254 as usize],
+        &ctl_vars_per_table[Table::Bn254 as usize],
+        &ctl_challenges,
+        config,
+    )?;
+    verify_stark_proof_with_challenges(
+        bls12381_fp_stark,
+        &all_proof.stark_proofs[Table::Bls12381Fp as usize].proof,
+        &stark_challenges[Table::Bls12381Fp as usize],
+        &ctl_vars_per_table[Table::Bls12381Fp as usize],
+        &ctl_challenges,
+        config,
+    )?;
+    verify_stark_proof_with_challenges(
+        uint256_stark,
+        &all_proof.stark_proofs[Table::Uint256 as usize].proof,
+        &stark_challenges[Table::Uint256 as usize],
+        &ctl_vars_per_table[Table::Uint256 as usize],
+        &ctl_challenges,
+        config,
+    )?;
+    verify_stark_proof_with_challenges(
+        blake_stark,
+        &all_proof.stark_proofs[Table::Blake as usize].proof,
+        &stark_challenges[Table::Blake as usize],
+        &ctl_vars_per_table[Table::Blake as usize],
+        &ctl_challenges,
+        config,
+    )?;
+    verify_stark_proof_with_challenges(
+        chacha_stark,
+        &all_proof.stark_proofs[Table::ChaCha as usize].proof,
+        &stark_challenges[Table::ChaCha as usize],
+        &ctl_vars_per_table[Table::ChaCha as usize],
+        &ctl_challenges,
+        config,
+    )?;
+    verify_stark_proof_with_challenges(
+        secp256r1_stark,
+        &all_proof.stark_proofs[Table::Secp256r1 as usize].proof,
+        &stark_challenges[Table::Secp256r1 as usize],
+        &ctl_vars_per_table[Table::Secp256r1 as usize],
+        &ctl_challenges,
+        config,
+    )?;
+    verify_stark_proof_with_challenges(
+        modexp_stark,
+        &all_proof.stark_proofs[Table::ModExp as usize].proof,
+        &stark_challenges[Table::ModExp as usize],
+        &ctl_vars_per_table[Table::ModExp as usize],
+        &ctl_challenges,
+        config,
+    )?;
     verify_stark_proof_with_challenges(
         logic_stark,
         &all_proof.stark_proofs[Table::Logic as usize].proof,
@@ -127,6 +244,11 @@ where
         .map(|i| get_memory_extra_looking_sum(&public_values, ctl_challenges.challenges[i]))
         .collect_vec();
 
+    // Exit code and image id
+    extra_looking_sums[Table::Cpu as usize] = (0..config.num_challenges)
+        .map(|i| get_public_values_extra_looking_sum(&public_values, ctl_challenges.challenges[i]))
+        .collect_vec();
+
     verify_cross_table_lookups::<F, D>(
         cross_table_lookups,
         all_proof
@@ -167,6 +289,31 @@ where
     F::ZERO
 }
 
+/// The exit code with the image id the segment ends with, and the image id it starts from, of
+/// the public values, looked up in the last row of the CPU trace and in its final bootstrap row,
+/// with the columns of `cpu_stark::ctl_data_public_values`.
+pub(crate) fn get_public_values_extra_looking_sum<F: RichField>(
+    public_values: &PublicValues,
+    challenge: GrandProductChallenge<F>,
+) -> F {
+    let exit = [F::from_canonical_u32(public_values.exit_code)]
+        .into_iter()
+        .chain(image_id_limbs(&public_values.roots_after))
+        .collect_vec();
+    let image_id = [F::ZERO]
+        .into_iter()
+        .chain(image_id_limbs(&public_values.roots_before))
+        .collect_vec();
+    challenge.combine(exit.iter()).inverse() + challenge.combine(image_id.iter()).inverse()
+}
+
+/// The limbs of the Poseidon digest of which `roots` is the little-endian encoding.
+pub(crate) fn image_id_limbs<F: RichField>(roots: &MemRoots) -> [F; 4] {
+    core::array::from_fn(|i| {
+        F::from_noncanonical_u64(((roots.root[2 * i + 1] as u64) << 32) | roots.root[2 * i] as u64)
+    })
+}
+
 fn add_data_write<F, const D: usize>(
     challenge: GrandProductChallenge<F>,
     segment: F,
@@ -414,6 +561,21 @@ mod tests {
 
     use crate::verifier::eval_l_0_and_l_last;
 
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use plonky2::plonk::config::PoseidonGoldilocksConfig;
+    use plonky2::util::timing::TimingTree;
+    use zkm_emulator::state::State;
+    use zkm_emulator::utils::split_prog_into_segs;
+
+    use crate::all_stark::AllStark;
+    use crate::config::StarkConfig;
+    use crate::cpu::kernel::assembler::segment_kernel;
+    use crate::generation::generate_traces;
+    use crate::prover::prove_with_traces;
+    use crate::verifier::verify_proof;
+
     #[test]
     fn test_eval_l_0_and_l_last() {
         type F = GoldilocksField;
@@ -428,4 +590,31 @@ mod tests {
         assert_eq!(l_first_x, expected_l_first_x);
         assert_eq!(l_last_x, expected_l_last_x);
     }
+
+    /// The image id a segment ends with is the one its exit kernel hashes, even if the prover
+    /// commits to another one before drawing the challenges.
+    #[test]
+    fn test_wrong_roots_after_is_rejected() -> anyhow::Result<()> {
+        type F = GoldilocksField;
+        type C = PoseidonGoldilocksConfig;
+        const D: usize = 2;
+
+        let mut state = State::new();
+        state.memory.set_memory(0, 0x34021096); // ori $v0, $zero, 0x1096
+        state.memory.set_memory(4, 0x0000000c); // syscall
+        let seg_dir = std::env::temp_dir().join("zkm_wrong_roots_after");
+        let _ = split_prog_into_segs(state, seg_dir.to_str().unwrap(), "", 0);
+        let kernel = segment_kernel("", "", "", BufReader::new(File::open(seg_dir.join("0"))?));
+
+        let all_stark = AllStark::<F, D>::default();
+        let config = StarkConfig::standard_fast_config();
+        let mut timing = TimingTree::default();
+        let (traces, mut public_values, _) =
+            generate_traces::<F, C, D>(&all_stark, &kernel, &config, &mut timing)?;
+        public_values.roots_after.root[0] ^= 1;
+        let proof =
+            prove_with_traces::<F, C, D>(&all_stark, &config, traces, public_values, &mut timing)?;
+        assert!(verify_proof(&all_stark, proof, &config).is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,62 @@
+use crate::modular::{IdentityColumns, ReducedColumns, N_LIMBS};
+use crate::util::impl_columns_view;
+
+/// A call to the `*_ADD` or `*_DOUBLE` syscall of a short Weierstrass curve, computing `p + q`
+/// or `2p` as the third point on the line of slope `lambda` through `p` and `q`, or tangent at
+/// `p`.
+#[repr(C)]
+#[derive(Eq, PartialEq, Debug)]
+pub(crate) struct WeierstrassColumnsView<T: Copy> {
+    /// 1 iff the row is an addition.
+    pub is_add: T,
+    /// 1 iff the row is a doubling. Padding rows are neither.
+    pub is_double: T,
+
+    /// The timestamp of the CPU row making the call. The points are read at this timestamp, and
+    /// the result written right after it.
+    pub timestamp: T,
+    /// The address of `p`, which the result replaces.
+    pub p_ptr: T,
+    /// The address of `q`, for an addition.
+    pub q_ptr: T,
+
+    /// The limbs of the coordinates of the points. A doubling has `q = p`.
+    pub p: [[T; N_LIMBS]; 2],
+    pub q: [[T; N_LIMBS]; 2],
+    pub lambda: [T; N_LIMBS],
+    pub result: [[T; N_LIMBS]; 2],
+
+    /// `lambda (x_q - x_p) = y_q - y_p` for an addition, `2 lambda y_p = 3 x_p^2 + a` for a
+    /// doubling.
+    pub lambda_identity: IdentityColumns<T>,
+    /// `x = lambda^2 - x_p - x_q` and `y = lambda (x_p - x) - y_p`.
+    pub result_identities: [IdentityColumns<T>; 2],
+    /// The coordinates of the result are reduced.
+    pub result_reduced: [ReducedColumns<T>; 2],
+
+    /// The bytes range checked by the table.
+    pub range_counter: T,
+    pub range_frequencies: T,
+}
+
+impl_columns_view!(
+    WeierstrassColumnsView,
+    NUM_WEIERSTRASS_COLUMNS,
+    WEIERSTRASS_COL_MAP
+);
+
+impl<T: Copy> WeierstrassColumnsView<T> {
+    /// The columns range checked in bytes.
+    pub(crate) fn bytes(&self) -> impl Iterator<Item = T> + '_ {
+        self.p
+            .iter()
+            .chain(&self.q)
+            .chain([&self.lambda])
+            .chain(&self.result)
+            .flatten()
+            .copied()
+            .chain(self.lambda_identity.bytes())
+            .chain(self.result_identities.iter().flat_map(|cols| cols.bytes()))
+            .chain(self.result_reduced.iter().flat_map(|cols| cols.bytes()))
+    }
+}
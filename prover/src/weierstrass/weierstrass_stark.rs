@@ -0,0 +1,693 @@
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use itertools::Itertools;
+use num_bigint::BigUint;
+use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::field::packed::PackedField;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::{Field, PrimeField64};
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::timed;
+use plonky2::util::timing::TimingTree;
+use zkm_emulator::weierstrass::{self, Point};
+use zkm_emulator::{bn254, p256, secp256k1};
+
+use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::cross_table_lookup::{Column, Filter};
+use crate::evaluation_frame::{StarkEvaluationFrame, StarkFrame};
+use crate::lookup::Lookup;
+use crate::memory::segments::Segment;
+use crate::modular::*;
+use crate::stark::Stark;
+use crate::util::trace_rows_to_poly_values;
+use crate::weierstrass::columns::*;
+
+/// A short Weierstrass curve `y^2 = x^3 + a x + b` over a prime field of at most 256 bits. Each
+/// curve has its own table, with the same columns and lookups.
+pub trait WeierstrassCurve: 'static + Copy + Default + Send + Sync {
+    /// The limbs of the modulus of the base field.
+    const MODULUS: [u8; N_LIMBS];
+
+    /// The limbs of the coefficient `a`, reduced. Only the slope of a doubling depends on it.
+    const A: [u8; N_LIMBS] = [0; N_LIMBS];
+
+    /// The modulus, for the formulas of the emulator.
+    fn modulus() -> &'static BigUint;
+
+    /// The coefficient `a`, for the formulas of the emulator.
+    fn a() -> &'static BigUint;
+}
+
+/// The curve of the `SECP256K1_ADD` and `SECP256K1_DOUBLE` syscalls.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Secp256k1;
+
+impl WeierstrassCurve for Secp256k1 {
+    /// `2^256 - 2^32 - 977`.
+    const MODULUS: [u8; N_LIMBS] = [
+        0x2f, 0xfc, 0xff, 0xff, 0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0xff,
+    ];
+
+    fn modulus() -> &'static BigUint {
+        &secp256k1::MODULUS
+    }
+
+    fn a() -> &'static BigUint {
+        &secp256k1::A
+    }
+}
+
+/// The curve of the `BN254_ADD` and `BN254_DOUBLE` syscalls.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Bn254;
+
+impl WeierstrassCurve for Bn254 {
+    const MODULUS: [u8; N_LIMBS] = [
+        0x47, 0xfd, 0x7c, 0xd8, 0x16, 0x8c, 0x20, 0x3c, 0x8d, 0xca, 0x71, 0x68, 0x91, 0x6a, 0x81,
+        0x97, 0x5d, 0x58, 0x81, 0x81, 0xb6, 0x45, 0x50, 0xb8, 0x29, 0xa0, 0x31, 0xe1, 0x72, 0x4e,
+        0x64, 0x30,
+    ];
+
+    fn modulus() -> &'static BigUint {
+        &bn254::MODULUS
+    }
+
+    fn a() -> &'static BigUint {
+        &bn254::A
+    }
+}
+
+/// The curve of the `SECP256R1_ADD` and `SECP256R1_DOUBLE` syscalls, also known as P-256.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Secp256r1;
+
+impl WeierstrassCurve for Secp256r1 {
+    /// `2^256 - 2^224 + 2^192 + 2^96 - 1`.
+    const MODULUS: [u8; N_LIMBS] = [
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0xff, 0xff,
+        0xff, 0xff,
+    ];
+
+    /// `p - 3`.
+    const A: [u8; N_LIMBS] = [
+        0xfc, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0xff, 0xff,
+        0xff, 0xff,
+    ];
+
+    fn modulus() -> &'static BigUint {
+        &p256::MODULUS
+    }
+
+    fn a() -> &'static BigUint {
+        &p256::A
+    }
+}
+
+/// The words of the points read and written, sixteen each: `p`, then `q` for an addition, then
+/// the result.
+pub(crate) const NUM_MEMORY_CTLS: usize = 48;
+
+/// Strict upper bound of the range checks.
+const RANGE_MAX: usize = 1 << LIMB_BITS;
+
+pub(crate) fn ctl_looked_data_add<F: Field>() -> Vec<Column<F>> {
+    let cols = WEIERSTRASS_COL_MAP;
+    Column::singles([cols.p_ptr, cols.q_ptr, cols.timestamp]).collect()
+}
+
+pub(crate) fn ctl_looked_filter_add<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(WEIERSTRASS_COL_MAP.is_add))
+}
+
+pub(crate) fn ctl_looked_data_double<F: Field>() -> Vec<Column<F>> {
+    let cols = WEIERSTRASS_COL_MAP;
+    Column::singles([cols.p_ptr, cols.timestamp]).collect()
+}
+
+pub(crate) fn ctl_looked_filter_double<F: Field>() -> Filter<F> {
+    Filter::new_simple(Column::single(WEIERSTRASS_COL_MAP.is_double))
+}
+
+/// The word `i` of a point, from its limbs.
+fn point_word<F: Field>(point: &[[usize; N_LIMBS]; 2], i: usize) -> Column<F> {
+    let limbs = &point[i / 8][4 * (i % 8)..4 * (i % 8) + 4];
+    Column::linear_combination(
+        limbs
+            .iter()
+            .enumerate()
+            .map(|(j, &c)| (c, F::from_canonical_u64(1 << (LIMB_BITS * j)))),
+    )
+}
+
+pub(crate) fn ctl_looking_memory<F: Field>(i: usize) -> Vec<Column<F>> {
+    let cols = WEIERSTRASS_COL_MAP;
+
+    let (is_read, ptr, point, timestamp) = match i / 16 {
+        0 => (true, cols.p_ptr, &cols.p, Column::single(cols.timestamp)),
+        1 => (true, cols.q_ptr, &cols.q, Column::single(cols.timestamp)),
+        _ => (
+            false,
+            cols.p_ptr,
+            &cols.result,
+            Column::linear_combination_with_constant([(cols.timestamp, F::ONE)], F::ONE),
+        ),
+    };
+    let res = vec![
+        Column::constant(F::from_bool(is_read)),
+        Column::zero(), // context
+        Column::constant(F::from_canonical_usize(Segment::Code as usize)),
+        Column::linear_combination_with_constant(
+            [(ptr, F::ONE)],
+            F::from_canonical_usize(4 * (i % 16)),
+        ),
+        point_word(point, i % 16),
+        timestamp,
+    ];
+
+    assert_eq!(
+        res.len(),
+        crate::memory::memory_stark::ctl_data::<F>().len()
+    );
+    res
+}
+
+pub(crate) fn ctl_looking_memory_filter<F: Field>(i: usize) -> Filter<F> {
+    let cols = WEIERSTRASS_COL_MAP;
+    if i / 16 == 1 {
+        Filter::new_simple(Column::single(cols.is_add))
+    } else {
+        Filter::new_simple(Column::sum([cols.is_add, cols.is_double]))
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum WeierstrassOpKind {
+    Add,
+    Double,
+}
+
+/// Information about a call to the `*_ADD` or `*_DOUBLE` syscall of a curve.
+#[derive(Clone, Debug)]
+pub(crate) struct WeierstrassOp {
+    pub(crate) kind: WeierstrassOpKind,
+    /// The address of `p`.
+    pub(crate) p_ptr: usize,
+    /// The address of `q`, for an addition.
+    pub(crate) q_ptr: usize,
+    /// The timestamp of the CPU row making the call.
+    pub(crate) timestamp: usize,
+    /// The points read, as sixteen words each. A doubling only reads `p`.
+    pub(crate) p: Point,
+    pub(crate) q: Point,
+}
+
+/// The limbs of the coordinates of a point, from its words.
+fn point_limbs(point: &Point) -> [[u8; N_LIMBS]; 2] {
+    core::array::from_fn(|i| {
+        let bytes = point[8 * i..8 * i + 8]
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect_vec();
+        bytes.try_into().unwrap()
+    })
+}
+
+#[derive(Copy, Clone, Default)]
+pub struct WeierstrassStark<F, const D: usize, E> {
+    f: PhantomData<F>,
+    e: PhantomData<E>,
+}
+
+pub type Secp256k1Stark<F, const D: usize> = WeierstrassStark<F, D, Secp256k1>;
+pub type Bn254Stark<F, const D: usize> = WeierstrassStark<F, D, Bn254>;
+pub type Secp256r1Stark<F, const D: usize> = WeierstrassStark<F, D, Secp256r1>;
+
+impl<F: RichField + Extendable<D>, const D: usize, E: WeierstrassCurve> WeierstrassStark<F, D, E> {
+    pub(crate) fn generate_trace(
+        &self,
+        operations: Vec<WeierstrassOp>,
+        min_rows: usize,
+        timing: &mut TimingTree,
+    ) -> Vec<PolynomialValues<F>> {
+        let trace_rows = timed!(
+            timing,
+            "generate trace rows",
+            self.generate_trace_rows(operations, min_rows)
+        );
+        timed!(
+            timing,
+            "convert to PolynomialValues",
+            trace_rows_to_poly_values(trace_rows)
+        )
+    }
+
+    fn generate_trace_rows(
+        &self,
+        operations: Vec<WeierstrassOp>,
+        min_rows: usize,
+    ) -> Vec<[F; NUM_WEIERSTRASS_COLUMNS]> {
+        // The range checks need at least `RANGE_MAX` rows.
+        let num_rows = operations
+            .len()
+            .max(min_rows)
+            .max(RANGE_MAX)
+            .next_power_of_two();
+        let mut rows = Vec::with_capacity(num_rows);
+        for op in operations {
+            rows.push(self.generate_row_for_op(op));
+        }
+        rows.resize(num_rows, [F::ZERO; NUM_WEIERSTRASS_COLUMNS]);
+        self.generate_range_checks(&mut rows);
+        rows
+    }
+
+    fn generate_range_checks(&self, rows: &mut [[F; NUM_WEIERSTRASS_COLUMNS]]) {
+        let cols = WEIERSTRASS_COL_MAP;
+        let mut frequencies = vec![0usize; RANGE_MAX];
+        for row in rows.iter() {
+            for c in cols.bytes() {
+                let x = row[c].to_canonical_u64() as usize;
+                assert!(
+                    x < RANGE_MAX,
+                    "column value {} exceeds the max range value {}",
+                    x,
+                    RANGE_MAX
+                );
+                frequencies[x] += 1;
+            }
+        }
+        for (i, row) in rows.iter_mut().enumerate() {
+            row[cols.range_counter] = F::from_canonical_usize(i.min(RANGE_MAX - 1));
+            row[cols.range_frequencies] =
+                F::from_canonical_usize(frequencies.get(i).copied().unwrap_or(0));
+        }
+    }
+
+    fn generate_row_for_op(&self, op: WeierstrassOp) -> [F; NUM_WEIERSTRASS_COLUMNS] {
+        let mut row = WeierstrassColumnsView::default();
+        let (lambda, result) = match op.kind {
+            WeierstrassOpKind::Add => {
+                row.is_add = F::ONE;
+                (
+                    weierstrass::add_slope(E::modulus(), &op.p, &op.q),
+                    weierstrass::add(E::modulus(), &op.p, &op.q),
+                )
+            }
+            WeierstrassOpKind::Double => {
+                row.is_double = F::ONE;
+                (
+                    weierstrass::double_slope(E::modulus(), E::a(), &op.p),
+                    weierstrass::double(E::modulus(), E::a(), &op.p),
+                )
+            }
+        };
+        let lambda = lambda.expect("the formula does not apply to the points");
+        let result = result.unwrap();
+        row.timestamp = F::from_canonical_usize(op.timestamp);
+        row.p_ptr = F::from_canonical_usize(op.p_ptr);
+        row.q_ptr = F::from_canonical_usize(op.q_ptr);
+
+        let p = point_limbs(&op.p);
+        let q = match op.kind {
+            WeierstrassOpKind::Add => point_limbs(&op.q),
+            WeierstrassOpKind::Double => p,
+        };
+        let mut lambda_limbs = [0u8; N_LIMBS];
+        for (limb, byte) in lambda_limbs.iter_mut().zip(lambda.to_bytes_le()) {
+            *limb = byte;
+        }
+        let r = point_limbs(&result);
+        row.p = p.map(|c| limbs_to_field(&c));
+        row.q = q.map(|c| limbs_to_field(&c));
+        row.lambda = limbs_to_field(&lambda_limbs);
+        row.result = r.map(|c| limbs_to_field(&c));
+
+        let [x1, y1] = p.map(|c| limbs_to_i64(&c));
+        let [x2, y2] = q.map(|c| limbs_to_i64(&c));
+        let [x3, y3] = r.map(|c| limbs_to_i64(&c));
+        let lambda = limbs_to_i64(&lambda_limbs);
+        let e_lambda = match op.kind {
+            WeierstrassOpKind::Add => {
+                pol_sub(&pol_mul(&lambda, &pol_sub(&x2, &x1)), &pol_sub(&y2, &y1))
+            }
+            WeierstrassOpKind::Double => pol_sub(
+                &pol_sub(
+                    &pol_scale(&pol_mul(&lambda, &y1), 2),
+                    &pol_scale(&pol_mul(&x1, &x1), 3),
+                ),
+                &limbs_to_i64(&E::A),
+            ),
+        };
+        let e_x = pol_sub(
+            &pol_sub(&pol_mul(&lambda, &lambda), &x1),
+            &pol_add(&x2, &x3),
+        );
+        let e_y = pol_sub(&pol_mul(&lambda, &pol_sub(&x1, &x3)), &pol_add(&y1, &y3));
+        generate_identity(&mut row.lambda_identity, &e_lambda, &E::MODULUS);
+        generate_identity(&mut row.result_identities[0], &e_x, &E::MODULUS);
+        generate_identity(&mut row.result_identities[1], &e_y, &E::MODULUS);
+        for (cols, c) in row.result_reduced.iter_mut().zip(&r) {
+            generate_reduced(cols, c, &E::MODULUS);
+        }
+
+        row.into()
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, E: WeierstrassCurve> Stark<F, D>
+    for WeierstrassStark<F, D, E>
+{
+    type EvaluationFrame<FE, P, const D2: usize>
+        = StarkFrame<P, NUM_WEIERSTRASS_COLUMNS>
+    where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>;
+
+    type EvaluationFrameTarget = StarkFrame<ExtensionTarget<D>, NUM_WEIERSTRASS_COLUMNS>;
+
+    fn eval_packed_generic<FE, P, const D2: usize>(
+        &self,
+        vars: &Self::EvaluationFrame<FE, P, D2>,
+        yield_constr: &mut ConstraintConsumer<P>,
+    ) where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>,
+    {
+        let local_values: &[P; NUM_WEIERSTRASS_COLUMNS] =
+            vars.get_local_values().try_into().unwrap();
+        let lv: &WeierstrassColumnsView<P> = local_values.borrow();
+        let next_values: &[P; NUM_WEIERSTRASS_COLUMNS] = vars.get_next_values().try_into().unwrap();
+        let nv: &WeierstrassColumnsView<P> = next_values.borrow();
+
+        // The range counter starts at 0, increments by 0 or 1, and ends at `RANGE_MAX - 1`.
+        yield_constr.constraint_first_row(lv.range_counter);
+        let incr = nv.range_counter - lv.range_counter;
+        yield_constr.constraint_transition(incr * incr - incr);
+        let range_max = P::Scalar::from_canonical_u64((RANGE_MAX - 1) as u64);
+        yield_constr.constraint_last_row(lv.range_counter - range_max);
+
+        let is_real = lv.is_add + lv.is_double;
+        for flag in [lv.is_add, lv.is_double, is_real] {
+            yield_constr.constraint(flag * (flag - P::ONES));
+        }
+
+        // A doubling is the addition of `p` to itself, but for the slope.
+        for (&q, &p) in lv.q.iter().flatten().zip(lv.p.iter().flatten()) {
+            yield_constr.constraint(lv.is_double * (q - p));
+        }
+
+        let [x1, y1] = &lv.p;
+        let [x2, y2] = &lv.q;
+        let [x3, y3] = &lv.result;
+        let lambda = &lv.lambda;
+
+        let e_add = pol_sub(&pol_mul(lambda, &pol_sub(x2, x1)), &pol_sub(y2, y1));
+        let a = E::A.map(|limb| P::from(FE::from_canonical_u8(limb)));
+        let e_double = pol_sub(
+            &pol_sub(
+                &pol_scale(&pol_mul(lambda, y1), P::from(FE::TWO)),
+                &pol_scale(&pol_mul(x1, x1), P::from(FE::from_canonical_u64(3))),
+            ),
+            &a,
+        );
+        let e_lambda = pol_add(
+            &pol_scale(&e_add, lv.is_add),
+            &pol_scale(&e_double, lv.is_double),
+        );
+        eval_identity(
+            yield_constr,
+            is_real,
+            &e_lambda,
+            &lv.lambda_identity,
+            &E::MODULUS,
+        );
+
+        let e_x = pol_sub(&pol_sub(&pol_mul(lambda, lambda), x1), &pol_add(x2, x3));
+        let e_y = pol_sub(&pol_mul(lambda, &pol_sub(x1, x3)), &pol_add(y1, y3));
+        for (e, cols) in [e_x, e_y].iter().zip(&lv.result_identities) {
+            let e = pol_scale(e, is_real);
+            eval_identity(yield_constr, is_real, &e, cols, &E::MODULUS);
+        }
+
+        for (c, cols) in lv.result.iter().zip(&lv.result_reduced) {
+            eval_reduced(yield_constr, is_real, c, cols, &E::MODULUS);
+        }
+    }
+
+    fn eval_ext_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: &Self::EvaluationFrameTarget,
+        yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+    ) {
+        let local_values: &[ExtensionTarget<D>; NUM_WEIERSTRASS_COLUMNS] =
+            vars.get_local_values().try_into().unwrap();
+        let lv: &WeierstrassColumnsView<ExtensionTarget<D>> = local_values.borrow();
+        let next_values: &[ExtensionTarget<D>; NUM_WEIERSTRASS_COLUMNS] =
+            vars.get_next_values().try_into().unwrap();
+        let nv: &WeierstrassColumnsView<ExtensionTarget<D>> = next_values.borrow();
+
+        yield_constr.constraint_first_row(builder, lv.range_counter);
+        let incr = builder.sub_extension(nv.range_counter, lv.range_counter);
+        let constraint = builder.mul_sub_extension(incr, incr, incr);
+        yield_constr.constraint_transition(builder, constraint);
+        let range_max =
+            builder.constant_extension(F::Extension::from_canonical_usize(RANGE_MAX - 1));
+        let constraint = builder.sub_extension(lv.range_counter, range_max);
+        yield_constr.constraint_last_row(builder, constraint);
+
+        let is_real = builder.add_extension(lv.is_add, lv.is_double);
+        for flag in [lv.is_add, lv.is_double, is_real] {
+            let constraint = builder.mul_sub_extension(flag, flag, flag);
+            yield_constr.constraint(builder, constraint);
+        }
+
+        for (&q, &p) in lv.q.iter().flatten().zip(lv.p.iter().flatten()) {
+            let diff = builder.sub_extension(q, p);
+            let constraint = builder.mul_extension(lv.is_double, diff);
+            yield_constr.constraint(builder, constraint);
+        }
+
+        let [x1, y1] = &lv.p;
+        let [x2, y2] = &lv.q;
+        let [x3, y3] = &lv.result;
+        let lambda = &lv.lambda;
+
+        let dx = pol_sub_ext_circuit(builder, x2, x1);
+        let dy = pol_sub_ext_circuit(builder, y2, y1);
+        let lambda_dx = pol_mul_ext_circuit(builder, lambda, &dx);
+        let e_add = pol_sub_ext_circuit(builder, &lambda_dx, &dy);
+        let lambda_y = pol_mul_ext_circuit(builder, lambda, y1);
+        let lambda_y = pol_scale_ext_circuit(builder, &lambda_y, F::TWO);
+        let x_squared = pol_mul_ext_circuit(builder, x1, x1);
+        let x_squared = pol_scale_ext_circuit(builder, &x_squared, F::from_canonical_u64(3));
+        let e_double = pol_sub_ext_circuit(builder, &lambda_y, &x_squared);
+        let a = E::A.map(|limb| builder.constant_extension(F::Extension::from_canonical_u8(limb)));
+        let e_double = pol_sub_ext_circuit(builder, &e_double, &a);
+        let e_add = e_add
+            .iter()
+            .map(|&c| builder.mul_extension(lv.is_add, c))
+            .collect_vec();
+        let e_double = e_double
+            .iter()
+            .map(|&c| builder.mul_extension(lv.is_double, c))
+            .collect_vec();
+        let e_lambda = pol_add_ext_circuit(builder, &e_add, &e_double);
+        eval_identity_ext_circuit(
+            builder,
+            yield_constr,
+            is_real,
+            &e_lambda,
+            &lv.lambda_identity,
+            &E::MODULUS,
+        );
+
+        let lambda_squared = pol_mul_ext_circuit(builder, lambda, lambda);
+        let e_x = pol_sub_ext_circuit(builder, &lambda_squared, x1);
+        let x2_plus_x3 = pol_add_ext_circuit(builder, x2, x3);
+        let e_x = pol_sub_ext_circuit(builder, &e_x, &x2_plus_x3);
+        let x1_minus_x3 = pol_sub_ext_circuit(builder, x1, x3);
+        let e_y = pol_mul_ext_circuit(builder, lambda, &x1_minus_x3);
+        let y1_plus_y3 = pol_add_ext_circuit(builder, y1, y3);
+        let e_y = pol_sub_ext_circuit(builder, &e_y, &y1_plus_y3);
+        for (e, cols) in [e_x, e_y].iter().zip(&lv.result_identities) {
+            let e = e
+                .iter()
+                .map(|&c| builder.mul_extension(is_real, c))
+                .collect_vec();
+            eval_identity_ext_circuit(builder, yield_constr, is_real, &e, cols, &E::MODULUS);
+        }
+
+        for (c, cols) in lv.result.iter().zip(&lv.result_reduced) {
+            eval_reduced_ext_circuit(builder, yield_constr, is_real, c, cols, &E::MODULUS);
+        }
+    }
+
+    fn constraint_degree(&self) -> usize {
+        3
+    }
+
+    fn lookups(&self) -> Vec<Lookup<F>> {
+        let cols = WEIERSTRASS_COL_MAP;
+        let columns = Column::singles(cols.bytes()).collect_vec();
+        let filter_columns = vec![None; columns.len()];
+        vec![Lookup {
+            columns,
+            table_column: Column::single(cols.range_counter),
+            frequencies_column: Column::single(cols.range_frequencies),
+            filter_columns,
+        }]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Borrow;
+
+    use anyhow::Result;
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::types::PrimeField64;
+    use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    use crate::stark_testing::{test_stark_circuit_constraints, test_stark_low_degree};
+    use crate::weierstrass::columns::WeierstrassColumnsView;
+    use crate::weierstrass::weierstrass_stark::{
+        Bn254Stark, Secp256k1Stark, Secp256r1Stark, WeierstrassOp, WeierstrassOpKind,
+    };
+
+    #[test]
+    fn test_stark_degree() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = Secp256k1Stark<F, D>;
+
+        let stark = S::default();
+        test_stark_low_degree(stark)
+    }
+
+    #[test]
+    fn test_stark_circuit() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = Secp256k1Stark<F, D>;
+
+        let stark = S::default();
+        test_stark_circuit_constraints::<F, C, S, D>(stark)
+    }
+
+    #[test]
+    fn test_generation() -> Result<()> {
+        const D: usize = 2;
+        type F = GoldilocksField;
+        type S = Secp256k1Stark<F, D>;
+
+        // The generator, and its double.
+        let g = [
+            0x16f81798, 0x59f2815b, 0x2dce28d9, 0x029bfcdb, 0xce870b07, 0x55a06295, 0xf9dcbbac,
+            0x79be667e, 0xfb10d4b8, 0x9c47d08f, 0xa6855419, 0xfd17b448, 0x0e1108a8, 0x5da4fbfc,
+            0x26a3c465, 0x483ada77,
+        ];
+        let g2 = [
+            0x5c709ee5, 0xabac09b9, 0x8cef3ca7, 0x5c778e4b, 0x95c07cd8, 0x3045406e, 0x41ed7d6d,
+            0xc6047f94, 0x50cfe52a, 0x236431a9, 0x3266d0e1, 0xf7f63265, 0x466ceaee, 0xa3c58419,
+            0xa63dc339, 0x1ae168fe,
+        ];
+
+        let stark = S::default();
+        let op = WeierstrassOp {
+            kind: WeierstrassOpKind::Double,
+            p_ptr: 0x1000,
+            q_ptr: 0,
+            timestamp: 0,
+            p: g,
+            q: g,
+        };
+        let row = stark.generate_row_for_op(op);
+        let row: &WeierstrassColumnsView<F> = row.borrow();
+        let x_low = (0..4)
+            .map(|i| row.result[0][i].to_canonical_u64() << (8 * i))
+            .sum::<u64>();
+        assert_eq!(x_low, g2[0] as u64);
+
+        let op = WeierstrassOp {
+            kind: WeierstrassOpKind::Add,
+            p_ptr: 0x1000,
+            q_ptr: 0x2000,
+            timestamp: 0,
+            p: g2,
+            q: g,
+        };
+        let row = stark.generate_row_for_op(op);
+        let row: &WeierstrassColumnsView<F> = row.borrow();
+        // The x of 3G is f9308a01...bce036f9.
+        assert_eq!(row.result[0][0].to_canonical_u64(), 0xf9);
+        assert_eq!(row.result[0][31].to_canonical_u64(), 0xf9);
+        Ok(())
+    }
+    #[test]
+    fn test_bn254_generation() -> Result<()> {
+        const D: usize = 2;
+        type F = GoldilocksField;
+        type S = Bn254Stark<F, D>;
+
+        // The generator (1, 2).
+        let mut g = [0u32; 16];
+        g[0] = 1;
+        g[8] = 2;
+
+        let stark = S::default();
+        let op = WeierstrassOp {
+            kind: WeierstrassOpKind::Double,
+            p_ptr: 0x1000,
+            q_ptr: 0,
+            timestamp: 0,
+            p: g,
+            q: g,
+        };
+        let row = stark.generate_row_for_op(op);
+        let row: &WeierstrassColumnsView<F> = row.borrow();
+        // The x of 2G is 030644e7...d87cfd3.
+        assert_eq!(row.result[0][0].to_canonical_u64(), 0xd3);
+        assert_eq!(row.result[0][31].to_canonical_u64(), 0x03);
+        Ok(())
+    }
+
+    #[test]
+    fn test_secp256r1_generation() -> Result<()> {
+        const D: usize = 2;
+        type F = GoldilocksField;
+        type S = Secp256r1Stark<F, D>;
+
+        // The generator.
+        let g = [
+            0xd898c296, 0xf4a13945, 0x2deb33a0, 0x77037d81, 0x63a440f2, 0xf8bce6e5, 0xe12c4247,
+            0x6b17d1f2, 0x37bf51f5, 0xcbb64068, 0x6b315ece, 0x2bce3357, 0x7c0f9e16, 0x8ee7eb4a,
+            0xfe1a7f9b, 0x4fe342e2,
+        ];
+
+        let stark = S::default();
+        let op = WeierstrassOp {
+            kind: WeierstrassOpKind::Double,
+            p_ptr: 0x1000,
+            q_ptr: 0,
+            timestamp: 0,
+            p: g,
+            q: g,
+        };
+        let row = stark.generate_row_for_op(op);
+        let row: &WeierstrassColumnsView<F> = row.borrow();
+        // The x of 2G is 7cf27b18...47669978.
+        assert_eq!(row.result[0][0].to_canonical_u64(), 0x78);
+        assert_eq!(row.result[0][31].to_canonical_u64(), 0x7c);
+        Ok(())
+    }
+}
@@ -6,6 +6,10 @@ pub enum ProgramError {
     InvalidSyscall,
     InvalidOpcode,
     Trap,
+    Break,
+    CoprocessorUnusable,
+    IntegerOverflow,
+    PageFault { is_store: bool, addr: u32 },
     StackUnderflow,
     InvalidRlp,
     InvalidJumpDestination,
@@ -18,6 +22,13 @@ pub enum ProgramError {
     IntegerTooLarge,
     ProverInputError(ProverInputError),
     UnknownContractCode,
+    SyscallWitness(String),
+}
+
+impl From<anyhow::Error> for ProgramError {
+    fn from(err: anyhow::Error) -> Self {
+        Self::SyscallWitness(format!("{:#}", err))
+    }
 }
 
 #[allow(clippy::enum_variant_names)]
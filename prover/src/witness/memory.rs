@@ -1,5 +1,7 @@
 use crate::cpu::membus::{NUM_CHANNELS, NUM_GP_CHANNELS};
 use itertools::Itertools;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
 
 #[derive(Clone, Copy, Debug)]
 pub enum MemoryChannel {
@@ -122,12 +124,13 @@ pub struct MemoryState {
 
 impl MemoryState {
     pub fn new(kernel_code: &[u8]) -> Self {
-        let code_u32s = kernel_code.iter().map(|&x| x.into()).collect();
+        let code_u32s = kernel_code.iter().map(|&x| x.into()).collect_vec();
         let mut result = Self::default();
-        result.contexts[0].segments[Segment::Code as usize].content = code_u32s;
+        result.contexts[0].segments[Segment::Code as usize] = MemorySegmentState::from(code_u32s);
 
         let shift_u32s = (0..32).map(|i| (1u32 << i).to_be()).collect_vec();
-        result.contexts[0].segments[Segment::ShiftTable as usize].content = shift_u32s;
+        result.contexts[0].segments[Segment::ShiftTable as usize] =
+            MemorySegmentState::from(shift_u32s);
 
         result
     }
@@ -218,20 +221,65 @@ impl Default for MemoryContextState {
     }
 }
 
+/// The number of values of a page of a segment.
+const PAGE_SIZE: usize = 1 << 12;
+
+/// The content of a segment, by page: the pages which were never written are not allocated, and
+/// read as zeros, so a program can use high addresses without the whole range being allocated.
 #[derive(Clone, Default, Debug)]
 pub(crate) struct MemorySegmentState {
-    pub(crate) content: Vec<u32>,
+    pages: HashMap<usize, Box<[u32; PAGE_SIZE]>>,
 }
 
 impl MemorySegmentState {
     pub(crate) fn get(&self, virtual_addr: usize) -> u32 {
-        self.content.get(virtual_addr).copied().unwrap_or(0)
+        self.pages
+            .get(&(virtual_addr / PAGE_SIZE))
+            .map_or(0, |page| page[virtual_addr % PAGE_SIZE])
     }
 
     pub(crate) fn set(&mut self, virtual_addr: usize, value: u32) {
-        if virtual_addr >= self.content.len() {
-            self.content.resize(virtual_addr + 1, 0);
+        let page = match self.pages.entry(virtual_addr / PAGE_SIZE) {
+            // Writing a zero to a missing page changes nothing.
+            Entry::Vacant(_) if value == 0 => return,
+            entry => entry.or_insert_with(|| Box::new([0; PAGE_SIZE])),
+        };
+        page[virtual_addr % PAGE_SIZE] = value;
+    }
+}
+
+impl From<Vec<u32>> for MemorySegmentState {
+    fn from(content: Vec<u32>) -> Self {
+        let mut segment = Self::default();
+        for (virt, value) in content.into_iter().enumerate() {
+            segment.set(virt, value);
         }
-        self.content[virtual_addr] = value;
+        segment
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sparse_segment() {
+        let mut segment = MemorySegmentState::default();
+        assert_eq!(segment.get(0x7fff_0000), 0);
+
+        segment.set(0x7fff_0000, 0xdead_beef);
+        segment.set(0x7fff_0004, 1);
+        segment.set(0x1000_0000, 0);
+        assert_eq!(segment.get(0x7fff_0000), 0xdead_beef);
+        assert_eq!(segment.get(0x7fff_0004), 1);
+        assert_eq!(segment.get(0x7fff_0008), 0);
+        assert_eq!(segment.get(0x1000_0000), 0);
+        assert_eq!(segment.pages.len(), 1);
+
+        let segment = MemorySegmentState::from(vec![1, 2, 3]);
+        assert_eq!(
+            (0..4).map(|virt| segment.get(virt)).collect_vec(),
+            vec![1, 2, 3, 0]
+        );
     }
 }
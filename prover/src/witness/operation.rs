@@ -1,8 +1,11 @@
 use super::util::*;
 use crate::cpu::columns::CpuColumnsView;
 use crate::cpu::kernel::assembler::Kernel;
+use crate::cpu::misc::{fill_rdhwr_selectors, HwRegister, HW_REGISTERS, LOCAL_USER_REG};
+use crate::cpu::syscall::{EXIT_CALLS, PRECOMPILE_CALLS};
 use crate::generation::state::GenerationState;
 use crate::memory::segments::Segment;
+use crate::weierstrass::weierstrass_stark::WeierstrassOpKind;
 use crate::witness::errors::ProgramError;
 use crate::witness::memory::MemoryAddress;
 use crate::{arithmetic, logic};
@@ -11,12 +14,22 @@ use anyhow::{Context, Result};
 
 use plonky2::field::types::Field;
 
+use crate::ed25519::ed25519_stark::Ed25519OpKind;
+use crate::keccak_sponge::columns::{KECCAK_RATE_BYTES, KECCAK_RATE_U32S};
 use crate::poseidon_sponge::columns::POSEIDON_RATE_BYTES;
 use itertools::Itertools;
+use num_bigint::BigUint;
 use plonky2::field::extension::Extendable;
 use plonky2::hash::hash_types::RichField;
 use plonky2::plonk::config::GenericConfig;
 use std::fs;
+use zkm_emulator::bls12_381::FpOp;
+use zkm_emulator::cp0::{self, CP0_REGISTERS_START};
+use zkm_emulator::ed25519;
+use zkm_emulator::modexp;
+use zkm_emulator::syscall;
+use zkm_emulator::weierstrass::{self, Point};
+use zkm_emulator::{bn254, p256, secp256k1};
 
 pub const WORD_SIZE: usize = core::mem::size_of::<u32>();
 
@@ -49,6 +62,47 @@ pub(crate) enum MovCond {
     NE,
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum TrapCond {
+    EQ,
+    NE,
+    GE,
+    GEU,
+    LT,
+    LTU,
+}
+
+impl TrapCond {
+    pub(crate) fn result(&self, input0: u32, input1: u32) -> bool {
+        match self {
+            TrapCond::EQ => input0 == input1,
+            TrapCond::NE => input0 != input1,
+            TrapCond::GE => input0 as i32 >= input1 as i32,
+            TrapCond::GEU => input0 >= input1,
+            TrapCond::LT => (input0 as i32) < input1 as i32,
+            TrapCond::LTU => input0 < input1,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum MaddOp {
+    MADD,
+    MADDU,
+    MSUB,
+    MSUBU,
+}
+
+impl MaddOp {
+    pub(crate) fn is_signed(&self) -> bool {
+        matches!(self, MaddOp::MADD | MaddOp::MSUB)
+    }
+
+    pub(crate) fn is_sub(&self) -> bool {
+        matches!(self, MaddOp::MSUB | MaddOp::MSUBU)
+    }
+}
+
 pub fn generate_pinv_diff<F: Field>(val0: u32, val1: u32, lv: &mut CpuColumnsView<F>) {
     let num_unequal_limbs = if val0 != val1 { 1 } else { 0 };
     let _equal = num_unequal_limbs == 0;
@@ -67,27 +121,60 @@ pub fn generate_pinv_diff<F: Field>(val0: u32, val1: u32, lv: &mut CpuColumnsVie
     logic.diff_pinv = (val0_f - val1_f).try_inverse().unwrap_or(F::ZERO) * num_unequal_limbs_inv;
 }
 
-pub(crate) const SYSGETPID: usize = 4020;
-pub(crate) const SYSGETGID: usize = 4047;
-pub(crate) const SYSMMAP2: usize = 4210;
-pub(crate) const SYSMMAP: usize = 4090;
-pub(crate) const SYSBRK: usize = 4045;
-pub(crate) const SYSCLONE: usize = 4120;
-pub(crate) const SYSEXITGROUP: usize = 4246;
-pub(crate) const SYSREAD: usize = 4003;
-pub(crate) const SYSWRITE: usize = 4004;
-pub(crate) const SYSFCNTL: usize = 4055;
-pub(crate) const SYSSETTHREADAREA: usize = 4283;
+// The syscall numbers are shared with the emulator, so that both dispatch on the same table.
+pub(crate) const SYSGETPID: usize = syscall::SYS_GETPID as usize;
+pub(crate) const SYSGETGID: usize = syscall::SYS_GETGID as usize;
+pub(crate) const SYSMMAP2: usize = syscall::SYS_MMAP2 as usize;
+pub(crate) const SYSMMAP: usize = syscall::SYS_MMAP as usize;
+pub(crate) const SYSMUNMAP: usize = syscall::SYS_MUNMAP as usize;
+pub(crate) const SYSMPROTECT: usize = syscall::SYS_MPROTECT as usize;
+pub(crate) const SYSBRK: usize = syscall::SYS_BRK as usize;
+pub(crate) const SYSCLONE: usize = syscall::SYS_CLONE as usize;
+pub(crate) const SYSEXIT: usize = syscall::SYS_EXIT as usize;
+pub(crate) const SYSEXITGROUP: usize = syscall::SYS_EXIT_GROUP as usize;
+pub(crate) const SYSREAD: usize = syscall::SYS_READ as usize;
+pub(crate) const SYSWRITE: usize = syscall::SYS_WRITE as usize;
+pub(crate) const SYSFCNTL: usize = syscall::SYS_FCNTL as usize;
+pub(crate) const SYSIOCTL: usize = syscall::SYS_IOCTL as usize;
+pub(crate) const SYSSETTHREADAREA: usize = syscall::SYS_SET_THREAD_AREA as usize;
+pub(crate) const SYSOPEN: usize = syscall::SYS_OPEN as usize;
+pub(crate) const SYSOPENAT: usize = syscall::SYS_OPENAT as usize;
+pub(crate) const SYSCLOSE: usize = syscall::SYS_CLOSE as usize;
+pub(crate) const SYSCLOCKGETTIME: usize = syscall::SYS_CLOCK_GETTIME as usize;
+pub(crate) const SYSCLOCKGETTIME64: usize = syscall::SYS_CLOCK_GETTIME64 as usize;
+pub(crate) const SYSGETTIMEOFDAY: usize = syscall::SYS_GETTIMEOFDAY as usize;
+
+pub(crate) const SYSHINTLEN: usize = syscall::SYS_HINT_LEN as usize;
+pub(crate) const SYSHINTREAD: usize = syscall::SYS_HINT_READ as usize;
+pub(crate) const SYSVERIFY: usize = syscall::SYS_VERIFY as usize;
+pub(crate) const SYSPREIMAGELEN: usize = syscall::SYS_PREIMAGE_LEN as usize;
+pub(crate) const SYSPREIMAGEREAD: usize = syscall::SYS_PREIMAGE_READ as usize;
+pub(crate) const SYSCYCLECOUNT: usize = syscall::SYS_CYCLE_COUNT as usize;
+pub(crate) const SYSKECCAK256: usize = syscall::SYS_KECCAK256 as usize;
+pub(crate) const SYSSHA256EXTEND: usize = syscall::SYS_SHA256_EXTEND as usize;
+pub(crate) const SYSSHA256COMPRESS: usize = syscall::SYS_SHA256_COMPRESS as usize;
+pub(crate) const SYSSECP256K1ADD: usize = syscall::SYS_SECP256K1_ADD as usize;
+pub(crate) const SYSSECP256K1DOUBLE: usize = syscall::SYS_SECP256K1_DOUBLE as usize;
+pub(crate) const SYSEDADD: usize = syscall::SYS_ED_ADD as usize;
+pub(crate) const SYSEDDECOMPRESS: usize = syscall::SYS_ED_DECOMPRESS as usize;
+pub(crate) const SYSBN254ADD: usize = syscall::SYS_BN254_ADD as usize;
+pub(crate) const SYSBN254DOUBLE: usize = syscall::SYS_BN254_DOUBLE as usize;
+pub(crate) const SYSSECP256R1ADD: usize = syscall::SYS_SECP256R1_ADD as usize;
+pub(crate) const SYSSECP256R1DOUBLE: usize = syscall::SYS_SECP256R1_DOUBLE as usize;
+pub(crate) const SYSBLS12381FPADD: usize = syscall::SYS_BLS12381_FP_ADD as usize;
+pub(crate) const SYSBLS12381FPSUB: usize = syscall::SYS_BLS12381_FP_SUB as usize;
+pub(crate) const SYSBLS12381FPMUL: usize = syscall::SYS_BLS12381_FP_MUL as usize;
+pub(crate) const SYSUINT256MULMOD: usize = syscall::SYS_UINT256_MULMOD as usize;
+pub(crate) const SYSBLAKE2BCOMPRESS: usize = syscall::SYS_BLAKE2B_COMPRESS as usize;
+pub(crate) const SYSBLAKE3COMPRESS: usize = syscall::SYS_BLAKE3_COMPRESS as usize;
+pub(crate) const SYSCHACHA20BLOCK: usize = syscall::SYS_CHACHA20_BLOCK as usize;
+pub(crate) const SYSMODEXP: usize = syscall::SYS_MODEXP as usize;
 
-pub(crate) const SYSHINTLEN: usize = 240;
-pub(crate) const SYSHINTREAD: usize = 241;
-pub(crate) const SYSVERIFY: usize = 242;
-
-pub(crate) const FD_STDIN: usize = 0;
 pub(crate) const FD_STDOUT: usize = 1;
 pub(crate) const FD_STDERR: usize = 2;
 pub(crate) const FD_PUBLIC_VALUES: usize = 3;
 pub(crate) const FD_HINT: usize = 4;
+pub(crate) const FD_CYCLE_TRACKER: usize = 5;
 
 pub(crate) const MIPSEBADF: usize = 0x9;
 
@@ -107,7 +194,6 @@ pub(crate) enum MemOp {
     LL,
     SC,
     LB,
-    SDC1,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -123,6 +209,8 @@ pub(crate) enum Operation {
     Jump(u8, u8),
     Jumpi(u8, u32),
     Branch(BranchCond, u8, u8, u32),
+    BranchLikely(BranchCond, u8, u8, u32),
+    BranchLink(BranchCond, u8, u32),
     JumpDirect(u8, u32),
     Pc,
     GetContext,
@@ -132,12 +220,16 @@ pub(crate) enum Operation {
     Nop,
     Ext(u8, u8, u8, u8),
     Ins(u8, u8, u8, u8),
-    Maddu(u8, u8),
+    Madd(MaddOp, u8, u8),
     Ror(u8, u8, u8),
+    Rorv(u8, u8, u8),
     Rdhwr(u8, u8),
+    Mfc0(u8, u8),
+    Mtc0(u8, u8),
+    Eret,
     Signext(u8, u8, u8),
     SwapHalf(u8, u8),
-    Teq(u8, u8),
+    Trap(TrapCond, u8, u8),
 }
 
 pub(crate) fn generate_cond_mov_op<
@@ -265,12 +357,13 @@ pub(crate) fn generate_binary_logic_imm_op<
 ) -> Result<(), ProgramError> {
     let (in0, log_in0) = reg_read_with_log(rs, 0, state, &mut row)?;
     let in1 = imm;
+    push_no_write(state, &mut row, in1, Some(1));
     let operation = logic::Operation::new(op, in0 as u32, in1);
     let out = operation.result;
 
     let log_out0 = reg_write_with_log(rd, 2, out as usize, state, &mut row)?;
 
-    //state.traces.push_logic(operation);
+    state.traces.push_logic(operation);
     state.traces.push_memory(log_in0);
     state.traces.push_memory(log_out0);
     state.traces.push_cpu(row);
@@ -298,6 +391,9 @@ pub(crate) fn generate_binary_arithmetic_op<
     .contains(&operator));
     let (in0, log_in0) = reg_read_with_log(rs, 0, state, &mut row)?;
     let (in1, log_in1) = reg_read_with_log(rt, 1, state, &mut row)?;
+    if operator.signed_overflow(in0 as u32, in1 as u32) {
+        return Err(ProgramError::IntegerOverflow);
+    }
     let operation = arithmetic::Operation::binary(operator, in0 as u32, in1 as u32);
     let out = operation.result().0;
 
@@ -382,6 +478,9 @@ pub(crate) fn generate_binary_arithmetic_imm_op<
 ) -> Result<(), ProgramError> {
     let (in0, log_in0) = reg_read_with_log(rs, 0, state, &mut row)?;
     let in1 = sign_extend::<16>(imm);
+    if operator.signed_overflow(in0 as u32, in1) {
+        return Err(ProgramError::IntegerOverflow);
+    }
     let log_in1 = reg_write_with_log(rt, 1, in1 as usize, state, &mut row)?;
     let operation = arithmetic::Operation::binary(operator, in0 as u32, in1);
 
@@ -501,6 +600,8 @@ pub(crate) fn generate_branch<
     src1: u8,
     src2: u8,
     target: u32,
+    likely: bool,
+    link: u8,
     state: &mut GenerationState<F, C, D>,
     mut row: CpuColumnsView<F>,
 ) -> Result<(), ProgramError> {
@@ -541,11 +642,31 @@ pub(crate) fn generate_branch<
     let log_out2 = reg_write_with_log(0, 4, aux3 as usize, state, &mut row)?;
     let log_out3 = reg_write_with_log(0, 5, aux4 as usize, state, &mut row)?;
     let pc = state.registers.program_counter as u32;
+    row.branch.is_likely = F::from_bool(likely);
+    // BLTZAL/BGEZAL link whether or not the branch is taken.
+    let link_op = if link != 0 {
+        row.branch.is_link = F::ONE;
+        Some(reg_write_with_log(
+            link,
+            6,
+            pc.wrapping_add(8) as usize,
+            state,
+            &mut row,
+        )?)
+    } else {
+        None
+    };
     if should_jump {
         target_pc = target_pc.wrapping_add(pc + 4);
         row.branch.should_jump = F::ONE;
         state.traces.push_cpu(row);
         state.jump_to(target_pc as usize);
+    } else if likely {
+        // Nullify the delay slot, continue with the instruction after it.
+        row.branch.should_jump = F::ZERO;
+        state.traces.push_cpu(row);
+        state.registers.program_counter = pc.wrapping_add(8) as usize;
+        state.registers.next_pc = pc.wrapping_add(12) as usize;
     } else {
         let next_pc = pc.wrapping_add(8);
         row.branch.should_jump = F::ZERO;
@@ -558,6 +679,9 @@ pub(crate) fn generate_branch<
     state.traces.push_memory(log_out1);
     state.traces.push_memory(log_out2);
     state.traces.push_memory(log_out3);
+    if let Some(link_op) = link_op {
+        state.traces.push_memory(link_op);
+    }
     Ok(())
 }
 
@@ -573,8 +697,14 @@ pub(crate) fn generate_jumpi<
 ) -> Result<(), ProgramError> {
     let (mut target_pc, _) = (target as usize).overflowing_shl(2);
     let pc = state.registers.program_counter;
+    // The target is in the 256MB region of the delay slot, not of the jump itself.
+    let delay_slot = (pc as u32).wrapping_add(4);
+    let bits_le = (0..32)
+        .map(|i| F::from_canonical_u32((delay_slot >> i) & 0x01))
+        .collect_vec();
+    row.general.jumps_mut().delay_slot_bits = bits_le.try_into().unwrap();
     let operation: logic::Operation =
-        logic::Operation::new(logic::Op::And, pc as u32, 0xf0000000u32);
+        logic::Operation::new(logic::Op::And, delay_slot, 0xf0000000u32);
     let pc_result = operation.result as usize;
     let result_op = reg_write_with_log(0, 2, pc_result, state, &mut row)?;
     target_pc = target_pc.wrapping_add(pc_result);
@@ -899,6 +1029,40 @@ pub(crate) fn generate_ror<
     Ok(())
 }
 
+pub(crate) fn generate_rorv<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    rd: u8,
+    rt: u8,
+    rs: u8,
+    state: &mut GenerationState<F, C, D>,
+    mut row: CpuColumnsView<F>,
+) -> Result<(), ProgramError> {
+    let (input0, log_in0) = reg_read_with_log(rt, 0, state, &mut row)?;
+    let (input1, log_in1) = reg_read_with_log(rs, 1, state, &mut row)?;
+
+    let sa = input1 & 0x1f;
+    let result = (input0 as u32).rotate_right(sa as u32);
+
+    let bits_le = |value: usize| -> [F; 32] {
+        core::array::from_fn(|i| F::from_canonical_usize((value >> i) & 0x01))
+    };
+    row.general.misc_mut().rs_bits = bits_le(input0);
+    row.general.misc_mut().is_msb = bits_le(input1);
+
+    row.general.misc_mut().is_lsb = [F::ZERO; 32];
+    row.general.misc_mut().is_lsb[sa] = F::ONE;
+
+    let outlog = reg_write_with_log(rd, 2, result as usize, state, &mut row)?;
+    state.traces.push_memory(log_in0);
+    state.traces.push_memory(log_in1);
+    state.traces.push_memory(outlog);
+    state.traces.push_cpu(row);
+    Ok(())
+}
+
 pub(crate) fn load_preimage<
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
@@ -1073,6 +1237,492 @@ pub(crate) fn load_input<
     Ok(())
 }
 
+/// Writes `bytes` to memory from `addr`, for the syscalls filling a guest buffer. The words which
+/// are only partially covered are read first, so that their other bytes are kept.
+pub(crate) fn store_bytes<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    state: &mut GenerationState<F, C, D>,
+    addr: usize,
+    bytes: &[u8],
+) -> Result<()> {
+    let mut cpu_row = CpuColumnsView::default();
+    cpu_row.clock = F::from_canonical_usize(state.traces.clock());
+    let mut j = 0;
+    for word_addr in ((addr & !3)..addr + bytes.len()).step_by(4) {
+        if j == 8 {
+            state.traces.push_cpu(cpu_row);
+            cpu_row = CpuColumnsView::default();
+            cpu_row.clock = F::from_canonical_usize(state.traces.clock());
+            j = 0;
+        }
+        let address = MemoryAddress::new(0, Segment::Code, word_addr);
+        let (mem, op) = mem_read_gp_with_log_and_fill(j, address, state, &mut cpu_row);
+        state.traces.push_memory(op);
+
+        let mut word = mem.to_be_bytes();
+        for (k, b) in word.iter_mut().enumerate() {
+            if let Some(v) = (word_addr + k).checked_sub(addr).and_then(|i| bytes.get(i)) {
+                *b = *v;
+            }
+        }
+        let op = mem_write_gp_log_and_fill(
+            j + 1,
+            address,
+            state,
+            &mut cpu_row,
+            u32::from_be_bytes(word),
+        );
+        state.traces.push_memory(op);
+        j += 2;
+    }
+
+    state.traces.push_cpu(cpu_row);
+
+    Ok(())
+}
+
+/// Reads the NUL-terminated string at `addr` without logging the accesses.
+fn peek_c_string<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    state: &GenerationState<F, C, D>,
+    addr: usize,
+) -> String {
+    let bytes = (addr..)
+        .map(|a| {
+            let word = state
+                .memory
+                .get(MemoryAddress::new(0, Segment::Code, a & !3));
+            word.to_be_bytes()[a & 3]
+        })
+        .take_while(|b| *b != 0)
+        .collect::<Vec<u8>>();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Returns the `len` bytes at `addr`, without logging the reads.
+fn peek_bytes<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    state: &GenerationState<F, C, D>,
+    addr: usize,
+    len: usize,
+) -> Vec<u8> {
+    (addr..addr + len)
+        .map(|a| {
+            let word = state
+                .memory
+                .get(MemoryAddress::new(0, Segment::Code, a & !3));
+            word.to_be_bytes()[a & 3]
+        })
+        .collect()
+}
+
+/// Reads the `len` bytes at `addr`, for the syscalls taking a key.
+pub(crate) fn load_bytes<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    state: &mut GenerationState<F, C, D>,
+    addr: usize,
+    len: usize,
+) -> Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(len);
+    let mut cpu_row = CpuColumnsView::default();
+    cpu_row.clock = F::from_canonical_usize(state.traces.clock());
+    let mut j = 0;
+    for word_addr in ((addr & !3)..addr + len).step_by(4) {
+        if j == 8 {
+            state.traces.push_cpu(cpu_row);
+            cpu_row = CpuColumnsView::default();
+            cpu_row.clock = F::from_canonical_usize(state.traces.clock());
+            j = 0;
+        }
+        let address = MemoryAddress::new(0, Segment::Code, word_addr);
+        let (mem, op) = mem_read_gp_with_log_and_fill(j, address, state, &mut cpu_row);
+        state.traces.push_memory(op);
+        j += 1;
+
+        for (k, b) in mem.to_be_bytes().into_iter().enumerate() {
+            if (addr..addr + len).contains(&(word_addr + k)) {
+                bytes.push(b);
+            }
+        }
+    }
+
+    state.traces.push_cpu(cpu_row);
+
+    Ok(bytes)
+}
+
+/// Hashes the `len` bytes at `addr` in the Keccak sponge table, and writes the digest to `out`.
+/// The sponge row is followed by a row writing the digest, one word per channel.
+pub(crate) fn keccak256<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    state: &mut GenerationState<F, C, D>,
+    addr: usize,
+    len: usize,
+    out: usize,
+) -> Result<()> {
+    let mut cpu_row = CpuColumnsView::default();
+    cpu_row.clock = F::from_canonical_usize(state.traces.clock());
+    cpu_row.is_keccak_sponge = F::ONE;
+
+    let base_address = (addr..addr + len.max(1))
+        .step_by(4)
+        .map(|virt| MemoryAddress::new(0, Segment::Code, virt))
+        .collect_vec();
+    // The sponge table looks up the address of the final block.
+    let final_block = base_address
+        .get(len / KECCAK_RATE_BYTES * KECCAK_RATE_U32S)
+        .map_or(0, |address| address.virt);
+    cpu_row.mem_channels[0].value = F::ZERO; // context
+    cpu_row.mem_channels[1].value = F::from_canonical_usize(Segment::Code as usize);
+    cpu_row.mem_channels[2].value = F::from_canonical_usize(final_block);
+    cpu_row.mem_channels[3].value = F::from_canonical_usize(len);
+
+    let input = peek_bytes(state, addr, len);
+    let digest = keccak_sponge_log(state, base_address, input);
+    let words = digest
+        .chunks_exact(4)
+        .map(|word| u32::from_be_bytes(word.try_into().unwrap()))
+        .collect_vec();
+    for (value, word) in cpu_row.general.keccak_mut().value.iter_mut().zip(&words) {
+        *value = F::from_canonical_u32(*word);
+    }
+    state.traces.push_cpu(cpu_row);
+
+    let mut cpu_row = CpuColumnsView::default();
+    cpu_row.clock = F::from_canonical_usize(state.traces.clock());
+    for (j, word) in words.into_iter().enumerate() {
+        let address = MemoryAddress::new(0, Segment::Code, out + 4 * j);
+        let op = mem_write_gp_log_and_fill(j, address, state, &mut cpu_row, word);
+        state.traces.push_memory(op);
+    }
+    state.traces.push_cpu(cpu_row);
+
+    Ok(())
+}
+
+/// Extends the message schedule at `w_ptr` in the SHA-256 extend table, which reads and writes
+/// `w` itself.
+pub(crate) fn sha_extend<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    state: &mut GenerationState<F, C, D>,
+    w_ptr: usize,
+) -> Result<()> {
+    let mut cpu_row = CpuColumnsView::default();
+    cpu_row.clock = F::from_canonical_usize(state.traces.clock());
+    cpu_row.is_sha_extend = F::ONE;
+    cpu_row.mem_channels[0].value = F::from_canonical_usize(w_ptr);
+
+    sha_extend_log(state, w_ptr);
+    state.traces.push_cpu(cpu_row);
+
+    Ok(())
+}
+
+/// Compresses the message schedule at `w_ptr` into the hash state at `h_ptr` in the SHA-256
+/// compress table, which reads both and writes the hash state.
+pub(crate) fn sha_compress<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    state: &mut GenerationState<F, C, D>,
+    w_ptr: usize,
+    h_ptr: usize,
+) -> Result<()> {
+    let mut cpu_row = CpuColumnsView::default();
+    cpu_row.clock = F::from_canonical_usize(state.traces.clock());
+    cpu_row.is_sha_compress = F::ONE;
+    cpu_row.mem_channels[0].value = F::from_canonical_usize(w_ptr);
+    cpu_row.mem_channels[1].value = F::from_canonical_usize(h_ptr);
+
+    sha_compress_log(state, w_ptr, h_ptr);
+    state.traces.push_cpu(cpu_row);
+
+    Ok(())
+}
+
+/// The base field modulus and the coefficient `a` of the curve of the `*_ADD` or `*_DOUBLE`
+/// syscall `sys_num`.
+fn weierstrass_curve(sys_num: usize) -> (&'static BigUint, &'static BigUint) {
+    match sys_num {
+        SYSSECP256K1ADD | SYSSECP256K1DOUBLE => (&secp256k1::MODULUS, &secp256k1::A),
+        SYSSECP256R1ADD | SYSSECP256R1DOUBLE => (&p256::MODULUS, &p256::A),
+        _ => (&bn254::MODULUS, &bn254::A),
+    }
+}
+
+/// Adds the point at `q_ptr` to the point at `p_ptr`, or doubles the point at `p_ptr`, in the
+/// table of the curve of the syscall `sys_num`, which reads the points and writes the result
+/// over `p`.
+pub(crate) fn weierstrass_op<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    state: &mut GenerationState<F, C, D>,
+    sys_num: usize,
+    kind: WeierstrassOpKind,
+    p_ptr: usize,
+    q_ptr: usize,
+) -> Result<()> {
+    let mut cpu_row = CpuColumnsView::default();
+    cpu_row.clock = F::from_canonical_usize(state.traces.clock());
+    match sys_num {
+        SYSSECP256K1ADD => cpu_row.is_secp256k1_add = F::ONE,
+        SYSSECP256K1DOUBLE => cpu_row.is_secp256k1_double = F::ONE,
+        SYSSECP256R1ADD => cpu_row.is_secp256r1_add = F::ONE,
+        SYSSECP256R1DOUBLE => cpu_row.is_secp256r1_double = F::ONE,
+        SYSBN254ADD => cpu_row.is_bn254_add = F::ONE,
+        _ => cpu_row.is_bn254_double = F::ONE,
+    }
+    cpu_row.mem_channels[0].value = F::from_canonical_usize(p_ptr);
+    cpu_row.mem_channels[1].value = F::from_canonical_usize(q_ptr);
+
+    match sys_num {
+        SYSSECP256K1ADD | SYSSECP256K1DOUBLE => secp256k1_log(state, kind, p_ptr, q_ptr),
+        SYSSECP256R1ADD | SYSSECP256R1DOUBLE => secp256r1_log(state, kind, p_ptr, q_ptr),
+        _ => bn254_log(state, kind, p_ptr, q_ptr),
+    }
+    state.traces.push_cpu(cpu_row);
+
+    Ok(())
+}
+
+/// Adds the point at `q_ptr` to the point at `p_ptr`, or decompresses the point at `p_ptr`, in
+/// the ed25519 table, which reads the inputs and writes the result over `p`.
+pub(crate) fn ed25519_op<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    state: &mut GenerationState<F, C, D>,
+    kind: Ed25519OpKind,
+    p_ptr: usize,
+    q_ptr: usize,
+) -> Result<()> {
+    let mut cpu_row = CpuColumnsView::default();
+    cpu_row.clock = F::from_canonical_usize(state.traces.clock());
+    match kind {
+        Ed25519OpKind::Add => cpu_row.is_ed25519_add = F::ONE,
+        Ed25519OpKind::Decompress => cpu_row.is_ed25519_decompress = F::ONE,
+    }
+    cpu_row.mem_channels[0].value = F::from_canonical_usize(p_ptr);
+    cpu_row.mem_channels[1].value = F::from_canonical_usize(q_ptr);
+
+    ed25519_log(state, kind, p_ptr, q_ptr);
+    state.traces.push_cpu(cpu_row);
+
+    Ok(())
+}
+
+/// Adds, subtracts or multiplies the element at `y_ptr` into the element at `x_ptr` in the
+/// BLS12-381 field table, which reads the elements and writes the result over `x`.
+pub(crate) fn bls12381_fp_op<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    state: &mut GenerationState<F, C, D>,
+    op: FpOp,
+    x_ptr: usize,
+    y_ptr: usize,
+) -> Result<()> {
+    let mut cpu_row = CpuColumnsView::default();
+    cpu_row.clock = F::from_canonical_usize(state.traces.clock());
+    match op {
+        FpOp::Add => cpu_row.is_bls12381_fp_add = F::ONE,
+        FpOp::Sub => cpu_row.is_bls12381_fp_sub = F::ONE,
+        FpOp::Mul => cpu_row.is_bls12381_fp_mul = F::ONE,
+    }
+    cpu_row.mem_channels[0].value = F::from_canonical_usize(x_ptr);
+    cpu_row.mem_channels[1].value = F::from_canonical_usize(y_ptr);
+
+    bls12381_fp_log(state, op, x_ptr, y_ptr);
+    state.traces.push_cpu(cpu_row);
+
+    Ok(())
+}
+
+/// Multiplies the integer at `x_ptr` by the integer at `y_ptr`, modulo the integer after it, in
+/// the uint256 table, which reads the operands and writes the result over `x`.
+pub(crate) fn uint256_mulmod<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    state: &mut GenerationState<F, C, D>,
+    x_ptr: usize,
+    y_ptr: usize,
+) -> Result<()> {
+    let mut cpu_row = CpuColumnsView::default();
+    cpu_row.clock = F::from_canonical_usize(state.traces.clock());
+    cpu_row.is_uint256_mulmod = F::ONE;
+    cpu_row.mem_channels[0].value = F::from_canonical_usize(x_ptr);
+    cpu_row.mem_channels[1].value = F::from_canonical_usize(y_ptr);
+
+    uint256_mulmod_log(state, x_ptr, y_ptr);
+    state.traces.push_cpu(cpu_row);
+
+    Ok(())
+}
+
+/// Compresses the block at `input_ptr` into the hash state at `h_ptr` in the BLAKE table, with
+/// BLAKE2b if `sys_num` is `BLAKE2B_COMPRESS` and BLAKE3 otherwise. The table reads the inputs
+/// and writes the new hash state.
+pub(crate) fn blake_compress<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    state: &mut GenerationState<F, C, D>,
+    sys_num: usize,
+    h_ptr: usize,
+    input_ptr: usize,
+) -> Result<()> {
+    let mut cpu_row = CpuColumnsView::default();
+    cpu_row.clock = F::from_canonical_usize(state.traces.clock());
+    cpu_row.mem_channels[0].value = F::from_canonical_usize(h_ptr);
+    cpu_row.mem_channels[1].value = F::from_canonical_usize(input_ptr);
+
+    if sys_num == SYSBLAKE2BCOMPRESS {
+        cpu_row.is_blake2b_compress = F::ONE;
+        blake2b_compress_log(state, h_ptr, input_ptr);
+    } else {
+        cpu_row.is_blake3_compress = F::ONE;
+        blake3_compress_log(state, h_ptr, input_ptr);
+    }
+    state.traces.push_cpu(cpu_row);
+
+    Ok(())
+}
+
+/// Replaces the state at `state_ptr` by its keystream block in the ChaCha table, which reads the
+/// state and writes the block.
+pub(crate) fn chacha20_block<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    state: &mut GenerationState<F, C, D>,
+    state_ptr: usize,
+) -> Result<()> {
+    let mut cpu_row = CpuColumnsView::default();
+    cpu_row.clock = F::from_canonical_usize(state.traces.clock());
+    cpu_row.is_chacha_block = F::ONE;
+    cpu_row.mem_channels[0].value = F::from_canonical_usize(state_ptr);
+
+    chacha20_block_log(state, state_ptr);
+    state.traces.push_cpu(cpu_row);
+
+    Ok(())
+}
+
+/// Raises the integer at `base_ptr` to the exponent in the parameters at `params_ptr`, modulo the
+/// modulus in them, in the modexp table, which reads the operands and writes the result over the
+/// base.
+pub(crate) fn modexp_op<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    state: &mut GenerationState<F, C, D>,
+    base_ptr: usize,
+    params_ptr: usize,
+) -> Result<()> {
+    let mut cpu_row = CpuColumnsView::default();
+    cpu_row.clock = F::from_canonical_usize(state.traces.clock());
+    cpu_row.is_modexp = F::ONE;
+    cpu_row.mem_channels[0].value = F::from_canonical_usize(base_ptr);
+    cpu_row.mem_channels[1].value = F::from_canonical_usize(params_ptr);
+
+    modexp_log(state, base_ptr, params_ptr);
+    state.traces.push_cpu(cpu_row);
+
+    Ok(())
+}
+
+/// Reads the `len` words at `ptr` without logging, to check the operands of a syscall.
+fn peek_words<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    state: &GenerationState<F, C, D>,
+    ptr: usize,
+    len: usize,
+) -> Vec<u32> {
+    (0..len)
+        .map(|i| {
+            state
+                .memory
+                .get(MemoryAddress::new(0, Segment::Code, ptr + 4 * i))
+        })
+        .collect()
+}
+
+/// Reads the point at `ptr` without logging, to check whether a curve formula applies to it.
+fn peek_point<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    state: &GenerationState<F, C, D>,
+    ptr: usize,
+) -> Point {
+    let mut point = [0u32; 16];
+    for (i, word) in point.iter_mut().enumerate() {
+        *word = state
+            .memory
+            .get(MemoryAddress::new(0, Segment::Code, ptr + 4 * i));
+    }
+    point
+}
+
+/// Reads the NUL-terminated string at `addr`, for the syscalls taking a path.
+pub(crate) fn load_c_string<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    state: &mut GenerationState<F, C, D>,
+    addr: usize,
+) -> Result<String> {
+    let mut bytes = Vec::new();
+    let mut cpu_row = CpuColumnsView::default();
+    cpu_row.clock = F::from_canonical_usize(state.traces.clock());
+    let mut j = 0;
+    let mut word_addr = addr & !3;
+    'words: loop {
+        if j == 8 {
+            state.traces.push_cpu(cpu_row);
+            cpu_row = CpuColumnsView::default();
+            cpu_row.clock = F::from_canonical_usize(state.traces.clock());
+            j = 0;
+        }
+        let address = MemoryAddress::new(0, Segment::Code, word_addr);
+        let (mem, op) = mem_read_gp_with_log_and_fill(j, address, state, &mut cpu_row);
+        state.traces.push_memory(op);
+        j += 1;
+
+        for (k, b) in mem.to_be_bytes().into_iter().enumerate() {
+            if word_addr + k < addr {
+                continue;
+            }
+            if b == 0 {
+                break 'words;
+            }
+            bytes.push(b);
+        }
+        word_addr += 4;
+    }
+
+    state.traces.push_cpu(cpu_row);
+
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
 pub(crate) fn generate_syscall<
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
@@ -1082,15 +1732,29 @@ pub(crate) fn generate_syscall<
     mut row: CpuColumnsView<F>,
     kernel: &Kernel,
 ) -> Result<(), ProgramError> {
-    let (sys_num, log_in1) = reg_read_with_log(2, 0, state, &mut row)?;
-    let (a0, log_in2) = reg_read_with_log(4, 1, state, &mut row)?;
-    let (a1, log_in3) = reg_read_with_log(5, 2, state, &mut row)?;
-    let (a2, log_in4) = reg_read_with_log(6, 3, state, &mut row)?;
+    let (sys_num, log_in1) = reg_read_with_log(syscall::REG_V0 as u8, 0, state, &mut row)?;
+    let (a0, log_in2) = reg_read_with_log(syscall::REG_A0 as u8, 1, state, &mut row)?;
+    let (a1, log_in3) = reg_read_with_log(syscall::REG_A1 as u8, 2, state, &mut row)?;
+    let (a2, log_in4) = reg_read_with_log(syscall::REG_A2 as u8, 3, state, &mut row)?;
     let mut v0 = 0usize;
     let mut v1 = 0usize;
     let mut is_load_preimage = false;
     let mut is_load_input = false;
     let mut is_verify = false;
+    let mut read_bytes = Vec::new();
+    let mut out_addr = a1;
+    let mut path_addr = None;
+    let mut key_addr = None;
+    let mut keccak = None;
+    let mut sha_extend_ptr = None;
+    let mut sha_compress_ptrs = None;
+    let mut weierstrass_call = None;
+    let mut ed25519_call = None;
+    let mut bls12381_fp_call = None;
+    let mut uint256_mulmod_ptrs = None;
+    let mut blake_call = None;
+    let mut chacha_ptr = None;
+    let mut modexp_ptrs = None;
     let result = match sys_num {
         SYSGETPID => {
             row.general.syscall_mut().sysnum[0] = F::ONE;
@@ -1122,6 +1786,7 @@ pub(crate) fn generate_syscall<
                 }
                 let (heap, log_in5) = reg_read_with_log(34, 6, state, &mut row)?;
                 v0 = heap;
+                state.guard_pages.map(heap as u32, sz as u32);
                 let heap = heap + sz;
                 let outlog = reg_write_with_log(34, 7, heap, state, &mut row)?;
                 state.traces.push_memory(log_in5);
@@ -1130,20 +1795,23 @@ pub(crate) fn generate_syscall<
                 row.general.syscall_mut().cond[3] = F::ONE;
                 row.general.syscall_mut().a0[2] = F::ONE;
                 v0 = a0;
+                state.guard_pages.map(a0 as u32, sz as u32);
             };
             Ok(())
         }
         SYSBRK => {
             row.general.syscall_mut().sysnum[2] = F::ONE;
             let (brk, log_in5) = reg_read_with_log(37, 6, state, &mut row)?;
-            if a0 > brk {
+            if a0 > brk && a0 <= syscall::MMAP_START as usize {
                 v0 = a0;
                 row.general.syscall_mut().cond[10] = F::ONE;
             } else {
                 v0 = brk;
                 row.general.syscall_mut().cond[11] = F::ONE;
             }
+            let outlog = reg_write_with_log(37, 7, v0, state, &mut row)?;
             state.traces.push_memory(log_in5);
+            state.traces.push_memory(outlog);
             Ok(())
         }
         SYSCLONE => {
@@ -1152,7 +1820,7 @@ pub(crate) fn generate_syscall<
             v0 = 1;
             Ok(())
         }
-        SYSEXITGROUP => {
+        SYSEXIT | SYSEXITGROUP => {
             row.general.syscall_mut().sysnum[4] = F::ONE;
             state.registers.exited = true;
             state.registers.exit_code = a0 as u8;
@@ -1160,16 +1828,23 @@ pub(crate) fn generate_syscall<
         }
         SYSREAD => {
             row.general.syscall_mut().sysnum[5] = F::ONE;
-            match a0 {
-                FD_STDIN => {
+            match state.fds.read(
+                a0 as u32,
+                a2,
+                &state.input_stream,
+                &mut state.input_stream_ptr,
+            ) {
+                Ok(bytes) => {
                     row.general.syscall_mut().a0[0] = F::ONE;
                     row.general.syscall_mut().cond[5] = F::ONE;
-                } // fdStdin
-                _ => {
+                    v0 = bytes.len();
+                    read_bytes = bytes;
+                }
+                Err(errno) => {
                     row.general.syscall_mut().a0[2] = F::ONE;
                     row.general.syscall_mut().cond[4] = F::ONE;
                     v0 = 0xFFFFFFFF;
-                    v1 = MIPSEBADF;
+                    v1 = errno as usize;
                 }
             };
             Ok(())
@@ -1178,10 +1853,15 @@ pub(crate) fn generate_syscall<
             row.general.syscall_mut().sysnum[6] = F::ONE;
             match a0 {
                 // fdStdout
-                FD_STDOUT | FD_STDERR | FD_PUBLIC_VALUES | FD_HINT => {
+                FD_STDOUT | FD_STDERR | FD_PUBLIC_VALUES | FD_HINT | FD_CYCLE_TRACKER => {
                     row.general.syscall_mut().a0[1] = F::ONE;
                     row.general.syscall_mut().cond[7] = F::ONE;
                     v0 = a2;
+                    if a0 == FD_PUBLIC_VALUES {
+                        // kept for the journal digest of the public values
+                        let bytes = peek_bytes(state, a1, a2);
+                        state.public_values_stream.extend(bytes);
+                    }
                 } // fdStdout
                 _ => {
                     row.general.syscall_mut().a0[2] = F::ONE;
@@ -1194,28 +1874,38 @@ pub(crate) fn generate_syscall<
         }
         SYSFCNTL => {
             row.general.syscall_mut().sysnum[7] = F::ONE;
-            match a0 {
-                FD_STDIN => {
+            // The constrained results are 0 (O_RDONLY, or a command without result), 1
+            // (O_WRONLY) and -1 with the errno given by the host.
+            match state.fds.fcntl(a0 as u32, a1 as u32) {
+                Ok(0) => {
                     row.general.syscall_mut().a0[0] = F::ONE;
                     row.general.syscall_mut().cond[8] = F::ONE;
                     v0 = 0;
-                } // fdStdin
-                FD_STDOUT | FD_STDERR => {
+                }
+                Ok(_) => {
                     row.general.syscall_mut().a0[1] = F::ONE;
                     row.general.syscall_mut().cond[9] = F::ONE;
                     v0 = 1;
-                } // fdStdout / fdStderr
-                _ => {
+                }
+                Err(errno) => {
                     row.general.syscall_mut().a0[2] = F::ONE;
                     v0 = 0xFFFFFFFF;
-                    v1 = MIPSEBADF;
+                    v1 = errno as usize;
                 }
             };
             Ok(())
         }
+        SYSIOCTL => {
+            row.general.syscall_mut().sysnum[11] = F::ONE;
+            if let Err(errno) = state.fds.ioctl(a0 as u32) {
+                v0 = 0xFFFFFFFF;
+                v1 = errno as usize;
+            }
+            Ok(())
+        }
         SYSSETTHREADAREA => {
             row.general.syscall_mut().sysnum[8] = F::ONE;
-            let localop = reg_write_with_log(38, 6, a0, state, &mut row)?;
+            let localop = reg_write_with_log(LOCAL_USER_REG as u8, 6, a0, state, &mut row)?;
             state.traces.push_memory(localop);
             Ok(())
         }
@@ -1233,17 +1923,255 @@ pub(crate) fn generate_syscall<
             is_load_input = true;
             Ok(())
         }
+        // The cycle count and the time are hints: their rows only select `sysnum[11]`, and the
+        // values returned are not checked.
+        SYSCYCLECOUNT => {
+            row.general.syscall_mut().sysnum[11] = F::ONE;
+            v0 = state.total_step as u32 as usize;
+            Ok(())
+        }
         SYSVERIFY => {
             is_verify = true;
             Ok(())
         }
+        SYSOPEN | SYSOPENAT => {
+            row.general.syscall_mut().sysnum[11] = F::ONE;
+            // openat: the directory fd is ignored, the registered files are looked up by path.
+            let (addr, flags) = if sys_num == SYSOPEN {
+                (a0, a1)
+            } else {
+                (a1, a2)
+            };
+            let path = peek_c_string(state, addr);
+            match state.fds.open(&path, flags as u32) {
+                Ok(fd) => v0 = fd as usize,
+                Err(errno) => {
+                    v0 = 0xFFFFFFFF;
+                    v1 = errno as usize;
+                }
+            }
+            path_addr = Some(addr);
+            Ok(())
+        }
+        SYSCLOCKGETTIME | SYSCLOCKGETTIME64 | SYSGETTIMEOFDAY => {
+            row.general.syscall_mut().sysnum[11] = F::ONE;
+            let nanos = syscall::guest_time_nanos(state.epoch, state.total_step as u64);
+            read_bytes = syscall::time_struct(sys_num as u32, nanos);
+            if sys_num == SYSGETTIMEOFDAY {
+                out_addr = a0;
+            }
+            Ok(())
+        }
+        SYSMUNMAP | SYSMPROTECT => {
+            row.general.syscall_mut().sysnum[11] = F::ONE;
+            let result = if sys_num == SYSMUNMAP {
+                state.guard_pages.unmap(a0 as u32, a1 as u32)
+            } else {
+                state.guard_pages.protect(a0 as u32, a1 as u32, a2 as u32)
+            };
+            if let Err(errno) = result {
+                v0 = 0xFFFFFFFF;
+                v1 = errno as usize;
+            }
+            Ok(())
+        }
+        SYSPREIMAGELEN | SYSPREIMAGEREAD => {
+            row.general.syscall_mut().sysnum[11] = F::ONE;
+            let key = peek_bytes(state, a0, syscall::PREIMAGE_KEY_SIZE);
+            match state.oracle.get(&key) {
+                Ok(data) if sys_num == SYSPREIMAGELEN => v0 = data.len(),
+                Ok(data) => {
+                    read_bytes = data[..data.len().min(a2)].to_vec();
+                    v0 = read_bytes.len();
+                }
+                Err(errno) => {
+                    v0 = 0xFFFFFFFF;
+                    v1 = errno as usize;
+                }
+            }
+            key_addr = Some(a0);
+            Ok(())
+        }
+        // A precompile call with invalid inputs traps, as its table only proves valid calls.
+        SYSKECCAK256 => {
+            row.general.syscall_mut().sysnum[11] = F::ONE;
+            if (a0 | a2) & 3 != 0 {
+                return Err(ProgramError::Trap);
+            }
+            keccak = Some((a0, a1, a2));
+            Ok(())
+        }
+        SYSSHA256EXTEND => {
+            row.general.syscall_mut().sysnum[11] = F::ONE;
+            if a0 & 3 != 0 {
+                return Err(ProgramError::Trap);
+            }
+            sha_extend_ptr = Some(a0);
+            Ok(())
+        }
+        SYSSHA256COMPRESS => {
+            row.general.syscall_mut().sysnum[11] = F::ONE;
+            if (a0 | a1) & 3 != 0 {
+                return Err(ProgramError::Trap);
+            }
+            sha_compress_ptrs = Some((a0, a1));
+            Ok(())
+        }
+        SYSSECP256K1ADD | SYSSECP256R1ADD | SYSBN254ADD => {
+            row.general.syscall_mut().sysnum[11] = F::ONE;
+            let (m, _) = weierstrass_curve(sys_num);
+            if (a0 | a1) & 3 != 0
+                || weierstrass::add(m, &peek_point(state, a0), &peek_point(state, a1)).is_none()
+            {
+                return Err(ProgramError::Trap);
+            }
+            weierstrass_call = Some((sys_num, WeierstrassOpKind::Add, a0, a1));
+            Ok(())
+        }
+        SYSSECP256K1DOUBLE | SYSSECP256R1DOUBLE | SYSBN254DOUBLE => {
+            row.general.syscall_mut().sysnum[11] = F::ONE;
+            let (m, a) = weierstrass_curve(sys_num);
+            if a0 & 3 != 0 || weierstrass::double(m, a, &peek_point(state, a0)).is_none() {
+                return Err(ProgramError::Trap);
+            }
+            weierstrass_call = Some((sys_num, WeierstrassOpKind::Double, a0, 0));
+            Ok(())
+        }
+        SYSEDADD => {
+            row.general.syscall_mut().sysnum[11] = F::ONE;
+            if (a0 | a1) & 3 != 0
+                || ed25519::add(&peek_point(state, a0), &peek_point(state, a1)).is_none()
+            {
+                return Err(ProgramError::Trap);
+            }
+            ed25519_call = Some((Ed25519OpKind::Add, a0, a1));
+            Ok(())
+        }
+        SYSEDDECOMPRESS => {
+            row.general.syscall_mut().sysnum[11] = F::ONE;
+            let mut compressed = [0u8; 32];
+            for (bytes, word) in compressed
+                .chunks_exact_mut(4)
+                .zip(&peek_point(state, a0)[8..])
+            {
+                bytes.copy_from_slice(&word.to_be_bytes());
+            }
+            if a0 & 3 != 0 || ed25519::decompress(&compressed).is_none() {
+                return Err(ProgramError::Trap);
+            }
+            ed25519_call = Some((Ed25519OpKind::Decompress, a0, 0));
+            Ok(())
+        }
+        SYSBLS12381FPADD | SYSBLS12381FPSUB | SYSBLS12381FPMUL => {
+            row.general.syscall_mut().sysnum[11] = F::ONE;
+            if (a0 | a1) & 3 != 0 {
+                return Err(ProgramError::Trap);
+            }
+            let op = match sys_num {
+                SYSBLS12381FPADD => FpOp::Add,
+                SYSBLS12381FPSUB => FpOp::Sub,
+                _ => FpOp::Mul,
+            };
+            bls12381_fp_call = Some((op, a0, a1));
+            Ok(())
+        }
+        SYSUINT256MULMOD => {
+            row.general.syscall_mut().sysnum[11] = F::ONE;
+            // `y` and the modulus after it are sixteen words, as a point.
+            if (a0 | a1) & 3 != 0 || peek_point(state, a1)[8..].iter().all(|&word| word == 0) {
+                return Err(ProgramError::Trap);
+            }
+            uint256_mulmod_ptrs = Some((a0, a1));
+            Ok(())
+        }
+        SYSBLAKE2BCOMPRESS | SYSBLAKE3COMPRESS => {
+            row.general.syscall_mut().sysnum[11] = F::ONE;
+            // The BLAKE2b `f` follows the block and `t`, 36 words in.
+            let invalid_f = sys_num == SYSBLAKE2BCOMPRESS
+                && state
+                    .memory
+                    .get(MemoryAddress::new(0, Segment::Code, a1 + 144))
+                    > 1;
+            if (a0 | a1) & 3 != 0 || invalid_f {
+                return Err(ProgramError::Trap);
+            }
+            blake_call = Some((sys_num, a0, a1));
+            Ok(())
+        }
+        SYSCHACHA20BLOCK => {
+            row.general.syscall_mut().sysnum[11] = F::ONE;
+            if a0 & 3 != 0 {
+                return Err(ProgramError::Trap);
+            }
+            chacha_ptr = Some(a0);
+            Ok(())
+        }
+        SYSMODEXP => {
+            row.general.syscall_mut().sysnum[11] = F::ONE;
+            let valid = (a0 | a1) & 3 == 0 && {
+                let lens = peek_words(state, a1, 2);
+                let (len, exp_len) = (lens[0] as usize, lens[1] as usize);
+                len <= modexp::MODEXP_MAX_WORDS
+                    && exp_len <= modexp::MODEXP_MAX_EXP_WORDS
+                    && modexp::modexp(
+                        &peek_words(state, a0, len),
+                        &peek_words(state, a1 + 8 + 4 * len, exp_len),
+                        &peek_words(state, a1 + 8, len),
+                    )
+                    .is_some()
+            };
+            if !valid {
+                return Err(ProgramError::Trap);
+            }
+            modexp_ptrs = Some((a0, a1));
+            Ok(())
+        }
+        SYSCLOSE => {
+            row.general.syscall_mut().sysnum[11] = F::ONE;
+            if let Err(errno) = state.fds.close(a0 as u32) {
+                v0 = 0xFFFFFFFF;
+                v1 = errno as usize;
+            }
+            Ok(())
+        }
         _ => {
             row.general.syscall_mut().sysnum[11] = F::ONE;
+            let args = syscall::SyscallArgs {
+                num: sys_num as u32,
+                a0: a0 as u32,
+                a1: a1 as u32,
+                a2: a2 as u32,
+                a3: state.registers.gprs[syscall::REG_A3] as u32,
+            };
+            if let Some((ret, out)) = syscall::runtime_syscall(&args) {
+                v0 = ret.v0 as usize;
+                v1 = ret.errno as usize;
+                if let Some((addr, bytes)) = out {
+                    out_addr = addr as usize;
+                    read_bytes = bytes;
+                }
+            }
             Ok(())
         }
     };
-    let outlog1 = reg_write_with_log(2, 4, v0, state, &mut row)?;
-    let outlog2 = reg_write_with_log(7, 5, v1, state, &mut row)?;
+    let syscall_row = row.general.syscall_mut();
+    for (i, &(num, _)) in PRECOMPILE_CALLS.iter().enumerate() {
+        let diff = F::from_canonical_usize(sys_num) - F::from_canonical_usize(num);
+        syscall_row.precompile[i] = F::from_bool(diff.is_zero());
+        syscall_row.precompile_inv[i] = diff.try_inverse().unwrap_or(F::ZERO);
+    }
+    for (i, &num) in EXIT_CALLS.iter().enumerate() {
+        let diff = F::from_canonical_usize(sys_num) - F::from_canonical_usize(num);
+        syscall_row.exit[i] = F::from_bool(diff.is_zero());
+        syscall_row.exit_inv[i] = diff.try_inverse().unwrap_or(F::ZERO);
+    }
+    if EXIT_CALLS.contains(&sys_num) {
+        for (i, bit) in syscall_row.exit_code_bits.iter_mut().enumerate() {
+            *bit = F::from_canonical_usize((a0 >> i) & 1);
+        }
+    }
+    let outlog1 = reg_write_with_log(syscall::REG_V0 as u8, 4, v0, state, &mut row)?;
+    let outlog2 = reg_write_with_log(syscall::REG_A3 as u8, 5, v1, state, &mut row)?;
     state.traces.push_memory(log_in1);
     state.traces.push_memory(log_in2);
     state.traces.push_memory(log_in3);
@@ -1252,15 +2180,67 @@ pub(crate) fn generate_syscall<
     state.traces.push_memory(outlog2);
     state.traces.push_cpu(row);
     if is_load_preimage {
-        let _ = load_preimage(state, kernel);
+        load_preimage(state, kernel)?;
     }
 
     if is_load_input {
-        let _ = load_input(state, a0, a1);
+        load_input(state, a0, a1)?;
     }
 
     if is_verify {
-        let _ = verify(state, a1, a2);
+        verify(state, a1, a2)?;
+    }
+
+    if !read_bytes.is_empty() {
+        store_bytes(state, out_addr, &read_bytes)?;
+    }
+
+    if let Some(addr) = path_addr {
+        load_c_string(state, addr)?;
+    }
+
+    if let Some(addr) = key_addr {
+        load_bytes(state, addr, syscall::PREIMAGE_KEY_SIZE)?;
+    }
+
+    if let Some((addr, len, out)) = keccak {
+        keccak256(state, addr, len, out)?;
+    }
+
+    if let Some(w_ptr) = sha_extend_ptr {
+        sha_extend(state, w_ptr)?;
+    }
+
+    if let Some((w_ptr, h_ptr)) = sha_compress_ptrs {
+        sha_compress(state, w_ptr, h_ptr)?;
+    }
+
+    if let Some((sys_num, kind, p_ptr, q_ptr)) = weierstrass_call {
+        weierstrass_op(state, sys_num, kind, p_ptr, q_ptr)?;
+    }
+
+    if let Some((kind, p_ptr, q_ptr)) = ed25519_call {
+        ed25519_op(state, kind, p_ptr, q_ptr)?;
+    }
+
+    if let Some((op, x_ptr, y_ptr)) = bls12381_fp_call {
+        bls12381_fp_op(state, op, x_ptr, y_ptr)?;
+    }
+
+    if let Some((x_ptr, y_ptr)) = uint256_mulmod_ptrs {
+        uint256_mulmod(state, x_ptr, y_ptr)?;
+    }
+
+    if let Some((sys_num, h_ptr, input_ptr)) = blake_call {
+        blake_compress(state, sys_num, h_ptr, input_ptr)?;
+    }
+
+    if let Some(state_ptr) = chacha_ptr {
+        chacha20_block(state, state_ptr)?;
+    }
+
+    if let Some((base_ptr, params_ptr)) = modexp_ptrs {
+        modexp_op(state, base_ptr, params_ptr)?;
     }
     result
 }
@@ -1282,6 +2262,12 @@ pub(crate) fn generate_mload_general<
 
     let virt_raw = (rs as u32).wrapping_add(sign_extend::<16>(offset));
     let virt = virt_raw & 0xFFFF_FFFC;
+    if state.guard_pages.contains(virt) {
+        return Err(ProgramError::PageFault {
+            is_store: false,
+            addr: virt_raw,
+        });
+    }
     let address = MemoryAddress::new(0, Segment::Code, virt as usize);
     let (mem, log_in3) = mem_read_gp_with_log_and_fill(2, address, state, &mut row);
 
@@ -1367,7 +2353,7 @@ pub(crate) fn generate_mload_general<
             let out = |i: u32| -> u32 { sign_extend::<8>((mem >> (24 - i * 8)) & 0xff) };
             (aux_rs_1_rs_0, out(rs & 3))
         }
-        _ => todo!(),
+        _ => return Err(ProgramError::InvalidOpcode),
     };
 
     row.general.io_mut().aux_rs0_mul_rs1 = F::from_canonical_u32(aux_a);
@@ -1400,6 +2386,12 @@ pub(crate) fn generate_mstore_general<
 
     let virt_raw = (rs as u32).wrapping_add(sign_extend::<16>(offset));
     let virt = virt_raw & 0xFFFF_FFFC;
+    if state.guard_pages.contains(virt) {
+        return Err(ProgramError::PageFault {
+            is_store: true,
+            addr: virt_raw,
+        });
+    }
     let address = MemoryAddress::new(0, Segment::Code, virt as usize);
     let (mem, log_in3) = mem_read_gp_with_log_and_fill(2, address, state, &mut row);
 
@@ -1473,7 +2465,7 @@ pub(crate) fn generate_mstore_general<
         MemOp::SWR => {
             row.memio.is_swr = F::ONE;
             let out = |i: u32| -> u32 {
-                let val = rt << (24 - (rs & i) * 8);
+                let val = rt << (24 - i * 8);
                 let mask = 0xffFFffFFu32 << (24 - i * 8);
                 (mem & (!mask)) | val
             };
@@ -1483,11 +2475,7 @@ pub(crate) fn generate_mstore_general<
             row.memio.is_sc = F::ONE;
             (0, rt)
         }
-        MemOp::SDC1 => {
-            row.memio.is_sdc1 = F::ONE;
-            (0, 0)
-        }
-        _ => todo!(),
+        _ => return Err(ProgramError::InvalidOpcode),
     };
 
     row.general.io_mut().aux_rs0_mul_rs1 = F::from_canonical_u32(aux_a);
@@ -1613,11 +2601,12 @@ pub(crate) fn generate_insert<
     Ok(())
 }
 
-pub(crate) fn generate_maddu<
+pub(crate) fn generate_madd<
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
     const D: usize,
 >(
+    op: MaddOp,
     rt: u8,
     rs: u8,
     state: &mut GenerationState<F, C, D>,
@@ -1627,12 +2616,53 @@ pub(crate) fn generate_maddu<
     let (in1, log_in1) = reg_read_with_log(rt, 1, state, &mut row)?;
     let (in2, log_in2) = reg_read_with_log(33, 2, state, &mut row)?;
     let (in3, log_in3) = reg_read_with_log(32, 3, state, &mut row)?;
-    let mul = in0 * in1;
-    let addend = (in2 << 32) + in3;
-    let (result, overflow) = (mul as u64).overflowing_add(addend as u64);
+
+    let extend = |x: usize| -> i64 {
+        if op.is_signed() {
+            (x as u32 as i32) as i64
+        } else {
+            x as u32 as i64
+        }
+    };
+    let mul = extend(in0) as i128 * extend(in1) as i128;
+    let addend = (((in2 as u64) << 32) | (in3 as u64)) as i128;
+    let acc = if op.is_sub() {
+        addend - mul
+    } else {
+        addend + mul
+    };
+    let result = acc as u64;
+
     let log_out0 = reg_write_with_log(33, 4, (result >> 32) as usize, state, &mut row)?;
     let log_out1 = reg_write_with_log(32, 5, (result as u32) as usize, state, &mut row)?;
-    row.general.misc_mut().auxm = F::from_canonical_usize((overflow as usize) << 32);
+
+    // The word identities of `eval_packed_madd`, where X + product == Y (mod 2^64).
+    let (x, y) = if op.is_sub() {
+        (result, addend as u64)
+    } else {
+        (addend as u64, result)
+    };
+    let (rs, rt) = (in0 as u32 as i128, in1 as u32 as i128);
+    let (a0, a1, b0, b1) = (rs & 0xffff, rs >> 16, rt & 0xffff, rt >> 16);
+    let rs_neg = op.is_signed() && (in0 as u32 as i32) < 0;
+    let rt_neg = op.is_signed() && (in1 as u32 as i32) < 0;
+    let low = (x as u32) as i128 + a0 * b0 + ((a0 * b1 + a1 * b0) << 16);
+    let carry_lo = low >> 32;
+    let sign_correction = rs_neg as i128 * rt + rt_neg as i128 * rs;
+    let high = (x >> 32) as i128 + a1 * b1 + carry_lo - sign_correction;
+    let carry_hi = (high - (y >> 32) as i128) >> 32;
+
+    let bits_le = |x: u64| -> [F; 32] { core::array::from_fn(|i| F::from_bool(x >> i & 1 == 1)) };
+    let madd = row.general.madd_mut();
+    madd.rs_bits = bits_le(in0 as u64);
+    madd.rt_bits = bits_le(in1 as u64);
+    madd.rs_neg = F::from_bool(rs_neg);
+    madd.rt_neg = F::from_bool(rt_neg);
+    madd.hi_bits = bits_le(result >> 32);
+    madd.lo_bits = bits_le(result & 0xffff_ffff);
+    madd.carry_lo_bits = core::array::from_fn(|i| F::from_bool(carry_lo >> i & 1 == 1));
+    madd.carry_hi_bits = core::array::from_fn(|i| F::from_bool((carry_hi + 2) >> i & 1 == 1));
+
     state.traces.push_memory(log_in0);
     state.traces.push_memory(log_in1);
     state.traces.push_memory(log_in2);
@@ -1642,6 +2672,7 @@ pub(crate) fn generate_maddu<
     state.traces.push_cpu(row);
     Ok(())
 }
+
 pub(crate) fn generate_rdhwr<
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
@@ -1652,17 +2683,15 @@ pub(crate) fn generate_rdhwr<
     state: &mut GenerationState<F, C, D>,
     mut row: CpuColumnsView<F>,
 ) -> Result<(), ProgramError> {
-    row.general.misc_mut().rd_index = F::from_canonical_u8(rd);
-    let result = if rd == 0 {
-        row.general.misc_mut().rd_index_eq_0 = F::ONE;
-        1
-    } else if rd == 29 {
-        row.general.misc_mut().rd_index_eq_29 = F::ONE;
-        let (in0, log_in0) = reg_read_with_log(38, 1, state, &mut row)?;
-        state.traces.push_memory(log_in0);
-        in0
-    } else {
-        0
+    fill_rdhwr_selectors(&mut row, rd);
+    let result = match HW_REGISTERS.iter().find(|&&(reg, _)| reg == rd) {
+        Some((_, HwRegister::Const(value))) => *value as usize,
+        Some((_, HwRegister::Register(slot))) => {
+            let (value, log_in0) = reg_read_with_log(*slot as u8, 1, state, &mut row)?;
+            state.traces.push_memory(log_in0);
+            value
+        }
+        None => 0,
     };
 
     let log_out0 = reg_write_with_log(rt, 0, result, state, &mut row)?;
@@ -1673,6 +2702,64 @@ pub(crate) fn generate_rdhwr<
     Ok(())
 }
 
+/// MFC0 and MTC0 move between `rt` and the register file slot of CP0 register `rd`.
+pub(crate) fn generate_cop0_move<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    to_cp0: bool,
+    rt: u8,
+    rd: u8,
+    state: &mut GenerationState<F, C, D>,
+    mut row: CpuColumnsView<F>,
+) -> Result<(), ProgramError> {
+    let cp0_reg = (CP0_REGISTERS_START as u8) + rd;
+    let (src, dst) = if to_cp0 { (rt, cp0_reg) } else { (cp0_reg, rt) };
+    let (value, log_in0) = reg_read_with_log(src, 0, state, &mut row)?;
+    let log_out0 = reg_write_with_log(dst, 1, value, state, &mut row)?;
+
+    state.traces.push_memory(log_in0);
+    state.traces.push_memory(log_out0);
+    state.traces.push_cpu(row);
+
+    Ok(())
+}
+
+/// ERET resumes at EPC, with Status.EXL cleared; no delay slot is executed.
+pub(crate) fn generate_eret<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    state: &mut GenerationState<F, C, D>,
+    mut row: CpuColumnsView<F>,
+) -> Result<(), ProgramError> {
+    let epc_reg = (CP0_REGISTERS_START + cp0::CP0_EPC) as u8;
+    let status_reg = (CP0_REGISTERS_START + cp0::CP0_STATUS) as u8;
+    let (epc, log_in0) = reg_read_with_log(epc_reg, 0, state, &mut row)?;
+    let (status, log_in1) = reg_read_with_log(status_reg, 1, state, &mut row)?;
+    row.general
+        .misc_mut()
+        .rs_bits
+        .iter_mut()
+        .enumerate()
+        .for_each(|(i, v)| *v = F::from_canonical_usize((status >> i) & 1));
+    let status = status & !(cp0::STATUS_EXL as usize);
+    let log_out0 = reg_write_with_log(status_reg, 2, status, state, &mut row)?;
+    // ERET has no delay slot.
+    row.next_program_counter = F::from_canonical_usize(epc);
+
+    state.traces.push_memory(log_in0);
+    state.traces.push_memory(log_in1);
+    state.traces.push_memory(log_out0);
+    state.traces.push_cpu(row);
+
+    state.registers.program_counter = epc;
+    state.registers.next_pc = epc + 4;
+    Ok(())
+}
+
 pub(crate) fn generate_signext<
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
@@ -1747,11 +2834,12 @@ pub(crate) fn generate_swaphalf<
     Ok(())
 }
 
-pub(crate) fn generate_teq<
+pub(crate) fn generate_trap<
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
     const D: usize,
 >(
+    cond: TrapCond,
     rs: u8,
     rt: u8,
     state: &mut GenerationState<F, C, D>,
@@ -1759,14 +2847,34 @@ pub(crate) fn generate_teq<
 ) -> Result<(), ProgramError> {
     let (in0, log_in0) = reg_read_with_log(rs, 0, state, &mut row)?;
     let (in1, log_in1) = reg_read_with_log(rt, 1, state, &mut row)?;
-    if in0 == in1 {
-        Err(ProgramError::Trap)
-    } else {
-        generate_pinv_diff(in0 as u32, in1 as u32, &mut row);
-        state.traces.push_memory(log_in0);
-        state.traces.push_memory(log_in1);
-        state.traces.push_cpu(row);
+    if cond.result(in0 as u32, in1 as u32) {
+        return Err(ProgramError::Trap);
+    }
 
-        Ok(())
+    match cond {
+        TrapCond::EQ | TrapCond::NE => generate_pinv_diff(in0 as u32, in1 as u32, &mut row),
+        TrapCond::GE | TrapCond::LT => {
+            let operation = arithmetic::Operation::binary(
+                arithmetic::BinaryOperator::SLT,
+                in0 as u32,
+                in1 as u32,
+            );
+            push_no_write(state, &mut row, operation.result().0, Some(2));
+            state.traces.push_arithmetic(operation);
+        }
+        TrapCond::GEU | TrapCond::LTU => {
+            let operation = arithmetic::Operation::binary(
+                arithmetic::BinaryOperator::SLTU,
+                in0 as u32,
+                in1 as u32,
+            );
+            push_no_write(state, &mut row, operation.result().0, Some(2));
+            state.traces.push_arithmetic(operation);
+        }
     }
+
+    state.traces.push_memory(log_in0);
+    state.traces.push_memory(log_in1);
+    state.traces.push_cpu(row);
+    Ok(())
 }
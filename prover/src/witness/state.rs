@@ -1,4 +1,5 @@
 use crate::cpu::kernel::assembler::Kernel;
+use zkm_emulator::cp0::Cp0;
 const KERNEL_CONTEXT: usize = 0;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -11,6 +12,7 @@ pub struct RegistersState {
     pub next_pc: usize,
     pub brk: usize,
     pub local_user: usize,
+    pub cp0: Cp0,
     pub is_kernel: bool,
     pub context: usize,
     pub exited: bool,
@@ -38,6 +40,7 @@ impl RegistersState {
             next_pc: kernel.program.next_pc,
             brk: kernel.program.brk,
             local_user: kernel.program.local_user,
+            cp0: kernel.program.cp0,
             is_kernel: true,
             context: 0,
             exited: false,
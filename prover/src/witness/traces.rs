@@ -1,19 +1,36 @@
+use crate::util::traced;
+use itertools::Itertools;
 use plonky2::field::extension::Extendable;
 use plonky2::field::polynomial::PolynomialValues;
 use plonky2::hash::hash_types::RichField;
-use plonky2::timed;
 use plonky2::util::timing::TimingTree;
+use plonky2_maybe_rayon::join;
 use std::cmp::max;
+use std::mem::size_of;
 
 use crate::all_stark::{AllStark, MIN_TRACE_LEN, NUM_TABLES};
 use crate::arithmetic::{BinaryOperator, Operation};
+use crate::blake::compress_stark::BlakeCompressOp;
+use crate::bls12_381::fp_stark::Bls12381FpOp;
+use crate::chacha::block_stark::ChaChaBlockOp;
+use crate::chacha::columns::NUM_CHACHA_ROWS;
 use crate::config::StarkConfig;
-use crate::cpu::columns::CpuColumnsView;
+use crate::cpu::columns::{CpuColumnsView, NUM_CPU_COLUMNS};
 
+use crate::ed25519::ed25519_stark::Ed25519Op;
+use crate::keccak::keccak_stark::{NUM_INPUTS, NUM_ROUNDS};
+use crate::keccak_sponge::columns::{KECCAK_RATE_BYTES, KECCAK_WIDTH_BYTES};
+use crate::keccak_sponge::keccak_sponge_stark::KeccakSpongeOp;
+use crate::modexp::modexp_stark::ModExpOp;
 use crate::poseidon::constants::SPONGE_WIDTH;
 use crate::poseidon_sponge::columns::POSEIDON_RATE_BYTES;
 use crate::poseidon_sponge::poseidon_sponge_stark::PoseidonSpongeOp;
-use crate::util::trace_rows_to_poly_values;
+use crate::sha256::columns::{NUM_COMPRESS_ROWS, NUM_EXTEND_ROWS};
+use crate::sha256::sha_compress_stark::ShaCompressOp;
+use crate::sha256::sha_extend_stark::ShaExtendOp;
+use crate::uint256::mulmod_stark::Uint256MulModOp;
+use crate::util::rows_to_poly_values;
+use crate::weierstrass::weierstrass_stark::WeierstrassOp;
 use crate::witness::memory::MemoryOp;
 use crate::{arithmetic, logic};
 
@@ -23,6 +40,19 @@ pub struct TraceCheckpoint {
     pub(self) cpu_len: usize,
     pub(self) poseidon_len: usize,
     pub(self) poseidon_sponge_len: usize,
+    pub(self) keccak_len: usize,
+    pub(self) keccak_sponge_len: usize,
+    pub(self) sha_extend_len: usize,
+    pub(self) sha_compress_len: usize,
+    pub(self) secp256k1_len: usize,
+    pub(self) ed25519_len: usize,
+    pub(self) bn254_len: usize,
+    pub(self) bls12381_fp_len: usize,
+    pub(self) uint256_len: usize,
+    pub(self) blake_len: usize,
+    pub(self) chacha_len: usize,
+    pub(self) secp256r1_len: usize,
+    pub(self) modexp_len: usize,
     pub(self) logic_len: usize,
     pub(self) memory_len: usize,
 }
@@ -35,6 +65,19 @@ pub(crate) struct Traces<T: Copy> {
     pub(crate) memory_ops: Vec<MemoryOp>,
     pub(crate) poseidon_inputs: Vec<([T; SPONGE_WIDTH], usize)>,
     pub(crate) poseidon_sponge_ops: Vec<PoseidonSpongeOp>,
+    pub(crate) keccak_inputs: Vec<([u64; NUM_INPUTS], usize)>,
+    pub(crate) keccak_sponge_ops: Vec<KeccakSpongeOp>,
+    pub(crate) sha_extend_ops: Vec<ShaExtendOp>,
+    pub(crate) sha_compress_ops: Vec<ShaCompressOp>,
+    pub(crate) secp256k1_ops: Vec<WeierstrassOp>,
+    pub(crate) ed25519_ops: Vec<Ed25519Op>,
+    pub(crate) bn254_ops: Vec<WeierstrassOp>,
+    pub(crate) bls12381_fp_ops: Vec<Bls12381FpOp>,
+    pub(crate) uint256_ops: Vec<Uint256MulModOp>,
+    pub(crate) blake_ops: Vec<BlakeCompressOp>,
+    pub(crate) chacha_ops: Vec<ChaChaBlockOp>,
+    pub(crate) secp256r1_ops: Vec<WeierstrassOp>,
+    pub(crate) modexp_ops: Vec<ModExpOp>,
 }
 
 impl<T: Copy> Traces<T> {
@@ -46,6 +89,19 @@ impl<T: Copy> Traces<T> {
             memory_ops: vec![],
             poseidon_inputs: vec![],
             poseidon_sponge_ops: vec![],
+            keccak_inputs: vec![],
+            keccak_sponge_ops: vec![],
+            sha_extend_ops: vec![],
+            sha_compress_ops: vec![],
+            secp256k1_ops: vec![],
+            ed25519_ops: vec![],
+            bn254_ops: vec![],
+            bls12381_fp_ops: vec![],
+            uint256_ops: vec![],
+            blake_ops: vec![],
+            chacha_ops: vec![],
+            secp256r1_ops: vec![],
+            modexp_ops: vec![],
         }
     }
 
@@ -70,6 +126,23 @@ impl<T: Copy> Traces<T> {
                 .iter()
                 .map(|op| op.input.len() / POSEIDON_RATE_BYTES + 1)
                 .sum(),
+            keccak_len: self.keccak_inputs.len() * NUM_ROUNDS,
+            keccak_sponge_len: self
+                .keccak_sponge_ops
+                .iter()
+                .map(|op| op.input.len() / KECCAK_RATE_BYTES + 1)
+                .sum(),
+            sha_extend_len: self.sha_extend_ops.len() * NUM_EXTEND_ROWS,
+            sha_compress_len: self.sha_compress_ops.len() * NUM_COMPRESS_ROWS,
+            secp256k1_len: self.secp256k1_ops.len(),
+            ed25519_len: self.ed25519_ops.len(),
+            bn254_len: self.bn254_ops.len(),
+            bls12381_fp_len: self.bls12381_fp_ops.len(),
+            uint256_len: self.uint256_ops.len(),
+            blake_len: self.blake_ops.iter().map(BlakeCompressOp::num_rows).sum(),
+            chacha_len: self.chacha_ops.len() * NUM_CHACHA_ROWS,
+            secp256r1_len: self.secp256r1_ops.len(),
+            modexp_len: self.modexp_ops.iter().map(ModExpOp::num_rows).sum(),
             logic_len: self.logic_ops.len(),
             // This is technically a lower-bound, as we may fill gaps,
             // but this gives a relatively good estimate.
@@ -84,6 +157,19 @@ impl<T: Copy> Traces<T> {
             cpu_len: self.cpu.len(),
             poseidon_len: self.poseidon_inputs.len(),
             poseidon_sponge_len: self.poseidon_sponge_ops.len(),
+            keccak_len: self.keccak_inputs.len(),
+            keccak_sponge_len: self.keccak_sponge_ops.len(),
+            sha_extend_len: self.sha_extend_ops.len(),
+            sha_compress_len: self.sha_compress_ops.len(),
+            secp256k1_len: self.secp256k1_ops.len(),
+            ed25519_len: self.ed25519_ops.len(),
+            bn254_len: self.bn254_ops.len(),
+            bls12381_fp_len: self.bls12381_fp_ops.len(),
+            uint256_len: self.uint256_ops.len(),
+            blake_len: self.blake_ops.len(),
+            chacha_len: self.chacha_ops.len(),
+            secp256r1_len: self.secp256r1_ops.len(),
+            modexp_len: self.modexp_ops.len(),
             logic_len: self.logic_ops.len(),
             memory_len: self.memory_ops.len(),
         }
@@ -95,6 +181,20 @@ impl<T: Copy> Traces<T> {
         self.poseidon_inputs.truncate(checkpoint.poseidon_len);
         self.poseidon_sponge_ops
             .truncate(checkpoint.poseidon_sponge_len);
+        self.keccak_inputs.truncate(checkpoint.keccak_len);
+        self.keccak_sponge_ops
+            .truncate(checkpoint.keccak_sponge_len);
+        self.sha_extend_ops.truncate(checkpoint.sha_extend_len);
+        self.sha_compress_ops.truncate(checkpoint.sha_compress_len);
+        self.secp256k1_ops.truncate(checkpoint.secp256k1_len);
+        self.ed25519_ops.truncate(checkpoint.ed25519_len);
+        self.bn254_ops.truncate(checkpoint.bn254_len);
+        self.bls12381_fp_ops.truncate(checkpoint.bls12381_fp_len);
+        self.uint256_ops.truncate(checkpoint.uint256_len);
+        self.blake_ops.truncate(checkpoint.blake_len);
+        self.chacha_ops.truncate(checkpoint.chacha_len);
+        self.secp256r1_ops.truncate(checkpoint.secp256r1_len);
+        self.modexp_ops.truncate(checkpoint.modexp_len);
         self.logic_ops.truncate(checkpoint.logic_len);
         self.memory_ops.truncate(checkpoint.memory_len);
     }
@@ -103,6 +203,11 @@ impl<T: Copy> Traces<T> {
         &self.memory_ops[checkpoint.memory_len..]
     }
 
+    /// The CPU rows pushed since `checkpoint`.
+    pub fn cpu_rows_since_mut(&mut self, checkpoint: TraceCheckpoint) -> &mut [CpuColumnsView<T>] {
+        &mut self.cpu[checkpoint.cpu_len..]
+    }
+
     pub fn push_cpu(&mut self, val: CpuColumnsView<T>) {
         self.cpu.push(val);
     }
@@ -127,6 +232,68 @@ impl<T: Copy> Traces<T> {
         self.poseidon_sponge_ops.push(op);
     }
 
+    pub fn push_keccak(&mut self, input: [u64; NUM_INPUTS], clock: usize) {
+        self.keccak_inputs.push((input, clock));
+    }
+
+    pub fn push_keccak_bytes(&mut self, input: [u8; KECCAK_WIDTH_BYTES], clock: usize) {
+        let chunks = input
+            .chunks(size_of::<u64>())
+            .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+            .collect_vec()
+            .try_into()
+            .unwrap();
+        self.push_keccak(chunks, clock);
+    }
+
+    pub fn push_keccak_sponge(&mut self, op: KeccakSpongeOp) {
+        self.keccak_sponge_ops.push(op);
+    }
+
+    pub fn push_sha_extend(&mut self, op: ShaExtendOp) {
+        self.sha_extend_ops.push(op);
+    }
+
+    pub fn push_sha_compress(&mut self, op: ShaCompressOp) {
+        self.sha_compress_ops.push(op);
+    }
+
+    pub fn push_secp256k1(&mut self, op: WeierstrassOp) {
+        self.secp256k1_ops.push(op);
+    }
+
+    pub fn push_ed25519(&mut self, op: Ed25519Op) {
+        self.ed25519_ops.push(op);
+    }
+
+    pub fn push_bn254(&mut self, op: WeierstrassOp) {
+        self.bn254_ops.push(op);
+    }
+
+    pub fn push_bls12381_fp(&mut self, op: Bls12381FpOp) {
+        self.bls12381_fp_ops.push(op);
+    }
+
+    pub fn push_uint256(&mut self, op: Uint256MulModOp) {
+        self.uint256_ops.push(op);
+    }
+
+    pub fn push_blake(&mut self, op: BlakeCompressOp) {
+        self.blake_ops.push(op);
+    }
+
+    pub fn push_chacha(&mut self, op: ChaChaBlockOp) {
+        self.chacha_ops.push(op);
+    }
+
+    pub fn push_secp256r1(&mut self, op: WeierstrassOp) {
+        self.secp256r1_ops.push(op);
+    }
+
+    pub fn push_modexp(&mut self, op: ModExpOp) {
+        self.modexp_ops.push(op);
+    }
+
     pub fn clock(&self) -> usize {
         self.cpu.len()
     }
@@ -149,50 +316,209 @@ impl<T: Copy> Traces<T> {
             memory_ops,
             poseidon_inputs,
             poseidon_sponge_ops,
+            keccak_inputs,
+            keccak_sponge_ops,
+            sha_extend_ops,
+            sha_compress_ops,
+            secp256k1_ops,
+            ed25519_ops,
+            bn254_ops,
+            bls12381_fp_ops,
+            uint256_ops,
+            blake_ops,
+            chacha_ops,
+            secp256r1_ops,
+            modexp_ops,
         } = self;
 
-        let arithmetic_trace = timed!(
-            timing,
-            "generate arithmetic trace",
-            all_stark.arithmetic_stark.generate_trace(arithmetic_ops)
-        );
-        let cpu_rows: Vec<_> = cpu.into_iter().map(|x| x.into()).collect();
-        let cpu_trace = trace_rows_to_poly_values(cpu_rows);
-        let poseidon_trace = timed!(
-            timing,
-            "generate Poseidon trace",
-            all_stark
-                .poseidon_stark
-                .generate_trace(poseidon_inputs, min_rows, timing)
-        );
-        let poseidon_sponge_trace = timed!(
-            timing,
-            "generate Poseidon sponge trace",
-            all_stark
-                .poseidon_sponge_stark
-                .generate_trace(poseidon_sponge_ops, min_rows, timing)
-        );
-        let logic_trace = timed!(
-            timing,
-            "generate logic trace",
-            all_stark
-                .logic_stark
-                .generate_trace(logic_ops, min_rows, timing)
-        );
-        let memory_trace = timed!(
-            timing,
-            "generate memory trace",
-            all_stark.memory_stark.generate_trace(memory_ops, timing)
+        // The tables are independent, so their traces are generated in parallel. Each is a
+        // function of its operations alone, whose order is that of the execution, so the traces
+        // are the same whatever the scheduling.
+        let (memory_trace, ((arithmetic_trace, cpu_trace, logic_trace), precompile_traces)) = join(
+            || {
+                let _span = tracing::info_span!("phase", name = "generate memory trace").entered();
+                let mut timing = TimingTree::new("generate memory trace", log::Level::Debug);
+                let trace = all_stark
+                    .memory_stark
+                    .generate_trace(memory_ops, &mut timing);
+                timing.print();
+                trace
+            },
+            || {
+                join(
+                    || {
+                        let mut timing = TimingTree::new("generate CPU traces", log::Level::Debug);
+                        let timing = &mut timing;
+                        let arithmetic_trace = traced!(
+                            timing,
+                            "generate arithmetic trace",
+                            all_stark.arithmetic_stark.generate_trace(arithmetic_ops)
+                        );
+                        let cpu_trace = traced!(
+                            timing,
+                            "generate CPU trace",
+                            rows_to_poly_values::<_, _, NUM_CPU_COLUMNS>(cpu)
+                        );
+                        let logic_trace = traced!(
+                            timing,
+                            "generate logic trace",
+                            all_stark
+                                .logic_stark
+                                .generate_trace(logic_ops, min_rows, timing)
+                        );
+                        timing.print();
+                        (arithmetic_trace, cpu_trace, logic_trace)
+                    },
+                    || {
+                        let poseidon_trace = traced!(
+                            timing,
+                            "generate Poseidon trace",
+                            all_stark.poseidon_stark.generate_trace(
+                                poseidon_inputs,
+                                min_rows,
+                                timing
+                            )
+                        );
+                        let poseidon_sponge_trace = traced!(
+                            timing,
+                            "generate Poseidon sponge trace",
+                            all_stark.poseidon_sponge_stark.generate_trace(
+                                poseidon_sponge_ops,
+                                min_rows,
+                                timing
+                            )
+                        );
+                        let keccak_trace = traced!(
+                            timing,
+                            "generate Keccak trace",
+                            all_stark
+                                .keccak_stark
+                                .generate_trace(keccak_inputs, min_rows, timing)
+                        );
+                        let keccak_sponge_trace = traced!(
+                            timing,
+                            "generate Keccak sponge trace",
+                            all_stark.keccak_sponge_stark.generate_trace(
+                                keccak_sponge_ops,
+                                min_rows,
+                                timing
+                            )
+                        );
+                        let sha_extend_trace = traced!(
+                            timing,
+                            "generate SHA-256 extend trace",
+                            all_stark.sha_extend_stark.generate_trace(
+                                sha_extend_ops,
+                                min_rows,
+                                timing
+                            )
+                        );
+                        let sha_compress_trace = traced!(
+                            timing,
+                            "generate SHA-256 compress trace",
+                            all_stark.sha_compress_stark.generate_trace(
+                                sha_compress_ops,
+                                min_rows,
+                                timing
+                            )
+                        );
+                        let secp256k1_trace = traced!(
+                            timing,
+                            "generate secp256k1 trace",
+                            all_stark.secp256k1_stark.generate_trace(
+                                secp256k1_ops,
+                                min_rows,
+                                timing
+                            )
+                        );
+                        let ed25519_trace = traced!(
+                            timing,
+                            "generate ed25519 trace",
+                            all_stark
+                                .ed25519_stark
+                                .generate_trace(ed25519_ops, min_rows, timing)
+                        );
+                        let bn254_trace = traced!(
+                            timing,
+                            "generate bn254 trace",
+                            all_stark
+                                .bn254_stark
+                                .generate_trace(bn254_ops, min_rows, timing)
+                        );
+                        let bls12381_fp_trace = traced!(
+                            timing,
+                            "generate BLS12-381 field trace",
+                            all_stark.bls12381_fp_stark.generate_trace(
+                                bls12381_fp_ops,
+                                min_rows,
+                                timing
+                            )
+                        );
+                        let uint256_trace = traced!(
+                            timing,
+                            "generate uint256 mulmod trace",
+                            all_stark
+                                .uint256_stark
+                                .generate_trace(uint256_ops, min_rows, timing)
+                        );
+                        let blake_trace = traced!(
+                            timing,
+                            "generate blake compression trace",
+                            all_stark
+                                .blake_stark
+                                .generate_trace(blake_ops, min_rows, timing)
+                        );
+                        let chacha_trace = traced!(
+                            timing,
+                            "generate ChaCha block trace",
+                            all_stark
+                                .chacha_stark
+                                .generate_trace(chacha_ops, min_rows, timing)
+                        );
+                        let secp256r1_trace = traced!(
+                            timing,
+                            "generate secp256r1 trace",
+                            all_stark.secp256r1_stark.generate_trace(
+                                secp256r1_ops,
+                                min_rows,
+                                timing
+                            )
+                        );
+                        let modexp_trace = traced!(
+                            timing,
+                            "generate modexp trace",
+                            all_stark
+                                .modexp_stark
+                                .generate_trace(modexp_ops, min_rows, timing)
+                        );
+                        [
+                            poseidon_trace,
+                            poseidon_sponge_trace,
+                            keccak_trace,
+                            keccak_sponge_trace,
+                            sha_extend_trace,
+                            sha_compress_trace,
+                            secp256k1_trace,
+                            ed25519_trace,
+                            bn254_trace,
+                            bls12381_fp_trace,
+                            uint256_trace,
+                            blake_trace,
+                            chacha_trace,
+                            secp256r1_trace,
+                            modexp_trace,
+                        ]
+                    },
+                )
+            },
         );
-
-        [
-            arithmetic_trace,
-            cpu_trace,
-            poseidon_trace,
-            poseidon_sponge_trace,
-            logic_trace,
-            memory_trace,
-        ]
+        [arithmetic_trace, cpu_trace]
+            .into_iter()
+            .chain(precompile_traces)
+            .chain([logic_trace, memory_trace])
+            .collect_vec()
+            .try_into()
+            .unwrap()
     }
 }
 
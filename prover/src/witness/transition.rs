@@ -1,22 +1,26 @@
-use anyhow::bail;
-use log::log_enabled;
 use plonky2::field::extension::Extendable;
 use plonky2::field::types::Field;
 use plonky2::hash::hash_types::RichField;
 use plonky2::plonk::config::GenericConfig;
+use tracing::{debug, trace, warn, Level};
 
 use crate::cpu::columns::CpuColumnsView;
 
 use crate::cpu::kernel::assembler::Kernel;
+use crate::error::ProvingError;
 use crate::generation::state::GenerationState;
 use crate::memory::segments::Segment;
+use crate::progress::ProgressMonitor;
 use crate::witness::errors::ProgramError;
 use crate::witness::memory::MemoryAddress;
 
+use crate::arithmetic::BinaryOperator as B;
 use crate::witness::operation::*;
-use crate::witness::state::RegistersState;
-use crate::witness::util::mem_read_code_with_log_and_fill;
+use crate::witness::util::{
+    mem_read_code_with_log_and_fill, reg_read_with_log, reg_write_with_log,
+};
 use crate::{arithmetic, logic};
+use zkm_emulator::cp0;
 
 fn read_code_memory<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
     state: &mut GenerationState<F, C, D>,
@@ -27,7 +31,7 @@ fn read_code_memory<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, co
 
     let address = MemoryAddress::new(code_context, Segment::Code, state.registers.program_counter);
     let (opcode, mem_log) = mem_read_code_with_log_and_fill(address, state, row);
-    log::trace!(
+    trace!(
         "read_code_memory: PC {:X} ({}) op: {:?}, {:?}",
         state.registers.program_counter,
         state.registers.program_counter,
@@ -39,273 +43,173 @@ fn read_code_memory<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, co
     opcode
 }
 
-fn decode(registers: RegistersState, insn: u32) -> Result<Operation, ProgramError> {
-    let opcode = ((insn >> 26) & 0x3F).to_le_bytes()[0];
-    let func = (insn & 0x3F).to_le_bytes()[0];
-    let rt = ((insn >> 16) & 0x1F).to_le_bytes()[0];
-    let rs = ((insn >> 21) & 0x1F).to_le_bytes()[0];
-    let rd = ((insn >> 11) & 0x1F).to_le_bytes()[0];
-    let sa = ((insn >> 6) & 0x1F).to_le_bytes()[0];
-    let offset = insn & 0xffff; // as known as imm
-    let target = insn & 0x3ffffff;
-    log::trace!(
-        "op {}, func {}, rt {}, rs {}, rd {}",
-        opcode,
-        func,
-        rt,
-        rs,
-        rd
-    );
-    log::trace!(
-        "decode: insn {:X}, opcode {:X}, func {:X}",
-        insn,
-        opcode,
-        func
-    );
+/// The fields of an instruction, of which each encoding uses some.
+#[derive(Clone, Copy)]
+struct Fields {
+    rs: u8,
+    rt: u8,
+    rd: u8,
+    sa: u8,
+    imm: u32,
+    target: u32,
+}
 
-    match (opcode, func, registers.is_kernel) {
-        (0b000000, 0b001010, _) => Ok(Operation::CondMov(MovCond::EQ, rs, rt, rd)), // MOVZ: rd = rs if rt == 0
-        (0b000000, 0b001011, _) => Ok(Operation::CondMov(MovCond::NE, rs, rt, rd)), // MOVN: rd = rs if rt != 0
-        (0b000000, 0b100000, _) => Ok(Operation::BinaryArithmetic(
-            arithmetic::BinaryOperator::ADD,
-            rs,
-            rt,
-            rd,
-        )), // ADD: rd = rs+rt
-        (0b000000, 0b100001, _) => Ok(Operation::BinaryArithmetic(
-            arithmetic::BinaryOperator::ADDU,
-            rs,
-            rt,
-            rd,
-        )), // ADDU: rd = rs+rt
-        (0b000000, 0b100010, _) => Ok(Operation::BinaryArithmetic(
-            arithmetic::BinaryOperator::SUB,
-            rs,
-            rt,
-            rd,
-        )), // SUB: rd = rs-rt
-        (0b000000, 0b100011, _) => Ok(Operation::BinaryArithmetic(
-            arithmetic::BinaryOperator::SUBU,
-            rs,
-            rt,
-            rd,
-        )), // SUBU: rd = rs-rt
-        (0b000000, 0b000000, _) => Ok(Operation::BinaryArithmetic(
-            arithmetic::BinaryOperator::SLL,
-            sa,
-            rt,
-            rd,
-        )), // SLL: rd = rt << sa
-        (0b000000, 0b000010, _) => {
-            if rs == 1 {
-                Ok(Operation::Ror(rd, rt, sa))
-            } else {
-                Ok(Operation::BinaryArithmetic(
-                    arithmetic::BinaryOperator::SRL,
-                    sa,
-                    rt,
-                    rd,
-                ))
-            }
-        } // SRL: rd = rt >> sa
-        (0b000000, 0b000011, _) => Ok(Operation::BinaryArithmetic(
-            arithmetic::BinaryOperator::SRA,
-            sa,
-            rt,
-            rd,
-        )), // SRA: rd = rt >> sa
-        (0b000000, 0b000100, _) => Ok(Operation::BinaryArithmetic(
-            arithmetic::BinaryOperator::SLLV,
-            rs,
-            rt,
-            rd,
-        )), // SLLV: rd = rt << rs[4:0]
-        (0b000000, 0b000110, _) => Ok(Operation::BinaryArithmetic(
-            arithmetic::BinaryOperator::SRLV,
-            rs,
-            rt,
-            rd,
-        )), // SRLV: rd = rt >> rs[4:0]
-        (0b000000, 0b000111, _) => Ok(Operation::BinaryArithmetic(
-            arithmetic::BinaryOperator::SRAV,
-            rs,
-            rt,
-            rd,
-        )), // SRAV: rd = rt >> rs[4:0]
-        (0b011100, 0b000010, _) => Ok(Operation::BinaryArithmetic(
-            arithmetic::BinaryOperator::MUL,
-            rs,
-            rt,
-            rd,
-        )), // MUL: rd = rt * rs
-        (0b000000, 0b011000, _) => Ok(Operation::BinaryArithmetic(
-            arithmetic::BinaryOperator::MULT,
-            rs,
-            rt,
-            rd,
-        )), // MULT: (hi, lo) = rt * rs
-        (0b000000, 0b011001, _) => Ok(Operation::BinaryArithmetic(
-            arithmetic::BinaryOperator::MULTU,
-            rs,
-            rt,
-            rd,
-        )), // MULTU: (hi, lo) = rt * rs
-        (0b000000, 0b011010, _) => Ok(Operation::BinaryArithmetic(
-            arithmetic::BinaryOperator::DIV,
-            rs,
-            rt,
-            rd,
-        )), // DIV: (hi, lo) = rt / rs
-        (0b000000, 0b011011, _) => Ok(Operation::BinaryArithmetic(
-            arithmetic::BinaryOperator::DIVU,
-            rs,
-            rt,
-            rd,
-        )), // DIVU: (hi, lo) = rt / rs
-        (0b000000, 0b010000, _) => Ok(Operation::BinaryArithmetic(
-            arithmetic::BinaryOperator::MFHI,
-            33,
-            0,
-            rd,
-        )), // MFHI: rd = hi
-        (0b000000, 0b010001, _) => Ok(Operation::BinaryArithmetic(
-            arithmetic::BinaryOperator::MTHI,
-            rs,
-            0,
-            33,
-        )), // MTHI: hi = rs
-        (0b000000, 0b010010, _) => Ok(Operation::BinaryArithmetic(
-            arithmetic::BinaryOperator::MFLO,
-            32,
-            0,
-            rd,
-        )), // MFLO: rd = lo
-        (0b000000, 0b010011, _) => Ok(Operation::BinaryArithmetic(
-            arithmetic::BinaryOperator::MTLO,
-            rs,
-            0,
-            32,
-        )), // MTLO: lo = rs
-        (0b000000, 0b001111, _) => Ok(Operation::Nop),                              // SYNC
-        (0b011100, 0b100000, _) => Ok(Operation::Count(false, rs, rd)), // CLZ: rd = count_leading_zeros(rs)
-        (0b011100, 0b100001, _) => Ok(Operation::Count(true, rs, rd)), // CLO: rd = count_leading_ones(rs)
-        (0x00, 0x08, _) => Ok(Operation::Jump(0u8, rs)),               // JR
-        (0x00, 0x09, _) => Ok(Operation::Jump(rd, rs)),                // JALR
-        (0x01, _, _) => {
-            if rt == 1 {
-                Ok(Operation::Branch(BranchCond::GE, rs, 0u8, offset)) // BGEZ
-            } else if rt == 0 {
-                Ok(Operation::Branch(BranchCond::LT, rs, 0u8, offset)) // BLTZ
-            } else if rt == 0x11 && rs == 0 {
-                Ok(Operation::JumpDirect(31, offset)) // BAL
-            } else {
-                Err(ProgramError::InvalidOpcode)
-            }
+impl Fields {
+    fn of(insn: u32) -> Self {
+        Self {
+            rs: ((insn >> 21) & 0x1F) as u8,
+            rt: ((insn >> 16) & 0x1F) as u8,
+            rd: ((insn >> 11) & 0x1F) as u8,
+            sa: ((insn >> 6) & 0x1F) as u8,
+            imm: insn & 0xffff,
+            target: insn & 0x3ffffff,
         }
-        (0x02, _, _) => Ok(Operation::Jumpi(0u8, target)), // J
-        (0x03, _, _) => Ok(Operation::Jumpi(31u8, target)), // JAL
-        (0x04, _, _) => Ok(Operation::Branch(BranchCond::EQ, rs, rt, offset)), // BEQ
-        (0x05, _, _) => Ok(Operation::Branch(BranchCond::NE, rs, rt, offset)), // BNE
-        (0x06, _, _) => Ok(Operation::Branch(BranchCond::LE, rs, 0u8, offset)), // BLEZ
-        (0x07, _, _) => Ok(Operation::Branch(BranchCond::GT, rs, 0u8, offset)), // BGTZ
-
-        (0b100000, _, _) => Ok(Operation::MloadGeneral(MemOp::LB, rs, rt, offset)),
-        (0b100001, _, _) => Ok(Operation::MloadGeneral(MemOp::LH, rs, rt, offset)),
-        (0b100010, _, _) => Ok(Operation::MloadGeneral(MemOp::LWL, rs, rt, offset)),
-        (0b100011, _, _) => Ok(Operation::MloadGeneral(MemOp::LW, rs, rt, offset)),
-        (0b100100, _, _) => Ok(Operation::MloadGeneral(MemOp::LBU, rs, rt, offset)),
-        (0b100101, _, _) => Ok(Operation::MloadGeneral(MemOp::LHU, rs, rt, offset)),
-        (0b100110, _, _) => Ok(Operation::MloadGeneral(MemOp::LWR, rs, rt, offset)),
-        (0b110000, _, _) => Ok(Operation::MloadGeneral(MemOp::LL, rs, rt, offset)),
-        (0b101000, _, _) => Ok(Operation::MstoreGeneral(MemOp::SB, rs, rt, offset)),
-        (0b101001, _, _) => Ok(Operation::MstoreGeneral(MemOp::SH, rs, rt, offset)),
-        (0b101010, _, _) => Ok(Operation::MstoreGeneral(MemOp::SWL, rs, rt, offset)),
-        (0b101011, _, _) => Ok(Operation::MstoreGeneral(MemOp::SW, rs, rt, offset)),
-        (0b101110, _, _) => Ok(Operation::MstoreGeneral(MemOp::SWR, rs, rt, offset)),
-        (0b111000, _, _) => Ok(Operation::MstoreGeneral(MemOp::SC, rs, rt, offset)),
-        (0b111101, _, _) => Ok(Operation::MstoreGeneral(MemOp::SDC1, rs, rt, offset)),
-        (0b001000, _, _) => Ok(Operation::BinaryArithmeticImm(
-            arithmetic::BinaryOperator::ADDI,
-            rs,
-            rt,
-            offset,
-        )), // ADDI: rt = rs + sext(imm)
-
-        (0b001001, _, _) => Ok(Operation::BinaryArithmeticImm(
-            arithmetic::BinaryOperator::ADDIU,
-            rs,
-            rt,
-            offset,
-        )), // ADDIU: rt = rs + sext(imm)
-
-        (0b001010, _, _) => Ok(Operation::BinaryArithmeticImm(
-            arithmetic::BinaryOperator::SLTI,
-            rs,
-            rt,
-            offset,
-        )), // SLTI: rt = rs < sext(imm)
-
-        (0b001011, _, _) => Ok(Operation::BinaryArithmeticImm(
-            arithmetic::BinaryOperator::SLTIU,
-            rs,
-            rt,
-            offset,
-        )), // SLTIU: rt = rs < sext(imm)
-
-        (0b000000, 0b101010, _) => Ok(Operation::BinaryArithmetic(
-            arithmetic::BinaryOperator::SLT,
-            rs,
-            rt,
-            rd,
-        )), // SLT: rd = rs < rt
-
-        (0b000000, 0b101011, _) => Ok(Operation::BinaryArithmetic(
-            arithmetic::BinaryOperator::SLTU,
-            rs,
-            rt,
-            rd,
-        )), // SLTU: rd = rs < rt
-
-        (0b001111, _, _) => Ok(Operation::BinaryArithmeticImm(
-            arithmetic::BinaryOperator::LUI,
-            rs,
-            rt,
-            offset,
-        )), // LUI: rt = imm << 16
-        (0b000000, 0b100100, _) => Ok(Operation::BinaryLogic(logic::Op::And, rs, rt, rd)), // AND: rd = rs & rt
-        (0b000000, 0b100101, _) => Ok(Operation::BinaryLogic(logic::Op::Or, rs, rt, rd)), // OR: rd = rs | rt
-        (0b000000, 0b100110, _) => Ok(Operation::BinaryLogic(logic::Op::Xor, rs, rt, rd)), // XOR: rd = rs ^ rt
-        (0b000000, 0b100111, _) => Ok(Operation::BinaryLogic(logic::Op::Nor, rs, rt, rd)), // NOR: rd = ! rs | rt
-
-        (0b001100, _, _) => Ok(Operation::BinaryLogicImm(logic::Op::And, rs, rt, offset)), // ANDI: rt = rs + zext(imm)
-        (0b001101, _, _) => Ok(Operation::BinaryLogicImm(logic::Op::Or, rs, rt, offset)), // ORI: rt = rs + zext(imm)
-        (0b001110, _, _) => Ok(Operation::BinaryLogicImm(logic::Op::Xor, rs, rt, offset)), // XORI: rt = rs + zext(imm)
-        (0b000000, 0b001100, _) => Ok(Operation::Syscall), // Syscall
-        (0b110011, _, _) => Ok(Operation::Nop),            // Pref
-        (0b011100, 0b000001, _) => Ok(Operation::Maddu(rt, rs)), // rdhwr
-        (0b011111, 0b000000, _) => Ok(Operation::Ext(rt, rs, rd, sa)), // ext
-        (0b011111, 0b000100, _) => Ok(Operation::Ins(rt, rs, rd, sa)), // ins
-        (0b011111, 0b111011, _) => Ok(Operation::Rdhwr(rt, rd)), // rdhwr
-        (0b011111, 0b100000, _) => {
-            if sa == 0b011000 {
-                Ok(Operation::Signext(rd, rt, 16)) // seh
-            } else if sa == 0b010000 {
-                Ok(Operation::Signext(rd, rt, 8)) // seb
-            } else if sa == 0b000010 {
-                Ok(Operation::SwapHalf(rd, rt)) // wsbh
-            } else {
-                log::warn!(
-                    "decode: invalid opcode {:#08b} {:#08b} {:#08b}",
-                    opcode,
-                    func,
-                    sa
-                );
-                Err(ProgramError::InvalidOpcode)
-            }
+    }
+}
+
+const OPCODE: u32 = 0xfc00_0000;
+const FUNC: u32 = 0xfc00_003f;
+const FUNC_SA: u32 = 0xfc00_07ff;
+const FUNC_RS: u32 = 0xffe0_003f;
+const REGIMM: u32 = 0xfc1f_0000;
+
+const fn special(func: u32) -> u32 {
+    func
+}
+const fn special2(func: u32) -> u32 {
+    (0x1c << 26) | func
+}
+const fn special3(func: u32) -> u32 {
+    (0x1f << 26) | func
+}
+const fn regimm(rt: u32) -> u32 {
+    (0x01 << 26) | (rt << 16)
+}
+const fn itype(opcode: u32) -> u32 {
+    opcode << 26
+}
+
+/// The MIPS encodings accepted by `decode`: mnemonic, fixed bits, mask of the fixed bits and
+/// the operation decoded from the remaining fields. An encoding nested in another one, as BAL
+/// is in BGEZAL, comes before it.
+#[rustfmt::skip]
+const SPEC: &[(&str, u32, u32, fn(Fields) -> Operation)] = &[
+    ("movz", special(0x0a), FUNC, |f| Operation::CondMov(MovCond::EQ, f.rs, f.rt, f.rd)),
+    ("movn", special(0x0b), FUNC, |f| Operation::CondMov(MovCond::NE, f.rs, f.rt, f.rd)),
+    ("add", special(0x20), FUNC, |f| Operation::BinaryArithmetic(B::ADD, f.rs, f.rt, f.rd)),
+    ("addu", special(0x21), FUNC, |f| Operation::BinaryArithmetic(B::ADDU, f.rs, f.rt, f.rd)),
+    ("sub", special(0x22), FUNC, |f| Operation::BinaryArithmetic(B::SUB, f.rs, f.rt, f.rd)),
+    ("subu", special(0x23), FUNC, |f| Operation::BinaryArithmetic(B::SUBU, f.rs, f.rt, f.rd)),
+    ("sll", special(0x00), FUNC, |f| Operation::BinaryArithmetic(B::SLL, f.sa, f.rt, f.rd)),
+    ("srl", special(0x02), FUNC_RS, |f| Operation::BinaryArithmetic(B::SRL, f.sa, f.rt, f.rd)),
+    ("rotr", special(0x02) | (1 << 21), FUNC_RS, |f| Operation::Ror(f.rd, f.rt, f.sa)),
+    ("sra", special(0x03), FUNC, |f| Operation::BinaryArithmetic(B::SRA, f.sa, f.rt, f.rd)),
+    ("sllv", special(0x04), FUNC, |f| Operation::BinaryArithmetic(B::SLLV, f.rs, f.rt, f.rd)),
+    ("srlv", special(0x06), FUNC_SA, |f| Operation::BinaryArithmetic(B::SRLV, f.rs, f.rt, f.rd)),
+    ("rotrv", special(0x46), FUNC_SA, |f| Operation::Rorv(f.rd, f.rt, f.rs)),
+    ("srav", special(0x07), FUNC, |f| Operation::BinaryArithmetic(B::SRAV, f.rs, f.rt, f.rd)),
+    ("jr", special(0x08), FUNC, |f| Operation::Jump(0, f.rs)),
+    ("jalr", special(0x09), FUNC, |f| Operation::Jump(f.rd, f.rs)),
+    ("syscall", special(0x0c), FUNC, |_| Operation::Syscall),
+    ("sync", special(0x0f), FUNC, |_| Operation::Nop),
+    ("mfhi", special(0x10), FUNC, |f| Operation::BinaryArithmetic(B::MFHI, 33, 0, f.rd)),
+    ("mthi", special(0x11), FUNC, |f| Operation::BinaryArithmetic(B::MTHI, f.rs, 0, 33)),
+    ("mflo", special(0x12), FUNC, |f| Operation::BinaryArithmetic(B::MFLO, 32, 0, f.rd)),
+    ("mtlo", special(0x13), FUNC, |f| Operation::BinaryArithmetic(B::MTLO, f.rs, 0, 32)),
+    ("mult", special(0x18), FUNC, |f| Operation::BinaryArithmetic(B::MULT, f.rs, f.rt, f.rd)),
+    ("multu", special(0x19), FUNC, |f| Operation::BinaryArithmetic(B::MULTU, f.rs, f.rt, f.rd)),
+    ("div", special(0x1a), FUNC, |f| Operation::BinaryArithmetic(B::DIV, f.rs, f.rt, f.rd)),
+    ("divu", special(0x1b), FUNC, |f| Operation::BinaryArithmetic(B::DIVU, f.rs, f.rt, f.rd)),
+    ("and", special(0x24), FUNC, |f| Operation::BinaryLogic(logic::Op::And, f.rs, f.rt, f.rd)),
+    ("or", special(0x25), FUNC, |f| Operation::BinaryLogic(logic::Op::Or, f.rs, f.rt, f.rd)),
+    ("xor", special(0x26), FUNC, |f| Operation::BinaryLogic(logic::Op::Xor, f.rs, f.rt, f.rd)),
+    ("nor", special(0x27), FUNC, |f| Operation::BinaryLogic(logic::Op::Nor, f.rs, f.rt, f.rd)),
+    ("slt", special(0x2a), FUNC, |f| Operation::BinaryArithmetic(B::SLT, f.rs, f.rt, f.rd)),
+    ("sltu", special(0x2b), FUNC, |f| Operation::BinaryArithmetic(B::SLTU, f.rs, f.rt, f.rd)),
+    ("tge", special(0x30), FUNC, |f| Operation::Trap(TrapCond::GE, f.rs, f.rt)),
+    ("tgeu", special(0x31), FUNC, |f| Operation::Trap(TrapCond::GEU, f.rs, f.rt)),
+    ("tlt", special(0x32), FUNC, |f| Operation::Trap(TrapCond::LT, f.rs, f.rt)),
+    ("tltu", special(0x33), FUNC, |f| Operation::Trap(TrapCond::LTU, f.rs, f.rt)),
+    ("teq", special(0x34), FUNC, |f| Operation::Trap(TrapCond::EQ, f.rs, f.rt)),
+    ("tne", special(0x36), FUNC, |f| Operation::Trap(TrapCond::NE, f.rs, f.rt)),
+    ("madd", special2(0x00), FUNC, |f| Operation::Madd(MaddOp::MADD, f.rt, f.rs)),
+    ("maddu", special2(0x01), FUNC, |f| Operation::Madd(MaddOp::MADDU, f.rt, f.rs)),
+    ("mul", special2(0x02), FUNC, |f| Operation::BinaryArithmetic(B::MUL, f.rs, f.rt, f.rd)),
+    ("msub", special2(0x04), FUNC, |f| Operation::Madd(MaddOp::MSUB, f.rt, f.rs)),
+    ("msubu", special2(0x05), FUNC, |f| Operation::Madd(MaddOp::MSUBU, f.rt, f.rs)),
+    ("clz", special2(0x20), FUNC, |f| Operation::Count(false, f.rs, f.rd)),
+    ("clo", special2(0x21), FUNC, |f| Operation::Count(true, f.rs, f.rd)),
+    ("ext", special3(0x00), FUNC, |f| Operation::Ext(f.rt, f.rs, f.rd, f.sa)),
+    ("ins", special3(0x04), FUNC, |f| Operation::Ins(f.rt, f.rs, f.rd, f.sa)),
+    ("seb", special3(0x420), FUNC_SA, |f| Operation::Signext(f.rd, f.rt, 8)),
+    ("seh", special3(0x620), FUNC_SA, |f| Operation::Signext(f.rd, f.rt, 16)),
+    ("wsbh", special3(0x0a0), FUNC_SA, |f| Operation::SwapHalf(f.rd, f.rt)),
+    ("rdhwr", special3(0x3b), FUNC, |f| Operation::Rdhwr(f.rt, f.rd)),
+    ("mfc0", itype(0x10), 0xffe0_07ff, |f| Operation::Mfc0(f.rt, f.rd)),
+    ("mtc0", itype(0x10) | (4 << 21), 0xffe0_07ff, |f| Operation::Mtc0(f.rt, f.rd)),
+    ("eret", cp0::ERET, 0xffff_ffff, |_| Operation::Eret),
+    ("bal", regimm(0x11), 0xffff_0000, |f| Operation::JumpDirect(31, f.imm)),
+    ("bltz", regimm(0x00), REGIMM, |f| Operation::Branch(BranchCond::LT, f.rs, 0, f.imm)),
+    ("bgez", regimm(0x01), REGIMM, |f| Operation::Branch(BranchCond::GE, f.rs, 0, f.imm)),
+    ("bltzl", regimm(0x02), REGIMM, |f| Operation::BranchLikely(BranchCond::LT, f.rs, 0, f.imm)),
+    ("bgezl", regimm(0x03), REGIMM, |f| Operation::BranchLikely(BranchCond::GE, f.rs, 0, f.imm)),
+    ("bltzal", regimm(0x10), REGIMM, |f| Operation::BranchLink(BranchCond::LT, f.rs, f.imm)),
+    ("bgezal", regimm(0x11), REGIMM, |f| Operation::BranchLink(BranchCond::GE, f.rs, f.imm)),
+    ("j", itype(0x02), OPCODE, |f| Operation::Jumpi(0, f.target)),
+    ("jal", itype(0x03), OPCODE, |f| Operation::Jumpi(31, f.target)),
+    ("beq", itype(0x04), OPCODE, |f| Operation::Branch(BranchCond::EQ, f.rs, f.rt, f.imm)),
+    ("bne", itype(0x05), OPCODE, |f| Operation::Branch(BranchCond::NE, f.rs, f.rt, f.imm)),
+    ("blez", itype(0x06), OPCODE, |f| Operation::Branch(BranchCond::LE, f.rs, 0, f.imm)),
+    ("bgtz", itype(0x07), OPCODE, |f| Operation::Branch(BranchCond::GT, f.rs, 0, f.imm)),
+    ("addi", itype(0x08), OPCODE, |f| Operation::BinaryArithmeticImm(B::ADDI, f.rs, f.rt, f.imm)),
+    ("addiu", itype(0x09), OPCODE, |f| Operation::BinaryArithmeticImm(B::ADDIU, f.rs, f.rt, f.imm)),
+    ("slti", itype(0x0a), OPCODE, |f| Operation::BinaryArithmeticImm(B::SLTI, f.rs, f.rt, f.imm)),
+    ("sltiu", itype(0x0b), OPCODE, |f| Operation::BinaryArithmeticImm(B::SLTIU, f.rs, f.rt, f.imm)),
+    ("andi", itype(0x0c), OPCODE, |f| Operation::BinaryLogicImm(logic::Op::And, f.rs, f.rt, f.imm)),
+    ("ori", itype(0x0d), OPCODE, |f| Operation::BinaryLogicImm(logic::Op::Or, f.rs, f.rt, f.imm)),
+    ("xori", itype(0x0e), OPCODE, |f| Operation::BinaryLogicImm(logic::Op::Xor, f.rs, f.rt, f.imm)),
+    ("lui", itype(0x0f), OPCODE, |f| Operation::BinaryArithmeticImm(B::LUI, f.rs, f.rt, f.imm)),
+    ("beql", itype(0x14), OPCODE, |f| Operation::BranchLikely(BranchCond::EQ, f.rs, f.rt, f.imm)),
+    ("bnel", itype(0x15), OPCODE, |f| Operation::BranchLikely(BranchCond::NE, f.rs, f.rt, f.imm)),
+    ("blezl", itype(0x16), OPCODE, |f| Operation::BranchLikely(BranchCond::LE, f.rs, 0, f.imm)),
+    ("bgtzl", itype(0x17), OPCODE, |f| Operation::BranchLikely(BranchCond::GT, f.rs, 0, f.imm)),
+    ("lb", itype(0x20), OPCODE, |f| Operation::MloadGeneral(MemOp::LB, f.rs, f.rt, f.imm)),
+    ("lh", itype(0x21), OPCODE, |f| Operation::MloadGeneral(MemOp::LH, f.rs, f.rt, f.imm)),
+    ("lwl", itype(0x22), OPCODE, |f| Operation::MloadGeneral(MemOp::LWL, f.rs, f.rt, f.imm)),
+    ("lw", itype(0x23), OPCODE, |f| Operation::MloadGeneral(MemOp::LW, f.rs, f.rt, f.imm)),
+    ("lbu", itype(0x24), OPCODE, |f| Operation::MloadGeneral(MemOp::LBU, f.rs, f.rt, f.imm)),
+    ("lhu", itype(0x25), OPCODE, |f| Operation::MloadGeneral(MemOp::LHU, f.rs, f.rt, f.imm)),
+    ("lwr", itype(0x26), OPCODE, |f| Operation::MloadGeneral(MemOp::LWR, f.rs, f.rt, f.imm)),
+    ("sb", itype(0x28), OPCODE, |f| Operation::MstoreGeneral(MemOp::SB, f.rs, f.rt, f.imm)),
+    ("sh", itype(0x29), OPCODE, |f| Operation::MstoreGeneral(MemOp::SH, f.rs, f.rt, f.imm)),
+    ("swl", itype(0x2a), OPCODE, |f| Operation::MstoreGeneral(MemOp::SWL, f.rs, f.rt, f.imm)),
+    ("sw", itype(0x2b), OPCODE, |f| Operation::MstoreGeneral(MemOp::SW, f.rs, f.rt, f.imm)),
+    ("swr", itype(0x2e), OPCODE, |f| Operation::MstoreGeneral(MemOp::SWR, f.rs, f.rt, f.imm)),
+    ("ll", itype(0x30), OPCODE, |f| Operation::MloadGeneral(MemOp::LL, f.rs, f.rt, f.imm)),
+    ("pref", itype(0x33), OPCODE, |_| Operation::Nop),
+    ("sc", itype(0x38), OPCODE, |f| Operation::MstoreGeneral(MemOp::SC, f.rs, f.rt, f.imm)),
+];
+
+fn decode(insn: u32) -> Result<Operation, ProgramError> {
+    if let Some(&(name, _, _, operation)) = SPEC
+        .iter()
+        .find(|&&(_, value, mask, _)| insn & mask == value)
+    {
+        trace!("decode: insn {:X}, {}", insn, name);
+        return Ok(operation(Fields::of(insn)));
+    }
+
+    let opcode = insn >> 26;
+    let func = insn & 0x3F;
+    match (opcode, func) {
+        (0b000000, 0b001101) => Err(ProgramError::Break), // break
+        // COP1/COP1X and the FPU loads/stores: there is no FPU, so soft-float code is expected
+        // and these raise a coprocessor unusable exception.
+        (0b010001 | 0b010011 | 0b110001 | 0b110101 | 0b111001 | 0b111101, _) => {
+            Err(ProgramError::CoprocessorUnusable)
         }
-        (0b000000, 0b110100, _) => Ok(Operation::Teq(rs, rt)), // teq
         _ => {
-            log::warn!("decode: invalid opcode {:#08b} {:#08b}", opcode, func);
+            warn!("decode: invalid instruction {:#010x}", insn);
             Err(ProgramError::InvalidOpcode)
         }
     }
@@ -333,7 +237,9 @@ fn fill_op_flag<F: Field>(op: Operation, row: &mut CpuColumnsView<F>) {
         Operation::Jump(_, _) => &mut flags.jumps,
         Operation::Jumpi(_, _) => &mut flags.jumpi,
         Operation::JumpDirect(_, _) => &mut flags.jumpdirect,
-        Operation::Branch(_, _, _, _) => &mut flags.branch,
+        Operation::Branch(_, _, _, _)
+        | Operation::BranchLikely(_, _, _, _)
+        | Operation::BranchLink(_, _, _) => &mut flags.branch,
         Operation::Pc => &mut flags.pc,
         Operation::GetContext => &mut flags.get_context,
         Operation::SetContext => &mut flags.set_context,
@@ -342,13 +248,19 @@ fn fill_op_flag<F: Field>(op: Operation, row: &mut CpuColumnsView<F>) {
         Operation::Nop => &mut flags.nop,
         Operation::Ext(_, _, _, _) => &mut flags.ext,
         Operation::Ins(_, _, _, _) => &mut flags.ins,
-        Operation::Maddu(_, _) => &mut flags.maddu,
+        Operation::Madd(_, _, _) => &mut flags.madd,
         Operation::Ror(_, _, _) => &mut flags.ror,
+        Operation::Rorv(_, _, _) => &mut flags.rorv,
         Operation::Rdhwr(_, _) => &mut flags.rdhwr,
+        Operation::Mfc0(_, _) => &mut flags.mfc0,
+        Operation::Mtc0(_, _) => &mut flags.mtc0,
+        Operation::Eret => &mut flags.eret,
         Operation::Signext(_, _, 8u8) => &mut flags.signext8,
         Operation::Signext(_, _, _) => &mut flags.signext16,
         Operation::SwapHalf(_, _) => &mut flags.swaphalf,
-        Operation::Teq(_, _) => &mut flags.teq,
+        Operation::Trap(TrapCond::EQ, _, _) => &mut flags.teq,
+        Operation::Trap(TrapCond::NE, _, _) => &mut flags.tne,
+        Operation::Trap(_, _, _) => &mut flags.trap_cmp,
     } = F::ONE;
 }
 
@@ -358,7 +270,7 @@ fn perform_op<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D:
     row: CpuColumnsView<F>,
     kernel: &Kernel,
 ) -> Result<(), ProgramError> {
-    log::trace!("perform_op {:?}", op);
+    trace!("perform_op {:?}", op);
     match op {
         Operation::Syscall => generate_syscall(state, row, kernel)?,
         Operation::CondMov(cond, rs, rt, rd) => generate_cond_mov_op(cond, rs, rt, rd, state, row)?,
@@ -443,7 +355,13 @@ fn perform_op<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D:
         Operation::Jumpi(link, target) => generate_jumpi(link, target, state, row)?,
         Operation::JumpDirect(link, target) => generate_jumpdirect(link, target, state, row)?,
         Operation::Branch(cond, input1, input2, target) => {
-            generate_branch(cond, input1, input2, target, state, row)?
+            generate_branch(cond, input1, input2, target, false, 0, state, row)?
+        }
+        Operation::BranchLikely(cond, input1, input2, target) => {
+            generate_branch(cond, input1, input2, target, true, 0, state, row)?
+        }
+        Operation::BranchLink(cond, input1, target) => {
+            generate_branch(cond, input1, 0, target, false, 31, state, row)?
         }
         Operation::Pc => generate_pc(state, row)?,
         Operation::MloadGeneral(op, base, rt, offset) => {
@@ -457,19 +375,26 @@ fn perform_op<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D:
         Operation::Nop => generate_nop(state, row)?,
         Operation::Ext(rt, rs, msbd, lsb) => generate_extract(rt, rs, msbd, lsb, state, row)?,
         Operation::Ins(rt, rs, msb, lsb) => generate_insert(rt, rs, msb, lsb, state, row)?,
-        Operation::Maddu(rt, rs) => generate_maddu(rt, rs, state, row)?,
+        Operation::Madd(op, rt, rs) => generate_madd(op, rt, rs, state, row)?,
         Operation::Ror(rd, rt, sa) => generate_ror(rd, rt, sa, state, row)?,
+        Operation::Rorv(rd, rt, rs) => generate_rorv(rd, rt, rs, state, row)?,
         Operation::Rdhwr(rt, rd) => generate_rdhwr(rt, rd, state, row)?,
+        Operation::Mfc0(rt, rd) => generate_cop0_move(false, rt, rd, state, row)?,
+        Operation::Mtc0(rt, rd) => generate_cop0_move(true, rt, rd, state, row)?,
+        Operation::Eret => generate_eret(state, row)?,
         Operation::Signext(rd, rt, bits) => generate_signext(rd, rt, bits, state, row)?,
         Operation::SwapHalf(rd, rt) => generate_swaphalf(rd, rt, state, row)?,
-        Operation::Teq(rs, rt) => generate_teq(rs, rt, state, row)?,
+        Operation::Trap(cond, rs, rt) => generate_trap(cond, rs, rt, state, row)?,
     };
 
     match op {
         Operation::Jump(_, _)
         | Operation::Jumpi(_, _)
         | Operation::JumpDirect(_, _)
-        | Operation::Branch(_, _, _, _) => {
+        | Operation::Branch(_, _, _, _)
+        | Operation::BranchLikely(_, _, _, _)
+        | Operation::BranchLink(_, _, _)
+        | Operation::Eret => {
             // Do nothing
         }
         _ => {
@@ -482,15 +407,17 @@ fn perform_op<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D:
         Operation::Jump(_, _)
         | Operation::Jumpi(_, _)
         | Operation::JumpDirect(_, _)
-        | Operation::Branch(_, _, _, _) => {
-            log::trace!(
+        | Operation::Branch(_, _, _, _)
+        | Operation::BranchLikely(_, _, _, _)
+        | Operation::BranchLink(_, _, _) => {
+            trace!(
                 "states: pc {} registers: {:?}",
                 state.registers.program_counter,
                 state.registers.gprs
             );
         }
         Operation::Syscall => {
-            log::trace!(
+            trace!(
                 "states: pc {} registers: {:?}",
                 state.registers.program_counter + 4,
                 state.registers.gprs
@@ -528,12 +455,12 @@ fn try_perform_instruction<
     kernel: &Kernel,
 ) -> Result<(), ProgramError> {
     let (mut row, opcode) = base_row(state);
-    let op = decode(state.registers, opcode)?;
+    let op = decode(opcode)?;
 
     if state.registers.is_kernel {
         log_kernel_instruction(state, op, kernel);
     } else {
-        log::trace!("user instruction: {:?}", op);
+        trace!("user instruction: {:?}", op);
     }
 
     fill_op_flag(op, &mut row);
@@ -566,7 +493,7 @@ fn log_kernel_instruction<
     kernel: &Kernel,
 ) {
     // The logic below is a bit costly, so skip it if debug logs aren't enabled.
-    if !log_enabled!(log::Level::Debug) {
+    if !tracing::enabled!(Level::DEBUG) && !log::log_enabled!(log::Level::Debug) {
         return;
     }
 
@@ -575,48 +502,187 @@ fn log_kernel_instruction<
         .offset_label(pc)
         .filter(|label| !label.starts_with("halt"))
         .is_some();
-    let level = if is_interesting_offset {
-        log::Level::Debug
-    } else {
-        log::Level::Trace
-    };
-    log::log!(
-        level,
-        "Cycle {}, ctx={}, pc={}, instruction={:?}, stack={:?}",
+    let (clock, context, offset) = (
         state.traces.clock(),
         state.registers.context,
         kernel.offset_name(pc),
-        op,
-        //state.stack(),
-        0,
     );
+    if is_interesting_offset {
+        debug!(clock, context, pc = %offset, instruction = ?op, "kernel instruction");
+    } else {
+        trace!(clock, context, pc = %offset, instruction = ?op, "kernel instruction");
+    }
 
     //assert!(pc < KERNEL.program.image.len(), "Kernel PC is out of range: {}", pc);
 }
 
+/// The CP0 Cause code of the exceptions the guest can handle, and the faulting address of those
+/// having one.
+fn exception(err: &ProgramError) -> Option<(u8, Option<u32>)> {
+    match *err {
+        ProgramError::PageFault {
+            is_store: false,
+            addr,
+        } => Some((2, Some(addr))),
+        ProgramError::PageFault {
+            is_store: true,
+            addr,
+        } => Some((3, Some(addr))),
+        // Same as the CP0 Cause codes used by the emulator.
+        ProgramError::Break => Some((9, None)),
+        // Reserved Instruction.
+        ProgramError::InvalidOpcode => Some((10, None)),
+        ProgramError::CoprocessorUnusable => Some((11, None)),
+        ProgramError::IntegerOverflow => Some((12, None)),
+        ProgramError::Trap => Some((13, None)),
+        _ => None,
+    }
+}
+
 fn handle_error<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
     state: &mut GenerationState<F, C, D>,
     err: ProgramError,
 ) -> anyhow::Result<()> {
-    let exc_code: u8 = match err {
-        ProgramError::OutOfGas => 0,
-        ProgramError::InvalidOpcode => 1,
-        ProgramError::StackUnderflow => 2,
-        ProgramError::InvalidJumpDestination => 3,
-        ProgramError::InvalidJumpiDestination => 4,
-        ProgramError::StackOverflow => 5,
-        _ => bail!("TODO: figure out what to do with this..."),
-    };
-    log::debug!("handle_error: {:?}", exc_code);
+    if let Some((exc_code, bad_vaddr)) = exception(&err) {
+        debug!("handle_error: {:?}", exc_code);
+        return generate_exception(state, exc_code, bad_vaddr);
+    }
+
+    // The other errors have no exception to deliver to the guest.
+    Err(instruction_error(state, &err, false).into())
+}
+
+/// Enters the exception handler of the guest, if it has one, else stops the execution at the
+/// faulting instruction, in a trap row, reporting the exception code as the exit code.
+///
+/// Entering the handler reads Status and writes Cause, EPC, Status and BadVAddr, in a row of
+/// their own which takes the place of the faulting instruction.
+fn generate_exception<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    state: &mut GenerationState<F, C, D>,
+    exc_code: u8,
+    bad_vaddr: Option<u32>,
+) -> anyhow::Result<()> {
+    if !state.registers.cp0.delivers() {
+        warn!(
+            "exception {} at pc {:X}",
+            exc_code, state.registers.program_counter
+        );
+        let checkpoint = state.checkpoint();
+        let mut row = exception_row(state);
+        row.is_trap = F::ONE;
+        let status_reg = (cp0::CP0_REGISTERS_START + cp0::CP0_STATUS) as u8;
+        let (status, op) = reg_read_with_log(status_reg, 0, state, &mut row)
+            .map_err(|e| anyhow::anyhow!("{:?}", e))?;
+        state.traces.push_memory(op);
+        for (i, bit) in row.general.misc_mut().rs_bits.iter_mut().enumerate() {
+            *bit = F::from_canonical_usize((status >> i) & 1);
+        }
+        state.traces.push_cpu(row);
+        state
+            .memory
+            .apply_ops(state.traces.mem_ops_since(checkpoint.traces));
+
+        state.registers.exited = true;
+        state.registers.exit_code = exc_code;
+        return Ok(());
+    }
+
+    debug!(
+        "exception {} at pc {:X}, entering handler",
+        exc_code, state.registers.program_counter
+    );
+    let pc = state.registers.program_counter;
+    let next_pc = state.registers.next_pc;
+    let mut cp0 = state.registers.cp0;
+    let vector = cp0.enter(exc_code, pc as u32, next_pc as u32, bad_vaddr);
 
     let checkpoint = state.checkpoint();
+    let mut row = exception_row(state);
+    row.is_exception = F::ONE;
+
+    let status_reg = (cp0::CP0_REGISTERS_START + cp0::CP0_STATUS) as u8;
+    let (status, op) = reg_read_with_log(status_reg, 0, state, &mut row)
+        .map_err(|e| anyhow::anyhow!("{:?}", e))?;
+    state.traces.push_memory(op);
+    let written = [
+        cp0::CP0_CAUSE,
+        cp0::CP0_EPC,
+        cp0::CP0_STATUS,
+        cp0::CP0_BADVADDR,
+    ];
+    for (channel, reg) in written.into_iter().enumerate() {
+        let index = (cp0::CP0_REGISTERS_START + reg) as u8;
+        let op = reg_write_with_log(index, channel + 1, cp0.regs[reg] as usize, state, &mut row)
+            .map_err(|e| anyhow::anyhow!("{:?}", e))?;
+        state.traces.push_memory(op);
+    }
 
+    let not_seq = F::from_canonical_usize(next_pc) - F::from_canonical_usize(pc + 4);
+    let cause = cp0.regs[cp0::CP0_CAUSE] as usize;
+    let misc = row.general.misc_mut();
+    misc.auxm = F::from_bool(cause & cp0::CAUSE_BD as usize != 0);
+    misc.auxl = not_seq.try_inverse().unwrap_or(F::ZERO);
+    let bits = misc.rs_bits.iter_mut().zip(misc.is_msb.iter_mut());
+    for (i, (status_bit, cause_bit)) in bits.enumerate() {
+        *status_bit = F::from_canonical_usize((status >> i) & 1);
+        *cause_bit = F::from_canonical_usize((cause >> i) & 1);
+    }
+    state.traces.push_cpu(row);
     state
         .memory
         .apply_ops(state.traces.mem_ops_since(checkpoint.traces));
+
+    state.registers.program_counter = vector as usize;
+    state.registers.next_pc = vector as usize + 4;
     Ok(())
 }
 
+/// The row of an exception, which takes the place of the faulting instruction, with its pc and
+/// next pc.
+fn exception_row<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    state: &GenerationState<F, C, D>,
+) -> CpuColumnsView<F> {
+    let mut row: CpuColumnsView<F> = CpuColumnsView::default();
+    row.clock = F::from_canonical_usize(state.traces.clock());
+    row.context = F::from_canonical_usize(state.registers.context);
+    row.program_counter = F::from_canonical_usize(state.registers.program_counter);
+    row.next_program_counter = F::from_canonical_usize(state.registers.next_pc);
+    row.is_kernel_mode = F::from_bool(state.registers.is_kernel);
+    row
+}
+
+/// The error of the instruction at the pc of `state`, which the prover cannot run. The segment
+/// is that of the [`ProgressMonitor`] of the thread.
+fn instruction_error<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    state: &GenerationState<F, C, D>,
+    err: &ProgramError,
+    in_kernel: bool,
+) -> ProvingError {
+    let pc = state.registers.program_counter;
+    let address = MemoryAddress::new(state.registers.code_context(), Segment::Code, pc);
+    let (segment, pc, opcode) = (
+        ProgressMonitor::current().segment(),
+        pc as u32,
+        state.memory.get(address).to_be(),
+    );
+    let reason = format!("{:?}", err);
+    if in_kernel {
+        ProvingError::KernelFailure {
+            segment,
+            pc,
+            opcode,
+            reason,
+        }
+    } else {
+        ProvingError::UnsupportedInstruction {
+            segment,
+            pc,
+            opcode,
+            reason,
+        }
+    }
+}
+
 pub(crate) fn transition<
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
@@ -633,22 +699,240 @@ pub(crate) fn transition<
             state
                 .memory
                 .apply_ops(state.traces.mem_ops_since(checkpoint.traces));
+            // The rows of the instruction without an op flag, as those of the precompiles a
+            // syscall looks up, carry the pc the instruction continues at, to chain it to the
+            // next instruction.
+            let pc = F::from_canonical_usize(state.registers.program_counter);
+            let next_pc = F::from_canonical_usize(state.registers.next_pc);
+            for row in state.traces.cpu_rows_since_mut(checkpoint.traces) {
+                if row.op.iter().all(|flag| flag.is_zero()) {
+                    row.program_counter = pc;
+                    row.next_program_counter = next_pc;
+                }
+            }
             Ok(())
         }
         Err(e) => {
-            if state.registers.is_kernel {
+            // The guest runs in kernel mode, its exceptions are not bugs of the prover.
+            if state.registers.is_kernel && exception(&e).is_none() {
                 let offset_name = kernel.offset_name(state.registers.program_counter);
-                bail!(
-                    "{:?} in kernel at pc={}, stack={:?}, memory={:?}",
+                debug!(
+                    "{:?} in kernel at pc={}, memory={:?}",
                     e,
                     offset_name,
-                    //state.stack(),
-                    0,
-                    state.memory.contexts[0].segments[Segment::KernelGeneral as usize].content,
+                    state.memory.contexts[0].segments[Segment::KernelGeneral as usize],
                 );
+                return Err(instruction_error(state, &e, true).into());
             }
             state.rollback(checkpoint);
             handle_error(state, e)
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Filler for the bits that are not fixed by an encoding (rs = 5, rt = 6, rd = 7, sa = 9).
+    const SAMPLE: u32 = (5 << 21) | (6 << 16) | (7 << 11) | (9 << 6) | 0x15;
+
+    #[test]
+    fn decode_conformance() {
+        for &(name, value, mask, expected) in SPEC {
+            let insn = value | (SAMPLE & !mask);
+            match decode(insn) {
+                Ok(op) => assert_eq!(op, expected(Fields::of(insn)), "{name}: {insn:#010x}"),
+                Err(e) => panic!("{name}: {insn:#010x} decoded to {e:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn decode_instructions() {
+        let cases = [
+            // add $v0, $a0, $a1
+            (0x00851020, Operation::BinaryArithmetic(B::ADD, 4, 5, 2)),
+            // jr $ra
+            (0x03e00008, Operation::Jump(0, 31)),
+            // sll $t0, $t1, 2
+            (0x00094080, Operation::BinaryArithmetic(B::SLL, 2, 9, 8)),
+            // bgezal $a0, 3
+            (0x04910003, Operation::BranchLink(BranchCond::GE, 4, 3)),
+            // bal 3
+            (0x04110003, Operation::JumpDirect(31, 3)),
+        ];
+        for (insn, expected) in cases {
+            assert_eq!(decode(insn).unwrap(), expected, "{insn:#010x}");
+        }
+    }
+
+    /// Two encodings may only overlap if one of them is strictly more specific, in which case
+    /// it comes first so that `decode` picks it (e.g. BAL in BGEZAL).
+    #[test]
+    fn encodings_do_not_shadow_each_other() {
+        for (i, &(name0, value0, mask0, _)) in SPEC.iter().enumerate() {
+            for &(name1, value1, mask1, _) in &SPEC[i + 1..] {
+                let overlap = (value0 ^ value1) & mask0 & mask1 == 0;
+                let nested = mask0 != mask1 && mask0 & mask1 == mask1;
+                assert!(!overlap || nested, "{name1} shadows {name0}");
+            }
+        }
+    }
+
+    /// There is no FPU: COP1, COP1X and the FPU loads and stores trap.
+    #[test]
+    fn fpu_instructions_are_unusable() {
+        for opcode in [0x11, 0x13, 0x31, 0x35, 0x39, 0x3d] {
+            let insn = (opcode << 26) | (SAMPLE & 0x03ff_ffff);
+            assert!(
+                matches!(decode(insn), Err(ProgramError::CoprocessorUnusable)),
+                "{insn:#010x}"
+            );
+        }
+    }
+
+    /// The mnemonic of `insn` in the opcode tables of the MIPS32 release 2 manual (volume II,
+    /// appendix A), among the instructions the zkVM runs. Only the fields which select the
+    /// instruction are checked.
+    fn manual(insn: u32) -> Option<&'static str> {
+        let Fields { rs, rt, sa, .. } = Fields::of(insn);
+        let func = insn & 0x3f;
+        Some(match insn >> 26 {
+            0x00 => match (func, rs, sa) {
+                (0x00, _, _) => "sll",
+                (0x02, 0, _) => "srl",
+                (0x02, 1, _) => "rotr",
+                (0x03, _, _) => "sra",
+                (0x04, _, _) => "sllv",
+                (0x06, _, 0) => "srlv",
+                (0x06, _, 1) => "rotrv",
+                (0x07, _, _) => "srav",
+                (0x08, _, _) => "jr",
+                (0x09, _, _) => "jalr",
+                (0x0a, _, _) => "movz",
+                (0x0b, _, _) => "movn",
+                (0x0c, _, _) => "syscall",
+                (0x0f, _, _) => "sync",
+                (0x10, _, _) => "mfhi",
+                (0x11, _, _) => "mthi",
+                (0x12, _, _) => "mflo",
+                (0x13, _, _) => "mtlo",
+                (0x18, _, _) => "mult",
+                (0x19, _, _) => "multu",
+                (0x1a, _, _) => "div",
+                (0x1b, _, _) => "divu",
+                (0x20, _, _) => "add",
+                (0x21, _, _) => "addu",
+                (0x22, _, _) => "sub",
+                (0x23, _, _) => "subu",
+                (0x24, _, _) => "and",
+                (0x25, _, _) => "or",
+                (0x26, _, _) => "xor",
+                (0x27, _, _) => "nor",
+                (0x2a, _, _) => "slt",
+                (0x2b, _, _) => "sltu",
+                (0x30, _, _) => "tge",
+                (0x31, _, _) => "tgeu",
+                (0x32, _, _) => "tlt",
+                (0x33, _, _) => "tltu",
+                (0x34, _, _) => "teq",
+                (0x36, _, _) => "tne",
+                _ => return None,
+            },
+            0x01 => match (rt, rs) {
+                (0x00, _) => "bltz",
+                (0x01, _) => "bgez",
+                (0x02, _) => "bltzl",
+                (0x03, _) => "bgezl",
+                (0x10, _) => "bltzal",
+                (0x11, 0) => "bal",
+                (0x11, _) => "bgezal",
+                _ => return None,
+            },
+            0x02 => "j",
+            0x03 => "jal",
+            0x04 => "beq",
+            0x05 => "bne",
+            0x06 => "blez",
+            0x07 => "bgtz",
+            0x08 => "addi",
+            0x09 => "addiu",
+            0x0a => "slti",
+            0x0b => "sltiu",
+            0x0c => "andi",
+            0x0d => "ori",
+            0x0e => "xori",
+            0x0f => "lui",
+            // Only the selector 0 of the CP0 registers.
+            0x10 => match (rs, insn & 0x7ff) {
+                (0x00, 0) => "mfc0",
+                (0x04, 0) => "mtc0",
+                _ if insn == 0x4200_0018 => "eret",
+                _ => return None,
+            },
+            0x14 => "beql",
+            0x15 => "bnel",
+            0x16 => "blezl",
+            0x17 => "bgtzl",
+            0x1c => match func {
+                0x00 => "madd",
+                0x01 => "maddu",
+                0x02 => "mul",
+                0x04 => "msub",
+                0x05 => "msubu",
+                0x20 => "clz",
+                0x21 => "clo",
+                _ => return None,
+            },
+            0x1f => match (func, sa) {
+                (0x00, _) => "ext",
+                (0x04, _) => "ins",
+                (0x20, 0x02) => "wsbh",
+                (0x20, 0x10) => "seb",
+                (0x20, 0x18) => "seh",
+                (0x3b, _) => "rdhwr",
+                _ => return None,
+            },
+            0x20 => "lb",
+            0x21 => "lh",
+            0x22 => "lwl",
+            0x23 => "lw",
+            0x24 => "lbu",
+            0x25 => "lhu",
+            0x26 => "lwr",
+            0x28 => "sb",
+            0x29 => "sh",
+            0x2a => "swl",
+            0x2b => "sw",
+            0x2e => "swr",
+            0x30 => "ll",
+            0x33 => "pref",
+            0x38 => "sc",
+            _ => return None,
+        })
+    }
+
+    /// `decode` accepts exactly the instructions of the manual, with the encoding of the
+    /// manual.
+    #[test]
+    fn decode_matches_the_manual() {
+        for opcode in 0..64u32 {
+            for func in 0..64u32 {
+                for rt in 0..32u32 {
+                    for rs in [0, 1, 4, 16] {
+                        for sa in [0, 1, 2, 0x10, 0x18] {
+                            let insn = (opcode << 26) | (rs << 21) | (rt << 16) | (sa << 6) | func;
+                            let spec = SPEC
+                                .iter()
+                                .find(|&&(_, value, mask, _)| insn & mask == value)
+                                .map(|&(name, ..)| name);
+                            assert_eq!(spec, manual(insn), "{insn:#010x}");
+                            assert_eq!(decode(insn).is_ok(), spec.is_some(), "{insn:#010x}");
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
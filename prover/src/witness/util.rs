@@ -5,22 +5,45 @@ use plonky2::field::packed::PackedField;
 use plonky2::field::types::Field;
 use plonky2::hash::hash_types::RichField;
 
+use crate::blake::compress_stark::{BlakeCompressOp, BlakeCompression};
+use crate::bls12_381::fp_stark::Bls12381FpOp;
+use crate::chacha::block_stark::ChaChaBlockOp;
 use crate::cpu::columns::CpuColumnsView;
+use crate::cpu::kernel::keccak_util::keccakf_u8s;
 use crate::cpu::membus::NUM_CHANNELS;
 use crate::cpu::membus::NUM_GP_CHANNELS;
+use crate::ed25519::ed25519_stark::{Ed25519Op, Ed25519OpKind};
 use crate::generation::state::GenerationState;
+use crate::keccak_sponge::columns::KECCAK_DIGEST_BYTES;
 use crate::keccak_sponge::columns::KECCAK_RATE_BYTES;
 use crate::keccak_sponge::columns::KECCAK_WIDTH_BYTES;
+use crate::keccak_sponge::keccak_sponge_stark::KeccakSpongeOp;
 use crate::logic;
 use crate::memory::segments::Segment;
+use crate::modexp::modexp_stark::ModExpOp;
 use crate::poseidon::constants::{SPONGE_RATE, SPONGE_WIDTH};
 use crate::poseidon::poseidon_stark::poseidon_with_witness;
 use crate::poseidon_sponge::columns::POSEIDON_RATE_BYTES;
 use crate::poseidon_sponge::poseidon_sponge_stark::PoseidonSpongeOp;
+use crate::sha256::sha_compress_stark::ShaCompressOp;
+use crate::sha256::sha_extend_stark::ShaExtendOp;
+use crate::uint256::mulmod_stark::Uint256MulModOp;
+use crate::weierstrass::weierstrass_stark::{WeierstrassOp, WeierstrassOpKind};
 use crate::witness::errors::ProgramError;
 use crate::witness::memory::{MemoryAddress, MemoryChannel, MemoryOp, MemoryOpKind};
+use num_bigint::BigUint;
 use plonky2::field::extension::Extendable;
 use plonky2::plonk::config::GenericConfig;
+use zkm_emulator::blake;
+use zkm_emulator::bls12_381::{self, FpOp};
+use zkm_emulator::chacha;
+use zkm_emulator::cp0::CP0_REGISTERS_START;
+use zkm_emulator::ed25519;
+use zkm_emulator::modexp;
+use zkm_emulator::sha256;
+use zkm_emulator::uint256;
+use zkm_emulator::weierstrass;
+use zkm_emulator::{bn254, p256, secp256k1};
 
 fn to_byte_checked(n: u32) -> u8 {
     let res: u8 = n.to_le_bytes()[0];
@@ -127,6 +150,13 @@ pub(crate) fn reg_read_with_log<
             state.registers.brk
         } else if index == 38 {
             state.registers.local_user
+        } else if let Some(reg) = state
+            .registers
+            .cp0
+            .regs
+            .get(index as usize - CP0_REGISTERS_START)
+        {
+            *reg as usize
         } else {
             return Err(ProgramError::InvalidRegister);
         }
@@ -182,6 +212,13 @@ pub(crate) fn reg_write_with_log<
         state.registers.brk = value;
     } else if index == 38 {
         state.registers.local_user = value;
+    } else if let Some(reg) = state
+        .registers
+        .cp0
+        .regs
+        .get_mut(index as usize - CP0_REGISTERS_START)
+    {
+        *reg = value as u32;
     } else {
         return Err(ProgramError::InvalidRegister);
     }
@@ -463,6 +500,479 @@ pub(crate) fn poseidon_sponge_log<
     });
 }
 
+/// Logs the Keccak sponge hashing `input`, read from the words at `base_address`, and returns the
+/// digest. As the sponge table reads whole words, the bytes of the last word after the input
+/// must hold the padding of the final block.
+pub(crate) fn keccak_sponge_log<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    state: &mut GenerationState<F, C, D>,
+    base_address: Vec<MemoryAddress>,
+    input: Vec<u8>,
+) -> [u8; KECCAK_DIGEST_BYTES] {
+    let clock = state.traces.clock();
+
+    let mut sponge_state = [0u8; KECCAK_WIDTH_BYTES];
+    // The sponge reads each byte on its own, from the word holding it.
+    for i in 0..input.len() {
+        let address = base_address[i / 4];
+        state.traces.push_memory(MemoryOp::new(
+            MemoryChannel::GeneralPurpose(i % (NUM_GP_CHANNELS - 1)),
+            clock,
+            address,
+            MemoryOpKind::Read,
+            state.memory.get(address),
+        ));
+    }
+
+    let mut input_blocks = input.chunks_exact(KECCAK_RATE_BYTES);
+    for block in input_blocks.by_ref() {
+        xor_into_sponge(state, &mut sponge_state, block.try_into().unwrap());
+        state
+            .traces
+            .push_keccak_bytes(sponge_state, clock * NUM_CHANNELS);
+        keccakf_u8s(&mut sponge_state);
+    }
+
+    let rem = input_blocks.remainder();
+    let mut final_block = [0u8; KECCAK_RATE_BYTES];
+    final_block[..rem.len()].copy_from_slice(rem);
+    // pad10*1 rule
+    final_block[rem.len()] = 1;
+    final_block[KECCAK_RATE_BYTES - 1] |= 0b10000000;
+    xor_into_sponge(state, &mut sponge_state, &final_block);
+    state
+        .traces
+        .push_keccak_bytes(sponge_state, clock * NUM_CHANNELS);
+    keccakf_u8s(&mut sponge_state);
+
+    state.traces.push_keccak_sponge(KeccakSpongeOp {
+        base_address,
+        timestamp: clock * NUM_CHANNELS,
+        input,
+    });
+
+    sponge_state[..KECCAK_DIGEST_BYTES].try_into().unwrap()
+}
+
+/// Logs the word reads of a precompile table at the timestamp of the current row.
+fn table_read_words<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    state: &mut GenerationState<F, C, D>,
+    addr: usize,
+    words: &mut [u32],
+) {
+    let clock = state.traces.clock();
+    for (i, word) in words.iter_mut().enumerate() {
+        let address = MemoryAddress::new(0, Segment::Code, addr + 4 * i);
+        *word = state.memory.get(address);
+        state.traces.push_memory(MemoryOp::new(
+            MemoryChannel::GeneralPurpose(i % NUM_GP_CHANNELS),
+            clock,
+            address,
+            MemoryOpKind::Read,
+            *word,
+        ));
+    }
+}
+
+/// Logs the word writes of a precompile table, right after the timestamp of the current row so
+/// that they follow its reads.
+fn table_write_words<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    state: &mut GenerationState<F, C, D>,
+    addr: usize,
+    words: &[u32],
+) {
+    let clock = state.traces.clock();
+    for (i, &word) in words.iter().enumerate() {
+        let address = MemoryAddress::new(0, Segment::Code, addr + 4 * i);
+        let op = MemoryOp::new(
+            MemoryChannel::GeneralPurpose(i % NUM_GP_CHANNELS),
+            clock,
+            address,
+            MemoryOpKind::Write,
+            word,
+        );
+        state.traces.push_memory(MemoryOp {
+            timestamp: op.timestamp + 1,
+            ..op
+        });
+    }
+}
+
+/// Logs a `SHA256_EXTEND` call on the `w` at `w_ptr`.
+pub(crate) fn sha_extend_log<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    state: &mut GenerationState<F, C, D>,
+    w_ptr: usize,
+) {
+    let mut w = [0u32; 64];
+    table_read_words(state, w_ptr, &mut w[..16]);
+    sha256::extend(&mut w);
+    table_write_words(state, w_ptr + 16 * 4, &w[16..]);
+
+    state.traces.push_sha_extend(ShaExtendOp {
+        w_ptr,
+        timestamp: state.traces.clock() * NUM_CHANNELS,
+        input: w[..16].try_into().unwrap(),
+    });
+}
+
+/// Logs a `SHA256_COMPRESS` call on the `w` at `w_ptr` and the hash state at `h_ptr`.
+pub(crate) fn sha_compress_log<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    state: &mut GenerationState<F, C, D>,
+    w_ptr: usize,
+    h_ptr: usize,
+) {
+    let mut w = [0u32; 64];
+    let mut h = [0u32; 8];
+    table_read_words(state, w_ptr, &mut w);
+    table_read_words(state, h_ptr, &mut h);
+    let mut new_h = h;
+    sha256::compress(&mut new_h, &w);
+    table_write_words(state, h_ptr, &new_h);
+
+    state.traces.push_sha_compress(ShaCompressOp {
+        w_ptr,
+        h_ptr,
+        timestamp: state.traces.clock() * NUM_CHANNELS,
+        w,
+        h,
+    });
+}
+
+/// Logs an addition of the points at `p_ptr` and `q_ptr`, or a doubling of the point at `p_ptr`,
+/// on the curve of base field modulus `m` and coefficient `a`, which the formula must apply to.
+fn weierstrass_log<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    state: &mut GenerationState<F, C, D>,
+    m: &BigUint,
+    a: &BigUint,
+    kind: WeierstrassOpKind,
+    p_ptr: usize,
+    q_ptr: usize,
+) -> WeierstrassOp {
+    let mut p = [0u32; 16];
+    table_read_words(state, p_ptr, &mut p);
+    let (q, result) = match kind {
+        WeierstrassOpKind::Add => {
+            let mut q = [0u32; 16];
+            table_read_words(state, q_ptr, &mut q);
+            (q, weierstrass::add(m, &p, &q))
+        }
+        WeierstrassOpKind::Double => (p, weierstrass::double(m, a, &p)),
+    };
+    table_write_words(state, p_ptr, &result.unwrap());
+
+    WeierstrassOp {
+        kind,
+        p_ptr,
+        q_ptr,
+        timestamp: state.traces.clock() * NUM_CHANNELS,
+        p,
+        q,
+    }
+}
+
+/// Logs a `SECP256K1_ADD` of the points at `p_ptr` and `q_ptr`, or a `SECP256K1_DOUBLE` of the
+/// point at `p_ptr`, which the formula must apply to.
+pub(crate) fn secp256k1_log<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    state: &mut GenerationState<F, C, D>,
+    kind: WeierstrassOpKind,
+    p_ptr: usize,
+    q_ptr: usize,
+) {
+    let op = weierstrass_log(
+        state,
+        &secp256k1::MODULUS,
+        &secp256k1::A,
+        kind,
+        p_ptr,
+        q_ptr,
+    );
+    state.traces.push_secp256k1(op);
+}
+
+/// Logs a `BN254_ADD` of the points at `p_ptr` and `q_ptr`, or a `BN254_DOUBLE` of the point at
+/// `p_ptr`, which the formula must apply to.
+pub(crate) fn bn254_log<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    state: &mut GenerationState<F, C, D>,
+    kind: WeierstrassOpKind,
+    p_ptr: usize,
+    q_ptr: usize,
+) {
+    let op = weierstrass_log(state, &bn254::MODULUS, &bn254::A, kind, p_ptr, q_ptr);
+    state.traces.push_bn254(op);
+}
+
+/// Logs a `SECP256R1_ADD` of the points at `p_ptr` and `q_ptr`, or a `SECP256R1_DOUBLE` of the
+/// point at `p_ptr`, which the formula must apply to.
+pub(crate) fn secp256r1_log<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    state: &mut GenerationState<F, C, D>,
+    kind: WeierstrassOpKind,
+    p_ptr: usize,
+    q_ptr: usize,
+) {
+    let op = weierstrass_log(state, &p256::MODULUS, &p256::A, kind, p_ptr, q_ptr);
+    state.traces.push_secp256r1(op);
+}
+
+/// Logs an `ED_ADD` of the points at `p_ptr` and `q_ptr`, or an `ED_DECOMPRESS` of the point at
+/// `p_ptr`, which must be on the curve.
+pub(crate) fn ed25519_log<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    state: &mut GenerationState<F, C, D>,
+    kind: Ed25519OpKind,
+    p_ptr: usize,
+    q_ptr: usize,
+) {
+    let mut p = [0u32; 16];
+    let mut q = [0u32; 16];
+    match kind {
+        Ed25519OpKind::Add => {
+            table_read_words(state, p_ptr, &mut p);
+            table_read_words(state, q_ptr, &mut q);
+            let sum = ed25519::add(&p, &q).unwrap();
+            table_write_words(state, p_ptr, &sum);
+        }
+        Ed25519OpKind::Decompress => {
+            table_read_words(state, p_ptr + 32, &mut p[8..]);
+            let mut compressed = [0u8; 32];
+            for (bytes, word) in compressed.chunks_exact_mut(4).zip(&p[8..]) {
+                bytes.copy_from_slice(&word.to_be_bytes());
+            }
+            let mut x = ed25519::decompress(&compressed).unwrap().to_bytes_le();
+            x.resize(32, 0);
+            let words = x
+                .chunks_exact(4)
+                .map(|bytes| u32::from_be_bytes(bytes.try_into().unwrap()))
+                .collect_vec();
+            table_write_words(state, p_ptr, &words);
+        }
+    }
+
+    state.traces.push_ed25519(Ed25519Op {
+        kind,
+        p_ptr,
+        q_ptr,
+        timestamp: state.traces.clock() * NUM_CHANNELS,
+        p,
+        q,
+    });
+}
+
+/// Logs a `BLS12381_FP_ADD`, `BLS12381_FP_SUB` or `BLS12381_FP_MUL` of the elements at `x_ptr`
+/// and `y_ptr`, writing the result over `x`.
+pub(crate) fn bls12381_fp_log<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    state: &mut GenerationState<F, C, D>,
+    op: FpOp,
+    x_ptr: usize,
+    y_ptr: usize,
+) {
+    let mut x = [0u32; 12];
+    let mut y = [0u32; 12];
+    table_read_words(state, x_ptr, &mut x);
+    table_read_words(state, y_ptr, &mut y);
+    table_write_words(state, x_ptr, &bls12_381::apply(op, &x, &y));
+
+    state.traces.push_bls12381_fp(Bls12381FpOp {
+        op,
+        x_ptr,
+        y_ptr,
+        timestamp: state.traces.clock() * NUM_CHANNELS,
+        x,
+        y,
+    });
+}
+
+/// Logs a `UINT256_MULMOD` of the integer at `x_ptr` by the integer at `y_ptr`, modulo the
+/// integer after it, which must not be zero. The result is written over `x`.
+pub(crate) fn uint256_mulmod_log<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    state: &mut GenerationState<F, C, D>,
+    x_ptr: usize,
+    y_ptr: usize,
+) {
+    let mut x = [0u32; 8];
+    let mut y = [0u32; 8];
+    let mut modulus = [0u32; 8];
+    table_read_words(state, x_ptr, &mut x);
+    table_read_words(state, y_ptr, &mut y);
+    table_read_words(state, y_ptr + 32, &mut modulus);
+    let result = uint256::mulmod(&x, &y, &modulus).unwrap();
+    table_write_words(state, x_ptr, &result);
+
+    state.traces.push_uint256(Uint256MulModOp {
+        x_ptr,
+        y_ptr,
+        timestamp: state.traces.clock() * NUM_CHANNELS,
+        x,
+        y,
+        modulus,
+    });
+}
+
+/// Logs a `MODEXP` of the integer at `base_ptr`. The parameters at `params_ptr` are the number
+/// of words of the base and of the modulus, then of the exponent, then the modulus and the
+/// exponent, which must be valid operands of `modexp::modexp`. The result is written over the
+/// base.
+pub(crate) fn modexp_log<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    state: &mut GenerationState<F, C, D>,
+    base_ptr: usize,
+    params_ptr: usize,
+) {
+    let mut lens = [0u32; 2];
+    table_read_words(state, params_ptr, &mut lens);
+    let (len, exp_len) = (lens[0] as usize, lens[1] as usize);
+    let mut base = vec![0u32; len];
+    let mut modulus = vec![0u32; len];
+    let mut exp = vec![0u32; exp_len];
+    table_read_words(state, base_ptr, &mut base);
+    table_read_words(state, params_ptr + 8, &mut modulus);
+    table_read_words(state, params_ptr + 8 + 4 * len, &mut exp);
+    let result = modexp::modexp(&base, &exp, &modulus).unwrap();
+    table_write_words(state, base_ptr, &result);
+
+    state.traces.push_modexp(ModExpOp {
+        base_ptr,
+        params_ptr,
+        timestamp: state.traces.clock() * NUM_CHANNELS,
+        base,
+        exp,
+        modulus,
+    });
+}
+
+/// Logs a `BLAKE2B_COMPRESS` of the block at `input_ptr` into the hash state at `h_ptr`. The
+/// block is followed by `t`, `f`, which must be 0 or 1, and the number of rounds.
+pub(crate) fn blake2b_compress_log<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    state: &mut GenerationState<F, C, D>,
+    h_ptr: usize,
+    input_ptr: usize,
+) {
+    let mut h_words = [0u32; 16];
+    let mut input = [0u32; 38];
+    table_read_words(state, h_ptr, &mut h_words);
+    table_read_words(state, input_ptr, &mut input);
+    let to_u64 = |words: &[u32]| (words[0] as u64) << 32 | words[1] as u64;
+    let h: [u64; 8] = core::array::from_fn(|i| to_u64(&h_words[2 * i..]));
+    let m: [u64; 16] = core::array::from_fn(|i| to_u64(&input[2 * i..]));
+    let t = [to_u64(&input[32..]), to_u64(&input[34..])];
+    let (f, rounds) = (input[36] == 1, input[37]);
+
+    let mut new_h = h;
+    blake::blake2b_compress(rounds, &mut new_h, &m, t, f);
+    let new_h_words = new_h
+        .iter()
+        .flat_map(|word| [(word >> 32) as u32, *word as u32])
+        .collect_vec();
+    table_write_words(state, h_ptr, &new_h_words);
+
+    state.traces.push_blake(BlakeCompressOp {
+        h_ptr,
+        input_ptr,
+        timestamp: state.traces.clock() * NUM_CHANNELS,
+        compression: BlakeCompression::Blake2b { rounds, h, m, t, f },
+    });
+}
+
+/// Logs a `BLAKE3_COMPRESS` of the block at `input_ptr` into the chaining value at `cv_ptr`.
+/// The block is followed by the low and high words of the counter, the block length and the
+/// flags.
+pub(crate) fn blake3_compress_log<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    state: &mut GenerationState<F, C, D>,
+    cv_ptr: usize,
+    input_ptr: usize,
+) {
+    let mut cv = [0u32; 8];
+    let mut input = [0u32; 20];
+    table_read_words(state, cv_ptr, &mut cv);
+    table_read_words(state, input_ptr, &mut input);
+    let block: [u32; 16] = input[..16].try_into().unwrap();
+    let counter = (input[17] as u64) << 32 | input[16] as u64;
+    let (block_len, flags) = (input[18], input[19]);
+
+    let mut new_cv = cv;
+    blake::blake3_compress(&mut new_cv, &block, counter, block_len, flags);
+    table_write_words(state, cv_ptr, &new_cv);
+
+    state.traces.push_blake(BlakeCompressOp {
+        h_ptr: cv_ptr,
+        input_ptr,
+        timestamp: state.traces.clock() * NUM_CHANNELS,
+        compression: BlakeCompression::Blake3 {
+            cv,
+            block,
+            counter,
+            block_len,
+            flags,
+        },
+    });
+}
+
+/// Logs a `CHACHA20_BLOCK` call on the state at `state_ptr`, which the keystream block is
+/// written over.
+pub(crate) fn chacha20_block_log<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    state: &mut GenerationState<F, C, D>,
+    state_ptr: usize,
+) {
+    let mut input = [0u32; 16];
+    table_read_words(state, state_ptr, &mut input);
+    let block = chacha::chacha20_block(&input);
+    table_write_words(state, state_ptr, &block);
+
+    state.traces.push_chacha(ChaChaBlockOp {
+        state_ptr,
+        timestamp: state.traces.clock() * NUM_CHANNELS,
+        state: input,
+    });
+}
+
 fn xor_into_sponge<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
     state: &mut GenerationState<F, C, D>,
     sponge_state: &mut [u8; KECCAK_WIDTH_BYTES],
@@ -0,0 +1,253 @@
+use std::fs::File;
+use std::io::BufReader;
+
+use plonky2::field::goldilocks_field::GoldilocksField;
+use plonky2::plonk::config::PoseidonGoldilocksConfig;
+use plonky2::util::timing::TimingTree;
+use zkm_emulator::cp0::EXCEPTION_VECTOR;
+use zkm_emulator::journal::Journal;
+use zkm_emulator::state::State;
+use zkm_emulator::stdin::Stdin;
+use zkm_emulator::utils::split_prog_into_segs;
+use zkm_prover::all_stark::AllStark;
+use zkm_prover::client::{ProverClient, ZkmProof};
+use zkm_prover::config::{StarkConfig, StarkHasher};
+use zkm_prover::cpu::kernel::assembler::{segment_kernel, Kernel};
+use zkm_prover::error::VerificationError;
+use zkm_prover::proof::{AllProof, PublicValues};
+use zkm_prover::prover::prove;
+use zkm_prover::verifier::verify_proof;
+
+type F = GoldilocksField;
+const D: usize = 2;
+type C = PoseidonGoldilocksConfig;
+
+/// Exits with code 0.
+const EXIT: [u32; 2] = [
+    0x34021096, // ori $v0, $zero, 0x1096
+    0x0000000c, // syscall
+];
+
+/// The kernel of the run of the program made of `blocks`, each at its address, in a single
+/// segment.
+fn segment_of(name: &str, blocks: &[(u32, &[u32])]) -> anyhow::Result<Kernel> {
+    env_logger::try_init().unwrap_or_default();
+
+    let mut state = State::new();
+    for (addr, words) in blocks {
+        for (i, word) in words.iter().enumerate() {
+            state.memory.set_memory(addr + 4 * i as u32, *word);
+        }
+    }
+    let seg_dir = std::env::temp_dir().join(format!("zkm_features_{}", name));
+    let _ = split_prog_into_segs(state, seg_dir.to_str().unwrap(), "", 0);
+
+    Ok(segment_kernel(
+        "",
+        "",
+        "",
+        BufReader::new(File::open(seg_dir.join("0"))?),
+    ))
+}
+
+/// Proves and verifies the run of the program made of `blocks`, returning the public values of
+/// the proof.
+fn prove_program(name: &str, blocks: &[(u32, &[u32])]) -> anyhow::Result<PublicValues> {
+    let kernel = segment_of(name, blocks)?;
+    let all_stark = AllStark::<F, D>::default();
+    let config = StarkConfig::standard_fast_config();
+    let proof = prove::<F, C, D>(&all_stark, &kernel, &config, &mut TimingTree::default())?;
+    let public_values = proof.public_values.clone();
+    verify_proof(&all_stark, proof, &config)?;
+    Ok(public_values)
+}
+
+#[test]
+fn test_prove_branches() -> anyhow::Result<()> {
+    // The delay slots run whether the branches are taken or not.
+    let program = [
+        0x34080001, // ori $t0, $zero, 1
+        0x11000003, // beq $t0, $zero, 5 (not taken)
+        0x34040001, // ori $a0, $zero, 1
+        0x15000002, // bne $t0, $zero, 6 (taken)
+        0x24840002, // addiu $a0, $a0, 2
+        0x34040055, // ori $a0, $zero, 0x55
+        EXIT[0], EXIT[1],
+    ];
+    let public_values = prove_program("branches", &[(0, &program)])?;
+    assert_eq!(public_values.exit_code, 3);
+    Ok(())
+}
+
+#[test]
+fn test_prove_jumps() -> anyhow::Result<()> {
+    let program = [
+        0x0c000040, // jal 0x100
+        0x34040001, // ori $a0, $zero, 1
+        0x24840004, // addiu $a0, $a0, 4
+        EXIT[0], EXIT[1],
+    ];
+    let function = [
+        0x03e00008, // jr $ra
+        0x24840002, // addiu $a0, $a0, 2
+    ];
+    let public_values = prove_program("jumps", &[(0, &program), (0x100, &function)])?;
+    assert_eq!(public_values.exit_code, 7);
+    Ok(())
+}
+
+#[test]
+fn test_prove_precompile() -> anyhow::Result<()> {
+    // Exits with the first byte of keccak256("abc").
+    let program = [
+        0x3c020001, // lui $v0, 0x1
+        0x34420109, // ori $v0, $v0, 0x109 (SYS_KECCAK256)
+        0x34041000, // ori $a0, $zero, 0x1000
+        0x34050003, // ori $a1, $zero, 3
+        0x34062000, // ori $a2, $zero, 0x2000
+        0x0000000c, // syscall
+        0x90c40000, // lbu $a0, 0($a2)
+        EXIT[0], EXIT[1],
+    ];
+    let public_values = prove_program(
+        "precompile",
+        &[(0, &program), (0x1000, &[0x61626300]), (0x2000, &[0; 8])],
+    )?;
+    assert_eq!(public_values.exit_code, 0x4e);
+    Ok(())
+}
+
+#[test]
+fn test_prove_invalid_precompile_call_traps() -> anyhow::Result<()> {
+    // The digest address is not word aligned, so the call traps instead of looking up the table.
+    let program = [
+        0x3c020001, // lui $v0, 0x1
+        0x34420109, // ori $v0, $v0, 0x109 (SYS_KECCAK256)
+        0x34041000, // ori $a0, $zero, 0x1000
+        0x34050003, // ori $a1, $zero, 3
+        0x34062001, // ori $a2, $zero, 0x2001
+        0x0000000c, // syscall
+        EXIT[0], EXIT[1],
+    ];
+    let public_values = prove_program("invalid_precompile_call", &[(0, &program)])?;
+    assert_eq!(public_values.exit_code, 13);
+    Ok(())
+}
+
+#[test]
+fn test_prove_exception_and_eret() -> anyhow::Result<()> {
+    let program = [
+        0x34080001, // ori $t0, $zero, 1
+        0x40886000, // mtc0 $t0, $12 (Status.IE)
+        0x0000000d, // break
+        0x34090007, // ori $t1, $zero, 7
+        EXIT[0], EXIT[1],
+    ];
+    let handler = [
+        0x401a7000, // mfc0 $k0, $14 (EPC)
+        0x275a0004, // addiu $k0, $k0, 4
+        0x409a7000, // mtc0 $k0, $14
+        0x42000018, // eret
+    ];
+    let public_values = prove_program(
+        "exception_and_eret",
+        &[(0, &program), (EXCEPTION_VECTOR, &handler)],
+    )?;
+    assert_eq!(public_values.exit_code, 0);
+    Ok(())
+}
+
+#[test]
+fn test_prove_exit_code() -> anyhow::Result<()> {
+    let program = [
+        0x34040103, // ori $a0, $zero, 0x103
+        EXIT[0], EXIT[1],
+    ];
+    let public_values = prove_program("exit_code", &[(0, &program)])?;
+    assert_eq!(public_values.exit_code, 3);
+    Ok(())
+}
+
+#[test]
+fn test_prove_trap() -> anyhow::Result<()> {
+    // Status.IE is clear, so the break stops the run.
+    let program = [
+        0x0000000d, // break
+        EXIT[0], EXIT[1],
+    ];
+    let public_values = prove_program("trap", &[(0, &program)])?;
+    assert_eq!(public_values.exit_code, 9);
+    Ok(())
+}
+
+/// A Go program, with the boot of the Go runtime, its TLS and its syscalls.
+#[test]
+#[ignore = "proves millions of cycles"]
+fn test_prove_go_hello() -> anyhow::Result<()> {
+    env_logger::try_init().unwrap_or_default();
+
+    let elf = std::fs::read("../emulator/test-vectors/hello")?;
+    let client = ProverClient::builder()
+        .seg_dir(std::env::temp_dir().join("zkm_features_go_hello"))
+        .build();
+    let proof = client.prove(&elf, &Stdin::default())?;
+    client.verify(&proof)?;
+    let last = proof.segments.last().unwrap();
+    assert_eq!(last.public_values.exit_code, 0);
+    Ok(())
+}
+
+#[test]
+fn test_serialized_proof_verifies() -> anyhow::Result<()> {
+    let kernel = segment_of("serialized_proof", &[(0, &EXIT)])?;
+    let all_stark = AllStark::<F, D>::default();
+    let config = StarkConfig::standard_fast_config();
+    let proof = prove::<F, C, D>(&all_stark, &kernel, &config, &mut TimingTree::default())?;
+    let bytes = proof.serialize()?;
+    verify_proof(&all_stark, AllProof::deserialize(&bytes)?, &config)?;
+    assert!(PublicValues::deserialize(&bytes).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_wrong_image_id_is_rejected() -> anyhow::Result<()> {
+    let kernel = segment_of("wrong_image_id", &[(0, &EXIT)])?;
+    let all_stark = AllStark::<F, D>::default();
+    let config = StarkConfig::standard_fast_config();
+    let mut proof = prove::<F, C, D>(&all_stark, &kernel, &config, &mut TimingTree::default())?;
+    proof.public_values.roots_before.root[0] ^= 1;
+    assert!(verify_proof(&all_stark, proof, &config).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_tampered_journal_is_rejected() -> anyhow::Result<()> {
+    // Commits "abc" to the journal.
+    let program = [
+        0x34020fa4, // ori $v0, $zero, 0xfa4 (SYS_WRITE)
+        0x34040003, // ori $a0, $zero, 3 (FD_PUBLIC_VALUES)
+        0x34051000, // ori $a1, $zero, 0x1000
+        0x34060003, // ori $a2, $zero, 3
+        0x0000000c, // syscall
+        EXIT[0], EXIT[1],
+    ];
+    let kernel = segment_of(
+        "tampered_journal",
+        &[(0, &program), (0x1000, &[0x61626300])],
+    )?;
+    let all_stark = AllStark::<F, D>::default();
+    let config = StarkConfig::standard_fast_config();
+    let segment = prove::<F, C, D>(&all_stark, &kernel, &config, &mut TimingTree::default())?;
+    let mut proof = ZkmProof {
+        segments: vec![segment],
+        journal: Journal::new(b"abc".to_vec()),
+        hasher: StarkHasher::Poseidon,
+    };
+    let client = ProverClient::new();
+    client.verify(&proof)?;
+
+    proof.journal.bytes[0] ^= 1;
+    let err = client.verify(&proof).unwrap_err();
+    assert_eq!(err.downcast_ref(), Some(&VerificationError::WrongJournal));
+    Ok(())
+}
@@ -0,0 +1,82 @@
+//! The arguments and environment variables the host started the program with, see
+//! `load_elf_with_env`.
+//!
+//! In the zkVM they are read from the initial stack, `argc, argv, 0, envp, 0`, which is part of
+//! the image id. Compiled for another target, they are those of the process.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+#[cfg(target_os = "zkvm")]
+mod stack {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use core::ffi::{c_char, CStr};
+
+    /// The stack pointer at the start of the program, where the kernel lays out `argc`.
+    const INIT_SP: usize = 0x7fffd000;
+
+    fn word(addr: usize) -> usize {
+        // SAFETY: The initial stack is mapped, and the program does not write above `INIT_SP`.
+        unsafe { (addr as *const u32).read_volatile() as usize }
+    }
+
+    /// The strings of the null-terminated array of pointers at `addr`.
+    fn strings(mut addr: usize) -> Vec<String> {
+        let mut strings = Vec::new();
+        loop {
+            let ptr = word(addr);
+            if ptr == 0 {
+                return strings;
+            }
+            // SAFETY: The kernel writes null-terminated strings.
+            let s = unsafe { CStr::from_ptr(ptr as *const c_char) };
+            strings.push(String::from_utf8_lossy(s.to_bytes()).into_owned());
+            addr += 4;
+        }
+    }
+
+    pub fn args() -> Vec<String> {
+        strings(INIT_SP + 4)
+    }
+
+    pub fn vars() -> Vec<(String, String)> {
+        let argc = word(INIT_SP);
+        strings(INIT_SP + 4 * (argc + 2))
+            .into_iter()
+            .filter_map(|var| {
+                let (key, value) = var.split_once('=')?;
+                Some((key.into(), value.into()))
+            })
+            .collect()
+    }
+}
+
+/// The arguments of the program, the first being its name if the host gave one.
+pub fn args() -> Vec<String> {
+    cfg_if::cfg_if! {
+        if #[cfg(target_os = "zkvm")] {
+            stack::args()
+        } else {
+            std::env::args().collect()
+        }
+    }
+}
+
+/// The environment variables of the program, in the order the host gave them.
+pub fn vars() -> Vec<(String, String)> {
+    cfg_if::cfg_if! {
+        if #[cfg(target_os = "zkvm")] {
+            stack::vars()
+        } else {
+            std::env::vars().collect()
+        }
+    }
+}
+
+/// The value of the environment variable `key`, or `None` if the host did not set it.
+pub fn var(key: &str) -> Option<String> {
+    vars()
+        .into_iter()
+        .find_map(|(k, value)| (k == key).then_some(value))
+}
@@ -7,8 +7,23 @@ use crate::syscalls::sys_alloc_aligned;
 /// A simple heap allocator.
 ///
 /// Allocates memory from left to right, without any deallocation.
+#[derive(Default)]
 pub struct SimpleAlloc;
 
+impl SimpleAlloc {
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+/// The allocator installed by [`entrypoint!`](crate::entrypoint).
+#[cfg(not(feature = "bigalloc"))]
+pub type Heap = SimpleAlloc;
+
+/// The allocator installed by [`entrypoint!`](crate::entrypoint).
+#[cfg(feature = "bigalloc")]
+pub type Heap = crate::memory::ReuseAlloc;
+
 unsafe impl GlobalAlloc for SimpleAlloc {
     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
         sys_alloc_aligned(layout.size(), layout.align())
@@ -1,6 +1,10 @@
 //! Ported from Entrypoint for SP1 zkVM.
 #![feature(asm_experimental_arch)]
+pub mod env;
 pub mod heap;
+pub mod memory;
+pub mod print;
+pub mod rng;
 pub mod syscalls;
 pub mod io {
     pub use zkm_precompiles::io::*;
@@ -11,15 +15,18 @@ pub mod precompiles {
 
 extern crate alloc;
 
+/// Declares `$path`, a `fn()`, as the entry point of the program.
+///
+/// The generated code installs the heap allocator, then the runtime calls `$path` with a panic
+/// hook that prints the message to stderr and halts with exit code 1. Returning from `$path` halts
+/// with exit code 0.
 #[macro_export]
 macro_rules! entrypoint {
     ($path:path) => {
         const ZKVM_ENTRY: fn() = $path;
 
-        use $crate::heap::SimpleAlloc;
-
         #[global_allocator]
-        static HEAP: SimpleAlloc = SimpleAlloc;
+        static HEAP: $crate::heap::Heap = $crate::heap::Heap::new();
 
         mod zkvm_generated_main {
 
@@ -39,7 +46,7 @@ pub const POSEIDON_NUM_WORDS: usize = 8;
 
 #[cfg(target_os = "zkvm")]
 mod zkvm {
-    use crate::syscalls::syscall_halt;
+    use crate::syscalls::{sys_panic, syscall_halt};
 
     use getrandom::{register_custom_getrandom, Error};
     use sha2::{Digest, Sha256};
@@ -51,6 +58,11 @@ mod zkvm {
     fn main() {
         unsafe {
             PUBLIC_VALUES_HASHER = Some(Sha256::new());
+            std::panic::set_hook(Box::new(|info| {
+                crate::print::flush();
+                let msg = format!("{info}\n");
+                sys_panic(msg.as_ptr(), msg.len())
+            }));
 
             extern "C" {
                 fn start();
@@ -58,6 +70,7 @@ mod zkvm {
             start()
         }
 
+        crate::print::flush();
         syscall_halt(0);
     }
 
@@ -65,9 +78,7 @@ mod zkvm {
     core::arch::global_asm!(include_str!("memcpy.s"));
 
     fn zkvm_getrandom(s: &mut [u8]) -> Result<(), Error> {
-        unsafe {
-            crate::syscalls::sys_rand(s.as_mut_ptr(), s.len());
-        }
+        crate::rng::fill_bytes(s);
 
         Ok(())
     }
@@ -1,51 +1,161 @@
-// Copyright 2023 RISC Zero, Inc.
-//
-// Licensed under the Apache License, Version 2.0 (the "License");
-// you may not use this file except in compliance with the License.
-// You may obtain a copy of the License at
-//
-//     http://www.apache.org/licenses/LICENSE-2.0
-//
-// Unless required by applicable law or agreed to in writing, software
-// distributed under the License is distributed on an "AS IS" BASIS,
-// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
-// See the License for the specific language governing permissions and
-// limitations under the License.
-
-const SYSTEM_START: usize = 0x0C00_0000;
-
-#[allow(clippy::missing_safety_doc)]
-#[no_mangle]
-pub unsafe extern "C" fn sys_alloc_aligned(bytes: usize, align: usize) -> *mut u8 {
-    extern "C" {
-        // https://lld.llvm.org/ELF/linker_script.html#sections-command
-        static _end: u8;
+//! The heap of the program, which the VM maps on demand with the mmap syscall.
+//!
+//! The default allocator bumps a pointer through the heap and never frees. Long-running programs
+//! which allocate and free a lot can enable the `bigalloc` feature to use dlmalloc instead, which
+//! reuses freed blocks.
+
+use crate::syscalls::syscall_mmap;
+
+/// The size of the pages of the VM, the granularity of [`reserve`].
+pub const PAGE_SIZE: usize = 4096;
+
+/// The smallest region the bump allocator reserves at once, so that most allocations don't cost
+/// a syscall.
+const BUMP_CHUNK_SIZE: usize = 1 << 20;
+
+/// The next free address of the bump allocator, and the end of the region it reserved so far.
+static mut HEAP_POS: usize = 0;
+static mut HEAP_END: usize = 0;
+
+/// Reserves `size` bytes of zeroed memory, rounded up to whole pages, or returns null if the heap
+/// is exhausted.
+pub fn reserve(size: usize) -> *mut u8 {
+    let ptr = syscall_mmap(size);
+    if ptr as usize == usize::MAX {
+        core::ptr::null_mut()
+    } else {
+        ptr
     }
+}
 
-    // Pointer to next heap address to use, or 0 if the heap has not yet been
-    // initialized.
-    static mut HEAP_POS: usize = 0;
+/// Allocates `bytes` bytes aligned to `align`, a power of two, from the bump allocator.
+///
+/// # Safety
+///
+/// The program must be single threaded.
+pub unsafe fn bump_alloc(bytes: usize, align: usize) -> *mut u8 {
+    // SAFETY: Single threaded, so nothing else can touch these while we're working.
+    let (pos, mut end) = unsafe { (HEAP_POS, HEAP_END) };
 
-    // SAFETY: Single threaded, so nothing else can touch this while we're working.
-    let mut heap_pos = unsafe { HEAP_POS };
+    let mut start = align_up(pos, align);
+    if end == 0 || start.checked_add(bytes).map_or(true, |next| next > end) {
+        let size = match bytes.checked_add(align) {
+            Some(size) => size.max(BUMP_CHUNK_SIZE),
+            None => return core::ptr::null_mut(),
+        };
+        let chunk = reserve(size);
+        if chunk.is_null() {
+            return core::ptr::null_mut();
+        }
+        // The mmap region grows upwards, so the chunk usually extends the previous one. If
+        // another allocator reserved memory in between, the rest of the previous one is lost.
+        if chunk as usize != end {
+            start = align_up(chunk as usize, align);
+        }
+        end = chunk as usize + size;
+    }
 
-    if heap_pos == 0 {
-        heap_pos = unsafe { (&_end) as *const u8 as usize };
+    unsafe {
+        HEAP_POS = start + bytes;
+        HEAP_END = end;
     }
+    start as *mut u8
+}
+
+/// The end of the memory allocated by the bump allocator, or 0 if it allocated nothing.
+pub fn heap_pos() -> usize {
+    // SAFETY: Single threaded.
+    unsafe { HEAP_POS }
+}
+
+fn align_up(addr: usize, align: usize) -> usize {
+    (addr + align - 1) & !(align - 1)
+}
+
+#[cfg(feature = "bigalloc")]
+pub use reuse::ReuseAlloc;
+
+#[cfg(feature = "bigalloc")]
+mod reuse {
+    use core::alloc::{GlobalAlloc, Layout};
+    use core::cell::UnsafeCell;
+
+    use dlmalloc::{Allocator, Dlmalloc};
 
-    let offset = heap_pos & (align - 1);
-    if offset != 0 {
-        heap_pos += align - offset;
+    use super::{reserve, PAGE_SIZE};
+
+    /// The source of the segments of dlmalloc. Memory reserved from the VM is never given back,
+    /// dlmalloc reuses it instead.
+    struct MmapSource;
+
+    unsafe impl Allocator for MmapSource {
+        fn alloc(&self, size: usize) -> (*mut u8, usize, u32) {
+            let ptr = reserve(size);
+            if ptr.is_null() {
+                (ptr, 0, 0)
+            } else {
+                (ptr, size, 0)
+            }
+        }
+
+        fn remap(&self, _: *mut u8, _: usize, _: usize, _: bool) -> *mut u8 {
+            core::ptr::null_mut()
+        }
+
+        fn free_part(&self, _: *mut u8, _: usize, _: usize) -> bool {
+            false
+        }
+
+        fn free(&self, _: *mut u8, _: usize) -> bool {
+            false
+        }
+
+        fn can_release_part(&self, _: u32) -> bool {
+            false
+        }
+
+        fn allocates_zeros(&self) -> bool {
+            true
+        }
+
+        fn page_size(&self) -> usize {
+            PAGE_SIZE
+        }
     }
 
-    let ptr = heap_pos as *mut u8;
-    heap_pos += bytes;
+    /// A heap allocator which reuses freed memory.
+    pub struct ReuseAlloc(UnsafeCell<Dlmalloc<MmapSource>>);
 
-    // Check to make sure heap doesn't collide with SYSTEM memory.
-    if SYSTEM_START < heap_pos {
-        panic!();
+    // SAFETY: The program is single threaded.
+    unsafe impl Sync for ReuseAlloc {}
+
+    impl ReuseAlloc {
+        pub const fn new() -> Self {
+            Self(UnsafeCell::new(Dlmalloc::new_with_allocator(MmapSource)))
+        }
+    }
+
+    impl Default for ReuseAlloc {
+        fn default() -> Self {
+            Self::new()
+        }
     }
 
-    unsafe { HEAP_POS = heap_pos };
-    ptr
+    unsafe impl GlobalAlloc for ReuseAlloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            (*self.0.get()).malloc(layout.size(), layout.align())
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            (*self.0.get()).calloc(layout.size(), layout.align())
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            (*self.0.get()).free(ptr, layout.size(), layout.align())
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            (*self.0.get()).realloc(ptr, layout.size(), layout.align(), new_size)
+        }
+    }
 }
@@ -0,0 +1,118 @@
+//! The `print!` family of macros, which write to the stdout and stderr of the host.
+//!
+//! The output is buffered up to the end of each line, so that a line printed with several
+//! `print!`s costs a single WRITE syscall. The rest is flushed when the program halts.
+
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use core::ptr::addr_of_mut;
+
+use crate::syscalls::syscall_write;
+
+pub const FD_STDOUT: u32 = 1;
+pub const FD_STDERR: u32 = 2;
+
+/// The output written to a file descriptor since its last newline.
+struct LineBuffer {
+    fd: u32,
+    buf: Vec<u8>,
+}
+
+impl LineBuffer {
+    const fn new(fd: u32) -> Self {
+        Self {
+            fd,
+            buf: Vec::new(),
+        }
+    }
+
+    fn flush(&mut self) {
+        if !self.buf.is_empty() {
+            syscall_write(self.fd, self.buf.as_ptr(), self.buf.len());
+            self.buf.clear();
+        }
+    }
+}
+
+impl Write for LineBuffer {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let bytes = s.as_bytes();
+        match bytes.iter().rposition(|&b| b == b'\n') {
+            Some(end) => {
+                self.buf.extend_from_slice(&bytes[..=end]);
+                self.flush();
+                self.buf.extend_from_slice(&bytes[end + 1..]);
+            }
+            None => self.buf.extend_from_slice(bytes),
+        }
+        Ok(())
+    }
+}
+
+static mut STDOUT: LineBuffer = LineBuffer::new(FD_STDOUT);
+static mut STDERR: LineBuffer = LineBuffer::new(FD_STDERR);
+
+fn buffer(fd: u32) -> &'static mut LineBuffer {
+    // SAFETY: Single threaded, and the references don't outlive the calls below.
+    unsafe {
+        match fd {
+            FD_STDERR => &mut *addr_of_mut!(STDERR),
+            _ => &mut *addr_of_mut!(STDOUT),
+        }
+    }
+}
+
+#[doc(hidden)]
+pub fn _print(fd: u32, args: fmt::Arguments) {
+    buffer(fd).write_fmt(args).unwrap();
+}
+
+/// Writes the output which doesn't end with a newline yet.
+pub fn flush() {
+    buffer(FD_STDOUT).flush();
+    buffer(FD_STDERR).flush();
+}
+
+/// Prints to the stdout of the host.
+#[macro_export]
+macro_rules! print {
+    ($($arg:tt)*) => {
+        $crate::print::_print($crate::print::FD_STDOUT, format_args!($($arg)*))
+    };
+}
+
+/// Prints to the stdout of the host, with a newline.
+#[macro_export]
+macro_rules! println {
+    () => {
+        $crate::print!("\n")
+    };
+    ($($arg:tt)*) => {
+        $crate::print::_print(
+            $crate::print::FD_STDOUT,
+            format_args!("{}\n", format_args!($($arg)*)),
+        )
+    };
+}
+
+/// Prints to the stderr of the host.
+#[macro_export]
+macro_rules! eprint {
+    ($($arg:tt)*) => {
+        $crate::print::_print($crate::print::FD_STDERR, format_args!($($arg)*))
+    };
+}
+
+/// Prints to the stderr of the host, with a newline.
+#[macro_export]
+macro_rules! eprintln {
+    () => {
+        $crate::eprint!("\n")
+    };
+    ($($arg:tt)*) => {
+        $crate::print::_print(
+            $crate::print::FD_STDERR,
+            format_args!("{}\n", format_args!($($arg)*)),
+        )
+    };
+}
@@ -0,0 +1,82 @@
+//! The bytes returned by `getrandom`, which `HashMap`, `rand` or `uuid` draw from.
+//!
+//! They are the ChaCha20 keystream, computed with the ChaCha precompile, of a 32-byte seed: the
+//! one registered with [`register_rng_seed`], or else the one the host stores at
+//! [`RANDOM_SEED_ADDRESS`] with `State::set_random_seed`. That one is part of the initial memory,
+//! so the image id commits to it, and the keystream is computed by the program, so the proof
+//! covers the bytes. Either way a run is deterministic, and the bytes are no secret from the
+//! prover.
+
+use core::ptr::addr_of_mut;
+
+use crate::syscalls::syscall_chacha20_block;
+
+/// Where the host stores the seed, see `zkm_emulator::memory::RANDOM_SEED_ADDRESS`.
+pub const RANDOM_SEED_ADDRESS: usize = 0x7fff_f000;
+
+/// The words of "expand 32-byte k".
+const CHACHA_CONSTANTS: [u32; 4] = [0x61707865, 0x3320646e, 0x79622d32, 0x6b206574];
+
+struct ChaChaRng {
+    /// The constants, the key, the block counter and the nonce.
+    state: [u32; 16],
+    /// The current keystream block, and the number of its bytes returned so far.
+    block: [u8; 64],
+    offset: usize,
+}
+
+impl ChaChaRng {
+    fn new(seed: &[u8; 32]) -> Self {
+        let mut state = [0u32; 16];
+        state[..4].copy_from_slice(&CHACHA_CONSTANTS);
+        for (word, bytes) in state[4..12].iter_mut().zip(seed.chunks_exact(4)) {
+            *word = u32::from_le_bytes(bytes.try_into().unwrap());
+        }
+        Self {
+            state,
+            block: [0; 64],
+            offset: 64,
+        }
+    }
+
+    fn fill_bytes(&mut self, buf: &mut [u8]) {
+        for byte in buf {
+            if self.offset == self.block.len() {
+                self.next_block();
+            }
+            *byte = self.block[self.offset];
+            self.offset += 1;
+        }
+    }
+
+    fn next_block(&mut self) {
+        let mut block = self.state;
+        syscall_chacha20_block(&mut block);
+        for (bytes, word) in self.block.chunks_exact_mut(4).zip(block) {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+        // The counter wraps after 256 GiB, which no program draws.
+        self.state[12] = self.state[12].wrapping_add(1);
+        self.offset = 0;
+    }
+}
+
+static mut RNG: Option<ChaChaRng> = None;
+
+/// Seeds the bytes returned by `getrandom` from now on with `seed`, instead of the seed of the
+/// host.
+pub fn register_rng_seed(seed: [u8; 32]) {
+    // SAFETY: Single threaded, so nothing else can touch this while we're working.
+    unsafe { *addr_of_mut!(RNG) = Some(ChaChaRng::new(&seed)) };
+}
+
+/// Fills `buf` with the next random bytes.
+pub fn fill_bytes(buf: &mut [u8]) {
+    // SAFETY: Single threaded, so nothing else can touch this while we're working.
+    let rng = unsafe { &mut *addr_of_mut!(RNG) }.get_or_insert_with(|| {
+        // SAFETY: The page is mapped, and zero if the host set no seed.
+        let seed = unsafe { core::ptr::read_volatile(RANDOM_SEED_ADDRESS as *const [u8; 32]) };
+        ChaChaRng::new(&seed)
+    });
+    rng.fill_bytes(buf);
+}
@@ -0,0 +1,32 @@
+use zkm_precompiles::BIGINT_WIDTH_WORDS;
+
+use super::syscall_uint256_mulmod;
+
+/// The `op` of `sys_bigint` for a modular multiplication, the only one supported.
+pub const BIGINT_OP_MULMOD: u32 = 0;
+
+/// Sets `result` to `x * y mod modulus`, for the bigint crates patched to use the precompiles.
+///
+/// `modulus` must not be zero.
+///
+/// # Safety
+///
+/// Make sure that all the pointers are valid and word aligned.
+#[no_mangle]
+pub unsafe extern "C" fn sys_bigint(
+    result: *mut [u32; BIGINT_WIDTH_WORDS],
+    op: u32,
+    x: *const [u32; BIGINT_WIDTH_WORDS],
+    y: *const [u32; BIGINT_WIDTH_WORDS],
+    modulus: *const [u32; BIGINT_WIDTH_WORDS],
+) {
+    assert_eq!(op, BIGINT_OP_MULMOD, "unsupported bigint operation");
+
+    // The syscall reads the modulus right after `y`.
+    let mut y_and_modulus = [0u32; 2 * BIGINT_WIDTH_WORDS];
+    y_and_modulus[..BIGINT_WIDTH_WORDS].copy_from_slice(&*y);
+    y_and_modulus[BIGINT_WIDTH_WORDS..].copy_from_slice(&*modulus);
+    // The syscall writes the result over its first operand.
+    *result = *x;
+    syscall_uint256_mulmod(result, y_and_modulus.as_ptr() as *const [u32; 8]);
+}
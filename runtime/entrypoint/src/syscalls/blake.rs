@@ -0,0 +1,48 @@
+cfg_if::cfg_if! {
+    if #[cfg(target_os = "zkvm")] {
+        use core::arch::asm;
+    }
+}
+
+/// Compresses a block into the BLAKE2b hash state at `h`, as the `F` function of EIP-152.
+///
+/// `input` holds the 16 64-bit words of the block, then the offset counter `t` as two 64-bit
+/// words, the final block flag `f`, which must be 0 or 1, and the number of rounds. The 64-bit
+/// words of `h` and `input` are stored high word first. Both must be word aligned.
+#[allow(unused_variables)]
+#[no_mangle]
+pub extern "C" fn syscall_blake2b_compress(h: *mut [u32; 16], input: *const [u32; 38]) {
+    #[cfg(target_os = "zkvm")]
+    unsafe {
+        asm!(
+            "syscall",
+            in("$2") crate::syscalls::BLAKE2B_COMPRESS,
+            in("$4") h,
+            in("$5") input,
+        );
+    }
+
+    #[cfg(not(target_os = "zkvm"))]
+    unreachable!()
+}
+
+/// Compresses a block into the BLAKE3 chaining value at `cv`.
+///
+/// `input` holds the 16 words of the block, then the low and high words of the counter, the
+/// block length in bytes and the domain flags. Both must be word aligned.
+#[allow(unused_variables)]
+#[no_mangle]
+pub extern "C" fn syscall_blake3_compress(cv: *mut [u32; 8], input: *const [u32; 20]) {
+    #[cfg(target_os = "zkvm")]
+    unsafe {
+        asm!(
+            "syscall",
+            in("$2") crate::syscalls::BLAKE3_COMPRESS,
+            in("$4") cv,
+            in("$5") input,
+        );
+    }
+
+    #[cfg(not(target_os = "zkvm"))]
+    unreachable!()
+}
@@ -0,0 +1,66 @@
+cfg_if::cfg_if! {
+    if #[cfg(target_os = "zkvm")] {
+        use core::arch::asm;
+    }
+}
+
+/// Adds the BLS12-381 base field element at `y` to the element at `x`, in place.
+///
+/// Elements are 12 little-endian words. `x` and `y` must be word aligned, and the result is
+/// reduced modulo `p`.
+#[allow(unused_variables)]
+#[no_mangle]
+pub extern "C" fn syscall_bls12381_fp_add(x: *mut [u32; 12], y: *const [u32; 12]) {
+    #[cfg(target_os = "zkvm")]
+    unsafe {
+        asm!(
+            "syscall",
+            in("$2") crate::syscalls::BLS12381_FP_ADD,
+            in("$4") x,
+            in("$5") y,
+        );
+    }
+
+    #[cfg(not(target_os = "zkvm"))]
+    unreachable!()
+}
+
+/// Subtracts the BLS12-381 base field element at `y` from the element at `x`, in place.
+///
+/// `x` and `y` must be word aligned, and the result is reduced modulo `p`.
+#[allow(unused_variables)]
+#[no_mangle]
+pub extern "C" fn syscall_bls12381_fp_sub(x: *mut [u32; 12], y: *const [u32; 12]) {
+    #[cfg(target_os = "zkvm")]
+    unsafe {
+        asm!(
+            "syscall",
+            in("$2") crate::syscalls::BLS12381_FP_SUB,
+            in("$4") x,
+            in("$5") y,
+        );
+    }
+
+    #[cfg(not(target_os = "zkvm"))]
+    unreachable!()
+}
+
+/// Multiplies the element at `x` by the BLS12-381 base field element at `y`, in place.
+///
+/// `x` and `y` must be word aligned, and the result is reduced modulo `p`.
+#[allow(unused_variables)]
+#[no_mangle]
+pub extern "C" fn syscall_bls12381_fp_mul(x: *mut [u32; 12], y: *const [u32; 12]) {
+    #[cfg(target_os = "zkvm")]
+    unsafe {
+        asm!(
+            "syscall",
+            in("$2") crate::syscalls::BLS12381_FP_MUL,
+            in("$4") x,
+            in("$5") y,
+        );
+    }
+
+    #[cfg(not(target_os = "zkvm"))]
+    unreachable!()
+}
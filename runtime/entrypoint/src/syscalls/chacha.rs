@@ -0,0 +1,25 @@
+cfg_if::cfg_if! {
+    if #[cfg(target_os = "zkvm")] {
+        use core::arch::asm;
+    }
+}
+
+/// Replaces the ChaCha20 state at `state` by its keystream block, as in RFC 8439.
+///
+/// The state is the constants, the key, the block counter and the nonce, as 16 words decoded
+/// from little-endian bytes. It must be word aligned. The block counter is not incremented.
+#[allow(unused_variables)]
+#[no_mangle]
+pub extern "C" fn syscall_chacha20_block(state: *mut [u32; 16]) {
+    #[cfg(target_os = "zkvm")]
+    unsafe {
+        asm!(
+            "syscall",
+            in("$2") crate::syscalls::CHACHA20_BLOCK,
+            in("$4") state,
+        );
+    }
+
+    #[cfg(not(target_os = "zkvm"))]
+    unreachable!()
+}
@@ -0,0 +1,28 @@
+cfg_if::cfg_if! {
+    if #[cfg(target_os = "zkvm")] {
+        use core::arch::asm;
+    }
+}
+
+/// The number of instructions executed before this syscall since the start of the program,
+/// modulo 2^32.
+///
+/// It is a hint of the prover, which the proof doesn't check: it is meant for profiling, not for
+/// the logic of the program.
+#[allow(unused_variables)]
+#[no_mangle]
+pub extern "C" fn syscall_cycle_count() -> u32 {
+    #[cfg(target_os = "zkvm")]
+    unsafe {
+        let count;
+        asm!(
+            "syscall",
+            in("$2") crate::syscalls::CYCLE_COUNT,
+            lateout("$2") count,
+        );
+        count
+    }
+
+    #[cfg(not(target_os = "zkvm"))]
+    unreachable!()
+}
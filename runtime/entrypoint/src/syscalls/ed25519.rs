@@ -0,0 +1,47 @@
+cfg_if::cfg_if! {
+    if #[cfg(target_os = "zkvm")] {
+        use core::arch::asm;
+    }
+}
+
+/// Adds the ed25519 point at `q` to the point at `p`, in place.
+///
+/// Points are the little-endian words of `x`, then those of `y`. `p` and `q` must be word aligned.
+#[allow(unused_variables)]
+#[no_mangle]
+pub extern "C" fn syscall_ed_add(p: *mut [u32; 16], q: *const [u32; 16]) {
+    #[cfg(target_os = "zkvm")]
+    unsafe {
+        asm!(
+            "syscall",
+            in("$2") crate::syscalls::ED_ADD,
+            in("$4") p,
+            in("$5") q,
+        );
+    }
+
+    #[cfg(not(target_os = "zkvm"))]
+    unreachable!()
+}
+
+/// Decompresses the ed25519 point in `point`, in place.
+///
+/// The last 32 bytes hold the compressed point: `y` in little-endian order, with the sign of `x`
+/// in the top bit. The syscall writes `x` in little-endian order to the first 32 bytes, matching
+/// the layout the patched `curve25519-dalek` expects. The syscall traps if `y` is not that of a
+/// point, or if `x` is zero and its sign is set.
+#[allow(unused_variables)]
+#[no_mangle]
+pub extern "C" fn syscall_ed_decompress(point: &mut [u8; 64]) {
+    #[cfg(target_os = "zkvm")]
+    unsafe {
+        asm!(
+            "syscall",
+            in("$2") crate::syscalls::ED_DECOMPRESS,
+            in("$4") point.as_mut_ptr(),
+        );
+    }
+
+    #[cfg(not(target_os = "zkvm"))]
+    unreachable!()
+}
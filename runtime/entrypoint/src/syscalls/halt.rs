@@ -8,7 +8,8 @@ cfg_if! {
     }
 }
 
-/// Halts the program.
+/// Halts the program. The end of the heap is passed along, for the execution report of the
+/// host.
 #[allow(unused_variables)]
 pub extern "C" fn syscall_halt(exit_code: u8) -> ! {
     #[cfg(target_os = "zkvm")]
@@ -16,7 +17,8 @@ pub extern "C" fn syscall_halt(exit_code: u8) -> ! {
         asm!(
             "syscall",
             in("$2") crate::syscalls::HALT,
-            in("$4") exit_code
+            in("$4") exit_code,
+            in("$5") crate::memory::heap_pos(),
         );
         unreachable!()
     }
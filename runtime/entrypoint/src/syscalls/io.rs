@@ -62,6 +62,48 @@ pub extern "C" fn syscall_hint_read(ptr: *mut u8, len: usize) {
     unreachable!()
 }
 
+/// Returns the length of the preimage of `key`, or `usize::MAX` if the host has none.
+#[allow(unused_variables)]
+#[no_mangle]
+pub extern "C" fn syscall_preimage_len(key: &[u8; 32]) -> usize {
+    #[cfg(target_os = "zkvm")]
+    unsafe {
+        let len;
+        asm!(
+            "syscall",
+            in("$2") crate::syscalls::PREIMAGE_LEN,
+            in("$4") key.as_ptr(),
+            lateout("$2") len,
+        );
+        len
+    }
+
+    #[cfg(not(target_os = "zkvm"))]
+    unreachable!()
+}
+
+/// Reads up to `len` bytes of the preimage of `key` to `ptr`, and returns how many were read.
+#[allow(unused_variables)]
+#[no_mangle]
+pub extern "C" fn syscall_preimage_read(key: &[u8; 32], ptr: *mut u8, len: usize) -> usize {
+    #[cfg(target_os = "zkvm")]
+    unsafe {
+        let read;
+        asm!(
+            "syscall",
+            in("$2") crate::syscalls::PREIMAGE_READ,
+            in("$4") key.as_ptr(),
+            in("$5") ptr,
+            in("$6") len,
+            lateout("$2") read,
+        );
+        read
+    }
+
+    #[cfg(not(target_os = "zkvm"))]
+    unreachable!()
+}
+
 #[allow(unused_variables)]
 #[no_mangle]
 pub extern "C" fn syscall_verify(claim_digest: &[u8; 32], control_root: &[u8; 32]) {
@@ -0,0 +1,27 @@
+cfg_if::cfg_if! {
+    if #[cfg(target_os = "zkvm")] {
+        use core::arch::asm;
+    }
+}
+
+/// Hashes the `len` bytes at `input` with Keccak-256, and writes the digest to `out`.
+///
+/// `input` must be word aligned, and the bytes of its last word after the input must hold the
+/// padding of the final block: `zkm_precompiles::keccak::keccak256` takes care of both.
+#[allow(unused_variables)]
+#[no_mangle]
+pub extern "C" fn syscall_keccak256(input: *const u32, len: usize, out: *mut [u32; 8]) {
+    #[cfg(target_os = "zkvm")]
+    unsafe {
+        asm!(
+            "syscall",
+            in("$2") crate::syscalls::KECCAK256,
+            in("$4") input,
+            in("$5") len,
+            in("$6") out,
+        );
+    }
+
+    #[cfg(not(target_os = "zkvm"))]
+    unreachable!()
+}
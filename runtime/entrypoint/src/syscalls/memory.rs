@@ -12,40 +12,36 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-const SYSTEM_START: usize = 0x8000_0000;
+cfg_if::cfg_if! {
+    if #[cfg(target_os = "zkvm")] {
+        use core::arch::asm;
+    }
+}
 
 #[allow(clippy::missing_safety_doc)]
 #[no_mangle]
 pub unsafe extern "C" fn sys_alloc_aligned(bytes: usize, align: usize) -> *mut u8 {
-    extern "C" {
-        // https://lld.llvm.org/ELF/linker_script.html#sections-command
-        static _end: u8;
-    }
-
-    // Pointer to next heap address to use, or 0 if the heap has not yet been
-    // initialized.
-    static mut HEAP_POS: usize = 0;
-
-    // SAFETY: Single threaded, so nothing else can touch this while we're working.
-    let mut heap_pos = unsafe { HEAP_POS };
-
-    if heap_pos == 0 {
-        heap_pos = unsafe { (&_end) as *const u8 as usize };
-    }
-
-    let offset = heap_pos & (align - 1);
-    if offset != 0 {
-        heap_pos += align - offset;
-    }
-
-    let ptr = heap_pos as *mut u8;
-    heap_pos += bytes;
+    crate::memory::bump_alloc(bytes, align)
+}
 
-    // Check to make sure heap doesn't collide with SYSTEM memory.
-    if SYSTEM_START < heap_pos {
-        panic!();
+/// Maps `size` bytes of zeroed memory at the top of the heap, rounded up to whole pages, and
+/// returns its address, or `usize::MAX` on failure.
+#[allow(unused_variables)]
+#[no_mangle]
+pub extern "C" fn syscall_mmap(size: usize) -> *mut u8 {
+    #[cfg(target_os = "zkvm")]
+    unsafe {
+        let ptr;
+        asm!(
+            "syscall",
+            in("$2") crate::syscalls::MMAP,
+            in("$4") 0,
+            in("$5") size,
+            lateout("$2") ptr,
+        );
+        ptr
     }
 
-    unsafe { HEAP_POS = heap_pos };
-    ptr
+    #[cfg(not(target_os = "zkvm"))]
+    unreachable!()
 }
@@ -1,14 +1,40 @@
 //! Ported from Entrypoint for SP1 zkVM.
 
+mod bigint;
+mod blake;
+mod bls12_381;
+mod bn254;
+mod chacha;
+mod cycles;
+mod ed25519;
 mod halt;
 mod io;
+mod keccak;
 mod memory;
+mod modexp;
+mod secp256k1;
+mod secp256r1;
+mod sha256;
 mod sys;
+mod uint256;
 
+pub use bigint::*;
+pub use blake::*;
+pub use bls12_381::*;
+pub use bn254::*;
+pub use chacha::*;
+pub use cycles::*;
+pub use ed25519::*;
 pub use halt::*;
 pub use io::*;
+pub use keccak::*;
 pub use memory::*;
+pub use modexp::*;
+pub use secp256k1::*;
+pub use secp256r1::*;
+pub use sha256::*;
 pub use sys::*;
+pub use uint256::*;
 
 /// These codes MUST match the codes in `core/src/runtime/syscall.rs`. There is a derived test
 /// that checks that the enum is consistent with the syscalls.
@@ -19,6 +45,9 @@ pub const HALT: u32 = 4246u32;
 /// Writes to a file descriptor. Currently only used for `STDOUT/STDERR`.
 pub const WRITE: u32 = 4004u32;
 
+/// Maps memory. Only used to grow the heap.
+pub const MMAP: u32 = 4090u32;
+
 /// Executes `HINT_LEN`.
 pub const HINT_LEN: u32 = 0x00_00_00_F0;
 
@@ -27,3 +56,69 @@ pub const HINT_READ: u32 = 0x00_00_00_F1;
 
 /// Executes `HINT_READ`.
 pub const VERIFY: u32 = 0x00_00_00_F2;
+
+/// Executes `PREIMAGE_LEN`.
+pub const PREIMAGE_LEN: u32 = 0x00_00_00_F3;
+
+/// Executes `PREIMAGE_READ`.
+pub const PREIMAGE_READ: u32 = 0x00_00_00_F4;
+
+/// Executes `CYCLE_COUNT`.
+pub const CYCLE_COUNT: u32 = 0x00_00_00_F5;
+
+/// Executes `KECCAK256`.
+pub const KECCAK256: u32 = 0x00_01_01_09;
+
+/// Executes `SHA_EXTEND`.
+pub const SHA_EXTEND: u32 = 0x00_30_01_05;
+
+/// Executes `SHA_COMPRESS`.
+pub const SHA_COMPRESS: u32 = 0x00_01_01_06;
+
+/// Executes `SECP256K1_ADD`.
+pub const SECP256K1_ADD: u32 = 0x00_01_01_0A;
+
+/// Executes `SECP256K1_DOUBLE`.
+pub const SECP256K1_DOUBLE: u32 = 0x00_00_01_0B;
+
+/// Executes `ED_ADD`.
+pub const ED_ADD: u32 = 0x00_01_01_07;
+
+/// Executes `ED_DECOMPRESS`.
+pub const ED_DECOMPRESS: u32 = 0x00_00_01_08;
+
+/// Executes `BN254_ADD`.
+pub const BN254_ADD: u32 = 0x00_01_01_0E;
+
+/// Executes `BN254_DOUBLE`.
+pub const BN254_DOUBLE: u32 = 0x00_00_01_0F;
+
+/// Executes `BLS12381_FP_ADD`.
+pub const BLS12381_FP_ADD: u32 = 0x00_01_01_71;
+
+/// Executes `BLS12381_FP_SUB`.
+pub const BLS12381_FP_SUB: u32 = 0x00_01_01_72;
+
+/// Executes `BLS12381_FP_MUL`.
+pub const BLS12381_FP_MUL: u32 = 0x00_01_01_73;
+
+/// Executes `UINT256_MULMOD`.
+pub const UINT256_MULMOD: u32 = 0x00_01_01_1D;
+
+/// Executes `BLAKE2B_COMPRESS`.
+pub const BLAKE2B_COMPRESS: u32 = 0x00_01_01_1E;
+
+/// Executes `BLAKE3_COMPRESS`.
+pub const BLAKE3_COMPRESS: u32 = 0x00_01_01_1F;
+
+/// Executes `CHACHA20_BLOCK`.
+pub const CHACHA20_BLOCK: u32 = 0x00_01_01_20;
+
+/// Executes `SECP256R1_ADD`.
+pub const SECP256R1_ADD: u32 = 0x00_01_01_2C;
+
+/// Executes `SECP256R1_DOUBLE`.
+pub const SECP256R1_DOUBLE: u32 = 0x00_00_01_2D;
+
+/// Executes `MODEXP`.
+pub const MODEXP: u32 = 0x00_01_01_2E;
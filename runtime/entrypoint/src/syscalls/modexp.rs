@@ -0,0 +1,29 @@
+cfg_if::cfg_if! {
+    if #[cfg(target_os = "zkvm")] {
+        use core::arch::asm;
+    }
+}
+
+/// Raises the integer at `base` to the power of the exponent in `params`, modulo the modulus in
+/// them, in place.
+///
+/// `params` is the number of words of the base and of the modulus, at most 64, then of the
+/// exponent, at most 256, then the modulus and the exponent. Integers are little-endian words
+/// and need not be reduced. Both pointers must be word aligned, neither length zero, and the
+/// modulus must not be zero. The result is reduced, with as many words as the modulus.
+#[allow(unused_variables)]
+#[no_mangle]
+pub extern "C" fn syscall_modexp(base: *mut u32, params: *const u32) {
+    #[cfg(target_os = "zkvm")]
+    unsafe {
+        asm!(
+            "syscall",
+            in("$2") crate::syscalls::MODEXP,
+            in("$4") base,
+            in("$5") params,
+        );
+    }
+
+    #[cfg(not(target_os = "zkvm"))]
+    unreachable!()
+}
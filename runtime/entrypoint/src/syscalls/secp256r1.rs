@@ -0,0 +1,45 @@
+cfg_if::cfg_if! {
+    if #[cfg(target_os = "zkvm")] {
+        use core::arch::asm;
+    }
+}
+
+/// Adds the secp256r1 point at `q` to the point at `p`, in place.
+///
+/// Points are the little-endian words of `x`, then those of `y`. `p` and `q` must be word aligned
+/// and have different `x`.
+#[allow(unused_variables)]
+#[no_mangle]
+pub extern "C" fn syscall_secp256r1_add(p: *mut [u32; 16], q: *const [u32; 16]) {
+    #[cfg(target_os = "zkvm")]
+    unsafe {
+        asm!(
+            "syscall",
+            in("$2") crate::syscalls::SECP256R1_ADD,
+            in("$4") p,
+            in("$5") q,
+        );
+    }
+
+    #[cfg(not(target_os = "zkvm"))]
+    unreachable!()
+}
+
+/// Doubles the secp256r1 point at `p`, in place.
+///
+/// `p` must be word aligned.
+#[allow(unused_variables)]
+#[no_mangle]
+pub extern "C" fn syscall_secp256r1_double(p: *mut [u32; 16]) {
+    #[cfg(target_os = "zkvm")]
+    unsafe {
+        asm!(
+            "syscall",
+            in("$2") crate::syscalls::SECP256R1_DOUBLE,
+            in("$4") p,
+        );
+    }
+
+    #[cfg(not(target_os = "zkvm"))]
+    unreachable!()
+}
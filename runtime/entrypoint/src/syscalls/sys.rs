@@ -1,40 +1,21 @@
 //! Ported from Entrypoint for SP1 zkVM.
 
-use std::sync::Mutex;
-
-use lazy_static::lazy_static;
-use rand::{rngs::StdRng, Rng, SeedableRng};
-
-use crate::syscalls::{syscall_halt, syscall_write};
-
-/// The random number generator seed for the zkVM.
-///
-/// In the future, we can pass in this seed from the host or have the verifier generate it.
-const PRNG_SEED: u64 = 0x123456789abcdef0;
-
-lazy_static! {
-    /// A lazy static to generate a global random number generator.
-    static ref RNG: Mutex<StdRng> = Mutex::new(StdRng::seed_from_u64(PRNG_SEED));
+cfg_if::cfg_if! {
+    if #[cfg(target_os = "zkvm")] {
+        use core::arch::asm;
+    }
 }
 
-/// A lazy static to print a warning once for using the `sys_rand` system call.
-static SYS_RAND_WARNING: std::sync::Once = std::sync::Once::new();
+use crate::syscalls::{syscall_halt, syscall_write};
 
-/// Generates random bytes.
+/// Generates random bytes, see [`crate::rng`].
 ///
 /// # Safety
 ///
 /// Make sure that `buf` has at least `nwords` words.
 #[no_mangle]
 pub unsafe extern "C" fn sys_rand(recv_buf: *mut u8, words: usize) {
-    SYS_RAND_WARNING.call_once(|| {
-        println!("WARNING: Using insecure random number generator.");
-    });
-    let mut rng = RNG.lock().unwrap();
-    for i in 0..words {
-        let element = recv_buf.add(i);
-        *element = rng.gen();
-    }
+    crate::rng::fill_bytes(core::slice::from_raw_parts_mut(recv_buf, words));
 }
 
 #[allow(clippy::missing_safety_doc)]
@@ -0,0 +1,27 @@
+cfg_if::cfg_if! {
+    if #[cfg(target_os = "zkvm")] {
+        use core::arch::asm;
+    }
+}
+
+/// Multiplies the 256-bit integer at `x` by the integer at `y`, modulo the integer right after
+/// `y`, in place.
+///
+/// Integers are 8 little-endian words. `x` and `y` must be word aligned, the modulus must not be
+/// zero, and the result is reduced.
+#[allow(unused_variables)]
+#[no_mangle]
+pub extern "C" fn syscall_uint256_mulmod(x: *mut [u32; 8], y: *const [u32; 8]) {
+    #[cfg(target_os = "zkvm")]
+    unsafe {
+        asm!(
+            "syscall",
+            in("$2") crate::syscalls::UINT256_MULMOD,
+            in("$4") x,
+            in("$5") y,
+        );
+    }
+
+    #[cfg(not(target_os = "zkvm"))]
+    unreachable!()
+}
@@ -0,0 +1,669 @@
+//! Arithmetic on BLS12-381, backed by the BLS12-381 base field precompiles.
+//!
+//! The precompiles add, subtract and multiply elements of `Fp`. Inversion, the quadratic
+//! extension `Fp2 = Fp[u] / (u^2 + 1)` and the G1 and G2 groups are built on top of them here,
+//! as well as the tower `Fp12 = Fp6[w] / (w^2 - v)`, `Fp6 = Fp2[v] / (v^3 - (1 + u))` and the
+//! optimal ate pairing.
+//!
+//! Compiled for another target than the zkVM, as when the program is tested on the host, the
+//! base field operations are computed in software instead.
+
+use core::cmp::Ordering;
+
+use cfg_if::cfg_if;
+
+cfg_if! {
+    if #[cfg(target_os = "zkvm")] {
+        use crate::{syscall_bls12381_fp_add, syscall_bls12381_fp_mul, syscall_bls12381_fp_sub};
+    } else {
+        use num_bigint::BigUint;
+    }
+}
+
+/// `p`, the modulus of the base field.
+const P: [u32; 12] = [
+    0xffffaaab, 0xb9feffff, 0xb153ffff, 0x1eabfffe, 0xf6b0f624, 0x6730d2a0, 0xf38512bf, 0x64774b84,
+    0x434bacd7, 0x4b1ba7b6, 0x397fe69a, 0x1a0111ea,
+];
+
+/// `p - 2`, the exponent inverting an element of `Fp`.
+const P_MINUS_2: [u32; 12] = [
+    0xffffaaa9, 0xb9feffff, 0xb153ffff, 0x1eabfffe, 0xf6b0f624, 0x6730d2a0, 0xf38512bf, 0x64774b84,
+    0x434bacd7, 0x4b1ba7b6, 0x397fe69a, 0x1a0111ea,
+];
+
+/// `(p + 1) / 4`, the exponent of a square root in `Fp`, as `p = 3 mod 4`.
+const P_PLUS_1_DIV_4: [u32; 12] = [
+    0xffffeaab, 0xee7fbfff, 0xac54ffff, 0x07aaffff, 0x3dac3d89, 0xd9cc34a8, 0x3ce144af, 0xd91dd2e1,
+    0x90d2eb35, 0x92c6e9ed, 0x8e5ff9a6, 0x0680447a,
+];
+
+/// `r`, the order of G1 and G2.
+pub const R: [u32; 8] = [
+    0x00000001, 0xffffffff, 0xfffe5bfe, 0x53bda402, 0x09a1d805, 0x3339d808, 0x299d7d48, 0x73eda753,
+];
+
+/// `-x`, where `x` is the parameter of the curve, which is negative.
+const X_ABS: u64 = 0xd201_0000_0001_0000;
+
+/// `(p^6 + 1) / r`, the exponent of the final exponentiation after `p^6 - 1`.
+const FINAL_EXP: [u32; 64] = [
+    0xc0705d6a, 0x8739e1cd, 0xe0381a16, 0x09a5256d, 0x61c791e2, 0x9cf0f70a, 0x7903f76e, 0x3a09c449,
+    0x3890f133, 0x2d727156, 0x6fec7760, 0x224741b3, 0x2a12bd40, 0x338259c2, 0x778e0de7, 0x38ee1cd4,
+    0x188a20b0, 0xc3b5ef4b, 0xe2764d7b, 0x1d615d49, 0xd076117d, 0x816101dd, 0x7ebe3afc, 0xf007c01e,
+    0x935021c3, 0x27d7bd90, 0x57c0b15f, 0xc3b5e2f5, 0xc4f82384, 0x5e886c94, 0x11e63f56, 0xee6a95db,
+    0x4a9c4f6f, 0x2b822f51, 0xd21b73da, 0x12d6a874, 0xf499dffb, 0x1304275e, 0xbcb95d1f, 0x967878fe,
+    0x8b2f2922, 0x4744497f, 0xf0841855, 0x85a2e707, 0x6c802eec, 0x9f0c5012, 0xbd2fa489, 0xfb46e197,
+    0x9bc5f61a, 0x548ce080, 0x73beaa8c, 0xcf56fb15, 0x763bdf7c, 0xad7375a3, 0x179bdecc, 0xe0ec9031,
+    0x3c48c1da, 0x6579aea8, 0x64cf5bb3, 0xdbf85ae6, 0x55ca7566, 0x7b6f235c, 0x14877503, 0x000028b3,
+];
+
+/// The field operations the curve formulas need, implemented for `Fp` and `Fp2`.
+pub trait FieldOps: Copy + PartialEq {
+    const ZERO: Self;
+    const ONE: Self;
+    /// The `b` of the curve `y^2 = x^3 + b` over this field.
+    const B: Self;
+
+    fn add(&self, other: &Self) -> Self;
+    fn sub(&self, other: &Self) -> Self;
+    fn mul(&self, other: &Self) -> Self;
+    /// The inverse, or `None` for zero.
+    fn inverse(&self) -> Option<Self>;
+
+    fn square(&self) -> Self {
+        self.mul(self)
+    }
+
+    fn double(&self) -> Self {
+        self.add(self)
+    }
+
+    fn neg(&self) -> Self {
+        Self::ZERO.sub(self)
+    }
+}
+
+/// An element of the base field, as 12 little-endian words, reduced modulo `p`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Fp(pub [u32; 12]);
+
+impl Fp {
+    /// Raises the element to the power of the little-endian words `exp`.
+    pub fn pow(&self, exp: &[u32]) -> Self {
+        let mut result = Self::ONE;
+        for word in exp.iter().rev() {
+            for i in (0..32).rev() {
+                result = result.square();
+                if (word >> i) & 1 == 1 {
+                    result = result.mul(self);
+                }
+            }
+        }
+        result
+    }
+
+    /// Decodes a big-endian element, or `None` if it is not reduced.
+    pub fn from_bytes_be(bytes: &[u8; 48]) -> Option<Self> {
+        let mut words = [0u32; 12];
+        for (word, chunk) in words.iter_mut().rev().zip(bytes.chunks_exact(4)) {
+            *word = u32::from_be_bytes(chunk.try_into().unwrap());
+        }
+        (cmp_words(&words, &P) == Ordering::Less).then_some(Fp(words))
+    }
+
+    /// A square root, or `None` if the element is not a square.
+    pub fn sqrt(&self) -> Option<Self> {
+        let root = self.pow(&P_PLUS_1_DIV_4);
+        (root.square() == *self).then_some(root)
+    }
+
+    /// Whether the element is greater than its opposite, which picks the sign of a point.
+    pub fn is_lexicographically_largest(&self) -> bool {
+        cmp_words(&self.0, &self.neg().0) == Ordering::Greater
+    }
+}
+
+#[cfg(not(target_os = "zkvm"))]
+impl Fp {
+    fn to_biguint(self) -> BigUint {
+        BigUint::from_slice(&self.0)
+    }
+
+    /// The element `n` modulo `p`.
+    fn reduce(n: BigUint) -> Self {
+        let mut words = [0u32; 12];
+        let digits = (n % BigUint::from_slice(&P)).to_u32_digits();
+        words[..digits.len()].copy_from_slice(&digits);
+        Fp(words)
+    }
+}
+
+/// Compares integers of little-endian words.
+fn cmp_words(a: &[u32], b: &[u32]) -> Ordering {
+    a.iter().rev().cmp(b.iter().rev())
+}
+
+impl FieldOps for Fp {
+    const ZERO: Self = Fp([0; 12]);
+    const ONE: Self = Fp([1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    const B: Self = Fp([4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+    fn add(&self, other: &Self) -> Self {
+        cfg_if! {
+            if #[cfg(target_os = "zkvm")] {
+                let mut result = self.0;
+                unsafe {
+                    syscall_bls12381_fp_add(&mut result, &other.0);
+                }
+                Fp(result)
+            } else {
+                Fp::reduce(self.to_biguint() + other.to_biguint())
+            }
+        }
+    }
+
+    fn sub(&self, other: &Self) -> Self {
+        cfg_if! {
+            if #[cfg(target_os = "zkvm")] {
+                let mut result = self.0;
+                unsafe {
+                    syscall_bls12381_fp_sub(&mut result, &other.0);
+                }
+                Fp(result)
+            } else {
+                Fp::reduce(self.to_biguint() + BigUint::from_slice(&P) - other.to_biguint())
+            }
+        }
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        cfg_if! {
+            if #[cfg(target_os = "zkvm")] {
+                let mut result = self.0;
+                unsafe {
+                    syscall_bls12381_fp_mul(&mut result, &other.0);
+                }
+                Fp(result)
+            } else {
+                Fp::reduce(self.to_biguint() * other.to_biguint())
+            }
+        }
+    }
+
+    fn inverse(&self) -> Option<Self> {
+        (*self != Self::ZERO).then(|| self.pow(&P_MINUS_2))
+    }
+}
+
+/// An element `c0 + c1 * u` of the quadratic extension, where `u^2 = -1`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Fp2 {
+    pub c0: Fp,
+    pub c1: Fp,
+}
+
+impl FieldOps for Fp2 {
+    const ZERO: Self = Fp2 {
+        c0: Fp::ZERO,
+        c1: Fp::ZERO,
+    };
+    const ONE: Self = Fp2 {
+        c0: Fp::ONE,
+        c1: Fp::ZERO,
+    };
+    const B: Self = Fp2 {
+        c0: Fp::B,
+        c1: Fp::B,
+    };
+
+    fn add(&self, other: &Self) -> Self {
+        Fp2 {
+            c0: self.c0.add(&other.c0),
+            c1: self.c1.add(&other.c1),
+        }
+    }
+
+    fn sub(&self, other: &Self) -> Self {
+        Fp2 {
+            c0: self.c0.sub(&other.c0),
+            c1: self.c1.sub(&other.c1),
+        }
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        Fp2 {
+            c0: self.c0.mul(&other.c0).sub(&self.c1.mul(&other.c1)),
+            c1: self.c0.mul(&other.c1).add(&self.c1.mul(&other.c0)),
+        }
+    }
+
+    fn inverse(&self) -> Option<Self> {
+        // (c0 + c1 * u)^-1 = (c0 - c1 * u) / (c0^2 + c1^2)
+        let norm_inv = self.c0.square().add(&self.c1.square()).inverse()?;
+        Some(Fp2 {
+            c0: self.c0.mul(&norm_inv),
+            c1: self.c1.neg().mul(&norm_inv),
+        })
+    }
+}
+
+impl Fp2 {
+    /// Multiplies by `1 + u`, the non-residue defining `Fp6`.
+    fn mul_by_nonresidue(&self) -> Self {
+        Fp2 {
+            c0: self.c0.sub(&self.c1),
+            c1: self.c0.add(&self.c1),
+        }
+    }
+
+    /// Multiplies by an element of `Fp`.
+    fn scale(&self, c: &Fp) -> Self {
+        Fp2 {
+            c0: self.c0.mul(c),
+            c1: self.c1.mul(c),
+        }
+    }
+}
+
+/// An element `c0 + c1 * v + c2 * v^2` of the sextic extension, where `v^3 = 1 + u`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Fp6 {
+    pub c0: Fp2,
+    pub c1: Fp2,
+    pub c2: Fp2,
+}
+
+impl Fp6 {
+    pub const ZERO: Self = Fp6 {
+        c0: Fp2::ZERO,
+        c1: Fp2::ZERO,
+        c2: Fp2::ZERO,
+    };
+    pub const ONE: Self = Fp6 {
+        c0: Fp2::ONE,
+        c1: Fp2::ZERO,
+        c2: Fp2::ZERO,
+    };
+
+    pub fn add(&self, other: &Self) -> Self {
+        Fp6 {
+            c0: self.c0.add(&other.c0),
+            c1: self.c1.add(&other.c1),
+            c2: self.c2.add(&other.c2),
+        }
+    }
+
+    pub fn sub(&self, other: &Self) -> Self {
+        Fp6 {
+            c0: self.c0.sub(&other.c0),
+            c1: self.c1.sub(&other.c1),
+            c2: self.c2.sub(&other.c2),
+        }
+    }
+
+    pub fn neg(&self) -> Self {
+        Self::ZERO.sub(self)
+    }
+
+    pub fn mul(&self, other: &Self) -> Self {
+        let (a, b) = (self, other);
+        Fp6 {
+            c0: a
+                .c0
+                .mul(&b.c0)
+                .add(&a.c1.mul(&b.c2).add(&a.c2.mul(&b.c1)).mul_by_nonresidue()),
+            c1: a
+                .c0
+                .mul(&b.c1)
+                .add(&a.c1.mul(&b.c0))
+                .add(&a.c2.mul(&b.c2).mul_by_nonresidue()),
+            c2: a.c0.mul(&b.c2).add(&a.c1.mul(&b.c1)).add(&a.c2.mul(&b.c0)),
+        }
+    }
+
+    /// Multiplies by `v`.
+    fn mul_by_v(&self) -> Self {
+        Fp6 {
+            c0: self.c2.mul_by_nonresidue(),
+            c1: self.c0,
+            c2: self.c1,
+        }
+    }
+
+    /// The inverse, or `None` for zero.
+    pub fn inverse(&self) -> Option<Self> {
+        let t0 = self
+            .c0
+            .square()
+            .sub(&self.c1.mul(&self.c2).mul_by_nonresidue());
+        let t1 = self
+            .c2
+            .square()
+            .mul_by_nonresidue()
+            .sub(&self.c0.mul(&self.c1));
+        let t2 = self.c1.square().sub(&self.c0.mul(&self.c2));
+        let norm = self
+            .c0
+            .mul(&t0)
+            .add(&self.c2.mul(&t1).add(&self.c1.mul(&t2)).mul_by_nonresidue());
+        let norm_inv = norm.inverse()?;
+        Some(Fp6 {
+            c0: t0.mul(&norm_inv),
+            c1: t1.mul(&norm_inv),
+            c2: t2.mul(&norm_inv),
+        })
+    }
+}
+
+/// An element `c0 + c1 * w` of the twelfth-degree extension, where `w^2 = v`, in which the
+/// pairing takes its values.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Fp12 {
+    pub c0: Fp6,
+    pub c1: Fp6,
+}
+
+impl Fp12 {
+    pub const ONE: Self = Fp12 {
+        c0: Fp6::ONE,
+        c1: Fp6::ZERO,
+    };
+
+    pub fn mul(&self, other: &Self) -> Self {
+        Fp12 {
+            c0: self
+                .c0
+                .mul(&other.c0)
+                .add(&self.c1.mul(&other.c1).mul_by_v()),
+            c1: self.c0.mul(&other.c1).add(&self.c1.mul(&other.c0)),
+        }
+    }
+
+    pub fn square(&self) -> Self {
+        self.mul(self)
+    }
+
+    /// The conjugate `c0 - c1 * w`, which is `self^(p^6)`.
+    pub fn conjugate(&self) -> Self {
+        Fp12 {
+            c0: self.c0,
+            c1: self.c1.neg(),
+        }
+    }
+
+    /// The inverse, or `None` for zero.
+    pub fn inverse(&self) -> Option<Self> {
+        // (c0 + c1 * w)^-1 = (c0 - c1 * w) / (c0^2 - c1^2 * v)
+        let norm = self.c0.mul(&self.c0).sub(&self.c1.mul(&self.c1).mul_by_v());
+        let norm_inv = norm.inverse()?;
+        Some(Fp12 {
+            c0: self.c0.mul(&norm_inv),
+            c1: self.c1.mul(&norm_inv).neg(),
+        })
+    }
+
+    /// Raises the element to the power of the little-endian words `exp`.
+    pub fn pow(&self, exp: &[u32]) -> Self {
+        let mut result = Self::ONE;
+        for word in exp.iter().rev() {
+            for i in (0..32).rev() {
+                result = result.square();
+                if (word >> i) & 1 == 1 {
+                    result = result.mul(self);
+                }
+            }
+        }
+        result
+    }
+}
+
+/// A point of the curve `y^2 = x^3 + F::B` in affine coordinates, `None` being the point at
+/// infinity.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Affine<F: FieldOps>(pub Option<(F, F)>);
+
+/// A point of G1, over `Fp`.
+pub type G1Affine = Affine<Fp>;
+
+/// A point of G2, over `Fp2`.
+pub type G2Affine = Affine<Fp2>;
+
+impl<F: FieldOps> Affine<F> {
+    pub const IDENTITY: Self = Affine(None);
+
+    pub fn new(x: F, y: F) -> Self {
+        Affine(Some((x, y)))
+    }
+
+    pub fn is_on_curve(&self) -> bool {
+        match self.0 {
+            None => true,
+            Some((x, y)) => y.square() == x.square().mul(&x).add(&F::B),
+        }
+    }
+
+    pub fn neg(&self) -> Self {
+        Affine(self.0.map(|(x, y)| (x, y.neg())))
+    }
+
+    pub fn add(&self, other: &Self) -> Self {
+        let ((x1, y1), (x2, y2)) = match (self.0, other.0) {
+            (None, _) => return *other,
+            (_, None) => return *self,
+            (Some(p), Some(q)) => (p, q),
+        };
+        if x1 == x2 {
+            return if y1 == y2 {
+                self.double()
+            } else {
+                Self::IDENTITY
+            };
+        }
+        let slope = y2.sub(&y1).mul(&x2.sub(&x1).inverse().unwrap());
+        Self::from_slope(slope, x1, y1, x2)
+    }
+
+    pub fn double(&self) -> Self {
+        let (x, y) = match self.0 {
+            Some((x, y)) if y != F::ZERO => (x, y),
+            _ => return Self::IDENTITY,
+        };
+        let x_squared = x.square();
+        let slope = x_squared
+            .double()
+            .add(&x_squared)
+            .mul(&y.double().inverse().unwrap());
+        Self::from_slope(slope, x, y, x)
+    }
+
+    /// Multiplies the point by the scalar of little-endian words `scalar`.
+    pub fn mul(&self, scalar: &[u32]) -> Self {
+        let mut result = Self::IDENTITY;
+        for word in scalar.iter().rev() {
+            for i in (0..32).rev() {
+                result = result.double();
+                if (word >> i) & 1 == 1 {
+                    result = result.add(self);
+                }
+            }
+        }
+        result
+    }
+
+    /// The third point of the line of slope `slope` through `(x1, y1)` and `(x2, _)`, reflected.
+    fn from_slope(slope: F, x1: F, y1: F, x2: F) -> Self {
+        let x3 = slope.square().sub(&x1).sub(&x2);
+        let y3 = slope.mul(&x1.sub(&x3)).sub(&y1);
+        Self::new(x3, y3)
+    }
+}
+
+impl G1Affine {
+    /// Decodes a point in the compressed form of the ZCash serialization: `x` in big endian,
+    /// with the compression flag, the infinity flag and the sign of `y` in its three top bits.
+    /// `None` if the encoding is invalid or the point is not on the curve. The point may not be
+    /// in G1, see `is_torsion_free`.
+    pub fn from_compressed(bytes: &[u8; 48]) -> Option<Self> {
+        let (compressed, infinity, sign) =
+            (bytes[0] >> 7, (bytes[0] >> 6) & 1, (bytes[0] >> 5) & 1);
+        if compressed == 0 {
+            return None;
+        }
+        if infinity == 1 {
+            let is_zero = sign == 0 && bytes[0] & 0x1f == 0 && bytes[1..].iter().all(|&b| b == 0);
+            return is_zero.then_some(Self::IDENTITY);
+        }
+        let mut x_bytes = *bytes;
+        x_bytes[0] &= 0x1f;
+        let x = Fp::from_bytes_be(&x_bytes)?;
+        let y = x.square().mul(&x).add(&Fp::B).sqrt()?;
+        let y = if y.is_lexicographically_largest() == (sign == 1) {
+            y
+        } else {
+            y.neg()
+        };
+        Some(Self::new(x, y))
+    }
+
+    /// Whether the point is in G1, the subgroup of order `r`.
+    pub fn is_torsion_free(&self) -> bool {
+        self.mul(&R) == Self::IDENTITY
+    }
+
+    /// The standard generator of G1.
+    pub const GENERATOR: Self = Affine(Some((
+        Fp([
+            0xdb22c6bb, 0xfb3af00a, 0xf97a1aef, 0x6c55e83f, 0x171bac58, 0xa14e3a3f, 0x9774b905,
+            0xc3688c4f, 0x4fa9ac0f, 0x2695638c, 0x3197d794, 0x17f1d3a7,
+        ]),
+        Fp([
+            0x46c5e7e1, 0x0caa2329, 0xa2888ae4, 0xd03cc744, 0x2c04b3ed, 0x00db18cb, 0xd5d00af6,
+            0xfcf5e095, 0x741d8ae4, 0xa09e30ed, 0xe3aaa0f1, 0x08b3f481,
+        ]),
+    )));
+}
+
+impl G2Affine {
+    /// The standard generator of G2.
+    pub const GENERATOR: Self = Affine(Some((
+        Fp2 {
+            c0: Fp([
+                0xc121bdb8, 0xd48056c8, 0xa805bbef, 0x0bac0326, 0x7ae3d177, 0xb4510b64, 0xfa403b02,
+                0xc6e47ad4, 0x2dc51051, 0x26080527, 0xf08f0a91, 0x024aa2b2,
+            ]),
+            c1: Fp([
+                0x5d042b7e, 0xe5ac7d05, 0x13945d57, 0x334cf112, 0xdc7f5049, 0xb5da61bb, 0x9920b61a,
+                0x596bd0d0, 0x88274f65, 0x7dacd3a0, 0x52719f60, 0x13e02b60,
+            ]),
+        },
+        Fp2 {
+            c0: Fp([
+                0x08b82801, 0xe1935486, 0x3baca289, 0x923ac9cc, 0x5160d12c, 0x6d429a69, 0x8cbdd3a7,
+                0xadfd9baa, 0xda2e351a, 0x8cc9cdc6, 0x727d6e11, 0x0ce5d527,
+            ]),
+            c1: Fp([
+                0xf05f79be, 0xaaa9075f, 0x5cec1da1, 0x3f370d27, 0x572e99ab, 0x267492ab, 0x85a763af,
+                0xcb3e287e, 0x2bc28b99, 0x32acd2b0, 0x2ea734cc, 0x0606c4a0,
+            ]),
+        },
+    )));
+}
+
+impl G2Affine {
+    /// The line through `t` of slope `slope`, evaluated at `(xp, yp)` once the twist is undone
+    /// and multiplied by `w^4`, which the final exponentiation cancels.
+    fn line(slope: &Fp2, t: (Fp2, Fp2), xp: &Fp, yp: &Fp) -> Fp12 {
+        let (xt, yt) = t;
+        Fp12 {
+            c0: Fp6 {
+                c0: Fp2::ZERO,
+                c1: Fp2::ZERO,
+                c2: Fp2 {
+                    c0: *yp,
+                    c1: Fp::ZERO,
+                },
+            },
+            c1: Fp6 {
+                c0: slope.mul(&xt).sub(&yt),
+                c1: slope.scale(xp).neg(),
+                c2: Fp2::ZERO,
+            },
+        }
+    }
+}
+
+/// The Miller loop of the optimal ate pairing of `p` and `q`, which must be in G1 and G2.
+fn miller_loop(p: &G1Affine, q: &G2Affine) -> Fp12 {
+    let (Some((xp, yp)), Some((xq, yq))) = (p.0, q.0) else {
+        return Fp12::ONE;
+    };
+    let mut f = Fp12::ONE;
+    let mut t = (xq, yq);
+    // The multiples of `q` below `r` are neither `q`, `-q` nor of order 2.
+    for i in (0..63).rev() {
+        let (xt, yt) = t;
+        let x_squared = xt.square();
+        let slope = x_squared
+            .double()
+            .add(&x_squared)
+            .mul(&yt.double().inverse().unwrap());
+        f = f.square().mul(&G2Affine::line(&slope, t, &xp, &yp));
+        t = G2Affine::from_slope(slope, xt, yt, xt).0.unwrap();
+
+        if (X_ABS >> i) & 1 == 1 {
+            let (xt, yt) = t;
+            let slope = yq.sub(&yt).mul(&xq.sub(&xt).inverse().unwrap());
+            f = f.mul(&G2Affine::line(&slope, t, &xp, &yp));
+            t = G2Affine::from_slope(slope, xt, yt, xq).0.unwrap();
+        }
+    }
+    // `x` is negative.
+    f.conjugate()
+}
+
+/// Whether the product of the pairings of the pairs is 1. The points must be in G1 and G2.
+pub fn pairing_check(pairs: &[(G1Affine, G2Affine)]) -> bool {
+    let f = pairs
+        .iter()
+        .fold(Fp12::ONE, |f, (p, q)| f.mul(&miller_loop(p, q)));
+    // f^(p^12 - 1) / r: the conjugate over `f` raises it to `p^6 - 1`.
+    let Some(f_inv) = f.inverse() else {
+        return false;
+    };
+    f.conjugate().mul(&f_inv).pow(&FINAL_EXP) == Fp12::ONE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generators_have_order_r() {
+        assert!(G1Affine::GENERATOR.is_on_curve());
+        assert!(G2Affine::GENERATOR.is_on_curve());
+        assert!(G1Affine::GENERATOR.is_torsion_free());
+        assert_eq!(G2Affine::GENERATOR.mul(&R), G2Affine::IDENTITY);
+    }
+
+    #[test]
+    fn test_pairing_is_bilinear() {
+        let (g1, g2) = (G1Affine::GENERATOR, G2Affine::GENERATOR);
+        // e([5] g1, [7] g2) * e([-35] g1, g2) = 1.
+        let lhs = (g1.mul(&[5]), g2.mul(&[7]));
+        assert!(pairing_check(&[lhs, (g1.mul(&[35]).neg(), g2)]));
+        assert!(!pairing_check(&[lhs, (g1.mul(&[36]).neg(), g2)]));
+        assert!(!pairing_check(&[(g1, g2)]));
+        assert!(pairing_check(&[(G1Affine::IDENTITY, g2)]));
+    }
+
+    #[test]
+    fn test_from_compressed() {
+        let mut bytes = [0u8; 48];
+        bytes[0] = 0xc0;
+        assert_eq!(G1Affine::from_compressed(&bytes), Some(G1Affine::IDENTITY));
+        // Without the compression flag.
+        bytes[0] = 0x40;
+        assert_eq!(G1Affine::from_compressed(&bytes), None);
+    }
+}
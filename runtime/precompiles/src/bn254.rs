@@ -0,0 +1,33 @@
+//! The G1 group of BN254, whose addition and doubling are precompiles, for the EVM `0x06` and
+//! `0x07` precompiles.
+//!
+//! The pairing check of the EVM `0x08` precompile is [`crate::bn254_pairing`], which the program
+//! computes in software, G2 included.
+
+use crate::utils::{AffinePoint, CurveOperations};
+use crate::{syscall_bn254_add, syscall_bn254_double};
+
+/// The operations on the G1 group of BN254, backed by the BN254 precompiles.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Bn254Operations;
+
+impl CurveOperations<16> for Bn254Operations {
+    /// The generator `(1, 2)`.
+    const GENERATOR: [u32; 16] = [1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0];
+
+    fn add_assign(limbs: &mut [u32; 16], other: &[u32; 16]) {
+        unsafe {
+            syscall_bn254_add(limbs, other);
+        }
+    }
+
+    fn double(limbs: &mut [u32; 16]) {
+        unsafe {
+            syscall_bn254_double(limbs);
+        }
+    }
+}
+
+/// An affine point of the G1 group of BN254, as the little-endian words of `x`, then those of
+/// `y`.
+pub type Bn254Point = AffinePoint<Bn254Operations, 16>;
@@ -0,0 +1,195 @@
+use sha2::{Digest, Sha512};
+
+use crate::modexp::modexp;
+use crate::uint256::{self, Uint256};
+use crate::utils::{AffinePoint, CurveOperations};
+use crate::{syscall_ed_add, syscall_ed_decompress};
+
+/// The modulus of the base field, `2^255 - 19`.
+const MODULUS: Uint256 = [
+    0xffffffed, 0xffffffff, 0xffffffff, 0xffffffff, 0xffffffff, 0xffffffff, 0xffffffff, 0x7fffffff,
+];
+
+/// The curve constant `d = -121665 / 121666`.
+const D: Uint256 = [
+    0x135978a3, 0x75eb4dca, 0x4141d8ab, 0x00700a4d, 0x7779e898, 0x8cc74079, 0x2b6ffe73, 0x52036cee,
+];
+
+/// `(p - 1) / 2`, the exponent of Euler's criterion in the base field.
+const EULER_EXP: Uint256 = [
+    0xfffffff6, 0xffffffff, 0xffffffff, 0xffffffff, 0xffffffff, 0xffffffff, 0xffffffff, 0x3fffffff,
+];
+
+/// `L`, the order of the subgroup generated by the base point.
+const L: Uint256 = [
+    0x5cf5d3ed, 0x5812631a, 0xa2f79cd6, 0x14def9de, 0x00000000, 0x00000000, 0x00000000, 0x10000000,
+];
+
+/// The identity `(0, 1)`.
+const IDENTITY: [u32; 16] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0];
+
+/// The ed25519 curve operations, backed by the `ED_ADD` precompile.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Ed25519Operations;
+
+impl CurveOperations<16> for Ed25519Operations {
+    const GENERATOR: [u32; 16] = [
+        0x8f25d51a, 0xc9562d60, 0x9525a7b2, 0x692cc760, 0xfdd6dc5c, 0xc0a4e231, 0xcd6e53fe,
+        0x216936d3, 0x66666658, 0x66666666, 0x66666666, 0x66666666, 0x66666666, 0x66666666,
+        0x66666666, 0x66666666,
+    ];
+
+    fn add_assign(limbs: &mut [u32; 16], other: &[u32; 16]) {
+        unsafe {
+            syscall_ed_add(limbs, other);
+        }
+    }
+
+    /// The twisted Edwards addition law is complete, so doubling is adding a point to itself.
+    fn double(limbs: &mut [u32; 16]) {
+        let copy = *limbs;
+        unsafe {
+            syscall_ed_add(limbs, &copy);
+        }
+    }
+}
+
+/// An affine ed25519 point, as the little-endian words of `x`, then those of `y`.
+pub type Ed25519Point = AffinePoint<Ed25519Operations, 16>;
+
+impl Ed25519Point {
+    /// Decodes a compressed point, as specified in RFC 8032, with the `ED_DECOMPRESS`
+    /// precompile. Non-canonical encodings are rejected.
+    pub fn decompress(compressed: &[u8; 32]) -> Option<Self> {
+        let sign = compressed[31] >> 7;
+        let mut y = *compressed;
+        y[31] &= 0x7f;
+        let y = uint256::from_le_bytes(&y);
+        if !uint256::lt(&y, &MODULUS) {
+            return None;
+        }
+
+        // The precompile traps for a y which is not that of a point, so check that x^2 = u / v,
+        // with u = y^2 - 1 and v = d y^2 + 1, is a square first, without the sign of a zero x.
+        // v is not zero, d not being a square, so u / v is a square iff u v is.
+        let yy = uint256::mulmod(&y, &y, &MODULUS);
+        let u = uint256::submod(&yy, &uint256::ONE, &MODULUS);
+        let v = uint256::addmod(&uint256::mulmod(&D, &yy, &MODULUS), &uint256::ONE, &MODULUS);
+        if uint256::is_zero(&u) {
+            if sign == 1 {
+                return None;
+            }
+        } else if uint256::powmod(&uint256::mulmod(&u, &v, &MODULUS), &EULER_EXP, &MODULUS)
+            != uint256::ONE
+        {
+            return None;
+        }
+
+        // The precompile works on a word-aligned buffer, and writes x over its first half.
+        let mut words = [0u32; 16];
+        let bytes = unsafe { &mut *(words.as_mut_ptr() as *mut [u8; 64]) };
+        bytes[32..].copy_from_slice(compressed);
+        unsafe {
+            syscall_ed_decompress(bytes);
+        }
+        bytes[63] &= 0x7f;
+        Some(Self::from_le_bytes(bytes))
+    }
+
+    /// `[scalar] self`, for any scalar: the addition law is complete.
+    fn mul(&self, scalar: &Uint256) -> Self {
+        let mut result = Self::new(IDENTITY);
+        let mut power = *self;
+        for word in scalar {
+            for i in 0..32 {
+                if (word >> i) & 1 == 1 {
+                    result.add_assign(&power);
+                }
+                power.double();
+            }
+        }
+        result
+    }
+}
+
+/// Whether `signature` is the ed25519 signature of `msg` by `public_key`, as specified in
+/// RFC 8032, without the cofactor: `[s] B = R + [k] A`.
+pub fn verify(public_key: &[u8; 32], msg: &[u8], signature: &[u8; 64]) -> bool {
+    let (r_bytes, s_bytes) = signature.split_at(32);
+    let s = uint256::from_le_bytes(s_bytes.try_into().unwrap());
+    if !uint256::lt(&s, &L) {
+        return false;
+    }
+    let Some(a) = Ed25519Point::decompress(public_key) else {
+        return false;
+    };
+    let Some(r) = Ed25519Point::decompress(r_bytes.try_into().unwrap()) else {
+        return false;
+    };
+
+    // k = SHA-512(R || A || msg) mod L, the hash being a little-endian integer.
+    let mut k = Sha512::new()
+        .chain_update(r_bytes)
+        .chain_update(public_key)
+        .chain_update(msg)
+        .finalize()
+        .to_vec();
+    k.reverse();
+    let k = modexp(&k, &[1], &uint256::to_be_bytes(&L));
+    let k = uint256::from_be_bytes(&k.try_into().unwrap());
+
+    let mut rhs = a.mul(&k);
+    rhs.add_assign(&r);
+    Ed25519Point::generator_in_affine().mul(&s) == rhs
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::{Signer, SigningKey};
+
+    use super::*;
+    use crate::wrappers::ed25519_verify;
+
+    /// Verifies with the precompiles, checking the result against the one of ed25519-dalek.
+    fn verify_checked(public_key: &[u8; 32], msg: &[u8], signature: &[u8; 64]) -> bool {
+        let valid = verify(public_key, msg, signature);
+        assert_eq!(valid, ed25519_verify(public_key, msg, signature));
+        valid
+    }
+
+    #[test]
+    fn test_verify() {
+        for seed in 1..4 {
+            let key = SigningKey::from_bytes(&[seed; 32]);
+            let public_key = key.verifying_key().to_bytes();
+            let msg = [seed; 100];
+            let signature = key.sign(&msg).to_bytes();
+            assert!(verify_checked(&public_key, &msg, &signature));
+            assert!(!verify_checked(&public_key, &msg[1..], &signature));
+
+            let mut wrong_r = signature;
+            wrong_r[0] ^= 1;
+            assert!(!verify_checked(&public_key, &msg, &wrong_r));
+            // s + L, which is not reduced.
+            let s = uint256::from_le_bytes(signature[32..].try_into().unwrap());
+            let mut s_plus_l = signature;
+            let s_plus_l_words = uint256::overflowing_add(&s, &L).0;
+            s_plus_l[32..].copy_from_slice(&crate::utils::words_to_bytes_le(&s_plus_l_words));
+            assert!(!verify_checked(&public_key, &msg, &s_plus_l));
+        }
+    }
+
+    #[test]
+    fn test_verify_identity_key() {
+        // With the identity as public key, `[s] B = R` whatever the message.
+        let mut identity = [0u8; 32];
+        identity[0] = 1;
+        let mut signature = [0x66; 64];
+        signature[0] = 0x58;
+        signature[32..].copy_from_slice(&[0; 32]);
+        signature[32] = 1;
+        assert!(verify_checked(&identity, b"any message", &signature));
+        signature[32] = 2;
+        assert!(!verify_checked(&identity, b"any message", &signature));
+    }
+}
@@ -0,0 +1,56 @@
+//! The syscalls of the precompiles the unit tests run, computed on the host as the emulator
+//! computes them, where the zkVM does not provide them. A call with invalid inputs traps in the
+//! zkVM, and panics here.
+
+use zkm_emulator::{ed25519, modexp, secp256k1, sha256, uint256, weierstrass};
+
+#[no_mangle]
+pub unsafe extern "C" fn syscall_uint256_mulmod(x: *mut [u32; 8], y: *const [u32; 8]) {
+    *x = uint256::mulmod(&*x, &*y, &*y.add(1)).expect("uint256 mulmod by a zero modulus");
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn syscall_modexp(base: *mut u32, params: *const u32) {
+    let (len, exp_len) = (*params as usize, *params.add(1) as usize);
+    let words = |ptr: *const u32, len: usize| std::slice::from_raw_parts(ptr, len).to_vec();
+    let modulus = words(params.add(2), len);
+    let exp = words(params.add(2 + len), exp_len);
+    let r = modexp::modexp(&words(base.cast_const(), len), &exp, &modulus)
+        .expect("modexp with invalid operands");
+    std::slice::from_raw_parts_mut(base, len).copy_from_slice(&r);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn syscall_secp256k1_add(p: *mut [u32; 16], q: *const [u32; 16]) {
+    *p = weierstrass::add(&secp256k1::MODULUS, &*p, &*q).expect("secp256k1 add of the same x");
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn syscall_secp256k1_double(p: *mut [u32; 16]) {
+    *p = weierstrass::double(&secp256k1::MODULUS, &secp256k1::A, &*p)
+        .expect("secp256k1 double of a zero y");
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn syscall_ed_add(p: *mut [u32; 16], q: *const [u32; 16]) {
+    *p = ed25519::add(&*p, &*q).expect("ed25519 add of points off the curve");
+}
+
+#[no_mangle]
+pub extern "C" fn syscall_ed_decompress(point: &mut [u8; 64]) {
+    let x = ed25519::decompress(point[32..].try_into().unwrap())
+        .expect("ed25519 decompress of an invalid y");
+    let mut bytes = x.to_bytes_le();
+    bytes.resize(32, 0);
+    point[..32].copy_from_slice(&bytes);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn syscall_sha256_extend(w: *mut [u32; 64]) {
+    sha256::extend(&mut *w);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn syscall_sha256_compress(w: *mut [u32; 64], state: *mut [u32; 8]) {
+    sha256::compress(&mut *state, &*w);
+}
@@ -1,19 +1,27 @@
 //! Ported from Precompiles for SP1 zkVM.
 
 #![allow(unused_unsafe)]
+use crate::sha256::Hasher;
+use crate::syscall_cycle_count;
 use crate::syscall_verify;
 use crate::syscall_write;
 use crate::{syscall_hint_len, syscall_hint_read};
+use crate::{syscall_preimage_len, syscall_preimage_read};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use sha2::{Digest, Sha256};
 use std::alloc::Layout;
 use std::io::Write;
+use std::ptr::addr_of_mut;
 
 const FD_HINT: u32 = 4;
+const FD_CYCLE_TRACKER: u32 = 5;
 pub const FD_PUBLIC_VALUES: u32 = 3;
 pub const ZERO: [u8; 32] = [0u8; 32];
 
+/// The SHA-256 hash of the public values committed so far.
+static mut COMMITTED: Hasher = Hasher::new();
+
 #[allow(dead_code)]
 pub struct SyscallWriter {
     fd: u32,
@@ -34,6 +42,31 @@ impl std::io::Write for SyscallWriter {
     }
 }
 
+/// Read the next input of the host as raw bytes, as pushed by `Stdin::write_slice`, straight
+/// into `buf`, and return them. The host writes whole words, so the input is read in place
+/// from the word-aligned `buf`, without allocating.
+///
+/// Panics if the input does not fit in `buf`.
+pub fn read_slice_aligned(buf: &mut [u32]) -> &mut [u8] {
+    let len = unsafe { syscall_hint_len() };
+    let capacity = buf.len() * 4;
+    assert!(
+        len <= capacity,
+        "hint input of {} bytes exceeds the buffer of {} bytes",
+        len,
+        capacity
+    );
+    // SAFETY: `buf` is aligned, and the host writes the `len` bytes of the input, padded with 0s
+    // to whole words, within its `capacity` bytes.
+    unsafe {
+        let ptr = buf.as_mut_ptr() as *mut u8;
+        syscall_hint_read(ptr, len);
+        std::slice::from_raw_parts_mut(ptr, len)
+    }
+}
+
+/// Read the next input of the host as raw bytes, as pushed by `Stdin::write_slice`, into a
+/// vector allocated once with the length of the input.
 pub fn read_vec() -> Vec<u8> {
     let len = unsafe { syscall_hint_len() };
     // Round up to the nearest multiple of 4 so that the memory allocated is in whole words
@@ -58,18 +91,53 @@ pub fn read_vec() -> Vec<u8> {
     vec
 }
 
+/// Read the next input of the host as a `T`, as pushed by `Stdin::write`.
 pub fn read<T: DeserializeOwned>() -> T {
     let vec = read_vec();
     bincode::deserialize(&vec).expect("deserialization failed")
 }
 
+/// Fetch the preimage of `key` from the host.
+///
+/// The host is not trusted to return the right data: if `key` is a hash, the caller has to
+/// check the preimage against it, see [`read_sha256_preimage`].
+pub fn read_preimage(key: &[u8; 32]) -> Vec<u8> {
+    let len = unsafe { syscall_preimage_len(key) };
+    assert_ne!(len, usize::MAX, "no preimage for key {:02x?}", key);
+    let mut vec = vec![0u8; len];
+    let read = unsafe { syscall_preimage_read(key, vec.as_mut_ptr(), len) };
+    assert_eq!(read, len, "preimage read length mismatch");
+    vec
+}
+
+/// Fetch the data whose SHA-256 digest is `digest` from the host.
+pub fn read_sha256_preimage(digest: &[u8; 32]) -> Vec<u8> {
+    let preimage = read_preimage(digest);
+    let actual: [u8; 32] = Sha256::digest(&preimage).into();
+    assert_eq!(&actual, digest, "preimage does not match its digest");
+    preimage
+}
+
+/// Append the raw bytes `buf` to the public values.
 pub fn commit_slice(buf: &[u8]) {
+    // SAFETY: Single threaded, so nothing else can touch this while we're working.
+    unsafe { (*addr_of_mut!(COMMITTED)).update(buf) };
     let mut my_writer: SyscallWriter = SyscallWriter {
         fd: FD_PUBLIC_VALUES,
     };
     my_writer.write_all(buf).unwrap();
 }
 
+/// The SHA-256 digest of the raw bytes of the public values committed so far, e.g. for the
+/// program to sign its output. The program hashes them as it commits them, so the proof covers
+/// the digest.
+pub fn committed_digest() -> [u8; 32] {
+    // SAFETY: Single threaded, so nothing else can touch this while we're working.
+    unsafe { (*addr_of_mut!(COMMITTED)).digest() }
+}
+
+/// Append `value`, serialized with bincode, to the public values. The host reads it back
+/// with `State::read_public_values::<T>()`, or decodes it from the journal of the receipt.
 pub fn commit<T: Serialize>(value: &T) {
     let mut buf = Vec::new();
     bincode::serialize_into(&mut buf, value).expect("serialization failed");
@@ -92,11 +160,14 @@ pub fn verify<T: Serialize>(image_id: Vec<u8>, public_input: &T) {
     unsafe { syscall_verify(&digest, &ZERO) }
 }
 
+/// Send the raw bytes `buf` to the host, which answers with inputs read next by [`read`] or
+/// [`read_vec`]. The host is not trusted: the program has to check the answer.
 pub fn hint_slice(buf: &[u8]) {
     let mut my_reader: SyscallWriter = SyscallWriter { fd: FD_HINT };
     my_reader.write_all(buf).unwrap();
 }
 
+/// Send `value`, serialized with bincode, to the host, see [`hint_slice`].
 pub fn hint<T: Serialize>(value: &T) {
     let mut buf = Vec::new();
     bincode::serialize_into(&mut buf, value).expect("serialization failed");
@@ -114,3 +185,26 @@ pub fn print(buf: Vec<u8>) {
         .write_all(buf.as_slice())
         .unwrap();
 }
+
+/// The number of instructions executed so far, modulo 2^32. The difference of two counts, with
+/// `wrapping_sub`, is the cost of the code in between.
+pub fn cycle_count() -> u32 {
+    unsafe { syscall_cycle_count() }
+}
+
+/// Starts a section of the cycle report of the host. The sections with the same label are summed.
+pub fn cycle_tracker_start(label: &str) {
+    cycle_tracker_mark(0, label);
+}
+
+/// Ends the last section started with `label`.
+pub fn cycle_tracker_end(label: &str) {
+    cycle_tracker_mark(1, label);
+}
+
+fn cycle_tracker_mark(tag: u8, label: &str) {
+    let mut marker = Vec::with_capacity(1 + label.len());
+    marker.push(tag);
+    marker.extend_from_slice(label.as_bytes());
+    write(FD_CYCLE_TRACKER, &marker);
+}
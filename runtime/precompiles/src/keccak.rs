@@ -0,0 +1,28 @@
+use crate::syscall_keccak256;
+
+/// The rate of Keccak-256, in bytes.
+const RATE: usize = 136;
+
+/// Computes the Keccak-256 digest of `input` with the Keccak precompile.
+pub fn keccak256(input: &[u8]) -> [u8; 32] {
+    // The precompile reads whole words, so the input is copied to a word-aligned buffer, whose
+    // bytes after the input hold the padding of the final block.
+    let mut words = vec![0u32; (input.len() + 3) / 4];
+    let bytes =
+        unsafe { core::slice::from_raw_parts_mut(words.as_mut_ptr() as *mut u8, words.len() * 4) };
+    bytes[..input.len()].copy_from_slice(input);
+    for (i, byte) in bytes.iter_mut().enumerate().skip(input.len()) {
+        if i % RATE == input.len() % RATE {
+            *byte |= 1;
+        }
+        if i % RATE == RATE - 1 {
+            *byte |= 0x80;
+        }
+    }
+
+    let mut digest = [0u32; 8];
+    unsafe {
+        syscall_keccak256(words.as_ptr(), input.len(), &mut digest);
+        core::mem::transmute::<[u32; 8], [u8; 32]>(digest)
+    }
+}
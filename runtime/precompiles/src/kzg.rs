@@ -0,0 +1,212 @@
+//! The KZG point evaluation precompile of EIP-4844, which rollup nodes use to verify openings of
+//! blob commitments against their versioned hashes.
+//!
+//! The pairing is computed over the BLS12-381 base field precompiles, and the versioned hash with
+//! the SHA-256 ones. On the host, both are computed in software.
+
+use crate::bls12_381::{pairing_check, Affine, Fp, Fp2, G1Affine, G2Affine, R};
+use crate::wrappers::sha256;
+
+/// The length of the input: the versioned hash, `z`, `y`, the commitment and the proof.
+pub const INPUT_LEN: usize = 192;
+
+/// The version byte of the versioned hash of a KZG commitment.
+pub const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+
+/// The number of field elements of a blob, the first half of the output.
+pub const FIELD_ELEMENTS_PER_BLOB: u64 = 4096;
+
+/// `r`, the order of the scalar field, in big endian, the second half of the output.
+pub const BLS_MODULUS: [u8; 32] = [
+    0x73, 0xed, 0xa7, 0x53, 0x29, 0x9d, 0x7d, 0x48, 0x33, 0x39, 0xd8, 0x08, 0x09, 0xa1, 0xd8, 0x05,
+    0x53, 0xbd, 0xa4, 0x02, 0xff, 0xfe, 0x5b, 0xfe, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x01,
+];
+
+/// `[s]_2`, the G2 point of the KZG ceremony, where `s` is its secret.
+const TAU_G2: G2Affine = Affine(Some((
+    Fp2 {
+        c0: Fp([
+            0x20c1def2, 0xc98edada, 0x621000ed, 0x087041de, 0x7ba4c60b, 0xa3685147, 0xcceceac9,
+            0x3926c911, 0xb38608e2, 0x734429b7, 0x53492714, 0x185cbfee,
+        ]),
+        c1: Fp([
+            0xf3499f72, 0xafaaab24, 0x0cb452d2, 0x2914e587, 0x615ac53d, 0x1009a2ce, 0xcbfbefa8,
+            0x26187075, 0x230af389, 0x843bc287, 0x8cdeb128, 0x15bfd7dd,
+        ]),
+    },
+    Fp2 {
+        c0: Fp([
+            0xbb832a99, 0xee689bfb, 0x5941f383, 0x4ce26d10, 0x96a9c979, 0xe82451a4, 0x0e28de18,
+            0x13156949, 0x99d1fca2, 0xd7d5ee85, 0xb96b626d, 0x014353bd,
+        ]),
+        c1: Fp([
+            0x0d0a154f, 0x23048ef3, 0x3d7ac9cd, 0x9495346f, 0x9bfa0789, 0xda5ed1ba, 0xfc63671f,
+            0xef79de09, 0xe0181b4b, 0x03432fca, 0x0a325295, 0x1666c54b,
+        ]),
+    },
+)));
+
+/// Why an input of the precompile is rejected.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum KzgError {
+    /// The input is not `INPUT_LEN` bytes long.
+    InvalidInputLength,
+    /// The versioned hash is not that of the commitment.
+    InvalidVersionedHash,
+    /// `z` or `y` is not below `BLS_MODULUS`.
+    InvalidFieldElement,
+    /// The commitment or the proof is not the encoding of a point of G1.
+    InvalidPoint,
+    /// The proof does not open the commitment to `y` at `z`.
+    InvalidProof,
+}
+
+/// Runs the point evaluation precompile on `input`, the versioned hash, `z`, `y`, the commitment
+/// and the proof. The output is `FIELD_ELEMENTS_PER_BLOB` then `BLS_MODULUS`, as 32-byte big
+/// endian integers.
+pub fn point_evaluation(input: &[u8]) -> Result<[u8; 64], KzgError> {
+    if input.len() != INPUT_LEN {
+        return Err(KzgError::InvalidInputLength);
+    }
+    let versioned_hash: &[u8; 32] = input[..32].try_into().unwrap();
+    let z: &[u8; 32] = input[32..64].try_into().unwrap();
+    let y: &[u8; 32] = input[64..96].try_into().unwrap();
+    let commitment: &[u8; 48] = input[96..144].try_into().unwrap();
+    let proof: &[u8; 48] = input[144..].try_into().unwrap();
+
+    if kzg_to_versioned_hash(commitment) != *versioned_hash {
+        return Err(KzgError::InvalidVersionedHash);
+    }
+    if !verify_kzg_proof(commitment, z, y, proof)? {
+        return Err(KzgError::InvalidProof);
+    }
+
+    let mut output = [0u8; 64];
+    output[24..32].copy_from_slice(&FIELD_ELEMENTS_PER_BLOB.to_be_bytes());
+    output[32..].copy_from_slice(&BLS_MODULUS);
+    Ok(output)
+}
+
+/// The versioned hash of a commitment: its SHA-256 hash, with the version as first byte.
+pub fn kzg_to_versioned_hash(commitment: &[u8; 48]) -> [u8; 32] {
+    let mut hash = sha256(commitment);
+    hash[0] = VERSIONED_HASH_VERSION_KZG;
+    hash
+}
+
+/// Whether `proof` opens `commitment` to `y` at `z`, that is whether
+/// `e(commitment - [y]_1, -[1]_2) * e(proof, [s]_2 - [z]_2) = 1`. `z` and `y` are big-endian
+/// scalars, the commitment and the proof compressed points of G1.
+pub fn verify_kzg_proof(
+    commitment: &[u8; 48],
+    z: &[u8; 32],
+    y: &[u8; 32],
+    proof: &[u8; 48],
+) -> Result<bool, KzgError> {
+    let z = scalar_from_bytes_be(z).ok_or(KzgError::InvalidFieldElement)?;
+    let y = scalar_from_bytes_be(y).ok_or(KzgError::InvalidFieldElement)?;
+    let commitment = g1_from_bytes(commitment)?;
+    let proof = g1_from_bytes(proof)?;
+
+    // e(proof, -[z]_2) = e([z] proof, -[1]_2), which keeps the scalar multiplications in G1.
+    let lhs = commitment
+        .add(&G1Affine::GENERATOR.mul(&y).neg())
+        .add(&proof.mul(&z));
+    Ok(pairing_check(&[
+        (lhs, G2Affine::GENERATOR.neg()),
+        (proof, TAU_G2),
+    ]))
+}
+
+/// Decodes a compressed point, checking that it is in G1.
+fn g1_from_bytes(bytes: &[u8; 48]) -> Result<G1Affine, KzgError> {
+    G1Affine::from_compressed(bytes)
+        .filter(G1Affine::is_torsion_free)
+        .ok_or(KzgError::InvalidPoint)
+}
+
+/// Decodes a big-endian scalar as little-endian words, or `None` if it is not below `r`.
+fn scalar_from_bytes_be(bytes: &[u8; 32]) -> Option<[u32; 8]> {
+    let mut words = [0u32; 8];
+    for (word, chunk) in words.iter_mut().rev().zip(bytes.chunks_exact(4)) {
+        *word = u32::from_be_bytes(chunk.try_into().unwrap());
+    }
+    words.iter().rev().lt(R.iter().rev()).then_some(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A valid opening of the test vectors of c-kzg-4844.
+    const COMMITMENT: &str = "8f59a8d2a1a625a17f3fea0fe5eb8c896db3764f3185481bc22f91b4aaffcca25f26936857bc3a7c2539ea8ec3a952b7";
+    const Z: &str = "73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000000";
+    const Y: &str = "1522a4a7f34e1ea350ae07c29c96c7e79655aa926122e95fe69fcbd932ca49e9";
+    const PROOF: &str = "a62ad71d14c5719385c0686f1871430475bf3a00f0aa3f7b8dd99a9abc2160744faf0070725e00b60ad9a026a15b1a8c";
+    const VERSIONED_HASH: &str = "01e798154708fe7789429634053cbf9f99b619f9f084048927333fce637f549b";
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn input() -> Vec<u8> {
+        [VERSIONED_HASH, Z, Y, COMMITMENT, PROOF]
+            .iter()
+            .flat_map(|hex| from_hex(hex))
+            .collect()
+    }
+
+    #[test]
+    fn test_versioned_hash() {
+        let commitment = from_hex(COMMITMENT).try_into().unwrap();
+        assert_eq!(
+            kzg_to_versioned_hash(&commitment).to_vec(),
+            from_hex(VERSIONED_HASH)
+        );
+    }
+
+    #[test]
+    fn test_valid_opening() {
+        let output = point_evaluation(&input()).unwrap();
+        assert_eq!(
+            output[..32],
+            from_hex(&format!("{:064x}", FIELD_ELEMENTS_PER_BLOB))
+        );
+        assert_eq!(output[32..], BLS_MODULUS);
+    }
+
+    #[test]
+    fn test_invalid_proof() {
+        let mut input = input();
+        input[95] ^= 1;
+        assert_eq!(point_evaluation(&input), Err(KzgError::InvalidProof));
+    }
+
+    #[test]
+    fn test_invalid_versioned_hash() {
+        let mut input = input();
+        input[1] ^= 1;
+        assert_eq!(
+            point_evaluation(&input),
+            Err(KzgError::InvalidVersionedHash)
+        );
+    }
+
+    #[test]
+    fn test_invalid_field_element() {
+        let mut input = input();
+        input[32..64].copy_from_slice(&BLS_MODULUS);
+        assert_eq!(point_evaluation(&input), Err(KzgError::InvalidFieldElement));
+    }
+
+    #[test]
+    fn test_invalid_input_length() {
+        assert_eq!(
+            point_evaluation(&input()[1..]),
+            Err(KzgError::InvalidInputLength)
+        );
+    }
+}
@@ -5,8 +5,24 @@
 //! function impls must live in sp1-zkvm, which is only imported into the end user program crate.
 //! In contrast, sp1-precompiles can be imported into any crate in the dependency tree.
 
+pub mod bls12_381;
+pub mod bn254;
+pub mod ed25519;
 pub mod io;
+pub mod keccak;
+pub mod kzg;
+pub mod modexp;
+pub mod secp256k1;
+pub mod secp256r1;
+pub mod sha256;
+pub mod uint256;
 pub mod utils;
+pub mod wrappers;
+
+#[cfg(test)]
+mod host_syscalls;
+
+pub use wrappers::{bn254_pairing, ed25519_verify, keccak256, secp256k1_recover, sha256};
 
 pub const BIGINT_WIDTH_WORDS: usize = 8;
 
@@ -15,6 +31,35 @@ extern "C" {
     pub fn syscall_write(fd: u32, write_buf: *const u8, nbytes: usize);
     pub fn syscall_hint_len() -> usize;
     pub fn syscall_hint_read(ptr: *mut u8, len: usize);
+    pub fn syscall_preimage_len(key: &[u8; 32]) -> usize;
+    pub fn syscall_preimage_read(key: &[u8; 32], ptr: *mut u8, len: usize) -> usize;
     pub fn sys_alloc_aligned(bytes: usize, align: usize) -> *mut u8;
     pub fn syscall_verify(claim_digest: &[u8; 32], control_root: &[u8; 32]);
+    pub fn syscall_cycle_count() -> u32;
+    pub fn syscall_keccak256(input: *const u32, len: usize, out: *mut [u32; 8]);
+    pub fn syscall_sha256_extend(w: *mut [u32; 64]);
+    pub fn syscall_sha256_compress(w: *mut [u32; 64], state: *mut [u32; 8]);
+    pub fn syscall_secp256k1_add(p: *mut [u32; 16], q: *const [u32; 16]);
+    pub fn syscall_secp256k1_double(p: *mut [u32; 16]);
+    pub fn syscall_ed_add(p: *mut [u32; 16], q: *const [u32; 16]);
+    pub fn syscall_ed_decompress(point: &mut [u8; 64]);
+    pub fn syscall_bn254_add(p: *mut [u32; 16], q: *const [u32; 16]);
+    pub fn syscall_bn254_double(p: *mut [u32; 16]);
+    pub fn syscall_bls12381_fp_add(x: *mut [u32; 12], y: *const [u32; 12]);
+    pub fn syscall_bls12381_fp_sub(x: *mut [u32; 12], y: *const [u32; 12]);
+    pub fn syscall_bls12381_fp_mul(x: *mut [u32; 12], y: *const [u32; 12]);
+    pub fn syscall_uint256_mulmod(x: *mut [u32; 8], y: *const [u32; 8]);
+    pub fn sys_bigint(
+        result: *mut [u32; BIGINT_WIDTH_WORDS],
+        op: u32,
+        x: *const [u32; BIGINT_WIDTH_WORDS],
+        y: *const [u32; BIGINT_WIDTH_WORDS],
+        modulus: *const [u32; BIGINT_WIDTH_WORDS],
+    );
+    pub fn syscall_blake2b_compress(h: *mut [u32; 16], input: *const [u32; 38]);
+    pub fn syscall_blake3_compress(cv: *mut [u32; 8], input: *const [u32; 20]);
+    pub fn syscall_chacha20_block(state: *mut [u32; 16]);
+    pub fn syscall_secp256r1_add(p: *mut [u32; 16], q: *const [u32; 16]);
+    pub fn syscall_secp256r1_double(p: *mut [u32; 16]);
+    pub fn syscall_modexp(base: *mut u32, params: *const u32);
 }
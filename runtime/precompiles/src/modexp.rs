@@ -0,0 +1,70 @@
+//! Modular exponentiation of big-endian byte strings, as the EVM `0x05` precompile, backed by
+//! the `MODEXP` precompile.
+//!
+//! The precompile takes integers of up to 2048 bits and exponents of up to 8192 bits, which
+//! covers RSA signature verification. Leading zero bytes do not count towards these limits.
+
+use crate::syscall_modexp;
+
+/// The largest number of significant bytes of the base and the modulus.
+pub const MODEXP_MAX_BYTES: usize = 256;
+
+/// The largest number of significant bytes of the exponent.
+pub const MODEXP_MAX_EXP_BYTES: usize = 1024;
+
+/// Computes `base^exp mod modulus` on big-endian byte strings, with as many bytes as `modulus`.
+///
+/// As in the EVM, the result is zero if the modulus is, and `1 mod modulus` if the exponent is
+/// empty. Panics if an operand exceeds the limits of the precompile.
+pub fn modexp(base: &[u8], exp: &[u8], modulus: &[u8]) -> Vec<u8> {
+    let mut result = vec![0u8; modulus.len()];
+    let (base, exp, modulus) = (trim(base), trim(exp), trim(modulus));
+    if modulus.is_empty() {
+        return result;
+    }
+    assert!(
+        base.len() <= MODEXP_MAX_BYTES && modulus.len() <= MODEXP_MAX_BYTES,
+        "modexp operands of more than {MODEXP_MAX_BYTES} bytes"
+    );
+    assert!(
+        exp.len() <= MODEXP_MAX_EXP_BYTES,
+        "modexp exponent of more than {MODEXP_MAX_EXP_BYTES} bytes"
+    );
+
+    // The base and the modulus have the same number of words, and the exponent at least one.
+    let len = base.len().max(modulus.len()).div_ceil(4);
+    let exp_len = exp.len().div_ceil(4).max(1);
+    let mut base = be_bytes_to_words(base, len);
+    let mut params = vec![len as u32, exp_len as u32];
+    params.extend(be_bytes_to_words(modulus, len));
+    params.extend(be_bytes_to_words(exp, exp_len));
+    unsafe {
+        syscall_modexp(base.as_mut_ptr(), params.as_ptr());
+    }
+
+    // The result is below the modulus, so its words beyond the length of the modulus are zero.
+    let bytes = base
+        .iter()
+        .rev()
+        .flat_map(|word| word.to_be_bytes())
+        .collect::<Vec<_>>();
+    let offset = result.len().min(bytes.len());
+    let len = result.len();
+    result[len - offset..].copy_from_slice(&bytes[bytes.len() - offset..]);
+    result
+}
+
+/// Strips the leading zero bytes of a big-endian integer.
+fn trim(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+/// The `len` little-endian words of a big-endian integer of at most `4 * len` bytes.
+fn be_bytes_to_words(bytes: &[u8], len: usize) -> Vec<u32> {
+    let mut words = vec![0u32; len];
+    for (i, &byte) in bytes.iter().rev().enumerate() {
+        words[i / 4] |= (byte as u32) << (8 * (i % 4));
+    }
+    words
+}
@@ -0,0 +1,234 @@
+use crate::uint256::{self, Uint256};
+use crate::utils::{AffinePoint, CurveOperations};
+use crate::{syscall_secp256k1_add, syscall_secp256k1_double};
+
+/// The modulus of the base field.
+const MODULUS: Uint256 = [
+    0xfffffc2f, 0xfffffffe, 0xffffffff, 0xffffffff, 0xffffffff, 0xffffffff, 0xffffffff, 0xffffffff,
+];
+
+/// `(p + 1) / 4`: as `p = 3 mod 4`, `a^((p + 1) / 4)` is a square root of `a` if it has one.
+const SQRT_EXP: Uint256 = [
+    0xbfffff0c, 0xffffffff, 0xffffffff, 0xffffffff, 0xffffffff, 0xffffffff, 0xffffffff, 0x3fffffff,
+];
+
+/// `n`, the order of the curve.
+const ORDER: Uint256 = [
+    0xd0364141, 0xbfd25e8c, 0xaf48a03b, 0xbaaedce6, 0xfffffffe, 0xffffffff, 0xffffffff, 0xffffffff,
+];
+
+/// The curve constant `b`, the curve being `y^2 = x^3 + 7`.
+const B: Uint256 = [7, 0, 0, 0, 0, 0, 0, 0];
+
+/// The secp256k1 curve operations, backed by the secp256k1 precompiles.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Secp256k1Operations;
+
+impl CurveOperations<16> for Secp256k1Operations {
+    const GENERATOR: [u32; 16] = [
+        0x16f81798, 0x59f2815b, 0x2dce28d9, 0x029bfcdb, 0xce870b07, 0x55a06295, 0xf9dcbbac,
+        0x79be667e, 0xfb10d4b8, 0x9c47d08f, 0xa6855419, 0xfd17b448, 0x0e1108a8, 0x5da4fbfc,
+        0x26a3c465, 0x483ada77,
+    ];
+
+    fn add_assign(limbs: &mut [u32; 16], other: &[u32; 16]) {
+        unsafe {
+            syscall_secp256k1_add(limbs, other);
+        }
+    }
+
+    fn double(limbs: &mut [u32; 16]) {
+        unsafe {
+            syscall_secp256k1_double(limbs);
+        }
+    }
+}
+
+/// An affine secp256k1 point, as the little-endian words of `x`, then those of `y`.
+pub type Secp256k1Point = AffinePoint<Secp256k1Operations, 16>;
+
+/// `p + q`, or `None` if it is the point at infinity. The precompiles add distinct points only.
+fn add(mut p: Secp256k1Point, q: &Secp256k1Point) -> Option<Secp256k1Point> {
+    if p.limbs[..8] != q.limbs[..8] {
+        p.add_assign(q);
+    } else if p.limbs[8..] == q.limbs[8..] {
+        p.double();
+    } else {
+        return None;
+    }
+    Some(p)
+}
+
+/// Recovers the public key which signed the 32-byte `msg_hash`, from the ECDSA `signature`,
+/// `r || s` in big endian, and its recovery id. The key is an uncompressed SEC1 point.
+///
+/// Returns `None` if the signature or the recovery id is invalid. As in `ecrecover`, signatures
+/// with a high `s` are accepted.
+pub fn recover(signature: &[u8; 64], recovery_id: u8, msg_hash: &[u8; 32]) -> Option<[u8; 65]> {
+    let r = uint256::from_be_bytes(signature[..32].try_into().unwrap());
+    let s = uint256::from_be_bytes(signature[32..].try_into().unwrap());
+    let in_range = |x: &Uint256| !uint256::is_zero(x) && uint256::lt(x, &ORDER);
+    if recovery_id > 3 || !in_range(&r) || !in_range(&s) {
+        return None;
+    }
+
+    // R, whose x is r, or r + n with the second bit of the recovery id, and whose y has the
+    // parity given by its first bit.
+    let x = if recovery_id & 2 == 0 {
+        r
+    } else {
+        match uint256::overflowing_add(&r, &ORDER) {
+            (x, false) => x,
+            (_, true) => return None,
+        }
+    };
+    if !uint256::lt(&x, &MODULUS) {
+        return None;
+    }
+    let xxx = uint256::mulmod(&uint256::mulmod(&x, &x, &MODULUS), &x, &MODULUS);
+    let yy = uint256::addmod(&xxx, &B, &MODULUS);
+    let mut y = uint256::powmod(&yy, &SQRT_EXP, &MODULUS);
+    if uint256::mulmod(&y, &y, &MODULUS) != yy {
+        return None;
+    }
+    if (y[0] & 1) as u8 != recovery_id & 1 {
+        y = uint256::negmod(&y, &MODULUS);
+    }
+    let mut limbs = [0u32; 16];
+    limbs[..8].copy_from_slice(&x);
+    limbs[8..].copy_from_slice(&y);
+    let point_r = Secp256k1Point::new(limbs);
+
+    // Q = r^-1 (s R - z G), where z is the hash reduced mod n, which is above 2^255.
+    let z = uint256::from_be_bytes(msg_hash);
+    let z = if uint256::lt(&z, &ORDER) {
+        z
+    } else {
+        uint256::overflowing_sub(&z, &ORDER).0
+    };
+    let r_inv = uint256::invmod(&r, &ORDER);
+    let u1 = uint256::negmod(&uint256::mulmod(&z, &r_inv, &ORDER), &ORDER);
+    let u2 = uint256::mulmod(&s, &r_inv, &ORDER);
+    let mut q = point_r;
+    q.mul_assign(&u2);
+    if !uint256::is_zero(&u1) {
+        let mut u1_g = Secp256k1Point::generator_in_affine();
+        u1_g.mul_assign(&u1);
+        q = add(q, &u1_g)?;
+    }
+
+    let mut key = [0u8; 65];
+    key[0] = 0x04;
+    let x: Uint256 = q.limbs[..8].try_into().unwrap();
+    let y: Uint256 = q.limbs[8..].try_into().unwrap();
+    key[1..33].copy_from_slice(&uint256::to_be_bytes(&x));
+    key[33..].copy_from_slice(&uint256::to_be_bytes(&y));
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use k256::ecdsa::SigningKey;
+
+    use super::*;
+    use crate::wrappers::secp256k1_recover;
+
+    /// Recovers with the precompiles, checking the key against the one of k256.
+    fn recover_checked(
+        signature: &[u8; 64],
+        recovery_id: u8,
+        msg_hash: &[u8; 32],
+    ) -> Option<[u8; 65]> {
+        let key = recover(signature, recovery_id, msg_hash);
+        assert_eq!(key, secp256k1_recover(signature, recovery_id, msg_hash));
+        key
+    }
+
+    /// A signature of `msg_hash` by a key derived from `seed`, with its recovery id and the key.
+    fn sign(seed: u8, msg_hash: &[u8; 32]) -> ([u8; 64], u8, [u8; 65]) {
+        let key = SigningKey::from_bytes(&[seed; 32].into()).unwrap();
+        let (signature, recovery_id) = key.sign_prehash_recoverable(msg_hash).unwrap();
+        let public_key = key.verifying_key().to_encoded_point(false);
+        (
+            signature.to_bytes().as_slice().try_into().unwrap(),
+            recovery_id.to_byte(),
+            public_key.as_bytes().try_into().unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_recover() {
+        for seed in 1..4 {
+            let msg_hash = [seed ^ 0x5a; 32];
+            let (signature, recovery_id, key) = sign(seed, &msg_hash);
+            assert_eq!(
+                recover_checked(&signature, recovery_id, &msg_hash),
+                Some(key)
+            );
+            // The other parity of R is another key.
+            assert_ne!(
+                recover_checked(&signature, recovery_id ^ 1, &msg_hash),
+                Some(key)
+            );
+        }
+        // A hash above the order is reduced.
+        let (signature, recovery_id, key) = sign(1, &[0xff; 32]);
+        assert_eq!(
+            recover_checked(&signature, recovery_id, &[0xff; 32]),
+            Some(key)
+        );
+    }
+
+    #[test]
+    fn test_recover_high_s() {
+        let msg_hash = [7; 32];
+        let (mut signature, recovery_id, key) = sign(1, &msg_hash);
+        let s = uint256::from_be_bytes(signature[32..].try_into().unwrap());
+        let high_s = uint256::overflowing_sub(&ORDER, &s).0;
+        signature[32..].copy_from_slice(&uint256::to_be_bytes(&high_s));
+        assert_eq!(
+            recover_checked(&signature, recovery_id ^ 1, &msg_hash),
+            Some(key)
+        );
+    }
+
+    #[test]
+    fn test_recover_x_above_order() {
+        // R has x = r + n, below p since r is small, for the first r whose x is on the curve.
+        let msg_hash = [3; 32];
+        let mut signature = [0u8; 64];
+        signature[63] = 5;
+        let mut recovered = None;
+        for r in 1..64 {
+            signature[31] = r;
+            recovered = recovered.or(recover_checked(&signature, 2, &msg_hash));
+            recover_checked(&signature, 3, &msg_hash);
+        }
+        assert!(recovered.is_some());
+        // With r above p - n.
+        let (signature, ..) = sign(1, &msg_hash);
+        assert_eq!(recover_checked(&signature, 2, &msg_hash), None);
+    }
+
+    #[test]
+    fn test_recover_invalid() {
+        let msg_hash = [9; 32];
+        let (signature, recovery_id, _) = sign(2, &msg_hash);
+        assert_eq!(recover_checked(&signature, 4, &msg_hash), None);
+        let mut zero_s = signature;
+        zero_s[32..].fill(0);
+        assert_eq!(recover_checked(&zero_s, recovery_id, &msg_hash), None);
+        let mut r_order = signature;
+        r_order[..32].copy_from_slice(&uint256::to_be_bytes(&ORDER));
+        assert_eq!(recover_checked(&r_order, recovery_id, &msg_hash), None);
+
+        // R = G and z = s give s R - z G, the point at infinity.
+        let generator = Secp256k1Point::generator_in_affine();
+        let x: Uint256 = generator.limbs[..8].try_into().unwrap();
+        let mut signature = [0u8; 64];
+        signature[..32].copy_from_slice(&uint256::to_be_bytes(&x));
+        signature[32..].copy_from_slice(&msg_hash);
+        let parity = (generator.limbs[8] & 1) as u8;
+        assert_eq!(recover_checked(&signature, parity, &msg_hash), None);
+    }
+}
@@ -0,0 +1,29 @@
+use crate::utils::{AffinePoint, CurveOperations};
+use crate::{syscall_secp256r1_add, syscall_secp256r1_double};
+
+/// The secp256r1 (P-256) curve operations, backed by the secp256r1 precompiles.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Secp256r1Operations;
+
+impl CurveOperations<16> for Secp256r1Operations {
+    const GENERATOR: [u32; 16] = [
+        0xd898c296, 0xf4a13945, 0x2deb33a0, 0x77037d81, 0x63a440f2, 0xf8bce6e5, 0xe12c4247,
+        0x6b17d1f2, 0x37bf51f5, 0xcbb64068, 0x6b315ece, 0x2bce3357, 0x7c0f9e16, 0x8ee7eb4a,
+        0xfe1a7f9b, 0x4fe342e2,
+    ];
+
+    fn add_assign(limbs: &mut [u32; 16], other: &[u32; 16]) {
+        unsafe {
+            syscall_secp256r1_add(limbs, other);
+        }
+    }
+
+    fn double(limbs: &mut [u32; 16]) {
+        unsafe {
+            syscall_secp256r1_double(limbs);
+        }
+    }
+}
+
+/// An affine secp256r1 point, as the little-endian words of `x`, then those of `y`.
+pub type Secp256r1Point = AffinePoint<Secp256r1Operations, 16>;
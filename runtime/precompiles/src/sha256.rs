@@ -0,0 +1,103 @@
+use crate::{syscall_sha256_compress, syscall_sha256_extend};
+
+/// The SHA-256 initial hash state.
+pub const IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// Compresses the 64-byte `block` into the SHA-256 hash state `state` with the SHA-256
+/// precompiles, as `sha2::compress256` does.
+pub fn compress(state: &mut [u32; 8], block: &[u8; 64]) {
+    let mut w = [0u32; 64];
+    for (word, bytes) in w.iter_mut().zip(block.chunks_exact(4)) {
+        *word = u32::from_be_bytes(bytes.try_into().unwrap());
+    }
+    unsafe {
+        syscall_sha256_extend(&mut w);
+        syscall_sha256_compress(&mut w, state);
+    }
+}
+
+/// A SHA-256 hash computed with the SHA-256 precompiles, of the bytes given so far.
+#[derive(Clone)]
+pub struct Hasher {
+    state: [u32; 8],
+    /// The bytes of the current block, and the number of bytes hashed so far.
+    block: [u8; 64],
+    len: u64,
+}
+
+impl Hasher {
+    pub const fn new() -> Self {
+        Self {
+            state: IV,
+            block: [0; 64],
+            len: 0,
+        }
+    }
+
+    pub fn update(&mut self, mut input: &[u8]) {
+        while !input.is_empty() {
+            let offset = (self.len % 64) as usize;
+            let n = (64 - offset).min(input.len());
+            self.block[offset..offset + n].copy_from_slice(&input[..n]);
+            self.len += n as u64;
+            input = &input[n..];
+            if offset + n == 64 {
+                compress(&mut self.state, &self.block);
+            }
+        }
+    }
+
+    /// The digest of the bytes given so far, which can be given more bytes afterwards.
+    pub fn digest(&self) -> [u8; 32] {
+        // The padding: a one bit, zeros, then the length in bits, in one or two blocks.
+        let rest = (self.len % 64) as usize;
+        let mut tail = [0u8; 128];
+        tail[..rest].copy_from_slice(&self.block[..rest]);
+        tail[rest] = 0x80;
+        let tail_len = if rest < 56 { 64 } else { 128 };
+        tail[tail_len - 8..tail_len].copy_from_slice(&(8 * self.len).to_be_bytes());
+        let mut state = self.state;
+        for block in tail[..tail_len].chunks_exact(64) {
+            compress(&mut state, block.try_into().unwrap());
+        }
+
+        let mut digest = [0u8; 32];
+        for (bytes, word) in digest.chunks_exact_mut(4).zip(state) {
+            bytes.copy_from_slice(&word.to_be_bytes());
+        }
+        digest
+    }
+}
+
+impl Default for Hasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Computes the SHA-256 digest of `input` with the SHA-256 precompiles.
+pub fn sha256(input: &[u8]) -> [u8; 32] {
+    let mut hasher = Hasher::new();
+    hasher.update(input);
+    hasher.digest()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+
+    #[test]
+    fn test_hasher() {
+        let input = (0..200u8).collect::<Vec<u8>>();
+        let mut hasher = Hasher::new();
+        for chunk in input.chunks(23) {
+            hasher.update(chunk);
+            let expected: [u8; 32] = Sha256::digest(&input[..hasher.len as usize]).into();
+            assert_eq!(hasher.digest(), expected);
+        }
+        assert_eq!(sha256(&input), hasher.digest());
+    }
+}
@@ -0,0 +1,118 @@
+//! Arithmetic on 256-bit integers modulo a 256-bit modulus, as eight little-endian words,
+//! backed by the `UINT256_MULMOD` and `MODEXP` precompiles.
+//!
+//! The operands of the modular operations must be below the modulus.
+
+use crate::modexp::modexp;
+use crate::syscall_uint256_mulmod;
+
+pub type Uint256 = [u32; 8];
+
+pub const ZERO: Uint256 = [0; 8];
+pub const ONE: Uint256 = [1, 0, 0, 0, 0, 0, 0, 0];
+
+/// Decodes a big-endian integer.
+pub fn from_be_bytes(bytes: &[u8; 32]) -> Uint256 {
+    let mut words = ZERO;
+    for (word, chunk) in words.iter_mut().rev().zip(bytes.chunks_exact(4)) {
+        *word = u32::from_be_bytes(chunk.try_into().unwrap());
+    }
+    words
+}
+
+/// Encodes `x` as a big-endian integer.
+pub fn to_be_bytes(x: &Uint256) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    for (chunk, word) in bytes.chunks_exact_mut(4).zip(x.iter().rev()) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    bytes
+}
+
+/// Decodes a little-endian integer.
+pub fn from_le_bytes(bytes: &[u8; 32]) -> Uint256 {
+    let mut words = ZERO;
+    for (word, chunk) in words.iter_mut().zip(bytes.chunks_exact(4)) {
+        *word = u32::from_le_bytes(chunk.try_into().unwrap());
+    }
+    words
+}
+
+pub fn lt(x: &Uint256, y: &Uint256) -> bool {
+    x.iter().rev().lt(y.iter().rev())
+}
+
+pub fn is_zero(x: &Uint256) -> bool {
+    *x == ZERO
+}
+
+/// `x + y mod 2^256`, and whether it overflowed.
+pub fn overflowing_add(x: &Uint256, y: &Uint256) -> (Uint256, bool) {
+    let mut sum = ZERO;
+    let mut carry = false;
+    for ((s, &a), &b) in sum.iter_mut().zip(x).zip(y) {
+        let (t, c1) = a.overflowing_add(b);
+        let (t, c2) = t.overflowing_add(carry as u32);
+        *s = t;
+        carry = c1 || c2;
+    }
+    (sum, carry)
+}
+
+/// `x - y mod 2^256`, and whether it underflowed.
+pub fn overflowing_sub(x: &Uint256, y: &Uint256) -> (Uint256, bool) {
+    let mut diff = ZERO;
+    let mut borrow = false;
+    for ((d, &a), &b) in diff.iter_mut().zip(x).zip(y) {
+        let (t, b1) = a.overflowing_sub(b);
+        let (t, b2) = t.overflowing_sub(borrow as u32);
+        *d = t;
+        borrow = b1 || b2;
+    }
+    (diff, borrow)
+}
+
+pub fn addmod(x: &Uint256, y: &Uint256, m: &Uint256) -> Uint256 {
+    let (sum, carry) = overflowing_add(x, y);
+    if carry || !lt(&sum, m) {
+        overflowing_sub(&sum, m).0
+    } else {
+        sum
+    }
+}
+
+pub fn submod(x: &Uint256, y: &Uint256, m: &Uint256) -> Uint256 {
+    let (diff, borrow) = overflowing_sub(x, y);
+    if borrow {
+        overflowing_add(&diff, m).0
+    } else {
+        diff
+    }
+}
+
+pub fn negmod(x: &Uint256, m: &Uint256) -> Uint256 {
+    submod(&ZERO, x, m)
+}
+
+pub fn mulmod(x: &Uint256, y: &Uint256, m: &Uint256) -> Uint256 {
+    let mut result = *x;
+    // The precompile reads the modulus right after `y`.
+    let mut operands = [0u32; 16];
+    operands[..8].copy_from_slice(y);
+    operands[8..].copy_from_slice(m);
+    unsafe {
+        syscall_uint256_mulmod(&mut result, operands.as_ptr() as *const [u32; 8]);
+    }
+    result
+}
+
+pub fn powmod(x: &Uint256, exp: &Uint256, m: &Uint256) -> Uint256 {
+    let result = modexp(&to_be_bytes(x), &to_be_bytes(exp), &to_be_bytes(m));
+    from_be_bytes(&result.try_into().unwrap())
+}
+
+/// The inverse of `x` modulo the prime `p`, or zero if `x` is zero.
+pub fn invmod(x: &Uint256, p: &Uint256) -> Uint256 {
+    let (exp, _) = overflowing_sub(p, &[2, 0, 0, 0, 0, 0, 0, 0]);
+    powmod(x, &exp, p)
+}
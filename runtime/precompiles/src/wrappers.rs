@@ -0,0 +1,121 @@
+//! Safe wrappers of the precompiles for the common cryptographic primitives.
+//!
+//! In the zkVM they are computed with the precompiles. Compiled for another target, as when the
+//! program is tested on the host, they fall back to the usual pure-Rust crates, which compute the
+//! same results.
+
+use bn::{pairing_batch, AffineG1, AffineG2, Fq, Fq2, Group, Gt, G1, G2};
+use cfg_if::cfg_if;
+
+/// The length of a pair of points of the input of [`bn254_pairing`].
+pub const BN254_PAIR_LEN: usize = 192;
+
+/// Computes the Keccak-256 digest of `input`.
+pub fn keccak256(input: &[u8]) -> [u8; 32] {
+    cfg_if! {
+        if #[cfg(target_os = "zkvm")] {
+            crate::keccak::keccak256(input)
+        } else {
+            use tiny_keccak::{Hasher, Keccak};
+
+            let mut digest = [0u8; 32];
+            let mut keccak = Keccak::v256();
+            keccak.update(input);
+            keccak.finalize(&mut digest);
+            digest
+        }
+    }
+}
+
+/// Computes the SHA-256 digest of `input`.
+pub fn sha256(input: &[u8]) -> [u8; 32] {
+    cfg_if! {
+        if #[cfg(target_os = "zkvm")] {
+            crate::sha256::sha256(input)
+        } else {
+            use sha2::{Digest, Sha256};
+
+            Sha256::digest(input).into()
+        }
+    }
+}
+
+/// Whether `signature` is the ed25519 signature of `msg` by `public_key`, with the
+/// verification equation of RFC 8032 without the cofactor.
+pub fn ed25519_verify(public_key: &[u8; 32], msg: &[u8], signature: &[u8; 64]) -> bool {
+    cfg_if! {
+        if #[cfg(target_os = "zkvm")] {
+            crate::ed25519::verify(public_key, msg, signature)
+        } else {
+            use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+
+            let Ok(key) = VerifyingKey::from_bytes(public_key) else {
+                return false;
+            };
+            key.verify(msg, &Signature::from_bytes(signature)).is_ok()
+        }
+    }
+}
+
+/// Recovers the public key which signed the 32-byte `msg_hash`, from the ECDSA `signature`,
+/// `r || s` in big endian, and its recovery id, as `ecrecover` does. The key is an uncompressed
+/// SEC1 point.
+///
+/// Returns `None` if the signature or the recovery id is invalid.
+pub fn secp256k1_recover(
+    signature: &[u8; 64],
+    recovery_id: u8,
+    msg_hash: &[u8; 32],
+) -> Option<[u8; 65]> {
+    cfg_if! {
+        if #[cfg(target_os = "zkvm")] {
+            crate::secp256k1::recover(signature, recovery_id, msg_hash)
+        } else {
+            use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+
+            let mut signature = Signature::from_slice(signature).ok()?;
+            let mut recovery_id = RecoveryId::from_byte(recovery_id)?;
+            // Negating s negates R, so a high s is accepted with the other parity of R.
+            if let Some(normalized) = signature.normalize_s() {
+                signature = normalized;
+                recovery_id =
+                    RecoveryId::new(!recovery_id.is_y_odd(), recovery_id.is_x_reduced());
+            }
+            let key = VerifyingKey::recover_from_prehash(msg_hash, &signature, recovery_id).ok()?;
+            key.to_encoded_point(false).as_bytes().try_into().ok()
+        }
+    }
+}
+
+/// Checks the product of the pairings of the pairs of points of `input`, the input of the EVM
+/// `0x08` precompile: whether it is one, or `None` if the input is invalid.
+///
+/// Each pair is 192 bytes, a point of G1 `(x, y)` then a point of G2 `(x, y)`, whose coordinates
+/// are big-endian integers, the imaginary part first for G2. `(0, 0)` is the point at infinity.
+/// There is no pairing precompile, so this is in software on all targets.
+pub fn bn254_pairing(input: &[u8]) -> Option<bool> {
+    if input.len() % BN254_PAIR_LEN != 0 {
+        return None;
+    }
+    let fq = |bytes: &[u8]| Fq::from_slice(bytes).ok();
+
+    let mut pairs = Vec::with_capacity(input.len() / BN254_PAIR_LEN);
+    for pair in input.chunks_exact(BN254_PAIR_LEN) {
+        let (x, y) = (fq(&pair[..32])?, fq(&pair[32..64])?);
+        let g1 = if x == Fq::zero() && y == Fq::zero() {
+            G1::zero()
+        } else {
+            AffineG1::new(x, y).ok()?.into()
+        };
+
+        let x = Fq2::new(fq(&pair[96..128])?, fq(&pair[64..96])?);
+        let y = Fq2::new(fq(&pair[160..192])?, fq(&pair[128..160])?);
+        let g2 = if x.is_zero() && y.is_zero() {
+            G2::zero()
+        } else {
+            AffineG2::new(x, y).ok()?.into()
+        };
+        pairs.push((g1, g2));
+    }
+    Some(pairing_batch(&pairs) == Gt::one())
+}
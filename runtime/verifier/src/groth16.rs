@@ -0,0 +1,111 @@
+//! Verification of Groth16 proofs on BN254, such as the proofs of the Groth16 wrapper.
+//!
+//! The points are encoded as for the EVM precompiles: a point of G1 is `x || y` and a point of
+//! G2 is `x_im || x_re || y_im || y_re`, with 32-byte big-endian coordinates, and `(0, 0)` is the
+//! point at infinity. The public inputs are 32-byte big-endian scalars below the group order.
+
+use alloc::vec::Vec;
+use bn::{pairing_batch, AffineG1, AffineG2, Fq, Fq2, Fr, Group, Gt, G1, G2};
+
+const G1_LEN: usize = 64;
+const G2_LEN: usize = 128;
+
+/// The length of an encoded [`Proof`].
+pub const PROOF_LEN: usize = 2 * G1_LEN + G2_LEN;
+
+#[derive(Clone, Debug)]
+pub struct VerifyingKey {
+    pub alpha_g1: G1,
+    pub beta_g2: G2,
+    pub gamma_g2: G2,
+    pub delta_g2: G2,
+    /// The points of G1 weighting the public inputs, the constant one first.
+    pub ic: Vec<G1>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Proof {
+    pub a: G1,
+    pub b: G2,
+    pub c: G1,
+}
+
+fn fq(bytes: &[u8]) -> Option<Fq> {
+    Fq::from_slice(bytes).ok()
+}
+
+fn read_g1(bytes: &[u8]) -> Option<G1> {
+    let (x, y) = (fq(&bytes[..32])?, fq(&bytes[32..64])?);
+    if x == Fq::zero() && y == Fq::zero() {
+        return Some(G1::zero());
+    }
+    AffineG1::new(x, y).ok().map(Into::into)
+}
+
+fn read_g2(bytes: &[u8]) -> Option<G2> {
+    let x = Fq2::new(fq(&bytes[32..64])?, fq(&bytes[..32])?);
+    let y = Fq2::new(fq(&bytes[96..128])?, fq(&bytes[64..96])?);
+    if x.is_zero() && y.is_zero() {
+        return Some(G2::zero());
+    }
+    AffineG2::new(x, y).ok().map(Into::into)
+}
+
+impl VerifyingKey {
+    /// Decodes `alpha_g1 || beta_g2 || gamma_g2 || delta_g2 || ic`, or returns `None` if a point
+    /// is invalid or `ic` is empty.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let ic_start = G1_LEN + 3 * G2_LEN;
+        if bytes.len() <= ic_start || (bytes.len() - ic_start) % G1_LEN != 0 {
+            return None;
+        }
+        let g2 = |i: usize| read_g2(&bytes[G1_LEN + i * G2_LEN..G1_LEN + (i + 1) * G2_LEN]);
+        Some(Self {
+            alpha_g1: read_g1(&bytes[..G1_LEN])?,
+            beta_g2: g2(0)?,
+            gamma_g2: g2(1)?,
+            delta_g2: g2(2)?,
+            ic: bytes[ic_start..]
+                .chunks_exact(G1_LEN)
+                .map(read_g1)
+                .collect::<Option<_>>()?,
+        })
+    }
+}
+
+impl Proof {
+    /// Decodes `a || b || c`, or returns `None` if a point is invalid.
+    pub fn from_bytes(bytes: &[u8; PROOF_LEN]) -> Option<Self> {
+        Some(Self {
+            a: read_g1(&bytes[..G1_LEN])?,
+            b: read_g2(&bytes[G1_LEN..G1_LEN + G2_LEN])?,
+            c: read_g1(&bytes[G1_LEN + G2_LEN..])?,
+        })
+    }
+}
+
+/// Whether `proof` is valid for the `public_inputs` of the circuit of `vk`, that is whether
+/// `e(a, b) = e(alpha, beta) * e(vk_x, gamma) * e(c, delta)`, with `vk_x` the sum of the points
+/// of `ic` weighted by one and the public inputs.
+///
+/// Returns false too if a public input is not a canonical scalar, or if their number does not
+/// match `vk`.
+pub fn verify(vk: &VerifyingKey, proof: &Proof, public_inputs: &[[u8; 32]]) -> bool {
+    if public_inputs.len() + 1 != vk.ic.len() {
+        return false;
+    }
+    let mut vk_x = vk.ic[0];
+    for (input, &point) in public_inputs.iter().zip(&vk.ic[1..]) {
+        let Ok(scalar) = Fr::from_slice(input) else {
+            return false;
+        };
+        vk_x = vk_x + point * scalar;
+    }
+
+    pairing_batch(&[
+        (-proof.a, proof.b),
+        (vk.alpha_g1, vk.beta_g2),
+        (vk_x, vk.gamma_g2),
+        (proof.c, vk.delta_g2),
+    ]) == Gt::one()
+}
@@ -0,0 +1,42 @@
+//! Verification of zkm proofs inside a guest program, to prove that it checked other proofs.
+//!
+//! A compressed proof is verified by [`verify_proof`], which adds the claim of the proof to the
+//! assumptions of the program: the host resolves it with the receipt of the proof, which the
+//! prover checks recursively. A proof wrapped with Groth16 is verified in the program itself by
+//! [`groth16::verify`], with the BN254 pairing.
+
+#![no_std]
+
+extern crate alloc;
+
+pub mod groth16;
+
+use sha2::{Digest, Sha256};
+
+extern "C" {
+    fn syscall_verify(claim_digest: &[u8; 32], control_root: &[u8; 32]);
+}
+
+/// The control root of the recursion circuits which verify the assumptions.
+const CONTROL_ROOT: [u8; 32] = [0u8; 32];
+
+/// The digest of the claim that the program `image_id` ran and committed `public_values`, the
+/// one of its `Receipt`: `sha256(image_id || sha256(public_values))`.
+pub fn claim_digest(image_id: &[u8; 32], public_values: &[u8]) -> [u8; 32] {
+    let public_values_digest: [u8; 32] = Sha256::digest(public_values).into();
+
+    let mut hasher = Sha256::new();
+    hasher.update(image_id);
+    hasher.update(public_values_digest);
+    hasher.finalize().into()
+}
+
+/// Verifies the compressed proof that the program `image_id` ran and committed the raw bytes
+/// `public_values`.
+///
+/// The proof is not checked by the program but by the prover, which fails unless the host added
+/// its receipt to the assumptions, so a program returning from this has a valid proof.
+pub fn verify_proof(image_id: &[u8; 32], public_values: &[u8]) {
+    let digest = claim_digest(image_id, public_values);
+    unsafe { syscall_verify(&digest, &CONTROL_ROOT) }
+}